@@ -5,6 +5,7 @@ use crate::context::UpdateContext;
 use crate::prelude::*;
 use gc_arena::{GcCell, MutationContext};
 use rand::Rng;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::convert::TryInto;
 
@@ -15,7 +16,7 @@ use crate::tag_utils::SwfSlice;
 
 #[cfg(test)]
 #[macro_use]
-mod test_utils;
+pub(crate) mod test_utils;
 
 #[macro_use]
 pub mod listeners;
@@ -24,6 +25,7 @@ mod activation;
 mod fscommand;
 pub mod function;
 pub mod globals;
+mod net_stream_object;
 pub mod object;
 mod property;
 mod return_value;
@@ -43,6 +45,7 @@ mod tests;
 use crate::avm1::listeners::SystemListener;
 use activation::Activation;
 pub use globals::SystemPrototypes;
+pub use net_stream_object::NetStreamObject;
 pub use object::{Object, ObjectPtr, TObject};
 use scope::Scope;
 pub use script_object::ScriptObject;
@@ -262,30 +265,38 @@ impl<'gc> Avm1<'gc> {
         context: &mut UpdateContext<'_, 'gc, '_>,
         name: &str,
     ) {
-        // Grab the property with the given name.
-        // Requires a dummy stack frame.
         let clip = active_clip.object().as_object();
         if let Ok(clip) = clip {
-            self.stack_frames.push(GcCell::allocate(
-                context.gc_context,
-                Activation::from_nothing(
-                    swf_version,
-                    self.globals,
-                    context.gc_context,
-                    active_clip,
-                ),
-            ));
-            let callback = clip
-                .get(name, self, context)
-                .and_then(|prop| prop.resolve(self, context));
-            self.stack_frames.pop();
+            self.insert_stack_frame_for_method(active_clip, clip, swf_version, context, name, &[]);
+        }
+    }
 
-            // Run the callback.
-            // The function exec pushes its own stack frame.
-            // The function is now ready to execute with `run_stack_till_empty`.
-            if let Ok(callback) = callback {
-                let _ = callback.call(self, context, clip, &[]);
-            }
+    /// Add a stack frame that executes the method `name` of `object`, if it exists.
+    pub fn insert_stack_frame_for_method(
+        &mut self,
+        active_clip: DisplayObject<'gc>,
+        object: Object<'gc>,
+        swf_version: u8,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        name: &str,
+        args: &[Value<'gc>],
+    ) {
+        // Grab the property with the given name.
+        // Requires a dummy stack frame.
+        self.stack_frames.push(GcCell::allocate(
+            context.gc_context,
+            Activation::from_nothing(swf_version, self.globals, context.gc_context, active_clip),
+        ));
+        let callback = object
+            .get(name, self, context)
+            .and_then(|prop| prop.resolve(self, context));
+        self.stack_frames.pop();
+
+        // Run the callback.
+        // The function exec pushes its own stack frame.
+        // The function is now ready to execute with `run_stack_till_empty`.
+        if let Ok(callback) = callback {
+            let _ = callback.call(self, context, object, args);
         }
     }
 
@@ -370,6 +381,7 @@ impl<'gc> Avm1<'gc> {
         };
 
         let mut read = Reader::new(data.as_ref(), swf_version);
+        read.set_codepage(context.system_codepage);
         read.seek(pc.try_into().unwrap());
 
         let r = func(self, &mut read, context);
@@ -1014,11 +1026,11 @@ impl<'gc> Avm1<'gc> {
     fn action_constant_pool(
         &mut self,
         context: &mut UpdateContext<'_, 'gc, '_>,
-        constant_pool: &[&str],
+        constant_pool: &[Cow<'_, str>],
     ) -> Result<(), Error> {
         self.constant_pool = GcCell::allocate(
             context.gc_context,
-            constant_pool.iter().map(|s| (*s).to_string()).collect(),
+            constant_pool.iter().map(|s| s.to_string()).collect(),
         );
         self.current_stack_frame()
             .unwrap()
@@ -1038,7 +1050,7 @@ impl<'gc> Avm1<'gc> {
         &mut self,
         context: &mut UpdateContext<'_, 'gc, '_>,
         name: &str,
-        params: &[&str],
+        params: &[Cow<'_, str>],
         actions: &[u8],
     ) -> Result<(), Error> {
         let swf_version = self.current_stack_frame().unwrap().read().swf_version();
@@ -1121,7 +1133,7 @@ impl<'gc> Avm1<'gc> {
             self.push(func_obj);
         } else {
             self.current_stack_frame().unwrap().read().define(
-                action_func.name,
+                &action_func.name,
                 func_obj,
                 context.gc_context,
             );
@@ -1658,18 +1670,37 @@ impl<'gc> Avm1<'gc> {
         Ok(())
     }
 
-    fn action_mb_ascii_to_char(&mut self, _context: &mut UpdateContext) -> Result<(), Error> {
+    fn action_mb_ascii_to_char(&mut self, context: &mut UpdateContext) -> Result<(), Error> {
         // TODO(Herschel): Results on incorrect operands?
         use std::convert::TryFrom;
-        let val = char::try_from(self.pop().as_f64()? as u32)?;
+        let code = self.pop().as_f64()? as u32;
+        // SWF 5 and earlier use character codes of the system codepage.
+        let val = if self.current_swf_version() < 6 {
+            u16::try_from(code)
+                .ok()
+                .and_then(|code| context.system_codepage.decode_char(code))
+                .ok_or("Invalid character code")?
+        } else {
+            char::try_from(code)?
+        };
         self.push(val.to_string());
         Ok(())
     }
 
-    fn action_mb_char_to_ascii(&mut self, _context: &mut UpdateContext) -> Result<(), Error> {
+    fn action_mb_char_to_ascii(&mut self, context: &mut UpdateContext) -> Result<(), Error> {
         // TODO(Herschel): Results on incorrect operands?
         let s = self.pop().into_string();
-        let result = s.chars().nth(0).unwrap_or('\0') as u32;
+        let c = s.chars().nth(0).unwrap_or('\0');
+        // SWF 5 and earlier use character codes of the system codepage.
+        let result = if self.current_swf_version() < 6 {
+            context
+                .system_codepage
+                .encode_char(c)
+                .map(u32::from)
+                .unwrap_or_else(|| u32::from(c))
+        } else {
+            u32::from(c)
+        };
         self.push(result);
         Ok(())
     }
@@ -1679,14 +1710,19 @@ impl<'gc> Avm1<'gc> {
         let len = self.pop().as_f64()? as usize;
         let start = self.pop().as_f64()? as usize;
         let s = self.pop().into_string();
-        let result = s[len..len + start].to_string(); // TODO(Herschel): Flash uses UTF-16 internally.
+        // This counts characters rather than bytes, and the index is 1-based.
+        let result = s
+            .chars()
+            .skip(start.saturating_sub(1))
+            .take(len)
+            .collect::<String>();
         self.push(result);
         Ok(())
     }
 
     fn action_mb_string_length(&mut self, _context: &mut UpdateContext) -> Result<(), Error> {
         // TODO(Herschel): Result with non-string operands?
-        let val = self.pop().into_string().len();
+        let val = self.pop().into_string().chars().count();
         self.push(val as f64);
         Ok(())
     }
@@ -2162,7 +2198,7 @@ impl<'gc> Avm1<'gc> {
     }
 
     fn action_stop_sounds(&mut self, context: &mut UpdateContext) -> Result<(), Error> {
-        context.audio.stop_all_sounds();
+        context.audio_manager.stop_all_sounds(context.audio);
         Ok(())
     }
 
@@ -2314,12 +2350,18 @@ impl<'gc> Avm1<'gc> {
     fn action_wait_for_frame(
         &mut self,
         _context: &mut UpdateContext,
-        _frame: u16,
+        frame: u16,
         num_actions_to_skip: u8,
         r: &mut Reader<'_>,
     ) -> Result<(), Error> {
-        // TODO(Herschel): Always true for now.
-        let loaded = true;
+        // The frame in the action is 0-based, not 1-based.
+        let clip = self.target_clip().and_then(|clip| clip.as_movie_clip());
+        let loaded = if let Some(clip) = clip {
+            frame < clip.frames_loaded()
+        } else {
+            log::warn!("WaitForFrame: Target is not a MovieClip");
+            true
+        };
         if !loaded {
             // Note that the offset is given in # of actions, NOT in bytes.
             // Read the actions and toss them away.
@@ -2334,9 +2376,22 @@ impl<'gc> Avm1<'gc> {
         num_actions_to_skip: u8,
         r: &mut Reader<'_>,
     ) -> Result<(), Error> {
-        // TODO(Herschel): Always true for now.
-        let _frame_num = self.pop().as_f64()? as u16;
-        let loaded = true;
+        // Param can either be a frame number or a frame label.
+        let frame = self.pop();
+        let clip = self.target_clip().and_then(|clip| clip.as_movie_clip());
+        let loaded = if let Some(clip) = clip {
+            let frame_num = if let Value::String(frame_label) = &frame {
+                // A label that hasn't loaded yet belongs to a frame that hasn't loaded either.
+                clip.frame_label_to_number(frame_label).unwrap_or_else(|| clip.total_frames())
+            } else {
+                // The frame on the stack is 1-based, not 0-based.
+                frame.as_f64()? as u16
+            };
+            frame_num <= clip.frames_loaded()
+        } else {
+            log::warn!("WaitForFrame2: Target is not a MovieClip");
+            true
+        };
         if !loaded {
             // Note that the offset is given in # of actions, NOT in bytes.
             // Read the actions and toss them away.
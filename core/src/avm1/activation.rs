@@ -194,7 +194,7 @@ impl<'gc> Activation<'gc> {
         Activation {
             swf_version,
             data: SwfSlice {
-                data: Arc::new(Vec::new()),
+                data: Arc::new(Vec::new().into()),
                 start: 0,
                 end: 0,
             },
@@ -10,6 +10,7 @@ use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext};
 use crate::display_object::{DisplayObject, TDisplayObject};
 use crate::tag_utils::SwfSlice;
 use gc_arena::{Collect, CollectionContext, GcCell};
+use std::borrow::Cow;
 use swf::avm1::types::FunctionParam;
 
 /// Represents a function defined in Ruffle's code.
@@ -85,7 +86,7 @@ impl<'gc> Avm1Function<'gc> {
         swf_version: u8,
         actions: SwfSlice,
         name: &str,
-        params: &[&str],
+        params: &[Cow<'_, str>],
         scope: GcCell<'gc, Scope<'gc>>,
         constant_pool: GcCell<'gc, Vec<String>>,
         base_clip: DisplayObject<'gc>,
@@ -109,7 +110,7 @@ impl<'gc> Avm1Function<'gc> {
             suppress_this: false,
             preload_this: false,
             preload_global: false,
-            params: params.iter().map(|&s| (None, s.to_string())).collect(),
+            params: params.iter().map(|s| (None, s.to_string())).collect(),
             scope,
             constant_pool,
             base_clip,
@@ -125,7 +126,7 @@ impl<'gc> Avm1Function<'gc> {
         constant_pool: GcCell<'gc, Vec<String>>,
         base_clip: DisplayObject<'gc>,
     ) -> Self {
-        let name = match swf_function.name {
+        let name = match &*swf_function.name {
             "" => None,
             name => Some(name.to_string()),
         };
@@ -136,7 +137,7 @@ impl<'gc> Avm1Function<'gc> {
             register_index: r,
         } in &swf_function.params
         {
-            owned_params.push((*r, (*s).to_string()))
+            owned_params.push((*r, s.to_string()))
         }
 
         Avm1Function {
@@ -11,15 +11,20 @@ use std::f64;
 
 mod array;
 mod color;
+pub(crate) mod filters;
 mod function;
 mod key;
 mod math;
 pub(crate) mod mouse;
 pub(crate) mod movie_clip;
+mod net_connection;
+mod net_stream;
 mod object;
 mod sound;
 mod stage;
+mod system;
 pub(crate) mod text_field;
+mod video;
 mod xml;
 
 #[allow(non_snake_case, unused_must_use)] //can't use errors yet
@@ -136,10 +141,13 @@ pub struct SystemPrototypes<'gc> {
     pub object: Object<'gc>,
     pub function: Object<'gc>,
     pub movie_clip: Object<'gc>,
+    pub net_stream: Object<'gc>,
     pub sound: Object<'gc>,
     pub text_field: Object<'gc>,
     pub array: Object<'gc>,
+    pub video: Object<'gc>,
     pub xml_node: Object<'gc>,
+    pub filters: filters::FilterPrototypes<'gc>,
 }
 
 unsafe impl<'gc> gc_arena::Collect for SystemPrototypes<'gc> {
@@ -148,10 +156,13 @@ unsafe impl<'gc> gc_arena::Collect for SystemPrototypes<'gc> {
         self.object.trace(cc);
         self.function.trace(cc);
         self.movie_clip.trace(cc);
+        self.net_stream.trace(cc);
         self.sound.trace(cc);
         self.text_field.trace(cc);
         self.array.trace(cc);
+        self.video.trace(cc);
         self.xml_node.trace(cc);
+        self.filters.trace(cc);
     }
 }
 
@@ -167,6 +178,12 @@ pub fn create_globals<'gc>(
     let movie_clip_proto: Object<'gc> =
         movie_clip::create_proto(gc_context, object_proto, function_proto);
 
+    let net_connection_proto: Object<'gc> =
+        net_connection::create_proto(gc_context, object_proto, function_proto);
+
+    let net_stream_proto: Object<'gc> =
+        net_stream::create_proto(gc_context, object_proto, function_proto);
+
     let sound_proto: Object<'gc> = sound::create_proto(gc_context, object_proto, function_proto);
 
     let text_field_proto: Object<'gc> =
@@ -174,12 +191,17 @@ pub fn create_globals<'gc>(
 
     let array_proto: Object<'gc> = array::create_proto(gc_context, object_proto, function_proto);
 
+    let video_proto: Object<'gc> = video::create_proto(gc_context, object_proto, function_proto);
+
     let color_proto: Object<'gc> = color::create_proto(gc_context, object_proto, function_proto);
     let xmlnode_proto: Object<'gc> =
         xml::create_xmlnode_proto(gc_context, object_proto, function_proto);
 
     let xml_proto: Object<'gc> = xml::create_xml_proto(gc_context, xmlnode_proto, function_proto);
 
+    let (filter_protos, filters_package) =
+        filters::create_package(gc_context, object_proto, function_proto);
+
     //TODO: These need to be constructors and should also set `.prototype` on each one
     let object = ScriptObject::function(
         gc_context,
@@ -206,6 +228,18 @@ pub fn create_globals<'gc>(
         Some(function_proto),
         Some(movie_clip_proto),
     );
+    let net_connection = ScriptObject::function(
+        gc_context,
+        Executable::Native(net_connection::constructor),
+        Some(function_proto),
+        Some(net_connection_proto),
+    );
+    let net_stream = ScriptObject::function(
+        gc_context,
+        Executable::Native(net_stream::constructor),
+        Some(function_proto),
+        Some(net_stream_proto),
+    );
     let sound = ScriptObject::function(
         gc_context,
         Executable::Native(sound::constructor),
@@ -224,6 +258,12 @@ pub fn create_globals<'gc>(
         Some(function_proto),
         Some(array_proto),
     );
+    let video = ScriptObject::function(
+        gc_context,
+        Executable::Native(video::constructor),
+        Some(function_proto),
+        Some(video_proto),
+    );
     let xmlnode = ScriptObject::function(
         gc_context,
         Executable::Native(xml::xmlnode_constructor),
@@ -245,10 +285,28 @@ pub fn create_globals<'gc>(
     globals.define_value(gc_context, "Object", object.into(), EnumSet::empty());
     globals.define_value(gc_context, "Function", function.into(), EnumSet::empty());
     globals.define_value(gc_context, "MovieClip", movie_clip.into(), EnumSet::empty());
+    globals.define_value(
+        gc_context,
+        "NetConnection",
+        net_connection.into(),
+        EnumSet::empty(),
+    );
+    globals.define_value(gc_context, "NetStream", net_stream.into(), EnumSet::empty());
     globals.define_value(gc_context, "Sound", sound.into(), EnumSet::empty());
     globals.define_value(gc_context, "TextField", text_field.into(), EnumSet::empty());
+    globals.define_value(gc_context, "Video", video.into(), EnumSet::empty());
     globals.define_value(gc_context, "XMLNode", xmlnode.into(), EnumSet::empty());
     globals.define_value(gc_context, "XML", xml.into(), EnumSet::empty());
+
+    let flash = ScriptObject::object(gc_context, Some(object_proto));
+    flash.define_value(
+        gc_context,
+        "filters",
+        filters_package.into(),
+        EnumSet::empty(),
+    );
+    globals.define_value(gc_context, "flash", flash.into(), EnumSet::empty());
+
     globals.force_set_function(
         "Number",
         number,
@@ -291,28 +349,25 @@ pub fn create_globals<'gc>(
             gc_context,
             Some(object_proto),
             Some(function_proto),
+            &listeners.key,
         )),
         EnumSet::empty(),
     );
     globals.define_value(
         gc_context,
-        "Key",
-        Value::Object(key::create_key_object(
+        "Stage",
+        Value::Object(stage::create_stage_object(
             gc_context,
             Some(object_proto),
+            Some(array_proto),
             Some(function_proto),
         )),
         EnumSet::empty(),
     );
     globals.define_value(
         gc_context,
-        "Stage",
-        Value::Object(stage::create_stage_object(
-            gc_context,
-            Some(object_proto),
-            Some(array_proto),
-            Some(function_proto),
-        )),
+        "System",
+        Value::Object(system::create_system_object(gc_context, Some(object_proto))),
         EnumSet::empty(),
     );
     globals.force_set_function(
@@ -363,10 +418,13 @@ pub fn create_globals<'gc>(
             object: object_proto,
             function: function_proto,
             movie_clip: movie_clip_proto,
+            net_stream: net_stream_proto,
             sound: sound_proto,
             text_field: text_field_proto,
             array: array_proto,
+            video: video_proto,
             xml_node: xmlnode_proto,
+            filters: filter_protos,
         },
         globals.into(),
         listeners,
@@ -0,0 +1,767 @@
+//! `flash.filters` package
+//!
+//! Filter objects hold their parameters as plain properties, which are converted to and from
+//! `swf::Filter` when they are applied to or read back from a display object.
+
+use crate::avm1::function::{Executable, NativeFunction};
+use crate::avm1::property::Attribute::*;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
+use enumset::EnumSet;
+use gc_arena::MutationContext;
+use swf::{Color, Filter, GradientRecord};
+
+/// The prototypes of `flash.filters.BitmapFilter` and its subclasses.
+#[derive(Clone, Copy)]
+pub struct FilterPrototypes<'gc> {
+    pub bitmap_filter: Object<'gc>,
+    pub blur: Object<'gc>,
+    pub drop_shadow: Object<'gc>,
+    pub glow: Object<'gc>,
+    pub bevel: Object<'gc>,
+    pub gradient_glow: Object<'gc>,
+    pub gradient_bevel: Object<'gc>,
+    pub convolution: Object<'gc>,
+    pub color_matrix: Object<'gc>,
+}
+
+unsafe impl<'gc> gc_arena::Collect for FilterPrototypes<'gc> {
+    #[inline]
+    fn trace(&self, cc: gc_arena::CollectionContext) {
+        self.bitmap_filter.trace(cc);
+        self.blur.trace(cc);
+        self.drop_shadow.trace(cc);
+        self.glow.trace(cc);
+        self.bevel.trace(cc);
+        self.gradient_glow.trace(cc);
+        self.gradient_bevel.trace(cc);
+        self.convolution.trace(cc);
+        self.color_matrix.trace(cc);
+    }
+}
+
+/// The default value of a filter property.
+#[derive(Clone, Copy)]
+enum Param {
+    Number(f64),
+    Bool(bool),
+    String(&'static str),
+    /// An array of numbers.
+    Array(&'static [f64]),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FilterKind {
+    Blur,
+    DropShadow,
+    Glow,
+    Bevel,
+    GradientGlow,
+    GradientBevel,
+    Convolution,
+    ColorMatrix,
+}
+
+const IDENTITY_COLOR_MATRIX: [f64; 20] = [
+    1.0, 0.0, 0.0, 0.0, 0.0, //
+    0.0, 1.0, 0.0, 0.0, 0.0, //
+    0.0, 0.0, 1.0, 0.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0, 0.0, //
+];
+
+impl FilterKind {
+    const ALL: [FilterKind; 8] = [
+        FilterKind::Blur,
+        FilterKind::DropShadow,
+        FilterKind::Glow,
+        FilterKind::Bevel,
+        FilterKind::GradientGlow,
+        FilterKind::GradientBevel,
+        FilterKind::Convolution,
+        FilterKind::ColorMatrix,
+    ];
+
+    fn class_name(self) -> &'static str {
+        match self {
+            FilterKind::Blur => "BlurFilter",
+            FilterKind::DropShadow => "DropShadowFilter",
+            FilterKind::Glow => "GlowFilter",
+            FilterKind::Bevel => "BevelFilter",
+            FilterKind::GradientGlow => "GradientGlowFilter",
+            FilterKind::GradientBevel => "GradientBevelFilter",
+            FilterKind::Convolution => "ConvolutionFilter",
+            FilterKind::ColorMatrix => "ColorMatrixFilter",
+        }
+    }
+
+    /// The properties of the filter, in the order of the parameters of its constructor.
+    fn params(self) -> &'static [(&'static str, Param)] {
+        use Param::*;
+        match self {
+            FilterKind::Blur => &[
+                ("blurX", Number(4.0)),
+                ("blurY", Number(4.0)),
+                ("quality", Number(1.0)),
+            ],
+            FilterKind::DropShadow => &[
+                ("distance", Number(4.0)),
+                ("angle", Number(45.0)),
+                ("color", Number(0.0)),
+                ("alpha", Number(1.0)),
+                ("blurX", Number(4.0)),
+                ("blurY", Number(4.0)),
+                ("strength", Number(1.0)),
+                ("quality", Number(1.0)),
+                ("inner", Bool(false)),
+                ("knockout", Bool(false)),
+                ("hideObject", Bool(false)),
+            ],
+            FilterKind::Glow => &[
+                ("color", Number(16_711_680.0)),
+                ("alpha", Number(1.0)),
+                ("blurX", Number(6.0)),
+                ("blurY", Number(6.0)),
+                ("strength", Number(2.0)),
+                ("quality", Number(1.0)),
+                ("inner", Bool(false)),
+                ("knockout", Bool(false)),
+            ],
+            FilterKind::Bevel => &[
+                ("distance", Number(4.0)),
+                ("angle", Number(45.0)),
+                ("highlightColor", Number(16_777_215.0)),
+                ("highlightAlpha", Number(1.0)),
+                ("shadowColor", Number(0.0)),
+                ("shadowAlpha", Number(1.0)),
+                ("blurX", Number(4.0)),
+                ("blurY", Number(4.0)),
+                ("strength", Number(1.0)),
+                ("quality", Number(1.0)),
+                ("type", String("inner")),
+                ("knockout", Bool(false)),
+            ],
+            FilterKind::GradientGlow | FilterKind::GradientBevel => &[
+                ("distance", Number(4.0)),
+                ("angle", Number(45.0)),
+                ("colors", Array(&[])),
+                ("alphas", Array(&[])),
+                ("ratios", Array(&[])),
+                ("blurX", Number(4.0)),
+                ("blurY", Number(4.0)),
+                ("strength", Number(1.0)),
+                ("quality", Number(1.0)),
+                ("type", String("inner")),
+                ("knockout", Bool(false)),
+            ],
+            FilterKind::Convolution => &[
+                ("matrixX", Number(0.0)),
+                ("matrixY", Number(0.0)),
+                ("matrix", Array(&[])),
+                ("divisor", Number(1.0)),
+                ("bias", Number(0.0)),
+                ("preserveAlpha", Bool(true)),
+                ("clamp", Bool(true)),
+                ("color", Number(0.0)),
+                ("alpha", Number(0.0)),
+            ],
+            FilterKind::ColorMatrix => &[("matrix", Array(&IDENTITY_COLOR_MATRIX))],
+        }
+    }
+
+    fn proto<'gc>(self, prototypes: &FilterPrototypes<'gc>) -> Object<'gc> {
+        match self {
+            FilterKind::Blur => prototypes.blur,
+            FilterKind::DropShadow => prototypes.drop_shadow,
+            FilterKind::Glow => prototypes.glow,
+            FilterKind::Bevel => prototypes.bevel,
+            FilterKind::GradientGlow => prototypes.gradient_glow,
+            FilterKind::GradientBevel => prototypes.gradient_bevel,
+            FilterKind::Convolution => prototypes.convolution,
+            FilterKind::ColorMatrix => prototypes.color_matrix,
+        }
+    }
+
+    /// Returns the kind of a filter object, from its prototype chain.
+    fn of_object<'gc>(object: Object<'gc>, prototypes: &FilterPrototypes<'gc>) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.proto(prototypes).is_prototype_of(object))
+    }
+}
+
+/// Creates the `flash.filters` package, returning the prototypes of its classes.
+pub fn create_package<'gc>(
+    gc_context: MutationContext<'gc, '_>,
+    proto: Object<'gc>,
+    fn_proto: Object<'gc>,
+) -> (FilterPrototypes<'gc>, Object<'gc>) {
+    let package = ScriptObject::object(gc_context, Some(proto));
+
+    let mut bitmap_filter_proto = ScriptObject::object(gc_context, Some(proto));
+    bitmap_filter_proto.force_set_function(
+        "clone",
+        clone,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+    let bitmap_filter_proto: Object<'gc> = bitmap_filter_proto.into();
+    let bitmap_filter = ScriptObject::function(
+        gc_context,
+        Executable::Native(bitmap_filter),
+        Some(fn_proto),
+        Some(bitmap_filter_proto),
+    );
+    package.define_value(
+        gc_context,
+        "BitmapFilter",
+        bitmap_filter.into(),
+        EnumSet::empty(),
+    );
+
+    let create_class = |kind: FilterKind, constructor: NativeFunction<'gc>| {
+        let proto: Object<'gc> = ScriptObject::object(gc_context, Some(bitmap_filter_proto)).into();
+        let constructor = ScriptObject::function(
+            gc_context,
+            Executable::Native(constructor),
+            Some(fn_proto),
+            Some(proto),
+        );
+        package.define_value(
+            gc_context,
+            kind.class_name(),
+            constructor.into(),
+            EnumSet::empty(),
+        );
+        proto
+    };
+
+    let prototypes = FilterPrototypes {
+        bitmap_filter: bitmap_filter_proto,
+        blur: create_class(FilterKind::Blur, blur_filter),
+        drop_shadow: create_class(FilterKind::DropShadow, drop_shadow_filter),
+        glow: create_class(FilterKind::Glow, glow_filter),
+        bevel: create_class(FilterKind::Bevel, bevel_filter),
+        gradient_glow: create_class(FilterKind::GradientGlow, gradient_glow_filter),
+        gradient_bevel: create_class(FilterKind::GradientBevel, gradient_bevel_filter),
+        convolution: create_class(FilterKind::Convolution, convolution_filter),
+        color_matrix: create_class(FilterKind::ColorMatrix, color_matrix_filter),
+    };
+    (prototypes, package.into())
+}
+
+/// Implements `BitmapFilter`, which is abstract.
+fn bitmap_filter<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    _this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(Value::Undefined.into())
+}
+
+/// Implements the constructor of a filter, which sets each property from its parameter.
+fn construct<'gc>(
+    kind: FilterKind,
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    for (i, &(name, param)) in kind.params().iter().enumerate() {
+        let value = match args.get(i) {
+            Some(Value::Undefined) | None => param_value(context, param),
+            Some(value) => match param {
+                Param::Number(_) => value.as_number(avm, context)?.into(),
+                Param::Bool(_) => Value::Bool(value.as_bool(avm.current_swf_version())),
+                Param::String(_) => Value::String(value.clone().coerce_to_string(avm, context)?),
+                Param::Array(_) => {
+                    let numbers = numbers_of(avm, context, value)?;
+                    number_array(context, &numbers)
+                }
+            },
+        };
+        this.define_value(context.gc_context, name, value, EnumSet::empty());
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn blur_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::Blur, avm, context, this, args)
+}
+
+fn drop_shadow_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::DropShadow, avm, context, this, args)
+}
+
+fn glow_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::Glow, avm, context, this, args)
+}
+
+fn bevel_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::Bevel, avm, context, this, args)
+}
+
+fn gradient_glow_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::GradientGlow, avm, context, this, args)
+}
+
+fn gradient_bevel_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::GradientBevel, avm, context, this, args)
+}
+
+fn convolution_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::Convolution, avm, context, this, args)
+}
+
+fn color_matrix_filter<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    construct(FilterKind::ColorMatrix, avm, context, this, args)
+}
+
+/// Implements `BitmapFilter.clone`, copying the properties of the filter.
+fn clone<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let kind = match FilterKind::of_object(this, &context.system_prototypes.filters) {
+        Some(kind) => kind,
+        None => return Ok(Value::Undefined.into()),
+    };
+    let copy = ScriptObject::object(context.gc_context, this.proto());
+    for &(name, param) in kind.params() {
+        let mut value = this.get(name, avm, context)?.resolve(avm, context)?;
+        if let Param::Array(_) = param {
+            let numbers = numbers_of(avm, context, &value)?;
+            value = number_array(context, &numbers);
+        }
+        copy.define_value(context.gc_context, name, value, EnumSet::empty());
+    }
+    Ok(Value::Object(copy.into()).into())
+}
+
+/// Converts a filter object to a filter to render.
+/// Returns `None` if the object isn't a filter.
+pub fn filter_from_object<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    object: Object<'gc>,
+) -> Result<Option<Filter>, Error> {
+    let kind = match FilterKind::of_object(object, &context.system_prototypes.filters) {
+        Some(kind) => kind,
+        None => return Ok(None),
+    };
+    let mut values = Vec::with_capacity(kind.params().len());
+    for &(name, _) in kind.params() {
+        values.push(object.get(name, avm, context)?.resolve(avm, context)?);
+    }
+    let mut numbers = Vec::with_capacity(values.len());
+    for (&(_, param), value) in kind.params().iter().zip(&values) {
+        numbers.push(match param {
+            Param::Number(_) => value.as_number(avm, context)?,
+            _ => std::f64::NAN,
+        });
+    }
+    let swf_version = avm.current_swf_version();
+    let boolean = |i: usize| values[i].as_bool(swf_version);
+    let clamp = |i: usize, max: f64| {
+        if numbers[i].is_nan() {
+            0.0
+        } else {
+            numbers[i].max(0.0).min(max)
+        }
+    };
+    let color = |rgb: usize, alpha: usize| color(numbers[rgb], numbers[alpha]);
+    let angle = |i: usize| numbers[i].to_radians();
+
+    let filter = match kind {
+        FilterKind::Blur => Filter::BlurFilter(Box::new(swf::BlurFilter {
+            blur_x: clamp(0, 255.0),
+            blur_y: clamp(1, 255.0),
+            num_passes: clamp(2, 15.0) as u8,
+        })),
+        FilterKind::DropShadow => Filter::DropShadowFilter(Box::new(swf::DropShadowFilter {
+            distance: numbers[0],
+            angle: angle(1),
+            color: color(2, 3),
+            blur_x: clamp(4, 255.0),
+            blur_y: clamp(5, 255.0),
+            strength: clamp(6, 255.0) as f32,
+            num_passes: clamp(7, 15.0) as u8,
+            is_inner: boolean(8),
+            is_knockout: boolean(9),
+            is_composite_source: !boolean(10),
+        })),
+        FilterKind::Glow => Filter::GlowFilter(Box::new(swf::GlowFilter {
+            color: color(0, 1),
+            blur_x: clamp(2, 255.0),
+            blur_y: clamp(3, 255.0),
+            strength: clamp(4, 255.0) as f32,
+            num_passes: clamp(5, 15.0) as u8,
+            is_inner: boolean(6),
+            is_knockout: boolean(7),
+        })),
+        FilterKind::Bevel => {
+            let (is_inner, is_on_top) = bevel_type(&values[10]);
+            Filter::BevelFilter(Box::new(swf::BevelFilter {
+                distance: numbers[0],
+                angle: angle(1),
+                highlight_color: color(2, 3),
+                shadow_color: color(4, 5),
+                blur_x: clamp(6, 255.0),
+                blur_y: clamp(7, 255.0),
+                strength: clamp(8, 255.0) as f32,
+                num_passes: clamp(9, 15.0) as u8,
+                is_inner,
+                is_on_top,
+                is_knockout: boolean(11),
+            }))
+        }
+        FilterKind::GradientGlow | FilterKind::GradientBevel => {
+            let rgbs = numbers_of(avm, context, &values[2])?;
+            let alphas = numbers_of(avm, context, &values[3])?;
+            let ratios = numbers_of(avm, context, &values[4])?;
+            let colors = rgbs
+                .iter()
+                .zip(&alphas)
+                .zip(&ratios)
+                .take(15)
+                .map(|((&rgb, &alpha), &ratio)| GradientRecord {
+                    ratio: if ratio.is_nan() {
+                        0
+                    } else {
+                        ratio.max(0.0).min(255.0) as u8
+                    },
+                    color: self::color(rgb, alpha),
+                })
+                .collect();
+            let (is_inner, is_on_top) = bevel_type(&values[9]);
+            if kind == FilterKind::GradientGlow {
+                Filter::GradientGlowFilter(Box::new(swf::GradientGlowFilter {
+                    colors,
+                    distance: numbers[0],
+                    angle: angle(1),
+                    blur_x: clamp(5, 255.0),
+                    blur_y: clamp(6, 255.0),
+                    strength: clamp(7, 255.0) as f32,
+                    num_passes: clamp(8, 15.0) as u8,
+                    is_inner,
+                    is_on_top,
+                    is_knockout: boolean(10),
+                }))
+            } else {
+                Filter::GradientBevelFilter(Box::new(swf::GradientBevelFilter {
+                    colors,
+                    distance: numbers[0],
+                    angle: angle(1),
+                    blur_x: clamp(5, 255.0),
+                    blur_y: clamp(6, 255.0),
+                    strength: clamp(7, 255.0) as f32,
+                    num_passes: clamp(8, 15.0) as u8,
+                    is_inner,
+                    is_on_top,
+                    is_knockout: boolean(10),
+                }))
+            }
+        }
+        FilterKind::Convolution => {
+            let num_matrix_cols = clamp(0, 15.0) as u8;
+            let num_matrix_rows = clamp(1, 15.0) as u8;
+            let mut matrix = numbers_of(avm, context, &values[2])?;
+            matrix.resize(
+                usize::from(num_matrix_cols) * usize::from(num_matrix_rows),
+                0.0,
+            );
+            Filter::ConvolutionFilter(Box::new(swf::ConvolutionFilter {
+                num_matrix_rows,
+                num_matrix_cols,
+                matrix,
+                divisor: numbers[3],
+                bias: numbers[4],
+                is_preserve_alpha: boolean(5),
+                is_clamped: boolean(6),
+                default_color: color(7, 8),
+            }))
+        }
+        FilterKind::ColorMatrix => {
+            let mut matrix = [0.0; 20];
+            for (value, number) in matrix.iter_mut().zip(numbers_of(avm, context, &values[0])?) {
+                *value = number;
+            }
+            Filter::ColorMatrixFilter(Box::new(swf::ColorMatrixFilter { matrix }))
+        }
+    };
+    Ok(Some(filter))
+}
+
+/// Creates a filter object from a filter of a display object.
+pub fn object_from_filter<'gc>(
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    filter: &Filter,
+) -> Object<'gc> {
+    let number = |value: f64| Value::Number(value);
+    let rgb = |color: &Color| {
+        Value::Number(f64::from(
+            (u32::from(color.r) << 16) | (u32::from(color.g) << 8) | u32::from(color.b),
+        ))
+    };
+    let alpha = |color: &Color| Value::Number(f64::from(color.a) / 255.0);
+    let angle = |radians: f64| Value::Number(radians.to_degrees());
+    let bevel_type = |is_inner: bool, is_on_top: bool| {
+        Value::String(
+            if is_on_top {
+                "full"
+            } else if is_inner {
+                "inner"
+            } else {
+                "outer"
+            }
+            .to_string(),
+        )
+    };
+
+    let (kind, values) = match filter {
+        Filter::BlurFilter(blur) => (
+            FilterKind::Blur,
+            vec![
+                number(blur.blur_x),
+                number(blur.blur_y),
+                number(blur.num_passes.into()),
+            ],
+        ),
+        Filter::DropShadowFilter(shadow) => (
+            FilterKind::DropShadow,
+            vec![
+                number(shadow.distance),
+                angle(shadow.angle),
+                rgb(&shadow.color),
+                alpha(&shadow.color),
+                number(shadow.blur_x),
+                number(shadow.blur_y),
+                number(shadow.strength.into()),
+                number(shadow.num_passes.into()),
+                Value::Bool(shadow.is_inner),
+                Value::Bool(shadow.is_knockout),
+                Value::Bool(!shadow.is_composite_source),
+            ],
+        ),
+        Filter::GlowFilter(glow) => (
+            FilterKind::Glow,
+            vec![
+                rgb(&glow.color),
+                alpha(&glow.color),
+                number(glow.blur_x),
+                number(glow.blur_y),
+                number(glow.strength.into()),
+                number(glow.num_passes.into()),
+                Value::Bool(glow.is_inner),
+                Value::Bool(glow.is_knockout),
+            ],
+        ),
+        Filter::BevelFilter(bevel) => (
+            FilterKind::Bevel,
+            vec![
+                number(bevel.distance),
+                angle(bevel.angle),
+                rgb(&bevel.highlight_color),
+                alpha(&bevel.highlight_color),
+                rgb(&bevel.shadow_color),
+                alpha(&bevel.shadow_color),
+                number(bevel.blur_x),
+                number(bevel.blur_y),
+                number(bevel.strength.into()),
+                number(bevel.num_passes.into()),
+                bevel_type(bevel.is_inner, bevel.is_on_top),
+                Value::Bool(bevel.is_knockout),
+            ],
+        ),
+        Filter::GradientGlowFilter(glow) => (
+            FilterKind::GradientGlow,
+            gradient_values(
+                context,
+                &glow.colors,
+                vec![number(glow.distance), angle(glow.angle)],
+                vec![
+                    number(glow.blur_x),
+                    number(glow.blur_y),
+                    number(glow.strength.into()),
+                    number(glow.num_passes.into()),
+                    bevel_type(glow.is_inner, glow.is_on_top),
+                    Value::Bool(glow.is_knockout),
+                ],
+            ),
+        ),
+        Filter::GradientBevelFilter(bevel) => (
+            FilterKind::GradientBevel,
+            gradient_values(
+                context,
+                &bevel.colors,
+                vec![number(bevel.distance), angle(bevel.angle)],
+                vec![
+                    number(bevel.blur_x),
+                    number(bevel.blur_y),
+                    number(bevel.strength.into()),
+                    number(bevel.num_passes.into()),
+                    bevel_type(bevel.is_inner, bevel.is_on_top),
+                    Value::Bool(bevel.is_knockout),
+                ],
+            ),
+        ),
+        Filter::ConvolutionFilter(convolution) => (
+            FilterKind::Convolution,
+            vec![
+                number(convolution.num_matrix_cols.into()),
+                number(convolution.num_matrix_rows.into()),
+                number_array(context, &convolution.matrix),
+                number(convolution.divisor),
+                number(convolution.bias),
+                Value::Bool(convolution.is_preserve_alpha),
+                Value::Bool(convolution.is_clamped),
+                rgb(&convolution.default_color),
+                alpha(&convolution.default_color),
+            ],
+        ),
+        Filter::ColorMatrixFilter(color_matrix) => (
+            FilterKind::ColorMatrix,
+            vec![number_array(context, &color_matrix.matrix)],
+        ),
+    };
+
+    let object = ScriptObject::object(
+        context.gc_context,
+        Some(kind.proto(&context.system_prototypes.filters)),
+    );
+    for (&(name, _), value) in kind.params().iter().zip(values) {
+        object.define_value(context.gc_context, name, value, EnumSet::empty());
+    }
+    object.into()
+}
+
+/// Returns the property values of a gradient filter, with the `colors`, `alphas` and `ratios`
+/// arrays between the given values.
+fn gradient_values<'gc>(
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    records: &[GradientRecord],
+    mut before: Vec<Value<'gc>>,
+    after: Vec<Value<'gc>>,
+) -> Vec<Value<'gc>> {
+    let colors: Vec<f64> = records
+        .iter()
+        .map(|record| {
+            let color = &record.color;
+            f64::from((u32::from(color.r) << 16) | (u32::from(color.g) << 8) | u32::from(color.b))
+        })
+        .collect();
+    let alphas: Vec<f64> = records
+        .iter()
+        .map(|record| f64::from(record.color.a) / 255.0)
+        .collect();
+    let ratios: Vec<f64> = records
+        .iter()
+        .map(|record| f64::from(record.ratio))
+        .collect();
+    before.push(number_array(context, &colors));
+    before.push(number_array(context, &alphas));
+    before.push(number_array(context, &ratios));
+    before.extend(after);
+    before
+}
+
+fn param_value<'gc>(context: &mut UpdateContext<'_, 'gc, '_>, param: Param) -> Value<'gc> {
+    match param {
+        Param::Number(value) => value.into(),
+        Param::Bool(value) => Value::Bool(value),
+        Param::String(value) => Value::String(value.to_string()),
+        Param::Array(values) => number_array(context, values),
+    }
+}
+
+fn number_array<'gc>(context: &mut UpdateContext<'_, 'gc, '_>, numbers: &[f64]) -> Value<'gc> {
+    let array = ScriptObject::array(context.gc_context, Some(context.system_prototypes.array));
+    for (i, &number) in numbers.iter().enumerate() {
+        array.set_array_element(i, number.into(), context.gc_context);
+    }
+    array.into()
+}
+
+/// Returns the elements of an array as numbers, or no numbers if the value isn't an object.
+fn numbers_of<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    value: &Value<'gc>,
+) -> Result<Vec<f64>, Error> {
+    let mut numbers = vec![];
+    if let Value::Object(array) = value {
+        for element in array.array() {
+            numbers.push(element.as_number(avm, context)?);
+        }
+    }
+    Ok(numbers)
+}
+
+/// Combines an RGB color and an alpha from 0 to 1.
+fn color(rgb: f64, alpha: f64) -> Color {
+    let rgb = if rgb.is_nan() { 0 } else { rgb as i64 as u32 };
+    let alpha = if alpha.is_nan() {
+        0.0
+    } else {
+        alpha.max(0.0).min(1.0)
+    };
+    Color {
+        r: (rgb >> 16) as u8,
+        g: (rgb >> 8) as u8,
+        b: rgb as u8,
+        a: (alpha * 255.0).round() as u8,
+    }
+}
+
+/// Returns `is_inner` and `is_on_top` for the `type` of a bevel or gradient filter.
+fn bevel_type(value: &Value<'_>) -> (bool, bool) {
+    match value {
+        Value::String(value) if value == "outer" => (false, false),
+        Value::String(value) if value == "full" => (true, true),
+        _ => (true, false),
+    }
+}
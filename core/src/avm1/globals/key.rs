@@ -1,6 +1,7 @@
+use crate::avm1::listeners::Listeners;
 use crate::avm1::property::Attribute;
 use crate::avm1::return_value::ReturnValue;
-use crate::avm1::{Avm1, Error, Object, ScriptObject, UpdateContext, Value};
+use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
 
 use crate::events::KeyCode;
 use gc_arena::MutationContext;
@@ -27,9 +28,12 @@ pub fn create_key_object<'gc>(
     gc_context: MutationContext<'gc, '_>,
     proto: Option<Object<'gc>>,
     fn_proto: Option<Object<'gc>>,
+    listener: &Listeners<'gc>,
 ) -> Object<'gc> {
     let mut key = ScriptObject::object(gc_context, proto);
 
+    register_listener!(gc_context, key, listener, fn_proto, key);
+
     key.force_set_function(
         "isDown",
         is_down,
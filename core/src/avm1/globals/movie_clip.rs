@@ -1,13 +1,14 @@
 //! MovieClip prototype
 
 use crate::avm1::function::Executable;
+use crate::avm1::globals::filters::{filter_from_object, object_from_filter};
 use crate::avm1::property::Attribute::*;
 use crate::avm1::return_value::ReturnValue;
 use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
 use crate::display_object::{MovieClip, TDisplayObject};
 use enumset::EnumSet;
 use gc_arena::MutationContext;
-use swf::Twips;
+use swf::{BlendMode, Twips};
 
 /// The depth at which dynamic clips are offset.
 const AVM_DEPTH_BIAS: i32 = 16384;
@@ -116,6 +117,129 @@ pub fn hit_test<'gc>(
     Ok(false.into())
 }
 
+/// Adds the properties of a movie clip that are backed by its display object.
+pub fn attach_virtual_properties<'gc>(gc_context: MutationContext<'gc, '_>, object: Object<'gc>) {
+    object.add_property(
+        gc_context,
+        "filters",
+        Executable::Native(get_filters),
+        Some(Executable::Native(set_filters)),
+        DontDelete | DontEnum,
+    );
+    object.add_property(
+        gc_context,
+        "blendMode",
+        Executable::Native(get_blend_mode),
+        Some(Executable::Native(set_blend_mode)),
+        DontDelete | DontEnum,
+    );
+}
+
+/// The names of the blend modes, in the order of their numeric values starting from 1.
+const BLEND_MODES: [(BlendMode, &str); 14] = [
+    (BlendMode::Normal, "normal"),
+    (BlendMode::Layer, "layer"),
+    (BlendMode::Multiply, "multiply"),
+    (BlendMode::Screen, "screen"),
+    (BlendMode::Lighten, "lighten"),
+    (BlendMode::Darken, "darken"),
+    (BlendMode::Difference, "difference"),
+    (BlendMode::Add, "add"),
+    (BlendMode::Subtract, "subtract"),
+    (BlendMode::Invert, "invert"),
+    (BlendMode::Alpha, "alpha"),
+    (BlendMode::Erase, "erase"),
+    (BlendMode::Overlay, "overlay"),
+    (BlendMode::HardLight, "hardlight"),
+];
+
+fn get_blend_mode<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(display_object) = this.as_display_object() {
+        let blend_mode = display_object.blend_mode();
+        if let Some((_, name)) = BLEND_MODES.iter().find(|(mode, _)| *mode == blend_mode) {
+            return Ok(Value::String(name.to_string()).into());
+        }
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// Sets the blend mode of a clip from its name or number, ignoring invalid values.
+fn set_blend_mode<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(mut display_object) = this.as_display_object() {
+        let blend_mode = match args.get(0) {
+            Some(Value::Number(number)) => {
+                let index = *number as usize;
+                if *number >= 1.0 && index as f64 == *number {
+                    BLEND_MODES.get(index - 1)
+                } else {
+                    None
+                }
+            }
+            Some(value) => {
+                let name = value.clone().coerce_to_string(avm, context)?;
+                BLEND_MODES.iter().find(|(_, mode_name)| *mode_name == name)
+            }
+            None => None,
+        };
+        if let Some((blend_mode, _)) = blend_mode {
+            display_object.set_blend_mode(context.gc_context, *blend_mode);
+        }
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// Returns copies of the filters of a clip, so changing them doesn't affect the clip.
+fn get_filters<'gc>(
+    _avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(display_object) = this.as_display_object() {
+        let filters = display_object.filters().to_vec();
+        let array = ScriptObject::array(context.gc_context, Some(context.system_prototypes.array));
+        for (i, filter) in filters.iter().enumerate() {
+            let filter = object_from_filter(context, filter);
+            array.set_array_element(i, filter.into(), context.gc_context);
+        }
+        return Ok(Value::Object(array.into()).into());
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// Sets the filters of a clip from an array of filter objects, ignoring other values.
+fn set_filters<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(mut display_object) = this.as_display_object() {
+        let mut filters = vec![];
+        if let Some(Value::Object(array)) = args.get(0) {
+            for value in array.array() {
+                if let Value::Object(object) = value {
+                    if let Some(filter) = filter_from_object(avm, context, object)? {
+                        filters.push(filter);
+                    }
+                }
+            }
+        }
+        display_object.set_filters(context.gc_context, filters);
+    }
+    Ok(Value::Undefined.into())
+}
+
 pub fn create_proto<'gc>(
     gc_context: MutationContext<'gc, '_>,
     proto: Object<'gc>,
@@ -154,13 +278,12 @@ pub fn create_proto<'gc>(
             movie_clip.stop(context);
             Ok(Value::Undefined.into())
         },
-        "getBytesLoaded" => |_movie_clip: MovieClip<'gc>, _avm: &mut Avm1<'gc>, _context: &mut UpdateContext<'_, 'gc, '_>, _args| {
-            // TODO find a correct value
-            Ok(1.0.into())
+        "getBytesLoaded" => |_movie_clip: MovieClip<'gc>, _avm: &mut Avm1<'gc>, context: &mut UpdateContext<'_, 'gc, '_>, _args| {
+            // TODO: Clips from movies loaded with `loadMovie` should report the bytes of their own movie.
+            Ok((context.swf_bytes_loaded as f64).into())
         },
-        "getBytesTotal" => |_movie_clip: MovieClip<'gc>, _avm: &mut Avm1<'gc>, _context: &mut UpdateContext<'_, 'gc, '_>, _args| {
-            // TODO find a correct value
-            Ok(1.0.into())
+        "getBytesTotal" => |_movie_clip: MovieClip<'gc>, _avm: &mut Avm1<'gc>, context: &mut UpdateContext<'_, 'gc, '_>, _args| {
+            Ok((context.swf_bytes_total as f64).into())
         },
         "hitTest" => |movie_clip: MovieClip<'gc>, avm: &mut Avm1<'gc>, context: &mut UpdateContext<'_, 'gc, '_>, args: &[Value<'gc>]| {
             hit_test(movie_clip, avm, context, args)
@@ -0,0 +1,100 @@
+//! AVM1 NetConnection object
+//!
+//! Only local connections, made with `NetConnection.connect(null)`, are supported.
+//! These let `NetStream` objects play FLV files without a streaming server.
+
+use crate::avm1::property::Attribute::*;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
+use crate::context::ActionType;
+use enumset::EnumSet;
+use gc_arena::MutationContext;
+
+/// Implements `NetConnection`
+pub fn constructor<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    _this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(Value::Undefined.into())
+}
+
+pub fn create_proto<'gc>(
+    gc_context: MutationContext<'gc, '_>,
+    proto: Object<'gc>,
+    fn_proto: Object<'gc>,
+) -> Object<'gc> {
+    let mut object = ScriptObject::object(gc_context, Some(proto));
+
+    object.force_set_function(
+        "connect",
+        connect,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.define_value(
+        gc_context,
+        "isConnected",
+        false.into(),
+        DontDelete | DontEnum,
+    );
+
+    object.into()
+}
+
+fn connect<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    let is_local = match args.get(0) {
+        None | Some(Value::Null) | Some(Value::Undefined) => true,
+        Some(uri) => {
+            let uri = uri.clone().coerce_to_string(avm, context)?;
+            log::warn!(
+                "NetConnection.connect: Streaming servers are not supported ({})",
+                uri
+            );
+            false
+        }
+    };
+
+    this.define_value(
+        context.gc_context,
+        "isConnected",
+        is_local.into(),
+        DontDelete | DontEnum,
+    );
+    if is_local {
+        this.define_value(
+            context.gc_context,
+            "uri",
+            "null".into(),
+            DontDelete | DontEnum,
+        );
+    }
+
+    let info = ScriptObject::object(context.gc_context, Some(avm.prototypes.object));
+    let (code, level) = if is_local {
+        ("NetConnection.Connect.Success", "status")
+    } else {
+        ("NetConnection.Connect.Failed", "error")
+    };
+    info.define_value(context.gc_context, "code", code.into(), EnumSet::empty());
+    info.define_value(context.gc_context, "level", level.into(), EnumSet::empty());
+    context.action_queue.queue_actions(
+        context.root,
+        ActionType::Callback {
+            object: this,
+            name: "onStatus",
+            args: vec![info.into()],
+        },
+        false,
+    );
+
+    Ok(is_local.into())
+}
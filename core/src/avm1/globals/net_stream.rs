@@ -0,0 +1,271 @@
+//! AVM1 NetStream object
+
+use crate::avm1::function::Executable;
+use crate::avm1::property::Attribute::*;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, NetStreamObject, Object, TObject, UpdateContext, Value};
+use gc_arena::MutationContext;
+
+/// Implements `NetStream`
+pub fn constructor<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    // The 1st parameter is the `NetConnection` to play from. Only local connections are
+    // supported, so it isn't needed.
+    Ok(this.into())
+}
+
+pub fn create_proto<'gc>(
+    gc_context: MutationContext<'gc, '_>,
+    proto: Object<'gc>,
+    fn_proto: Object<'gc>,
+) -> Object<'gc> {
+    let object = NetStreamObject::empty_net_stream(gc_context, Some(proto));
+
+    object.add_property(
+        gc_context,
+        "bufferLength",
+        Executable::Native(buffer_length),
+        None,
+        DontDelete | ReadOnly | DontEnum,
+    );
+
+    object.add_property(
+        gc_context,
+        "bufferTime",
+        Executable::Native(buffer_time),
+        None,
+        DontDelete | ReadOnly | DontEnum,
+    );
+
+    object.add_property(
+        gc_context,
+        "bytesLoaded",
+        Executable::Native(bytes_loaded),
+        None,
+        DontDelete | ReadOnly | DontEnum,
+    );
+
+    object.add_property(
+        gc_context,
+        "bytesTotal",
+        Executable::Native(bytes_total),
+        None,
+        DontDelete | ReadOnly | DontEnum,
+    );
+
+    object.as_script_object().unwrap().force_set_function(
+        "close",
+        close,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.as_script_object().unwrap().force_set_function(
+        "pause",
+        pause,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.as_script_object().unwrap().force_set_function(
+        "play",
+        play,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.as_script_object().unwrap().force_set_function(
+        "seek",
+        seek,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.as_script_object().unwrap().force_set_function(
+        "setBufferTime",
+        set_buffer_time,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.add_property(
+        gc_context,
+        "time",
+        Executable::Native(time),
+        None,
+        DontDelete | ReadOnly | DontEnum,
+    );
+
+    object.into()
+}
+
+fn buffer_length<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        Ok(net_stream.buffer_length().into())
+    } else {
+        log::warn!("NetStream.bufferLength: this is not a NetStream");
+        Ok(Value::Undefined.into())
+    }
+}
+
+fn buffer_time<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        Ok(net_stream.buffer_time().into())
+    } else {
+        log::warn!("NetStream.bufferTime: this is not a NetStream");
+        Ok(Value::Undefined.into())
+    }
+}
+
+fn bytes_loaded<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        Ok(net_stream.bytes_loaded().into())
+    } else {
+        log::warn!("NetStream.bytesLoaded: this is not a NetStream");
+        Ok(Value::Undefined.into())
+    }
+}
+
+fn bytes_total<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        Ok(net_stream.bytes_total().into())
+    } else {
+        log::warn!("NetStream.bytesTotal: this is not a NetStream");
+        Ok(Value::Undefined.into())
+    }
+}
+
+fn close<'gc>(
+    _avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        net_stream.close(context);
+    } else {
+        log::warn!("NetStream.close: this is not a NetStream");
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn pause<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        // Without a flag, the stream toggles between paused and playing.
+        let is_paused = match args.get(0) {
+            None | Some(Value::Undefined) => None,
+            Some(flag) => Some(flag.as_bool(avm.current_swf_version())),
+        };
+        net_stream.pause(context, is_paused);
+    } else {
+        log::warn!("NetStream.pause: this is not a NetStream");
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn play<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        let url = args
+            .get(0)
+            .unwrap_or(&Value::Undefined)
+            .clone()
+            .coerce_to_string(avm, context)?;
+        net_stream.play(context, url);
+    } else {
+        log::warn!("NetStream.play: this is not a NetStream");
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn seek<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        let seconds = args
+            .get(0)
+            .unwrap_or(&Value::Undefined)
+            .as_number(avm, context)?;
+        if seconds.is_finite() {
+            net_stream.seek(context, seconds);
+        }
+    } else {
+        log::warn!("NetStream.seek: this is not a NetStream");
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn set_buffer_time<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        let buffer_time = args
+            .get(0)
+            .unwrap_or(&Value::Undefined)
+            .as_number(avm, context)?;
+        if buffer_time.is_finite() {
+            net_stream.set_buffer_time(context.gc_context, buffer_time.max(0.0));
+        }
+    } else {
+        log::warn!("NetStream.setBufferTime: this is not a NetStream");
+    }
+    Ok(Value::Undefined.into())
+}
+
+fn time<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(net_stream) = this.as_net_stream_object() {
+        Ok(net_stream.time().into())
+    } else {
+        log::warn!("NetStream.time: this is not a NetStream");
+        Ok(Value::Undefined.into())
+    }
+}
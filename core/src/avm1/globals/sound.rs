@@ -1,10 +1,10 @@
 //! AVM1 Sound object
-//! TODO: Sound position, transform, loadSound
 
 use crate::avm1::function::Executable;
 use crate::avm1::property::Attribute::*;
 use crate::avm1::return_value::ReturnValue;
-use crate::avm1::{Avm1, Error, Object, SoundObject, TObject, UpdateContext, Value};
+use crate::avm1::{Avm1, Error, Object, ScriptObject, SoundObject, TObject, UpdateContext, Value};
+use crate::backend::audio::SoundTransform;
 use crate::character::Character;
 use gc_arena::MutationContext;
 
@@ -84,7 +84,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "get_transform",
+        "getTransform",
         get_transform,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -92,7 +92,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "get_volume",
+        "getVolume",
         get_volume,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -100,7 +100,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "load_sound",
+        "loadSound",
         load_sound,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -116,7 +116,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "set_pan",
+        "setPan",
         set_pan,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -124,7 +124,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "set_transform",
+        "setTransform",
         set_transform,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -132,7 +132,7 @@ pub fn create_proto<'gc>(
     );
 
     object.as_script_object().unwrap().force_set_function(
-        "set_volume",
+        "setVolume",
         set_volume,
         gc_context,
         DontDelete | ReadOnly | DontEnum,
@@ -203,99 +203,153 @@ fn duration<'gc>(
 fn get_bytes_loaded<'gc>(
     avm: &mut Avm1<'gc>,
     _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.getBytesLoaded: Unimplemented");
-        Ok(1.into())
-    } else {
-        Ok(Value::Undefined.into())
+        if let Some(sound_object) = this.as_sound_object() {
+            if let Some(bytes_loaded) = sound_object.bytes_loaded() {
+                return Ok(bytes_loaded.into());
+            }
+        } else {
+            log::warn!("Sound.getBytesLoaded: this is not a Sound");
+        }
     }
+
+    Ok(Value::Undefined.into())
 }
 
 fn get_bytes_total<'gc>(
     avm: &mut Avm1<'gc>,
     _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.getBytesTotal: Unimplemented");
-        Ok(1.into())
-    } else {
-        Ok(Value::Undefined.into())
+        if let Some(sound_object) = this.as_sound_object() {
+            if let Some(bytes_total) = sound_object.bytes_total() {
+                return Ok(bytes_total.into());
+            }
+        } else {
+            log::warn!("Sound.getBytesTotal: this is not a Sound");
+        }
     }
+
+    Ok(Value::Undefined.into())
 }
 
 fn get_pan<'gc>(
     _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.getPan: Unimplemented");
-    Ok(0.into())
+    if let Some(sound_object) = this.as_sound_object() {
+        let transform = context.audio_manager.sound_transform(sound_object.owner());
+        Ok(transform.pan().into())
+    } else {
+        log::warn!("Sound.getPan: this is not a Sound");
+        Ok(Value::Undefined.into())
+    }
 }
 
 fn get_transform<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.getTransform: Unimplemented");
-    Ok(Value::Undefined.into())
+    if let Some(sound_object) = this.as_sound_object() {
+        let transform = context.audio_manager.sound_transform(sound_object.owner());
+        let out = ScriptObject::object(context.gc_context, Some(avm.prototypes.object));
+        out.set("ll", transform.left_to_left.into(), avm, context)?;
+        out.set("lr", transform.left_to_right.into(), avm, context)?;
+        out.set("rl", transform.right_to_left.into(), avm, context)?;
+        out.set("rr", transform.right_to_right.into(), avm, context)?;
+        Ok(out.into())
+    } else {
+        log::warn!("Sound.getTransform: this is not a Sound");
+        Ok(Value::Undefined.into())
+    }
 }
 
 fn get_volume<'gc>(
     _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.getVolume: Unimplemented");
-    Ok(100.into())
+    if let Some(sound_object) = this.as_sound_object() {
+        let transform = context.audio_manager.sound_transform(sound_object.owner());
+        Ok(transform.volume.into())
+    } else {
+        log::warn!("Sound.getVolume: this is not a Sound");
+        Ok(Value::Undefined.into())
+    }
 }
 
 fn id3<'gc>(
     avm: &mut Avm1<'gc>,
     _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
+    this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.id3: Unimplemented");
+        if let Some(sound_object) = this.as_sound_object() {
+            if let Some(id3) = sound_object.id3() {
+                return Ok(id3.into());
+            }
+        } else {
+            log::warn!("Sound.id3: this is not a Sound");
+        }
     }
+
     Ok(Value::Undefined.into())
 }
 
 fn load_sound<'gc>(
     avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
-        log::warn!("Sound.loadSound: Unimplemented");
+        if let Some(sound_object) = this.as_sound_object() {
+            let url = args
+                .get(0)
+                .unwrap_or(&Value::Undefined)
+                .clone()
+                .coerce_to_string(avm, context)?;
+            let is_streaming = args
+                .get(1)
+                .unwrap_or(&Value::Undefined)
+                .as_bool(avm.current_swf_version());
+            sound_object.set_bytes_loaded(context.gc_context, Some(0), None);
+            context
+                .audio_manager
+                .load_sound(context.navigator, url, sound_object, is_streaming);
+        } else {
+            log::warn!("Sound.loadSound: this is not a Sound");
+        }
     }
     Ok(Value::Undefined.into())
 }
 
 fn position<'gc>(
     avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
     this: Object<'gc>,
     _args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
     if avm.current_swf_version() >= 6 {
         if let Some(sound_object) = this.as_sound_object() {
-            // TODO: The position is "sticky"; even if the sound is no longer playing, it should return
+            // The position is "sticky"; even if the sound is no longer playing, it returns
             // the previous valid position.
-            // Needs some audio backend work for this.
             if sound_object.sound().is_some() {
-                if let Some(_sound_instance) = sound_object.sound_instance() {
-                    log::warn!("Sound.position: Unimplemented");
+                if let Some(sound_instance) = sound_object.sound_instance() {
+                    if let Some(position) = context.audio.get_sound_position(sound_instance) {
+                        sound_object.set_position(context.gc_context, position);
+                    }
                 }
                 return Ok(sound_object.position().into());
             }
@@ -307,32 +361,89 @@ fn position<'gc>(
 }
 
 fn set_pan<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.setPan: Unimplemented");
+    let pan = args
+        .get(0)
+        .unwrap_or(&Value::Undefined)
+        .as_number(avm, context)?;
+    if let Some(sound_object) = this.as_sound_object() {
+        let owner = sound_object.owner();
+        let mut transform = context.audio_manager.sound_transform(owner);
+        transform.set_pan(pan as i32);
+        context
+            .audio_manager
+            .set_sound_transform(context.audio, owner, transform);
+    } else {
+        log::warn!("Sound.setPan: this is not a Sound");
+    }
     Ok(Value::Undefined.into())
 }
 
 fn set_transform<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.setTransform: Unimplemented");
+    let sound_object = if let Some(sound_object) = this.as_sound_object() {
+        sound_object
+    } else {
+        log::warn!("Sound.setTransform: this is not a Sound");
+        return Ok(Value::Undefined.into());
+    };
+
+    if let Ok(obj) = args.get(0).unwrap_or(&Value::Undefined).as_object() {
+        let owner = sound_object.owner();
+        let mut transform = context.audio_manager.sound_transform(owner);
+
+        // Missing properties keep their previous values.
+        let mut get_channel = |name: &str, value: &mut i32| -> Result<(), Error> {
+            if obj.has_property(name) {
+                *value = obj
+                    .get(name, avm, context)?
+                    .resolve(avm, context)?
+                    .as_number(avm, context)? as i32;
+            }
+            Ok(())
+        };
+        get_channel("ll", &mut transform.left_to_left)?;
+        get_channel("lr", &mut transform.left_to_right)?;
+        get_channel("rl", &mut transform.right_to_left)?;
+        get_channel("rr", &mut transform.right_to_right)?;
+
+        context
+            .audio_manager
+            .set_sound_transform(context.audio, owner, transform);
+    }
     Ok(Value::Undefined.into())
 }
 
 fn set_volume<'gc>(
-    _avm: &mut Avm1<'gc>,
-    _context: &mut UpdateContext<'_, 'gc, '_>,
-    _this: Object<'gc>,
-    _args: &[Value<'gc>],
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
 ) -> Result<ReturnValue<'gc>, Error> {
-    log::warn!("Sound.setVolume: Unimplemented");
+    let volume = args
+        .get(0)
+        .unwrap_or(&Value::Undefined)
+        .as_number(avm, context)?;
+    if let Some(sound_object) = this.as_sound_object() {
+        let owner = sound_object.owner();
+        let transform = SoundTransform {
+            volume: volume as i32,
+            ..context.audio_manager.sound_transform(owner)
+        };
+        context
+            .audio_manager
+            .set_sound_transform(context.audio, owner, transform);
+    } else {
+        log::warn!("Sound.setVolume: this is not a Sound");
+    }
     Ok(Value::Undefined.into())
 }
 
@@ -360,7 +471,8 @@ fn start<'gc>(
     use swf::{SoundEvent, SoundInfo};
     if let Some(sound_object) = this.as_sound_object() {
         if let Some(sound) = sound_object.sound() {
-            let sound_instance = context.audio.start_sound(
+            let sound_instance = context.audio_manager.start_sound(
+                context.audio,
                 sound,
                 &SoundInfo {
                     event: SoundEvent::Start,
@@ -373,8 +485,18 @@ fn start<'gc>(
                     num_loops: loops,
                     envelope: None,
                 },
+                sound_object.owner(),
+                Some(sound_object),
             );
             sound_object.set_sound_instance(context.gc_context, Some(sound_instance));
+            sound_object.set_position(
+                context.gc_context,
+                if start_offset > 0.0 {
+                    (start_offset * 1000.0) as u32
+                } else {
+                    0
+                },
+            );
         } else {
             log::warn!("Sound.start: No sound is attached");
         }
@@ -395,23 +517,27 @@ fn stop<'gc>(
         if let Some(name) = args.get(0) {
             // Usage 1: Stop all instances of a particular sound, using the name parameter.
             let name = name.clone().coerce_to_string(avm, context)?;
-            if let Some(Character::Sound(sound)) =
+            if let Some(Character::Sound(sound_handle)) =
                 context.library.get_character_by_export_name(&name)
             {
                 // Stop all sounds with the given name.
-                context.audio.stop_sounds_with_handle(*sound);
+                // A `Sound` with an owner only stops the instances playing within that clip.
+                context.audio_manager.stop_sounds_with_handle(
+                    context.audio,
+                    *sound_handle,
+                    sound.owner(),
+                );
             } else {
                 log::warn!("Sound.stop: Sound '{}' not found", name);
             }
-        } else if let Some(_owner) = sound.owner() {
+        } else if let Some(owner) = sound.owner() {
             // Usage 2: Stop all sound running within a given clip.
-            // TODO: We just stop the last played sound for now.
-            if let Some(sound_instance) = sound.sound_instance() {
-                context.audio.stop_sound(sound_instance);
-            }
+            context
+                .audio_manager
+                .stop_sounds_with_owner(context.audio, owner);
         } else {
             // Usage 3: If there is no owner and no name, this call acts like `stopAllSounds()`.
-            context.audio.stop_all_sounds();
+            context.audio_manager.stop_all_sounds(context.audio);
         }
     } else {
         log::warn!("Sound.stop: this is not a Sound");
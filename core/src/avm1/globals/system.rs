@@ -0,0 +1,47 @@
+//! System object
+//!
+//! TODO: Only `useCodepage` is implemented so far.
+use crate::avm1::function::Executable;
+use crate::avm1::property::Attribute;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
+
+use gc_arena::MutationContext;
+
+pub fn create_system_object<'gc>(
+    gc_context: MutationContext<'gc, '_>,
+    proto: Option<Object<'gc>>,
+) -> Object<'gc> {
+    let system = ScriptObject::object(gc_context, proto);
+
+    system.add_property(
+        gc_context,
+        "useCodepage",
+        Executable::Native(use_codepage),
+        Some(Executable::Native(set_use_codepage)),
+        Attribute::DontDelete.into(),
+    );
+
+    system.into()
+}
+
+fn use_codepage<'gc>(
+    _avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    _this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok((*context.use_codepage).into())
+}
+
+fn set_use_codepage<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    _this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(value) = args.get(0) {
+        *context.use_codepage = value.as_bool(avm.current_swf_version());
+    }
+    Ok(Value::Undefined.into())
+}
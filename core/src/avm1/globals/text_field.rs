@@ -1,9 +1,10 @@
-use crate::avm1::function::Executable;
+use crate::avm1::function::{Executable, NativeFunction};
 use crate::avm1::property::Attribute::*;
 use crate::avm1::return_value::ReturnValue;
 use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
-use crate::display_object::{EditText, TDisplayObject};
+use crate::display_object::{AutoSizeMode, EditText, TDisplayObject};
 use gc_arena::MutationContext;
+use swf::Color;
 
 /// Implements `TextField`
 pub fn constructor<'gc>(
@@ -43,7 +44,7 @@ pub fn set_text<'gc>(
                         .to_owned()
                         .coerce_to_string(avm, context)
                         .unwrap_or_else(|_| "undefined".to_string()),
-                    context.gc_context,
+                    context,
                 )
             }
         }
@@ -51,6 +52,262 @@ pub fn set_text<'gc>(
     Ok(Value::Undefined.into())
 }
 
+/// Converts an ActionScript color value (`0xRRGGBB`) to a `Color`.
+fn color_from_value<'gc>(
+    avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    value: &Value<'gc>,
+) -> Result<Color, Error> {
+    let rgb = value.as_number(avm, context)? as i64 as u32;
+    Ok(Color {
+        r: (rgb >> 16) as u8,
+        g: (rgb >> 8) as u8,
+        b: rgb as u8,
+        a: 255,
+    })
+}
+
+/// Converts a `Color` to an ActionScript color value (`0xRRGGBB`).
+fn color_to_value<'gc>(color: Color) -> Value<'gc> {
+    let rgb = (u32::from(color.r) << 16) | (u32::from(color.g) << 8) | u32::from(color.b);
+    Value::Number(rgb.into())
+}
+
+// Defines a native getter/setter pair for a virtual property of `TextField`.
+// The getter and setter are only called if `this` is a text field.
+macro_rules! text_field_property {
+    ($getter:ident, $setter:ident, |$gtf:ident, $gavm:ident, $gcontext:ident| $get:expr, |$stf:ident, $savm:ident, $scontext:ident, $value:ident| $set:expr) => {
+        pub fn $getter<'gc>(
+            $gavm: &mut Avm1<'gc>,
+            $gcontext: &mut UpdateContext<'_, 'gc, '_>,
+            this: Object<'gc>,
+            _args: &[Value<'gc>],
+        ) -> Result<ReturnValue<'gc>, Error> {
+            if let Some($gtf) = this.as_display_object().and_then(|o| o.as_edit_text()) {
+                let value: Value<'gc> = $get;
+                return Ok(value.into());
+            }
+            Ok(Value::Undefined.into())
+        }
+
+        pub fn $setter<'gc>(
+            $savm: &mut Avm1<'gc>,
+            $scontext: &mut UpdateContext<'_, 'gc, '_>,
+            this: Object<'gc>,
+            args: &[Value<'gc>],
+        ) -> Result<ReturnValue<'gc>, Error> {
+            if let Some($stf) = this.as_display_object().and_then(|o| o.as_edit_text()) {
+                if let Some($value) = args.get(0) {
+                    $set;
+                }
+            }
+            Ok(Value::Undefined.into())
+        }
+    };
+}
+
+text_field_property!(
+    get_text_color,
+    set_text_color,
+    |text_field, _avm, _context| color_to_value(text_field.text_color()),
+    |text_field, avm, context, value| {
+        let color = color_from_value(avm, context, value)?;
+        text_field.set_text_color(color, context.gc_context);
+    }
+);
+
+text_field_property!(
+    get_multiline,
+    set_multiline,
+    |text_field, _avm, _context| text_field.is_multiline().into(),
+    |text_field, _avm, context, value| {
+        text_field.set_multiline(value.as_bool(context.swf_version), context);
+    }
+);
+
+text_field_property!(
+    get_word_wrap,
+    set_word_wrap,
+    |text_field, _avm, _context| text_field.is_word_wrap().into(),
+    |text_field, _avm, context, value| {
+        text_field.set_word_wrap(value.as_bool(context.swf_version), context);
+    }
+);
+
+text_field_property!(
+    get_auto_size,
+    set_auto_size,
+    |text_field, _avm, _context| match text_field.autosize() {
+        AutoSizeMode::None => "none",
+        AutoSizeMode::Left => "left",
+        AutoSizeMode::Center => "center",
+        AutoSizeMode::Right => "right",
+    }
+    .into(),
+    |text_field, _avm, context, value| {
+        // `true` is equivalent to "left"; any unknown value disables autosizing.
+        let autosize = match value {
+            Value::String(s) if s == "left" => AutoSizeMode::Left,
+            Value::String(s) if s == "center" => AutoSizeMode::Center,
+            Value::String(s) if s == "right" => AutoSizeMode::Right,
+            Value::Bool(true) => AutoSizeMode::Left,
+            _ => AutoSizeMode::None,
+        };
+        text_field.set_autosize(autosize, context);
+    }
+);
+
+text_field_property!(
+    get_border,
+    set_border,
+    |text_field, _avm, _context| text_field.has_border().into(),
+    |text_field, _avm, context, value| {
+        text_field.set_has_border(value.as_bool(context.swf_version), context.gc_context);
+    }
+);
+
+text_field_property!(
+    get_border_color,
+    set_border_color,
+    |text_field, _avm, _context| color_to_value(text_field.border_color()),
+    |text_field, avm, context, value| {
+        let color = color_from_value(avm, context, value)?;
+        text_field.set_border_color(color, context.gc_context);
+    }
+);
+
+text_field_property!(
+    get_background,
+    set_background,
+    |text_field, _avm, _context| text_field.has_background().into(),
+    |text_field, _avm, context, value| {
+        text_field.set_has_background(value.as_bool(context.swf_version), context.gc_context);
+    }
+);
+
+text_field_property!(
+    get_background_color,
+    set_background_color,
+    |text_field, _avm, _context| color_to_value(text_field.background_color()),
+    |text_field, avm, context, value| {
+        let color = color_from_value(avm, context, value)?;
+        text_field.set_background_color(color, context.gc_context);
+    }
+);
+
+text_field_property!(
+    get_scroll,
+    set_scroll,
+    |text_field, _avm, _context| Value::Number(text_field.scroll() as f64),
+    |text_field, avm, context, value| {
+        let scroll = value.as_number(avm, context)?;
+        if scroll.is_finite() {
+            text_field.set_scroll(scroll.max(0.0) as usize, context.gc_context);
+        }
+    }
+);
+
+text_field_property!(
+    get_password,
+    set_password,
+    |text_field, _avm, _context| text_field.is_password().into(),
+    |text_field, _avm, context, value| {
+        text_field.set_password(value.as_bool(context.swf_version), context);
+    }
+);
+
+text_field_property!(
+    get_max_chars,
+    set_max_chars,
+    |text_field, _avm, _context| match text_field.max_chars() {
+        Some(max_chars) => Value::Number(max_chars as f64),
+        None => Value::Null,
+    },
+    |text_field, avm, context, value| {
+        // `null` or 0 removes the limit.
+        let max_chars = match value {
+            Value::Undefined | Value::Null => None,
+            value => {
+                let max_chars = value.as_number(avm, context)?;
+                if max_chars.is_finite() && max_chars >= 1.0 {
+                    Some(max_chars as usize)
+                } else {
+                    None
+                }
+            }
+        };
+        text_field.set_max_chars(max_chars, context.gc_context);
+    }
+);
+
+text_field_property!(
+    get_restrict,
+    set_restrict,
+    |text_field, _avm, _context| match text_field.restrict() {
+        Some(restrict) => restrict.into(),
+        None => Value::Null,
+    },
+    |text_field, avm, context, value| {
+        let restrict = match value {
+            Value::Undefined | Value::Null => None,
+            value => Some(value.to_owned().coerce_to_string(avm, context)?),
+        };
+        text_field.set_restrict(restrict, context.gc_context);
+    }
+);
+
+/// Implements the read-only `TextField.maxscroll` property.
+pub fn get_max_scroll<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(text_field) = this.as_display_object().and_then(|o| o.as_edit_text()) {
+        return Ok(Value::Number(text_field.max_scroll() as f64).into());
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// Implements the read-only `TextField.bottomScroll` property.
+pub fn get_bottom_scroll<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(text_field) = this.as_display_object().and_then(|o| o.as_edit_text()) {
+        return Ok(Value::Number(text_field.bottom_scroll() as f64).into());
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// Implements the read-only `TextField.textWidth` property.
+pub fn get_text_width<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(text_field) = this.as_display_object().and_then(|o| o.as_edit_text()) {
+        return Ok(Value::Number(text_field.text_width().to_pixels()).into());
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// Implements the read-only `TextField.textHeight` property.
+pub fn get_text_height<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(text_field) = this.as_display_object().and_then(|o| o.as_edit_text()) {
+        return Ok(Value::Number(text_field.text_height().to_pixels()).into());
+    }
+    Ok(Value::Undefined.into())
+}
+
 macro_rules! with_text_field {
     ( $gc_context: ident, $object:ident, $fn_proto: expr, $($name:expr => $fn:expr),* ) => {{
         $(
@@ -99,4 +356,48 @@ pub fn attach_virtual_properties<'gc>(gc_context: MutationContext<'gc, '_>, obje
         Some(Executable::Native(set_text)),
         DontDelete | ReadOnly | DontEnum,
     );
+
+    let read_write_properties: &[(&str, NativeFunction<'gc>, NativeFunction<'gc>)] = &[
+        ("textColor", get_text_color, set_text_color),
+        ("multiline", get_multiline, set_multiline),
+        ("wordWrap", get_word_wrap, set_word_wrap),
+        ("autoSize", get_auto_size, set_auto_size),
+        ("border", get_border, set_border),
+        ("borderColor", get_border_color, set_border_color),
+        ("background", get_background, set_background),
+        (
+            "backgroundColor",
+            get_background_color,
+            set_background_color,
+        ),
+        ("scroll", get_scroll, set_scroll),
+        ("password", get_password, set_password),
+        ("maxChars", get_max_chars, set_max_chars),
+        ("restrict", get_restrict, set_restrict),
+    ];
+    for (name, getter, setter) in read_write_properties {
+        object.add_property(
+            gc_context,
+            name,
+            Executable::Native(*getter),
+            Some(Executable::Native(*setter)),
+            DontDelete | DontEnum,
+        );
+    }
+
+    let read_only_properties: &[(&str, NativeFunction<'gc>)] = &[
+        ("maxscroll", get_max_scroll),
+        ("bottomScroll", get_bottom_scroll),
+        ("textWidth", get_text_width),
+        ("textHeight", get_text_height),
+    ];
+    for (name, getter) in read_only_properties {
+        object.add_property(
+            gc_context,
+            name,
+            Executable::Native(*getter),
+            None,
+            DontDelete | ReadOnly | DontEnum,
+        );
+    }
 }
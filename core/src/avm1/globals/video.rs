@@ -0,0 +1,113 @@
+//! AVM1 Video object
+
+use crate::avm1::function::Executable;
+use crate::avm1::property::Attribute::*;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, Object, ScriptObject, TObject, UpdateContext, Value};
+use crate::display_object::{TDisplayObject, Video};
+use gc_arena::MutationContext;
+
+/// Implements `Video`
+pub fn constructor<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    _this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    Ok(Value::Undefined.into())
+}
+
+pub fn create_proto<'gc>(
+    gc_context: MutationContext<'gc, '_>,
+    proto: Object<'gc>,
+    fn_proto: Object<'gc>,
+) -> Object<'gc> {
+    let mut object = ScriptObject::object(gc_context, Some(proto));
+
+    object.force_set_function(
+        "attachVideo",
+        attach_video,
+        gc_context,
+        DontDelete | ReadOnly | DontEnum,
+        Some(fn_proto),
+    );
+
+    object.add_property(
+        gc_context,
+        "height",
+        Executable::Native(height),
+        None,
+        DontDelete | ReadOnly | DontEnum,
+    );
+
+    object.add_property(
+        gc_context,
+        "width",
+        Executable::Native(width),
+        None,
+        DontDelete | ReadOnly | DontEnum,
+    );
+
+    object.into()
+}
+
+fn video_object<'gc>(this: Object<'gc>) -> Option<Video<'gc>> {
+    this.as_display_object().and_then(|o| o.as_video())
+}
+
+fn attach_video<'gc>(
+    _avm: &mut Avm1<'gc>,
+    context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(video) = video_object(this) {
+        // Anything other than a `NetStream`, such as `null`, detaches the stream.
+        let net_stream = args
+            .get(0)
+            .and_then(|v| v.as_object().ok())
+            .and_then(|o| o.as_net_stream_object());
+        video.attach_net_stream(context.gc_context, net_stream);
+    } else {
+        log::warn!("Video.attachVideo: this is not a Video");
+    }
+    Ok(Value::Undefined.into())
+}
+
+/// The height of the displayed frame in pixels, which may differ from the size of the `Video`.
+fn height<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(video) = video_object(this) {
+        let height = video
+            .current_bitmap()
+            .map(|b| u32::from(b.height))
+            .unwrap_or(0);
+        Ok(height.into())
+    } else {
+        log::warn!("Video.height: this is not a Video");
+        Ok(Value::Undefined.into())
+    }
+}
+
+/// The width of the displayed frame in pixels, which may differ from the size of the `Video`.
+fn width<'gc>(
+    _avm: &mut Avm1<'gc>,
+    _context: &mut UpdateContext<'_, 'gc, '_>,
+    this: Object<'gc>,
+    _args: &[Value<'gc>],
+) -> Result<ReturnValue<'gc>, Error> {
+    if let Some(video) = video_object(this) {
+        let width = video
+            .current_bitmap()
+            .map(|b| u32::from(b.width))
+            .unwrap_or(0);
+        Ok(width.into())
+    } else {
+        log::warn!("Video.width: this is not a Video");
+        Ok(Value::Undefined.into())
+    }
+}
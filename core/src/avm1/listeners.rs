@@ -138,24 +138,28 @@ impl<'gc> Listeners<'gc> {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SystemListener {
+    Key,
     Mouse,
 }
 
 #[derive(Clone, Collect, Debug, Copy)]
 #[collect(no_drop)]
 pub struct SystemListeners<'gc> {
+    pub key: Listeners<'gc>,
     pub mouse: Listeners<'gc>,
 }
 
 impl<'gc> SystemListeners<'gc> {
     pub fn new(gc_context: MutationContext<'gc, '_>, array_proto: Option<Object<'gc>>) -> Self {
         Self {
+            key: Listeners::new(gc_context, array_proto),
             mouse: Listeners::new(gc_context, array_proto),
         }
     }
 
     pub fn get(&self, listener: SystemListener) -> Listeners<'gc> {
         match listener {
+            SystemListener::Key => self.key,
             SystemListener::Mouse => self.mouse,
         }
     }
@@ -0,0 +1,397 @@
+//! AVM1 object type to represent NetStream objects.
+
+use crate::avm1::function::Executable;
+use crate::avm1::property::Attribute;
+use crate::avm1::return_value::ReturnValue;
+use crate::avm1::{Avm1, Error, Object, ObjectPtr, ScriptObject, TObject, Value};
+use crate::backend::render::BitmapInfo;
+use crate::context::{ActionType, UpdateContext};
+use crate::display_object::DisplayObject;
+use crate::flv::{amf0, ScriptTag};
+use crate::net_stream::{NetStream, NetStreamEvent};
+use enumset::EnumSet;
+use gc_arena::{Collect, GcCell, MutationContext};
+use std::collections::HashSet;
+use std::fmt;
+
+/// A NetStream object that plays an FLV file.
+///
+/// The playback is done by a `NetStream`, whose events are dispatched to the object as
+/// calls of `onStatus`, `onMetaData` and `onCuePoint`.
+#[derive(Clone, Copy, Collect)]
+#[collect(no_drop)]
+pub struct NetStreamObject<'gc>(GcCell<'gc, NetStreamObjectData<'gc>>);
+
+pub struct NetStreamObjectData<'gc> {
+    /// The underlying script object.
+    base: ScriptObject<'gc>,
+
+    stream: NetStream,
+}
+
+unsafe impl<'gc> Collect for NetStreamObjectData<'gc> {
+    fn trace(&self, cc: gc_arena::CollectionContext) {
+        self.base.trace(cc);
+    }
+}
+
+impl fmt::Debug for NetStreamObject<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let this = self.0.read();
+        f.debug_struct("NetStreamObject")
+            .field("time", &this.stream.time())
+            .field("is_active", &this.stream.is_active())
+            .finish()
+    }
+}
+
+impl<'gc> NetStreamObject<'gc> {
+    pub fn empty_net_stream(
+        gc_context: MutationContext<'gc, '_>,
+        proto: Option<Object<'gc>>,
+    ) -> NetStreamObject<'gc> {
+        NetStreamObject(GcCell::allocate(
+            gc_context,
+            NetStreamObjectData {
+                base: ScriptObject::object(gc_context, proto),
+                stream: NetStream::new(),
+            },
+        ))
+    }
+
+    /// The position of the playhead in seconds.
+    pub fn time(self) -> f64 {
+        self.0.read().stream.time()
+    }
+
+    /// The number of seconds of the file that are loaded ahead of the playhead.
+    pub fn buffer_length(self) -> f64 {
+        self.0.read().stream.buffer_length()
+    }
+
+    pub fn buffer_time(self) -> f64 {
+        self.0.read().stream.buffer_time()
+    }
+
+    pub fn set_buffer_time(self, gc_context: MutationContext<'gc, '_>, buffer_time: f64) {
+        self.0.write(gc_context).stream.set_buffer_time(buffer_time);
+    }
+
+    pub fn bytes_loaded(self) -> u32 {
+        self.0.read().stream.bytes_loaded()
+    }
+
+    pub fn bytes_total(self) -> u32 {
+        self.0.read().stream.bytes_total()
+    }
+
+    /// The last frame of video that was decoded.
+    pub fn decoded_frame(self) -> Option<BitmapInfo> {
+        self.0.read().stream.decoded_frame()
+    }
+
+    /// Whether the stream is loading or playing a file.
+    pub fn is_active(self) -> bool {
+        self.0.read().stream.is_active()
+    }
+
+    /// Begins loading and playing the file at the given URL.
+    pub fn play(self, context: &mut UpdateContext<'_, 'gc, '_>, url: String) {
+        self.0.write(context.gc_context).stream.play(context, url);
+        context.net_stream_manager.add(self);
+        self.dispatch_events(context);
+    }
+
+    /// Stops playing and unloads the current file.
+    pub fn close(self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        self.0.write(context.gc_context).stream.close(context);
+        self.dispatch_events(context);
+    }
+
+    /// Pauses or resumes playback. `None` toggles between the two.
+    pub fn pause(self, context: &mut UpdateContext<'_, 'gc, '_>, is_paused: Option<bool>) {
+        self.0
+            .write(context.gc_context)
+            .stream
+            .pause(context, is_paused);
+        self.dispatch_events(context);
+    }
+
+    /// Moves the playhead to the keyframe closest before the given time in seconds.
+    pub fn seek(self, context: &mut UpdateContext<'_, 'gc, '_>, seconds: f64) {
+        self.0
+            .write(context.gc_context)
+            .stream
+            .seek(context, seconds);
+        self.dispatch_events(context);
+    }
+
+    /// Advances the stream by the given number of milliseconds.
+    /// Called by the `NetStreamManager` every frame.
+    pub fn update(self, context: &mut UpdateContext<'_, 'gc, '_>, frame_time: f64) {
+        self.0
+            .write(context.gc_context)
+            .stream
+            .update(context, frame_time);
+        self.dispatch_events(context);
+    }
+
+    /// Queues the handlers of the events of the stream.
+    fn dispatch_events(self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        let events = self.0.write(context.gc_context).stream.take_events();
+        for event in events {
+            match event {
+                NetStreamEvent::Status { code, level } => self.queue_status(context, code, level),
+                NetStreamEvent::Script(script) => self.queue_script_event(context, &script),
+            }
+        }
+    }
+
+    /// Queues a call of `onStatus` with an info object.
+    fn queue_status(self, context: &mut UpdateContext<'_, 'gc, '_>, code: &str, level: &str) {
+        let info = ScriptObject::object(context.gc_context, Some(context.system_prototypes.object));
+        info.define_value(context.gc_context, "code", code.into(), EnumSet::empty());
+        info.define_value(context.gc_context, "level", level.into(), EnumSet::empty());
+        context.action_queue.queue_actions(
+            context.root,
+            ActionType::Callback {
+                object: self.into(),
+                name: "onStatus",
+                args: vec![info.into()],
+            },
+            false,
+        );
+    }
+
+    /// Queues a call of the handler of a script data event, such as `onMetaData`.
+    fn queue_script_event(self, context: &mut UpdateContext<'_, 'gc, '_>, script: &ScriptTag) {
+        let name = match &script.name[..] {
+            "onMetaData" => "onMetaData",
+            "onCuePoint" => "onCuePoint",
+            name => {
+                log::info!("NetStream: Ignoring script data event {}", name);
+                return;
+            }
+        };
+        let value = amf0_to_avm1(context, &script.value);
+        context.action_queue.queue_actions(
+            context.root,
+            ActionType::Callback {
+                object: self.into(),
+                name,
+                args: vec![value],
+            },
+            false,
+        );
+    }
+
+    fn base(self) -> ScriptObject<'gc> {
+        self.0.read().base
+    }
+}
+
+/// Converts the value of a script data event to an AVM1 value.
+fn amf0_to_avm1<'gc>(context: &mut UpdateContext<'_, 'gc, '_>, value: &amf0::Value) -> Value<'gc> {
+    match value {
+        amf0::Value::Number(n) | amf0::Value::Date(n) => Value::Number(*n),
+        amf0::Value::Bool(b) => Value::Bool(*b),
+        amf0::Value::String(s) => Value::String(s.clone()),
+        amf0::Value::Null => Value::Null,
+        amf0::Value::Undefined => Value::Undefined,
+        amf0::Value::Object(properties) | amf0::Value::EcmaArray(properties) => {
+            let object =
+                ScriptObject::object(context.gc_context, Some(context.system_prototypes.object));
+            for (name, value) in properties {
+                let value = amf0_to_avm1(context, value);
+                object.define_value(context.gc_context, name, value, EnumSet::empty());
+            }
+            object.into()
+        }
+        amf0::Value::StrictArray(values) => {
+            let array =
+                ScriptObject::array(context.gc_context, Some(context.system_prototypes.array));
+            for (i, value) in values.iter().enumerate() {
+                let value = amf0_to_avm1(context, value);
+                array.set_array_element(i, value, context.gc_context);
+            }
+            array.into()
+        }
+    }
+}
+
+impl<'gc> TObject<'gc> for NetStreamObject<'gc> {
+    fn get_local(
+        &self,
+        name: &str,
+        avm: &mut Avm1<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        this: Object<'gc>,
+    ) -> Result<ReturnValue<'gc>, Error> {
+        self.base().get_local(name, avm, context, this)
+    }
+
+    fn set(
+        &self,
+        name: &str,
+        value: Value<'gc>,
+        avm: &mut Avm1<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+    ) -> Result<(), Error> {
+        self.base().set(name, value, avm, context)
+    }
+
+    fn call(
+        &self,
+        avm: &mut Avm1<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        this: Object<'gc>,
+        args: &[Value<'gc>],
+    ) -> Result<ReturnValue<'gc>, Error> {
+        self.base().call(avm, context, this, args)
+    }
+
+    #[allow(clippy::new_ret_no_self)]
+    fn new(
+        &self,
+        avm: &mut Avm1<'gc>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        _this: Object<'gc>,
+        _args: &[Value<'gc>],
+    ) -> Result<Object<'gc>, Error> {
+        Ok(
+            NetStreamObject::empty_net_stream(context.gc_context, Some(avm.prototypes.net_stream))
+                .into(),
+        )
+    }
+
+    fn delete(&self, gc_context: MutationContext<'gc, '_>, name: &str) -> bool {
+        self.base().delete(gc_context, name)
+    }
+
+    fn proto(&self) -> Option<Object<'gc>> {
+        self.base().proto()
+    }
+
+    fn define_value(
+        &self,
+        gc_context: MutationContext<'gc, '_>,
+        name: &str,
+        value: Value<'gc>,
+        attributes: EnumSet<Attribute>,
+    ) {
+        self.base()
+            .define_value(gc_context, name, value, attributes)
+    }
+
+    fn set_attributes(
+        &mut self,
+        gc_context: MutationContext<'gc, '_>,
+        name: Option<&str>,
+        set_attributes: EnumSet<Attribute>,
+        clear_attributes: EnumSet<Attribute>,
+    ) {
+        self.base()
+            .set_attributes(gc_context, name, set_attributes, clear_attributes)
+    }
+
+    fn add_property(
+        &self,
+        gc_context: MutationContext<'gc, '_>,
+        name: &str,
+        get: Executable<'gc>,
+        set: Option<Executable<'gc>>,
+        attributes: EnumSet<Attribute>,
+    ) {
+        self.base()
+            .add_property(gc_context, name, get, set, attributes)
+    }
+
+    fn has_property(&self, name: &str) -> bool {
+        self.base().has_property(name)
+    }
+
+    fn has_own_property(&self, name: &str) -> bool {
+        self.base().has_own_property(name)
+    }
+
+    fn is_property_overwritable(&self, name: &str) -> bool {
+        self.base().is_property_overwritable(name)
+    }
+
+    fn is_property_enumerable(&self, name: &str) -> bool {
+        self.base().is_property_enumerable(name)
+    }
+
+    fn get_keys(&self) -> HashSet<String> {
+        self.base().get_keys()
+    }
+
+    fn as_string(&self) -> String {
+        self.base().as_string()
+    }
+
+    fn type_of(&self) -> &'static str {
+        self.base().type_of()
+    }
+
+    fn interfaces(&self) -> Vec<Object<'gc>> {
+        self.base().interfaces()
+    }
+
+    fn set_interfaces(
+        &mut self,
+        gc_context: MutationContext<'gc, '_>,
+        iface_list: Vec<Object<'gc>>,
+    ) {
+        self.base().set_interfaces(gc_context, iface_list)
+    }
+
+    fn as_script_object(&self) -> Option<ScriptObject<'gc>> {
+        Some(self.base())
+    }
+
+    fn as_display_object(&self) -> Option<DisplayObject<'gc>> {
+        None
+    }
+
+    fn as_executable(&self) -> Option<Executable<'gc>> {
+        None
+    }
+
+    fn as_net_stream_object(&self) -> Option<NetStreamObject<'gc>> {
+        Some(*self)
+    }
+
+    fn as_ptr(&self) -> *const ObjectPtr {
+        self.0.as_ptr() as *const ObjectPtr
+    }
+
+    fn length(&self) -> usize {
+        self.base().length()
+    }
+
+    fn array(&self) -> Vec<Value<'gc>> {
+        self.base().array()
+    }
+
+    fn set_length(&self, gc_context: MutationContext<'gc, '_>, length: usize) {
+        self.base().set_length(gc_context, length)
+    }
+
+    fn array_element(&self, index: usize) -> Value<'gc> {
+        self.base().array_element(index)
+    }
+
+    fn set_array_element(
+        &self,
+        index: usize,
+        value: Value<'gc>,
+        gc_context: MutationContext<'gc, '_>,
+    ) -> usize {
+        self.base().set_array_element(index, value, gc_context)
+    }
+
+    fn delete_array_element(&self, index: usize, gc_context: MutationContext<'gc, '_>) {
+        self.base().delete_array_element(index, gc_context)
+    }
+}
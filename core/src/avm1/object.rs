@@ -7,7 +7,9 @@ use crate::avm1::super_object::SuperObject;
 use crate::avm1::xml_attributes_object::XMLAttributesObject;
 use crate::avm1::xml_idmap_object::XMLIDMapObject;
 use crate::avm1::xml_object::XMLObject;
-use crate::avm1::{Avm1, Error, ScriptObject, SoundObject, StageObject, UpdateContext, Value};
+use crate::avm1::{
+    Avm1, Error, NetStreamObject, ScriptObject, SoundObject, StageObject, UpdateContext, Value,
+};
 use crate::display_object::DisplayObject;
 use crate::xml::XMLNode;
 use enumset::EnumSet;
@@ -23,6 +25,7 @@ use std::fmt::Debug;
     #[collect(no_drop)]
     pub enum Object<'gc> {
         ScriptObject(ScriptObject<'gc>),
+        NetStreamObject(NetStreamObject<'gc>),
         SoundObject(SoundObject<'gc>),
         StageObject(StageObject<'gc>),
         SuperObject(SuperObject<'gc>),
@@ -259,6 +262,11 @@ pub trait TObject<'gc>: 'gc + Collect + Debug + Into<Object<'gc>> + Clone + Copy
         None
     }
 
+    /// Get the underlying NetStream object, if it exists.
+    fn as_net_stream_object(&self) -> Option<NetStreamObject<'gc>> {
+        None
+    }
+
     /// Get the underlying super object, if it exists.
     fn as_super_object(&self) -> Option<SuperObject<'gc>> {
         None
@@ -642,12 +642,15 @@ mod tests {
 
     use crate::avm1::activation::Activation;
     use crate::avm1::property::Attribute::*;
-    use crate::backend::audio::NullAudioBackend;
+    use crate::backend::audio::{AudioManager, NullAudioBackend};
+    use crate::backend::font::NullFontBackend;
     use crate::backend::input::NullInputBackend;
     use crate::backend::navigator::NullNavigatorBackend;
     use crate::backend::render::NullRenderer;
+    use crate::backend::video::NullVideoBackend;
     use crate::display_object::MovieClip;
     use crate::library::Library;
+    use crate::net_stream::NetStreamManager;
     use crate::prelude::*;
     use gc_arena::rootless_arena;
     use rand::{rngs::SmallRng, SeedableRng};
@@ -671,7 +674,11 @@ mod tests {
                 rng: &mut SmallRng::from_seed([0u8; 16]),
                 action_queue: &mut crate::context::ActionQueue::new(),
                 audio: &mut NullAudioBackend::new(),
+                audio_manager: &mut AudioManager::new(),
+                net_stream_manager: &mut NetStreamManager::new(),
                 input: &mut NullInputBackend::new(),
+                fonts: &mut NullFontBackend::new(),
+                video: &mut NullVideoBackend::new(),
                 background_color: &mut Color {
                     r: 0,
                     g: 0,
@@ -682,10 +689,15 @@ mod tests {
                 navigator: &mut NullNavigatorBackend::new(),
                 renderer: &mut NullRenderer::new(),
                 swf_data: &mut Arc::new(vec![]),
+                swf_bytes_loaded: 0,
+                swf_bytes_total: 0,
+                system_codepage: swf::Codepage::default(),
+                use_codepage: &mut false,
                 system_prototypes: avm.prototypes().clone(),
                 mouse_hovered_object: None,
                 mouse_position: &(Twips::new(0), Twips::new(0)),
                 drag_object: &mut None,
+                focused_text_field: &mut None,
                 stage_size: (Twips::from_pixels(550.0), Twips::from_pixels(400.0)),
             };
 
@@ -38,12 +38,24 @@ pub struct SoundObjectData<'gc> {
 
     /// Duration of the currently attached sound in milliseconds.
     duration: u32,
+
+    /// Number of bytes loaded by `Sound.loadSound`.
+    /// `None` if no sound has been loaded.
+    bytes_loaded: Option<u32>,
+
+    /// Total size of the sound loaded by `Sound.loadSound`, in bytes.
+    /// `None` if no sound has been loaded, or the size is not known yet.
+    bytes_total: Option<u32>,
+
+    /// The ID3 metadata of the loaded sound, exposed as `Sound.id3`.
+    id3: Option<Object<'gc>>,
 }
 
 unsafe impl<'gc> Collect for SoundObjectData<'gc> {
     fn trace(&self, cc: gc_arena::CollectionContext) {
         self.base.trace(cc);
         self.owner.trace(cc);
+        self.id3.trace(cc);
     }
 }
 
@@ -72,6 +84,9 @@ impl<'gc> SoundObject<'gc> {
                 owner: None,
                 position: 0,
                 duration: 0,
+                bytes_loaded: None,
+                bytes_total: None,
+                id3: None,
             },
         ))
     }
@@ -124,6 +139,33 @@ impl<'gc> SoundObject<'gc> {
         self.0.write(gc_context).position = position;
     }
 
+    pub fn bytes_loaded(self) -> Option<u32> {
+        self.0.read().bytes_loaded
+    }
+
+    pub fn bytes_total(self) -> Option<u32> {
+        self.0.read().bytes_total
+    }
+
+    pub fn set_bytes_loaded(
+        self,
+        gc_context: MutationContext<'gc, '_>,
+        bytes_loaded: Option<u32>,
+        bytes_total: Option<u32>,
+    ) {
+        let mut data = self.0.write(gc_context);
+        data.bytes_loaded = bytes_loaded;
+        data.bytes_total = bytes_total;
+    }
+
+    pub fn id3(self) -> Option<Object<'gc>> {
+        self.0.read().id3
+    }
+
+    pub fn set_id3(self, gc_context: MutationContext<'gc, '_>, id3: Option<Object<'gc>>) {
+        self.0.write(gc_context).id3 = id3;
+    }
+
     fn base(self) -> ScriptObject<'gc> {
         self.0.read().base
     }
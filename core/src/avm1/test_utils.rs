@@ -1,12 +1,15 @@
 use crate::avm1::activation::Activation;
 use crate::avm1::{Avm1, Object, UpdateContext};
-use crate::backend::audio::NullAudioBackend;
+use crate::backend::audio::{AudioManager, NullAudioBackend};
+use crate::backend::font::NullFontBackend;
 use crate::backend::input::NullInputBackend;
 use crate::backend::navigator::NullNavigatorBackend;
 use crate::backend::render::NullRenderer;
+use crate::backend::video::{NullVideoBackend, VideoBackend};
 use crate::context::ActionQueue;
 use crate::display_object::{MovieClip, TDisplayObject};
 use crate::library::Library;
+use crate::net_stream::NetStreamManager;
 use crate::prelude::*;
 use gc_arena::{rootless_arena, GcCell, MutationContext};
 use rand::{rngs::SmallRng, SeedableRng};
@@ -16,7 +19,20 @@ pub fn with_avm<F, R>(swf_version: u8, test: F) -> R
 where
     F: for<'a, 'gc> FnOnce(&mut Avm1<'gc>, &mut UpdateContext<'a, 'gc, '_>, Object<'gc>) -> R,
 {
-    fn in_the_arena<'gc, F, R>(swf_version: u8, test: F, gc_context: MutationContext<'gc, '_>) -> R
+    with_avm_and_video(swf_version, &mut NullVideoBackend::new(), test)
+}
+
+/// Runs a test with the given video backend, instead of one that can't decode video.
+pub fn with_avm_and_video<F, R>(swf_version: u8, video: &mut dyn VideoBackend, test: F) -> R
+where
+    F: for<'a, 'gc> FnOnce(&mut Avm1<'gc>, &mut UpdateContext<'a, 'gc, '_>, Object<'gc>) -> R,
+{
+    fn in_the_arena<'gc, F, R>(
+        swf_version: u8,
+        video: &mut dyn VideoBackend,
+        test: F,
+        gc_context: MutationContext<'gc, '_>,
+    ) -> R
     where
         F: for<'a> FnOnce(&mut Avm1<'gc>, &mut UpdateContext<'a, 'gc, '_>, Object<'gc>) -> R,
     {
@@ -32,7 +48,11 @@ where
             root,
             rng: &mut SmallRng::from_seed([0u8; 16]),
             audio: &mut NullAudioBackend::new(),
+            audio_manager: &mut AudioManager::new(),
+            net_stream_manager: &mut NetStreamManager::new(),
             input: &mut NullInputBackend::new(),
+            fonts: &mut NullFontBackend::new(),
+            video,
             action_queue: &mut ActionQueue::new(),
             background_color: &mut Color {
                 r: 0,
@@ -44,10 +64,15 @@ where
             navigator: &mut NullNavigatorBackend::new(),
             renderer: &mut NullRenderer::new(),
             swf_data: &mut Arc::new(vec![]),
+            swf_bytes_loaded: 0,
+            swf_bytes_total: 0,
+            system_codepage: swf::Codepage::default(),
+            use_codepage: &mut false,
             system_prototypes: avm.prototypes().clone(),
             mouse_hovered_object: None,
             mouse_position: &(Twips::new(0), Twips::new(0)),
             drag_object: &mut None,
+            focused_text_field: &mut None,
             stage_size: (Twips::from_pixels(550.0), Twips::from_pixels(400.0)),
         };
 
@@ -62,7 +87,7 @@ where
         test(&mut avm, &mut context, this)
     }
 
-    rootless_arena(|gc_context| in_the_arena(swf_version, test, gc_context))
+    rootless_arena(|gc_context| in_the_arena(swf_version, video, test, gc_context))
 }
 
 macro_rules! test_method {
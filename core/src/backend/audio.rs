@@ -1,7 +1,15 @@
+use crate::avm1::{Object, ScriptObject, SoundObject, TObject, Value};
+use crate::backend::navigator::{FetchHandle, FetchStatus, NavigatorBackend};
+use crate::context::{ActionQueue, ActionType, UpdateContext};
+use crate::display_object::{DisplayObject, TDisplayObject};
+use gc_arena::{Collect, CollectionContext, MutationContext};
 use generational_arena::{Arena, Index};
 use std::ops::{Deref, DerefMut};
 
+pub mod capture;
 pub mod decoders;
+pub mod id3;
+pub mod mixer;
 pub mod swf {
     pub use swf::{
         read, AudioCompression, CharacterId, Sound, SoundEnvelope, SoundEnvelopePoint, SoundEvent,
@@ -18,6 +26,26 @@ type Error = Box<dyn std::error::Error>;
 pub trait AudioBackend {
     fn prime_audio(&mut self) {}
     fn register_sound(&mut self, swf_sound: &swf::Sound) -> Result<SoundHandle, Error>;
+
+    /// Registers an external MP3 file, such as one loaded by `Sound.loadSound`.
+    /// `data` should not contain ID3 tags.
+    fn register_mp3(&mut self, data: &[u8]) -> Result<SoundHandle, Error> {
+        let metadata = decoders::mp3_metadata(data).ok_or("Invalid MP3 data")?;
+        // MP3 data in SWF `DefineSound` tags is prefixed by the "latency seek" sample count.
+        let mut sound_data = vec![0, 0];
+        sound_data.extend_from_slice(data);
+        self.register_sound(&swf::Sound {
+            id: 0,
+            format: swf::SoundFormat {
+                compression: swf::AudioCompression::Mp3,
+                sample_rate: metadata.sample_rate,
+                is_16_bit: true,
+                is_stereo: metadata.is_stereo,
+            },
+            num_samples: metadata.num_sample_frames,
+            data: sound_data,
+        })
+    }
     fn preload_sound_stream_head(
         &mut self,
         _clip_id: swf::CharacterId,
@@ -67,10 +95,46 @@ pub trait AudioBackend {
     /// which only plays a sound if that sound is not already playing.
     fn is_sound_playing_with_handle(&mut self, handle: SoundHandle) -> bool;
 
+    /// Returns whether a sound instance is still playing.
+    /// Used by `AudioManager` to prune finished sounds and fire `Sound.onSoundComplete`.
+    fn is_sound_playing(&mut self, _instance: SoundInstanceHandle) -> bool {
+        false
+    }
+
+    /// Returns whether a stream sound is still playing.
+    /// Used by `AudioManager` to prune finished streams. Backends that can't tell keep
+    /// streams until they are stopped.
+    fn is_stream_playing(&mut self, _stream: AudioStreamHandle) -> bool {
+        true
+    }
+
+    /// Get the playback position of a sound instance in milliseconds,
+    /// relative to the start of the current loop.
+    /// Returns `None` if the sound is not playing.
+    fn get_sound_position(&self, _instance: SoundInstanceHandle) -> Option<u32> {
+        None
+    }
+
+    /// Get the playback position of a stream sound in milliseconds,
+    /// relative to the frame where the stream started.
+    /// Used to keep the timeline in sync with the stream.
+    /// Returns `None` if the stream is not playing or the position is unknown.
+    fn get_stream_position(&self, _stream: AudioStreamHandle) -> Option<u32> {
+        None
+    }
+
     /// Get the duration of a sound in milliseconds.
     /// Returns `None` if sound is not registered.
     fn get_sound_duration(&self, sound: SoundHandle) -> Option<u32>;
 
+    /// Sets the volume and channel mix of a playing sound instance.
+    /// No-op if the sound is not playing.
+    fn set_sound_transform(&mut self, _instance: SoundInstanceHandle, _transform: SoundTransform) {}
+
+    /// Sets the volume and channel mix of a playing stream sound.
+    /// No-op if the stream is not playing.
+    fn set_stream_transform(&mut self, _stream: AudioStreamHandle, _transform: SoundTransform) {}
+
     // TODO: Eventually remove this/move it to library.
     fn is_loading_complete(&self) -> bool {
         true
@@ -90,6 +154,9 @@ impl<T: AudioBackend + ?Sized> AudioBackend for Box<T> {
     fn register_sound(&mut self, swf_sound: &swf::Sound) -> Result<SoundHandle, Error> {
         self.deref_mut().register_sound(swf_sound)
     }
+    fn register_mp3(&mut self, data: &[u8]) -> Result<SoundHandle, Error> {
+        self.deref_mut().register_mp3(data)
+    }
     fn preload_sound_stream_head(
         &mut self,
         clip_id: swf::CharacterId,
@@ -147,10 +214,34 @@ impl<T: AudioBackend + ?Sized> AudioBackend for Box<T> {
         self.deref_mut().is_sound_playing_with_handle(handle)
     }
 
+    fn is_sound_playing(&mut self, instance: SoundInstanceHandle) -> bool {
+        self.deref_mut().is_sound_playing(instance)
+    }
+
+    fn is_stream_playing(&mut self, stream: AudioStreamHandle) -> bool {
+        self.deref_mut().is_stream_playing(stream)
+    }
+
+    fn get_sound_position(&self, instance: SoundInstanceHandle) -> Option<u32> {
+        self.deref().get_sound_position(instance)
+    }
+
+    fn get_stream_position(&self, stream: AudioStreamHandle) -> Option<u32> {
+        self.deref().get_stream_position(stream)
+    }
+
     fn get_sound_duration(&self, sound: SoundHandle) -> Option<u32> {
         self.deref().get_sound_duration(sound)
     }
 
+    fn set_sound_transform(&mut self, instance: SoundInstanceHandle, transform: SoundTransform) {
+        self.deref_mut().set_sound_transform(instance, transform)
+    }
+
+    fn set_stream_transform(&mut self, stream: AudioStreamHandle, transform: SoundTransform) {
+        self.deref_mut().set_stream_transform(stream, transform)
+    }
+
     fn is_loading_complete(&self) -> bool {
         self.deref().is_loading_complete()
     }
@@ -208,6 +299,10 @@ impl AudioBackend for NullAudioBackend {
         false
     }
 
+    fn is_stream_playing(&mut self, stream: AudioStreamHandle) -> bool {
+        self.streams.contains(stream)
+    }
+
     fn get_sound_duration(&self, _sound: SoundHandle) -> Option<u32> {
         None
     }
@@ -218,3 +313,846 @@ impl Default for NullAudioBackend {
         NullAudioBackend::new()
     }
 }
+
+/// The volume and channel mix applied to a sound, as set by the AVM1 `Sound.setTransform`.
+///
+/// All values are percentages. `left_to_right` is how much of the left input channel is
+/// played in the right speaker, and so on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SoundTransform {
+    pub volume: i32,
+    pub left_to_left: i32,
+    pub left_to_right: i32,
+    pub right_to_left: i32,
+    pub right_to_right: i32,
+}
+
+impl SoundTransform {
+    /// Returns the transform that results from applying `child` first, then `self`.
+    pub fn concat(self, child: SoundTransform) -> SoundTransform {
+        SoundTransform {
+            volume: self.volume * child.volume / 100,
+            left_to_left: (self.left_to_left * child.left_to_left
+                + self.right_to_left * child.left_to_right)
+                / 100,
+            left_to_right: (self.left_to_right * child.left_to_left
+                + self.right_to_right * child.left_to_right)
+                / 100,
+            right_to_left: (self.left_to_left * child.right_to_left
+                + self.right_to_left * child.right_to_right)
+                / 100,
+            right_to_right: (self.left_to_right * child.right_to_left
+                + self.right_to_right * child.right_to_right)
+                / 100,
+        }
+    }
+
+    /// The pan of this transform, from -100 (left) to 100 (right).
+    pub fn pan(&self) -> i32 {
+        self.right_to_right - self.left_to_left
+    }
+
+    /// Sets the pan of this transform, from -100 (left) to 100 (right).
+    /// Panning resets any cross-channel mixing.
+    pub fn set_pan(&mut self, pan: i32) {
+        if pan < 0 {
+            self.left_to_left = 100;
+            self.right_to_right = 100 + pan.max(-100);
+        } else {
+            self.left_to_left = 100 - pan.min(100);
+            self.right_to_right = 100;
+        }
+        self.left_to_right = 0;
+        self.right_to_left = 0;
+    }
+
+    /// Applies this transform to a stereo sample, returning the new sample.
+    pub fn apply(&self, left: f32, right: f32) -> (f32, f32) {
+        let volume = self.volume as f32 / 100.0;
+        let ll = self.left_to_left as f32 / 100.0;
+        let lr = self.left_to_right as f32 / 100.0;
+        let rl = self.right_to_left as f32 / 100.0;
+        let rr = self.right_to_right as f32 / 100.0;
+        (
+            (left * ll + right * rl) * volume,
+            (left * lr + right * rr) * volume,
+        )
+    }
+}
+
+impl Default for SoundTransform {
+    fn default() -> Self {
+        SoundTransform {
+            volume: 100,
+            left_to_left: 100,
+            left_to_right: 0,
+            right_to_left: 0,
+            right_to_right: 100,
+        }
+    }
+}
+
+/// A sound that was started through the `AudioManager`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ActiveSoundHandle {
+    Sound(SoundInstanceHandle),
+    Stream(AudioStreamHandle),
+}
+
+struct ActiveSound<'gc> {
+    handle: ActiveSoundHandle,
+
+    /// The sound definition of an event sound.
+    sound: Option<SoundHandle>,
+
+    /// The display object that started this sound, if any.
+    /// The sound is affected by the sound transforms of this object and its ancestors.
+    owner: Option<DisplayObject<'gc>>,
+
+    /// The AVM1 `Sound` object that started this sound, if any.
+    /// Receives position updates and the `onSoundComplete` event.
+    avm1_object: Option<SoundObject<'gc>>,
+
+    /// The frame of the owning clip where a stream sound started.
+    stream_start_frame: Option<u16>,
+}
+
+/// The progress of a stream sound that the timeline is synced to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StreamSync {
+    /// The playback position of the stream in milliseconds.
+    pub position: u32,
+
+    /// How many frames the timeline is behind the stream, or a negative number if it is ahead.
+    pub frames_behind: i32,
+}
+
+/// An external MP3 being loaded by `Sound.loadSound`.
+struct SoundLoad<'gc> {
+    handle: FetchHandle,
+
+    /// The `Sound` object that will receive the loaded sound.
+    object: SoundObject<'gc>,
+
+    /// Streaming sounds start playing as soon as they are loaded.
+    /// Event sounds have to be started with `Sound.start`.
+    ///
+    /// TODO: Flash Player starts streaming sounds while they are still downloading.
+    /// `NavigatorBackend` only hands over the data of a fetch once it is complete,
+    /// so streaming sounds wait for the whole file, like event sounds.
+    is_streaming: bool,
+}
+
+/// Keeps track of the playing sounds and the sound transforms applied to them.
+///
+/// Sounds started on a movie clip's timeline are owned by that clip. An AVM1 `Sound` object
+/// constructed on a clip controls the transform of every sound owned by the clip and its
+/// children, while `new Sound()` controls the global transform.
+pub struct AudioManager<'gc> {
+    sounds: Vec<ActiveSound<'gc>>,
+
+    /// The sound transforms set on display objects.
+    transforms: Vec<(DisplayObject<'gc>, SoundTransform)>,
+
+    /// The sound transform applied to all sounds.
+    global_transform: SoundTransform,
+
+    /// Whether newly started sounds are still missing the transforms of their owner's ancestors.
+    transforms_dirty: bool,
+
+    /// External sounds being loaded by `Sound.loadSound`.
+    loads: Vec<SoundLoad<'gc>>,
+}
+
+impl<'gc> AudioManager<'gc> {
+    pub fn new() -> Self {
+        Self {
+            sounds: Vec::new(),
+            transforms: Vec::new(),
+            global_transform: Default::default(),
+            transforms_dirty: false,
+            loads: Vec::new(),
+        }
+    }
+
+    /// Starts an event sound owned by the given display object.
+    /// `avm1_object` is the `Sound` object that started the sound from ActionScript, if any.
+    pub fn start_sound(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        sound: SoundHandle,
+        settings: &swf::SoundInfo,
+        owner: Option<DisplayObject<'gc>>,
+        avm1_object: Option<SoundObject<'gc>>,
+    ) -> SoundInstanceHandle {
+        let handle = audio.start_sound(sound, settings);
+        let transform = self.initial_transform(owner);
+        if transform != SoundTransform::default() {
+            audio.set_sound_transform(handle, transform);
+        }
+        self.add_sound(ActiveSound {
+            handle: ActiveSoundHandle::Sound(handle),
+            sound: Some(sound),
+            owner,
+            avm1_object,
+            stream_start_frame: None,
+        });
+        handle
+    }
+
+    /// Starts a stream sound owned by the given movie clip.
+    pub fn start_stream(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        clip_id: crate::prelude::CharacterId,
+        clip_frame: u16,
+        clip_data: crate::tag_utils::SwfSlice,
+        stream_info: &swf::SoundStreamHead,
+        owner: Option<DisplayObject<'gc>>,
+    ) -> AudioStreamHandle {
+        let handle = audio.start_stream(clip_id, clip_frame, clip_data, stream_info);
+        let transform = self.initial_transform(owner);
+        if transform != SoundTransform::default() {
+            audio.set_stream_transform(handle, transform);
+        }
+        self.add_sound(ActiveSound {
+            handle: ActiveSoundHandle::Stream(handle),
+            sound: None,
+            owner,
+            avm1_object: None,
+            stream_start_frame: Some(clip_frame),
+        });
+        handle
+    }
+
+    pub fn stop_sound(&mut self, audio: &mut dyn AudioBackend, instance: SoundInstanceHandle) {
+        audio.stop_sound(instance);
+        self.sounds
+            .retain(|sound| sound.handle != ActiveSoundHandle::Sound(instance));
+    }
+
+    pub fn stop_stream(&mut self, audio: &mut dyn AudioBackend, stream: AudioStreamHandle) {
+        audio.stop_stream(stream);
+        self.sounds
+            .retain(|sound| sound.handle != ActiveSoundHandle::Stream(stream));
+    }
+
+    pub fn stop_all_sounds(&mut self, audio: &mut dyn AudioBackend) {
+        audio.stop_all_sounds();
+        self.sounds.clear();
+    }
+
+    /// Stops all sounds owned by the given display object or any of its children.
+    /// Used by `Sound.stop` on a `Sound` object constructed on a movie clip.
+    pub fn stop_sounds_with_owner(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        owner: DisplayObject<'gc>,
+    ) {
+        self.stop_sounds_matching(audio, |sound| {
+            sound.owner.map(|o| is_ancestor(owner, o)).unwrap_or(false)
+        });
+    }
+
+    /// Stops all instances of a particular sound.
+    /// If `owner` is given, only instances owned by that display object or its children are stopped.
+    /// Used by the `StartSound` tag with `SoundEvent::Stop` and `Sound.stop(idName)`.
+    pub fn stop_sounds_with_handle(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        handle: SoundHandle,
+        owner: Option<DisplayObject<'gc>>,
+    ) {
+        self.stop_sounds_matching(audio, |sound| {
+            sound.sound == Some(handle)
+                && match (owner, sound.owner) {
+                    (Some(owner), Some(o)) => is_ancestor(owner, o),
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                }
+        });
+    }
+
+    /// Returns the playback position of a playing stream sound, and how many frames the
+    /// timeline of its clip is behind the stream.
+    /// Returns `None` if no stream is playing or the audio backend can't report its position.
+    ///
+    /// The first stream that was started is used, which is usually the stream on the root
+    /// timeline.
+    pub fn stream_sync(&self, audio: &dyn AudioBackend, frame_rate: f64) -> Option<StreamSync> {
+        self.sounds.iter().find_map(|sound| {
+            let stream = match sound.handle {
+                ActiveSoundHandle::Stream(stream) => stream,
+                ActiveSoundHandle::Sound(_) => return None,
+            };
+            let clip = sound.owner?.as_movie_clip()?;
+            let start_frame = sound.stream_start_frame?;
+            let position = audio.get_stream_position(stream)?;
+            let stream_frame =
+                f64::from(start_frame) + (f64::from(position) * frame_rate / 1000.0).floor();
+            Some(StreamSync {
+                position,
+                frames_behind: stream_frame as i32 - i32::from(clip.current_frame()),
+            })
+        })
+    }
+
+    fn stop_sounds_matching(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        f: impl Fn(&ActiveSound<'gc>) -> bool,
+    ) {
+        let (stopped, kept) = self.sounds.drain(..).partition(f);
+        self.sounds = kept;
+        for sound in stopped {
+            match sound.handle {
+                ActiveSoundHandle::Sound(handle) => audio.stop_sound(handle),
+                ActiveSoundHandle::Stream(handle) => audio.stop_stream(handle),
+            }
+        }
+    }
+
+    /// Returns the sound transform set on the given display object,
+    /// or the global transform if `owner` is `None`.
+    pub fn sound_transform(&self, owner: Option<DisplayObject<'gc>>) -> SoundTransform {
+        if let Some(owner) = owner {
+            self.local_transform(owner).unwrap_or_default()
+        } else {
+            self.global_transform
+        }
+    }
+
+    /// Sets the sound transform of the given display object,
+    /// or the global transform if `owner` is `None`, and updates all playing sounds.
+    pub fn set_sound_transform(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        owner: Option<DisplayObject<'gc>>,
+        transform: SoundTransform,
+    ) {
+        if let Some(owner) = owner {
+            self.transforms
+                .retain(|(object, _)| !DisplayObject::ptr_eq(*object, owner));
+            if transform != SoundTransform::default() {
+                self.transforms.push((owner, transform));
+            }
+        } else {
+            self.global_transform = transform;
+        }
+        self.update_transforms(audio);
+    }
+
+    /// Prunes finished sounds and applies any pending transforms.
+    ///
+    /// `Sound` objects that started a sound get their position updated, and an `onSoundComplete`
+    /// call is queued for each sound that finished playing. The sound transforms of display
+    /// objects that were removed from the display list are dropped.
+    /// Called by the player every frame, once the display list is no longer borrowed.
+    pub fn update_sounds(
+        &mut self,
+        audio: &mut dyn AudioBackend,
+        gc_context: MutationContext<'gc, '_>,
+        action_queue: &mut ActionQueue<'gc>,
+        root: DisplayObject<'gc>,
+    ) {
+        self.sounds.retain(|sound| {
+            let handle = match sound.handle {
+                ActiveSoundHandle::Sound(handle) => handle,
+                ActiveSoundHandle::Stream(stream) => return audio.is_stream_playing(stream),
+            };
+
+            if audio.is_sound_playing(handle) {
+                if let (Some(object), Some(position)) =
+                    (sound.avm1_object, audio.get_sound_position(handle))
+                {
+                    object.set_position(gc_context, position);
+                }
+                return true;
+            }
+
+            if let Some(object) = sound.avm1_object {
+                // The position stays at the end of the sound once it completes.
+                object.set_position(gc_context, object.duration());
+                if object.sound_instance() == Some(handle) {
+                    object.set_sound_instance(gc_context, None);
+                }
+                action_queue.queue_actions(
+                    root,
+                    ActionType::Callback {
+                        object: Object::from(object),
+                        name: "onSoundComplete",
+                        args: vec![],
+                    },
+                    false,
+                );
+            }
+            false
+        });
+
+        self.transforms.retain(|(object, _)| !is_removed(*object));
+
+        if self.transforms_dirty {
+            self.update_transforms(audio);
+        }
+    }
+
+    /// Begins loading an external MP3 file into the given `Sound` object.
+    /// Any previous load into the same object is cancelled.
+    pub fn load_sound(
+        &mut self,
+        navigator: &mut dyn NavigatorBackend,
+        url: String,
+        object: SoundObject<'gc>,
+        is_streaming: bool,
+    ) {
+        self.loads
+            .retain(|load| !Object::ptr_eq(load.object.into(), object.into()));
+        let handle = navigator.fetch(url);
+        self.loads.push(SoundLoad {
+            handle,
+            object,
+            is_streaming,
+        });
+    }
+
+    /// Polls the navigator for the progress of external sounds.
+    ///
+    /// Completed sounds are registered with the audio backend and attached to their `Sound`
+    /// object, queueing the `onID3` and `onLoad` events.
+    pub fn update_loads(context: &mut UpdateContext<'_, 'gc, '_>) {
+        let loads = std::mem::take(&mut context.audio_manager.loads);
+        for load in loads {
+            let object = load.object;
+            match context.navigator.poll_fetch(load.handle) {
+                FetchStatus::Loading {
+                    bytes_loaded,
+                    bytes_total,
+                } => {
+                    object.set_bytes_loaded(
+                        context.gc_context,
+                        Some(bytes_loaded as u32),
+                        bytes_total.map(|n| n as u32),
+                    );
+                    context.audio_manager.loads.push(load);
+                }
+                FetchStatus::Complete(data) => {
+                    let len = Some(data.len() as u32);
+                    object.set_bytes_loaded(context.gc_context, len, len);
+
+                    let success = match context.audio.register_mp3(id3::strip_id3(&data)) {
+                        Ok(sound) => {
+                            Self::attach_loaded_sound(context, object, sound, &data);
+                            if load.is_streaming {
+                                let instance = context.audio_manager.start_sound(
+                                    context.audio,
+                                    sound,
+                                    &swf::SoundInfo {
+                                        event: swf::SoundEvent::Start,
+                                        in_sample: None,
+                                        out_sample: None,
+                                        num_loops: 1,
+                                        envelope: None,
+                                    },
+                                    object.owner(),
+                                    Some(object),
+                                );
+                                object.set_sound_instance(context.gc_context, Some(instance));
+                            }
+                            true
+                        }
+                        Err(e) => {
+                            log::warn!("Sound.loadSound: Unable to load MP3: {}", e);
+                            false
+                        }
+                    };
+                    Self::queue_sound_event(context, object, "onLoad", vec![success.into()]);
+                }
+                FetchStatus::Failed => {
+                    Self::queue_sound_event(context, object, "onLoad", vec![false.into()]);
+                }
+            }
+        }
+    }
+
+    /// Attaches a sound loaded by `Sound.loadSound` to its `Sound` object,
+    /// filling in the `id3` property.
+    fn attach_loaded_sound(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        object: SoundObject<'gc>,
+        sound: SoundHandle,
+        data: &[u8],
+    ) {
+        object.set_sound(context.gc_context, Some(sound));
+        object.set_duration(
+            context.gc_context,
+            context.audio.get_sound_duration(sound).unwrap_or(0),
+        );
+        object.set_position(context.gc_context, 0);
+
+        let codepage = if *context.use_codepage {
+            Some(context.system_codepage)
+        } else {
+            None
+        };
+        if let Some(tag) = id3::read_id3(data, codepage) {
+            let id3_object =
+                ScriptObject::object(context.gc_context, Some(context.system_prototypes.object));
+            for (name, value) in tag.properties {
+                id3_object.define_value(
+                    context.gc_context,
+                    &name,
+                    Value::String(value),
+                    Default::default(),
+                );
+            }
+            object.set_id3(context.gc_context, Some(id3_object.into()));
+            Self::queue_sound_event(context, object, "onID3", vec![]);
+        }
+    }
+
+    fn queue_sound_event(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        object: SoundObject<'gc>,
+        name: &'static str,
+        args: Vec<Value<'gc>>,
+    ) {
+        context.action_queue.queue_actions(
+            context.root,
+            ActionType::Callback {
+                object: object.into(),
+                name,
+                args,
+            },
+            false,
+        );
+    }
+
+    /// Recalculates and applies the transform of every playing sound.
+    pub fn update_transforms(&mut self, audio: &mut dyn AudioBackend) {
+        for sound in &self.sounds {
+            let transform = self.transform_for_owner(sound.owner);
+            match sound.handle {
+                ActiveSoundHandle::Sound(handle) => audio.set_sound_transform(handle, transform),
+                ActiveSoundHandle::Stream(handle) => audio.set_stream_transform(handle, transform),
+            }
+        }
+        self.transforms_dirty = false;
+    }
+
+    fn add_sound(&mut self, sound: ActiveSound<'gc>) {
+        if sound.owner.is_some() && !self.transforms.is_empty() {
+            self.transforms_dirty = true;
+        }
+        self.sounds.push(sound);
+    }
+
+    fn local_transform(&self, object: DisplayObject<'gc>) -> Option<SoundTransform> {
+        self.transforms
+            .iter()
+            .find(|(o, _)| DisplayObject::ptr_eq(*o, object))
+            .map(|(_, transform)| *transform)
+    }
+
+    /// The transform of a sound that is just starting.
+    ///
+    /// Sounds are often started while the owner's timeline is executing, when the display list
+    /// can't be walked. Only the owner's own transform is applied here; the transforms of its
+    /// ancestors are applied by the next `update_sounds`.
+    fn initial_transform(&self, owner: Option<DisplayObject<'gc>>) -> SoundTransform {
+        match owner.and_then(|owner| self.local_transform(owner)) {
+            Some(transform) => self.global_transform.concat(transform),
+            None => self.global_transform,
+        }
+    }
+
+    /// The combined transform of the global transform,
+    /// the owner's transform and the transforms of all its ancestors.
+    fn transform_for_owner(&self, owner: Option<DisplayObject<'gc>>) -> SoundTransform {
+        let mut transform = SoundTransform::default();
+        let mut object = owner;
+        while let Some(display_object) = object {
+            if let Some(local) = self.local_transform(display_object) {
+                transform = local.concat(transform);
+            }
+            object = display_object.parent();
+        }
+        self.global_transform.concat(transform)
+    }
+}
+
+impl<'gc> Default for AudioManager<'gc> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<'gc> Collect for AudioManager<'gc> {
+    fn trace(&self, cc: CollectionContext) {
+        for sound in &self.sounds {
+            sound.owner.trace(cc);
+            sound.avm1_object.trace(cc);
+        }
+        for (object, _) in &self.transforms {
+            object.trace(cc);
+        }
+        for load in &self.loads {
+            load.object.trace(cc);
+        }
+    }
+}
+
+/// Returns whether `ancestor` is `object` or one of its ancestors.
+fn is_ancestor<'gc>(ancestor: DisplayObject<'gc>, object: DisplayObject<'gc>) -> bool {
+    let mut current = Some(object);
+    while let Some(display_object) = current {
+        if DisplayObject::ptr_eq(display_object, ancestor) {
+            return true;
+        }
+        current = display_object.parent();
+    }
+    false
+}
+
+/// Returns whether a display object or any of its ancestors was removed from the display list.
+fn is_removed(object: DisplayObject<'_>) -> bool {
+    let mut current = Some(object);
+    while let Some(display_object) = current {
+        if display_object.removed() {
+            return true;
+        }
+        current = display_object.parent();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display_object::MovieClip;
+    use crate::tag_utils::SwfSlice;
+    use gc_arena::rootless_arena;
+    use std::sync::Arc;
+
+    /// An audio backend where the test decides when sounds finish.
+    struct TestAudioBackend {
+        sounds: Arena<()>,
+
+        /// The playing sound instances and streams, with their positions in milliseconds.
+        playing: Arena<u32>,
+    }
+
+    impl TestAudioBackend {
+        fn new() -> Self {
+            Self {
+                sounds: Arena::new(),
+                playing: Arena::new(),
+            }
+        }
+    }
+
+    impl AudioBackend for TestAudioBackend {
+        fn register_sound(&mut self, _sound: &swf::Sound) -> Result<SoundHandle, Error> {
+            Ok(self.sounds.insert(()))
+        }
+
+        fn start_sound(
+            &mut self,
+            _sound: SoundHandle,
+            _settings: &swf::SoundInfo,
+        ) -> SoundInstanceHandle {
+            self.playing.insert(0)
+        }
+
+        fn start_stream(
+            &mut self,
+            _clip_id: swf::CharacterId,
+            _clip_frame: u16,
+            _clip_data: SwfSlice,
+            _stream_info: &swf::SoundStreamHead,
+        ) -> AudioStreamHandle {
+            self.playing.insert(0)
+        }
+
+        fn stop_sound(&mut self, sound: SoundInstanceHandle) {
+            self.playing.remove(sound);
+        }
+
+        fn stop_stream(&mut self, stream: AudioStreamHandle) {
+            self.playing.remove(stream);
+        }
+
+        fn stop_all_sounds(&mut self) {
+            self.playing.clear();
+        }
+
+        fn stop_sounds_with_handle(&mut self, _handle: SoundHandle) {}
+
+        fn is_sound_playing_with_handle(&mut self, _handle: SoundHandle) -> bool {
+            false
+        }
+
+        fn is_sound_playing(&mut self, instance: SoundInstanceHandle) -> bool {
+            self.playing.contains(instance)
+        }
+
+        fn is_stream_playing(&mut self, stream: AudioStreamHandle) -> bool {
+            self.playing.contains(stream)
+        }
+
+        fn get_sound_position(&self, instance: SoundInstanceHandle) -> Option<u32> {
+            self.playing.get(instance).copied()
+        }
+
+        fn get_sound_duration(&self, _sound: SoundHandle) -> Option<u32> {
+            None
+        }
+    }
+
+    fn sound_info() -> swf::SoundInfo {
+        swf::SoundInfo {
+            event: swf::SoundEvent::Event,
+            in_sample: None,
+            out_sample: None,
+            num_loops: 1,
+            envelope: None,
+        }
+    }
+
+    fn stream_head() -> swf::SoundStreamHead {
+        let format = swf::SoundFormat {
+            compression: swf::AudioCompression::Uncompressed,
+            sample_rate: 44100,
+            is_stereo: true,
+            is_16_bit: true,
+        };
+        swf::SoundStreamHead {
+            stream_format: format.clone(),
+            playback_format: format,
+            num_samples_per_block: 0,
+            latency_seek: 0,
+        }
+    }
+
+    #[test]
+    fn sound_position_and_completion() {
+        rootless_arena(|gc_context| {
+            let root: DisplayObject<'_> = MovieClip::new(8, gc_context).into();
+            let mut audio = TestAudioBackend::new();
+            let mut manager = AudioManager::new();
+            let mut action_queue = ActionQueue::new();
+
+            let sound = audio.sounds.insert(());
+            let object = SoundObject::empty_sound(gc_context, None);
+            object.set_duration(gc_context, 1000);
+            let instance =
+                manager.start_sound(&mut audio, sound, &sound_info(), Some(root), Some(object));
+            object.set_sound_instance(gc_context, Some(instance));
+
+            // The position of a playing sound is tracked.
+            audio.playing[instance] = 250;
+            manager.update_sounds(&mut audio, gc_context, &mut action_queue, root);
+            assert_eq!(object.position(), 250);
+            assert!(action_queue.pop().is_none());
+
+            // A finished sound is pruned, and its `Sound` object is told once.
+            audio.playing.remove(instance);
+            manager.update_sounds(&mut audio, gc_context, &mut action_queue, root);
+            assert_eq!(object.position(), 1000);
+            assert_eq!(object.sound_instance(), None);
+            match action_queue.pop().map(|actions| actions.action_type) {
+                Some(ActionType::Callback {
+                    object: o, name, ..
+                }) => {
+                    assert!(Object::ptr_eq(o, object.into()));
+                    assert_eq!(name, "onSoundComplete");
+                }
+                action => panic!("Expected onSoundComplete, got {:?}", action),
+            }
+            assert!(manager.sounds.is_empty());
+
+            manager.update_sounds(&mut audio, gc_context, &mut action_queue, root);
+            assert!(action_queue.pop().is_none());
+        });
+    }
+
+    #[test]
+    fn prune_streams_and_removed_transforms() {
+        rootless_arena(|gc_context| {
+            let root: DisplayObject<'_> = MovieClip::new(8, gc_context).into();
+            let mut clip: DisplayObject<'_> = MovieClip::new(8, gc_context).into();
+            let mut child: DisplayObject<'_> = MovieClip::new(8, gc_context).into();
+            clip.set_parent(gc_context, Some(root));
+            child.set_parent(gc_context, Some(clip));
+            let mut audio = TestAudioBackend::new();
+            let mut manager = AudioManager::new();
+            let mut action_queue = ActionQueue::new();
+
+            let clip_data = SwfSlice {
+                data: Arc::new(Vec::new().into()),
+                start: 0,
+                end: 0,
+            };
+            let stream =
+                manager.start_stream(&mut audio, 1, 1, clip_data, &stream_head(), Some(child));
+            let transform = SoundTransform {
+                volume: 50,
+                ..Default::default()
+            };
+            manager.set_sound_transform(&mut audio, Some(child), transform);
+            manager.update_sounds(&mut audio, gc_context, &mut action_queue, root);
+            assert_eq!(manager.sounds.len(), 1);
+            assert_eq!(manager.sound_transform(Some(child)), transform);
+
+            audio.playing.remove(stream);
+            clip.set_removed(gc_context, true);
+            manager.update_sounds(&mut audio, gc_context, &mut action_queue, root);
+            assert!(manager.sounds.is_empty());
+            assert!(manager.transforms.is_empty());
+            assert!(action_queue.pop().is_none());
+        });
+    }
+
+    #[test]
+    fn sound_transform_pan() {
+        let mut transform = SoundTransform::default();
+        transform.set_pan(-40);
+        assert_eq!(transform.left_to_left, 100);
+        assert_eq!(transform.right_to_right, 60);
+        assert_eq!(transform.pan(), -40);
+        transform.set_pan(25);
+        assert_eq!(transform.left_to_left, 75);
+        assert_eq!(transform.right_to_right, 100);
+        assert_eq!(transform.pan(), 25);
+    }
+
+    #[test]
+    fn sound_transform_concat() {
+        let identity = SoundTransform::default();
+        let swap = SoundTransform {
+            volume: 50,
+            left_to_left: 0,
+            left_to_right: 100,
+            right_to_left: 100,
+            right_to_right: 0,
+        };
+        assert_eq!(identity.concat(swap), swap);
+        assert_eq!(swap.concat(identity), swap);
+
+        // Swapping channels twice is the identity, at a quarter of the volume.
+        let twice = swap.concat(swap);
+        assert_eq!(
+            twice,
+            SoundTransform {
+                volume: 25,
+                ..Default::default()
+            }
+        );
+
+        let mut left = SoundTransform::default();
+        left.set_pan(-100);
+        // Swap, then silence the right channel: only the original left input is heard, on the left.
+        let combined = left.concat(swap);
+        assert_eq!(combined.apply(1.0, 0.0), (0.0, 0.0));
+        assert_eq!(combined.apply(0.0, 1.0), (0.5, 0.0));
+    }
+}
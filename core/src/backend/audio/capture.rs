@@ -0,0 +1,370 @@
+//! A headless audio backend that captures the mixed audio into memory.
+//!
+//! Used by the regression tests to verify sound output, and to export the audio of a movie
+//! as a WAV file.
+
+use super::mixer::AudioMixer;
+use super::AudioBackend;
+use crate::impl_audio_mixer_backend;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+/// The default sample rate of captured audio.
+pub const CAPTURE_SAMPLE_RATE: u32 = 44100;
+
+/// Audio backend that mixes into an in-memory buffer of stereo samples.
+///
+/// Audio does not play in real time. Instead, `advance` mixes the given amount of time,
+/// so the captured audio only depends on the player time. This makes it deterministic.
+pub struct CaptureAudioBackend {
+    mixer: AudioMixer,
+
+    /// The captured interleaved stereo samples, from -1.0 to 1.0.
+    samples: Vec<f32>,
+
+    /// The total time that has been captured, in milliseconds.
+    time: f64,
+}
+
+impl CaptureAudioBackend {
+    pub fn new() -> Self {
+        Self::with_sample_rate(CAPTURE_SAMPLE_RATE)
+    }
+
+    pub fn with_sample_rate(sample_rate: u32) -> Self {
+        Self {
+            mixer: AudioMixer::new(2, sample_rate),
+            samples: vec![],
+            time: 0.0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.mixer.output_sample_rate()
+    }
+
+    /// Mixes the playing sounds for `dt` milliseconds and appends the audio to the buffer.
+    pub fn advance(&mut self, dt: f64) {
+        self.time += dt;
+        // Round the total number of frames, so that rounding errors don't accumulate.
+        let total_frames = (self.time * f64::from(self.sample_rate()) / 1000.0).round() as usize;
+        let start = self.samples.len();
+        if total_frames * 2 > start {
+            self.samples.resize(total_frames * 2, 0.0);
+            self.mixer.mix(&mut self.samples[start..]);
+        }
+    }
+
+    /// The captured interleaved stereo samples.
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// The number of captured sample frames.
+    pub fn num_frames(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    /// Writes the captured audio as a 16-bit stereo WAV file.
+    pub fn write_wav<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_wav(writer, &self.samples, 2, self.sample_rate())
+    }
+
+    /// Calculates a fingerprint of the captured audio.
+    pub fn fingerprint(&self, window_ms: u32) -> AudioFingerprint {
+        AudioFingerprint::new(&self.samples, self.sample_rate(), window_ms)
+    }
+}
+
+impl AudioBackend for CaptureAudioBackend {
+    impl_audio_mixer_backend!(mixer);
+}
+
+impl Default for CaptureAudioBackend {
+    fn default() -> Self {
+        CaptureAudioBackend::new()
+    }
+}
+
+/// A compact summary of a stereo waveform: the RMS level of each channel
+/// over consecutive windows of time.
+///
+/// The text form has a header line with the window length, followed by
+/// one line per window with the left and right levels:
+/// ```text
+/// window_ms 50
+/// 0.0000 0.0000
+/// 0.3536 0.3536
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioFingerprint {
+    pub window_ms: u32,
+    pub levels: Vec<[f32; 2]>,
+}
+
+impl AudioFingerprint {
+    /// Calculates the fingerprint of interleaved stereo `samples`.
+    /// A partial window at the end is included.
+    pub fn new(samples: &[f32], sample_rate: u32, window_ms: u32) -> Self {
+        let window_frames = (sample_rate as usize * window_ms as usize / 1000).max(1);
+        let levels = samples
+            .chunks(window_frames * 2)
+            .map(|window| {
+                let mut sums = [0.0f64; 2];
+                for frame in window.chunks_exact(2) {
+                    sums[0] += f64::from(frame[0]) * f64::from(frame[0]);
+                    sums[1] += f64::from(frame[1]) * f64::from(frame[1]);
+                }
+                let num_frames = (window.len() / 2).max(1) as f64;
+                [
+                    (sums[0] / num_frames).sqrt() as f32,
+                    (sums[1] / num_frames).sqrt() as f32,
+                ]
+            })
+            .collect();
+        Self { window_ms, levels }
+    }
+
+    /// Compares this fingerprint with an expected fingerprint.
+    /// Each level may differ by at most `tolerance`.
+    /// Returns a description of the first mismatch.
+    pub fn compare(&self, expected: &AudioFingerprint, tolerance: f32) -> Result<(), String> {
+        if self.window_ms != expected.window_ms {
+            return Err(format!(
+                "Window length {}ms does not match expected {}ms",
+                self.window_ms, expected.window_ms
+            ));
+        }
+        if self.levels.len() != expected.levels.len() {
+            return Err(format!(
+                "Length of {} windows does not match expected {} windows",
+                self.levels.len(),
+                expected.levels.len()
+            ));
+        }
+        for (i, (actual, expected)) in self.levels.iter().zip(&expected.levels).enumerate() {
+            for channel in 0..2 {
+                if (actual[channel] - expected[channel]).abs() > tolerance {
+                    return Err(format!(
+                        "Window {} (at {}ms), channel {}: level {:.4} does not match expected {:.4}",
+                        i,
+                        i as u32 * self.window_ms,
+                        channel,
+                        actual[channel],
+                        expected[channel]
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AudioFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "window_ms {}", self.window_ms)?;
+        for [left, right] in &self.levels {
+            writeln!(f, "{:.4} {:.4}", left, right)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AudioFingerprint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        let window_ms = lines
+            .next()
+            .filter(|line| line.starts_with("window_ms "))
+            .and_then(|line| line["window_ms ".len()..].parse().ok())
+            .ok_or("Missing window_ms header")?;
+        let levels = lines
+            .map(|line| {
+                let mut values = line.split_whitespace().map(f32::from_str);
+                match (values.next(), values.next(), values.next()) {
+                    (Some(Ok(left)), Some(Ok(right)), None) => Ok([left, right]),
+                    _ => Err(format!("Invalid fingerprint line: {}", line)),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { window_ms, levels })
+    }
+}
+
+/// Writes interleaved samples as a 16-bit PCM WAV file.
+pub fn write_wav<W: Write>(
+    mut writer: W,
+    samples: &[f32],
+    num_channels: u16,
+    sample_rate: u32,
+) -> io::Result<()> {
+    let data_len = samples.len() as u32 * 2;
+    let block_align = num_channels * 2;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&num_channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * u32::from(block_align)).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    for &sample in samples {
+        let sample = if sample.abs() > 1.0 {
+            sample.signum()
+        } else {
+            sample
+        };
+        let sample = (sample * 32767.0).round() as i16;
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// A waveform read from a WAV file.
+#[derive(Debug, Clone)]
+pub struct Wav {
+    pub num_channels: u16,
+    pub sample_rate: u32,
+
+    /// The interleaved samples, from -1.0 to 1.0.
+    pub samples: Vec<f32>,
+}
+
+/// Reads a 16-bit PCM WAV file, such as one written by `write_wav`.
+pub fn read_wav<R: Read>(mut reader: R) -> io::Result<Wav> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+        return Err(invalid("Not a WAV file"));
+    }
+
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let id = &data[pos..pos + 4];
+        let len = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]])
+            as usize;
+        let chunk = data
+            .get(pos + 8..pos + 8 + len)
+            .ok_or_else(|| invalid("Truncated WAV chunk"))?;
+        match id {
+            b"fmt " if len >= 16 => {
+                let read_u16 = |i: usize| u16::from_le_bytes([chunk[i], chunk[i + 1]]);
+                if read_u16(0) != 1 || read_u16(14) != 16 {
+                    return Err(invalid("Only 16-bit PCM WAV files are supported"));
+                }
+                let sample_rate = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+                format = Some((read_u16(2), sample_rate));
+            }
+            b"data" => {
+                let (num_channels, sample_rate) =
+                    format.ok_or_else(|| invalid("Missing WAV format chunk"))?;
+                let samples = chunk
+                    .chunks_exact(2)
+                    .map(|s| f32::from(i16::from_le_bytes([s[0], s[1]])) / 32767.0)
+                    .collect();
+                return Ok(Wav {
+                    num_channels,
+                    sample_rate,
+                    samples,
+                });
+            }
+            _ => (),
+        }
+        // Chunks are padded to an even length.
+        pos += 8 + len + (len & 1);
+    }
+    Err(invalid("Missing WAV data chunk"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::audio::swf;
+
+    #[test]
+    fn capture_is_driven_by_time() {
+        let mut audio = CaptureAudioBackend::with_sample_rate(1000);
+        let sound = audio
+            .register_sound(&swf::Sound {
+                id: 1,
+                format: swf::SoundFormat {
+                    compression: swf::AudioCompression::Uncompressed,
+                    sample_rate: 5512,
+                    is_16_bit: false,
+                    is_stereo: false,
+                },
+                num_samples: 0,
+                data: vec![],
+            })
+            .unwrap();
+        audio.start_sound(
+            sound,
+            &swf::SoundInfo {
+                event: swf::SoundEvent::Event,
+                in_sample: None,
+                out_sample: None,
+                num_loops: 1,
+                envelope: None,
+            },
+        );
+        // 3 frames at 1/3 of a millisecond each should not drift.
+        for _ in 0..3000 {
+            audio.advance(1.0 / 3.0);
+        }
+        assert_eq!(audio.num_frames(), 1000);
+        assert!(audio.samples().iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn wav_round_trip() {
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0, 0.25];
+        let mut wav = vec![];
+        write_wav(&mut wav, &samples, 2, 22050).unwrap();
+        assert_eq!(wav.len(), 44 + samples.len() * 2);
+
+        let wav = read_wav(&wav[..]).unwrap();
+        assert_eq!(wav.num_channels, 2);
+        assert_eq!(wav.sample_rate, 22050);
+        for (actual, expected) in wav.samples.iter().zip(&samples) {
+            assert!((actual - expected).abs() < 1.0 / 32767.0);
+        }
+    }
+
+    #[test]
+    fn fingerprint_round_trip() {
+        let samples: Vec<f32> = (0..300)
+            .flat_map(|i| {
+                if i < 100 {
+                    vec![0.0, 0.0]
+                } else {
+                    vec![0.5, -0.25]
+                }
+            })
+            .collect();
+        let fingerprint = AudioFingerprint::new(&samples, 1000, 100);
+        assert_eq!(
+            fingerprint.levels,
+            vec![[0.0, 0.0], [0.5, 0.25], [0.5, 0.25]]
+        );
+
+        let parsed: AudioFingerprint = fingerprint.to_string().parse().unwrap();
+        assert_eq!(parsed, fingerprint);
+
+        let mut louder = fingerprint.clone();
+        louder.levels[2][1] = 0.3;
+        assert!(louder.compare(&fingerprint, 0.1).is_ok());
+        assert!(louder.compare(&fingerprint, 0.01).is_err());
+    }
+}
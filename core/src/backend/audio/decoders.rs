@@ -2,10 +2,12 @@
 
 mod adpcm;
 mod mp3;
+mod nellymoser;
 mod pcm;
 
 pub use adpcm::AdpcmDecoder;
-pub use mp3::Mp3Decoder;
+pub use mp3::{mp3_metadata, Mp3Decoder, Mp3Metadata};
+pub use nellymoser::NellymoserDecoder;
 pub use pcm::PcmDecoder;
 
 use crate::tag_utils::SwfSlice;
@@ -22,6 +24,48 @@ pub trait Decoder: Iterator<Item = [i16; 2]> {
     fn sample_rate(&self) -> u16;
 }
 
+/// Returns the sample rate of the decoded audio.
+/// The 8kHz and 16kHz variants of Nellymoser ignore the sample rate in the sound format.
+pub fn decoded_sample_rate(format: &SoundFormat) -> u16 {
+    match format.compression {
+        AudioCompression::Nellymoser8Khz => 8000,
+        AudioCompression::Nellymoser16Khz => 16000,
+        _ => format.sample_rate,
+    }
+}
+
+/// Returns the number of sample frames in a packet of audio, without decoding it.
+/// Returns `None` for Speex, which isn't supported.
+pub fn num_sample_frames(format: &SoundFormat, data: &[u8]) -> Option<u32> {
+    let num_channels = if format.is_stereo { 2 } else { 1 };
+    let num_sample_frames = match format.compression {
+        AudioCompression::Uncompressed | AudioCompression::UncompressedUnknownEndian => {
+            let bytes_per_sample = if format.is_16_bit { 2 } else { 1 };
+            data.len() / (bytes_per_sample * num_channels)
+        }
+        AudioCompression::Adpcm => {
+            // After the 2 bit sample size, each block has a header with the initial sample
+            // of each channel, followed by up to 4095 sample frames.
+            let bits_per_sample = usize::from(*data.first()? >> 6) + 2;
+            let header_bits = 22 * num_channels;
+            let frame_bits = bits_per_sample * num_channels;
+            let block_bits = header_bits + 4095 * frame_bits;
+            let data_bits = data.len() * 8 - 2;
+            let last_block_bits = data_bits % block_bits;
+            data_bits / block_bits * 4095 + last_block_bits.saturating_sub(header_bits) / frame_bits
+        }
+        AudioCompression::Mp3 => {
+            mp3_metadata(data).map_or(0, |metadata| metadata.num_sample_frames as usize)
+        }
+        // Each 64 byte block of Nellymoser holds 256 samples.
+        AudioCompression::Nellymoser
+        | AudioCompression::Nellymoser8Khz
+        | AudioCompression::Nellymoser16Khz => data.len() / 64 * 256,
+        AudioCompression::Speex => return None,
+    };
+    Some(num_sample_frames as u32)
+}
+
 /// Instantiate a decoder for the compression that the sound data uses.
 pub fn make_decoder<'a, R: 'a + Send + Read>(
     format: &SoundFormat,
@@ -54,6 +98,11 @@ pub fn make_decoder<'a, R: 'a + Send + Read>(
             format.sample_rate.into(),
             data,
         )),
+        AudioCompression::Nellymoser
+        | AudioCompression::Nellymoser8Khz
+        | AudioCompression::Nellymoser16Khz => {
+            Box::new(NellymoserDecoder::new(data, decoded_sample_rate(format)))
+        }
         _ => {
             log::error!(
                 "make_decoder: Unhandled audio compression {:?}",
@@ -71,7 +120,8 @@ pub fn make_decoder<'a, R: 'a + Send + Read>(
 /// In the Flash IDE, the is created by changing the "Sync" setting of the sound
 /// to "Stream."
 ///
-/// TODO: Add `current_frame`.
+/// A stream can start decoding at any frame, such as after the MovieClip seeks with a goto.
+/// The stream is restarted from the `SoundStreamBlock` of the new frame.
 pub trait StreamDecoder: Decoder {}
 
 /// The `StandardStreamDecoder` takes care of reading the audio data from `SoundStreamBlock` tags
@@ -79,17 +129,36 @@ pub trait StreamDecoder: Decoder {}
 struct StandardStreamDecoder {
     /// The underlying decoder. The decoder will get its data from a `StreamTagReader`.
     decoder: Box<dyn Decoder + Send>,
+
+    /// The number of decoded sample frames to skip before the audio of the starting frame.
+    skip_sample_frames: u16,
 }
 
 impl StandardStreamDecoder {
     /// Constructs a new `StandardStreamDecoder.
-    /// `swf_data` should be the tag data of the MovieClip that contains the stream.
+    /// `swf_data` should be the tag data of the MovieClip that contains the stream,
+    /// starting at the frame where the stream starts.
     fn new(format: &SoundFormat, swf_data: SwfSlice, swf_version: u8) -> Self {
         // Create a tag reader to get the audio data from SoundStreamBlock tags.
-        let tag_reader = StreamTagReader::new(format.compression, swf_data, swf_version);
+        let mut tag_reader = StreamTagReader::new(format.compression, swf_data, swf_version);
+
+        // MP3 frames don't line up with the timeline frames, so each MP3 stream block stores
+        // the number of samples to skip to reach the audio of its frame.
+        // Only the seek of the first block matters, because the rest of the MP3 data is
+        // decoded continuously.
+        let skip_sample_frames = if format.compression == AudioCompression::Mp3 {
+            tag_reader.next();
+            tag_reader.seek_samples.max(0) as u16
+        } else {
+            0
+        };
+
         // Wrap the tag reader in the decoder.
         let decoder = make_decoder(format, tag_reader);
-        Self { decoder }
+        Self {
+            decoder,
+            skip_sample_frames,
+        }
     }
 }
 
@@ -107,6 +176,10 @@ impl Iterator for StandardStreamDecoder {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        while self.skip_sample_frames > 0 {
+            self.skip_sample_frames -= 1;
+            self.decoder.next()?;
+        }
         self.decoder.next()
     }
 }
@@ -173,7 +246,8 @@ impl Iterator for AdpcmStreamDecoder {
     }
 }
 
-/// Makes a `StreamDecoder` for the given stream. `swf_data` should be the MovieClip's tag data.
+/// Makes a `StreamDecoder` for the given stream. `swf_data` should be the MovieClip's tag data,
+/// starting at the frame where the stream starts.
 /// Generally this will return a `StandardStreamDecoder`, except for ADPCM streams.
 pub fn make_stream_decoder(
     format: &swf::SoundFormat,
@@ -209,9 +283,11 @@ pub trait SeekableDecoder: Decoder {
 /// will return consecutive slices of the underlying audio data.
 struct StreamTagReader {
     reader: swf::read::Reader<Cursor<SwfSlice>>,
-    current_frame: u16,
     current_audio_data: SwfSlice,
     compression: AudioCompression,
+
+    /// The number of samples to skip at the start of the most recent MP3 stream block.
+    seek_samples: i16,
 }
 
 impl StreamTagReader {
@@ -221,8 +297,8 @@ impl StreamTagReader {
         Self {
             compression,
             reader: swf::read::Reader::new(Cursor::new(swf_data), swf_version),
-            current_frame: 1,
             current_audio_data: SwfSlice::empty(),
+            seek_samples: 0,
         }
     }
 }
@@ -231,8 +307,8 @@ impl Iterator for StreamTagReader {
     type Item = SwfSlice;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let current_frame = &mut self.current_frame;
         let audio_data = &mut self.current_audio_data;
+        let seek_samples = &mut self.seek_samples;
         let compression = self.compression;
         let mut found = false;
         // MP3 stream blocks store seek samples and sample count in the first 4 bytes.
@@ -245,15 +321,15 @@ impl Iterator for StreamTagReader {
 
         let tag_callback =
             |reader: &mut swf::read::Reader<Cursor<SwfSlice>>, tag_code, tag_len| match tag_code {
-                TagCode::ShowFrame => {
-                    *current_frame += 1;
-                    Ok(())
-                }
                 TagCode::SoundStreamBlock => {
                     // TODO: Implement index ops on `SwfSlice`.
                     let pos = reader.get_ref().position() as usize;
                     let pos = reader.get_ref().get_ref().start + pos;
                     found = true;
+                    if skip_len > 0 && tag_len >= skip_len {
+                        let data = &reader.get_ref().get_ref().data;
+                        *seek_samples = i16::from_le_bytes([data[pos + 2], data[pos + 3]]);
+                    }
                     if tag_len >= skip_len {
                         *audio_data = SwfSlice {
                             data: std::sync::Arc::clone(&reader.get_ref().get_ref().data),
@@ -304,3 +380,115 @@ impl Read for StreamTagReader {
         Ok(len)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Encodes a tag with a short header.
+    fn tag(code: TagCode, data: &[u8]) -> Vec<u8> {
+        let header = ((code as u16) << 6) | data.len() as u16;
+        let mut tag = header.to_le_bytes().to_vec();
+        tag.extend_from_slice(data);
+        tag
+    }
+
+    /// A clip with a `SoundStreamBlock` on each frame.
+    /// Returns the tag data and the start position of each frame.
+    fn stream_clip(blocks: &[Vec<u8>]) -> (Arc<Vec<u8>>, Vec<usize>) {
+        let mut data = vec![];
+        let mut frame_starts = vec![];
+        for block in blocks {
+            frame_starts.push(data.len());
+            data.extend(tag(TagCode::SoundStreamBlock, block));
+            data.extend(tag(TagCode::ShowFrame, &[]));
+        }
+        (Arc::new(data), frame_starts)
+    }
+
+    #[test]
+    fn stream_starts_at_frame() {
+        let format = SoundFormat {
+            compression: AudioCompression::Uncompressed,
+            sample_rate: 5512,
+            is_16_bit: true,
+            is_stereo: false,
+        };
+        let blocks: Vec<Vec<u8>> = (1..=3i16)
+            .map(|i| [(i * 100).to_le_bytes(), (i * 100).to_le_bytes()].concat())
+            .collect();
+        let (data, frame_starts) = stream_clip(&blocks);
+        let len = data.len();
+
+        let swf_data = SwfSlice {
+            data,
+            start: frame_starts[1],
+            end: len,
+        };
+        let decoder = make_stream_decoder(&format, swf_data, 8);
+        let samples: Vec<i16> = decoder.map(|frame| frame[0]).collect();
+        assert_eq!(samples, [200, 200, 300, 300]);
+    }
+
+    #[test]
+    fn mp3_stream_block_seek_samples() {
+        // MP3 stream blocks start with the sample count and seek samples.
+        let blocks = vec![
+            [
+                &1152u16.to_le_bytes()[..],
+                &576i16.to_le_bytes()[..],
+                &[1, 2, 3],
+            ]
+            .concat(),
+            [
+                &1152u16.to_le_bytes()[..],
+                &100i16.to_le_bytes()[..],
+                &[4, 5],
+            ]
+            .concat(),
+        ];
+        let (data, _) = stream_clip(&blocks);
+        let len = data.len();
+        let swf_data = SwfSlice {
+            data,
+            start: 0,
+            end: len,
+        };
+
+        let mut tag_reader = StreamTagReader::new(AudioCompression::Mp3, swf_data, 8);
+        assert_eq!(tag_reader.next().unwrap().as_ref(), [1, 2, 3]);
+        assert_eq!(tag_reader.seek_samples, 576);
+        assert_eq!(tag_reader.next().unwrap().as_ref(), [4, 5]);
+        assert_eq!(tag_reader.seek_samples, 100);
+        assert!(tag_reader.next().is_none());
+    }
+
+    #[test]
+    fn adpcm_num_sample_frames() {
+        // The count must match the decoder, including partial blocks.
+        for &is_stereo in &[false, true] {
+            for bits_per_sample in 2..=5u8 {
+                for &len in &[1, 3, 6, 100, 2100, 4100, 9000] {
+                    let mut data: Vec<u8> = (0..len).map(|i| (i * 37 % 251) as u8).collect();
+                    data[0] = (bits_per_sample - 2) << 6 | (data[0] & 0x3f);
+                    let format = SoundFormat {
+                        compression: AudioCompression::Adpcm,
+                        sample_rate: 22050,
+                        is_16_bit: true,
+                        is_stereo,
+                    };
+                    let decoder = AdpcmDecoder::new(Cursor::new(&data[..]), is_stereo, 22050);
+                    assert_eq!(
+                        num_sample_frames(&format, &data),
+                        Some(decoder.count() as u32),
+                        "{} bits, {} bytes, stereo: {}",
+                        bits_per_sample,
+                        len,
+                        is_stereo
+                    );
+                }
+            }
+        }
+    }
+}
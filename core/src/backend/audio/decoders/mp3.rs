@@ -170,3 +170,126 @@ impl<R: AsRef<[u8]> + Default> SeekableDecoder for Mp3Decoder<Cursor<R>> {
         *self = Mp3Decoder::new(self.num_channels, self.sample_rate, cursor);
     }
 }
+
+/// The format and length of an MP3 file, read from its frame headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mp3Metadata {
+    pub sample_rate: u16,
+    pub is_stereo: bool,
+    pub num_sample_frames: u32,
+}
+
+/// Scans the frame headers of an MP3 file to determine its format and length,
+/// without decoding the audio.
+/// `data` should not contain ID3 tags. Returns `None` if no MP3 frames are found.
+pub fn mp3_metadata(data: &[u8]) -> Option<Mp3Metadata> {
+    let mut metadata: Option<Mp3Metadata> = None;
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        if let Some(header) = Mp3FrameHeader::parse(&data[pos..pos + 4]) {
+            let metadata = metadata.get_or_insert(Mp3Metadata {
+                sample_rate: header.sample_rate,
+                is_stereo: header.is_stereo,
+                num_sample_frames: 0,
+            });
+            metadata.num_sample_frames += header.num_sample_frames;
+            pos += header.frame_len;
+        } else {
+            // Not a frame header; resync.
+            pos += 1;
+        }
+    }
+    metadata
+}
+
+/// The header of an MPEG-1/2/2.5 Layer III frame.
+struct Mp3FrameHeader {
+    sample_rate: u16,
+    is_stereo: bool,
+    num_sample_frames: u32,
+    frame_len: usize,
+}
+
+impl Mp3FrameHeader {
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        const MPEG1_BITRATES: [u32; 15] = [
+            0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+        ];
+        const MPEG2_BITRATES: [u32; 15] =
+            [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+        // Frame sync and Layer III.
+        if bytes[0] != 0xff || bytes[1] & 0xe0 != 0xe0 || (bytes[1] >> 1) & 0b11 != 0b01 {
+            return None;
+        }
+        let version = (bytes[1] >> 3) & 0b11;
+        let bitrate_index = usize::from(bytes[2] >> 4);
+        let sample_rate_index = usize::from((bytes[2] >> 2) & 0b11);
+        if version == 0b01 || bitrate_index == 0 || bitrate_index == 15 || sample_rate_index == 3 {
+            return None;
+        }
+
+        let is_mpeg1 = version == 0b11;
+        let sample_rate = match version {
+            0b11 => [44100, 48000, 32000][sample_rate_index],
+            0b10 => [22050, 24000, 16000][sample_rate_index],
+            _ => [11025, 12000, 8000][sample_rate_index],
+        };
+        let bitrate = if is_mpeg1 {
+            MPEG1_BITRATES[bitrate_index]
+        } else {
+            MPEG2_BITRATES[bitrate_index]
+        } * 1000;
+        let padding = u32::from((bytes[2] >> 1) & 1);
+        let (num_sample_frames, frame_len) = if is_mpeg1 {
+            (1152, 144 * bitrate / u32::from(sample_rate) + padding)
+        } else {
+            (576, 72 * bitrate / u32::from(sample_rate) + padding)
+        };
+
+        Some(Self {
+            sample_rate,
+            is_stereo: bytes[3] >> 6 != 0b11,
+            num_sample_frames,
+            frame_len: frame_len as usize,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mp3_metadata_from_headers() {
+        // MPEG-1 Layer III, 128kbps, 44.1kHz, joint stereo: 417 bytes per frame.
+        let mut data = vec![];
+        for _ in 0..3 {
+            let mut frame = vec![0; 417];
+            frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x64]);
+            data.extend(frame);
+        }
+        assert_eq!(
+            mp3_metadata(&data),
+            Some(Mp3Metadata {
+                sample_rate: 44100,
+                is_stereo: true,
+                num_sample_frames: 3 * 1152,
+            })
+        );
+
+        // MPEG-2 Layer III, 32kbps, 22.05kHz, mono: 104 bytes per frame.
+        let mut frame = vec![0; 104];
+        frame[..4].copy_from_slice(&[0xff, 0xf3, 0x40, 0xc0]);
+        assert_eq!(
+            mp3_metadata(&frame),
+            Some(Mp3Metadata {
+                sample_rate: 22050,
+                is_stereo: false,
+                num_sample_frames: 576,
+            })
+        );
+
+        assert_eq!(mp3_metadata(&[0; 100]), None);
+    }
+}
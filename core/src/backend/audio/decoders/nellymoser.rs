@@ -0,0 +1,538 @@
+use super::{Decoder, SeekableDecoder};
+use bitstream_io::{BitReader, LittleEndian};
+use std::f32::consts::PI;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// The size of a Nellymoser block in bytes.
+const BLOCK_LEN: usize = 64;
+
+/// The number of MDCT coefficients in each half of a block.
+const BUF_LEN: usize = 128;
+
+/// The number of samples decoded from each block.
+const SAMPLES_PER_BLOCK: usize = 2 * BUF_LEN;
+
+/// The number of MDCT coefficients that contain audio data. The rest are zero.
+const FILL_LEN: usize = 124;
+
+const NUM_BANDS: usize = 23;
+const HEADER_BITS: u32 = 116;
+const DETAIL_BITS: i32 = 198;
+const BIT_CAP: i32 = 6;
+const BASE_OFF: i32 = 4228;
+const BASE_SHIFT: i32 = 19;
+
+/// Scales the decoded audio to the range of an `i16`.
+const SCALE_BIAS: f32 = 1.0 / 8.0;
+
+/// Decoder for Nellymoser Asao audio data in a Flash file.
+///
+/// Nellymoser is a transform codec used mainly for voice. Each 64 byte block holds 256 mono
+/// samples as two halves of MDCT coefficients. Coefficients are grouped into 23 bands which
+/// share an exponent, and the remaining bits are allocated among the coefficients based on the
+/// band energies.
+pub struct NellymoserDecoder<R: Read> {
+    inner: R,
+    sample_rate: u16,
+
+    /// The IMDCT output of the previous half-block, overlapped with the next one.
+    prev_output: [f32; SAMPLES_PER_BLOCK],
+
+    /// The samples decoded from the current block.
+    samples: [i16; SAMPLES_PER_BLOCK],
+    cur_sample: usize,
+
+    /// The index of the next block to be decoded.
+    block_index: u32,
+
+    /// Seed for the random signs of noise-filled coefficients.
+    random_state: u32,
+
+    /// `cos(PI * i / 512)` for the IMDCT.
+    cos_table: Vec<f32>,
+
+    /// Sine window applied during overlap-add.
+    window: Vec<f32>,
+}
+
+impl<R: Read> NellymoserDecoder<R> {
+    /// Dequantized coefficient values, indexed by `(1 << bits) - 1 + value`.
+    const DEQUANTIZATION_TABLE: [f32; 127] = [
+        0.0, -0.847256, 0.722471, -1.524748, -0.453148, 0.375361, 1.47179, -1.982258, -1.192938,
+        -0.582937, -0.069378, 0.390957, 0.90692, 1.486274, 2.221541, -2.388787, -1.806754,
+        -1.410542, -1.077361, -0.799501, -0.555811, -0.333402, -0.132449, 0.056802, 0.254877,
+        0.477355, 0.738685, 1.044306, 1.395446, 1.809875, 2.391876, -2.389383, -1.988468,
+        -1.751404, -1.564312, -1.392213, -1.216465, -1.04695, -0.89051, -0.764558, -0.645458,
+        -0.525928, -0.405955, -0.302972, -0.20969, -0.123987, -0.047923, 0.025773, 0.100134,
+        0.173718, 0.258554, 0.35229, 0.456988, 0.576775, 0.700316, 0.842552, 1.009388, 1.182135,
+        1.353456, 1.532082, 1.733262, 1.972235, 2.397814, -2.575631, -2.057332, -1.898492,
+        -1.772781, -1.66626, -1.574218, -1.499332, -1.431664, -1.365228, -1.300099, -1.228093,
+        -1.158858, -1.092125, -1.013574, -0.920285, -0.828705, -0.737489, -0.644776, -0.559094,
+        -0.485714, -0.411032, -0.34597, -0.285116, -0.234162, -0.187058, -0.14425, -0.110717,
+        -0.073968, -0.036561, -0.007329, 0.020361, 0.047904, 0.075197, 0.0981, 0.122039, 0.1459,
+        0.169435, 0.197046, 0.225243, 0.255687, 0.28701, 0.31971, 0.352583, 0.388907, 0.433492,
+        0.476946, 0.520482, 0.564453, 0.612204, 0.668593, 0.734165, 0.803216, 0.878404, 0.956621,
+        1.039707, 1.129377, 1.221116, 1.308028, 1.40248, 1.505682, 1.622773, 1.772496, 1.943088,
+        2.290393,
+    ];
+
+    /// The number of coefficients in each band.
+    const BAND_SIZES_TABLE: [usize; NUM_BANDS] = [
+        2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 4, 4, 5, 6, 6, 7, 8, 9, 10, 12, 14, 15,
+    ];
+
+    /// Exponent of the first band.
+    const INIT_TABLE: [i32; 64] = [
+        3134, 5342, 6870, 7792, 8569, 9185, 9744, 10191, 10631, 11061, 11434, 11770, 12116, 12513,
+        12925, 13300, 13674, 14027, 14352, 14716, 15117, 15477, 15824, 16157, 16513, 16804, 17090,
+        17401, 17679, 17948, 18238, 18520, 18764, 19078, 19329, 19594, 19918, 20228, 20539, 20836,
+        21150, 21461, 21760, 22084, 22355, 22640, 22926, 23240, 23532, 23838, 24101, 24384, 24661,
+        24933, 25226, 25499, 25780, 26034, 26289, 26557, 26852, 27118, 27377, 27650,
+    ];
+
+    /// Exponent deltas of the following bands.
+    const DELTA_TABLE: [i32; 32] = [
+        -11725, -9420, -7910, -6801, -5948, -5233, -4599, -4039, -3507, -3030, -2596, -2170, -1774,
+        -1383, -1016, -660, -329, -1, 337, 696, 1085, 1512, 1962, 2433, 2968, 3569, 4314, 5176,
+        6225, 7541, 9205, 11383,
+    ];
+
+    pub fn new(inner: R, sample_rate: u16) -> Self {
+        let cos_table = (0..4 * SAMPLES_PER_BLOCK)
+            .map(|i| (PI * i as f32 / (2 * SAMPLES_PER_BLOCK) as f32).cos())
+            .collect();
+        let window = (0..SAMPLES_PER_BLOCK)
+            .map(|i| (PI * (i as f32 + 0.5) / SAMPLES_PER_BLOCK as f32).sin())
+            .collect();
+        Self {
+            inner,
+            sample_rate,
+            prev_output: [0.0; SAMPLES_PER_BLOCK],
+            samples: [0; SAMPLES_PER_BLOCK],
+            cur_sample: SAMPLES_PER_BLOCK,
+            block_index: 0,
+            random_state: 0,
+            cos_table,
+            window,
+        }
+    }
+
+    /// Reads and decodes the next block of audio data.
+    fn next_block(&mut self) -> Result<(), std::io::Error> {
+        let mut block = [0u8; BLOCK_LEN];
+        self.inner.read_exact(&mut block)?;
+        self.decode_block(&block)?;
+        self.block_index += 1;
+        self.cur_sample = 0;
+        Ok(())
+    }
+
+    fn decode_block(&mut self, block: &[u8; BLOCK_LEN]) -> Result<(), std::io::Error> {
+        // The noise only has to be random-sounding, so reseed it per block to make seeking
+        // deterministic.
+        self.random_state = self.block_index.wrapping_mul(0x9E37_79B9);
+
+        // Read the band exponents from the header.
+        let mut reader = BitReader::<_, LittleEndian>::new(&block[..]);
+        let mut exponents = [0.0f32; FILL_LEN];
+        let mut gains = [0.0f32; FILL_LEN];
+        let mut val = Self::INIT_TABLE[reader.read::<u32>(6)? as usize];
+        let mut i = 0;
+        for (band, &band_size) in Self::BAND_SIZES_TABLE.iter().enumerate() {
+            if band > 0 {
+                val += Self::DELTA_TABLE[reader.read::<u32>(5)? as usize];
+            }
+            let gain = -(val as f32 / 2048.0).exp2() * SCALE_BIAS;
+            for _ in 0..band_size {
+                exponents[i] = val as f32;
+                gains[i] = gain;
+                i += 1;
+            }
+        }
+
+        let bits = sample_bits(&exponents);
+
+        for half in 0..2 {
+            // The two halves always start at fixed bit positions, even if the bit allocation
+            // did not use every bit.
+            let mut reader = BitReader::<_, LittleEndian>::new(&block[..]);
+            reader.skip(HEADER_BITS + half as u32 * DETAIL_BITS as u32)?;
+
+            let mut coefficients = [0.0f32; BUF_LEN];
+            let coefficient_params = bits.iter().zip(gains.iter());
+            for (coefficient, (&bits, &gain)) in coefficients.iter_mut().zip(coefficient_params) {
+                *coefficient = if bits <= 0 {
+                    // Coefficients without any bits are filled with noise.
+                    let sign = if self.next_random() & 1 != 0 {
+                        -1.0
+                    } else {
+                        1.0
+                    };
+                    sign * std::f32::consts::FRAC_1_SQRT_2 * gain
+                } else {
+                    let v = reader.read::<u32>(bits as u32)? as usize;
+                    Self::DEQUANTIZATION_TABLE[(1 << bits) - 1 + v] * gain
+                };
+            }
+
+            let output = self.imdct(&coefficients);
+            let samples = &mut self.samples[half * BUF_LEN..(half + 1) * BUF_LEN];
+            for (n, sample) in samples.iter_mut().enumerate() {
+                let value = self.prev_output[BUF_LEN + n] * self.window[BUF_LEN + n]
+                    + output[n] * self.window[n];
+                *sample = if value > 32767.0 {
+                    32767
+                } else if value < -32768.0 {
+                    -32768
+                } else {
+                    value.round() as i16
+                };
+            }
+            self.prev_output = output;
+        }
+
+        Ok(())
+    }
+
+    /// Computes the inverse MDCT of one half-block of coefficients.
+    ///
+    /// This uses the same sign convention as FFmpeg's IMDCT, so the output is not inverted
+    /// compared to other Nellymoser decoders.
+    fn imdct(&self, coefficients: &[f32; BUF_LEN]) -> [f32; SAMPLES_PER_BLOCK] {
+        // y[n] = -sum(X[k] * cos(PI / 128 * (n + 0.5 + 64) * (k + 0.5)))
+        //      = -sum(X[k] * cos(PI / 512 * (2n + 129) * (2k + 1)))
+        let mut output = [0.0f32; SAMPLES_PER_BLOCK];
+        let period = self.cos_table.len();
+        for (n, out) in output.iter_mut().enumerate() {
+            let a = 2 * n + BUF_LEN + 1;
+            *out = -coefficients[..FILL_LEN]
+                .iter()
+                .enumerate()
+                .map(|(k, x)| x * self.cos_table[(a * (2 * k + 1)) % period])
+                .sum::<f32>();
+        }
+        output
+    }
+
+    fn next_random(&mut self) -> u32 {
+        self.random_state = self
+            .random_state
+            .wrapping_mul(1_103_515_245)
+            .wrapping_add(12345);
+        self.random_state >> 16
+    }
+}
+
+/// Distributes the detail bits of a half-block among its coefficients, based on the band
+/// exponents. Coefficients in louder bands get more bits.
+fn sample_bits(exponents: &[f32; FILL_LEN]) -> [i32; FILL_LEN] {
+    fn headroom(la: &mut i32) -> i32 {
+        if *la == 0 {
+            return 31;
+        }
+        let l = 30 - (31 - la.abs().leading_zeros() as i32);
+        *la = la.wrapping_mul(1 << l);
+        l
+    }
+
+    fn signed_shift(i: i32, shift: i32) -> i32 {
+        if shift > 0 {
+            ((i as u32) << shift) as i32
+        } else {
+            i >> -shift
+        }
+    }
+
+    fn clip_bits(bits: i32) -> i32 {
+        if bits < 0 {
+            0
+        } else if bits > BIT_CAP {
+            BIT_CAP
+        } else {
+            bits
+        }
+    }
+
+    fn sum_bits(sbuf: &[i16; FILL_LEN], shift: i32, off: i32) -> i32 {
+        sbuf.iter()
+            .map(|&s| {
+                let b = i32::from(s) - off;
+                let b = ((b >> (shift - 1)) + 1) >> 1;
+                clip_bits(b)
+            })
+            .sum()
+    }
+
+    let mut max = exponents
+        .iter()
+        .fold(0, |max, &e| std::cmp::max(max, e as i32));
+    let mut shift = -16 + headroom(&mut max);
+
+    let mut sbuf = [0i16; FILL_LEN];
+    let mut sum = 0i32;
+    for (s, &e) in sbuf.iter_mut().zip(exponents.iter()) {
+        let v = signed_shift(e as i32, shift) as i16;
+        *s = ((3 * i32::from(v)) >> 2) as i16;
+        sum += i32::from(*s);
+    }
+
+    shift += 11;
+    let shift_saved = shift;
+    sum -= DETAIL_BITS;
+    shift += headroom(&mut sum);
+    let mut small_off = (BASE_OFF * (sum >> 16)) >> 15;
+    shift = shift_saved - (BASE_SHIFT + shift - 31);
+    small_off = signed_shift(small_off, shift);
+
+    let mut bitsum = sum_bits(&sbuf, shift_saved, small_off);
+
+    if bitsum != DETAIL_BITS {
+        // Search for the offset that allocates exactly the available bits.
+        let mut off = bitsum - DETAIL_BITS;
+        shift = 0;
+        while off.abs() <= 16383 {
+            off *= 2;
+            shift += 1;
+        }
+        off = (off * BASE_OFF) >> 15;
+        shift = shift_saved - (BASE_SHIFT + shift - 15);
+        off = signed_shift(off, shift);
+
+        let mut last_off = small_off;
+        let mut last_bitsum = bitsum;
+        let mut j = 1;
+        while j < 20 {
+            last_off = small_off;
+            small_off += off;
+            last_bitsum = bitsum;
+            bitsum = sum_bits(&sbuf, shift_saved, small_off);
+            if (bitsum - DETAIL_BITS) * (last_bitsum - DETAIL_BITS) <= 0 {
+                break;
+            }
+            j += 1;
+        }
+
+        let mut big_off;
+        let mut big_bitsum;
+        let mut small_bitsum;
+        if bitsum > DETAIL_BITS {
+            big_off = small_off;
+            small_off = last_off;
+            big_bitsum = bitsum;
+            small_bitsum = last_bitsum;
+        } else {
+            big_off = last_off;
+            big_bitsum = last_bitsum;
+            small_bitsum = bitsum;
+        }
+
+        // Bisect between the two closest offsets.
+        while bitsum != DETAIL_BITS && j <= 19 {
+            let off = (big_off + small_off) >> 1;
+            bitsum = sum_bits(&sbuf, shift_saved, off);
+            if bitsum > DETAIL_BITS {
+                big_off = off;
+                big_bitsum = bitsum;
+            } else {
+                small_off = off;
+                small_bitsum = bitsum;
+            }
+            j += 1;
+        }
+
+        if (big_bitsum - DETAIL_BITS).abs() >= (small_bitsum - DETAIL_BITS).abs() {
+            bitsum = small_bitsum;
+        } else {
+            small_off = big_off;
+            bitsum = big_bitsum;
+        }
+    }
+
+    let mut bits = [0i32; FILL_LEN];
+    for (bit, &s) in bits.iter_mut().zip(sbuf.iter()) {
+        let tmp = i32::from(s) - small_off;
+        let tmp = ((tmp >> (shift_saved - 1)) + 1) >> 1;
+        *bit = clip_bits(tmp);
+    }
+
+    if bitsum > DETAIL_BITS {
+        // Too many bits were allocated; truncate the allocation.
+        let mut total = 0;
+        let mut i = 0;
+        while total < DETAIL_BITS {
+            total += bits[i];
+            i += 1;
+        }
+        bits[i - 1] -= total - DETAIL_BITS;
+        for bit in &mut bits[i..] {
+            *bit = 0;
+        }
+    }
+
+    bits
+}
+
+impl<R: Read> Iterator for NellymoserDecoder<R> {
+    type Item = [i16; 2];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur_sample >= SAMPLES_PER_BLOCK {
+            self.next_block().ok()?;
+        }
+        let sample = self.samples[self.cur_sample];
+        self.cur_sample += 1;
+        Some([sample, sample])
+    }
+}
+
+impl<R: Read> Decoder for NellymoserDecoder<R> {
+    #[inline]
+    fn num_channels(&self) -> u8 {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u16 {
+        self.sample_rate
+    }
+}
+
+impl<R: AsRef<[u8]>> SeekableDecoder for NellymoserDecoder<Cursor<R>> {
+    #[inline]
+    fn reset(&mut self) {
+        self.seek_to_sample_frame(0);
+    }
+
+    fn seek_to_sample_frame(&mut self, frame: u32) {
+        // Each block depends on the previous one for overlap-add,
+        // so start decoding from the block before the target.
+        let block = frame as usize / SAMPLES_PER_BLOCK;
+        let start_block = block.saturating_sub(1);
+        let _ = self
+            .inner
+            .seek(SeekFrom::Start((start_block * BLOCK_LEN) as u64));
+        self.prev_output = [0.0; SAMPLES_PER_BLOCK];
+        self.block_index = start_block as u32;
+        self.cur_sample = SAMPLES_PER_BLOCK;
+        if block > 0 {
+            let _ = self.next_block();
+            self.cur_sample = SAMPLES_PER_BLOCK;
+        }
+        for _ in 0..frame as usize % SAMPLES_PER_BLOCK {
+            self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A block that has every band at the lowest exponent.
+    fn quiet_block() -> Vec<u8> {
+        vec![0; BLOCK_LEN]
+    }
+
+    /// A block that has loud low frequencies and quiet high frequencies.
+    fn loud_block() -> Vec<u8> {
+        let mut block = vec![0; BLOCK_LEN];
+        // Initial exponent index 63, then delta index 17 (-1) for every band.
+        let mut writer = bitstream_io::BitWriter::endian(&mut block[..], LittleEndian);
+        writer.write(6, 63u32).unwrap();
+        for _ in 1..NUM_BANDS {
+            writer.write(5, 17u32).unwrap();
+        }
+        for i in 0..(BLOCK_LEN as u32 * 8 - HEADER_BITS) / 8 {
+            writer.write(8, (i * 37) & 0xff).unwrap();
+        }
+        block
+    }
+
+    #[test]
+    fn nellymoser_block_size() {
+        let data = [quiet_block(), quiet_block(), quiet_block()].concat();
+        let decoder = NellymoserDecoder::new(Cursor::new(data), 8000);
+        assert_eq!(decoder.num_channels(), 1);
+        assert_eq!(decoder.sample_rate(), 8000);
+        assert_eq!(decoder.count(), 3 * SAMPLES_PER_BLOCK);
+    }
+
+    #[test]
+    fn nellymoser_bit_allocation() {
+        let mut exponents = [0.0; FILL_LEN];
+        for (i, e) in exponents.iter_mut().enumerate() {
+            *e = (27650 - i as i32 * 150) as f32;
+        }
+        let bits = sample_bits(&exponents);
+        assert!(bits.iter().sum::<i32>() <= DETAIL_BITS);
+        assert!(bits.iter().all(|b| (0..=BIT_CAP).contains(b)));
+        // Louder coefficients receive at least as many bits.
+        assert!(bits.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn nellymoser_decode() {
+        let quiet: Vec<_> = NellymoserDecoder::new(Cursor::new(quiet_block()), 8000)
+            .map(|frame| frame[0])
+            .collect();
+        assert!(quiet.iter().all(|&s| s.abs() < 16));
+
+        let data = [loud_block(), loud_block()].concat();
+        let loud: Vec<_> = NellymoserDecoder::new(Cursor::new(data), 8000)
+            .map(|frame| frame[0])
+            .collect();
+        assert_eq!(loud.len(), 2 * SAMPLES_PER_BLOCK);
+        assert!(loud.iter().any(|&s| s.abs() > 1000));
+
+        // Seeking reproduces the same samples as decoding from the start.
+        let data = [loud_block(), loud_block()].concat();
+        let mut decoder = NellymoserDecoder::new(Cursor::new(data), 8000);
+        decoder.seek_to_sample_frame(300);
+        assert_eq!(decoder.next().unwrap()[0], loud[300]);
+    }
+
+    /// Three blocks with quiet high bands, so the noise filling of unallocated coefficients
+    /// has no audible effect on the output.
+    const REFERENCE_BLOCKS: [u8; 3 * BLOCK_LEN] = [
+        0xa0, 0x52, 0x50, 0xd5, 0xda, 0x58, 0xb4, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60,
+        0x73, 0xf1, 0x4a, 0x5d, 0x10, 0x82, 0x30, 0xb5, 0xcc, 0x8a, 0xe7, 0x87, 0x91, 0xb2, 0xbb,
+        0x16, 0x58, 0xd8, 0xe8, 0x6d, 0xfe, 0x19, 0xf5, 0x44, 0xa4, 0xdf, 0xc2, 0x87, 0xfc, 0x57,
+        0x7b, 0xc4, 0x6c, 0xe2, 0x03, 0xe1, 0xc1, 0x46, 0x05, 0x70, 0x64, 0xe4, 0x7e, 0xc0, 0x91,
+        0x35, 0x16, 0x5b, 0xae, 0xa7, 0x84, 0x4c, 0x3d, 0xe9, 0x9e, 0x55, 0x4c, 0xb9, 0x08, 0x00,
+        0x00, 0x00, 0x00, 0x80, 0xe1, 0x22, 0x42, 0xa3, 0xdd, 0x02, 0x01, 0x7f, 0x3d, 0x79, 0x8e,
+        0xd5, 0xdc, 0xb8, 0xe7, 0xbd, 0xd5, 0xb2, 0x5d, 0x28, 0x47, 0xd6, 0xea, 0x00, 0x6d, 0x3e,
+        0x35, 0x9a, 0x3f, 0x7d, 0xc4, 0x93, 0x65, 0x09, 0x11, 0xb6, 0x18, 0xc0, 0x22, 0x7c, 0xe4,
+        0x93, 0xb9, 0xc4, 0x02, 0xa7, 0x05, 0x7a, 0x77, 0xe0, 0x73, 0xce, 0x2d, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x2c, 0x8b, 0xd0, 0x1b, 0x08, 0xec, 0x0b,
+        0xe4, 0xf4, 0x75, 0x92, 0x26, 0xc6, 0xe5, 0x56, 0x0f, 0x38, 0x71, 0x9c, 0x02, 0xd9, 0x5a,
+        0x81, 0x92, 0x41, 0x0e, 0xfb, 0x91, 0x8e, 0x08, 0x4e, 0x45, 0xcf, 0x1a, 0x9f, 0x8e, 0xd6,
+        0x50, 0xd9, 0xb0, 0xdc, 0xba, 0x7b, 0x50, 0x5b, 0x05, 0xf6, 0xa3, 0x86,
+    ];
+
+    /// Every 8th sample of `REFERENCE_BLOCKS`, decoded by a port of FFmpeg's Nellymoser decoder.
+    const REFERENCE_SAMPLES: [i16; 3 * SAMPLES_PER_BLOCK / 8] = [
+        0, 2, 20, 36, 53, 63, 49, 13, -6, -16, -78, -116, -127, -120, -83, -13, 55, 74, 38, 5, -1,
+        68, 13, -13, 57, 76, 64, 108, 37, 114, 102, 51, 81, 47, 166, 198, 65, -126, -144, -396,
+        -214, 203, -70, -98, -240, -293, -60, 334, 329, 23, -299, -419, -694, -321, -293, 322, 155,
+        12, 268, 330, -9, -295, -458, -15, 222, 42, -32, -96, -320, -226, -332, -690, -351, -280,
+        19, 124, 1, -3, 109, 133, 20, -40, 117, 210, 74, -12, 59, 55, -4, -24, -26, 49, 99, 65,
+        155, 347,
+    ];
+
+    #[test]
+    fn nellymoser_reference_pcm() {
+        let decoder = NellymoserDecoder::new(Cursor::new(&REFERENCE_BLOCKS[..]), 8000);
+        let samples: Vec<_> = decoder.map(|frame| frame[0]).step_by(8).collect();
+        assert_eq!(samples.len(), REFERENCE_SAMPLES.len());
+        for (i, (&sample, &expected)) in samples.iter().zip(REFERENCE_SAMPLES.iter()).enumerate() {
+            assert!(
+                (i32::from(sample) - i32::from(expected)).abs() <= 2,
+                "sample {}: got {}, expected {}",
+                i * 8,
+                sample,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//! ID3 metadata tags of MP3 files.
+//!
+//! Used by `Sound.loadSound` to fill in the AVM1 `Sound.id3` object.
+//! Both ID3v1 and ID3v2 (versions 2.2 to 2.4) tags are supported.
+use swf::Codepage;
+
+/// The metadata of an MP3 file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Id3Tag {
+    /// The properties of the `Sound.id3` object, in order.
+    ///
+    /// ID3v2 frames are listed under their frame ID (e.g. `TIT2`), and the common fields are
+    /// also listed under their ID3v1 names (`songname`, `artist`, `album`, `year`,
+    /// `comment`, `track` and `genre`).
+    pub properties: Vec<(String, String)>,
+}
+
+impl Id3Tag {
+    /// Returns the value of the given property.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| &value[..])
+    }
+
+    fn set(&mut self, name: &str, value: String) {
+        if let Some(property) = self.properties.iter_mut().find(|(key, _)| key == name) {
+            property.1 = value;
+        } else {
+            self.properties.push((name.to_string(), value));
+        }
+    }
+}
+
+/// ID3v2 frames that are also exposed under the ID3v1 property names.
+const ID3V1_NAMES: &[(&str, &str)] = &[
+    ("TIT2", "songname"),
+    ("TPE1", "artist"),
+    ("TALB", "album"),
+    ("TYER", "year"),
+    ("TDRC", "year"),
+    ("COMM", "comment"),
+    ("TRCK", "track"),
+    ("TCON", "genre"),
+];
+
+/// ID3v2.2 frame IDs for the frames in `ID3V1_NAMES`.
+const ID3V22_FRAME_IDS: &[(&[u8], &str)] = &[
+    (b"TT2", "TIT2"),
+    (b"TP1", "TPE1"),
+    (b"TAL", "TALB"),
+    (b"TYE", "TYER"),
+    (b"COM", "COMM"),
+    (b"TRK", "TRCK"),
+    (b"TCO", "TCON"),
+];
+
+/// Reads the ID3 tags of an MP3 file.
+/// Returns `None` if the file has no ID3 tags.
+///
+/// Text that is neither UTF-8 nor UTF-16 is decoded as Latin-1, unless a codepage is given.
+/// This is the case when `System.useCodepage` is set.
+pub fn read_id3(data: &[u8], codepage: Option<Codepage>) -> Option<Id3Tag> {
+    let v2 = read_id3v2(data, codepage);
+    let v1 = read_id3v1(data, codepage);
+    if v2.is_none() && v1.is_none() {
+        return None;
+    }
+
+    let mut tag = Id3Tag::default();
+    // ID3v1 values are only used for fields missing from the ID3v2 tag.
+    if let Some(v1) = v1 {
+        tag.properties = v1;
+    }
+    if let Some(frames) = v2 {
+        for (id, value) in frames {
+            if let Some((_, name)) = ID3V1_NAMES.iter().find(|(frame_id, _)| *frame_id == id) {
+                tag.set(name, value.clone());
+            }
+            tag.set(&id, value);
+        }
+    }
+    Some(tag)
+}
+
+/// Returns the audio data of an MP3 file, without any ID3 tags.
+pub fn strip_id3(data: &[u8]) -> &[u8] {
+    let mut data = data;
+    if let Some(len) = id3v2_len(data) {
+        data = &data[len.min(data.len())..];
+    }
+    if data.len() >= 128 && data[data.len() - 128..].starts_with(b"TAG") {
+        data = &data[..data.len() - 128];
+    }
+    data
+}
+
+/// Returns the total length of the ID3v2 tag at the start of `data`, including its header.
+fn id3v2_len(data: &[u8]) -> Option<usize> {
+    if data.len() >= 10 && data.starts_with(b"ID3") {
+        let footer_len = if data[5] & 0x10 != 0 { 10 } else { 0 };
+        Some(10 + syncsafe(&data[6..10]) + footer_len)
+    } else {
+        None
+    }
+}
+
+/// Reads a "synchsafe" integer, which only uses the lower 7 bits of each byte.
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |n, &byte| (n << 7) | usize::from(byte & 0x7f))
+}
+
+fn read_id3v1(data: &[u8], codepage: Option<Codepage>) -> Option<Vec<(String, String)>> {
+    if data.len() < 128 {
+        return None;
+    }
+    let tag = &data[data.len() - 128..];
+    if !tag.starts_with(b"TAG") {
+        return None;
+    }
+
+    let mut properties = vec![
+        ("songname".to_string(), decode_latin1(&tag[3..33], codepage)),
+        ("artist".to_string(), decode_latin1(&tag[33..63], codepage)),
+        ("album".to_string(), decode_latin1(&tag[63..93], codepage)),
+        ("year".to_string(), decode_latin1(&tag[93..97], codepage)),
+    ];
+    // ID3v1.1 stores the track number in the last byte of the comment.
+    if tag[125] == 0 && tag[126] != 0 {
+        properties.push((
+            "comment".to_string(),
+            decode_latin1(&tag[97..125], codepage),
+        ));
+        properties.push(("track".to_string(), tag[126].to_string()));
+    } else {
+        properties.push((
+            "comment".to_string(),
+            decode_latin1(&tag[97..127], codepage),
+        ));
+    }
+    properties.push(("genre".to_string(), tag[127].to_string()));
+    Some(properties)
+}
+
+/// Reads the text frames of an ID3v2 tag, returning the frame IDs and their values.
+fn read_id3v2(data: &[u8], codepage: Option<Codepage>) -> Option<Vec<(String, String)>> {
+    let tag_len = id3v2_len(data)?;
+    let version = data[3];
+    let flags = data[5];
+    let mut body = &data[10..tag_len.min(data.len())];
+
+    // Skip the extended header.
+    if flags & 0x40 != 0 && body.len() >= 4 {
+        let len = if version >= 4 {
+            syncsafe(&body[..4])
+        } else {
+            4 + read_u32(&body[..4])
+        };
+        body = &body[len.min(body.len())..];
+    }
+
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+    let mut frames = vec![];
+    while body.len() >= header_len && body[0] != 0 {
+        let id = &body[..id_len];
+        let size = match version {
+            2 => (usize::from(body[3]) << 16) | (usize::from(body[4]) << 8) | usize::from(body[5]),
+            3 => read_u32(&body[4..8]),
+            _ => syncsafe(&body[4..8]),
+        };
+        let end = (header_len + size).min(body.len());
+        let content = &body[header_len..end];
+        body = &body[end..];
+
+        let id = if version == 2 {
+            match ID3V22_FRAME_IDS.iter().find(|(v22_id, _)| *v22_id == id) {
+                Some((_, id)) => id.to_string(),
+                None => String::from_utf8_lossy(id).into_owned(),
+            }
+        } else {
+            String::from_utf8_lossy(id).into_owned()
+        };
+
+        if let Some(value) = read_frame_text(&id, content, codepage) {
+            frames.push((id, value));
+        }
+    }
+    Some(frames)
+}
+
+/// Decodes the text of a text frame (`T***`) or a comment frame (`COMM`).
+fn read_frame_text(id: &str, content: &[u8], codepage: Option<Codepage>) -> Option<String> {
+    let (&encoding, text) = content.split_first()?;
+    if id == "COMM" {
+        // Comments have a 3-byte language code and a short description before the text.
+        let text = text.get(3..)?;
+        let description_len = terminated_len(encoding, text);
+        let text = text.get(description_len..)?;
+        Some(decode_text(encoding, text, codepage))
+    } else if id.starts_with('T') && id != "TXXX" {
+        // Only the first of multiple strings is used.
+        let len = terminated_len(encoding, text);
+        let text = &text[..len];
+        Some(decode_text(encoding, text, codepage))
+    } else {
+        None
+    }
+}
+
+/// Returns the length of a null-terminated string in the given encoding, including the terminator.
+fn terminated_len(encoding: u8, text: &[u8]) -> usize {
+    if encoding == 1 || encoding == 2 {
+        text.chunks(2)
+            .position(|c| c == [0, 0])
+            .map(|i| i * 2 + 2)
+            .unwrap_or_else(|| text.len())
+    } else {
+        text.iter()
+            .position(|&b| b == 0)
+            .map(|i| i + 1)
+            .unwrap_or_else(|| text.len())
+    }
+}
+
+fn decode_text(encoding: u8, text: &[u8], codepage: Option<Codepage>) -> String {
+    let text = match encoding {
+        1 | 2 => {
+            let (text, big_endian) = if text.starts_with(&[0xfe, 0xff]) {
+                (&text[2..], true)
+            } else if text.starts_with(&[0xff, 0xfe]) {
+                (&text[2..], false)
+            } else {
+                (text, encoding == 2)
+            };
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|c| {
+                    if big_endian {
+                        u16::from_be_bytes([c[0], c[1]])
+                    } else {
+                        u16::from_le_bytes([c[0], c[1]])
+                    }
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        3 => String::from_utf8_lossy(text).into_owned(),
+        _ => decode_latin1(text, codepage),
+    };
+    text.trim_end_matches('\0').to_string()
+}
+
+fn decode_latin1(bytes: &[u8], codepage: Option<Codepage>) -> String {
+    let bytes = match bytes.iter().position(|&b| b == 0) {
+        Some(i) => &bytes[..i],
+        None => bytes,
+    };
+    let text = match codepage {
+        Some(codepage) => codepage.decode(bytes).into_owned(),
+        None => bytes.iter().map(|&b| char::from(b)).collect(),
+    };
+    text.trim_end().to_string()
+}
+
+fn read_u32(bytes: &[u8]) -> usize {
+    (usize::from(bytes[0]) << 24)
+        | (usize::from(bytes[1]) << 16)
+        | (usize::from(bytes[2]) << 8)
+        | usize::from(bytes[3])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id3v1_tag() -> Vec<u8> {
+        let mut tag = b"TAG".to_vec();
+        let field = |text: &[u8], len: usize| {
+            let mut bytes = text.to_vec();
+            bytes.resize(len, 0);
+            bytes
+        };
+        tag.extend(field(b"Song", 30));
+        tag.extend(field(b"Artist", 30));
+        tag.extend(field(b"Album", 30));
+        tag.extend(field(b"2001", 4));
+        tag.extend(field(b"Comment", 28));
+        tag.extend(&[0, 7, 12]);
+        tag
+    }
+
+    fn id3v2_frame(id: &[u8], content: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend(&(content.len() as u32).to_be_bytes());
+        frame.extend(&[0, 0]);
+        frame.extend(content);
+        frame
+    }
+
+    fn id3v2_tag(frames: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = frames.concat();
+        let len = body.len();
+        let mut tag = b"ID3\x03\x00\x00".to_vec();
+        tag.extend(&[
+            (len >> 21) as u8 & 0x7f,
+            (len >> 14) as u8 & 0x7f,
+            (len >> 7) as u8 & 0x7f,
+            len as u8 & 0x7f,
+        ]);
+        tag.extend(body);
+        tag
+    }
+
+    #[test]
+    fn read_id3v1_tag() {
+        let mut data = vec![0xff; 64];
+        data.extend(id3v1_tag());
+        let tag = read_id3(&data, None).unwrap();
+        assert_eq!(tag.get("songname"), Some("Song"));
+        assert_eq!(tag.get("artist"), Some("Artist"));
+        assert_eq!(tag.get("album"), Some("Album"));
+        assert_eq!(tag.get("year"), Some("2001"));
+        assert_eq!(tag.get("comment"), Some("Comment"));
+        assert_eq!(tag.get("track"), Some("7"));
+        assert_eq!(tag.get("genre"), Some("12"));
+        assert_eq!(strip_id3(&data), &[0xff; 64][..]);
+    }
+
+    #[test]
+    fn read_id3v2_tag() {
+        let mut data = id3v2_tag(&[
+            id3v2_frame(b"TIT2", b"\x00Title\x00"),
+            id3v2_frame(b"TPE1", b"\x01\xff\xfeA\x00r\x00t\x00"),
+            id3v2_frame(b"COMM", b"\x03engdesc\x00Hello"),
+            id3v2_frame(b"APIC", b"\x00ignored"),
+        ]);
+        data.extend(&[0xff, 0xfb]);
+        data.extend(id3v1_tag());
+
+        let tag = read_id3(&data, None).unwrap();
+        assert_eq!(tag.get("TIT2"), Some("Title"));
+        assert_eq!(tag.get("songname"), Some("Title"));
+        assert_eq!(tag.get("TPE1"), Some("Art"));
+        assert_eq!(tag.get("artist"), Some("Art"));
+        assert_eq!(tag.get("COMM"), Some("Hello"));
+        assert_eq!(tag.get("comment"), Some("Hello"));
+        assert_eq!(tag.get("APIC"), None);
+        // Fields missing from the ID3v2 tag come from the ID3v1 tag.
+        assert_eq!(tag.get("album"), Some("Album"));
+        assert_eq!(strip_id3(&data), &[0xff, 0xfb][..]);
+    }
+
+    #[test]
+    fn read_id3_with_codepage() {
+        let data = id3v2_tag(&[id3v2_frame(b"TIT2", b"\x00\x93\xfa\x96\x7b\x00")]);
+        let tag = read_id3(&data, None).unwrap();
+        assert_eq!(tag.get("TIT2"), Some("\u{93}ú\u{96}{"));
+        let tag = read_id3(&data, Some(Codepage::ShiftJis)).unwrap();
+        assert_eq!(tag.get("TIT2"), Some("日本"));
+    }
+
+    #[test]
+    fn no_id3_tag() {
+        let data = [0xff, 0xfb, 0x90, 0x64];
+        assert_eq!(read_id3(&data, None), None);
+        assert_eq!(strip_id3(&data), &data[..]);
+    }
+}
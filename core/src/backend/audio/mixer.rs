@@ -0,0 +1,954 @@
+//! A software audio mixer shared by the audio backends.
+//!
+//! `AudioMixer` handles sound registration, decoding, resampling, envelopes, looping,
+//! stream sounds and sound transforms. Platform backends only have to pull the mixed audio
+//! using an `AudioMixerProxy`, usually from an audio thread or callback.
+
+use super::decoders::{
+    self, AdpcmDecoder, Mp3Decoder, NellymoserDecoder, PcmDecoder, SeekableDecoder,
+};
+use super::{swf, AudioStreamHandle, SoundHandle, SoundInstanceHandle, SoundTransform};
+use crate::tag_utils::SwfSlice;
+use generational_arena::Arena;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use swf::AudioCompression;
+
+type Error = Box<dyn std::error::Error>;
+
+/// A stream of stereo sample frames at the output sample rate.
+type Signal = Box<dyn Send + Iterator<Item = [i16; 2]>>;
+
+/// Contains the data and metadata for a sound in an SWF file.
+/// A `Sound` is defined by the `DefineSound` SWF tags.
+struct Sound {
+    format: swf::SoundFormat,
+    data: Arc<Vec<u8>>,
+
+    /// Number of samples in this audio.
+    /// This does not include the skip_sample_frames.
+    num_sample_frames: u32,
+
+    /// Number of samples to skip encoder delay.
+    skip_sample_frames: u16,
+}
+
+/// An actively playing instance of a sound.
+/// This sound can be either an event sound (`StartSound`) or
+/// a stream sound (`SoundStreamBlock`).
+struct SoundInstance {
+    /// The handle the sound definition inside `sounds`.
+    /// `None` if this is a stream sound.
+    handle: Option<SoundHandle>,
+
+    /// The audio stream, resampled to the output sample rate.
+    signal: Signal,
+
+    /// Flag indicating whether this sound is still playing.
+    /// If this flag is false, the sound will be cleaned up during the next mix.
+    active: bool,
+
+    /// The volume and channel mix applied to this sound, set by `Sound.setTransform` etc.
+    /// This is applied on top of the sound envelope.
+    transform: SoundTransform,
+
+    /// The number of sample frames output by this sound, at the output sample rate.
+    /// Used to calculate the sound position.
+    num_frames_played: u64,
+
+    /// The position where playback started, in milliseconds.
+    start_position: f64,
+
+    /// The length of one loop in milliseconds, if this sound loops.
+    loop_duration: Option<f64>,
+}
+
+/// Mixes the playing sounds into a buffer of interleaved `f32` sample frames.
+pub struct AudioMixer {
+    sounds: Arena<Sound>,
+    sound_instances: Arc<Mutex<Arena<SoundInstance>>>,
+    num_output_channels: u8,
+    output_sample_rate: u32,
+
+    /// The number of sample frames between mixing audio and hearing it,
+    /// such as the size of the output buffer.
+    output_latency: u32,
+}
+
+impl AudioMixer {
+    pub fn new(num_output_channels: u8, output_sample_rate: u32) -> Self {
+        Self {
+            sounds: Arena::new(),
+            sound_instances: Arc::new(Mutex::new(Arena::new())),
+            num_output_channels,
+            output_sample_rate,
+            output_latency: 0,
+        }
+    }
+
+    /// Sets the number of sample frames that the mixed audio is delayed by before it is heard.
+    /// Stream positions are reported relative to the audio that has been heard, so that the
+    /// timeline stays in sync with what the user hears.
+    pub fn set_output_latency(&mut self, num_frames: u32) {
+        self.output_latency = num_frames;
+    }
+
+    /// Returns a proxy that can mix the audio of this mixer from another thread.
+    pub fn proxy(&self) -> AudioMixerProxy {
+        AudioMixerProxy {
+            sound_instances: Arc::clone(&self.sound_instances),
+            num_output_channels: self.num_output_channels,
+        }
+    }
+
+    pub fn num_output_channels(&self) -> u8 {
+        self.num_output_channels
+    }
+
+    pub fn output_sample_rate(&self) -> u32 {
+        self.output_sample_rate
+    }
+
+    /// Fills `output_buffer` with interleaved sample frames, advancing all playing sounds.
+    pub fn mix(&self, output_buffer: &mut [f32]) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        Self::mix_audio(
+            &mut sound_instances,
+            self.num_output_channels,
+            output_buffer,
+        );
+    }
+
+    /// Refill the output buffer by stepping through all active sounds
+    /// and mixing in their output.
+    fn mix_audio(
+        sound_instances: &mut Arena<SoundInstance>,
+        num_output_channels: u8,
+        output_buffer: &mut [f32],
+    ) {
+        let num_output_channels = usize::from(num_output_channels.max(1));
+        for output_frame in output_buffer.chunks_exact_mut(num_output_channels) {
+            // For each sample, mix the samples from all active sound instances.
+            let mut left = 0.0;
+            let mut right = 0.0;
+            for (_, sound) in sound_instances.iter_mut() {
+                if !sound.active {
+                    continue;
+                }
+                if let Some(frame) = sound.signal.next() {
+                    sound.num_frames_played += 1;
+                    let (l, r) = sound
+                        .transform
+                        .apply(f32::from(frame[0]), f32::from(frame[1]));
+                    left += l;
+                    right += r;
+                } else {
+                    sound.active = false;
+                }
+            }
+
+            let to_output = |sample: f32| {
+                let sample = sample / 32768.0;
+                if sample.abs() > 1.0 {
+                    sample.signum()
+                } else {
+                    sample
+                }
+            };
+            let (left, right) = (to_output(left), to_output(right));
+            if num_output_channels == 1 {
+                output_frame[0] = (left + right) / 2.0;
+            } else {
+                output_frame[0] = left;
+                output_frame[1] = right;
+                for sample in &mut output_frame[2..] {
+                    *sample = 0.0;
+                }
+            }
+        }
+
+        // Remove all dead sounds.
+        sound_instances.retain(|_, sound| sound.active);
+    }
+
+    pub fn register_sound(&mut self, swf_sound: &swf::Sound) -> Result<SoundHandle, Error> {
+        if swf_sound.format.compression == AudioCompression::Speex {
+            return Err("Speex sounds are not supported".into());
+        }
+
+        // Slice off latency seek for MP3 data.
+        let (skip_sample_frames, data) = if swf_sound.format.compression == AudioCompression::Mp3 {
+            if swf_sound.data.len() < 2 {
+                return Err("MP3 sound is missing latency seek".into());
+            }
+            let skip_sample_frames =
+                u16::from(swf_sound.data[0]) | (u16::from(swf_sound.data[1]) << 8);
+            (skip_sample_frames, &swf_sound.data[2..])
+        } else {
+            (0, &swf_sound.data[..])
+        };
+
+        // Nellymoser 8kHz/16kHz sounds ignore the sample rate in the format.
+        let mut format = swf_sound.format.clone();
+        format.sample_rate = decoders::decoded_sample_rate(&format);
+
+        let sound = Sound {
+            format,
+            data: Arc::new(data.to_vec()),
+            num_sample_frames: swf_sound.num_samples,
+            skip_sample_frames,
+        };
+        Ok(self.sounds.insert(sound))
+    }
+
+    pub fn start_sound(
+        &mut self,
+        sound_handle: SoundHandle,
+        settings: &swf::SoundInfo,
+    ) -> SoundInstanceHandle {
+        let sound = &self.sounds[sound_handle];
+        let data = Cursor::new(VecAsRef(Arc::clone(&sound.data)));
+        // Create a signal that decodes and resamples the sound.
+        let signal: Signal = if sound.skip_sample_frames == 0
+            && settings.in_sample.is_none()
+            && settings.out_sample.is_none()
+            && settings.num_loops <= 1
+            && settings.envelope.is_none()
+        {
+            // For simple event sounds, just use the same signal as streams.
+            let decoder = decoders::make_decoder(&sound.format, data);
+            self.make_resampler(&sound.format, decoder)
+        } else {
+            // For event sounds with envelopes/other properties, wrap it in `EventSoundStream`.
+            let decoder = Self::make_seekable_decoder(&sound.format, data);
+            let stream = EventSoundStream::new_with_settings(
+                decoder,
+                settings,
+                sound.num_sample_frames,
+                sound.skip_sample_frames,
+            );
+            self.make_resampler(&sound.format, stream)
+        };
+
+        // Note that start/end values are in 44.1kHZ samples regardless of the sound's sample rate.
+        let start_position = f64::from(settings.in_sample.unwrap_or(0)) / 44.1;
+        let loop_duration = if settings.num_loops > 1 {
+            let end_position = if let Some(out_sample) = settings.out_sample {
+                f64::from(out_sample) / 44.1
+            } else {
+                f64::from(sound.num_sample_frames) * 1000.0 / f64::from(sound.format.sample_rate)
+            };
+            Some(end_position - start_position)
+        } else {
+            None
+        };
+
+        // Add sound instance to active list.
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.insert(SoundInstance {
+            handle: Some(sound_handle),
+            signal,
+            active: true,
+            transform: Default::default(),
+            num_frames_played: 0,
+            start_position,
+            loop_duration,
+        })
+    }
+
+    pub fn start_stream(
+        &mut self,
+        _clip_id: swf::CharacterId,
+        _clip_frame: u16,
+        clip_data: SwfSlice,
+        stream_info: &swf::SoundStreamHead,
+    ) -> AudioStreamHandle {
+        let format = &stream_info.stream_format;
+
+        // The audio data for stream sounds is distributed among the frames of a
+        // movie clip. The stream tag reader will parse through the SWF and
+        // feed the decoder audio data on the fly.
+        // TODO: Use actual SWF version here (would only matter for SWF <3...)
+        let decoder = decoders::make_stream_decoder(format, clip_data, 8);
+        let signal = self.make_resampler(format, decoder);
+
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.insert(SoundInstance {
+            handle: None,
+            signal,
+            active: true,
+            transform: Default::default(),
+            num_frames_played: 0,
+            start_position: 0.0,
+            loop_duration: None,
+        })
+    }
+
+    pub fn stop_sound(&mut self, sound: SoundInstanceHandle) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.remove(sound);
+    }
+
+    pub fn stop_stream(&mut self, stream: AudioStreamHandle) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.remove(stream);
+    }
+
+    pub fn stop_all_sounds(&mut self) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances.clear();
+    }
+
+    pub fn stop_sounds_with_handle(&mut self, handle: SoundHandle) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        let handle = Some(handle);
+        sound_instances.retain(|_, instance| instance.handle != handle);
+    }
+
+    pub fn is_sound_playing_with_handle(&mut self, handle: SoundHandle) -> bool {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        let handle = Some(handle);
+        sound_instances
+            .iter()
+            .any(|(_, instance)| instance.handle == handle && instance.active)
+    }
+
+    pub fn is_sound_playing(&mut self, instance: SoundInstanceHandle) -> bool {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        sound_instances
+            .get(instance)
+            .map(|instance| instance.active)
+            .unwrap_or(false)
+    }
+
+    pub fn is_stream_playing(&mut self, stream: AudioStreamHandle) -> bool {
+        self.is_sound_playing(stream)
+    }
+
+    pub fn get_sound_position(&self, instance: SoundInstanceHandle) -> Option<u32> {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        let instance = sound_instances.get(instance).filter(|i| i.active)?;
+        let mut position =
+            instance.num_frames_played as f64 * 1000.0 / f64::from(self.output_sample_rate);
+        // The position restarts at the beginning of each loop.
+        if let Some(loop_duration) = instance.loop_duration.filter(|&d| d > 0.0) {
+            position %= loop_duration;
+        }
+        Some((instance.start_position + position) as u32)
+    }
+
+    pub fn get_stream_position(&self, stream: AudioStreamHandle) -> Option<u32> {
+        let sound_instances = self.sound_instances.lock().unwrap();
+        let instance = sound_instances.get(stream).filter(|i| i.active)?;
+        // The last `output_latency` frames have been mixed, but not heard yet.
+        let num_frames_heard = instance
+            .num_frames_played
+            .saturating_sub(self.output_latency.into());
+        Some((num_frames_heard as f64 * 1000.0 / f64::from(self.output_sample_rate)) as u32)
+    }
+
+    pub fn get_sound_duration(&self, sound: SoundHandle) -> Option<u32> {
+        if let Some(sound) = self.sounds.get(sound) {
+            // AS duration does not subtract skip_sample_frames.
+            let num_sample_frames = u64::from(sound.num_sample_frames);
+            let ms = num_sample_frames * 1000 / u64::from(sound.format.sample_rate);
+            Some(ms as u32)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_sound_transform(
+        &mut self,
+        instance: SoundInstanceHandle,
+        transform: SoundTransform,
+    ) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        if let Some(instance) = sound_instances.get_mut(instance) {
+            instance.transform = transform;
+        }
+    }
+
+    pub fn set_stream_transform(&mut self, stream: AudioStreamHandle, transform: SoundTransform) {
+        self.set_sound_transform(stream, transform);
+    }
+
+    /// Instantiate a seekable decoder for the compression that the sound data uses.
+    fn make_seekable_decoder(
+        format: &swf::SoundFormat,
+        data: Cursor<VecAsRef>,
+    ) -> Box<dyn Send + SeekableDecoder> {
+        match format.compression {
+            AudioCompression::Uncompressed | AudioCompression::UncompressedUnknownEndian => {
+                Box::new(PcmDecoder::new(
+                    data,
+                    format.is_stereo,
+                    format.sample_rate,
+                    format.is_16_bit,
+                ))
+            }
+            AudioCompression::Adpcm => Box::new(AdpcmDecoder::new(
+                data,
+                format.is_stereo,
+                format.sample_rate,
+            )),
+            AudioCompression::Mp3 => Box::new(Mp3Decoder::new(
+                if format.is_stereo { 2 } else { 1 },
+                format.sample_rate.into(),
+                data,
+            )),
+            AudioCompression::Nellymoser
+            | AudioCompression::Nellymoser8Khz
+            | AudioCompression::Nellymoser16Khz => Box::new(NellymoserDecoder::new(
+                data,
+                decoders::decoded_sample_rate(format),
+            )),
+            AudioCompression::Speex => unreachable!("Speex sounds are rejected by register_sound"),
+        }
+    }
+
+    /// Resamples a decoded sound to the output sample rate.
+    fn make_resampler<I: 'static + Send + Iterator<Item = [i16; 2]>>(
+        &self,
+        format: &swf::SoundFormat,
+        input: I,
+    ) -> Signal {
+        let input_sample_rate = decoders::decoded_sample_rate(format);
+        if u32::from(input_sample_rate) == self.output_sample_rate {
+            Box::new(input)
+        } else {
+            Box::new(Resampler::new(
+                input,
+                input_sample_rate.into(),
+                self.output_sample_rate,
+            ))
+        }
+    }
+}
+
+/// A handle to an `AudioMixer` that can mix its audio from another thread.
+#[derive(Clone)]
+pub struct AudioMixerProxy {
+    sound_instances: Arc<Mutex<Arena<SoundInstance>>>,
+    num_output_channels: u8,
+}
+
+impl AudioMixerProxy {
+    /// Fills `output_buffer` with interleaved sample frames, advancing all playing sounds.
+    pub fn mix(&self, output_buffer: &mut [f32]) {
+        let mut sound_instances = self.sound_instances.lock().unwrap();
+        AudioMixer::mix_audio(
+            &mut sound_instances,
+            self.num_output_channels,
+            output_buffer,
+        );
+    }
+}
+
+/// Implements the methods of `AudioBackend` by forwarding them to an `AudioMixer` field.
+///
+/// Used inside of an `impl AudioBackend` block:
+/// ```ignore
+/// impl AudioBackend for MyAudioBackend {
+///     impl_audio_mixer_backend!(mixer);
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_audio_mixer_backend {
+    ($mixer:ident) => {
+        fn register_sound(
+            &mut self,
+            swf_sound: &$crate::backend::audio::swf::Sound,
+        ) -> Result<$crate::backend::audio::SoundHandle, Box<dyn std::error::Error>> {
+            self.$mixer.register_sound(swf_sound)
+        }
+
+        fn start_sound(
+            &mut self,
+            sound: $crate::backend::audio::SoundHandle,
+            settings: &$crate::backend::audio::swf::SoundInfo,
+        ) -> $crate::backend::audio::SoundInstanceHandle {
+            self.$mixer.start_sound(sound, settings)
+        }
+
+        fn start_stream(
+            &mut self,
+            clip_id: $crate::backend::audio::swf::CharacterId,
+            clip_frame: u16,
+            clip_data: $crate::tag_utils::SwfSlice,
+            stream_info: &$crate::backend::audio::swf::SoundStreamHead,
+        ) -> $crate::backend::audio::AudioStreamHandle {
+            self.$mixer
+                .start_stream(clip_id, clip_frame, clip_data, stream_info)
+        }
+
+        fn stop_sound(&mut self, sound: $crate::backend::audio::SoundInstanceHandle) {
+            self.$mixer.stop_sound(sound)
+        }
+
+        fn stop_stream(&mut self, stream: $crate::backend::audio::AudioStreamHandle) {
+            self.$mixer.stop_stream(stream)
+        }
+
+        fn stop_all_sounds(&mut self) {
+            self.$mixer.stop_all_sounds()
+        }
+
+        fn stop_sounds_with_handle(&mut self, handle: $crate::backend::audio::SoundHandle) {
+            self.$mixer.stop_sounds_with_handle(handle)
+        }
+
+        fn is_sound_playing_with_handle(
+            &mut self,
+            handle: $crate::backend::audio::SoundHandle,
+        ) -> bool {
+            self.$mixer.is_sound_playing_with_handle(handle)
+        }
+
+        fn is_sound_playing(
+            &mut self,
+            instance: $crate::backend::audio::SoundInstanceHandle,
+        ) -> bool {
+            self.$mixer.is_sound_playing(instance)
+        }
+
+        fn is_stream_playing(&mut self, stream: $crate::backend::audio::AudioStreamHandle) -> bool {
+            self.$mixer.is_stream_playing(stream)
+        }
+
+        fn get_sound_position(
+            &self,
+            instance: $crate::backend::audio::SoundInstanceHandle,
+        ) -> Option<u32> {
+            self.$mixer.get_sound_position(instance)
+        }
+
+        fn get_stream_position(
+            &self,
+            stream: $crate::backend::audio::AudioStreamHandle,
+        ) -> Option<u32> {
+            self.$mixer.get_stream_position(stream)
+        }
+
+        fn get_sound_duration(&self, sound: $crate::backend::audio::SoundHandle) -> Option<u32> {
+            self.$mixer.get_sound_duration(sound)
+        }
+
+        fn set_sound_transform(
+            &mut self,
+            instance: $crate::backend::audio::SoundInstanceHandle,
+            transform: $crate::backend::audio::SoundTransform,
+        ) {
+            self.$mixer.set_sound_transform(instance, transform)
+        }
+
+        fn set_stream_transform(
+            &mut self,
+            stream: $crate::backend::audio::AudioStreamHandle,
+            transform: $crate::backend::audio::SoundTransform,
+        ) {
+            self.$mixer.set_stream_transform(stream, transform)
+        }
+    };
+}
+
+/// A dummy wrapper struct to implement `AsRef<[u8]>` for `Arc<Vec<u8>`.
+/// Not having this trait causes problems when trying to use `Cursor<Vec<u8>>`.
+struct VecAsRef(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for VecAsRef {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Default for VecAsRef {
+    fn default() -> Self {
+        VecAsRef(Arc::new(vec![]))
+    }
+}
+
+/// A stream for event sound instances using sound settings (looping, start/end point, envelope).
+struct EventSoundStream {
+    decoder: Box<dyn SeekableDecoder + Send>,
+    num_loops: u16,
+    envelope: Option<Envelope>,
+    start_sample_frame: u32,
+    end_sample_frame: Option<u32>,
+    cur_sample_frame: u32,
+    is_exhausted: bool,
+}
+
+impl EventSoundStream {
+    fn new_with_settings(
+        decoder: Box<dyn SeekableDecoder + Send>,
+        settings: &swf::SoundInfo,
+        num_sample_frames: u32,
+        skip_sample_frames: u16,
+    ) -> Self {
+        let skip_sample_frames = u32::from(skip_sample_frames);
+        let sample_divisor = 44100.0 / f64::from(decoder.sample_rate());
+        let start_sample_frame = (f64::from(settings.in_sample.unwrap_or(0)) / sample_divisor)
+            as u32
+            + skip_sample_frames;
+        let end_sample_frame = settings
+            .out_sample
+            .map(|n| (f64::from(n) / sample_divisor) as u32)
+            .unwrap_or(num_sample_frames)
+            + skip_sample_frames;
+
+        let envelope = settings
+            .envelope
+            .as_ref()
+            .map(|envelope| Envelope::new(envelope.clone(), sample_divisor));
+
+        let mut stream = Self {
+            decoder,
+            num_loops: settings.num_loops,
+            envelope,
+            start_sample_frame,
+            end_sample_frame: Some(end_sample_frame),
+            cur_sample_frame: start_sample_frame,
+            is_exhausted: false,
+        };
+        stream.next_loop();
+        stream
+    }
+
+    /// Resets the decoder to the start point of the loop.
+    fn next_loop(&mut self) {
+        if self.num_loops > 0 {
+            self.num_loops -= 1;
+            self.decoder.seek_to_sample_frame(self.start_sample_frame);
+            self.cur_sample_frame = self.start_sample_frame;
+        } else {
+            self.is_exhausted = true;
+        }
+    }
+}
+
+impl Iterator for EventSoundStream {
+    type Item = [i16; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Loop the sound if necessary, and get the next frame.
+        let frame = loop {
+            if self.is_exhausted {
+                return None;
+            }
+            if let Some(frame) = self.decoder.next() {
+                self.cur_sample_frame += 1;
+                if let Some(end) = self.end_sample_frame {
+                    if self.cur_sample_frame > end {
+                        self.next_loop();
+                    }
+                }
+                break frame;
+            } else {
+                self.next_loop();
+            }
+        };
+
+        if let Some(envelope) = &mut self.envelope {
+            let [left_volume, right_volume] = envelope.next();
+            Some([
+                (f32::from(frame[0]) * left_volume) as i16,
+                (f32::from(frame[1]) * right_volume) as i16,
+            ])
+        } else {
+            Some(frame)
+        }
+    }
+}
+
+/// The sound envelope for an event sound.
+/// The sound gets multiplied by the envelope for volume/panning effects.
+struct Envelope {
+    /// Iterator through the envelope points specified in the SWF file.
+    envelope: std::vec::IntoIter<swf::SoundEnvelopePoint>,
+
+    /// The starting envelope point.
+    prev_point: swf::SoundEnvelopePoint,
+
+    /// The ending envelope point.
+    next_point: swf::SoundEnvelopePoint,
+
+    /// The current position, in 44.1kHz samples.
+    cur_sample: f64,
+
+    /// The number of 44.1kHz samples per sample frame of the sound.
+    samples_per_frame: f64,
+}
+
+impl Envelope {
+    fn new(envelope: swf::SoundEnvelope, samples_per_frame: f64) -> Self {
+        let mut envelope = envelope.into_iter();
+        let first_point = envelope.next().unwrap_or(swf::SoundEnvelopePoint {
+            sample: 0,
+            left_volume: 1.0,
+            right_volume: 1.0,
+        });
+        Self {
+            // The initial volume is the first point's volume.
+            prev_point: swf::SoundEnvelopePoint {
+                sample: 0,
+                left_volume: first_point.left_volume,
+                right_volume: first_point.right_volume,
+            },
+            next_point: first_point,
+            cur_sample: 0.0,
+            samples_per_frame,
+            envelope,
+        }
+    }
+
+    /// Returns the left and right volume for the next sample frame.
+    fn next(&mut self) -> [f32; 2] {
+        // Calculate interpolated volume.
+        let out = if self.prev_point.sample < self.next_point.sample {
+            let a = self.cur_sample - f64::from(self.prev_point.sample);
+            let b = f64::from(self.next_point.sample - self.prev_point.sample);
+            let lerp = (a / b) as f32;
+            [
+                self.prev_point.left_volume
+                    + (self.next_point.left_volume - self.prev_point.left_volume) * lerp,
+                self.prev_point.right_volume
+                    + (self.next_point.right_volume - self.prev_point.right_volume) * lerp,
+            ]
+        } else {
+            [self.next_point.left_volume, self.next_point.right_volume]
+        };
+
+        // Update envelope endpoints.
+        self.cur_sample += self.samples_per_frame;
+        while self.cur_sample > f64::from(self.next_point.sample) {
+            self.prev_point = self.next_point.clone();
+            self.next_point = self.envelope.next().unwrap_or(swf::SoundEnvelopePoint {
+                sample: std::u32::MAX,
+                left_volume: self.prev_point.left_volume,
+                right_volume: self.prev_point.right_volume,
+            });
+
+            if self.prev_point.sample > self.next_point.sample {
+                self.next_point.sample = self.prev_point.sample;
+                log::error!("Invalid sound envelope; sample indices are out of order");
+            }
+        }
+
+        out
+    }
+}
+
+/// Converts a stream of sample frames to a different sample rate using linear interpolation.
+struct Resampler<I> {
+    input: I,
+    prev_frame: [i16; 2],
+    next_frame: [i16; 2],
+
+    /// The position between `prev_frame` and `next_frame`, from 0 to 1.
+    position: f64,
+
+    /// The input sample frames per output sample frame.
+    step: f64,
+
+    /// Whether `next_frame` is the last frame of the input.
+    is_exhausted: bool,
+}
+
+impl<I: Iterator<Item = [i16; 2]>> Resampler<I> {
+    fn new(mut input: I, input_sample_rate: u32, output_sample_rate: u32) -> Self {
+        let prev_frame = input.next();
+        let next_frame = input.next();
+        let is_exhausted = next_frame.is_none();
+        let prev_frame = prev_frame.unwrap_or([0, 0]);
+        let next_frame = next_frame.unwrap_or(prev_frame);
+        Self {
+            input,
+            prev_frame,
+            next_frame,
+            position: 0.0,
+            step: f64::from(input_sample_rate) / f64::from(output_sample_rate),
+            is_exhausted,
+        }
+    }
+}
+
+impl<I: Iterator<Item = [i16; 2]>> Iterator for Resampler<I> {
+    type Item = [i16; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.position >= 1.0 {
+            if self.is_exhausted {
+                return None;
+            }
+            self.prev_frame = self.next_frame;
+            if let Some(frame) = self.input.next() {
+                self.next_frame = frame;
+            } else {
+                // Hold the last frame so that it is still output.
+                self.is_exhausted = true;
+            }
+            self.position -= 1.0;
+        }
+
+        let lerp = |a: i16, b: i16| {
+            (f64::from(a) + (f64::from(b) - f64::from(a)) * self.position).round() as i16
+        };
+        let frame = [
+            lerp(self.prev_frame[0], self.next_frame[0]),
+            lerp(self.prev_frame[1], self.next_frame[1]),
+        ];
+        self.position += self.step;
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16-bit mono PCM sound with the given samples.
+    fn pcm_sound(samples: &[i16], sample_rate: u16) -> swf::Sound {
+        swf::Sound {
+            id: 1,
+            format: swf::SoundFormat {
+                compression: AudioCompression::Uncompressed,
+                sample_rate,
+                is_16_bit: true,
+                is_stereo: false,
+            },
+            num_samples: samples.len() as u32,
+            data: samples
+                .iter()
+                .flat_map(|s| s.to_le_bytes().to_vec())
+                .collect(),
+        }
+    }
+
+    fn sound_info(num_loops: u16, envelope: Option<swf::SoundEnvelope>) -> swf::SoundInfo {
+        swf::SoundInfo {
+            event: swf::SoundEvent::Event,
+            in_sample: None,
+            out_sample: None,
+            num_loops,
+            envelope,
+        }
+    }
+
+    #[test]
+    fn mix_sounds() {
+        let mut mixer = AudioMixer::new(2, 44100);
+        let sound = mixer
+            .register_sound(&pcm_sound(&[8192, 8192, 8192], 44100))
+            .unwrap();
+        let instance = mixer.start_sound(sound, &sound_info(1, None));
+        mixer.start_sound(sound, &sound_info(1, None));
+        assert!(mixer.is_sound_playing(instance));
+
+        let mut output = [0.0; 8];
+        mixer.proxy().mix(&mut output);
+        assert_eq!(output, [0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.0, 0.0]);
+        assert!(!mixer.is_sound_playing(instance));
+        assert!(!mixer.is_sound_playing_with_handle(sound));
+    }
+
+    #[test]
+    fn mix_loops_and_transform() {
+        let mut mixer = AudioMixer::new(2, 44100);
+        let sound = mixer
+            .register_sound(&pcm_sound(&[16384, -16384], 44100))
+            .unwrap();
+        let instance = mixer.start_sound(sound, &sound_info(2, None));
+        let mut transform = SoundTransform::default();
+        transform.set_pan(-100);
+        mixer.set_sound_transform(instance, transform);
+
+        let mut output = [0.0; 10];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.5, 0.0, -0.5, 0.0, 0.5, 0.0, -0.5, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn mix_envelope() {
+        let mut mixer = AudioMixer::new(1, 44100);
+        let sound = mixer
+            .register_sound(&pcm_sound(&[16384; 5], 44100))
+            .unwrap();
+        let envelope = vec![
+            swf::SoundEnvelopePoint {
+                sample: 0,
+                left_volume: 0.0,
+                right_volume: 0.0,
+            },
+            swf::SoundEnvelopePoint {
+                sample: 4,
+                left_volume: 1.0,
+                right_volume: 1.0,
+            },
+        ];
+        mixer.start_sound(sound, &sound_info(1, Some(envelope)));
+
+        let mut output = [0.0; 5];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.0, 0.125, 0.25, 0.375, 0.5]);
+    }
+
+    #[test]
+    fn resample_sound() {
+        let mut mixer = AudioMixer::new(1, 44100);
+        let sound = mixer
+            .register_sound(&pcm_sound(&[0, 4096, 8192], 22050))
+            .unwrap();
+        let instance = mixer.start_sound(sound, &sound_info(1, None));
+        assert_eq!(mixer.get_sound_duration(sound), Some(0));
+
+        let mut output = [0.0; 2];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.0, 0.0625]);
+        assert_eq!(mixer.get_sound_position(instance), Some(0));
+
+        // The last input frame lasts for one input sample period.
+        let mut output = [0.0; 5];
+        mixer.mix(&mut output);
+        assert_eq!(output, [0.125, 0.1875, 0.25, 0.25, 0.0]);
+        assert_eq!(mixer.get_sound_position(instance), None);
+    }
+
+    #[test]
+    fn stream_position_latency() {
+        let mut mixer = AudioMixer::new(1, 44100);
+        mixer.set_output_latency(441);
+
+        // A `SoundStreamBlock` tag with 20ms of silence.
+        let block_len = 2 * 882;
+        let mut data = ((::swf::TagCode::SoundStreamBlock as u16) << 6 | 0x3f)
+            .to_le_bytes()
+            .to_vec();
+        data.extend_from_slice(&(block_len as u32).to_le_bytes());
+        data.resize(data.len() + block_len, 0);
+        let format = pcm_sound(&[], 44100).format;
+        let stream_info = swf::SoundStreamHead {
+            stream_format: format.clone(),
+            playback_format: format,
+            num_samples_per_block: 882,
+            latency_seek: 0,
+        };
+        let end = data.len();
+        let clip_data = SwfSlice {
+            data: Arc::new(data.into()),
+            start: 0,
+            end,
+        };
+        let stream = mixer.start_stream(1, 1, clip_data, &stream_info);
+        assert_eq!(mixer.get_stream_position(stream), Some(0));
+
+        // The first 10ms are still in the output buffer.
+        let mut output = [0.0; 441];
+        mixer.mix(&mut output);
+        assert_eq!(mixer.get_stream_position(stream), Some(0));
+
+        mixer.mix(&mut output);
+        assert_eq!(mixer.get_stream_position(stream), Some(10));
+    }
+}
@@ -0,0 +1,112 @@
+pub use crate::font::opentype::{read_font_faces, FontFace};
+
+/// Provides device fonts, which are the fonts installed on the system.
+///
+/// Text fields that don't embed their glyphs refer to a device font by name,
+/// such as "Arial" or one of the generic names `_sans`, `_serif` and `_typewriter`.
+pub trait FontBackend {
+    /// Returns the data of the installed font with the given family name and style,
+    /// or `None` if no font with this family is installed.
+    ///
+    /// The data must be a TrueType or OpenType font, or a font collection along with the
+    /// index of the font in the collection. The index is 0 for other fonts. Family names are
+    /// compared case-insensitively. If the family doesn't have the exact style, the closest
+    /// style of the same family may be returned.
+    fn load_font(&mut self, family: &str, is_bold: bool, is_italic: bool)
+        -> Option<(Vec<u8>, u32)>;
+}
+
+/// Font backend for platforms without access to the installed fonts.
+/// Text always uses the built-in device font.
+pub struct NullFontBackend;
+
+impl NullFontBackend {
+    pub fn new() -> Self {
+        NullFontBackend
+    }
+}
+
+impl Default for NullFontBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FontBackend for NullFontBackend {
+    fn load_font(
+        &mut self,
+        _family: &str,
+        _is_bold: bool,
+        _is_italic: bool,
+    ) -> Option<(Vec<u8>, u32)> {
+        None
+    }
+}
+
+/// Returns the font families to try for a device font name, in order of preference.
+///
+/// The generic device font names map to common fonts on each platform,
+/// and common Windows fonts map to their metric-compatible open source equivalents.
+pub fn device_font_families(name: &str) -> Vec<&str> {
+    const SANS: &[&str] = &[
+        "Arial",
+        "Helvetica",
+        "Liberation Sans",
+        "Arimo",
+        "DejaVu Sans",
+        "Noto Sans",
+    ];
+    const SERIF: &[&str] = &[
+        "Times New Roman",
+        "Times",
+        "Liberation Serif",
+        "Tinos",
+        "DejaVu Serif",
+        "Noto Serif",
+    ];
+    const TYPEWRITER: &[&str] = &[
+        "Courier New",
+        "Courier",
+        "Liberation Mono",
+        "Cousine",
+        "DejaVu Sans Mono",
+        "Noto Mono",
+    ];
+
+    // The Japanese versions of Flash use localized names for the generic fonts.
+    match name {
+        "_sans" | "_ゴシック" => SANS.to_vec(),
+        "_serif" | "_明朝" => SERIF.to_vec(),
+        "_typewriter" | "_等幅" => TYPEWRITER.to_vec(),
+        _ => {
+            let substitutes = if name.eq_ignore_ascii_case("Arial") {
+                &SANS[2..4]
+            } else if name.eq_ignore_ascii_case("Times New Roman") {
+                &SERIF[2..4]
+            } else if name.eq_ignore_ascii_case("Courier New") {
+                &TYPEWRITER[2..4]
+            } else {
+                &[]
+            };
+            let mut families = vec![name];
+            families.extend_from_slice(substitutes);
+            families
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_device_font_families() {
+        assert_eq!(device_font_families("_sans")[0], "Arial");
+        assert_eq!(device_font_families("_等幅")[0], "Courier New");
+        assert_eq!(
+            device_font_families("arial"),
+            vec!["arial", "Liberation Sans", "Arimo"]
+        );
+        assert_eq!(device_font_families("Verdana"), vec!["Verdana"]);
+    }
+}
@@ -8,14 +8,27 @@ pub trait InputBackend {
     fn hide_mouse(&mut self);
 
     fn show_mouse(&mut self);
+
+    /// Returns the text currently on the clipboard.
+    /// Used when pasting into an editable text field.
+    fn clipboard_content(&mut self) -> String;
+
+    /// Places the given text on the clipboard.
+    /// Used when copying or cutting from a selectable text field.
+    fn set_clipboard_content(&mut self, content: String);
 }
 
 /// Input backend that does nothing
-pub struct NullInputBackend {}
+pub struct NullInputBackend {
+    /// A clipboard local to this backend, so that copy and paste work within a movie.
+    clipboard: String,
+}
 
 impl NullInputBackend {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            clipboard: String::new(),
+        }
     }
 }
 
@@ -31,6 +44,14 @@ impl InputBackend for NullInputBackend {
     fn hide_mouse(&mut self) {}
 
     fn show_mouse(&mut self) {}
+
+    fn clipboard_content(&mut self) -> String {
+        self.clipboard.clone()
+    }
+
+    fn set_clipboard_content(&mut self, content: String) {
+        self.clipboard = content;
+    }
 }
 
 impl Default for NullInputBackend {
@@ -1,8 +1,11 @@
 //! Browser-related platform functions
 
+use generational_arena::{Arena, Index};
 use std::collections::HashMap;
 use swf::avm1::types::SendVarsMethod;
 
+pub type FetchHandle = Index;
+
 /// Enumerates all possible navigation methods.
 pub enum NavigationMethod {
     /// Indicates that navigation should generate a GET request.
@@ -23,6 +26,22 @@ impl NavigationMethod {
     }
 }
 
+/// The state of a resource requested with `NavigatorBackend::fetch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchStatus {
+    /// The resource is still loading.
+    Loading {
+        bytes_loaded: usize,
+        bytes_total: Option<usize>,
+    },
+
+    /// The resource finished loading.
+    Complete(Vec<u8>),
+
+    /// The resource could not be loaded.
+    Failed,
+}
+
 /// A backend interacting with a browser environment.
 pub trait NavigatorBackend {
     /// Cause a browser navigation to a given URL.
@@ -53,14 +72,32 @@ pub trait NavigatorBackend {
         window: Option<String>,
         vars_method: Option<(NavigationMethod, HashMap<String, String>)>,
     );
+
+    /// Begins loading the resource at the given URL, such as an external MP3
+    /// requested by `Sound.loadSound`.
+    ///
+    /// Loading happens in the background; the player calls `poll_fetch` each
+    /// frame to report progress and receive the data.
+    fn fetch(&mut self, url: String) -> FetchHandle;
+
+    /// Returns the state of a resource requested with `fetch`.
+    ///
+    /// Once `Complete` or `Failed` has been returned, the request is finished
+    /// and the handle is no longer valid.
+    fn poll_fetch(&mut self, handle: FetchHandle) -> FetchStatus;
 }
 
 /// A null implementation for platforms that do not live in a web browser.
-pub struct NullNavigatorBackend {}
+/// All fetches fail.
+pub struct NullNavigatorBackend {
+    fetches: Arena<()>,
+}
 
 impl NullNavigatorBackend {
     pub fn new() -> Self {
-        NullNavigatorBackend {}
+        NullNavigatorBackend {
+            fetches: Arena::new(),
+        }
     }
 }
 
@@ -78,4 +115,13 @@ impl NavigatorBackend for NullNavigatorBackend {
         _vars_method: Option<(NavigationMethod, HashMap<String, String>)>,
     ) {
     }
+
+    fn fetch(&mut self, _url: String) -> FetchHandle {
+        self.fetches.insert(())
+    }
+
+    fn poll_fetch(&mut self, handle: FetchHandle) -> FetchStatus {
+        self.fetches.remove(handle);
+        FetchStatus::Failed
+    }
 }
@@ -2,6 +2,14 @@ pub use crate::{transform::Transform, Color};
 use std::io::Read;
 pub use swf;
 
+pub mod blend;
+pub mod filters;
+pub mod software;
+
+pub use software::SoftwareRenderBackend;
+
+type Error = Box<dyn std::error::Error>;
+
 pub trait RenderBackend {
     fn set_viewport_dimensions(&mut self, width: u32, height: u32);
     fn register_shape(&mut self, shape: &swf::Shape) -> ShapeHandle;
@@ -21,9 +29,33 @@ pub trait RenderBackend {
     ) -> BitmapInfo;
     fn register_bitmap_png(&mut self, swf_tag: &swf::DefineBitsLossless) -> BitmapInfo;
 
+    /// Registers a bitmap from decoded RGBA pixels, such as a frame of video.
+    /// The pixels are not premultiplied by alpha.
+    fn register_bitmap_raw(
+        &mut self,
+        id: swf::CharacterId,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> BitmapInfo;
+
+    /// Replaces the pixels of a bitmap registered by `register_bitmap_raw`.
+    /// The size of the bitmap may change.
+    fn update_texture(
+        &mut self,
+        bitmap: BitmapHandle,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> Result<BitmapHandle, Error>;
+
+    /// Releases the pixels of a bitmap registered by `register_bitmap_raw`.
+    /// The handle must not be used afterwards.
+    fn unregister_bitmap(&mut self, bitmap: BitmapHandle);
+
     fn begin_frame(&mut self);
     fn clear(&mut self, color: Color);
-    fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform);
+    fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform, smoothing: bool);
     fn render_shape(&mut self, shape: ShapeHandle, transform: &Transform);
     fn end_frame(&mut self);
     fn draw_pause_overlay(&mut self);
@@ -31,6 +63,15 @@ pub trait RenderBackend {
     fn push_mask(&mut self);
     fn activate_mask(&mut self);
     fn pop_mask(&mut self);
+
+    /// Starts rendering into a new transparent offscreen layer, until the matching `pop_layer`.
+    /// The layer is composited with the given blend mode. It is known up front so that backends
+    /// without offscreen rendering can approximate it while drawing.
+    fn push_layer(&mut self, blend_mode: swf::BlendMode);
+
+    /// Applies the filters to the current layer and composites it onto the layer below.
+    /// The sizes of the filters are in stage pixels, and are multiplied by `scale`.
+    fn pop_layer(&mut self, filters: &[swf::Filter], scale: f32);
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -114,16 +155,41 @@ impl RenderBackend for NullRenderer {
             height: 0,
         }
     }
+    fn register_bitmap_raw(
+        &mut self,
+        _id: swf::CharacterId,
+        width: u32,
+        height: u32,
+        _rgba: Vec<u8>,
+    ) -> BitmapInfo {
+        BitmapInfo {
+            handle: BitmapHandle(0),
+            width: width as u16,
+            height: height as u16,
+        }
+    }
+    fn update_texture(
+        &mut self,
+        bitmap: BitmapHandle,
+        _width: u32,
+        _height: u32,
+        _rgba: Vec<u8>,
+    ) -> Result<BitmapHandle, Error> {
+        Ok(bitmap)
+    }
+    fn unregister_bitmap(&mut self, _bitmap: BitmapHandle) {}
     fn begin_frame(&mut self) {}
     fn end_frame(&mut self) {}
     fn clear(&mut self, _color: Color) {}
-    fn render_bitmap(&mut self, _bitmap: BitmapHandle, _transform: &Transform) {}
+    fn render_bitmap(&mut self, _bitmap: BitmapHandle, _transform: &Transform, _smoothing: bool) {}
     fn render_shape(&mut self, _shape: ShapeHandle, _transform: &Transform) {}
     fn draw_pause_overlay(&mut self) {}
     fn draw_letterbox(&mut self, _letterbox: Letterbox) {}
     fn push_mask(&mut self) {}
     fn activate_mask(&mut self) {}
     fn pop_mask(&mut self) {}
+    fn push_layer(&mut self, _blend_mode: swf::BlendMode) {}
+    fn pop_layer(&mut self, _filters: &[swf::Filter], _scale: f32) {}
 }
 
 pub fn glue_swf_jpeg_to_tables(jpeg_tables: &[u8], jpeg_data: &[u8]) -> Vec<u8> {
@@ -342,3 +408,13 @@ pub fn unmultiply_alpha_rgba(rgba: &mut [u8]) {
         }
     })
 }
+
+/// Converts RGBA with straight alpha to premultiplied alpha.
+pub fn premultiply_alpha_rgba(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let alpha = u16::from(pixel[3]);
+        for channel in &mut pixel[..3] {
+            *channel = ((u16::from(*channel) * alpha + 127) / 255) as u8;
+        }
+    }
+}
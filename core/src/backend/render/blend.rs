@@ -0,0 +1,178 @@
+//! Reference implementations of the SWF blend modes.
+//!
+//! Colors are RGBA with premultiplied alpha, with each channel from 0.0 to 1.0.
+
+use swf::BlendMode;
+
+/// Composites the color of a layer onto the color beneath it.
+///
+/// The alpha and erase modes multiply the destination by the alpha, or the inverse alpha,
+/// of the source, knocking it out. Every other mode draws the source over the destination,
+/// mixing the colors where both are present.
+pub fn composite(blend_mode: BlendMode, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+    let (src_alpha, dst_alpha) = (src[3], dst[3]);
+    let blend: fn(f32, f32) -> f32 = match blend_mode {
+        BlendMode::Normal | BlendMode::Layer => {
+            return [
+                src[0] + dst[0] * (1.0 - src_alpha),
+                src[1] + dst[1] * (1.0 - src_alpha),
+                src[2] + dst[2] * (1.0 - src_alpha),
+                src_alpha + dst_alpha * (1.0 - src_alpha),
+            ];
+        }
+        BlendMode::Alpha => {
+            return [
+                dst[0] * src_alpha,
+                dst[1] * src_alpha,
+                dst[2] * src_alpha,
+                dst_alpha * src_alpha,
+            ];
+        }
+        BlendMode::Erase => {
+            return [
+                dst[0] * (1.0 - src_alpha),
+                dst[1] * (1.0 - src_alpha),
+                dst[2] * (1.0 - src_alpha),
+                dst_alpha * (1.0 - src_alpha),
+            ];
+        }
+        BlendMode::Multiply => |src, dst| src * dst,
+        BlendMode::Screen => screen,
+        BlendMode::Lighten => |src: f32, dst| src.max(dst),
+        BlendMode::Darken => |src: f32, dst| src.min(dst),
+        BlendMode::Difference => |src: f32, dst: f32| (src - dst).abs(),
+        BlendMode::Add => |src: f32, dst| (src + dst).min(1.0),
+        BlendMode::Subtract => |src: f32, dst: f32| (dst - src).max(0.0),
+        BlendMode::Invert => |_src, dst| 1.0 - dst,
+        BlendMode::Overlay => |src, dst| hard_light(dst, src),
+        BlendMode::HardLight => hard_light,
+    };
+
+    // Where only one of the colors is present, it is kept as is.
+    // Where both are, the colors are blended.
+    let both = src_alpha * dst_alpha;
+    let mut out = [0.0; 4];
+    for i in 0..3 {
+        let src_color = if src_alpha > 0.0 {
+            src[i] / src_alpha
+        } else {
+            0.0
+        };
+        let dst_color = if dst_alpha > 0.0 {
+            dst[i] / dst_alpha
+        } else {
+            0.0
+        };
+        out[i] = src[i] * (1.0 - dst_alpha)
+            + dst[i] * (1.0 - src_alpha)
+            + both * blend(src_color, dst_color);
+    }
+    out[3] = src_alpha + dst_alpha - both;
+    out
+}
+
+/// Composites a layer onto another of the same size, both in RGBA with premultiplied alpha.
+pub fn composite_rgba(blend_mode: BlendMode, src: &[u8], dst: &mut [u8]) {
+    for (src, dst) in src.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+        let to_f32 = |pixel: &[u8]| {
+            [
+                f32::from(pixel[0]) / 255.0,
+                f32::from(pixel[1]) / 255.0,
+                f32::from(pixel[2]) / 255.0,
+                f32::from(pixel[3]) / 255.0,
+            ]
+        };
+        let color = composite(blend_mode, to_f32(src), to_f32(dst));
+        for (dst, value) in dst.iter_mut().zip(&color) {
+            *dst = (value * 255.0).round().max(0.0).min(255.0) as u8;
+        }
+    }
+}
+
+fn screen(src: f32, dst: f32) -> f32 {
+    src + dst - src * dst
+}
+
+fn hard_light(src: f32, dst: f32) -> f32 {
+    if src <= 0.5 {
+        dst * 2.0 * src
+    } else {
+        screen(dst, 2.0 * src - 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const GRAY: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+    const CLEAR: [f32; 4] = [0.0; 4];
+
+    #[test]
+    fn separable_modes() {
+        assert_eq!(composite(BlendMode::Normal, RED, GRAY), RED);
+        assert_eq!(
+            composite(BlendMode::Multiply, RED, GRAY),
+            [0.5, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            composite(BlendMode::Screen, RED, GRAY),
+            [1.0, 0.5, 0.5, 1.0]
+        );
+        assert_eq!(
+            composite(BlendMode::Lighten, RED, GRAY),
+            [1.0, 0.5, 0.5, 1.0]
+        );
+        assert_eq!(
+            composite(BlendMode::Darken, RED, GRAY),
+            [0.5, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(composite(BlendMode::Difference, RED, GRAY), GRAY);
+        assert_eq!(composite(BlendMode::Add, RED, GRAY), [1.0, 0.5, 0.5, 1.0]);
+        assert_eq!(
+            composite(BlendMode::Subtract, RED, GRAY),
+            [0.0, 0.5, 0.5, 1.0]
+        );
+        assert_eq!(
+            composite(BlendMode::Invert, RED, [1.0, 0.0, 0.25, 1.0]),
+            [0.0, 1.0, 0.75, 1.0]
+        );
+        assert_eq!(
+            composite(BlendMode::HardLight, RED, GRAY),
+            [1.0, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            composite(BlendMode::Overlay, RED, GRAY),
+            [1.0, 0.0, 0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn transparent_colors() {
+        // Where only one of the colors is present, it is unchanged.
+        assert_eq!(composite(BlendMode::Multiply, RED, CLEAR), RED);
+        assert_eq!(composite(BlendMode::Difference, CLEAR, GRAY), GRAY);
+        let half_red = [0.5, 0.0, 0.0, 0.5];
+        assert_eq!(
+            composite(BlendMode::Multiply, half_red, GRAY),
+            [0.5, 0.25, 0.25, 1.0]
+        );
+    }
+
+    #[test]
+    fn knockout_modes() {
+        let half = [0.0, 0.0, 0.0, 0.5];
+        assert_eq!(
+            composite(BlendMode::Alpha, half, GRAY),
+            [0.25, 0.25, 0.25, 0.5]
+        );
+        assert_eq!(composite(BlendMode::Alpha, CLEAR, GRAY), CLEAR);
+        assert_eq!(composite(BlendMode::Erase, RED, GRAY), CLEAR);
+        assert_eq!(composite(BlendMode::Erase, CLEAR, GRAY), GRAY);
+
+        let mut dst = vec![128, 128, 128, 255, 255, 0, 0, 255];
+        composite_rgba(BlendMode::Erase, &[0, 0, 0, 255, 0, 0, 0, 0], &mut dst);
+        assert_eq!(dst, vec![0, 0, 0, 0, 255, 0, 0, 255]);
+    }
+}
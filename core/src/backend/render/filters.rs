@@ -0,0 +1,808 @@
+//! Reference implementations of the SWF bitmap filters.
+//!
+//! Filters run on an offscreen layer holding a rendered display object.
+//! Backends without a native implementation can read the layer back and filter it with these.
+
+use swf::{Color, Filter, GradientRecord};
+
+/// Applies filters in order to an image in RGBA with premultiplied alpha.
+/// Blur sizes and distances are in stage pixels, and are multiplied by `scale`.
+pub fn apply_filters(filters: &[Filter], width: u32, height: u32, rgba: &mut [u8], scale: f32) {
+    if filters.is_empty() {
+        return;
+    }
+    let mut image = Image::from_rgba(width as usize, height as usize, rgba);
+    for filter in filters {
+        image = apply_filter(filter, &image, scale);
+    }
+    image.write_rgba(rgba);
+}
+
+/// An image with premultiplied alpha, with each channel from 0.0 to 1.0.
+#[derive(Clone)]
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Image {
+    fn from_rgba(width: usize, height: usize, rgba: &[u8]) -> Self {
+        let pixels = rgba
+            .chunks_exact(4)
+            .map(|pixel| {
+                [
+                    f32::from(pixel[0]) / 255.0,
+                    f32::from(pixel[1]) / 255.0,
+                    f32::from(pixel[2]) / 255.0,
+                    f32::from(pixel[3]) / 255.0,
+                ]
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn write_rgba(&self, rgba: &mut [u8]) {
+        for (dst, src) in rgba.chunks_exact_mut(4).zip(&self.pixels) {
+            for (dst, &src) in dst.iter_mut().zip(src) {
+                *dst = (src * 255.0).round().max(0.0).min(255.0) as u8;
+            }
+        }
+    }
+
+    fn alpha(&self) -> Vec<f32> {
+        self.pixels.iter().map(|pixel| pixel[3]).collect()
+    }
+
+    fn with_pixels(&self, pixels: Vec<[f32; 4]>) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+}
+
+/// Where the effect of a shadow, glow or bevel is drawn, relative to the filtered object.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Placement {
+    /// Inside the object, on top of it.
+    Inner,
+    /// Outside the object, beneath it.
+    Outer,
+    /// Both inside and outside the object, on top of it.
+    Full,
+}
+
+impl Placement {
+    fn new(is_inner: bool, is_on_top: bool) -> Self {
+        if is_on_top {
+            Placement::Full
+        } else if is_inner {
+            Placement::Inner
+        } else {
+            Placement::Outer
+        }
+    }
+}
+
+fn apply_filter(filter: &Filter, image: &Image, scale: f32) -> Image {
+    match filter {
+        Filter::BlurFilter(blur) => {
+            let (blur_x, blur_y) = (blur.blur_x as f32 * scale, blur.blur_y as f32 * scale);
+            let mut channels: Vec<Vec<f32>> = (0..4)
+                .map(|i| image.pixels.iter().map(|pixel| pixel[i]).collect())
+                .collect();
+            for channel in &mut channels {
+                blur_plane(
+                    channel,
+                    image.width,
+                    image.height,
+                    blur_x,
+                    blur_y,
+                    blur.num_passes,
+                );
+            }
+            let pixels = (0..image.pixels.len())
+                .map(|i| {
+                    [
+                        channels[0][i],
+                        channels[1][i],
+                        channels[2][i],
+                        channels[3][i],
+                    ]
+                })
+                .collect();
+            image.with_pixels(pixels)
+        }
+
+        Filter::DropShadowFilter(shadow) => {
+            let placement = Placement::new(shadow.is_inner, false);
+            let coverage = shadow_coverage(
+                image,
+                shadow.blur_x as f32 * scale,
+                shadow.blur_y as f32 * scale,
+                offset(shadow.angle, shadow.distance, scale),
+                shadow.num_passes,
+            );
+            let color = premultiplied(&shadow.color);
+            let effect = glow_strength(&coverage, shadow.strength, placement)
+                .map(|value| scale_color(color, value))
+                .collect();
+            composite(
+                image,
+                &effect,
+                placement,
+                shadow.is_knockout,
+                !shadow.is_composite_source,
+            )
+        }
+
+        Filter::GlowFilter(glow) => {
+            let placement = Placement::new(glow.is_inner, false);
+            let coverage = shadow_coverage(
+                image,
+                glow.blur_x as f32 * scale,
+                glow.blur_y as f32 * scale,
+                (0.0, 0.0),
+                glow.num_passes,
+            );
+            let color = premultiplied(&glow.color);
+            let effect = glow_strength(&coverage, glow.strength, placement)
+                .map(|value| scale_color(color, value))
+                .collect();
+            composite(image, &effect, placement, glow.is_knockout, false)
+        }
+
+        Filter::GradientGlowFilter(glow) => {
+            let placement = Placement::new(glow.is_inner, glow.is_on_top);
+            let coverage = shadow_coverage(
+                image,
+                glow.blur_x as f32 * scale,
+                glow.blur_y as f32 * scale,
+                offset(glow.angle, glow.distance, scale),
+                glow.num_passes,
+            );
+            let colors = gradient_colors(&glow.colors);
+            let effect = glow_strength(&coverage, glow.strength, placement)
+                .map(|value| colors[(value * 255.0).round() as usize])
+                .collect();
+            composite(image, &effect, placement, glow.is_knockout, false)
+        }
+
+        Filter::BevelFilter(bevel) => {
+            let placement = Placement::new(bevel.is_inner, bevel.is_on_top);
+            let highlight = premultiplied(&bevel.highlight_color);
+            let shadow = premultiplied(&bevel.shadow_color);
+            let effect = bevel_strength(
+                image,
+                bevel.blur_x as f32 * scale,
+                bevel.blur_y as f32 * scale,
+                offset(bevel.angle, bevel.distance, scale),
+                bevel.strength,
+                bevel.num_passes,
+            )
+            .into_iter()
+            .map(|value| {
+                if value > 0.0 {
+                    scale_color(highlight, value)
+                } else {
+                    scale_color(shadow, -value)
+                }
+            })
+            .collect();
+            composite(image, &effect, placement, bevel.is_knockout, false)
+        }
+
+        Filter::GradientBevelFilter(bevel) => {
+            let placement = Placement::new(bevel.is_inner, bevel.is_on_top);
+            let colors = gradient_colors(&bevel.colors);
+            // The highlight is at the start of the gradient, and the shadow at the end.
+            let effect = bevel_strength(
+                image,
+                bevel.blur_x as f32 * scale,
+                bevel.blur_y as f32 * scale,
+                offset(bevel.angle, bevel.distance, scale),
+                bevel.strength,
+                bevel.num_passes,
+            )
+            .into_iter()
+            .map(|value| colors[((1.0 - value) * 127.5).round() as usize])
+            .collect();
+            composite(image, &effect, placement, bevel.is_knockout, false)
+        }
+
+        Filter::ColorMatrixFilter(color_matrix) => {
+            let m = &color_matrix.matrix;
+            let pixels = image
+                .pixels
+                .iter()
+                .map(|&pixel| {
+                    let [r, g, b, a] = unmultiplied(pixel);
+                    let row = |i: usize| {
+                        let value = m[i] * f64::from(r)
+                            + m[i + 1] * f64::from(g)
+                            + m[i + 2] * f64::from(b)
+                            + m[i + 3] * f64::from(a)
+                            + m[i + 4] / 255.0;
+                        (value as f32).max(0.0).min(1.0)
+                    };
+                    premultiply([row(0), row(5), row(10), row(15)])
+                })
+                .collect();
+            image.with_pixels(pixels)
+        }
+
+        Filter::ConvolutionFilter(convolution) => {
+            let (cols, rows) = (
+                usize::from(convolution.num_matrix_cols),
+                usize::from(convolution.num_matrix_rows),
+            );
+            if convolution.matrix.len() < cols * rows {
+                return image.clone();
+            }
+            let divisor = if convolution.divisor != 0.0 {
+                convolution.divisor as f32
+            } else {
+                1.0
+            };
+            let bias = convolution.bias as f32 / 255.0;
+            let default_color = unmultiplied(premultiplied(&convolution.default_color));
+            let (width, height) = (image.width as isize, image.height as isize);
+            let source = |x: isize, y: isize| {
+                if convolution.is_clamped {
+                    let x = x.max(0).min(width - 1);
+                    let y = y.max(0).min(height - 1);
+                    unmultiplied(image.pixels[(y * width + x) as usize])
+                } else if x < 0 || y < 0 || x >= width || y >= height {
+                    default_color
+                } else {
+                    unmultiplied(image.pixels[(y * width + x) as usize])
+                }
+            };
+
+            let mut pixels = Vec::with_capacity(image.pixels.len());
+            for y in 0..height {
+                for x in 0..width {
+                    let mut sum = [0.0; 4];
+                    for row in 0..rows {
+                        for col in 0..cols {
+                            let weight = convolution.matrix[row * cols + col] as f32;
+                            let sample = source(
+                                x + col as isize - (cols / 2) as isize,
+                                y + row as isize - (rows / 2) as isize,
+                            );
+                            for (sum, value) in sum.iter_mut().zip(&sample) {
+                                *sum += weight * value;
+                            }
+                        }
+                    }
+                    let mut color = [0.0; 4];
+                    for (color, sum) in color.iter_mut().zip(&sum) {
+                        *color = (sum / divisor + bias).max(0.0).min(1.0);
+                    }
+                    if convolution.is_preserve_alpha {
+                        color[3] = image.pixels[(y * width + x) as usize][3];
+                    }
+                    pixels.push(premultiply(color));
+                }
+            }
+            image.with_pixels(pixels)
+        }
+    }
+}
+
+/// Returns the offset of a shadow in device pixels.
+fn offset(angle: f64, distance: f64, scale: f32) -> (f32, f32) {
+    let distance = distance as f32 * scale;
+    let angle = angle as f32;
+    (angle.cos() * distance, angle.sin() * distance)
+}
+
+/// Returns the blurred alpha of the image, offset by `(dx, dy)`.
+fn shadow_coverage(
+    image: &Image,
+    blur_x: f32,
+    blur_y: f32,
+    (dx, dy): (f32, f32),
+    num_passes: u8,
+) -> Vec<f32> {
+    let mut alpha = shift_plane(&image.alpha(), image.width, image.height, dx, dy);
+    blur_plane(
+        &mut alpha,
+        image.width,
+        image.height,
+        blur_x,
+        blur_y,
+        num_passes,
+    );
+    alpha
+}
+
+/// Returns the strength of a glow or shadow from its coverage, from 0.0 to 1.0.
+/// Inner glows are strongest where the coverage of the object fades out.
+fn glow_strength<'a>(
+    coverage: &'a [f32],
+    strength: f32,
+    placement: Placement,
+) -> impl Iterator<Item = f32> + 'a {
+    coverage.iter().map(move |&coverage| {
+        let value = if placement == Placement::Inner {
+            1.0 - coverage
+        } else {
+            coverage
+        };
+        (value * strength).max(0.0).min(1.0)
+    })
+}
+
+/// Returns the strength of a bevel, from -1.0 for full shadow to 1.0 for full highlight.
+/// The highlight faces the light, which shines along `offset`.
+fn bevel_strength(
+    image: &Image,
+    blur_x: f32,
+    blur_y: f32,
+    (dx, dy): (f32, f32),
+    strength: f32,
+    num_passes: u8,
+) -> Vec<f32> {
+    let (width, height) = (image.width, image.height);
+    let mut alpha = image.alpha();
+    blur_plane(&mut alpha, width, height, blur_x, blur_y, num_passes);
+    let towards_shadow = shift_plane(&alpha, width, height, -dx, -dy);
+    let towards_highlight = shift_plane(&alpha, width, height, dx, dy);
+    towards_shadow
+        .iter()
+        .zip(&towards_highlight)
+        .map(|(shadow, highlight)| ((shadow - highlight) * strength).max(-1.0).min(1.0))
+        .collect()
+}
+
+/// Combines the effect of a shadow, glow or bevel with the filtered object.
+///
+/// A knockout effect is drawn without the object.
+/// `hide_object` draws the whole effect without the object, as with the `hideObject` flag of
+/// a drop shadow.
+fn composite(
+    image: &Image,
+    effect: &Vec<[f32; 4]>,
+    placement: Placement,
+    is_knockout: bool,
+    hide_object: bool,
+) -> Image {
+    let pixels = image
+        .pixels
+        .iter()
+        .zip(effect)
+        .map(|(&source, &effect)| {
+            let source_alpha = source[3];
+            match placement {
+                Placement::Inner => {
+                    let inside = scale_color(effect, source_alpha);
+                    if is_knockout || hide_object {
+                        inside
+                    } else {
+                        // The effect is drawn atop the object.
+                        let below = scale_color(source, 1.0 - effect[3]);
+                        [
+                            inside[0] + below[0],
+                            inside[1] + below[1],
+                            inside[2] + below[2],
+                            inside[3] + below[3],
+                        ]
+                    }
+                }
+                Placement::Outer => {
+                    if is_knockout {
+                        scale_color(effect, 1.0 - source_alpha)
+                    } else if hide_object {
+                        effect
+                    } else {
+                        over(source, effect)
+                    }
+                }
+                Placement::Full => {
+                    if is_knockout || hide_object {
+                        effect
+                    } else {
+                        over(effect, source)
+                    }
+                }
+            }
+        })
+        .collect();
+    image.with_pixels(pixels)
+}
+
+/// Blurs a single channel with `num_passes` box blurs of the given sizes.
+/// Pixels outside of the image are transparent.
+fn blur_plane(
+    plane: &mut [f32],
+    width: usize,
+    height: usize,
+    blur_x: f32,
+    blur_y: f32,
+    num_passes: u8,
+) {
+    let mut line = vec![];
+    for _ in 0..num_passes {
+        if blur_x > 1.0 {
+            for row in plane.chunks_exact_mut(width) {
+                line.clear();
+                line.extend_from_slice(row);
+                box_blur(&line, blur_x, |x, value| row[x] = value);
+            }
+        }
+        if blur_y > 1.0 {
+            for x in 0..width {
+                line.clear();
+                line.extend((0..height).map(|y| plane[y * width + x]));
+                box_blur(&line, blur_y, |y, value| plane[y * width + x] = value);
+            }
+        }
+    }
+}
+
+/// Averages each value over a window of `size` values centered on it.
+/// Values at the ends of the window are weighted by how much of them it covers.
+fn box_blur(values: &[f32], size: f32, mut output: impl FnMut(usize, f32)) {
+    let len = values.len();
+    let mut sums = Vec::with_capacity(len + 1);
+    sums.push(0.0);
+    let mut sum = 0.0;
+    for value in values {
+        sum += value;
+        sums.push(sum);
+    }
+    // The sum of the values from the start of the line to a fractional position.
+    let sum_to = |position: f32| {
+        let position = position.max(0.0).min(len as f32);
+        let i = position.floor() as usize;
+        if i < len {
+            sums[i] + values[i] * (position - i as f32)
+        } else {
+            sums[len]
+        }
+    };
+    let radius = size / 2.0;
+    for i in 0..len {
+        let center = i as f32 + 0.5;
+        output(
+            i,
+            (sum_to(center + radius) - sum_to(center - radius)) / size,
+        );
+    }
+}
+
+/// Moves a single channel by `(dx, dy)`, sampling between pixels for fractional offsets.
+fn shift_plane(plane: &[f32], width: usize, height: usize, dx: f32, dy: f32) -> Vec<f32> {
+    if dx == 0.0 && dy == 0.0 {
+        return plane.to_vec();
+    }
+    let sample = |x: isize, y: isize| {
+        if x < 0 || y < 0 || x >= width as isize || y >= height as isize {
+            0.0
+        } else {
+            plane[y as usize * width + x as usize]
+        }
+    };
+    let (x_offset, y_offset) = (dx.floor(), dy.floor());
+    let (fx, fy) = (dx - x_offset, dy - y_offset);
+    let (x_offset, y_offset) = (x_offset as isize, y_offset as isize);
+    let mut shifted = Vec::with_capacity(plane.len());
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let (sx, sy) = (x - x_offset, y - y_offset);
+            let top = sample(sx, sy) * (1.0 - fx) + sample(sx - 1, sy) * fx;
+            let bottom = sample(sx, sy - 1) * (1.0 - fx) + sample(sx - 1, sy - 1) * fx;
+            shifted.push(top * (1.0 - fy) + bottom * fy);
+        }
+    }
+    shifted
+}
+
+/// Returns the 256 premultiplied colors of a gradient.
+fn gradient_colors(records: &[GradientRecord]) -> Vec<[f32; 4]> {
+    let mut colors = Vec::with_capacity(256);
+    for i in 0..256 {
+        let ratio = i as u8;
+        let next = records.iter().position(|record| record.ratio >= ratio);
+        let color = match next {
+            Some(0) => straight(&records[0].color),
+            Some(next) => {
+                let (a, b) = (&records[next - 1], &records[next]);
+                let t = f32::from(ratio - a.ratio) / f32::from(b.ratio - a.ratio);
+                let (a, b) = (straight(&a.color), straight(&b.color));
+                [
+                    a[0] + (b[0] - a[0]) * t,
+                    a[1] + (b[1] - a[1]) * t,
+                    a[2] + (b[2] - a[2]) * t,
+                    a[3] + (b[3] - a[3]) * t,
+                ]
+            }
+            None => records
+                .last()
+                .map(|record| straight(&record.color))
+                .unwrap_or([0.0; 4]),
+        };
+        colors.push(premultiply(color));
+    }
+    colors
+}
+
+/// Composites `top` over `bottom`.
+fn over(top: [f32; 4], bottom: [f32; 4]) -> [f32; 4] {
+    let inverse_alpha = 1.0 - top[3];
+    [
+        top[0] + bottom[0] * inverse_alpha,
+        top[1] + bottom[1] * inverse_alpha,
+        top[2] + bottom[2] * inverse_alpha,
+        top[3] + bottom[3] * inverse_alpha,
+    ]
+}
+
+fn scale_color(color: [f32; 4], amount: f32) -> [f32; 4] {
+    [
+        color[0] * amount,
+        color[1] * amount,
+        color[2] * amount,
+        color[3] * amount,
+    ]
+}
+
+fn straight(color: &Color) -> [f32; 4] {
+    [
+        f32::from(color.r) / 255.0,
+        f32::from(color.g) / 255.0,
+        f32::from(color.b) / 255.0,
+        f32::from(color.a) / 255.0,
+    ]
+}
+
+fn premultiplied(color: &Color) -> [f32; 4] {
+    premultiply(straight(color))
+}
+
+fn premultiply(color: [f32; 4]) -> [f32; 4] {
+    [
+        color[0] * color[3],
+        color[1] * color[3],
+        color[2] * color[3],
+        color[3],
+    ]
+}
+
+fn unmultiplied(color: [f32; 4]) -> [f32; 4] {
+    if color[3] > 0.0 {
+        [
+            color[0] / color[3],
+            color[1] / color[3],
+            color[2] / color[3],
+            color[3],
+        ]
+    } else {
+        [0.0; 4]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swf::{
+        BevelFilter, BlurFilter, ColorMatrixFilter, ConvolutionFilter, DropShadowFilter, GlowFilter,
+    };
+
+    const RED: Color = Color {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+
+    const BLACK: Color = Color {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+
+    const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+        a: 255,
+    };
+
+    /// A 20x20 transparent image with an opaque blue square from (5, 5) to (15, 15).
+    fn square() -> Vec<u8> {
+        let mut rgba = vec![0; 20 * 20 * 4];
+        for y in 5..15 {
+            for x in 5..15 {
+                rgba[(y * 20 + x) * 4..(y * 20 + x) * 4 + 4].copy_from_slice(&[0, 0, 255, 255]);
+            }
+        }
+        rgba
+    }
+
+    fn pixel(rgba: &[u8], x: usize, y: usize) -> [u8; 4] {
+        let i = (y * 20 + x) * 4;
+        [rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]]
+    }
+
+    fn glow(is_inner: bool, is_knockout: bool) -> Filter {
+        Filter::GlowFilter(Box::new(GlowFilter {
+            color: RED,
+            blur_x: 4.0,
+            blur_y: 4.0,
+            strength: 1.0,
+            is_inner,
+            is_knockout,
+            num_passes: 1,
+        }))
+    }
+
+    #[test]
+    fn blur_filter() {
+        let mut rgba = square();
+        let blur = Filter::BlurFilter(Box::new(BlurFilter {
+            blur_x: 4.0,
+            blur_y: 4.0,
+            num_passes: 2,
+        }));
+        apply_filters(&[blur], 20, 20, &mut rgba, 1.0);
+        let (inside, edge, outside) = (
+            pixel(&rgba, 10, 10),
+            pixel(&rgba, 5, 10),
+            pixel(&rgba, 3, 10),
+        );
+        assert_eq!(inside, [0, 0, 255, 255]);
+        assert!(edge[3] > 64 && edge[3] < 192, "{:?}", edge);
+        assert!(outside[3] > 0 && outside[3] < edge[3], "{:?}", outside);
+        // The blue channel stays premultiplied by alpha.
+        assert_eq!(edge[2], edge[3]);
+        assert_eq!(pixel(&rgba, 0, 0), [0; 4]);
+
+        // Doubling the scale doubles the size of the blur.
+        let mut scaled = square();
+        let blur = Filter::BlurFilter(Box::new(BlurFilter {
+            blur_x: 2.0,
+            blur_y: 2.0,
+            num_passes: 2,
+        }));
+        apply_filters(&[blur], 20, 20, &mut scaled, 2.0);
+        assert_eq!(scaled, rgba);
+    }
+
+    #[test]
+    fn glow_filter() {
+        // An outer glow surrounds the object.
+        let mut rgba = square();
+        apply_filters(&[glow(false, false)], 20, 20, &mut rgba, 1.0);
+        assert_eq!(pixel(&rgba, 10, 10), [0, 0, 255, 255]);
+        let outside = pixel(&rgba, 4, 10);
+        assert!(outside[0] > 0 && outside[2] == 0, "{:?}", outside);
+        assert_eq!(pixel(&rgba, 0, 0), [0; 4]);
+
+        // A knockout glow is drawn without the object.
+        let mut knockout = square();
+        apply_filters(&[glow(false, true)], 20, 20, &mut knockout, 1.0);
+        assert_eq!(pixel(&knockout, 10, 10), [0; 4]);
+        assert_eq!(pixel(&knockout, 4, 10), outside);
+
+        // An inner glow is drawn inside the edges of the object.
+        let mut inner = square();
+        apply_filters(&[glow(true, false)], 20, 20, &mut inner, 1.0);
+        assert_eq!(pixel(&inner, 10, 10), [0, 0, 255, 255]);
+        let edge = pixel(&inner, 5, 10);
+        assert!(edge[0] > 0 && edge[2] < 255 && edge[3] == 255, "{:?}", edge);
+        assert_eq!(pixel(&inner, 4, 10), [0; 4]);
+    }
+
+    #[test]
+    fn drop_shadow_filter() {
+        let drop_shadow = |is_composite_source| {
+            Filter::DropShadowFilter(Box::new(DropShadowFilter {
+                color: BLACK,
+                blur_x: 0.0,
+                blur_y: 0.0,
+                angle: 0.0,
+                distance: 3.0,
+                strength: 1.0,
+                is_inner: false,
+                is_knockout: false,
+                is_composite_source,
+                num_passes: 1,
+            }))
+        };
+
+        // The shadow is offset to the right of the object.
+        let mut rgba = square();
+        apply_filters(&[drop_shadow(true)], 20, 20, &mut rgba, 1.0);
+        assert_eq!(pixel(&rgba, 16, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&rgba, 14, 10), [0, 0, 255, 255]);
+        assert_eq!(pixel(&rgba, 4, 10), [0; 4]);
+
+        // `hideObject` leaves only the shadow.
+        let mut hidden = square();
+        apply_filters(&[drop_shadow(false)], 20, 20, &mut hidden, 1.0);
+        assert_eq!(pixel(&hidden, 16, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&hidden, 10, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&hidden, 6, 10), [0; 4]);
+    }
+
+    #[test]
+    fn bevel_filter() {
+        let mut rgba = square();
+        let bevel = Filter::BevelFilter(Box::new(BevelFilter {
+            shadow_color: BLACK,
+            highlight_color: WHITE,
+            blur_x: 2.0,
+            blur_y: 2.0,
+            angle: std::f64::consts::FRAC_PI_4,
+            distance: 2.0,
+            strength: 1.0,
+            is_inner: true,
+            is_knockout: false,
+            is_on_top: false,
+            num_passes: 1,
+        }));
+        apply_filters(&[bevel], 20, 20, &mut rgba, 1.0);
+        // The light shines from the top left.
+        let (highlight, shadow) = (pixel(&rgba, 5, 5), pixel(&rgba, 14, 14));
+        assert!(highlight[0] > 128 && highlight[3] == 255, "{:?}", highlight);
+        assert!(shadow[0] == 0 && shadow[2] < 128, "{:?}", shadow);
+        assert_eq!(pixel(&rgba, 10, 10), [0, 0, 255, 255]);
+        assert_eq!(pixel(&rgba, 4, 4), [0; 4]);
+    }
+
+    #[test]
+    fn color_matrix_filter() {
+        let mut rgba = square();
+        // Swap the red and blue channels, and halve the alpha.
+        #[rustfmt::skip]
+        let matrix = [
+            0.0, 0.0, 1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0, 0.0,
+            1.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 0.5, 0.0,
+        ];
+        let filter = Filter::ColorMatrixFilter(Box::new(ColorMatrixFilter { matrix }));
+        apply_filters(&[filter], 20, 20, &mut rgba, 1.0);
+        assert_eq!(pixel(&rgba, 10, 10), [128, 0, 0, 128]);
+        assert_eq!(pixel(&rgba, 0, 0), [0; 4]);
+    }
+
+    #[test]
+    fn convolution_filter() {
+        let convolution = |is_clamped, is_preserve_alpha| {
+            Filter::ConvolutionFilter(Box::new(ConvolutionFilter {
+                num_matrix_rows: 1,
+                num_matrix_cols: 3,
+                matrix: vec![1.0, 1.0, 1.0],
+                divisor: 3.0,
+                bias: 0.0,
+                default_color: RED,
+                is_clamped,
+                is_preserve_alpha,
+            }))
+        };
+
+        // Without clamping, pixels outside the image are the default color.
+        let mut rgba = vec![0, 0, 255, 255, 0, 0, 255, 255];
+        apply_filters(&[convolution(false, false)], 2, 1, &mut rgba, 1.0);
+        assert_eq!(rgba, vec![85, 0, 170, 255, 85, 0, 170, 255]);
+
+        let mut rgba = vec![0, 0, 255, 255, 0, 0, 255, 255];
+        apply_filters(&[convolution(true, false)], 2, 1, &mut rgba, 1.0);
+        assert_eq!(rgba, vec![0, 0, 255, 255, 0, 0, 255, 255]);
+
+        // Preserving alpha only convolves the colors.
+        let mut rgba = vec![0, 0, 255, 255, 0, 0, 0, 0];
+        apply_filters(&[convolution(true, true)], 2, 1, &mut rgba, 1.0);
+        assert_eq!(rgba, vec![0, 0, 170, 255, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,1278 @@
+//! Render backend that rasterizes in software.
+
+mod raster;
+
+use crate::backend::render::{
+    blend, define_bits_jpeg_to_rgba, define_bits_lossless_to_rgba, filters, glue_tables_to_jpeg,
+    premultiply_alpha_rgba, remove_invalid_jpeg_data, unmultiply_alpha_rgba, BitmapHandle,
+    BitmapInfo, Color, Letterbox, RenderBackend, ShapeHandle, Transform,
+};
+use crate::color_transform::ColorTransform;
+use crate::matrix::Matrix;
+use crate::shape_utils::{swf_shape_to_paths, DrawCommand, DrawPath};
+use raster::{FillRule, LineCap, LineJoin, Point, Polygon, StrokeStyle};
+use std::convert::TryInto;
+use swf::{BlendMode, CharacterId, FillStyle, Twips};
+
+type Error = Box<dyn std::error::Error>;
+
+/// Renders into an RGBA framebuffer in memory, without a GPU or a window.
+///
+/// Shapes are rasterized with anti-aliasing, and support every fill style, color transforms
+/// and masks. This is used for screenshots and for comparing the output of the player in tests.
+pub struct SoftwareRenderBackend {
+    target: RenderTarget,
+
+    /// The layers beneath `target` while it is an offscreen layer, from the bottom.
+    layers: Vec<Layer>,
+
+    shapes: Vec<Shape>,
+    bitmaps: Vec<Bitmap>,
+}
+
+/// The framebuffer and the masks that clip drawing into it.
+struct RenderTarget {
+    width: u32,
+    height: u32,
+
+    /// The framebuffer, in RGBA with premultiplied alpha.
+    pixels: Vec<u8>,
+
+    /// The masks that are being drawn or are clipping, from the outermost.
+    masks: Vec<Mask>,
+}
+
+/// The target beneath an offscreen layer.
+struct Layer {
+    /// The target that the layer is composited onto.
+    parent: RenderTarget,
+
+    /// How the layer is composited onto its parent.
+    blend_mode: BlendMode,
+}
+
+struct Shape {
+    draws: Vec<Draw>,
+}
+
+enum Draw {
+    Fill {
+        paint: Paint,
+        commands: Vec<DrawCommand>,
+    },
+    Stroke {
+        paint: Paint,
+        commands: Vec<DrawCommand>,
+        is_closed: bool,
+        width: Twips,
+        style: StrokeStyle,
+
+        /// Whether the width of the stroke is scaled with the shape.
+        is_scaled: bool,
+    },
+}
+
+/// How the area of a draw is colored.
+enum Paint {
+    Color(Color),
+    Gradient {
+        gradient: swf::Gradient,
+        kind: GradientKind,
+    },
+    Bitmap {
+        source: BitmapSource,
+        /// Maps the pixels of the bitmap to the space of the shape, in twips.
+        matrix: Matrix,
+        is_smoothed: bool,
+        is_repeating: bool,
+    },
+}
+
+/// The bitmap drawn by a bitmap paint.
+#[derive(Copy, Clone)]
+enum BitmapSource {
+    /// The last bitmap registered with a character ID, as used by the fills of shapes.
+    Character(CharacterId),
+
+    /// A bitmap drawn with `render_bitmap`. Bitmaps such as video frames can share an ID.
+    Handle(BitmapHandle),
+}
+
+#[derive(Copy, Clone)]
+enum GradientKind {
+    Linear,
+    Radial,
+    Focal(f32),
+}
+
+struct Bitmap {
+    id: CharacterId,
+    width: u32,
+    height: u32,
+
+    /// The pixels of the bitmap, in RGBA with premultiplied alpha.
+    data: Vec<u8>,
+}
+
+struct Mask {
+    /// The coverage of the masker at each pixel of the framebuffer.
+    coverage: Vec<f32>,
+
+    /// Whether the masker has been drawn, and the mask clips the maskees.
+    is_active: bool,
+}
+
+impl SoftwareRenderBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            target: RenderTarget::new(width, height),
+            layers: vec![],
+            shapes: vec![],
+            bitmaps: vec![],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.target.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.target.height
+    }
+
+    /// The rendered pixels, in RGBA with premultiplied alpha.
+    pub fn pixels(&self) -> &[u8] {
+        &self.target.pixels
+    }
+
+    /// Returns a copy of the rendered pixels, in RGBA with straight alpha.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut rgba = self.target.pixels.clone();
+        unmultiply_alpha_rgba(&mut rgba);
+        rgba
+    }
+
+    fn register_bitmap(
+        &mut self,
+        id: CharacterId,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    ) -> BitmapInfo {
+        let handle = BitmapHandle(self.bitmaps.len());
+        self.bitmaps.push(Bitmap {
+            id,
+            width,
+            height,
+            data,
+        });
+        BitmapInfo {
+            handle,
+            width: width.try_into().unwrap_or(u16::MAX),
+            height: height.try_into().unwrap_or(u16::MAX),
+        }
+    }
+
+    fn render_draw(&mut self, draw: &Draw, transform: &Transform) {
+        let to_device = |x: Twips, y: Twips| {
+            let (x, y) = (x.get() as f32, y.get() as f32);
+            let matrix = &transform.matrix;
+            Point::new(
+                (matrix.a * x + matrix.c * y + matrix.tx) / 20.0,
+                (matrix.b * x + matrix.d * y + matrix.ty) / 20.0,
+            )
+        };
+
+        let (paint, polygon, fill_rule) = match draw {
+            Draw::Fill { paint, commands } => {
+                let mut polygon = Polygon::new();
+                for command in commands {
+                    match *command {
+                        DrawCommand::MoveTo { x, y } => polygon.move_to(to_device(x, y)),
+                        DrawCommand::LineTo { x, y } => polygon.line_to(to_device(x, y)),
+                        DrawCommand::CurveTo { x1, y1, x2, y2 } => {
+                            polygon.curve_to(to_device(x1, y1), to_device(x2, y2))
+                        }
+                    }
+                }
+                (paint, polygon, FillRule::EvenOdd)
+            }
+            Draw::Stroke {
+                paint,
+                commands,
+                is_closed,
+                width,
+                style,
+                is_scaled,
+            } => {
+                let matrix = &transform.matrix;
+                let scale = if *is_scaled {
+                    (matrix.a * matrix.d - matrix.b * matrix.c).abs().sqrt()
+                } else {
+                    1.0
+                };
+                // Strokes are at least one pixel wide, including hairlines with a width of 0.
+                let style = StrokeStyle {
+                    width: (width.to_pixels() as f32 * scale).max(1.0),
+                    ..*style
+                };
+
+                let mut polygon = Polygon::new();
+                let mut polyline: Vec<Point> = vec![];
+                let mut stroke = |polyline: &mut Vec<Point>| {
+                    if let (Some(first), Some(last)) = (polyline.first(), polyline.last()) {
+                        let is_closed = *is_closed && first == last;
+                        raster::stroke_polyline(polyline, is_closed, &style, &mut polygon);
+                    }
+                    polyline.clear();
+                };
+                for command in commands {
+                    match *command {
+                        DrawCommand::MoveTo { x, y } => {
+                            stroke(&mut polyline);
+                            polyline.push(to_device(x, y));
+                        }
+                        DrawCommand::LineTo { x, y } => polyline.push(to_device(x, y)),
+                        DrawCommand::CurveTo { x1, y1, x2, y2 } => {
+                            let start = polyline
+                                .last()
+                                .copied()
+                                .unwrap_or_else(|| to_device(x1, y1));
+                            let end = to_device(x2, y2);
+                            polyline.extend(raster::flatten_curve(start, to_device(x1, y1), end));
+                        }
+                    }
+                }
+                stroke(&mut polyline);
+                (paint, polygon, FillRule::NonZero)
+            }
+        };
+
+        if polygon.is_empty() {
+            return;
+        }
+        if let Some(shader) = Shader::new(paint, transform, &self.bitmaps) {
+            self.target.fill_polygon(&polygon, fill_rule, &shader, true);
+        }
+    }
+}
+
+impl RenderTarget {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+            masks: vec![],
+        }
+    }
+
+    /// Fills a polygon in device space with the given shader.
+    ///
+    /// While a masker is being drawn, only the coverage of the polygon is drawn into the mask.
+    /// Otherwise, the polygon is clipped by the active mask.
+    fn fill_polygon(
+        &mut self,
+        polygon: &Polygon,
+        fill_rule: FillRule,
+        shader: &Shader,
+        is_masked: bool,
+    ) {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let pixels = &mut self.pixels;
+        let (mut target_mask, clip) = match self.masks.split_last_mut() {
+            Some((top, rest)) if !top.is_active && is_masked => {
+                (Some(top), rest.iter().rev().find(|mask| mask.is_active))
+            }
+            Some((top, _)) if is_masked => (None, Some(&*top)),
+            _ => (None, None),
+        };
+
+        raster::rasterize(polygon, fill_rule, width, height, |x_start, y, coverage| {
+            for (i, &coverage) in coverage.iter().enumerate() {
+                let x = x_start + i;
+                let index = y * width + x;
+                let coverage = coverage * clip.map_or(1.0, |mask| mask.coverage[index]);
+                if coverage <= 0.0 {
+                    continue;
+                }
+                if let Some(mask) = &mut target_mask {
+                    let mask_coverage = &mut mask.coverage[index];
+                    *mask_coverage += coverage * (1.0 - *mask_coverage);
+                } else {
+                    let color = shader.shade(x, y);
+                    blend(&mut pixels[index * 4..index * 4 + 4], color, coverage);
+                }
+            }
+        });
+    }
+
+    /// Composites the pixels of an offscreen layer of the same size onto this target.
+    ///
+    /// Like a polygon, the layer is drawn into the mask that is being drawn, if any.
+    /// Otherwise, it is clipped by the active mask.
+    fn composite_layer(&mut self, layer: &[u8], blend_mode: BlendMode) {
+        let (mut target_mask, clip) = match self.masks.split_last_mut() {
+            Some((top, rest)) if !top.is_active => {
+                (Some(top), rest.iter().rev().find(|mask| mask.is_active))
+            }
+            Some((top, _)) => (None, Some(&*top)),
+            None => (None, None),
+        };
+        for (index, (pixel, color)) in self
+            .pixels
+            .chunks_exact_mut(4)
+            .zip(layer.chunks_exact(4))
+            .enumerate()
+        {
+            let coverage = clip.map_or(1.0, |mask| mask.coverage[index]);
+            // Transparent pixels of the layer only affect the target in the alpha mode,
+            // where they knock it out.
+            if coverage <= 0.0 || (color[3] == 0 && blend_mode != BlendMode::Alpha) {
+                continue;
+            }
+            let color = [
+                f32::from(color[0]) / 255.0,
+                f32::from(color[1]) / 255.0,
+                f32::from(color[2]) / 255.0,
+                f32::from(color[3]) / 255.0,
+            ];
+            if let Some(mask) = &mut target_mask {
+                let mask_coverage = &mut mask.coverage[index];
+                *mask_coverage += color[3] * coverage * (1.0 - *mask_coverage);
+            } else {
+                let below = [
+                    f32::from(pixel[0]) / 255.0,
+                    f32::from(pixel[1]) / 255.0,
+                    f32::from(pixel[2]) / 255.0,
+                    f32::from(pixel[3]) / 255.0,
+                ];
+                let blended = blend::composite(blend_mode, color, below);
+                for ((pixel, below), blended) in pixel.iter_mut().zip(&below).zip(&blended) {
+                    *pixel = to_u8(below + (blended - below) * coverage);
+                }
+            }
+        }
+    }
+
+    /// Fills a rectangle of the framebuffer, ignoring masks.
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
+        let mut polygon = Polygon::new();
+        polygon.move_to(Point::new(x, y));
+        polygon.line_to(Point::new(x + width, y));
+        polygon.line_to(Point::new(x + width, y + height));
+        polygon.line_to(Point::new(x, y + height));
+        self.fill_polygon(&polygon, FillRule::NonZero, &Shader::Solid(color), false);
+    }
+}
+
+impl RenderBackend for SoftwareRenderBackend {
+    fn set_viewport_dimensions(&mut self, width: u32, height: u32) {
+        self.target = RenderTarget::new(width, height);
+        self.layers.clear();
+    }
+
+    fn register_shape(&mut self, shape: &swf::Shape) -> ShapeHandle {
+        let handle = ShapeHandle(self.shapes.len());
+        let draws = swf_shape_to_paths(shape)
+            .into_iter()
+            .map(|path| match path {
+                DrawPath::Fill { style, commands } => Draw::Fill {
+                    paint: Paint::from_fill_style(style),
+                    commands,
+                },
+                DrawPath::Stroke {
+                    style,
+                    is_closed,
+                    commands,
+                } => Draw::Stroke {
+                    paint: match &style.fill_style {
+                        Some(fill_style) => Paint::from_fill_style(fill_style),
+                        None => Paint::Color(style.color.clone()),
+                    },
+                    commands,
+                    is_closed,
+                    width: style.width,
+                    style: StrokeStyle {
+                        width: 0.0,
+                        start_cap: line_cap(style.start_cap),
+                        end_cap: line_cap(style.end_cap),
+                        join: match style.join_style {
+                            swf::LineJoinStyle::Round => LineJoin::Round,
+                            swf::LineJoinStyle::Bevel => LineJoin::Bevel,
+                            swf::LineJoinStyle::Miter(limit) => LineJoin::Miter(limit),
+                        },
+                    },
+                    is_scaled: style.allow_scale_x || style.allow_scale_y,
+                },
+            })
+            .collect();
+        self.shapes.push(Shape { draws });
+        handle
+    }
+
+    fn register_glyph_shape(&mut self, glyph: &swf::Glyph) -> ShapeHandle {
+        let shape = swf::Shape {
+            version: 2,
+            id: 0,
+            shape_bounds: Default::default(),
+            edge_bounds: Default::default(),
+            has_fill_winding_rule: false,
+            has_non_scaling_strokes: false,
+            has_scaling_strokes: true,
+            styles: swf::ShapeStyles {
+                fill_styles: vec![FillStyle::Color(Color {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                    a: 255,
+                })],
+                line_styles: vec![],
+            },
+            shape: glyph.shape_records.clone(),
+        };
+        self.register_shape(&shape)
+    }
+
+    fn register_bitmap_jpeg(
+        &mut self,
+        id: CharacterId,
+        data: &[u8],
+        jpeg_tables: Option<&[u8]>,
+    ) -> BitmapInfo {
+        let data = glue_tables_to_jpeg(data, jpeg_tables);
+        self.register_bitmap_jpeg_2(id, &data[..])
+    }
+
+    fn register_bitmap_jpeg_2(&mut self, id: CharacterId, data: &[u8]) -> BitmapInfo {
+        let (width, height, rgba) = decode_jpeg(data).unwrap_or_else(|e| {
+            log::error!("Unable to decode JPEG {}: {}", id, e);
+            (0, 0, vec![])
+        });
+        self.register_bitmap(id, width, height, rgba)
+    }
+
+    fn register_bitmap_jpeg_3(
+        &mut self,
+        id: CharacterId,
+        jpeg_data: &[u8],
+        alpha_data: &[u8],
+    ) -> BitmapInfo {
+        let (width, height, rgba) =
+            define_bits_jpeg_to_rgba(jpeg_data, alpha_data).unwrap_or_else(|e| {
+                log::error!("Unable to decode DefineBitsJPEG3 {}: {}", id, e);
+                (0, 0, vec![])
+            });
+        self.register_bitmap(id, width, height, rgba)
+    }
+
+    fn register_bitmap_png(&mut self, swf_tag: &swf::DefineBitsLossless) -> BitmapInfo {
+        // Lossless bitmaps already have premultiplied alpha.
+        let (width, height, rgba) = match define_bits_lossless_to_rgba(swf_tag) {
+            Ok(rgba) => (swf_tag.width.into(), swf_tag.height.into(), rgba),
+            Err(e) => {
+                log::error!("Unable to decode DefineBitsLossless {}: {}", swf_tag.id, e);
+                (0, 0, vec![])
+            }
+        };
+        self.register_bitmap(swf_tag.id, width, height, rgba)
+    }
+
+    fn register_bitmap_raw(
+        &mut self,
+        id: CharacterId,
+        width: u32,
+        height: u32,
+        mut rgba: Vec<u8>,
+    ) -> BitmapInfo {
+        premultiply_alpha_rgba(&mut rgba);
+        self.register_bitmap(id, width, height, rgba)
+    }
+
+    fn update_texture(
+        &mut self,
+        bitmap: BitmapHandle,
+        width: u32,
+        height: u32,
+        mut rgba: Vec<u8>,
+    ) -> Result<BitmapHandle, Error> {
+        let old_bitmap = self
+            .bitmaps
+            .get_mut(bitmap.0)
+            .ok_or("Unknown bitmap handle")?;
+        premultiply_alpha_rgba(&mut rgba);
+        old_bitmap.width = width;
+        old_bitmap.height = height;
+        old_bitmap.data = rgba;
+        Ok(bitmap)
+    }
+
+    fn unregister_bitmap(&mut self, bitmap: BitmapHandle) {
+        if let Some(bitmap) = self.bitmaps.get_mut(bitmap.0) {
+            bitmap.width = 0;
+            bitmap.height = 0;
+            bitmap.data = vec![];
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        if !self.layers.is_empty() {
+            log::warn!("Layers were not popped in the previous frame");
+            self.target = self.layers.swap_remove(0).parent;
+            self.layers.clear();
+        }
+        self.target.masks.clear();
+    }
+
+    fn clear(&mut self, color: Color) {
+        let color = premultiply(color_to_f32(&color));
+        let color = [
+            to_u8(color[0]),
+            to_u8(color[1]),
+            to_u8(color[2]),
+            to_u8(color[3]),
+        ];
+        for pixel in self.target.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+    }
+
+    fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform, smoothing: bool) {
+        let (width, height) = match self.bitmaps.get(bitmap.0) {
+            Some(bitmap) => (bitmap.width, bitmap.height),
+            None => return,
+        };
+        // The bitmap is drawn as a rectangle with a bitmap fill, one pixel per 20 twips.
+        let (width, height) = (
+            Twips::from_pixels(width.into()),
+            Twips::from_pixels(height.into()),
+        );
+        let zero = Twips::new(0);
+        let draw = Draw::Fill {
+            paint: Paint::Bitmap {
+                source: BitmapSource::Handle(bitmap),
+                matrix: Matrix {
+                    a: 20.0,
+                    d: 20.0,
+                    ..Default::default()
+                },
+                is_smoothed: smoothing,
+                is_repeating: false,
+            },
+            commands: vec![
+                DrawCommand::MoveTo { x: zero, y: zero },
+                DrawCommand::LineTo { x: width, y: zero },
+                DrawCommand::LineTo {
+                    x: width,
+                    y: height,
+                },
+                DrawCommand::LineTo { x: zero, y: height },
+                DrawCommand::LineTo { x: zero, y: zero },
+            ],
+        };
+        self.render_draw(&draw, transform);
+    }
+
+    fn render_shape(&mut self, shape: ShapeHandle, transform: &Transform) {
+        // Take the shape out while drawing it, so that its draws can borrow the backend.
+        let draws = match self.shapes.get_mut(shape.0) {
+            Some(shape) => std::mem::take(&mut shape.draws),
+            None => return,
+        };
+        for draw in &draws {
+            self.render_draw(draw, transform);
+        }
+        self.shapes[shape.0].draws = draws;
+    }
+
+    fn end_frame(&mut self) {}
+
+    fn draw_pause_overlay(&mut self) {
+        let target = &mut self.target;
+        let (width, height) = (target.width as f32, target.height as f32);
+        target.fill_rect(0.0, 0.0, width, height, [0.0, 0.0, 0.0, 0.5]);
+    }
+
+    fn draw_letterbox(&mut self, letterbox: Letterbox) {
+        let target = &mut self.target;
+        let (width, height) = (target.width as f32, target.height as f32);
+        let black = [0.0, 0.0, 0.0, 1.0];
+        match letterbox {
+            Letterbox::None => (),
+            Letterbox::Letterbox(margin_height) => {
+                target.fill_rect(0.0, 0.0, width, margin_height, black);
+                target.fill_rect(0.0, height - margin_height, width, margin_height, black);
+            }
+            Letterbox::Pillarbox(margin_width) => {
+                target.fill_rect(0.0, 0.0, margin_width, height, black);
+                target.fill_rect(width - margin_width, 0.0, margin_width, height, black);
+            }
+        }
+    }
+
+    fn push_mask(&mut self) {
+        // The masker is drawn into a coverage buffer, clipped by any enclosing mask.
+        let target = &mut self.target;
+        target.masks.push(Mask {
+            coverage: vec![0.0; target.width as usize * target.height as usize],
+            is_active: false,
+        });
+    }
+
+    fn activate_mask(&mut self) {
+        if let Some(mask) = self.target.masks.last_mut() {
+            mask.is_active = true;
+        }
+    }
+
+    fn pop_mask(&mut self) {
+        if self.target.masks.pop().is_none() {
+            log::warn!("Mask stack underflow");
+        }
+    }
+
+    fn push_layer(&mut self, blend_mode: BlendMode) {
+        let layer = RenderTarget::new(self.target.width, self.target.height);
+        self.layers.push(Layer {
+            parent: std::mem::replace(&mut self.target, layer),
+            blend_mode,
+        });
+    }
+
+    fn pop_layer(&mut self, filters: &[swf::Filter], scale: f32) {
+        let Layer { parent, blend_mode } = match self.layers.pop() {
+            Some(layer) => layer,
+            None => {
+                log::warn!("Layer stack underflow");
+                return;
+            }
+        };
+        let mut layer = std::mem::replace(&mut self.target, parent);
+        filters::apply_filters(filters, layer.width, layer.height, &mut layer.pixels, scale);
+        self.target.composite_layer(&layer.pixels, blend_mode);
+    }
+}
+
+impl Paint {
+    fn from_fill_style(style: &FillStyle) -> Self {
+        match style {
+            FillStyle::Color(color) => Paint::Color(color.clone()),
+            FillStyle::LinearGradient(gradient) => Paint::Gradient {
+                gradient: gradient.clone(),
+                kind: GradientKind::Linear,
+            },
+            FillStyle::RadialGradient(gradient) => Paint::Gradient {
+                gradient: gradient.clone(),
+                kind: GradientKind::Radial,
+            },
+            FillStyle::FocalGradient {
+                gradient,
+                focal_point,
+            } => Paint::Gradient {
+                gradient: gradient.clone(),
+                kind: GradientKind::Focal(*focal_point),
+            },
+            FillStyle::Bitmap {
+                id,
+                matrix,
+                is_smoothed,
+                is_repeating,
+            } => Paint::Bitmap {
+                source: BitmapSource::Character(*id),
+                matrix: matrix.clone().into(),
+                is_smoothed: *is_smoothed,
+                is_repeating: *is_repeating,
+            },
+        }
+    }
+}
+
+/// Computes the color of each pixel that a paint covers.
+enum Shader<'a> {
+    /// A color with premultiplied alpha.
+    Solid([f32; 4]),
+    Gradient {
+        /// The colors of the gradient for each ratio, with premultiplied alpha.
+        colors: Vec<[f32; 4]>,
+        /// Maps pixels to the gradient square, which spans -16384 to 16384.
+        inverse: Matrix,
+        kind: GradientKind,
+        spread: swf::GradientSpread,
+    },
+    Bitmap {
+        bitmap: &'a Bitmap,
+        /// Maps pixels to the pixels of the bitmap.
+        inverse: Matrix,
+        is_smoothed: bool,
+        is_repeating: bool,
+        color_transform: Option<ColorTransform>,
+    },
+}
+
+impl<'a> Shader<'a> {
+    /// Creates the shader for a paint, or `None` if it uses a bitmap that doesn't exist.
+    fn new(paint: &Paint, transform: &Transform, bitmaps: &'a [Bitmap]) -> Option<Self> {
+        let color_transform = &transform.color_transform;
+        // Maps a space to twips on screen, to be inverted to find the source of each pixel.
+        let to_pixels = |matrix: Matrix| {
+            let mut matrix = Matrix {
+                a: 0.05,
+                d: 0.05,
+                ..Default::default()
+            } * transform.matrix
+                * matrix;
+            matrix.invert();
+            matrix
+        };
+
+        let shader = match paint {
+            Paint::Color(color) => Shader::Solid(premultiply(apply_color_transform(
+                color_to_f32(color),
+                color_transform,
+            ))),
+            Paint::Gradient { gradient, kind } => Shader::Gradient {
+                colors: gradient_colors(gradient)
+                    .into_iter()
+                    .map(|color| premultiply(apply_color_transform(color, color_transform)))
+                    .collect(),
+                inverse: to_pixels(gradient.matrix.clone().into()),
+                kind: *kind,
+                spread: gradient.spread,
+            },
+            Paint::Bitmap {
+                source,
+                matrix,
+                is_smoothed,
+                is_repeating,
+            } => {
+                let bitmap = match source {
+                    BitmapSource::Character(id) => {
+                        bitmaps.iter().rev().find(|bitmap| bitmap.id == *id)?
+                    }
+                    BitmapSource::Handle(handle) => bitmaps.get(handle.0)?,
+                };
+                if bitmap.width == 0 || bitmap.height == 0 {
+                    return None;
+                }
+                Shader::Bitmap {
+                    bitmap,
+                    inverse: to_pixels(*matrix),
+                    is_smoothed: *is_smoothed,
+                    is_repeating: *is_repeating,
+                    color_transform: if color_transform.is_identity() {
+                        None
+                    } else {
+                        Some(*color_transform)
+                    },
+                }
+            }
+        };
+        Some(shader)
+    }
+
+    /// Returns the color of a pixel, with premultiplied alpha.
+    fn shade(&self, x: usize, y: usize) -> [f32; 4] {
+        // Sample the center of the pixel.
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        match self {
+            Shader::Solid(color) => *color,
+            Shader::Gradient {
+                colors,
+                inverse,
+                kind,
+                spread,
+            } => {
+                let u = (inverse.a * x + inverse.c * y + inverse.tx) / 16384.0;
+                let v = (inverse.b * x + inverse.d * y + inverse.ty) / 16384.0;
+                let t = match kind {
+                    GradientKind::Linear => (u + 1.0) / 2.0,
+                    GradientKind::Radial => (u * u + v * v).sqrt(),
+                    GradientKind::Focal(focal_point) => {
+                        // The distance from the focal point, relative to the distance
+                        // from the focal point to the edge of the circle in that direction.
+                        let focal_point = focal_point.max(-0.99).min(0.99);
+                        let (dx, dy) = (u - focal_point, v);
+                        let length = (dx * dx + dy * dy).sqrt();
+                        if length > 0.0 {
+                            let (dx, dy) = (dx / length, dy / length);
+                            let edge = (1.0 - focal_point * focal_point * dy * dy).sqrt()
+                                - focal_point * dx;
+                            length / edge
+                        } else {
+                            0.0
+                        }
+                    }
+                };
+                let t = match spread {
+                    swf::GradientSpread::Pad => t,
+                    swf::GradientSpread::Repeat => t.rem_euclid(1.0),
+                    swf::GradientSpread::Reflect => {
+                        let t = t.abs().rem_euclid(2.0);
+                        if t > 1.0 {
+                            2.0 - t
+                        } else {
+                            t
+                        }
+                    }
+                };
+                let index = (t * 255.0).round().max(0.0).min(255.0) as usize;
+                colors[index]
+            }
+            Shader::Bitmap {
+                bitmap,
+                inverse,
+                is_smoothed,
+                is_repeating,
+                color_transform,
+            } => {
+                let u = inverse.a * x + inverse.c * y + inverse.tx;
+                let v = inverse.b * x + inverse.d * y + inverse.ty;
+                let color = if *is_smoothed {
+                    // Interpolate between the four nearest pixel centers.
+                    let (u, v) = (u - 0.5, v - 0.5);
+                    let (x0, y0) = (u.floor(), v.floor());
+                    let (fx, fy) = (u - x0, v - y0);
+                    let (x0, y0) = (x0 as i64, y0 as i64);
+                    let top = lerp(
+                        bitmap.pixel(x0, y0, *is_repeating),
+                        bitmap.pixel(x0 + 1, y0, *is_repeating),
+                        fx,
+                    );
+                    let bottom = lerp(
+                        bitmap.pixel(x0, y0 + 1, *is_repeating),
+                        bitmap.pixel(x0 + 1, y0 + 1, *is_repeating),
+                        fx,
+                    );
+                    lerp(top, bottom, fy)
+                } else {
+                    bitmap.pixel(u.floor() as i64, v.floor() as i64, *is_repeating)
+                };
+                match color_transform {
+                    Some(color_transform) => {
+                        premultiply(apply_color_transform(unmultiply(color), color_transform))
+                    }
+                    None => color,
+                }
+            }
+        }
+    }
+}
+
+impl Bitmap {
+    /// Returns a pixel of the bitmap, with premultiplied alpha.
+    /// Coordinates outside of the bitmap are wrapped or clamped to the edges.
+    fn pixel(&self, x: i64, y: i64, is_repeating: bool) -> [f32; 4] {
+        let (width, height) = (i64::from(self.width), i64::from(self.height));
+        let (x, y) = if is_repeating {
+            (x.rem_euclid(width), y.rem_euclid(height))
+        } else {
+            (x.max(0).min(width - 1), y.max(0).min(height - 1))
+        };
+        let index = (y * width + x) as usize * 4;
+        match self.data.get(index..index + 4) {
+            Some(pixel) => [
+                f32::from(pixel[0]) / 255.0,
+                f32::from(pixel[1]) / 255.0,
+                f32::from(pixel[2]) / 255.0,
+                f32::from(pixel[3]) / 255.0,
+            ],
+            None => [0.0; 4],
+        }
+    }
+}
+
+/// Computes the colors of a gradient for each of the 256 ratios, with straight alpha.
+fn gradient_colors(gradient: &swf::Gradient) -> Vec<[f32; 4]> {
+    let records = &gradient.records;
+    let is_linear_rgb = gradient.interpolation == swf::GradientInterpolation::LinearRGB;
+    let color = |record: &swf::GradientRecord| {
+        let color = color_to_f32(&record.color);
+        if is_linear_rgb {
+            [
+                srgb_to_linear(color[0]),
+                srgb_to_linear(color[1]),
+                srgb_to_linear(color[2]),
+                color[3],
+            ]
+        } else {
+            color
+        }
+    };
+
+    (0..=255u8)
+        .map(|ratio| {
+            let next = records.iter().position(|record| record.ratio >= ratio);
+            let color = match next {
+                None => records.last().map_or([0.0; 4], color),
+                Some(0) => color(&records[0]),
+                Some(i) => {
+                    let (a, b) = (&records[i - 1], &records[i]);
+                    let t = f32::from(ratio - a.ratio) / f32::from(b.ratio - a.ratio);
+                    lerp(color(a), color(b), t)
+                }
+            };
+            if is_linear_rgb {
+                [
+                    linear_to_srgb(color[0]),
+                    linear_to_srgb(color[1]),
+                    linear_to_srgb(color[2]),
+                    color[3],
+                ]
+            } else {
+                color
+            }
+        })
+        .collect()
+}
+
+fn line_cap(cap: swf::LineCapStyle) -> LineCap {
+    match cap {
+        swf::LineCapStyle::None => LineCap::Butt,
+        swf::LineCapStyle::Round => LineCap::Round,
+        swf::LineCapStyle::Square => LineCap::Square,
+    }
+}
+
+/// Decodes a JPEG into RGBA.
+fn decode_jpeg(data: &[u8]) -> Result<(u32, u32, Vec<u8>), Error> {
+    let data = remove_invalid_jpeg_data(data);
+    let mut decoder = jpeg_decoder::Decoder::new(&data[..]);
+    let pixels = decoder.decode()?;
+    let info = decoder.info().ok_or("Missing JPEG info")?;
+    let rgba = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => pixels.iter().flat_map(|&l| vec![l, l, l, 255]).collect(),
+        jpeg_decoder::PixelFormat::RGB24 => pixels
+            .chunks_exact(3)
+            .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        jpeg_decoder::PixelFormat::CMYK32 => pixels
+            .chunks_exact(4)
+            .flat_map(|cmyk| {
+                let k = u16::from(cmyk[3]);
+                let channel = |c: u8| (u16::from(255 - c) * (255 - k) / 255) as u8;
+                vec![channel(cmyk[0]), channel(cmyk[1]), channel(cmyk[2]), 255]
+            })
+            .collect(),
+    };
+    Ok((info.width.into(), info.height.into(), rgba))
+}
+
+/// Composites a color with premultiplied alpha over a pixel of the framebuffer.
+fn blend(pixel: &mut [u8], color: [f32; 4], coverage: f32) {
+    let alpha = color[3] * coverage;
+    for (dst, &src) in pixel.iter_mut().zip(color.iter()) {
+        let dst_value = f32::from(*dst) / 255.0;
+        *dst = to_u8(src * coverage + dst_value * (1.0 - alpha));
+    }
+}
+
+fn apply_color_transform(color: [f32; 4], color_transform: &ColorTransform) -> [f32; 4] {
+    let clamp = |value: f32| value.max(0.0).min(1.0);
+    [
+        clamp(color[0] * color_transform.r_mult + color_transform.r_add),
+        clamp(color[1] * color_transform.g_mult + color_transform.g_add),
+        clamp(color[2] * color_transform.b_mult + color_transform.b_add),
+        clamp(color[3] * color_transform.a_mult + color_transform.a_add),
+    ]
+}
+
+fn color_to_f32(color: &Color) -> [f32; 4] {
+    [
+        f32::from(color.r) / 255.0,
+        f32::from(color.g) / 255.0,
+        f32::from(color.b) / 255.0,
+        f32::from(color.a) / 255.0,
+    ]
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value * 255.0).round().max(0.0).min(255.0) as u8
+}
+
+fn premultiply(color: [f32; 4]) -> [f32; 4] {
+    [
+        color[0] * color[3],
+        color[1] * color[3],
+        color[2] * color[3],
+        color[3],
+    ]
+}
+
+fn unmultiply(color: [f32; 4]) -> [f32; 4] {
+    if color[3] > 0.0 {
+        [
+            color[0] / color[3],
+            color[1] / color[3],
+            color[2] / color[3],
+            color[3],
+        ]
+    } else {
+        [0.0; 4]
+    }
+}
+
+fn lerp(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swf::{ShapeRecord, StyleChangeData};
+
+    const RED: Color = Color {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+
+    const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+        a: 255,
+    };
+
+    /// Builds a shape of a square from `(x, y)`, in pixels.
+    fn square(
+        x: f64,
+        y: f64,
+        size: f64,
+        fill_styles: Vec<FillStyle>,
+        line_styles: Vec<swf::LineStyle>,
+    ) -> swf::Shape {
+        let edge = |delta_x: f64, delta_y: f64| ShapeRecord::StraightEdge {
+            delta_x: Twips::from_pixels(delta_x),
+            delta_y: Twips::from_pixels(delta_y),
+        };
+        let records = vec![
+            ShapeRecord::StyleChange(StyleChangeData {
+                move_to: Some((Twips::from_pixels(x), Twips::from_pixels(y))),
+                fill_style_0: None,
+                fill_style_1: if fill_styles.is_empty() {
+                    None
+                } else {
+                    Some(1)
+                },
+                line_style: if line_styles.is_empty() {
+                    None
+                } else {
+                    Some(1)
+                },
+                new_styles: None,
+            }),
+            edge(size, 0.0),
+            edge(0.0, size),
+            edge(-size, 0.0),
+            edge(0.0, -size),
+        ];
+        let bounds = crate::shape_utils::calculate_shape_bounds(&records);
+        swf::Shape {
+            version: 4,
+            id: 1,
+            shape_bounds: bounds.clone(),
+            edge_bounds: bounds,
+            has_fill_winding_rule: false,
+            has_non_scaling_strokes: false,
+            has_scaling_strokes: true,
+            styles: swf::ShapeStyles {
+                fill_styles,
+                line_styles,
+            },
+            shape: records,
+        }
+    }
+
+    fn pixel(renderer: &SoftwareRenderBackend, x: usize, y: usize) -> [u8; 4] {
+        let index = (y * renderer.width() as usize + x) * 4;
+        renderer.to_rgba()[index..index + 4].try_into().unwrap()
+    }
+
+    fn renderer_with_background() -> SoftwareRenderBackend {
+        let mut renderer = SoftwareRenderBackend::new(10, 10);
+        renderer.begin_frame();
+        renderer.clear(WHITE);
+        renderer
+    }
+
+    #[test]
+    fn render_solid_fill() {
+        let mut renderer = renderer_with_background();
+        let shape =
+            renderer.register_shape(&square(2.0, 2.0, 6.0, vec![FillStyle::Color(RED)], vec![]));
+        renderer.render_shape(shape, &Default::default());
+        assert_eq!(pixel(&renderer, 0, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&renderer, 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 7, 7), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 8, 8), [255, 255, 255, 255]);
+
+        // Edges that fall inside of a pixel are anti-aliased.
+        let transform = Transform {
+            matrix: Matrix {
+                tx: 10.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut renderer = renderer_with_background();
+        let shape =
+            renderer.register_shape(&square(2.0, 2.0, 6.0, vec![FillStyle::Color(RED)], vec![]));
+        renderer.render_shape(shape, &transform);
+        assert_eq!(pixel(&renderer, 2, 5), [255, 128, 128, 255]);
+        assert_eq!(pixel(&renderer, 3, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 8, 5), [255, 128, 128, 255]);
+    }
+
+    #[test]
+    fn render_color_transform() {
+        let mut renderer = renderer_with_background();
+        let shape =
+            renderer.register_shape(&square(0.0, 0.0, 10.0, vec![FillStyle::Color(RED)], vec![]));
+        let transform = Transform {
+            color_transform: ColorTransform {
+                a_mult: 0.5,
+                b_add: 1.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        renderer.render_shape(shape, &transform);
+        assert_eq!(pixel(&renderer, 5, 5), [255, 128, 255, 255]);
+    }
+
+    #[test]
+    fn render_linear_gradient() {
+        let mut renderer = renderer_with_background();
+        // The gradient square spans the 10 pixels of the shape.
+        let gradient = swf::Gradient {
+            matrix: swf::Matrix {
+                translate_x: Twips::from_pixels(5.0),
+                translate_y: Twips::from_pixels(5.0),
+                scale_x: 200.0 / 32768.0,
+                scale_y: 200.0 / 32768.0,
+                ..Default::default()
+            },
+            spread: swf::GradientSpread::Pad,
+            interpolation: swf::GradientInterpolation::RGB,
+            records: vec![
+                swf::GradientRecord {
+                    ratio: 0,
+                    color: Color {
+                        r: 0,
+                        g: 0,
+                        b: 0,
+                        a: 255,
+                    },
+                },
+                swf::GradientRecord {
+                    ratio: 255,
+                    color: WHITE,
+                },
+            ],
+        };
+        let shape = renderer.register_shape(&square(
+            0.0,
+            0.0,
+            10.0,
+            vec![FillStyle::LinearGradient(gradient)],
+            vec![],
+        ));
+        renderer.render_shape(shape, &Default::default());
+        let row: Vec<u8> = (0..10).map(|x| pixel(&renderer, x, 3)[0]).collect();
+        assert_eq!(row, vec![13, 38, 64, 89, 115, 140, 166, 191, 217, 242]);
+        assert_eq!(pixel(&renderer, 4, 8), pixel(&renderer, 4, 0));
+    }
+
+    #[test]
+    fn render_bitmap() {
+        let mut renderer = renderer_with_background();
+        #[rustfmt::skip]
+        let rgba = vec![
+            255, 0, 0, 255,   0, 255, 0, 255,
+            0, 0, 255, 255,   0, 0, 0, 0,
+        ];
+        let bitmap = renderer.register_bitmap_raw(1, 2, 2, rgba);
+        assert_eq!((bitmap.width, bitmap.height), (2, 2));
+        let transform = Transform {
+            matrix: Matrix {
+                a: 2.0,
+                d: 2.0,
+                tx: 40.0,
+                ty: 20.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        renderer.render_bitmap(bitmap.handle, &transform, false);
+        assert_eq!(pixel(&renderer, 1, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(&renderer, 2, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 3, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 4, 2), [0, 255, 0, 255]);
+        assert_eq!(pixel(&renderer, 2, 3), [0, 0, 255, 255]);
+        assert_eq!(pixel(&renderer, 5, 4), [255, 255, 255, 255]);
+        assert_eq!(pixel(&renderer, 6, 4), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn render_stroke() {
+        let mut renderer = renderer_with_background();
+        let line_style = swf::LineStyle::new_v1(Twips::from_pixels(2.0), RED);
+        let shape = renderer.register_shape(&square(2.0, 2.0, 6.0, vec![], vec![line_style]));
+        renderer.render_shape(shape, &Default::default());
+        assert_eq!(pixel(&renderer, 1, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 2, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 3, 5), [255, 255, 255, 255]);
+        assert_eq!(pixel(&renderer, 5, 8), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 5, 5), [255, 255, 255, 255]);
+        assert_eq!(pixel(&renderer, 0, 5), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn render_masks() {
+        let mut renderer = renderer_with_background();
+        let mask = renderer.register_shape(&square(
+            0.0,
+            0.0,
+            5.0,
+            vec![FillStyle::Color(WHITE)],
+            vec![],
+        ));
+        let maskee =
+            renderer.register_shape(&square(2.0, 2.0, 8.0, vec![FillStyle::Color(RED)], vec![]));
+        renderer.push_mask();
+        renderer.render_shape(mask, &Default::default());
+        renderer.activate_mask();
+        renderer.render_shape(maskee, &Default::default());
+        renderer.pop_mask();
+        assert_eq!(pixel(&renderer, 1, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(&renderer, 3, 3), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 6, 6), [255, 255, 255, 255]);
+
+        // Drawing after the mask is popped is not clipped.
+        renderer.render_shape(maskee, &Default::default());
+        assert_eq!(pixel(&renderer, 6, 6), [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,583 @@
+//! Scanline rasterization of paths into anti-aliased coverage.
+//!
+//! Paths are flattened into polygons in device space, and the polygons are scan converted
+//! with a few sub-scanlines per row of pixels. The horizontal coverage of each sub-scanline
+//! is computed exactly, so edges are anti-aliased in both directions.
+
+/// The number of sub-scanlines sampled in each row of pixels.
+const SUBSAMPLES: usize = 4;
+
+/// The maximum distance between a flattened curve and the real curve, in pixels.
+const FLATTEN_TOLERANCE: f32 = 0.1;
+
+/// A point in device space, in pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    fn length(self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+}
+
+impl std::ops::Add for Point {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl std::ops::Sub for Point {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl std::ops::Mul<f32> for Point {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        Point::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+/// How overlapping parts of a polygon are filled.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FillRule {
+    /// Areas enclosed an odd number of times are filled. Used by shape fills.
+    EvenOdd,
+
+    /// Areas with a nonzero winding number are filled. Used by strokes, which are built
+    /// from overlapping pieces with the same winding.
+    NonZero,
+}
+
+/// A set of closed polygons that are filled together.
+#[derive(Debug, Default)]
+pub struct Polygon {
+    contours: Vec<Vec<Point>>,
+}
+
+impl Polygon {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Starts a new contour at the given point.
+    pub fn move_to(&mut self, point: Point) {
+        self.contours.push(vec![point]);
+    }
+
+    pub fn line_to(&mut self, point: Point) {
+        match self.contours.last_mut() {
+            Some(contour) => contour.push(point),
+            None => self.move_to(point),
+        }
+    }
+
+    /// Adds a quadratic curve, flattened into lines.
+    pub fn curve_to(&mut self, control: Point, point: Point) {
+        let start = match self.contours.last().and_then(|contour| contour.last()) {
+            Some(&start) => start,
+            None => return self.move_to(point),
+        };
+        for point in flatten_curve(start, control, point) {
+            self.line_to(point);
+        }
+    }
+
+    /// Adds a contour, reversed if needed so that it winds clockwise on screen.
+    /// Contours added this way are unioned by the nonzero fill rule.
+    fn add_positive_contour(&mut self, mut contour: Vec<Point>) {
+        if signed_area(&contour) < 0.0 {
+            contour.reverse();
+        }
+        self.contours.push(contour);
+    }
+
+    pub fn contours(&self) -> &[Vec<Point>] {
+        &self.contours
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contours.iter().all(|contour| contour.len() < 2)
+    }
+}
+
+/// The flattened points of a quadratic curve, excluding its start point.
+pub fn flatten_curve(start: Point, control: Point, end: Point) -> impl Iterator<Item = Point> {
+    // The distance between the curve and its chord is a quarter of this,
+    // and it falls with the square of the number of segments.
+    let deviation = (start - control * 2.0 + end).length();
+    let segments = ((deviation / (8.0 * FLATTEN_TOLERANCE)).sqrt().ceil() as usize)
+        .max(1)
+        .min(100);
+    (1..=segments).map(move |i| {
+        let t = i as f32 / segments as f32;
+        let u = 1.0 - t;
+        start * (u * u) + control * (2.0 * u * t) + end * (t * t)
+    })
+}
+
+fn signed_area(points: &[Point]) -> f32 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+    area / 2.0
+}
+
+/// How the ends of an open stroke are drawn.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+/// How the corners of a stroke are drawn.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineJoin {
+    Round,
+    Bevel,
+    /// A miter, with the limit of its length as a multiple of the half width.
+    Miter(f32),
+}
+
+/// The style of a stroke.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub start_cap: LineCap,
+    pub end_cap: LineCap,
+    pub join: LineJoin,
+}
+
+/// Converts a flattened polyline into the polygon covered by stroking it.
+///
+/// The polygon is built from a quad for each segment, plus the joins and caps,
+/// which are unioned by filling with the nonzero rule.
+pub fn stroke_polyline(points: &[Point], is_closed: bool, style: &StrokeStyle, out: &mut Polygon) {
+    // Remove repeated points, which have no direction.
+    let mut polyline: Vec<Point> = Vec::with_capacity(points.len());
+    for &point in points {
+        if polyline
+            .last()
+            .map_or(true, |&last| (point - last).length() > 1e-4)
+        {
+            polyline.push(point);
+        }
+    }
+    if is_closed
+        && polyline.len() > 2
+        && (polyline[0] - polyline[polyline.len() - 1]).length() <= 1e-4
+    {
+        polyline.pop();
+    }
+
+    let half_width = style.width / 2.0;
+    if polyline.len() == 1 {
+        // A single point is only visible with round or square caps.
+        let point = polyline[0];
+        match style.start_cap {
+            LineCap::Round => out.add_positive_contour(circle(point, half_width)),
+            LineCap::Square => out.add_positive_contour(vec![
+                point + Point::new(-half_width, -half_width),
+                point + Point::new(half_width, -half_width),
+                point + Point::new(half_width, half_width),
+                point + Point::new(-half_width, half_width),
+            ]),
+            LineCap::Butt => (),
+        }
+        return;
+    }
+    if polyline.len() < 2 {
+        return;
+    }
+
+    let num_segments = if is_closed {
+        polyline.len()
+    } else {
+        polyline.len() - 1
+    };
+    for i in 0..num_segments {
+        let mut a = polyline[i];
+        let mut b = polyline[(i + 1) % polyline.len()];
+        let direction = unit(b - a);
+        if !is_closed {
+            if i == 0 && style.start_cap == LineCap::Square {
+                a = a - direction * half_width;
+            }
+            if i == num_segments - 1 && style.end_cap == LineCap::Square {
+                b = b + direction * half_width;
+            }
+        }
+        let normal = Point::new(-direction.y, direction.x) * half_width;
+        out.add_positive_contour(vec![a + normal, b + normal, b - normal, a - normal]);
+    }
+
+    // Joins between each pair of segments.
+    let joins = if is_closed {
+        0..polyline.len()
+    } else {
+        1..polyline.len() - 1
+    };
+    for i in joins {
+        let prev = polyline[(i + polyline.len() - 1) % polyline.len()];
+        let point = polyline[i];
+        let next = polyline[(i + 1) % polyline.len()];
+        add_join(prev, point, next, half_width, style.join, out);
+    }
+
+    if !is_closed {
+        if style.start_cap == LineCap::Round {
+            out.add_positive_contour(circle(polyline[0], half_width));
+        }
+        if style.end_cap == LineCap::Round {
+            out.add_positive_contour(circle(polyline[polyline.len() - 1], half_width));
+        }
+    }
+}
+
+fn add_join(
+    prev: Point,
+    point: Point,
+    next: Point,
+    half_width: f32,
+    join: LineJoin,
+    out: &mut Polygon,
+) {
+    let d0 = unit(point - prev);
+    let d1 = unit(next - point);
+    let cross = d0.x * d1.y - d0.y * d1.x;
+    let dot = d0.x * d1.x + d0.y * d1.y;
+    if cross.abs() < 1e-6 && dot > 0.0 {
+        // The segments are collinear, so there is no gap to fill.
+        return;
+    }
+    // The joins of flattened curves turn slightly, and any style of join looks the same.
+    let join = if cross.abs() < 0.1 && dot > 0.0 {
+        LineJoin::Bevel
+    } else {
+        join
+    };
+
+    // The outer side of the corner is to the left of a right turn.
+    let side = if cross > 0.0 { -1.0 } else { 1.0 };
+    let n0 = Point::new(-d0.y, d0.x) * (half_width * side);
+    let n1 = Point::new(-d1.y, d1.x) * (half_width * side);
+    match join {
+        LineJoin::Round => out.add_positive_contour(circle(point, half_width)),
+        LineJoin::Bevel => out.add_positive_contour(vec![point, point + n0, point + n1]),
+        LineJoin::Miter(limit) => {
+            // The miter extends to where the outer edges of the segments meet.
+            let bisector = n0 + n1;
+            let cos_half_angle = bisector.length() / (2.0 * half_width);
+            if cos_half_angle > 1e-4 && 1.0 / cos_half_angle <= limit {
+                let tip = point + unit(bisector) * (half_width / cos_half_angle);
+                out.add_positive_contour(vec![point, point + n0, tip, point + n1]);
+            } else {
+                out.add_positive_contour(vec![point, point + n0, point + n1]);
+            }
+        }
+    }
+}
+
+fn unit(point: Point) -> Point {
+    let length = point.length();
+    if length > 0.0 {
+        point * (1.0 / length)
+    } else {
+        Point::new(1.0, 0.0)
+    }
+}
+
+fn circle(center: Point, radius: f32) -> Vec<Point> {
+    let segments = ((radius * 2.0).ceil() as usize).max(8).min(64);
+    (0..segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::PI * 2.0;
+            center + Point::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// A non-horizontal edge of a polygon.
+#[derive(Debug)]
+struct Edge {
+    y_min: f32,
+    y_max: f32,
+    /// The x of the edge at `y_min`.
+    x: f32,
+    /// The change in x for each unit of y.
+    slope: f32,
+    /// +1 for edges going down, -1 for edges going up.
+    winding: i32,
+}
+
+/// Rasterizes a polygon, calling `span` with the coverage of each row of pixels it touches.
+///
+/// The coverage is between 0 and 1 for each pixel from the start of the span.
+/// Pixels outside of the `width` by `height` area are clipped.
+pub fn rasterize(
+    polygon: &Polygon,
+    fill_rule: FillRule,
+    width: usize,
+    height: usize,
+    mut span: impl FnMut(usize, usize, &[f32]),
+) {
+    let mut edges = vec![];
+    let mut x_min = f32::INFINITY;
+    let mut x_max = f32::NEG_INFINITY;
+    for contour in polygon.contours() {
+        for (i, &a) in contour.iter().enumerate() {
+            let b = contour[(i + 1) % contour.len()];
+            if !(a.x.is_finite() && a.y.is_finite() && b.x.is_finite() && b.y.is_finite()) {
+                continue;
+            }
+            x_min = x_min.min(a.x);
+            x_max = x_max.max(a.x);
+            #[allow(clippy::float_cmp)]
+            let is_horizontal = a.y == b.y;
+            if is_horizontal {
+                continue;
+            }
+            let (top, bottom, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+            let slope = (bottom.x - top.x) / (bottom.y - top.y);
+            edges.push(Edge {
+                y_min: top.y,
+                y_max: bottom.y,
+                x: top.x,
+                slope,
+                winding,
+            });
+        }
+    }
+    if edges.is_empty() || width == 0 || height == 0 {
+        return;
+    }
+    edges.sort_by(|a, b| a.y_min.partial_cmp(&b.y_min).unwrap());
+
+    let y_start = edges[0].y_min.floor().max(0.0) as usize;
+    let y_end = edges
+        .iter()
+        .map(|edge| edge.y_max)
+        .fold(f32::NEG_INFINITY, f32::max)
+        .ceil()
+        .min(height as f32);
+    if y_end <= y_start as f32 {
+        return;
+    }
+    let y_end = y_end as usize;
+    let span_start = x_min.floor().max(0.0).min(width as f32) as usize;
+    let span_end = x_max.ceil().max(0.0).min(width as f32) as usize;
+    if span_start >= span_end {
+        return;
+    }
+    let span_width = span_end - span_start;
+
+    let mut coverage = vec![0.0f32; span_width + 1];
+    let mut fill = vec![0.0f32; span_width + 1];
+    let mut active: Vec<usize> = vec![];
+    let mut next_edge = 0;
+    let mut crossings: Vec<(f32, i32)> = vec![];
+    let weight = 1.0 / SUBSAMPLES as f32;
+    let offset = span_start as f32;
+
+    for y in y_start..y_end {
+        let mut touched = false;
+        for sample in 0..SUBSAMPLES {
+            let sample_y = y as f32 + (sample as f32 + 0.5) / SUBSAMPLES as f32;
+
+            while next_edge < edges.len() && edges[next_edge].y_min <= sample_y {
+                active.push(next_edge);
+                next_edge += 1;
+            }
+            active.retain(|&i| edges[i].y_max > sample_y);
+
+            crossings.clear();
+            for &i in &active {
+                let edge = &edges[i];
+                if edge.y_min <= sample_y {
+                    let x = edge.x + (sample_y - edge.y_min) * edge.slope;
+                    crossings.push((x - offset, edge.winding));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = match fill_rule {
+                    FillRule::EvenOdd => winding % 2 != 0,
+                    FillRule::NonZero => winding != 0,
+                };
+                if inside {
+                    add_span(&mut coverage, &mut fill, pair[0].0, pair[1].0, weight);
+                    touched = true;
+                }
+            }
+        }
+
+        if touched {
+            // Add the fully covered pixels to the partial coverage at the ends of spans.
+            let mut accumulated = 0.0;
+            for (coverage, fill) in coverage.iter_mut().zip(fill.iter_mut()) {
+                accumulated += *fill;
+                *coverage = (*coverage + accumulated).min(1.0);
+                *fill = 0.0;
+            }
+            span(span_start, y, &coverage[..span_width]);
+            for coverage in &mut coverage {
+                *coverage = 0.0;
+            }
+        }
+    }
+}
+
+/// Adds the coverage of a span of a sub-scanline, clipped to the buffer.
+/// Fully covered pixels are added to `fill` as a difference, to be summed later.
+fn add_span(coverage: &mut [f32], fill: &mut [f32], x0: f32, x1: f32, weight: f32) {
+    let len = (coverage.len() - 1) as f32;
+    let x0 = x0.max(0.0).min(len);
+    let x1 = x1.max(0.0).min(len);
+    if x1 <= x0 {
+        return;
+    }
+    let start = x0 as usize;
+    let end = x1 as usize;
+    if start == end {
+        coverage[start] += (x1 - x0) * weight;
+    } else {
+        coverage[start] += (start as f32 + 1.0 - x0) * weight;
+        fill[start + 1] += weight;
+        fill[end] -= weight;
+        coverage[end] += (x1 - end as f32) * weight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(polygon: &Polygon, fill_rule: FillRule, width: usize, height: usize) -> Vec<f32> {
+        let mut out = vec![0.0; width * height];
+        rasterize(polygon, fill_rule, width, height, |x, y, coverage| {
+            out[y * width + x..y * width + x + coverage.len()].copy_from_slice(coverage);
+        });
+        out
+    }
+
+    fn rectangle(polygon: &mut Polygon, x0: f32, y0: f32, x1: f32, y1: f32) {
+        polygon.move_to(Point::new(x0, y0));
+        polygon.line_to(Point::new(x1, y0));
+        polygon.line_to(Point::new(x1, y1));
+        polygon.line_to(Point::new(x0, y1));
+    }
+
+    #[test]
+    fn rasterize_rectangle() {
+        let mut polygon = Polygon::new();
+        rectangle(&mut polygon, 1.0, 1.0, 3.5, 3.0);
+        let coverage = render(&polygon, FillRule::EvenOdd, 5, 4);
+        #[rustfmt::skip]
+        assert_eq!(
+            coverage,
+            vec![
+                0.0, 0.0, 0.0, 0.0, 0.0,
+                0.0, 1.0, 1.0, 0.5, 0.0,
+                0.0, 1.0, 1.0, 0.5, 0.0,
+                0.0, 0.0, 0.0, 0.0, 0.0,
+            ]
+        );
+    }
+
+    #[test]
+    fn rasterize_clips_to_bounds() {
+        let mut polygon = Polygon::new();
+        rectangle(&mut polygon, -10.0, -10.0, 10.0, 1.5);
+        let coverage = render(&polygon, FillRule::EvenOdd, 2, 3);
+        assert_eq!(coverage, vec![1.0, 1.0, 0.5, 0.5, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn rasterize_fill_rules() {
+        // Two overlapping rectangles with the same winding.
+        let mut polygon = Polygon::new();
+        rectangle(&mut polygon, 0.0, 0.0, 2.0, 1.0);
+        rectangle(&mut polygon, 1.0, 0.0, 3.0, 1.0);
+        assert_eq!(
+            render(&polygon, FillRule::EvenOdd, 3, 1),
+            vec![1.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            render(&polygon, FillRule::NonZero, 3, 1),
+            vec![1.0, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn flatten_curves() {
+        let start = Point::new(0.0, 0.0);
+        let end = Point::new(10.0, 0.0);
+        let straight: Vec<_> = flatten_curve(start, Point::new(5.0, 0.0), end).collect();
+        assert_eq!(straight, vec![end]);
+
+        let curved: Vec<_> = flatten_curve(start, Point::new(5.0, 10.0), end).collect();
+        assert!(curved.len() > 4);
+        assert_eq!(curved.last(), Some(&end));
+        assert!(curved.iter().all(|point| point.y >= 0.0 && point.y <= 5.0));
+    }
+
+    #[test]
+    fn stroke_line() {
+        let style = StrokeStyle {
+            width: 2.0,
+            start_cap: LineCap::Butt,
+            end_cap: LineCap::Butt,
+            join: LineJoin::Miter(3.0),
+        };
+        let mut polygon = Polygon::new();
+        stroke_polyline(
+            &[Point::new(1.0, 2.0), Point::new(4.0, 2.0)],
+            false,
+            &style,
+            &mut polygon,
+        );
+        let coverage = render(&polygon, FillRule::NonZero, 6, 4);
+        #[rustfmt::skip]
+        assert_eq!(
+            coverage,
+            vec![
+                0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+                0.0, 1.0, 1.0, 1.0, 0.0, 0.0,
+                0.0, 1.0, 1.0, 1.0, 0.0, 0.0,
+                0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            ]
+        );
+
+        // Square caps extend the line by half of its width.
+        let style = StrokeStyle {
+            start_cap: LineCap::Square,
+            end_cap: LineCap::Square,
+            ..style
+        };
+        let mut polygon = Polygon::new();
+        stroke_polyline(
+            &[Point::new(1.0, 2.0), Point::new(4.0, 2.0)],
+            false,
+            &style,
+            &mut polygon,
+        );
+        let coverage = render(&polygon, FillRule::NonZero, 6, 4);
+        assert_eq!(&coverage[6..12], &[1.0, 1.0, 1.0, 1.0, 1.0, 0.0]);
+    }
+}
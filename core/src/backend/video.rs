@@ -0,0 +1,172 @@
+//! Video decoding backends.
+
+use crate::backend::render::{BitmapInfo, RenderBackend};
+use generational_arena::{Arena, Index};
+use swf::{CharacterId, VideoCodec, VideoDeblocking};
+
+pub mod decoders;
+pub mod software;
+
+pub use software::SoftwareVideoBackend;
+
+pub type VideoStreamHandle = Index;
+
+type Error = Box<dyn std::error::Error>;
+
+/// An encoded frame of video, such as the data of a `VideoFrame` tag.
+#[derive(Clone, Copy, Debug)]
+pub struct EncodedFrame<'a> {
+    /// The codec that the frame is encoded with.
+    pub codec: VideoCodec,
+
+    /// The encoded frame data.
+    pub data: &'a [u8],
+
+    /// The number of the frame in its stream.
+    pub frame_id: u32,
+}
+
+/// Whether a frame of video can be decoded on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameDependency {
+    /// The frame is a keyframe, and can be decoded without any other frames.
+    None,
+
+    /// The frame is decoded from the frame before it.
+    Past,
+}
+
+impl FrameDependency {
+    pub fn is_keyframe(self) -> bool {
+        self == FrameDependency::None
+    }
+}
+
+/// A decoded frame of video in RGBA format.
+/// The pixels are not premultiplied by alpha.
+#[derive(Clone, Debug)]
+pub struct DecodedFrame {
+    pub width: u16,
+    pub height: u16,
+    pub rgba: Vec<u8>,
+}
+
+impl DecodedFrame {
+    /// Crops the frame to the given size, if it's larger.
+    pub fn crop(self, width: u16, height: u16) -> Self {
+        if width >= self.width && height >= self.height {
+            return self;
+        }
+        let (width, height) = (width.min(self.width), height.min(self.height));
+        let stride = usize::from(self.width) * 4;
+        let rgba = self
+            .rgba
+            .chunks_exact(stride)
+            .take(height.into())
+            .flat_map(|row| &row[..usize::from(width) * 4])
+            .copied()
+            .collect();
+        Self {
+            width,
+            height,
+            rgba,
+        }
+    }
+}
+
+pub trait VideoBackend {
+    /// Registers a video stream, such as the video of a `DefineVideoStream` tag.
+    /// Errors if the codec isn't supported.
+    fn register_video_stream(
+        &mut self,
+        id: CharacterId,
+        num_frames: u32,
+        size: (u16, u16),
+        codec: VideoCodec,
+        deblocking: VideoDeblocking,
+    ) -> Result<VideoStreamHandle, Error>;
+
+    /// Inspects a frame of a video stream as it is preloaded,
+    /// and returns whether it is a keyframe that seeking can start from.
+    fn preload_video_stream_frame(
+        &mut self,
+        stream: VideoStreamHandle,
+        encoded_frame: EncodedFrame<'_>,
+    ) -> Result<FrameDependency, Error>;
+
+    /// Decodes a frame of a video stream, and uploads it to the renderer.
+    ///
+    /// Frames must be decoded in order, starting from a keyframe.
+    /// Returns the bitmap containing the decoded frame.
+    fn decode_video_stream_frame(
+        &mut self,
+        stream: VideoStreamHandle,
+        encoded_frame: EncodedFrame<'_>,
+        renderer: &mut dyn RenderBackend,
+    ) -> Result<BitmapInfo, Error>;
+
+    /// Unregisters a video stream, and releases the bitmap that its frames were decoded to.
+    fn unregister_video_stream(
+        &mut self,
+        stream: VideoStreamHandle,
+        renderer: &mut dyn RenderBackend,
+    );
+}
+
+/// Video backend for platforms without video decoding.
+/// Video streams can be registered, but their frames can't be displayed.
+pub struct NullVideoBackend {
+    streams: Arena<()>,
+}
+
+impl NullVideoBackend {
+    pub fn new() -> Self {
+        Self {
+            streams: Arena::new(),
+        }
+    }
+}
+
+impl Default for NullVideoBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VideoBackend for NullVideoBackend {
+    fn register_video_stream(
+        &mut self,
+        _id: CharacterId,
+        _num_frames: u32,
+        _size: (u16, u16),
+        _codec: VideoCodec,
+        _deblocking: VideoDeblocking,
+    ) -> Result<VideoStreamHandle, Error> {
+        Ok(self.streams.insert(()))
+    }
+
+    fn preload_video_stream_frame(
+        &mut self,
+        _stream: VideoStreamHandle,
+        _encoded_frame: EncodedFrame<'_>,
+    ) -> Result<FrameDependency, Error> {
+        Ok(FrameDependency::None)
+    }
+
+    fn decode_video_stream_frame(
+        &mut self,
+        _stream: VideoStreamHandle,
+        _encoded_frame: EncodedFrame<'_>,
+        _renderer: &mut dyn RenderBackend,
+    ) -> Result<BitmapInfo, Error> {
+        Err("Video decoding is not supported".into())
+    }
+
+    fn unregister_video_stream(
+        &mut self,
+        stream: VideoStreamHandle,
+        _renderer: &mut dyn RenderBackend,
+    ) {
+        self.streams.remove(stream);
+    }
+}
@@ -0,0 +1,85 @@
+//! Video decoders.
+
+mod h263;
+mod screen;
+mod vp6;
+mod yuv;
+
+pub use h263::H263Decoder;
+pub use screen::ScreenVideoDecoder;
+pub use vp6::Vp6Decoder;
+
+use crate::backend::video::{DecodedFrame, EncodedFrame, FrameDependency};
+use swf::{VideoCodec, VideoDeblocking};
+
+type Error = Box<dyn std::error::Error>;
+
+/// A decoder for a single video stream.
+pub trait VideoDecoder {
+    /// Inspects a frame as it is preloaded, and returns whether it is a keyframe.
+    fn preload_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<FrameDependency, Error>;
+
+    /// Decodes a frame into RGBA pixels.
+    /// Frames are decoded in order, starting from a keyframe.
+    fn decode_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<DecodedFrame, Error>;
+}
+
+/// Creates a decoder for a video stream.
+/// The deblocking setting of the stream is passed to the decoder.
+pub type DecoderFactory = fn(VideoDeblocking) -> Box<dyn VideoDecoder>;
+
+/// The video decoders available for each codec.
+pub struct CodecRegistry {
+    factories: Vec<(VideoCodec, DecoderFactory)>,
+}
+
+impl CodecRegistry {
+    /// Creates a registry without any decoders.
+    pub fn new() -> Self {
+        Self { factories: vec![] }
+    }
+
+    /// Registers the decoder used for a codec, replacing any previous decoder for the codec.
+    pub fn register(&mut self, codec: VideoCodec, factory: DecoderFactory) {
+        self.factories.retain(|(other, _)| *other != codec);
+        self.factories.push((codec, factory));
+    }
+
+    /// Returns whether there is a decoder for a codec.
+    pub fn supports(&self, codec: VideoCodec) -> bool {
+        self.factories.iter().any(|(other, _)| *other == codec)
+    }
+
+    /// Creates a decoder for a codec, or `None` if the codec isn't supported.
+    pub fn create_decoder(
+        &self,
+        codec: VideoCodec,
+        deblocking: VideoDeblocking,
+    ) -> Option<Box<dyn VideoDecoder>> {
+        self.factories
+            .iter()
+            .find(|(other, _)| *other == codec)
+            .map(|(_, factory)| factory(deblocking))
+    }
+}
+
+impl Default for CodecRegistry {
+    /// Creates a registry with all of the built-in decoders.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(VideoCodec::H263, |deblocking| {
+            Box::new(H263Decoder::new(deblocking))
+        });
+        registry.register(VideoCodec::ScreenVideo, |_| {
+            Box::new(ScreenVideoDecoder::new())
+        });
+        registry.register(VideoCodec::ScreenVideoV2, |_| {
+            Box::new(ScreenVideoDecoder::new_v2())
+        });
+        registry.register(VideoCodec::VP6, |_| Box::new(Vp6Decoder::new()));
+        registry.register(VideoCodec::VP6WithAlpha, |_| {
+            Box::new(Vp6Decoder::with_alpha())
+        });
+        registry
+    }
+}
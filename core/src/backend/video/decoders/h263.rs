@@ -0,0 +1,869 @@
+//! Sorenson Spark video decoder.
+//!
+//! Sorenson Spark is baseline H.263 with a simpler picture header and without group of
+//! blocks headers. Besides intra and inter frames, it has disposable inter frames, which
+//! are never used to predict later frames.
+
+mod vlc;
+
+use crate::backend::video::decoders::yuv::{clamp_u8, Plane, YuvPicture, ZIGZAG};
+use crate::backend::video::decoders::VideoDecoder;
+use crate::backend::video::{DecodedFrame, EncodedFrame, FrameDependency};
+use bitstream_io::{BigEndian, BitReader};
+use swf::VideoDeblocking;
+use vlc::{
+    Tables, INTER_MCBPC_STUFFING, INTRA_MCBPC_STUFFING, TCOEF_ESCAPE, TCOEF_FIRST_LAST,
+    TCOEF_LEVEL, TCOEF_RUN,
+};
+
+type Error = Box<dyn std::error::Error>;
+
+type Reader<'a> = BitReader<&'a [u8], BigEndian>;
+
+/// The strength of the deblocking filter for each quantizer (table J.2).
+const DEBLOCKING_STRENGTH: [i32; 32] = [
+    0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5, 5, 5, 6, 6, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10, 10, 10, 11, 11, 11,
+    12, 12,
+];
+
+/// Rounding of the sum of four luma motion vectors to a chroma motion vector.
+const CHROMA_ROUNDING: [i32; 16] = [0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2];
+
+/// Decodes a Sorenson Spark video stream.
+pub struct H263Decoder {
+    deblocking: VideoDeblocking,
+    tables: Tables,
+
+    /// The basis functions of the inverse DCT.
+    idct: [[f32; 8]; 8],
+
+    /// The last intra or inter frame, which the next inter frame is predicted from.
+    reference: Option<YuvPicture>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PictureType {
+    Intra,
+    Inter,
+    DisposableInter,
+}
+
+#[derive(Clone, Debug)]
+struct PictureHeader {
+    version: u8,
+    width: u16,
+    height: u16,
+    picture_type: PictureType,
+    deblocking: bool,
+    quantizer: u8,
+}
+
+/// The properties of a decoded macroblock that the deblocking filter uses.
+#[derive(Clone, Copy, Debug)]
+struct MacroblockInfo {
+    quantizer: u8,
+    is_coded: bool,
+}
+
+impl H263Decoder {
+    pub fn new(deblocking: VideoDeblocking) -> Self {
+        let mut idct = [[0.0; 8]; 8];
+        for (x, row) in idct.iter_mut().enumerate() {
+            for (u, basis) in row.iter_mut().enumerate() {
+                let scale = if u == 0 {
+                    std::f32::consts::FRAC_1_SQRT_2
+                } else {
+                    1.0
+                };
+                let angle = (2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0;
+                *basis = scale / 2.0 * angle.cos();
+            }
+        }
+        Self {
+            deblocking,
+            tables: Tables::new(),
+            idct,
+            reference: None,
+        }
+    }
+
+    fn decode_picture(
+        &self,
+        data: &[u8],
+    ) -> Result<(PictureHeader, YuvPicture, Vec<MacroblockInfo>), Error> {
+        let mut reader = BitReader::new(data);
+        let header = read_picture_header(&mut reader)?;
+        let mb_width = (usize::from(header.width) + 15) / 16;
+        let mb_height = (usize::from(header.height) + 15) / 16;
+
+        let reference = if header.picture_type == PictureType::Intra {
+            None
+        } else {
+            let reference = self
+                .reference
+                .as_ref()
+                .ok_or("Inter frame without a reference frame")?;
+            if reference.y.width != mb_width * 16 || reference.y.height != mb_height * 16 {
+                return Err("Inter frame size differs from the reference frame".into());
+            }
+            Some(reference)
+        };
+
+        let decoder = PictureDecoder {
+            reader,
+            tables: &self.tables,
+            idct: &self.idct,
+            reference,
+            picture: YuvPicture::new(mb_width * 16, mb_height * 16),
+            version: header.version,
+            is_intra: header.picture_type == PictureType::Intra,
+            quantizer: header.quantizer,
+            mb_width,
+            motion_vectors: vec![(0, 0); mb_width * mb_height * 4],
+            macroblocks: Vec::with_capacity(mb_width * mb_height),
+        };
+        let (picture, macroblocks) = decoder.decode(mb_height)?;
+        Ok((header, picture, macroblocks))
+    }
+
+    fn is_deblocking_enabled(&self, header: &PictureHeader) -> bool {
+        match self.deblocking {
+            VideoDeblocking::UseVideoPacketValue => header.deblocking,
+            VideoDeblocking::None => false,
+            _ => true,
+        }
+    }
+}
+
+impl VideoDecoder for H263Decoder {
+    fn preload_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<FrameDependency, Error> {
+        let mut reader = BitReader::new(encoded_frame.data);
+        let header = read_picture_header(&mut reader)?;
+        if header.picture_type == PictureType::Intra {
+            Ok(FrameDependency::None)
+        } else {
+            Ok(FrameDependency::Past)
+        }
+    }
+
+    fn decode_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<DecodedFrame, Error> {
+        let (header, picture, macroblocks) = self.decode_picture(encoded_frame.data)?;
+        let (width, height) = (usize::from(header.width), usize::from(header.height));
+
+        // The deblocking filter is only applied to the output,
+        // so the filtered picture isn't used for prediction.
+        let rgba = if self.is_deblocking_enabled(&header) {
+            let mut filtered = picture.clone();
+            deblock(&mut filtered, &macroblocks);
+            filtered.to_rgba(width, height)
+        } else {
+            picture.to_rgba(width, height)
+        };
+
+        if header.picture_type != PictureType::DisposableInter {
+            self.reference = Some(picture);
+        }
+
+        Ok(DecodedFrame {
+            width: header.width,
+            height: header.height,
+            rgba,
+        })
+    }
+}
+
+fn read_picture_header(reader: &mut Reader<'_>) -> Result<PictureHeader, Error> {
+    if reader.read::<u32>(17)? != 1 {
+        return Err("Invalid picture start code".into());
+    }
+    let version = reader.read::<u8>(5)?;
+    if version > 1 {
+        return Err(format!("Unsupported Sorenson Spark version {}", version).into());
+    }
+    let _temporal_reference = reader.read::<u8>(8)?;
+    let (width, height) = match reader.read::<u8>(3)? {
+        0 => (reader.read::<u16>(8)?, reader.read::<u16>(8)?),
+        1 => (reader.read::<u16>(16)?, reader.read::<u16>(16)?),
+        2 => (352, 288),
+        3 => (176, 144),
+        4 => (128, 96),
+        5 => (320, 240),
+        6 => (160, 120),
+        _ => return Err("Invalid picture size".into()),
+    };
+    if width == 0 || height == 0 {
+        return Err("Invalid picture size".into());
+    }
+    let picture_type = match reader.read::<u8>(2)? {
+        0 => PictureType::Intra,
+        1 => PictureType::Inter,
+        2 => PictureType::DisposableInter,
+        _ => return Err("Invalid picture type".into()),
+    };
+    let deblocking = reader.read_bit()?;
+    let quantizer = reader.read::<u8>(5)?;
+    if quantizer == 0 {
+        return Err("Invalid quantizer".into());
+    }
+    // Extra insertion information, which is reserved.
+    while reader.read_bit()? {
+        reader.skip(8)?;
+    }
+    Ok(PictureHeader {
+        version,
+        width,
+        height,
+        picture_type,
+        deblocking,
+        quantizer,
+    })
+}
+
+/// The state of decoding the macroblocks of a picture.
+struct PictureDecoder<'a> {
+    reader: Reader<'a>,
+    tables: &'a Tables,
+    idct: &'a [[f32; 8]; 8],
+    reference: Option<&'a YuvPicture>,
+    picture: YuvPicture,
+    version: u8,
+    is_intra: bool,
+    quantizer: u8,
+    mb_width: usize,
+
+    /// The motion vector of each 8x8 luma block, in half pixels.
+    motion_vectors: Vec<(i32, i32)>,
+
+    macroblocks: Vec<MacroblockInfo>,
+}
+
+impl<'a> PictureDecoder<'a> {
+    fn decode(mut self, mb_height: usize) -> Result<(YuvPicture, Vec<MacroblockInfo>), Error> {
+        for mb_y in 0..mb_height {
+            for mb_x in 0..self.mb_width {
+                self.decode_macroblock(mb_x, mb_y)?;
+            }
+        }
+        Ok((self.picture, self.macroblocks))
+    }
+
+    fn decode_macroblock(&mut self, mb_x: usize, mb_y: usize) -> Result<(), Error> {
+        let (is_intra, has_quantizer_change, has_four_vectors, cbpc) = if self.is_intra {
+            let mcbpc = loop {
+                let mcbpc = self.tables.intra_mcbpc.read(&mut self.reader)?;
+                if mcbpc != INTRA_MCBPC_STUFFING {
+                    break mcbpc;
+                }
+            };
+            (true, mcbpc & 4 != 0, false, mcbpc & 3)
+        } else {
+            let mcbpc = loop {
+                let is_skipped = self.reader.read_bit()?;
+                if is_skipped {
+                    self.skip_macroblock(mb_x, mb_y);
+                    return Ok(());
+                }
+                let mcbpc = self.tables.inter_mcbpc.read(&mut self.reader)?;
+                if mcbpc != INTER_MCBPC_STUFFING {
+                    break mcbpc;
+                }
+            };
+            (mcbpc & 4 != 0, mcbpc & 8 != 0, mcbpc & 16 != 0, mcbpc & 3)
+        };
+
+        let mut cbpy = self.tables.cbpy.read(&mut self.reader)?;
+        if !is_intra {
+            cbpy ^= 0xf;
+        }
+        let coded_block_pattern = (cbpy << 2) | cbpc;
+
+        if has_quantizer_change {
+            const QUANTIZER_CHANGE: [i8; 4] = [-1, -2, 1, 2];
+            let change = QUANTIZER_CHANGE[self.reader.read::<u8>(2)? as usize];
+            self.quantizer = (self.quantizer as i8 + change).max(1).min(31) as u8;
+        }
+
+        let mut vectors = [(0, 0); 4];
+        if !is_intra {
+            if has_four_vectors {
+                for (block, vector) in vectors.iter_mut().enumerate() {
+                    *vector = self.read_motion_vector(mb_x, mb_y, block)?;
+                    self.set_motion_vector(mb_x, mb_y, block, *vector);
+                }
+            } else {
+                vectors = [self.read_motion_vector(mb_x, mb_y, 0)?; 4];
+            }
+        }
+        for (block, &vector) in vectors.iter().enumerate() {
+            self.set_motion_vector(mb_x, mb_y, block, vector);
+        }
+
+        self.macroblocks.push(MacroblockInfo {
+            quantizer: self.quantizer,
+            is_coded: true,
+        });
+
+        let chroma_vector = chroma_motion_vector(&vectors);
+        for block in 0..6 {
+            let is_coded = coded_block_pattern & (32 >> block) != 0;
+            let coefficients = self.read_block(is_intra, is_coded)?;
+            let vector = vectors.get(block).copied().unwrap_or(chroma_vector);
+            self.reconstruct_block(mb_x, mb_y, block, is_intra, vector, coefficients);
+        }
+        Ok(())
+    }
+
+    /// Copies a macroblock that isn't coded from the reference frame.
+    fn skip_macroblock(&mut self, mb_x: usize, mb_y: usize) {
+        for block in 0..4 {
+            self.set_motion_vector(mb_x, mb_y, block, (0, 0));
+        }
+        self.macroblocks.push(MacroblockInfo {
+            quantizer: self.quantizer,
+            is_coded: false,
+        });
+        for block in 0..6 {
+            self.reconstruct_block(mb_x, mb_y, block, false, (0, 0), None);
+        }
+    }
+
+    /// Reads the quantized transform coefficients of a block, and dequantizes them.
+    /// Returns `None` if the block has no coefficients.
+    fn read_block(&mut self, is_intra: bool, is_coded: bool) -> Result<Option<[i32; 64]>, Error> {
+        if !is_intra && !is_coded {
+            return Ok(None);
+        }
+
+        let mut coefficients = [0; 64];
+        let mut index = 0;
+        if is_intra {
+            let dc = self.reader.read::<u8>(8)?;
+            if dc == 0 || dc == 128 {
+                return Err("Invalid intra DC coefficient".into());
+            }
+            coefficients[0] = if dc == 255 { 1024 } else { i32::from(dc) * 8 };
+            index = 1;
+        }
+
+        if is_coded {
+            let quantizer = i32::from(self.quantizer);
+            loop {
+                let code = self.tables.tcoef.read(&mut self.reader)?;
+                let (is_last, run, level) = if code == TCOEF_ESCAPE {
+                    self.read_escaped_coefficient()?
+                } else {
+                    let level = i32::from(TCOEF_LEVEL[usize::from(code)]);
+                    let is_negative = self.reader.read_bit()?;
+                    (
+                        code >= TCOEF_FIRST_LAST,
+                        TCOEF_RUN[usize::from(code)],
+                        if is_negative { -level } else { level },
+                    )
+                };
+
+                index += usize::from(run);
+                if index >= 64 {
+                    return Err("Too many transform coefficients".into());
+                }
+                coefficients[ZIGZAG[index]] = dequantize(level, quantizer);
+                if is_last {
+                    break;
+                }
+                index += 1;
+            }
+        }
+        Ok(Some(coefficients))
+    }
+
+    /// Reads a transform coefficient that follows an escape code,
+    /// and returns whether it is the last coefficient, its run and its level.
+    fn read_escaped_coefficient(&mut self) -> Result<(bool, u8, i32), Error> {
+        if self.version == 0 {
+            let is_last = self.reader.read_bit()?;
+            let run = self.reader.read::<u8>(6)?;
+            let mut level = self.reader.read_signed::<i32>(8)?;
+            if level == -128 {
+                // An 11-bit level, with the 5 low bits first.
+                let low = self.reader.read::<i32>(5)?;
+                let high = self.reader.read_signed::<i32>(6)?;
+                level = high * 32 + low;
+            }
+            Ok((is_last, run, level))
+        } else {
+            // Version 1 can code levels in either 7 or 11 bits.
+            let is_long = self.reader.read_bit()?;
+            let is_last = self.reader.read_bit()?;
+            let run = self.reader.read::<u8>(6)?;
+            let level = self
+                .reader
+                .read_signed::<i32>(if is_long { 11 } else { 7 })?;
+            Ok((is_last, run, level))
+        }
+    }
+
+    fn read_motion_vector(
+        &mut self,
+        mb_x: usize,
+        mb_y: usize,
+        block: usize,
+    ) -> Result<(i32, i32), Error> {
+        let (predictor_x, predictor_y) = self.predict_motion_vector(mb_x, mb_y, block);
+        let x = self.read_motion_vector_component(predictor_x)?;
+        let y = self.read_motion_vector_component(predictor_y)?;
+        Ok((x, y))
+    }
+
+    fn read_motion_vector_component(&mut self, predictor: i32) -> Result<i32, Error> {
+        let difference = i32::from(self.tables.mvd.read(&mut self.reader)?);
+        if difference == 0 {
+            return Ok(predictor);
+        }
+        let is_negative = self.reader.read_bit()?;
+        let value = if is_negative {
+            predictor - difference
+        } else {
+            predictor + difference
+        };
+        // Motion vectors range from -16 to 15.5 pixels, and wrap around outside of it.
+        Ok(((value + 32) & 63) - 32)
+    }
+
+    /// Predicts the motion vector of a luma block from the median of the vectors of the
+    /// blocks to its left, above it, and above and to its right.
+    fn predict_motion_vector(&self, mb_x: usize, mb_y: usize, block: usize) -> (i32, i32) {
+        const ABOVE_RIGHT_OFFSET: [isize; 4] = [2, 1, 1, -1];
+        let blocks_width = self.mb_width * 2;
+        let x = mb_x * 2 + (block & 1);
+        let y = mb_y * 2 + (block >> 1);
+
+        let left = if x > 0 {
+            self.motion_vectors[y * blocks_width + x - 1]
+        } else {
+            (0, 0)
+        };
+        if y == 0 {
+            // The blocks above are outside of the picture, so they're predicted as the left block.
+            return left;
+        }
+        let above = self.motion_vectors[(y - 1) * blocks_width + x];
+        let above_right_x = x as isize + ABOVE_RIGHT_OFFSET[block];
+        let above_right = if above_right_x < blocks_width as isize {
+            self.motion_vectors[(y - 1) * blocks_width + above_right_x as usize]
+        } else {
+            (0, 0)
+        };
+        (
+            median(left.0, above.0, above_right.0),
+            median(left.1, above.1, above_right.1),
+        )
+    }
+
+    fn set_motion_vector(&mut self, mb_x: usize, mb_y: usize, block: usize, vector: (i32, i32)) {
+        let x = mb_x * 2 + (block & 1);
+        let y = mb_y * 2 + (block >> 1);
+        self.motion_vectors[y * self.mb_width * 2 + x] = vector;
+    }
+
+    /// Reconstructs a block from its prediction and its transform coefficients.
+    fn reconstruct_block(
+        &mut self,
+        mb_x: usize,
+        mb_y: usize,
+        block: usize,
+        is_intra: bool,
+        vector: (i32, i32),
+        coefficients: Option<[i32; 64]>,
+    ) {
+        let (x, y) = if block < 4 {
+            (mb_x * 16 + (block & 1) * 8, mb_y * 16 + (block >> 1) * 8)
+        } else {
+            (mb_x * 8, mb_y * 8)
+        };
+
+        let mut samples = [0; 64];
+        if let Some(coefficients) = coefficients {
+            self.inverse_transform(&coefficients, &mut samples);
+        }
+        if !is_intra {
+            if let Some(reference) = self.reference {
+                add_prediction(block_plane(reference, block), x, y, vector, &mut samples);
+            }
+        }
+
+        let plane = match block {
+            0..=3 => &mut self.picture.y,
+            4 => &mut self.picture.u,
+            _ => &mut self.picture.v,
+        };
+        for (i, &sample) in samples.iter().enumerate() {
+            plane.set(x + i % 8, y + i / 8, clamp_u8(sample));
+        }
+    }
+
+    fn inverse_transform(&self, coefficients: &[i32; 64], samples: &mut [i32; 64]) {
+        let mut rows = [0.0; 64];
+        for y in 0..8 {
+            for x in 0..8 {
+                rows[y * 8 + x] = (0..8)
+                    .map(|u| self.idct[x][u] * coefficients[y * 8 + u] as f32)
+                    .sum();
+            }
+        }
+        for y in 0..8 {
+            for x in 0..8 {
+                let sample: f32 = (0..8).map(|v| self.idct[y][v] * rows[v * 8 + x]).sum();
+                samples[y * 8 + x] = sample.round() as i32;
+            }
+        }
+    }
+}
+
+fn block_plane(picture: &YuvPicture, block: usize) -> &Plane {
+    match block {
+        0..=3 => &picture.y,
+        4 => &picture.u,
+        _ => &picture.v,
+    }
+}
+
+/// Adds the motion compensated prediction of an 8x8 block to its samples.
+/// Vectors may point outside of the reference plane, which is extended from its edges.
+fn add_prediction(
+    reference: &Plane,
+    x: usize,
+    y: usize,
+    (vector_x, vector_y): (i32, i32),
+    samples: &mut [i32; 64],
+) {
+    let source_x = x as isize + (vector_x >> 1) as isize;
+    let source_y = y as isize + (vector_y >> 1) as isize;
+    let is_half_x = vector_x & 1 != 0;
+    let is_half_y = vector_y & 1 != 0;
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let x = source_x + (i % 8) as isize;
+        let y = source_y + (i / 8) as isize;
+        let get = |x, y| i32::from(reference.get_clamped(x, y));
+        *sample += match (is_half_x, is_half_y) {
+            (false, false) => get(x, y),
+            (true, false) => (get(x, y) + get(x + 1, y) + 1) >> 1,
+            (false, true) => (get(x, y) + get(x, y + 1) + 1) >> 1,
+            (true, true) => {
+                (get(x, y) + get(x + 1, y) + get(x, y + 1) + get(x + 1, y + 1) + 2) >> 2
+            }
+        };
+    }
+}
+
+/// Derives the motion vector of the chroma blocks from the vectors of the luma blocks.
+fn chroma_motion_vector(vectors: &[(i32, i32); 4]) -> (i32, i32) {
+    let round = |sum: i32| CHROMA_ROUNDING[(sum & 15) as usize] + ((sum >> 3) & !1);
+    let sum_x = vectors.iter().map(|v| v.0).sum();
+    let sum_y = vectors.iter().map(|v| v.1).sum();
+    (round(sum_x), round(sum_y))
+}
+
+fn dequantize(level: i32, quantizer: i32) -> i32 {
+    if level == 0 {
+        return 0;
+    }
+    let mut magnitude = quantizer * (2 * level.abs() + 1);
+    if quantizer % 2 == 0 {
+        magnitude -= 1;
+    }
+    (level.signum() * magnitude).max(-2048).min(2047)
+}
+
+fn median(a: i32, b: i32, c: i32) -> i32 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+/// Applies the deblocking filter of Annex J to the block edges of a picture.
+fn deblock(picture: &mut YuvPicture, macroblocks: &[MacroblockInfo]) {
+    let mb_width = picture.y.width / 16;
+    deblock_plane(&mut picture.y, macroblocks, mb_width, 16);
+    deblock_plane(&mut picture.u, macroblocks, mb_width, 8);
+    deblock_plane(&mut picture.v, macroblocks, mb_width, 8);
+}
+
+fn deblock_plane(
+    plane: &mut Plane,
+    macroblocks: &[MacroblockInfo],
+    mb_width: usize,
+    mb_size: usize,
+) {
+    let macroblock = |x: usize, y: usize| macroblocks[(y / mb_size) * mb_width + x / mb_size];
+
+    // Edges between blocks that aren't coded are left alone.
+    // The strength of the filter depends on the quantizer of the block below or to the right.
+    for y in (8..plane.height).step_by(8) {
+        for x in 0..plane.width {
+            let (above, below) = (macroblock(x, y - 1), macroblock(x, y));
+            if above.is_coded || below.is_coded {
+                let strength = DEBLOCKING_STRENGTH[usize::from(below.quantizer)];
+                let samples = [
+                    plane.get(x, y - 2),
+                    plane.get(x, y - 1),
+                    plane.get(x, y),
+                    plane.get(x, y + 1),
+                ];
+                let samples = filter_edge(samples, strength);
+                for (i, &sample) in samples.iter().enumerate() {
+                    plane.set(x, y + i - 2, sample);
+                }
+            }
+        }
+    }
+
+    for x in (8..plane.width).step_by(8) {
+        for y in 0..plane.height {
+            let (left, right) = (macroblock(x - 1, y), macroblock(x, y));
+            if left.is_coded || right.is_coded {
+                let strength = DEBLOCKING_STRENGTH[usize::from(right.quantizer)];
+                let samples = [
+                    plane.get(x - 2, y),
+                    plane.get(x - 1, y),
+                    plane.get(x, y),
+                    plane.get(x + 1, y),
+                ];
+                let samples = filter_edge(samples, strength);
+                for (i, &sample) in samples.iter().enumerate() {
+                    plane.set(x + i - 2, y, sample);
+                }
+            }
+        }
+    }
+}
+
+/// Filters the two samples on each side of a block edge.
+fn filter_edge(samples: [u8; 4], strength: i32) -> [u8; 4] {
+    let [a, b, c, d] = [
+        i32::from(samples[0]),
+        i32::from(samples[1]),
+        i32::from(samples[2]),
+        i32::from(samples[3]),
+    ];
+    let delta = (a - 4 * b + 4 * c - d) / 8;
+    let ramp = delta.signum() * (delta.abs() - (2 * (delta.abs() - strength)).max(0)).max(0);
+    let limit = ramp.abs() / 2;
+    let outer = ((a - d) / 4).max(-limit).min(limit);
+    [
+        clamp_u8(a - outer),
+        clamp_u8(b + ramp),
+        clamp_u8(c - ramp),
+        clamp_u8(d + outer),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::vlc::{CBPY, INTER_MCBPC, INTRA_MCBPC, MVD, TCOEF};
+    use super::*;
+    use crate::flv::{Flv, TagData};
+    use bitstream_io::BitWriter;
+    use swf::VideoCodec;
+
+    /// The frames of the video in `swf/tests/swfs/DefineVideoStream.swf`: an 8x8 red
+    /// intra frame, followed by inter frames that skip their only macroblock.
+    const RED_FRAME: [u8; 14] = [0, 0, 132, 0, 4, 4, 17, 38, 190, 190, 190, 190, 201, 182];
+    const SKIPPED_FRAMES: [[u8; 8]; 3] = [
+        [0, 0, 132, 4, 4, 4, 49, 32],
+        [0, 0, 132, 8, 4, 4, 49, 32],
+        [0, 0, 132, 12, 4, 4, 49, 32],
+    ];
+
+    type Writer<'a> = BitWriter<&'a mut Vec<u8>, BigEndian>;
+
+    fn encoded_frame(data: &[u8], frame_id: u32) -> EncodedFrame<'_> {
+        EncodedFrame {
+            codec: VideoCodec::H263,
+            data,
+            frame_id,
+        }
+    }
+
+    /// Writes the header of a 16x16 picture without deblocking.
+    fn write_header(writer: &mut Writer, picture_type: u8, quantizer: u8) {
+        writer.write(17, 1u32).unwrap();
+        writer.write(5, 1u8).unwrap();
+        writer.write(8, 0u8).unwrap();
+        writer.write(3, 0u8).unwrap();
+        writer.write(8, 16u8).unwrap();
+        writer.write(8, 16u8).unwrap();
+        writer.write(2, picture_type).unwrap();
+        writer.write_bit(false).unwrap();
+        writer.write(5, quantizer).unwrap();
+        writer.write_bit(false).unwrap();
+    }
+
+    fn write_code(writer: &mut Writer, (code, len): (u16, u8)) {
+        writer.write(u32::from(len), code).unwrap();
+    }
+
+    /// An intra frame whose top-left luma block has a horizontal gradient,
+    /// getting darker from left to right. The rest of the picture is flat.
+    fn gradient_frame() -> Vec<u8> {
+        let mut data = vec![];
+        let mut writer = BitWriter::endian(&mut data, BigEndian);
+        write_header(&mut writer, 0, 8);
+        write_code(&mut writer, INTRA_MCBPC[0]);
+        // Only the first luma block has AC coefficients.
+        write_code(&mut writer, CBPY[0b1000]);
+        writer.write(8, 100u8).unwrap();
+        // The last coefficient, with a run of 0 and a level of 3.
+        write_code(&mut writer, TCOEF[60]);
+        writer.write_bit(false).unwrap();
+        for _ in 1..6 {
+            writer.write(8, 100u8).unwrap();
+        }
+        writer.byte_align().unwrap();
+        data
+    }
+
+    /// An inter frame whose only macroblock moves one pixel to the right,
+    /// without any transform coefficients.
+    fn moved_frame(picture_type: u8) -> Vec<u8> {
+        let mut data = vec![];
+        let mut writer = BitWriter::endian(&mut data, BigEndian);
+        write_header(&mut writer, picture_type, 8);
+        writer.write_bit(false).unwrap();
+        write_code(&mut writer, INTER_MCBPC[0]);
+        write_code(&mut writer, CBPY[0b1111]);
+        // A motion vector of 2 half pixels to the right.
+        write_code(&mut writer, MVD[2]);
+        writer.write_bit(false).unwrap();
+        write_code(&mut writer, MVD[0]);
+        writer.byte_align().unwrap();
+        data
+    }
+
+    fn reference_luma(decoder: &H263Decoder) -> Plane {
+        decoder.reference.as_ref().unwrap().y.clone()
+    }
+
+    #[test]
+    fn h263_intra_frame() {
+        let mut decoder = H263Decoder::new(VideoDeblocking::UseVideoPacketValue);
+        let dependency = decoder.preload_frame(encoded_frame(&RED_FRAME, 0)).unwrap();
+        assert_eq!(dependency, FrameDependency::None);
+
+        let frame = decoder.decode_frame(encoded_frame(&RED_FRAME, 0)).unwrap();
+        assert_eq!((frame.width, frame.height), (8, 8));
+        assert_eq!(frame.rgba.len(), 8 * 8 * 4);
+        for pixel in frame.rgba.chunks(4) {
+            assert_eq!(pixel, [237, 29, 36, 255]);
+        }
+    }
+
+    #[test]
+    fn h263_skipped_macroblocks() {
+        let mut decoder = H263Decoder::new(VideoDeblocking::UseVideoPacketValue);
+        let keyframe = decoder.decode_frame(encoded_frame(&RED_FRAME, 0)).unwrap();
+        for (i, data) in SKIPPED_FRAMES.iter().enumerate() {
+            let frame_id = i as u32 + 1;
+            let dependency = decoder
+                .preload_frame(encoded_frame(data, frame_id))
+                .unwrap();
+            assert_eq!(dependency, FrameDependency::Past);
+            let frame = decoder.decode_frame(encoded_frame(data, frame_id)).unwrap();
+            assert_eq!(frame.rgba, keyframe.rgba);
+        }
+    }
+
+    #[test]
+    fn h263_flv_frames() {
+        // The same video as an FLV file, as written by FFmpeg.
+        let data = include_bytes!("../../../../../swf/tests/swfs/dummy.flv");
+        let flv = Flv::parse(data).unwrap();
+        let frames: Vec<_> = flv
+            .tags
+            .iter()
+            .filter_map(|tag| match &tag.data {
+                TagData::Video(video) => Some(video),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(frames.len(), 4);
+
+        let mut decoder = H263Decoder::new(VideoDeblocking::UseVideoPacketValue);
+        for (i, video) in frames.iter().enumerate() {
+            assert_eq!(video.codec, Some(VideoCodec::H263));
+            assert_eq!(video.is_keyframe, i == 0);
+            let frame = decoder
+                .decode_frame(encoded_frame(&video.data, i as u32))
+                .unwrap();
+            assert_eq!((frame.width, frame.height), (8, 8));
+            for pixel in frame.rgba.chunks(4) {
+                assert_eq!(pixel, [237, 29, 36, 255]);
+            }
+        }
+    }
+
+    #[test]
+    fn h263_inter_frame_without_reference() {
+        let mut decoder = H263Decoder::new(VideoDeblocking::UseVideoPacketValue);
+        assert!(decoder
+            .decode_frame(encoded_frame(&SKIPPED_FRAMES[0], 1))
+            .is_err());
+    }
+
+    #[test]
+    fn h263_transform_coefficients() {
+        let mut decoder = H263Decoder::new(VideoDeblocking::None);
+        let frame = decoder
+            .decode_frame(encoded_frame(&gradient_frame(), 0))
+            .unwrap();
+        assert_eq!((frame.width, frame.height), (16, 16));
+
+        let luma = reference_luma(&decoder);
+        for y in 0..8 {
+            for x in 0..7 {
+                assert!(luma.get(x, y) >= luma.get(x + 1, y));
+                assert_eq!(luma.get(x, y), luma.get(x, 0));
+            }
+            assert!(luma.get(0, y) > 105);
+            assert!(luma.get(7, y) < 95);
+        }
+        for y in 8..16 {
+            for x in 8..16 {
+                assert_eq!(luma.get(x, y), 100);
+            }
+        }
+    }
+
+    #[test]
+    fn h263_motion_compensation() {
+        let mut decoder = H263Decoder::new(VideoDeblocking::None);
+        decoder
+            .decode_frame(encoded_frame(&gradient_frame(), 0))
+            .unwrap();
+        let keyframe = reference_luma(&decoder);
+
+        decoder
+            .decode_frame(encoded_frame(&moved_frame(1), 1))
+            .unwrap();
+        let moved = reference_luma(&decoder);
+        for y in 0..16 {
+            for x in 0..16 {
+                assert_eq!(moved.get(x, y), keyframe.get((x + 1).min(15), y));
+            }
+        }
+    }
+
+    #[test]
+    fn h263_disposable_frame() {
+        let mut decoder = H263Decoder::new(VideoDeblocking::None);
+        let keyframe = decoder
+            .decode_frame(encoded_frame(&gradient_frame(), 0))
+            .unwrap();
+        let disposable = decoder
+            .decode_frame(encoded_frame(&moved_frame(2), 1))
+            .unwrap();
+        assert_ne!(disposable.rgba, keyframe.rgba);
+
+        // The next frame is predicted from the keyframe.
+        let frame = decoder
+            .decode_frame(encoded_frame(&moved_frame(1), 2))
+            .unwrap();
+        assert_eq!(frame.rgba, disposable.rgba);
+    }
+}
@@ -0,0 +1,328 @@
+//! The variable length codes of H.263.
+
+use bitstream_io::{BigEndian, BitReader};
+use std::io::Read;
+
+type Error = Box<dyn std::error::Error>;
+
+/// A decoding tree for a table of variable length codes.
+/// Each code decodes to its index in the table.
+pub struct Vlc {
+    nodes: Vec<[Node; 2]>,
+}
+
+#[derive(Clone, Copy)]
+enum Node {
+    Invalid,
+    Branch(usize),
+    Leaf(u8),
+}
+
+impl Vlc {
+    /// Builds the tree from a table of `(code, length)` pairs.
+    /// Entries with a length of 0 are unused.
+    pub fn new(codes: &[(u16, u8)]) -> Self {
+        let mut nodes = vec![[Node::Invalid; 2]];
+        for (value, &(code, len)) in codes.iter().enumerate() {
+            if len == 0 {
+                continue;
+            }
+            let mut node = 0;
+            for i in (1..len).rev() {
+                let bit = usize::from((code >> i) & 1);
+                node = match nodes[node][bit] {
+                    Node::Branch(next) => next,
+                    _ => {
+                        nodes.push([Node::Invalid; 2]);
+                        let next = nodes.len() - 1;
+                        nodes[node][bit] = Node::Branch(next);
+                        next
+                    }
+                };
+            }
+            nodes[node][usize::from(code & 1)] = Node::Leaf(value as u8);
+        }
+        Self { nodes }
+    }
+
+    /// Reads a code from the bitstream, and returns its value.
+    pub fn read<R: Read>(&self, reader: &mut BitReader<R, BigEndian>) -> Result<u8, Error> {
+        let mut node = 0;
+        loop {
+            let bit = reader.read_bit()?;
+            match self.nodes[node][bit as usize] {
+                Node::Branch(next) => node = next,
+                Node::Leaf(value) => return Ok(value),
+                Node::Invalid => return Err("Invalid variable length code".into()),
+            }
+        }
+    }
+}
+
+/// The decoding trees for each of the tables.
+pub struct Tables {
+    pub intra_mcbpc: Vlc,
+    pub inter_mcbpc: Vlc,
+    pub cbpy: Vlc,
+    pub mvd: Vlc,
+    pub tcoef: Vlc,
+}
+
+impl Tables {
+    pub fn new() -> Self {
+        Self {
+            intra_mcbpc: Vlc::new(&INTRA_MCBPC),
+            inter_mcbpc: Vlc::new(&INTER_MCBPC),
+            cbpy: Vlc::new(&CBPY),
+            mvd: Vlc::new(&MVD),
+            tcoef: Vlc::new(&TCOEF),
+        }
+    }
+}
+
+/// MCBPC codes of I-pictures (table 7).
+/// The value is the chroma coded block pattern, with bit 2 set for MB type 4 (INTRA+Q).
+/// Value 8 is stuffing.
+pub const INTRA_MCBPC: [(u16, u8); 9] = [
+    (1, 1),
+    (1, 3),
+    (2, 3),
+    (3, 3),
+    (1, 4),
+    (1, 6),
+    (2, 6),
+    (3, 6),
+    (1, 9),
+];
+
+pub const INTRA_MCBPC_STUFFING: u8 = 8;
+
+/// MCBPC codes of P-pictures (table 8).
+/// The value is the chroma coded block pattern, with bit 2 set for intra macroblocks,
+/// bit 3 set for a quantizer change and bit 4 set for four motion vectors.
+/// Value 20 is stuffing.
+pub const INTER_MCBPC: [(u16, u8); 28] = [
+    // INTER
+    (1, 1),
+    (3, 4),
+    (2, 4),
+    (5, 6),
+    // INTRA
+    (3, 5),
+    (4, 8),
+    (3, 8),
+    (3, 7),
+    // INTER+Q
+    (3, 3),
+    (7, 7),
+    (6, 7),
+    (5, 9),
+    // INTRA+Q
+    (4, 6),
+    (4, 9),
+    (3, 9),
+    (2, 9),
+    // INTER4V
+    (2, 3),
+    (5, 7),
+    (4, 7),
+    (5, 8),
+    // Stuffing
+    (1, 9),
+    (0, 0),
+    (0, 0),
+    (0, 0),
+    // INTER4V+Q
+    (2, 11),
+    (12, 13),
+    (14, 13),
+    (15, 13),
+];
+
+pub const INTER_MCBPC_STUFFING: u8 = 20;
+
+/// CBPY codes (table 13), by the coded block pattern of intra macroblocks.
+/// The pattern is inverted for inter macroblocks.
+pub const CBPY: [(u16, u8); 16] = [
+    (3, 4),
+    (5, 5),
+    (4, 5),
+    (9, 4),
+    (3, 5),
+    (7, 4),
+    (2, 6),
+    (11, 4),
+    (2, 5),
+    (3, 6),
+    (5, 4),
+    (10, 4),
+    (4, 4),
+    (8, 4),
+    (6, 4),
+    (3, 2),
+];
+
+/// Motion vector difference codes (table 14), by magnitude in half pixels.
+/// The sign follows every code except 0.
+pub const MVD: [(u16, u8); 33] = [
+    (1, 1),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (3, 6),
+    (5, 7),
+    (4, 7),
+    (3, 7),
+    (11, 9),
+    (10, 9),
+    (9, 9),
+    (17, 10),
+    (16, 10),
+    (15, 10),
+    (14, 10),
+    (13, 10),
+    (12, 10),
+    (11, 10),
+    (10, 10),
+    (9, 10),
+    (8, 10),
+    (7, 10),
+    (6, 10),
+    (5, 10),
+    (4, 10),
+    (7, 11),
+    (6, 11),
+    (5, 11),
+    (4, 11),
+    (3, 11),
+    (2, 11),
+    (3, 12),
+    (2, 12),
+];
+
+/// Transform coefficient codes (table 16).
+/// The sign of the level follows every code except the escape code.
+pub const TCOEF: [(u16, u8); 103] = [
+    (0x2, 2),
+    (0xf, 4),
+    (0x15, 6),
+    (0x17, 7),
+    (0x1f, 8),
+    (0x25, 9),
+    (0x24, 9),
+    (0x21, 10),
+    (0x20, 10),
+    (0x7, 11),
+    (0x6, 11),
+    (0x20, 11),
+    (0x6, 3),
+    (0x14, 6),
+    (0x1e, 8),
+    (0xf, 10),
+    (0x21, 11),
+    (0x50, 12),
+    (0xe, 4),
+    (0x1d, 8),
+    (0xe, 10),
+    (0x51, 12),
+    (0xd, 5),
+    (0x23, 9),
+    (0xd, 10),
+    (0xc, 5),
+    (0x22, 9),
+    (0x52, 12),
+    (0xb, 5),
+    (0xc, 10),
+    (0x53, 12),
+    (0x13, 6),
+    (0xb, 10),
+    (0x54, 12),
+    (0x12, 6),
+    (0xa, 10),
+    (0x11, 6),
+    (0x9, 10),
+    (0x10, 6),
+    (0x8, 10),
+    (0x16, 7),
+    (0x55, 12),
+    (0x15, 7),
+    (0x14, 7),
+    (0x1c, 8),
+    (0x1b, 8),
+    (0x21, 9),
+    (0x20, 9),
+    (0x1f, 9),
+    (0x1e, 9),
+    (0x1d, 9),
+    (0x1c, 9),
+    (0x1b, 9),
+    (0x1a, 9),
+    (0x22, 11),
+    (0x23, 11),
+    (0x56, 12),
+    (0x57, 12),
+    (0x7, 4),
+    (0x19, 9),
+    (0x5, 11),
+    (0xf, 6),
+    (0x4, 11),
+    (0xe, 6),
+    (0xd, 6),
+    (0xc, 6),
+    (0x13, 7),
+    (0x12, 7),
+    (0x11, 7),
+    (0x10, 7),
+    (0x1a, 8),
+    (0x19, 8),
+    (0x18, 8),
+    (0x17, 8),
+    (0x16, 8),
+    (0x15, 8),
+    (0x14, 8),
+    (0x13, 8),
+    (0x18, 9),
+    (0x17, 9),
+    (0x16, 9),
+    (0x15, 9),
+    (0x14, 9),
+    (0x13, 9),
+    (0x12, 9),
+    (0x11, 9),
+    (0x7, 10),
+    (0x6, 10),
+    (0x5, 10),
+    (0x4, 10),
+    (0x24, 11),
+    (0x25, 11),
+    (0x26, 11),
+    (0x27, 11),
+    (0x58, 12),
+    (0x59, 12),
+    (0x5a, 12),
+    (0x5b, 12),
+    (0x5c, 12),
+    (0x5d, 12),
+    (0x5e, 12),
+    (0x5f, 12),
+    (0x3, 7),
+];
+
+pub const TCOEF_ESCAPE: u8 = 102;
+
+/// The first transform coefficient code that ends a block.
+pub const TCOEF_FIRST_LAST: u8 = 58;
+
+pub const TCOEF_RUN: [u8; 102] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 4, 4, 4, 5, 5, 5, 6,
+    6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
+    0, 0, 0, 1, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
+    24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40,
+];
+
+pub const TCOEF_LEVEL: [u8; 102] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 1, 2, 3, 1, 2, 3, 1, 2, 3,
+    1, 2, 3, 1, 2, 1, 2, 1, 2, 1, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 3, 1, 2,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1,
+];
@@ -0,0 +1,592 @@
+//! Screen Video decoder.
+//!
+//! Screen Video divides each frame into blocks of zlib compressed pixels. Blocks that haven't
+//! changed since the previous frame are left out. Screen Video 2 adds a hybrid palette format,
+//! blocks that only code the rows that differ from the last keyframe, and blocks that are
+//! compressed with a zlib dictionary primed from another block.
+
+use crate::backend::video::decoders::VideoDecoder;
+use crate::backend::video::{DecodedFrame, EncodedFrame, FrameDependency};
+use std::io::Read;
+
+type Error = Box<dyn std::error::Error>;
+
+/// The size of the zlib window, which limits how far back a primed block can refer.
+const ZLIB_WINDOW_SIZE: usize = 32768;
+
+/// The palette of Screen Video 2 blocks in the hybrid format, as RGB colors.
+const DEFAULT_PALETTE: [u32; 128] = [
+    0x000000, 0x333333, 0x666666, 0x999999, 0xCCCCCC, 0xFFFFFF, 0x330000, 0x660000, 0x990000,
+    0xCC0000, 0xFF0000, 0x003300, 0x006600, 0x009900, 0x00CC00, 0x00FF00, 0x000033, 0x000066,
+    0x000099, 0x0000CC, 0x0000FF, 0x333300, 0x666600, 0x999900, 0xCCCC00, 0xFFFF00, 0x003333,
+    0x006666, 0x009999, 0x00CCCC, 0x00FFFF, 0x330033, 0x660066, 0x990099, 0xCC00CC, 0xFF00FF,
+    0xFFFF33, 0xFFFF66, 0xFFFF99, 0xFFFFCC, 0xFF33FF, 0xFF66FF, 0xFF99FF, 0xFFCCFF, 0x33FFFF,
+    0x66FFFF, 0x99FFFF, 0xCCFFFF, 0xCCCC33, 0xCCCC66, 0xCCCC99, 0xCCCCFF, 0xCC33CC, 0xCC66CC,
+    0xCC99CC, 0xCCFFCC, 0x33CCCC, 0x66CCCC, 0x99CCCC, 0xFFCCCC, 0x999933, 0x999966, 0x9999CC,
+    0x9999FF, 0x993399, 0x996699, 0x99CC99, 0x99FF99, 0x339999, 0x669999, 0xCC9999, 0xFF9999,
+    0x666633, 0x666699, 0x6666CC, 0x6666FF, 0x663366, 0x669966, 0x66CC66, 0x66FF66, 0x336666,
+    0x996666, 0xCC6666, 0xFF6666, 0x333366, 0x333399, 0x3333CC, 0x3333FF, 0x336633, 0x339933,
+    0x33CC33, 0x33FF33, 0x663333, 0x993333, 0xCC3333, 0xFF3333, 0x003366, 0x336600, 0x660033,
+    0x006633, 0x330066, 0x663300, 0x336699, 0x669933, 0x993366, 0x339966, 0x663399, 0x996633,
+    0x6699CC, 0x99CC66, 0xCC6699, 0x66CC99, 0x9966CC, 0xCC9966, 0x99CCFF, 0xCCFF99, 0xFF99CC,
+    0x99FFCC, 0xCC99FF, 0xFFCC99, 0x111111, 0x222222, 0x444444, 0x555555, 0xAAAAAA, 0xBBBBBB,
+    0xDDDDDD, 0xEEEEEE,
+];
+
+/// Decodes a Screen Video or Screen Video 2 stream.
+pub struct ScreenVideoDecoder {
+    is_v2: bool,
+    width: usize,
+    height: usize,
+
+    /// The current frame, in RGBA.
+    image: Vec<u8>,
+
+    /// The last keyframe, which the diff blocks of Screen Video 2 are relative to.
+    keyframe: Vec<u8>,
+
+    /// The decompressed blocks of the last keyframe, which Screen Video 2 blocks can be
+    /// primed with.
+    keyframe_blocks: Vec<Option<Vec<u8>>>,
+
+    palette: [[u8; 3]; 128],
+}
+
+/// The frame header and the blocks of a video packet.
+struct Packet<'a> {
+    block_width: usize,
+    block_height: usize,
+    width: usize,
+    height: usize,
+    palette: Option<BlockData<'a>>,
+    blocks: Vec<Option<BlockData<'a>>>,
+}
+
+/// A block that has changed since the previous frame.
+struct BlockData<'a> {
+    format: PixelFormat,
+
+    /// The first row and the number of rows that are coded, from the bottom of the block.
+    /// The other rows are copied from the last keyframe.
+    diff_rows: Option<(usize, usize)>,
+
+    prime: Option<Prime>,
+    compressed: &'a [u8],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PixelFormat {
+    /// 24-bit colors in BGR order.
+    Bgr,
+
+    /// 7-bit palette indices and 15-bit colors.
+    Hybrid,
+}
+
+/// The block whose decompressed data primes the zlib dictionary of a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Prime {
+    /// The same block of the last keyframe.
+    Keyframe,
+
+    /// A block of the current frame, by column and row.
+    Current(usize, usize),
+}
+
+impl ScreenVideoDecoder {
+    pub fn new() -> Self {
+        Self::with_version(false)
+    }
+
+    pub fn new_v2() -> Self {
+        Self::with_version(true)
+    }
+
+    fn with_version(is_v2: bool) -> Self {
+        let mut palette = [[0; 3]; 128];
+        for (entry, &color) in palette.iter_mut().zip(DEFAULT_PALETTE.iter()) {
+            *entry = [(color >> 16) as u8, (color >> 8) as u8, color as u8];
+        }
+        Self {
+            is_v2,
+            width: 0,
+            height: 0,
+            image: vec![],
+            keyframe: vec![],
+            keyframe_blocks: vec![],
+            palette,
+        }
+    }
+
+    fn read_packet<'a>(&self, mut data: &'a [u8]) -> Result<Packet<'a>, Error> {
+        let horizontal = read_u16(&mut data)?;
+        let vertical = read_u16(&mut data)?;
+        let block_width = (usize::from(horizontal >> 12) + 1) * 16;
+        let width = usize::from(horizontal & 0xfff);
+        let block_height = (usize::from(vertical >> 12) + 1) * 16;
+        let height = usize::from(vertical & 0xfff);
+        if width == 0 || height == 0 {
+            return Err("Invalid video size".into());
+        }
+
+        let mut palette = None;
+        if self.is_v2 {
+            let flags = read_bytes(&mut data, 1)?[0];
+            if flags & 0b10 != 0 {
+                return Err("Screen Video 2 I-frame images are not supported".into());
+            }
+            if flags & 0b1 != 0 {
+                palette = self.read_block(&mut data)?;
+            }
+        }
+
+        let columns = (width + block_width - 1) / block_width;
+        let rows = (height + block_height - 1) / block_height;
+        let mut blocks = Vec::with_capacity(columns * rows);
+        for _ in 0..columns * rows {
+            blocks.push(self.read_block(&mut data)?);
+        }
+
+        Ok(Packet {
+            block_width,
+            block_height,
+            width,
+            height,
+            palette,
+            blocks,
+        })
+    }
+
+    /// Reads a block, or returns `None` if the block hasn't changed.
+    fn read_block<'a>(&self, data: &mut &'a [u8]) -> Result<Option<BlockData<'a>>, Error> {
+        let size = usize::from(read_u16(data)?);
+        if size == 0 {
+            return Ok(None);
+        }
+        let mut block = read_bytes(data, size)?;
+        if !self.is_v2 {
+            return Ok(Some(BlockData {
+                format: PixelFormat::Bgr,
+                diff_rows: None,
+                prime: None,
+                compressed: block,
+            }));
+        }
+
+        let flags = read_bytes(&mut block, 1)?[0];
+        let format = match (flags >> 3) & 0b11 {
+            0 => PixelFormat::Bgr,
+            2 => PixelFormat::Hybrid,
+            _ => return Err("Invalid Screen Video 2 color depth".into()),
+        };
+        let diff_rows = if flags & 0b100 != 0 {
+            let header = read_bytes(&mut block, 2)?;
+            Some((usize::from(header[0]), usize::from(header[1])))
+        } else {
+            None
+        };
+        let prime = if flags & 0b10 != 0 {
+            let header = read_bytes(&mut block, 2)?;
+            Some(Prime::Current(
+                usize::from(header[0]),
+                usize::from(header[1]),
+            ))
+        } else if flags & 0b1 != 0 {
+            Some(Prime::Keyframe)
+        } else {
+            None
+        };
+        Ok(Some(BlockData {
+            format,
+            diff_rows,
+            prime,
+            compressed: block,
+        }))
+    }
+
+    /// Decompresses a block of the current frame.
+    fn decompress_block(
+        &self,
+        packet: &Packet<'_>,
+        index: usize,
+        block: &BlockData<'_>,
+        decompressed_blocks: &[Option<Vec<u8>>],
+    ) -> Result<Vec<u8>, Error> {
+        let dictionary = match block.prime {
+            None => return inflate(block.compressed),
+            Some(Prime::Keyframe) => self.keyframe_blocks.get(index),
+            Some(Prime::Current(column, row)) => {
+                let columns = packet.columns();
+                if column < columns {
+                    decompressed_blocks.get(row * columns + column)
+                } else {
+                    None
+                }
+            }
+        };
+        match dictionary {
+            Some(Some(dictionary)) => inflate_primed(dictionary, block.compressed),
+            _ => Err("Missing block for zlib priming".into()),
+        }
+    }
+
+    /// Writes the decompressed rows of a block to the image.
+    /// `y` is the position of the bottom of the block, from the bottom of the image.
+    #[allow(clippy::too_many_arguments)]
+    fn write_block(
+        &mut self,
+        pixels: &[u8],
+        format: PixelFormat,
+        x: usize,
+        y: usize,
+        width: usize,
+        first_row: usize,
+        num_rows: usize,
+    ) -> Result<(), Error> {
+        let mut pixels = pixels;
+        for row in first_row..first_row + num_rows {
+            let image_y = self.height - 1 - (y + row);
+            let line = &mut self.image[(image_y * self.width + x) * 4..][..width * 4];
+            match format {
+                PixelFormat::Bgr => {
+                    let source = read_bytes(&mut pixels, width * 3)?;
+                    for (pixel, bgr) in line.chunks_mut(4).zip(source.chunks(3)) {
+                        pixel[..3].copy_from_slice(&[bgr[2], bgr[1], bgr[0]]);
+                    }
+                }
+                PixelFormat::Hybrid => {
+                    for pixel in line.chunks_mut(4) {
+                        let first = read_bytes(&mut pixels, 1)?[0];
+                        if first & 0x80 != 0 {
+                            // A 15-bit color, with 5 bits each of red, green and blue.
+                            let second = read_bytes(&mut pixels, 1)?[0];
+                            let color = (u16::from(first & 0x7f) << 8) | u16::from(second);
+                            let expand = |c: u16| ((c << 3) | (c >> 2)) as u8;
+                            pixel[0] = expand((color >> 10) & 0x1f);
+                            pixel[1] = expand((color >> 5) & 0x1f);
+                            pixel[2] = expand(color & 0x1f);
+                        } else {
+                            pixel[..3].copy_from_slice(&self.palette[usize::from(first)]);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the rows of a block from the last keyframe.
+    fn copy_keyframe_block(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        for row in 0..height {
+            let image_y = self.height - 1 - (y + row);
+            let start = (image_y * self.width + x) * 4;
+            let end = start + width * 4;
+            self.image[start..end].copy_from_slice(&self.keyframe[start..end]);
+        }
+    }
+
+    fn set_palette(&mut self, block: &BlockData<'_>) -> Result<(), Error> {
+        let palette = inflate(block.compressed)?;
+        for (entry, bgr) in self.palette.iter_mut().zip(palette.chunks_exact(3)) {
+            *entry = [bgr[2], bgr[1], bgr[0]];
+        }
+        Ok(())
+    }
+}
+
+impl Default for ScreenVideoDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// The number of blocks in each row of the frame.
+    fn columns(&self) -> usize {
+        (self.width + self.block_width - 1) / self.block_width
+    }
+
+    /// Returns whether every block of the frame is coded without the previous frames.
+    fn is_keyframe(&self) -> bool {
+        self.blocks.iter().all(|block| match block {
+            Some(block) => block.diff_rows.is_none() && block.prime != Some(Prime::Keyframe),
+            None => false,
+        })
+    }
+}
+
+impl VideoDecoder for ScreenVideoDecoder {
+    fn preload_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<FrameDependency, Error> {
+        let packet = self.read_packet(encoded_frame.data)?;
+        if packet.is_keyframe() {
+            Ok(FrameDependency::None)
+        } else {
+            Ok(FrameDependency::Past)
+        }
+    }
+
+    fn decode_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<DecodedFrame, Error> {
+        let packet = self.read_packet(encoded_frame.data)?;
+        let is_keyframe = packet.is_keyframe();
+        if packet.width != self.width || packet.height != self.height {
+            if !is_keyframe {
+                return Err("Inter frame size differs from the previous frame".into());
+            }
+            self.width = packet.width;
+            self.height = packet.height;
+            self.image = [0, 0, 0, 255].repeat(self.width * self.height);
+            self.keyframe = vec![];
+            self.keyframe_blocks = vec![];
+        }
+
+        if let Some(palette) = &packet.palette {
+            self.set_palette(palette)?;
+        }
+
+        let columns = packet.columns();
+        let mut decompressed_blocks = vec![None; packet.blocks.len()];
+        for (index, block) in packet.blocks.iter().enumerate() {
+            let block = match block {
+                Some(block) => block,
+                None => continue,
+            };
+
+            // Blocks are ordered from the bottom left of the image.
+            let x = (index % columns) * packet.block_width;
+            let y = (index / columns) * packet.block_height;
+            let width = packet.block_width.min(packet.width - x);
+            let height = packet.block_height.min(packet.height - y);
+
+            let (first_row, num_rows) = block.diff_rows.unwrap_or((0, height));
+            if first_row + num_rows > height {
+                return Err("Invalid Screen Video 2 diff block".into());
+            }
+            if block.diff_rows.is_some() {
+                if self.keyframe.is_empty() {
+                    return Err("Diff block without a keyframe".into());
+                }
+                self.copy_keyframe_block(x, y, width, height);
+            }
+
+            let pixels = self.decompress_block(&packet, index, block, &decompressed_blocks)?;
+            self.write_block(&pixels, block.format, x, y, width, first_row, num_rows)?;
+            decompressed_blocks[index] = Some(pixels);
+        }
+
+        if is_keyframe && self.is_v2 {
+            self.keyframe = self.image.clone();
+            self.keyframe_blocks = decompressed_blocks;
+        }
+
+        Ok(DecodedFrame {
+            width: self.width as u16,
+            height: self.height as u16,
+            rgba: self.image.clone(),
+        })
+    }
+}
+
+fn read_u16(data: &mut &[u8]) -> Result<u16, Error> {
+    let bytes = read_bytes(data, 2)?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_bytes<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if data.len() < len {
+        return Err("Unexpected end of video packet".into());
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+fn inflate(compressed: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut data = vec![];
+    let mut decoder = libflate::zlib::Decoder::new(compressed)?;
+    decoder.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Decompresses a block whose zlib stream continues a stream that compressed the dictionary.
+///
+/// The block has no zlib header, and can refer back to the dictionary. The dictionary is
+/// prepended to it as uncompressed deflate blocks, so that it's in the window when the block
+/// is decompressed.
+fn inflate_primed(dictionary: &[u8], compressed: &[u8]) -> Result<Vec<u8>, Error> {
+    let dictionary = &dictionary[dictionary.len().saturating_sub(ZLIB_WINDOW_SIZE)..];
+    let mut stream = Vec::with_capacity(dictionary.len() + compressed.len() + 5);
+    for chunk in dictionary.chunks(0xffff) {
+        let len = chunk.len() as u16;
+        // A stored block that isn't the final block.
+        stream.push(0);
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+    stream.extend_from_slice(compressed);
+
+    let mut data = vec![];
+    let mut decoder = libflate::deflate::Decoder::new(&stream[..]);
+    decoder.read_to_end(&mut data)?;
+    if data.len() < dictionary.len() {
+        return Err("Invalid primed block".into());
+    }
+    Ok(data.split_off(dictionary.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use swf::VideoCodec;
+
+    fn encoded_frame(data: &[u8]) -> EncodedFrame<'_> {
+        EncodedFrame {
+            codec: VideoCodec::ScreenVideo,
+            data,
+            frame_id: 0,
+        }
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = libflate::zlib::Encoder::new(vec![]).unwrap();
+        encoder.write_all(data).unwrap();
+        encoder.finish().into_result().unwrap()
+    }
+
+    /// Writes the header of a frame with 16x16 blocks.
+    fn packet_header(width: u16, height: u16) -> Vec<u8> {
+        [width.to_be_bytes(), height.to_be_bytes()].concat()
+    }
+
+    fn push_block(packet: &mut Vec<u8>, block: &[u8]) {
+        packet.extend_from_slice(&(block.len() as u16).to_be_bytes());
+        packet.extend_from_slice(block);
+    }
+
+    /// A block of a single BGR color.
+    fn solid_block(width: usize, height: usize, bgr: [u8; 3]) -> Vec<u8> {
+        deflate(&bgr.repeat(width * height))
+    }
+
+    fn pixel(frame: &DecodedFrame, x: usize, y: usize) -> &[u8] {
+        let i = (y * usize::from(frame.width) + x) * 4;
+        &frame.rgba[i..i + 4]
+    }
+
+    #[test]
+    fn screen_video_keyframe() {
+        // A 20x18 frame has 2x2 blocks, with partial blocks on the right and the top.
+        let mut packet = packet_header(20, 18);
+        push_block(&mut packet, &solid_block(16, 16, [0, 0, 255]));
+        push_block(&mut packet, &solid_block(4, 16, [0, 255, 0]));
+        push_block(&mut packet, &solid_block(16, 2, [255, 0, 0]));
+        push_block(&mut packet, &solid_block(4, 2, [255, 255, 255]));
+
+        let mut decoder = ScreenVideoDecoder::new();
+        let dependency = decoder.preload_frame(encoded_frame(&packet)).unwrap();
+        assert_eq!(dependency, FrameDependency::None);
+
+        let frame = decoder.decode_frame(encoded_frame(&packet)).unwrap();
+        assert_eq!((frame.width, frame.height), (20, 18));
+        assert_eq!(pixel(&frame, 0, 17), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 19, 2), [0, 255, 0, 255]);
+        assert_eq!(pixel(&frame, 0, 1), [0, 0, 255, 255]);
+        assert_eq!(pixel(&frame, 19, 0), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn screen_video_unchanged_blocks() {
+        let mut keyframe = packet_header(32, 16);
+        push_block(&mut keyframe, &solid_block(16, 16, [0, 0, 255]));
+        push_block(&mut keyframe, &solid_block(16, 16, [0, 0, 255]));
+        let mut inter_frame = packet_header(32, 16);
+        push_block(&mut inter_frame, &[]);
+        push_block(&mut inter_frame, &solid_block(16, 16, [255, 0, 0]));
+
+        let mut decoder = ScreenVideoDecoder::new();
+        assert!(decoder.decode_frame(encoded_frame(&inter_frame)).is_err());
+        decoder.decode_frame(encoded_frame(&keyframe)).unwrap();
+        let dependency = decoder.preload_frame(encoded_frame(&inter_frame)).unwrap();
+        assert_eq!(dependency, FrameDependency::Past);
+
+        let frame = decoder.decode_frame(encoded_frame(&inter_frame)).unwrap();
+        assert_eq!(pixel(&frame, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 16, 0), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn screen_video_v2_hybrid_palette() {
+        // Palette index 10 is red, followed by a 15-bit blue.
+        let mut pixels = vec![];
+        for _ in 0..8 {
+            pixels.extend_from_slice(&[10; 16]);
+            for _ in 0..16 {
+                pixels.extend_from_slice(&[0x80, 0x1f]);
+            }
+        }
+        let mut block = vec![0b10_000];
+        block.extend(deflate(&pixels));
+
+        let mut packet = packet_header(16, 16);
+        packet.push(0);
+        push_block(&mut packet, &block);
+
+        let mut decoder = ScreenVideoDecoder::new_v2();
+        let frame = decoder.decode_frame(encoded_frame(&packet)).unwrap();
+        assert_eq!(pixel(&frame, 0, 15), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 0, 14), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn screen_video_v2_diff_block() {
+        let mut keyframe = packet_header(16, 16);
+        keyframe.push(0);
+        let mut block = vec![0];
+        block.extend(solid_block(16, 16, [0, 0, 255]));
+        push_block(&mut keyframe, &block);
+
+        // Only the bottom two rows change.
+        let mut inter_frame = packet_header(16, 16);
+        inter_frame.push(0);
+        let mut block = vec![0b100, 0, 2];
+        block.extend(solid_block(16, 2, [255, 0, 0]));
+        push_block(&mut inter_frame, &block);
+
+        let mut decoder = ScreenVideoDecoder::new_v2();
+        assert!(decoder.decode_frame(encoded_frame(&inter_frame)).is_err());
+        decoder.decode_frame(encoded_frame(&keyframe)).unwrap();
+        let dependency = decoder.preload_frame(encoded_frame(&inter_frame)).unwrap();
+        assert_eq!(dependency, FrameDependency::Past);
+
+        let frame = decoder.decode_frame(encoded_frame(&inter_frame)).unwrap();
+        assert_eq!(pixel(&frame, 0, 15), [0, 0, 255, 255]);
+        assert_eq!(pixel(&frame, 0, 14), [0, 0, 255, 255]);
+        assert_eq!(pixel(&frame, 0, 13), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn screen_video_v2_primed_block() {
+        let mut keyframe = packet_header(16, 16);
+        keyframe.push(0);
+        let mut block = vec![0];
+        block.extend(solid_block(16, 16, [0, 0, 255]));
+        push_block(&mut keyframe, &block);
+
+        // The continuation of a zlib stream: a final stored block, then the checksum.
+        let pixels = [0, 255, 0].repeat(256);
+        let mut block = vec![0b1, 1];
+        block.extend_from_slice(&(pixels.len() as u16).to_le_bytes());
+        block.extend_from_slice(&(!(pixels.len() as u16)).to_le_bytes());
+        block.extend_from_slice(&pixels);
+        block.extend_from_slice(&[0; 4]);
+        let mut inter_frame = packet_header(16, 16);
+        inter_frame.push(0);
+        push_block(&mut inter_frame, &block);
+
+        let mut decoder = ScreenVideoDecoder::new_v2();
+        decoder.decode_frame(encoded_frame(&keyframe)).unwrap();
+        let frame = decoder.decode_frame(encoded_frame(&inter_frame)).unwrap();
+        assert_eq!(pixel(&frame, 8, 8), [0, 255, 0, 255]);
+    }
+}
@@ -0,0 +1,1456 @@
+//! On2 VP6 video decoder.
+//!
+//! VP6 codes most of a frame with a boolean range coder, whose probabilities are adapted
+//! to the video by the frames themselves. Inter frames are predicted from the previous
+//! frame, or from the golden frame, which is kept until a later frame replaces it.
+//!
+//! VP6 with alpha contains a second VP6 stream in each frame, whose luma is the alpha
+//! channel of the video.
+
+mod dsp;
+mod huffman;
+mod models;
+mod range_coder;
+mod tables;
+
+use crate::backend::video::decoders::yuv::{clamp_u8, Plane, YuvPicture, ZIGZAG};
+use crate::backend::video::decoders::VideoDecoder;
+use crate::backend::video::{DecodedFrame, EncodedFrame, FrameDependency};
+use bitstream_io::{BigEndian, BitReader};
+use huffman::HuffmanTree;
+use models::Model;
+use range_coder::RangeDecoder;
+use tables::*;
+
+type Error = Box<dyn std::error::Error>;
+
+type Reader<'a> = BitReader<&'a [u8], BigEndian>;
+
+/// The luma block to the left of each block of a macroblock, or the chroma block for
+/// chroma blocks, as an index of the blocks to the left of the macroblock.
+const LEFT_BLOCK: [usize; 6] = [0, 0, 1, 1, 2, 3];
+
+/// The plane of each block of a macroblock.
+const BLOCK_PLANE: [usize; 6] = [0, 0, 0, 0, 1, 2];
+
+/// Decodes a VP6 video stream, which may have an alpha channel.
+pub struct Vp6Decoder {
+    color: StreamDecoder,
+
+    /// The decoder of the alpha channel, for VP6 with alpha.
+    alpha: Option<StreamDecoder>,
+}
+
+impl Vp6Decoder {
+    pub fn new() -> Self {
+        Self {
+            color: StreamDecoder::new(),
+            alpha: None,
+        }
+    }
+
+    /// Creates a decoder for VP6 with an alpha channel.
+    pub fn with_alpha() -> Self {
+        Self {
+            color: StreamDecoder::new(),
+            alpha: Some(StreamDecoder::new()),
+        }
+    }
+
+    /// Splits a frame into the data of the color stream and of the alpha stream.
+    /// Frames with alpha start with the 24-bit size of the color data.
+    fn split_frame<'a>(&self, data: &'a [u8]) -> Result<(&'a [u8], Option<&'a [u8]>), Error> {
+        if self.alpha.is_none() {
+            return Ok((data, None));
+        }
+        if data.len() < 3 {
+            return Err("Missing offset to the VP6 alpha channel".into());
+        }
+        let offset = usize::from(data[0]) << 16 | usize::from(data[1]) << 8 | usize::from(data[2]);
+        let data = &data[3..];
+        if offset > data.len() {
+            return Err("Invalid offset to the VP6 alpha channel".into());
+        }
+        Ok((&data[..offset], Some(&data[offset..])))
+    }
+}
+
+impl Default for Vp6Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VideoDecoder for Vp6Decoder {
+    fn preload_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<FrameDependency, Error> {
+        let (data, _) = self.split_frame(encoded_frame.data)?;
+        match data.first() {
+            Some(byte) if byte & 0x80 == 0 => Ok(FrameDependency::None),
+            Some(_) => Ok(FrameDependency::Past),
+            None => Err("Empty VP6 frame".into()),
+        }
+    }
+
+    fn decode_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<DecodedFrame, Error> {
+        let (color_data, alpha_data) = self.split_frame(encoded_frame.data)?;
+        let picture = self.color.decode(color_data, true)?;
+        let (width, height) = (picture.y.width, picture.y.height);
+        let mut rgba = picture.to_rgba(width, height);
+
+        if let (Some(alpha), Some(alpha_data)) = (&mut self.alpha, alpha_data) {
+            // Only the luma of the alpha stream is used.
+            let alpha_picture = alpha.decode(alpha_data, false)?;
+            if alpha_picture.y.width != width || alpha_picture.y.height != height {
+                return Err("VP6 alpha channel size differs from the video".into());
+            }
+            for (pixel, &alpha) in rgba.chunks_exact_mut(4).zip(&alpha_picture.y.data) {
+                pixel[3] = alpha;
+            }
+        }
+
+        Ok(DecodedFrame {
+            width: width as u16,
+            height: height as u16,
+            rgba,
+        })
+    }
+}
+
+/// How luma predictions are interpolated between pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FilterMode {
+    Bilinear,
+    Bicubic,
+
+    /// Bicubic, except for long motion vectors and flat blocks.
+    Adaptive,
+}
+
+/// The settings of the filters applied to predictions, which persist between frames.
+#[derive(Clone, Copy, Debug)]
+struct PredictionFilter {
+    /// Whether block edges are smoothed in the area that is predicted from.
+    deblocking: bool,
+    mode: FilterMode,
+
+    /// Adaptive filtering uses bilinear filtering for blocks with less variance than this.
+    variance_threshold: i32,
+
+    /// Adaptive filtering uses bilinear filtering for motion vectors longer than this.
+    max_vector_length: i32,
+
+    /// The set of bicubic filters to use.
+    selection: usize,
+}
+
+/// The properties of a frame that are parsed from its header.
+#[derive(Clone, Copy, Debug)]
+struct FrameHeader {
+    is_keyframe: bool,
+
+    /// Whether the frame replaces the golden frame.
+    is_golden: bool,
+
+    /// Whether the coefficients are Huffman coded, when they are in a separate partition.
+    use_huffman: bool,
+
+    /// The offset of the partition of the coefficients, from the start of the frame.
+    coeff_offset: Option<usize>,
+}
+
+/// Decodes a single VP6 bitstream.
+struct StreamDecoder {
+    /// The version of the bitstream, or 0 before the first keyframe.
+    sub_version: u8,
+
+    /// Whether frames have the header of the advanced profile, which codes the filters.
+    has_filter_header: bool,
+    interlaced: bool,
+    mb_width: usize,
+    mb_height: usize,
+    quantizer: usize,
+    filter: PredictionFilter,
+    model: Model,
+
+    /// The last decoded frame.
+    previous: Option<YuvPicture>,
+
+    /// The last keyframe or golden frame.
+    golden: Option<YuvPicture>,
+}
+
+impl StreamDecoder {
+    fn new() -> Self {
+        Self {
+            sub_version: 0,
+            has_filter_header: false,
+            interlaced: false,
+            mb_width: 0,
+            mb_height: 0,
+            quantizer: 0,
+            filter: PredictionFilter {
+                deblocking: true,
+                mode: FilterMode::Bilinear,
+                variance_threshold: 0,
+                max_vector_length: 0,
+                selection: 16,
+            },
+            model: Model::new(false),
+            previous: None,
+            golden: None,
+        }
+    }
+
+    /// Decodes a frame, returning the decoded picture.
+    /// Chroma blocks are parsed but not rendered unless `render_chroma` is set.
+    fn decode(&mut self, data: &[u8], render_chroma: bool) -> Result<&YuvPicture, Error> {
+        let (header, mut decoder) = self.parse_header(data)?;
+        if header.is_keyframe {
+            self.model = Model::new(self.interlaced);
+        } else {
+            self.model.parse_mb_type_models(&mut decoder);
+            self.model.parse_vector_models(&mut decoder);
+        }
+        self.model
+            .parse_coeff_models(&mut decoder, header.is_keyframe);
+        if !header.use_huffman {
+            self.model.update_dcct();
+        }
+
+        let mut coefficients = match header.coeff_offset {
+            None => Coefficients::Main,
+            Some(offset) => {
+                let data = data
+                    .get(offset..)
+                    .ok_or("Invalid offset to the VP6 coefficients")?;
+                if header.use_huffman {
+                    Coefficients::Huffman(BitReader::new(data), HuffmanTrees::new(&self.model))
+                } else {
+                    Coefficients::Range(RangeDecoder::new(data))
+                }
+            }
+        };
+
+        let frame_decoder = FrameDecoder::new(self, header.is_keyframe, render_chroma);
+        let picture = frame_decoder.decode(&mut decoder, &mut coefficients)?;
+        if header.is_keyframe || header.is_golden {
+            self.golden = Some(picture.clone());
+        }
+        self.previous = Some(picture);
+        Ok(self.previous.as_ref().unwrap())
+    }
+
+    /// Parses the header of a frame, and the settings that follow it in the range coded
+    /// data. Returns the range decoder of the rest of the frame.
+    fn parse_header<'a>(
+        &mut self,
+        data: &'a [u8],
+    ) -> Result<(FrameHeader, RangeDecoder<'a>), Error> {
+        let first_byte = *data.first().ok_or("Empty VP6 frame")?;
+        let is_keyframe = first_byte & 0x80 == 0;
+        let has_separated_coeffs = first_byte & 1 != 0;
+        self.quantizer = usize::from((first_byte >> 1) & 0x3f);
+
+        let mut parse_filter_info = false;
+        let mut variance_shift = 0;
+        let (coeff_offset, mut decoder, is_golden) = if is_keyframe {
+            let second_byte = *data.get(1).ok_or("Truncated VP6 frame header")?;
+            let sub_version = second_byte >> 3;
+            if sub_version > 8 {
+                return Err(format!("Unsupported VP6 version {}", sub_version).into());
+            }
+            self.sub_version = sub_version;
+            self.has_filter_header = second_byte & 0x06 != 0;
+            self.interlaced = second_byte & 1 != 0;
+
+            let (coeff_offset, position) = if has_separated_coeffs || !self.has_filter_header {
+                (read_coeff_offset(data.get(2..4))?, 4)
+            } else {
+                (None, 2)
+            };
+            // The size in macroblocks, and then the displayed size, which is ignored.
+            let size = data
+                .get(position..position + 4)
+                .ok_or("Truncated VP6 frame header")?;
+            if size[0] == 0 || size[1] == 0 {
+                return Err("Invalid VP6 frame size".into());
+            }
+            self.mb_height = usize::from(size[0]);
+            self.mb_width = usize::from(size[1]);
+
+            let mut decoder = RangeDecoder::new(&data[position + 4..]);
+            // The scaling mode, which is ignored.
+            decoder.read_bits(2);
+            parse_filter_info = self.has_filter_header;
+            if sub_version < 8 {
+                variance_shift = 5;
+            }
+            (coeff_offset, decoder, false)
+        } else {
+            if self.previous.is_none() {
+                return Err("Inter frame without a reference frame".into());
+            }
+            let (coeff_offset, position) = if has_separated_coeffs || !self.has_filter_header {
+                (read_coeff_offset(data.get(1..3))?, 3)
+            } else {
+                (None, 1)
+            };
+            let data = data.get(position..).ok_or("Truncated VP6 frame header")?;
+            let mut decoder = RangeDecoder::new(data);
+            let is_golden = decoder.read_bit();
+            if self.has_filter_header {
+                self.filter.deblocking = decoder.read_bit();
+                if self.filter.deblocking {
+                    decoder.read_bit();
+                }
+                if self.sub_version > 7 {
+                    parse_filter_info = decoder.read_bit();
+                }
+            }
+            (coeff_offset, decoder, is_golden)
+        };
+
+        if parse_filter_info {
+            if decoder.read_bit() {
+                self.filter.mode = FilterMode::Adaptive;
+                self.filter.variance_threshold = (decoder.read_bits(5) << variance_shift) as i32;
+                self.filter.max_vector_length = 2 << decoder.read_bits(3);
+            } else if decoder.read_bit() {
+                self.filter.mode = FilterMode::Bicubic;
+            } else {
+                self.filter.mode = FilterMode::Bilinear;
+            }
+            self.filter.selection = if self.sub_version > 7 {
+                decoder.read_bits(4) as usize
+            } else {
+                16
+            };
+        }
+        let use_huffman = decoder.read_bit();
+
+        let header = FrameHeader {
+            is_keyframe,
+            is_golden,
+            use_huffman,
+            coeff_offset,
+        };
+        Ok((header, decoder))
+    }
+}
+
+/// Reads the 16-bit offset of the partition of the coefficients.
+/// An offset of 2 means that the coefficients aren't in a separate partition.
+fn read_coeff_offset(bytes: Option<&[u8]>) -> Result<Option<usize>, Error> {
+    let bytes = bytes.ok_or("Truncated VP6 frame header")?;
+    match usize::from(bytes[0]) << 8 | usize::from(bytes[1]) {
+        2 => Ok(None),
+        offset if offset > 2 => Ok(Some(offset)),
+        _ => Err("Invalid offset to the VP6 coefficients".into()),
+    }
+}
+
+/// Where the coefficients of a frame are read from.
+enum Coefficients<'a> {
+    /// The range decoder of the rest of the frame.
+    Main,
+
+    /// A separate partition, with its own range decoder.
+    Range(RangeDecoder<'a>),
+
+    /// A separate partition with Huffman codes.
+    Huffman(Reader<'a>, HuffmanTrees),
+}
+
+/// The Huffman codes of the coefficients of a frame, built from the probability models.
+struct HuffmanTrees {
+    /// By plane type.
+    dc: Vec<HuffmanTree>,
+
+    /// By whether the run starts at the sixth coefficient or later.
+    runs: Vec<HuffmanTree>,
+
+    /// By plane type, code type and coefficient group, for the first four groups.
+    ac: Vec<Vec<Vec<HuffmanTree>>>,
+}
+
+impl HuffmanTrees {
+    fn new(model: &Model) -> Self {
+        Self {
+            dc: model
+                .coeff_dccv
+                .iter()
+                .map(|probabilities| HuffmanTree::new(probabilities, &HUFFMAN_COEFF_MAP))
+                .collect(),
+            runs: model
+                .coeff_runv
+                .iter()
+                .map(|probabilities| HuffmanTree::new(probabilities, &HUFFMAN_RUN_MAP))
+                .collect(),
+            ac: model
+                .coeff_ract
+                .iter()
+                .map(|code_types| {
+                    code_types
+                        .iter()
+                        .map(|groups| {
+                            groups[..4]
+                                .iter()
+                                .map(|probabilities| {
+                                    HuffmanTree::new(probabilities, &HUFFMAN_COEFF_MAP)
+                                })
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The frame that a macroblock is predicted from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ReferenceFrame {
+    /// Intra macroblocks aren't predicted from another frame.
+    Current = 0,
+    Previous = 1,
+    Golden = 2,
+}
+
+/// The types of macroblocks, in the order that their probabilities are indexed by.
+///
+/// Inter macroblocks may use no motion vector, a coded vector that is relative to the
+/// nearest candidate vector, or one of the two candidate vectors from nearby macroblocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MacroblockType {
+    InterNoVector,
+    Intra,
+    InterDelta,
+    InterNearest,
+    InterNear,
+    GoldenNoVector,
+    GoldenDelta,
+    InterFourVectors,
+    GoldenNearest,
+    GoldenNear,
+}
+
+const MACROBLOCK_TYPES: [MacroblockType; 10] = [
+    MacroblockType::InterNoVector,
+    MacroblockType::Intra,
+    MacroblockType::InterDelta,
+    MacroblockType::InterNearest,
+    MacroblockType::InterNear,
+    MacroblockType::GoldenNoVector,
+    MacroblockType::GoldenDelta,
+    MacroblockType::InterFourVectors,
+    MacroblockType::GoldenNearest,
+    MacroblockType::GoldenNear,
+];
+
+impl MacroblockType {
+    fn reference_frame(self) -> ReferenceFrame {
+        match self {
+            MacroblockType::Intra => ReferenceFrame::Current,
+            MacroblockType::GoldenNoVector
+            | MacroblockType::GoldenDelta
+            | MacroblockType::GoldenNearest
+            | MacroblockType::GoldenNear => ReferenceFrame::Golden,
+            _ => ReferenceFrame::Previous,
+        }
+    }
+}
+
+/// A decoded macroblock, which later macroblocks take candidate motion vectors from.
+#[derive(Clone, Copy, Debug)]
+struct Macroblock {
+    mb_type: MacroblockType,
+    vector: (i32, i32),
+}
+
+/// The DC coefficient of a decoded block, which neighbouring blocks are predicted from.
+#[derive(Clone, Copy, Debug, Default)]
+struct BlockDc {
+    /// The frame that the block was predicted from, or `None` for blocks outside of
+    /// the picture.
+    reference: Option<ReferenceFrame>,
+    dc: i16,
+
+    /// Whether the coded DC coefficient wasn't zero.
+    has_dc: bool,
+}
+
+/// The state of decoding a single frame.
+struct FrameDecoder<'a> {
+    model: &'a Model,
+    previous: Option<&'a YuvPicture>,
+    golden: Option<&'a YuvPicture>,
+    picture: YuvPicture,
+    is_keyframe: bool,
+    render_chroma: bool,
+    quantizer: usize,
+    dequant_dc: i32,
+    dequant_ac: i32,
+    filter: PredictionFilter,
+    mb_width: usize,
+    mb_height: usize,
+    macroblocks: Vec<Macroblock>,
+
+    /// The type of the last macroblock.
+    mb_type: MacroblockType,
+
+    /// The candidate motion vectors of the current macroblock.
+    vector_candidates: [(i32, i32); 2],
+
+    /// The index in `CANDIDATE_POSITIONS` of the last candidate that was found.
+    vector_candidate_position: usize,
+
+    /// The motion vector of each block of the current macroblock.
+    vectors: [(i32, i32); 6],
+
+    /// The coefficients of each block of the current macroblock.
+    blocks: [[i16; 64]; 6],
+
+    /// The number of coefficient positions that were parsed for each block of the
+    /// current macroblock, up to and including the last coefficient.
+    coefficient_counts: [usize; 6],
+
+    /// The blocks above the current row, with a block of padding on either side.
+    /// The luma blocks of the picture come first, followed by each chroma plane.
+    above_blocks: Vec<BlockDc>,
+
+    /// The index in `above_blocks` of the block above each block of the macroblock.
+    above_block_index: [usize; 6],
+
+    /// The two luma blocks and the chroma blocks to the left of the current macroblock.
+    left_blocks: [BlockDc; 4],
+
+    /// The last DC coefficient of each plane and reference frame, for blocks
+    /// without neighbours that are predicted from the same frame.
+    previous_dc: [[i16; 3]; 3],
+
+    /// The remaining number of blocks without a DC coefficient or without
+    /// AC coefficients, by plane type, when the coefficients are Huffman coded.
+    zero_blocks: [[u32; 2]; 2],
+}
+
+impl<'a> FrameDecoder<'a> {
+    fn new(stream: &'a StreamDecoder, is_keyframe: bool, render_chroma: bool) -> Self {
+        let (mb_width, mb_height) = (stream.mb_width, stream.mb_height);
+        let mut previous_dc = [[0; 3]; 3];
+        previous_dc[1][ReferenceFrame::Current as usize] = 128;
+        previous_dc[2][ReferenceFrame::Current as usize] = 128;
+        Self {
+            model: &stream.model,
+            previous: stream.previous.as_ref(),
+            golden: stream.golden.as_ref(),
+            picture: YuvPicture::new(mb_width * 16, mb_height * 16),
+            is_keyframe,
+            render_chroma,
+            quantizer: stream.quantizer,
+            dequant_dc: i32::from(DC_DEQUANT[stream.quantizer]) * 4,
+            dequant_ac: i32::from(AC_DEQUANT[stream.quantizer]) * 4,
+            filter: stream.filter,
+            mb_width,
+            mb_height,
+            macroblocks: vec![
+                Macroblock {
+                    mb_type: MacroblockType::Intra,
+                    vector: (0, 0),
+                };
+                mb_width * mb_height
+            ],
+            mb_type: MacroblockType::InterNoVector,
+            vector_candidates: [(0, 0); 2],
+            vector_candidate_position: 0,
+            vectors: [(0, 0); 6],
+            blocks: [[0; 64]; 6],
+            coefficient_counts: [0; 6],
+            above_blocks: vec![BlockDc::default(); 4 * mb_width + 6],
+            above_block_index: [0; 6],
+            left_blocks: [BlockDc::default(); 4],
+            previous_dc,
+            zero_blocks: [[0; 2]; 2],
+        }
+    }
+
+    fn decode(
+        mut self,
+        decoder: &mut RangeDecoder,
+        coefficients: &mut Coefficients,
+    ) -> Result<YuvPicture, Error> {
+        let mb_width = self.mb_width;
+        for mb_y in 0..self.mb_height {
+            self.left_blocks = [BlockDc::default(); 4];
+            self.above_block_index = [1, 2, 1, 2, 2 * mb_width + 3, 3 * mb_width + 5];
+            for mb_x in 0..mb_width {
+                self.decode_macroblock(decoder, coefficients, mb_x, mb_y)?;
+                for index in &mut self.above_block_index[..4] {
+                    *index += 2;
+                }
+                for index in &mut self.above_block_index[4..] {
+                    *index += 1;
+                }
+            }
+        }
+        Ok(self.picture)
+    }
+
+    fn decode_macroblock(
+        &mut self,
+        decoder: &mut RangeDecoder,
+        coefficients: &mut Coefficients,
+        mb_x: usize,
+        mb_y: usize,
+    ) -> Result<(), Error> {
+        let mb_type = if self.is_keyframe {
+            MacroblockType::Intra
+        } else {
+            self.decode_motion_vectors(decoder, mb_x, mb_y)
+        };
+
+        self.blocks = [[0; 64]; 6];
+        match coefficients {
+            Coefficients::Main => self.parse_coefficients(decoder),
+            Coefficients::Range(decoder) => self.parse_coefficients(decoder),
+            Coefficients::Huffman(reader, trees) => {
+                self.parse_huffman_coefficients(reader, trees)?
+            }
+        }
+        let reference_frame = mb_type.reference_frame();
+        self.add_dc_predictions(reference_frame);
+
+        let reference = match reference_frame {
+            ReferenceFrame::Current => None,
+            ReferenceFrame::Previous => self.previous,
+            ReferenceFrame::Golden => self.golden,
+        };
+        let block_count = if self.render_chroma { 6 } else { 4 };
+        for block in 0..block_count {
+            let (x, y) = block_position(block, mb_x, mb_y);
+            let residual = if reference_frame != ReferenceFrame::Current
+                && self.coefficient_counts[block] <= 1
+            {
+                [dsp::inverse_transform_dc(self.blocks[block][0]); 64]
+            } else {
+                dsp::inverse_transform(&self.blocks[block])
+            };
+            let prediction = reference.map(|reference| {
+                self.predict(
+                    block_plane(reference, block),
+                    x,
+                    y,
+                    self.vectors[block],
+                    block < 4,
+                )
+            });
+            let plane = block_plane_mut(&mut self.picture, block);
+            for (i, &residual) in residual.iter().enumerate() {
+                let base = prediction.map_or(128, |prediction| i32::from(prediction[i]));
+                plane.set(x + i % 8, y + i / 8, clamp_u8(base + residual));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the type of an inter macroblock, and its motion vectors.
+    fn decode_motion_vectors(
+        &mut self,
+        decoder: &mut RangeDecoder,
+        mb_x: usize,
+        mb_y: usize,
+    ) -> MacroblockType {
+        let context = self.find_vector_candidates(mb_x, mb_y, ReferenceFrame::Previous);
+        let probabilities = &self.model.mb_type[context][self.mb_type as usize];
+        if !decoder.read(probabilities[0]) {
+            let index = decoder.read_tree(&MB_TYPE_TREE, probabilities);
+            self.mb_type = MACROBLOCK_TYPES[usize::from(index)];
+        }
+        let mb_type = self.mb_type;
+        let index = mb_y * self.mb_width + mb_x;
+        self.macroblocks[index].mb_type = mb_type;
+
+        if mb_type.reference_frame() == ReferenceFrame::Golden {
+            self.find_vector_candidates(mb_x, mb_y, ReferenceFrame::Golden);
+        }
+        let vector = match mb_type {
+            MacroblockType::InterNearest | MacroblockType::GoldenNearest => {
+                self.vector_candidates[0]
+            }
+            MacroblockType::InterNear | MacroblockType::GoldenNear => self.vector_candidates[1],
+            MacroblockType::InterDelta | MacroblockType::GoldenDelta => {
+                self.parse_vector_adjustment(decoder)
+            }
+            MacroblockType::InterFourVectors => {
+                self.parse_four_vectors(decoder);
+                self.macroblocks[index].vector = self.vectors[3];
+                return mb_type;
+            }
+            _ => (0, 0),
+        };
+        self.macroblocks[index].vector = vector;
+        self.vectors = [vector; 6];
+        mb_type
+    }
+
+    /// Parses a motion vector for each luma block. The chroma blocks use their average.
+    fn parse_four_vectors(&mut self, decoder: &mut RangeDecoder) {
+        let mut types = [MacroblockType::InterNoVector; 4];
+        for mb_type in &mut types {
+            // Only the types that are predicted from the previous frame are coded.
+            let index = decoder.read_bits(2) as usize;
+            if index != 0 {
+                *mb_type = MACROBLOCK_TYPES[index + 1];
+            }
+        }
+
+        let mut sum = (0, 0);
+        for (block, &mb_type) in types.iter().enumerate() {
+            let vector = match mb_type {
+                MacroblockType::InterDelta => self.parse_vector_adjustment(decoder),
+                MacroblockType::InterNearest => self.vector_candidates[0],
+                MacroblockType::InterNear => self.vector_candidates[1],
+                _ => (0, 0),
+            };
+            self.vectors[block] = vector;
+            sum.0 += vector.0;
+            sum.1 += vector.1;
+        }
+        let chroma_vector = (divide_by_4(sum.0), divide_by_4(sum.1));
+        self.vectors[4] = chroma_vector;
+        self.vectors[5] = chroma_vector;
+    }
+
+    /// Finds the candidate motion vectors of a macroblock, from nearby macroblocks
+    /// that are predicted from the same frame. Returns the context of the macroblock
+    /// type probabilities.
+    fn find_vector_candidates(
+        &mut self,
+        mb_x: usize,
+        mb_y: usize,
+        reference: ReferenceFrame,
+    ) -> usize {
+        let mut vectors = [(0, 0); 2];
+        let mut count = 0;
+        for (position, &(dx, dy)) in CANDIDATE_POSITIONS.iter().enumerate() {
+            let x = mb_x as isize + dx;
+            let y = mb_y as isize + dy;
+            if x < 0 || x >= self.mb_width as isize || y < 0 || y >= self.mb_height as isize {
+                continue;
+            }
+            let macroblock = self.macroblocks[y as usize * self.mb_width + x as usize];
+            if macroblock.mb_type.reference_frame() != reference
+                || macroblock.vector == vectors[0]
+                || macroblock.vector == (0, 0)
+            {
+                continue;
+            }
+            vectors[count] = macroblock.vector;
+            count += 1;
+            if count > 1 {
+                // There are enough candidates to use the context with two candidates.
+                self.vector_candidates = vectors;
+                return 0;
+            }
+            self.vector_candidate_position = position;
+        }
+        self.vector_candidates = vectors;
+        count + 1
+    }
+
+    /// Parses a motion vector, which is relative to the nearest candidate vector
+    /// if that came from an adjacent macroblock.
+    fn parse_vector_adjustment(&mut self, decoder: &mut RangeDecoder) -> (i32, i32) {
+        /// The order that the bits of long vector components are coded in.
+        const LONG_VECTOR_BITS: [usize; 7] = [0, 1, 2, 7, 6, 5, 4];
+
+        let model = self.model;
+        let mut vector = if self.vector_candidate_position < 2 {
+            self.vector_candidates[0]
+        } else {
+            (0, 0)
+        };
+        for component in 0..2 {
+            let mut delta = if decoder.read(model.vector_dct[component]) {
+                let probabilities = &model.vector_fdv[component];
+                let mut delta = 0;
+                for &bit in &LONG_VECTOR_BITS {
+                    delta |= i32::from(decoder.read(probabilities[bit])) << bit;
+                }
+                // Bit 3 is only coded when the higher bits are set.
+                if delta & 0xf0 != 0 {
+                    delta | i32::from(decoder.read(probabilities[3])) << 3
+                } else {
+                    delta | 8
+                }
+            } else {
+                i32::from(decoder.read_tree(&SHORT_VECTOR_TREE, &model.vector_pdv[component]))
+            };
+            if delta != 0 && decoder.read(model.vector_sig[component]) {
+                delta = -delta;
+            }
+            if component == 0 {
+                vector.0 += delta;
+            } else {
+                vector.1 += delta;
+            }
+        }
+        vector
+    }
+
+    /// Parses the coefficients of a macroblock with the range coder.
+    fn parse_coefficients(&mut self, decoder: &mut RangeDecoder) {
+        let model = self.model;
+        for (block, &left) in LEFT_BLOCK.iter().enumerate() {
+            let plane_type = if block < 4 { 0 } else { 1 };
+            let above = self.above_block_index[block];
+            let context = usize::from(self.left_blocks[left].has_dc)
+                + usize::from(self.above_blocks[above].has_dc);
+
+            // The first nodes of DC coefficients depend on the neighbouring blocks.
+            let mut head: &[u8] = &model.coeff_dcct[plane_type][context];
+            let mut probabilities = &model.coeff_dccv[plane_type];
+            let mut code_type = 1;
+            let mut index = 0;
+            loop {
+                let mut run = 1;
+                if (index > 1 && code_type == 0) || decoder.read(head[0]) {
+                    let value = if decoder.read(head[2]) {
+                        code_type = 2;
+                        if decoder.read(head[3]) {
+                            let category =
+                                usize::from(decoder.read_tree(&COEFF_CATEGORY_TREE, probabilities));
+                            let length = usize::from(COEFF_BIT_LENGTH[category]);
+                            let mut value = i32::from(COEFF_BIAS[category + 5]);
+                            for (bit, &probability) in COEFF_BIT_PROBABILITIES[category][..=length]
+                                .iter()
+                                .enumerate()
+                            {
+                                value += i32::from(decoder.read(probability)) << (length - bit);
+                            }
+                            value
+                        } else if decoder.read(head[4]) {
+                            3 + i32::from(decoder.read(probabilities[5]))
+                        } else {
+                            2
+                        }
+                    } else {
+                        code_type = 1;
+                        1
+                    };
+                    let value = if decoder.read_bit() { -value } else { value };
+                    let value = if index > 0 {
+                        value * self.dequant_ac
+                    } else {
+                        value
+                    };
+                    let position = usize::from(model.coeff_index_to_pos[index]);
+                    self.blocks[block][ZIGZAG[position]] = value as i16;
+                } else {
+                    code_type = 0;
+                    if index > 0 {
+                        if !decoder.read(head[1]) {
+                            // The end of the block.
+                            break;
+                        }
+                        let run_probabilities = &model.coeff_runv[usize::from(index >= 6)];
+                        run = usize::from(decoder.read_tree(&RUN_TREE, run_probabilities));
+                        if run == 0 {
+                            run = 9;
+                            for (bit, &probability) in run_probabilities[8..].iter().enumerate() {
+                                run += usize::from(decoder.read(probability)) << bit;
+                            }
+                        }
+                    }
+                }
+
+                index += run;
+                if index >= 64 {
+                    break;
+                }
+                let group = usize::from(COEFF_GROUPS[index]);
+                probabilities = &model.coeff_ract[plane_type][code_type][group];
+                head = probabilities;
+            }
+            self.coefficient_counts[block] = index;
+
+            let has_dc = self.blocks[block][0] != 0;
+            self.left_blocks[left].has_dc = has_dc;
+            self.above_blocks[above].has_dc = has_dc;
+        }
+    }
+
+    /// Parses the coefficients of a macroblock with Huffman codes.
+    fn parse_huffman_coefficients(
+        &mut self,
+        reader: &mut Reader,
+        trees: &HuffmanTrees,
+    ) -> Result<(), Error> {
+        /// The token of the end of a block.
+        const END_OF_BLOCK: u8 = 11;
+
+        let model = self.model;
+        for block in 0..6 {
+            let plane_type = if block < 4 { 0 } else { 1 };
+            let mut tree = &trees.dc[plane_type];
+            let mut code_type = 0;
+            let mut index = 0;
+            loop {
+                let mut run = 1;
+                if index < 2 && self.zero_blocks[index][plane_type] > 0 {
+                    // Runs of blocks without a DC coefficient, or without AC coefficients,
+                    // are coded once for the whole run.
+                    self.zero_blocks[index][plane_type] -= 1;
+                    if index > 0 {
+                        break;
+                    }
+                } else {
+                    let token = tree.read(reader)?;
+                    if token == 0 {
+                        if index > 0 {
+                            let run_tree = &trees.runs[usize::from(index >= 6)];
+                            run += usize::from(run_tree.read(reader)?);
+                            if run >= 9 {
+                                run += reader.read::<u32>(6)? as usize;
+                            }
+                        } else {
+                            self.zero_blocks[0][plane_type] = read_zero_block_count(reader)?;
+                        }
+                        code_type = 0;
+                    } else if token == END_OF_BLOCK {
+                        if index == 1 {
+                            self.zero_blocks[1][plane_type] = read_zero_block_count(reader)?;
+                        }
+                        break;
+                    } else {
+                        let token = usize::from(token);
+                        let mut value = i32::from(COEFF_BIAS[token]);
+                        if token > 4 {
+                            let bits = if token <= 9 { token as u32 - 4 } else { 11 };
+                            value += reader.read::<u32>(bits)? as i32;
+                        }
+                        code_type = if value > 1 { 2 } else { 1 };
+                        let value = if reader.read_bit()? { -value } else { value };
+                        let value = if index > 0 {
+                            value * self.dequant_ac
+                        } else {
+                            value
+                        };
+                        let position = usize::from(model.coeff_index_to_pos[index]);
+                        self.blocks[block][ZIGZAG[position]] = value as i16;
+                    }
+                }
+
+                index += run;
+                if index >= 64 {
+                    break;
+                }
+                let group = usize::from(COEFF_GROUPS[index]).min(3);
+                tree = &trees.ac[plane_type][code_type][group];
+            }
+            self.coefficient_counts[block] = index;
+        }
+        Ok(())
+    }
+
+    /// Adds the predicted DC coefficient to each block, and dequantizes it.
+    /// DC coefficients are predicted from the blocks to the left and above
+    /// that are predicted from the same frame.
+    fn add_dc_predictions(&mut self, reference: ReferenceFrame) {
+        for block in 0..6 {
+            let left = LEFT_BLOCK[block];
+            let above = self.above_block_index[block];
+            let plane = BLOCK_PLANE[block];
+
+            let mut count = 0;
+            let mut prediction = 0;
+            for neighbour in &[self.left_blocks[left], self.above_blocks[above]] {
+                if neighbour.reference == Some(reference) {
+                    prediction += i32::from(neighbour.dc);
+                    count += 1;
+                }
+            }
+            let prediction = match count {
+                0 => i32::from(self.previous_dc[plane][reference as usize]),
+                1 => prediction,
+                _ => prediction / 2,
+            };
+
+            let dc = (i32::from(self.blocks[block][0]) + prediction) as i16;
+            self.previous_dc[plane][reference as usize] = dc;
+            for neighbour in &mut [&mut self.left_blocks[left], &mut self.above_blocks[above]] {
+                neighbour.reference = Some(reference);
+                neighbour.dc = dc;
+            }
+            self.blocks[block][0] = (i32::from(dc) * self.dequant_dc) as i16;
+        }
+    }
+
+    /// Predicts an 8x8 block from a reference plane, with a motion vector in quarters
+    /// of a luma pixel or eighths of a chroma pixel.
+    fn predict(
+        &self,
+        reference: &Plane,
+        x: usize,
+        y: usize,
+        vector: (i32, i32),
+        is_luma: bool,
+    ) -> [u8; 64] {
+        let divisor = if is_luma { 4 } else { 8 };
+        let (dx, dy) = (vector.0 / divisor, vector.1 / divisor);
+
+        // The block is predicted from a window with room for the filter taps.
+        let mut window = [[0; dsp::WINDOW_SIZE]; dsp::WINDOW_SIZE];
+        let left = x as isize + dx as isize - 2;
+        let top = y as isize + dy as isize - 2;
+        for (row, samples) in window.iter_mut().enumerate() {
+            for (column, sample) in samples.iter_mut().enumerate() {
+                *sample = reference.get_clamped(left + column as isize, top + row as isize);
+            }
+        }
+
+        if self.filter.deblocking {
+            // Smooth the edges of the blocks of the reference frame within the window.
+            let threshold = i32::from(FILTER_THRESHOLD[self.quantizer]);
+            if dx & 7 != 0 {
+                dsp::filter_vertical_edge(&mut window, (10 - (dx & 7)) as usize, threshold);
+            }
+            if dy & 7 != 0 {
+                dsp::filter_horizontal_edge(&mut window, (10 - (dy & 7)) as usize, threshold);
+            }
+        }
+
+        let mask = divisor - 1;
+        let (fraction_x, fraction_y) = (vector.0 & mask, vector.1 & mask);
+        if fraction_x == 0 && fraction_y == 0 {
+            return dsp::copy(&window, 2, 2);
+        }
+        // Negative vectors are interpolated from the pixel before their position.
+        let x0 = if vector.0 < 0 && fraction_x != 0 {
+            1
+        } else {
+            2
+        };
+        let y0 = if vector.1 < 0 && fraction_y != 0 {
+            1
+        } else {
+            2
+        };
+        let (x8, y8) = if is_luma {
+            (fraction_x as usize * 2, fraction_y as usize * 2)
+        } else {
+            (fraction_x as usize, fraction_y as usize)
+        };
+        if is_luma && self.use_bicubic(&window, vector) {
+            dsp::bicubic(&window, x0, y0, x8, y8, self.filter.selection)
+        } else {
+            dsp::bilinear(&window, x0, y0, x8, y8)
+        }
+    }
+
+    fn use_bicubic(&self, window: &dsp::Window, vector: (i32, i32)) -> bool {
+        match self.filter.mode {
+            FilterMode::Bilinear => false,
+            FilterMode::Bicubic => true,
+            FilterMode::Adaptive => {
+                let max_length = self.filter.max_vector_length;
+                let is_long =
+                    max_length != 0 && (vector.0.abs() > max_length || vector.1.abs() > max_length);
+                let threshold = self.filter.variance_threshold;
+                let is_flat = threshold != 0 && dsp::block_variance(window, 2, 2) < threshold;
+                !is_long && !is_flat
+            }
+        }
+    }
+}
+
+/// Reads the number of consecutive blocks without a DC coefficient or without
+/// AC coefficients, in Huffman coded frames.
+fn read_zero_block_count(reader: &mut Reader) -> Result<u32, Error> {
+    let count = reader.read::<u32>(2)?;
+    Ok(match count {
+        2 => count + reader.read::<u32>(2)?,
+        3 => {
+            let extra_bits = if reader.read_bit()? { 4 } else { 0 };
+            6 + extra_bits + reader.read::<u32>(2 + extra_bits)?
+        }
+        _ => count,
+    })
+}
+
+/// Divides by 4, rounding to the nearest integer, and away from zero for halves.
+fn divide_by_4(value: i32) -> i32 {
+    if value > 0 {
+        (value + 2) >> 2
+    } else {
+        (value + 1) >> 2
+    }
+}
+
+/// Returns the position of the top-left pixel of a block of a macroblock in its plane.
+fn block_position(block: usize, mb_x: usize, mb_y: usize) -> (usize, usize) {
+    if block < 4 {
+        (mb_x * 16 + (block & 1) * 8, mb_y * 16 + (block >> 1) * 8)
+    } else {
+        (mb_x * 8, mb_y * 8)
+    }
+}
+
+fn block_plane(picture: &YuvPicture, block: usize) -> &Plane {
+    match block {
+        0..=3 => &picture.y,
+        4 => &picture.u,
+        _ => &picture.v,
+    }
+}
+
+fn block_plane_mut(picture: &mut YuvPicture, block: usize) -> &mut Plane {
+    match block {
+        0..=3 => &mut picture.y,
+        4 => &mut picture.u,
+        _ => &mut picture.v,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use swf::VideoCodec;
+
+    const QUANTIZER: u8 = 32;
+
+    /// The DC coefficient of a luma block that adds 50 to the prediction at `QUANTIZER`.
+    const BRIGHTER: i32 = 16;
+
+    /// Encodes booleans for the `RangeDecoder`.
+    struct RangeEncoder {
+        data: Vec<u8>,
+        range: u32,
+        bottom: u32,
+        bit_count: u32,
+    }
+
+    impl RangeEncoder {
+        fn new(header: &[u8]) -> Self {
+            Self {
+                data: header.to_vec(),
+                range: 255,
+                bottom: 0,
+                bit_count: 24,
+            }
+        }
+
+        fn write(&mut self, probability: u8, bit: bool) {
+            let split = 1 + (((self.range - 1) * u32::from(probability)) >> 8);
+            if bit {
+                self.bottom += split;
+                self.range -= split;
+            } else {
+                self.range = split;
+            }
+            while self.range < 128 {
+                self.range <<= 1;
+                if self.bottom & (1 << 31) != 0 {
+                    // Carry into the bytes that are already written.
+                    for byte in self.data.iter_mut().rev() {
+                        if *byte == 255 {
+                            *byte = 0;
+                        } else {
+                            *byte += 1;
+                            break;
+                        }
+                    }
+                }
+                self.bottom <<= 1;
+                self.bit_count -= 1;
+                if self.bit_count == 0 {
+                    self.data.push((self.bottom >> 24) as u8);
+                    self.bottom &= (1 << 24) - 1;
+                    self.bit_count = 8;
+                }
+            }
+        }
+
+        fn write_bit(&mut self, bit: bool) {
+            self.write(128, bit);
+        }
+
+        fn write_bits(&mut self, bits: u32, value: u32) {
+            for bit in (0..bits).rev() {
+                self.write_bit(value >> bit & 1 != 0);
+            }
+        }
+
+        fn write_tree(&mut self, tree: &Tree, probabilities: &[u8], value: u8) {
+            for (probability, bit) in tree_path(tree, 0, value).unwrap() {
+                self.write(probabilities[usize::from(probability)], bit);
+            }
+        }
+
+        fn finish(mut self) -> Vec<u8> {
+            for _ in 0..32 {
+                self.write_bit(false);
+            }
+            self.data
+        }
+    }
+
+    /// Returns the branches that lead to a value of a tree.
+    fn tree_path(tree: &Tree, node: usize, value: u8) -> Option<Vec<(u8, bool)>> {
+        let (offset, probability) = tree[node];
+        if offset <= 0 {
+            return if -offset as u8 == value {
+                Some(vec![])
+            } else {
+                None
+            };
+        }
+        for &(bit, next) in &[(false, node + 1), (true, node + offset as usize)] {
+            if let Some(mut path) = tree_path(tree, next, value) {
+                path.insert(0, (probability, bit));
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    fn encoded_frame(data: &[u8], frame_id: u32) -> EncodedFrame<'_> {
+        EncodedFrame {
+            codec: VideoCodec::VP6,
+            data,
+            frame_id,
+        }
+    }
+
+    /// Writes that none of the coefficient probabilities are updated.
+    fn write_coeff_models(encoder: &mut RangeEncoder) {
+        for &update in DCCV_UPDATE.iter().flatten() {
+            encoder.write(update, false);
+        }
+        encoder.write_bit(false);
+        for &update in RUNV_UPDATE.iter().flatten() {
+            encoder.write(update, false);
+        }
+        for &update in RACT_UPDATE.iter().flatten().flatten().flatten() {
+            encoder.write(update, false);
+        }
+    }
+
+    /// Writes a block with only a DC coefficient, which isn't dequantized.
+    fn write_dc_block(
+        encoder: &mut RangeEncoder,
+        model: &Model,
+        plane_type: usize,
+        context: usize,
+        value: i32,
+    ) {
+        let head = &model.coeff_dcct[plane_type][context];
+        let probabilities = &model.coeff_dccv[plane_type];
+        let magnitude = value.abs();
+        encoder.write(head[0], magnitude != 0);
+        if magnitude != 0 {
+            encoder.write(head[2], magnitude > 1);
+            if magnitude > 1 {
+                encoder.write(head[3], magnitude > 4);
+                if magnitude > 4 {
+                    let category = (0..6)
+                        .rev()
+                        .find(|&category| magnitude >= i32::from(COEFF_BIAS[category + 5]))
+                        .unwrap();
+                    encoder.write_tree(&COEFF_CATEGORY_TREE, probabilities, category as u8);
+                    let length = usize::from(COEFF_BIT_LENGTH[category]);
+                    let extra = magnitude - i32::from(COEFF_BIAS[category + 5]);
+                    for (bit, &probability) in COEFF_BIT_PROBABILITIES[category][..=length]
+                        .iter()
+                        .enumerate()
+                    {
+                        encoder.write(probability, extra >> (length - bit) & 1 != 0);
+                    }
+                } else {
+                    encoder.write(head[4], magnitude > 2);
+                    if magnitude > 2 {
+                        encoder.write(probabilities[5], magnitude == 4);
+                    }
+                }
+            }
+            encoder.write_bit(value < 0);
+        }
+
+        // The end of the block, at the first AC coefficient.
+        let code_type = magnitude.min(2) as usize;
+        let probabilities = &model.coeff_ract[plane_type][code_type][0];
+        encoder.write(probabilities[0], false);
+        encoder.write(probabilities[1], false);
+    }
+
+    /// Writes the coefficients of a macroblock, which are all zero except for the DC
+    /// coefficients of the top-left luma block and the chroma blocks.
+    fn write_macroblock(encoder: &mut RangeEncoder, model: &Model, luma_dc: i32, chroma_dc: i32) {
+        let has_dc = usize::from(luma_dc != 0);
+        write_dc_block(encoder, model, 0, 0, luma_dc);
+        write_dc_block(encoder, model, 0, has_dc, 0);
+        write_dc_block(encoder, model, 0, has_dc, 0);
+        write_dc_block(encoder, model, 0, 0, 0);
+        write_dc_block(encoder, model, 1, 0, chroma_dc);
+        write_dc_block(encoder, model, 1, 0, chroma_dc);
+    }
+
+    /// A 16x16 keyframe with flat luma and neutral chroma.
+    /// The DC coefficients of the other luma blocks are predicted from the first block.
+    fn keyframe(luma_dc: i32) -> Vec<u8> {
+        // The advanced profile, without a separate partition for the coefficients.
+        let mut encoder = RangeEncoder::new(&[QUANTIZER << 1, 6 << 3 | 6, 1, 1, 1, 1]);
+        encoder.write_bits(2, 0);
+        // Bilinear filtering.
+        encoder.write_bit(false);
+        encoder.write_bit(false);
+        // Not Huffman coded.
+        encoder.write_bit(false);
+        write_coeff_models(&mut encoder);
+
+        let mut model = Model::new(false);
+        model.update_dcct();
+        // Chroma DC coefficients are predicted from 128.
+        write_macroblock(&mut encoder, &model, luma_dc, -128);
+        encoder.finish()
+    }
+
+    /// A 16x16 inter frame that adds a DC coefficient to the top-left luma block of the
+    /// previous frame, and predicts the rest of the macroblock from it.
+    fn inter_frame(luma_dc: i32) -> Vec<u8> {
+        let mut encoder = RangeEncoder::new(&[0x80 | QUANTIZER << 1]);
+        // Not a golden frame, and without deblocking.
+        encoder.write_bit(false);
+        encoder.write_bit(false);
+        encoder.write_bit(false);
+
+        // Macroblock type models.
+        for _ in 0..3 {
+            encoder.write_bit(false);
+            encoder.write_bit(false);
+        }
+        // Motion vector models.
+        for &update in VECTOR_SIG_DCT_UPDATE.iter().flatten() {
+            encoder.write(update, false);
+        }
+        for &update in VECTOR_PDV_UPDATE.iter().flatten() {
+            encoder.write(update, false);
+        }
+        for &update in VECTOR_FDV_UPDATE.iter().flatten() {
+            encoder.write(update, false);
+        }
+        write_coeff_models(&mut encoder);
+
+        let mut model = Model::new(false);
+        model.update_mb_type_probabilities();
+        model.update_dcct();
+        // Without candidate vectors, the macroblock repeats the initial type,
+        // which has no motion vector.
+        encoder.write(model.mb_type[1][0][0], true);
+        write_macroblock(&mut encoder, &model, luma_dc, 0);
+        encoder.finish()
+    }
+
+    /// Prefixes a color frame with the offset to its alpha channel.
+    fn alpha_frame(color: &[u8], alpha: &[u8]) -> Vec<u8> {
+        let offset = color.len() as u32;
+        let mut data = offset.to_be_bytes()[1..].to_vec();
+        data.extend_from_slice(color);
+        data.extend_from_slice(alpha);
+        data
+    }
+
+    #[test]
+    fn vp6_range_coder() {
+        let mut encoder = RangeEncoder::new(&[]);
+        let bits: Vec<_> = (0..200u32).map(|i| i % 3 == 0 || i % 7 == 0).collect();
+        for (i, &bit) in bits.iter().enumerate() {
+            encoder.write((i * 37 % 254 + 1) as u8, bit);
+        }
+        encoder.write_tree(&RUN_TREE, &DEFAULT_RUNV[0], 6);
+        let data = encoder.finish();
+
+        let mut decoder = RangeDecoder::new(&data);
+        for (i, &bit) in bits.iter().enumerate() {
+            assert_eq!(decoder.read((i * 37 % 254 + 1) as u8), bit);
+        }
+        assert_eq!(decoder.read_tree(&RUN_TREE, &DEFAULT_RUNV[0]), 6);
+    }
+
+    #[test]
+    fn vp6_keyframe() {
+        let data = keyframe(BRIGHTER);
+        let mut decoder = Vp6Decoder::new();
+        let dependency = decoder.preload_frame(encoded_frame(&data, 0)).unwrap();
+        assert_eq!(dependency, FrameDependency::None);
+
+        let frame = decoder.decode_frame(encoded_frame(&data, 0)).unwrap();
+        assert_eq!((frame.width, frame.height), (16, 16));
+        let picture = decoder.color.previous.as_ref().unwrap();
+        assert!(picture.y.data.iter().all(|&luma| luma == 178));
+        assert!(picture.u.data.iter().all(|&chroma| chroma == 128));
+        assert!(picture.v.data.iter().all(|&chroma| chroma == 128));
+        for pixel in frame.rgba.chunks(4) {
+            assert_eq!(pixel, [189, 189, 189, 255]);
+        }
+    }
+
+    #[test]
+    fn vp6_inter_frame_without_reference() {
+        let data = inter_frame(0);
+        let mut decoder = Vp6Decoder::new();
+        let dependency = decoder.preload_frame(encoded_frame(&data, 1)).unwrap();
+        assert_eq!(dependency, FrameDependency::Past);
+        assert!(decoder.decode_frame(encoded_frame(&data, 1)).is_err());
+    }
+
+    #[test]
+    fn vp6_inter_frame() {
+        let mut decoder = Vp6Decoder::new();
+        let keyframe = decoder
+            .decode_frame(encoded_frame(&keyframe(BRIGHTER), 0))
+            .unwrap();
+        let unchanged = decoder
+            .decode_frame(encoded_frame(&inter_frame(0), 1))
+            .unwrap();
+        assert_eq!(unchanged.rgba, keyframe.rgba);
+
+        decoder
+            .decode_frame(encoded_frame(&inter_frame(BRIGHTER), 2))
+            .unwrap();
+        let picture = decoder.color.previous.as_ref().unwrap();
+        assert!(picture.y.data.iter().all(|&luma| luma == 228));
+        assert!(picture.u.data.iter().all(|&chroma| chroma == 128));
+
+        // The golden frame is still the keyframe.
+        let golden = decoder.color.golden.as_ref().unwrap();
+        assert!(golden.y.data.iter().all(|&luma| luma == 178));
+    }
+
+    #[test]
+    fn vp6_alpha_channel() {
+        let data = alpha_frame(&keyframe(BRIGHTER), &keyframe(-BRIGHTER));
+        let mut decoder = Vp6Decoder::with_alpha();
+        let dependency = decoder.preload_frame(encoded_frame(&data, 0)).unwrap();
+        assert_eq!(dependency, FrameDependency::None);
+
+        let frame = decoder.decode_frame(encoded_frame(&data, 0)).unwrap();
+        assert_eq!((frame.width, frame.height), (16, 16));
+        for pixel in frame.rgba.chunks(4) {
+            assert_eq!(pixel, [189, 189, 189, 78]);
+        }
+
+        let data = alpha_frame(&inter_frame(0), &inter_frame(BRIGHTER));
+        let frame = decoder.decode_frame(encoded_frame(&data, 1)).unwrap();
+        for pixel in frame.rgba.chunks(4) {
+            assert_eq!(pixel, [189, 189, 189, 128]);
+        }
+    }
+
+    #[test]
+    fn vp6_alpha_channel_offset() {
+        let mut decoder = Vp6Decoder::with_alpha();
+        let data = [0, 0, 10, 0];
+        assert!(decoder.preload_frame(encoded_frame(&data, 0)).is_err());
+        assert!(decoder.decode_frame(encoded_frame(&data, 0)).is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! The inverse transform and the prediction filters of VP6.
+
+use super::tables::BICUBIC_FILTERS;
+use crate::backend::video::decoders::yuv::clamp_u8;
+
+/// The size of the area of the reference frame that a block is predicted from.
+/// It has room for the taps of the filters around the 8x8 block.
+pub const WINDOW_SIZE: usize = 12;
+
+pub type Window = [[u8; WINDOW_SIZE]; WINDOW_SIZE];
+
+/// Computes the inverse DCT of a block, returning the residual samples.
+///
+/// This is the exact integer transform of VP3 and its successors.
+pub fn inverse_transform(coefficients: &[i16; 64]) -> [i32; 64] {
+    let mut rows = [0; 64];
+    for y in 0..8 {
+        let mut input = [0; 8];
+        for (x, value) in input.iter_mut().enumerate() {
+            *value = i32::from(coefficients[y * 8 + x]);
+        }
+        for (x, &value) in idct_1d(input, 0).iter().enumerate() {
+            // The intermediate values are stored in 16 bits.
+            rows[y * 8 + x] = i32::from(value as i16);
+        }
+    }
+
+    let mut samples = [0; 64];
+    for x in 0..8 {
+        let mut input = [0; 8];
+        for (y, value) in input.iter_mut().enumerate() {
+            *value = rows[y * 8 + x];
+        }
+        for (y, &value) in idct_1d(input, 8).iter().enumerate() {
+            samples[y * 8 + x] = value >> 4;
+        }
+    }
+    samples
+}
+
+/// Computes the residual of a block that only has a DC coefficient.
+///
+/// The reference decoder uses this shortcut for inter blocks, and it rounds differently
+/// than the full transform.
+pub fn inverse_transform_dc(dc: i16) -> i32 {
+    (i32::from(dc) + 15) >> 5
+}
+
+fn idct_1d(input: [i32; 8], rounding: i32) -> [i32; 8] {
+    const C1S7: i32 = 64277;
+    const C2S6: i32 = 60547;
+    const C3S5: i32 = 54491;
+    const C4S4: i32 = 46341;
+    const C5S3: i32 = 36410;
+    const C6S2: i32 = 25080;
+    const C7S1: i32 = 12785;
+    let m = |a: i32, b: i32| ((i64::from(a) * i64::from(b)) >> 16) as i32;
+
+    let a = m(C1S7, input[1]) + m(C7S1, input[7]);
+    let b = m(C7S1, input[1]) - m(C1S7, input[7]);
+    let c = m(C3S5, input[3]) + m(C5S3, input[5]);
+    let d = m(C3S5, input[5]) - m(C5S3, input[3]);
+    let ad = m(C4S4, a - c);
+    let bd = m(C4S4, b - d);
+    let cd = a + c;
+    let dd = b + d;
+    let e = m(C4S4, input[0] + input[4]) + rounding;
+    let f = m(C4S4, input[0] - input[4]) + rounding;
+    let g = m(C2S6, input[2]) + m(C6S2, input[6]);
+    let h = m(C6S2, input[2]) - m(C2S6, input[6]);
+    let ed = e - g;
+    let gd = e + g;
+    let add = f + ad;
+    let bdd = bd - h;
+    let fd = f - ad;
+    let hd = bd + h;
+    [
+        gd + cd,
+        add + hd,
+        add - hd,
+        ed + dd,
+        ed - dd,
+        fd + bdd,
+        fd - bdd,
+        gd - cd,
+    ]
+}
+
+/// Smooths the vertical block edge at the given column of a window.
+pub fn filter_vertical_edge(window: &mut Window, x: usize, threshold: i32) {
+    for row in window.iter_mut() {
+        let (a, b, c, d) = (row[x - 2], row[x - 1], row[x], row[x + 1]);
+        let delta = edge_delta(a, b, c, d, threshold);
+        row[x - 1] = clamp_u8(i32::from(b) + delta);
+        row[x] = clamp_u8(i32::from(c) - delta);
+    }
+}
+
+/// Smooths the horizontal block edge at the given row of a window.
+pub fn filter_horizontal_edge(window: &mut Window, y: usize, threshold: i32) {
+    let (above, below) = (window[y - 2], window[y + 1]);
+    let (mut top, mut bottom) = (window[y - 1], window[y]);
+    for (((&a, b), c), &d) in above.iter().zip(&mut top).zip(&mut bottom).zip(&below) {
+        let delta = edge_delta(a, *b, *c, d, threshold);
+        *b = clamp_u8(i32::from(*b) + delta);
+        *c = clamp_u8(i32::from(*c) - delta);
+    }
+    window[y - 1] = top;
+    window[y] = bottom;
+}
+
+/// The correction of the samples on either side of an edge. Small differences are
+/// smoothed, and the correction falls off for differences beyond the threshold,
+/// which are likely to be real edges of the picture.
+fn edge_delta(a: u8, b: u8, c: u8, d: u8, threshold: i32) -> i32 {
+    let (a, b, c, d) = (i32::from(a), i32::from(b), i32::from(c), i32::from(d));
+    let delta = (a - d + 3 * (c - b) + 4) >> 3;
+    let magnitude = delta.abs();
+    let magnitude = if magnitude < threshold {
+        magnitude
+    } else {
+        (2 * threshold - magnitude).max(0)
+    };
+    magnitude * delta.signum()
+}
+
+/// Returns the variance of every other sample of the 8x8 block at the given position.
+pub fn block_variance(window: &Window, x: usize, y: usize) -> i32 {
+    let mut sum = 0;
+    let mut square_sum = 0;
+    for row in window[y..y + 8].iter().step_by(2) {
+        for &sample in row[x..x + 8].iter().step_by(2) {
+            let sample = i32::from(sample);
+            sum += sample;
+            square_sum += sample * sample;
+        }
+    }
+    (16 * square_sum - sum * sum) >> 8
+}
+
+/// Copies the 8x8 block at the given position of a window.
+pub fn copy(window: &Window, x: usize, y: usize) -> [u8; 64] {
+    let mut block = [0; 64];
+    for (i, sample) in block.iter_mut().enumerate() {
+        *sample = window[y + i / 8][x + i % 8];
+    }
+    block
+}
+
+/// Interpolates the 8x8 block at the given position of a window, offset by eighths of
+/// a pixel, with bilinear filtering.
+pub fn bilinear(window: &Window, x: usize, y: usize, x8: usize, y8: usize) -> [u8; 64] {
+    let (x8, y8) = (x8 as u32, y8 as u32);
+    let mut block = [0; 64];
+    if x8 != 0 && y8 != 0 {
+        // Diagonal offsets are filtered horizontally and then vertically.
+        let mut rows = [[0; 8]; 9];
+        for (row, samples) in rows.iter_mut().zip(&window[y..]) {
+            for (i, sample) in row.iter_mut().enumerate() {
+                let (a, b) = (u32::from(samples[x + i]), u32::from(samples[x + i + 1]));
+                *sample = ((8 - x8) * 8 * a + x8 * 8 * b + 32) >> 6;
+            }
+        }
+        for (i, sample) in block.iter_mut().enumerate() {
+            let (a, b) = (rows[i / 8][i % 8], rows[i / 8 + 1][i % 8]);
+            *sample = (((8 - y8) * 8 * a + y8 * 8 * b + 32) >> 6) as u8;
+        }
+    } else {
+        for (i, sample) in block.iter_mut().enumerate() {
+            let (sx, sy) = (x + i % 8, y + i / 8);
+            let a = u32::from(window[sy][sx]);
+            let b = u32::from(window[sy][sx + 1]);
+            let c = u32::from(window[sy + 1][sx]);
+            let d = u32::from(window[sy + 1][sx + 1]);
+            *sample = (((8 - x8) * (8 - y8) * a
+                + x8 * (8 - y8) * b
+                + (8 - x8) * y8 * c
+                + x8 * y8 * d
+                + 32)
+                >> 6) as u8;
+        }
+    }
+    block
+}
+
+/// Interpolates the 8x8 block at the given position of a window, offset by eighths of
+/// a pixel, with one of the bicubic filters.
+pub fn bicubic(
+    window: &Window,
+    x: usize,
+    y: usize,
+    x8: usize,
+    y8: usize,
+    filter: usize,
+) -> [u8; 64] {
+    let taps = |samples: [u8; 4], weights: &[i32; 4]| {
+        let sum: i32 = samples
+            .iter()
+            .zip(weights)
+            .map(|(&sample, &weight)| i32::from(sample) * weight)
+            .sum();
+        clamp_u8((sum + 64) >> 7)
+    };
+    let horizontal = &BICUBIC_FILTERS[filter][x8];
+    let vertical = &BICUBIC_FILTERS[filter][y8];
+
+    let mut block = [0; 64];
+    if y8 == 0 {
+        for (i, sample) in block.iter_mut().enumerate() {
+            let row = &window[y + i / 8];
+            let sx = x + i % 8;
+            *sample = taps([row[sx - 1], row[sx], row[sx + 1], row[sx + 2]], horizontal);
+        }
+    } else if x8 == 0 {
+        for (i, sample) in block.iter_mut().enumerate() {
+            let (sx, sy) = (x + i % 8, y + i / 8);
+            *sample = taps(
+                [
+                    window[sy - 1][sx],
+                    window[sy][sx],
+                    window[sy + 1][sx],
+                    window[sy + 2][sx],
+                ],
+                vertical,
+            );
+        }
+    } else {
+        // Diagonal offsets are filtered horizontally, from the row above the block to
+        // the second row below it, and then vertically.
+        let mut rows = [[0; 8]; 11];
+        for (row, samples) in rows.iter_mut().zip(&window[y - 1..]) {
+            for (i, sample) in row.iter_mut().enumerate() {
+                let sx = x + i;
+                *sample = taps(
+                    [
+                        samples[sx - 1],
+                        samples[sx],
+                        samples[sx + 1],
+                        samples[sx + 2],
+                    ],
+                    horizontal,
+                );
+            }
+        }
+        for (i, sample) in block.iter_mut().enumerate() {
+            let (sx, sy) = (i % 8, i / 8 + 1);
+            *sample = taps(
+                [
+                    rows[sy - 1][sx],
+                    rows[sy][sx],
+                    rows[sy + 1][sx],
+                    rows[sy + 2][sx],
+                ],
+                vertical,
+            );
+        }
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The inverse DCT of a block in floating point, with the scale of the integer transform.
+    fn reference_transform(coefficients: &[i16; 64]) -> [f64; 64] {
+        use std::f64::consts::{FRAC_1_SQRT_2, PI};
+        let basis = |k: usize, n: usize| {
+            let scale = if k == 0 { FRAC_1_SQRT_2 } else { 1.0 };
+            scale * ((2 * n + 1) as f64 * k as f64 * PI / 16.0).cos()
+        };
+        let mut samples = [0.0; 64];
+        for (i, sample) in samples.iter_mut().enumerate() {
+            let (x, y) = (i % 8, i / 8);
+            for (j, &coefficient) in coefficients.iter().enumerate() {
+                let (u, v) = (j % 8, j / 8);
+                *sample += f64::from(coefficient) * basis(u, x) * basis(v, y);
+            }
+            *sample /= 16.0;
+        }
+        samples
+    }
+
+    #[test]
+    fn inverse_transform_matches_reference() {
+        // A linear congruential generator, for coefficients in the range of 12-bit samples.
+        let mut seed = 1u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((seed >> 16) % 512) as i16 - 256
+        };
+        // Like the IEEE 1180 test of IDCTs, check the peak, mean and mean square errors.
+        let (mut sum, mut square_sum) = (0.0, 0.0);
+        for _ in 0..1000 {
+            let mut coefficients = [0; 64];
+            for coefficient in coefficients.iter_mut() {
+                *coefficient = next();
+            }
+            let samples = inverse_transform(&coefficients);
+            let reference = reference_transform(&coefficients);
+            for (&sample, &expected) in samples.iter().zip(reference.iter()) {
+                let error = f64::from(sample) - expected;
+                assert!(error.abs() < 2.5);
+                sum += error;
+                square_sum += error * error;
+            }
+        }
+        let count = 64.0 * 1000.0;
+        assert!((sum / count).abs() < 0.05);
+        assert!(square_sum / count < 0.2);
+    }
+
+    #[test]
+    fn inverse_transform_of_dc() {
+        let mut coefficients = [0; 64];
+        coefficients[0] = 320;
+        assert_eq!(inverse_transform(&coefficients), [10; 64]);
+        assert_eq!(inverse_transform_dc(320), 10);
+        // The shortcut rounds up where the full transform rounds down.
+        coefficients[0] = 369;
+        assert_eq!(inverse_transform(&coefficients), [11; 64]);
+        assert_eq!(inverse_transform_dc(369), 12);
+        assert_eq!(inverse_transform_dc(-17), -1);
+    }
+}
@@ -0,0 +1,82 @@
+//! The Huffman codes that the coefficients of a frame can be coded with,
+//! instead of the range coder.
+
+use bitstream_io::{BigEndian, BitReader};
+use std::io::Read;
+
+type Error = Box<dyn std::error::Error>;
+
+/// A Huffman code built from the probabilities of a binary tree.
+pub struct HuffmanTree {
+    /// The nodes of the code, with the root last.
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Node {
+    /// The decoded value of a leaf, or `None` for an inner node.
+    symbol: Option<u8>,
+    count: u32,
+
+    /// The index of the first of the two children of an inner node.
+    children: usize,
+}
+
+impl HuffmanTree {
+    /// Builds the code of a binary tree with the given probabilities.
+    ///
+    /// `map` gives the children of each node of the binary tree, starting from the root.
+    /// Children below the number of values are leaves, and the others are inner nodes.
+    pub fn new(probabilities: &[u8], map: &[u8]) -> Self {
+        let size = map.len() / 2 + 1;
+
+        // The count of each value is estimated from the probabilities of its branches.
+        let mut counts = vec![0; size * 2];
+        counts[size] = 256;
+        for (i, &probability) in probabilities.iter().take(size - 1).enumerate() {
+            let count = counts[size + i];
+            let left = (count * u32::from(probability)) >> 8;
+            let right = (count * (255 - u32::from(probability))) >> 8;
+            counts[usize::from(map[2 * i])] = left.max(1);
+            counts[usize::from(map[2 * i + 1])] = right.max(1);
+        }
+
+        let mut nodes: Vec<_> = (0..size)
+            .map(|value| Node {
+                symbol: Some(value as u8),
+                count: counts[value],
+                children: 0,
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.count.cmp(&b.count).then(b.symbol.cmp(&a.symbol)));
+
+        // The two least likely nodes are merged, and the merged node is inserted in order,
+        // before any nodes of the same count.
+        nodes.resize(size * 2 - 1, Node::default());
+        for (i, end) in (0..size - 1).map(|merge| (merge * 2, size + merge)) {
+            let count = nodes[i].count + nodes[i + 1].count;
+            let mut position = end;
+            while position > i + 2 && count <= nodes[position - 1].count {
+                nodes[position] = nodes[position - 1];
+                position -= 1;
+            }
+            nodes[position] = Node {
+                symbol: None,
+                count,
+                children: i,
+            };
+        }
+        Self { nodes }
+    }
+
+    /// Reads a code from the bitstream, and returns its value.
+    pub fn read<R: Read>(&self, reader: &mut BitReader<R, BigEndian>) -> Result<u8, Error> {
+        let mut node = self.nodes[self.nodes.len() - 1];
+        loop {
+            match node.symbol {
+                Some(value) => return Ok(value),
+                None => node = self.nodes[node.children + reader.read_bit()? as usize],
+            }
+        }
+    }
+}
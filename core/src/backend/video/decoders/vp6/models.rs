@@ -0,0 +1,249 @@
+//! The probability models of VP6, which adapt to the video as frames update them.
+
+use super::range_coder::RangeDecoder;
+use super::tables::*;
+
+/// The probabilities that macroblock types, motion vectors and coefficients are coded with.
+#[derive(Clone, Debug)]
+pub struct Model {
+    /// The probability that a motion vector component is long, by component.
+    pub vector_dct: [u8; 2],
+
+    /// The probability that a motion vector component is positive, by component.
+    pub vector_sig: [u8; 2],
+
+    /// The probabilities of the tree of short motion vector components, by component.
+    pub vector_pdv: [[u8; 7]; 2],
+
+    /// The probabilities of the bits of long motion vector components, by component.
+    pub vector_fdv: [[u8; 8]; 2],
+
+    /// The probabilities of DC coefficients, by plane type.
+    pub coeff_dccv: [[u8; 11]; 2],
+
+    /// The probabilities of AC coefficients, by plane type, code type and coefficient group.
+    pub coeff_ract: [[[[u8; 11]; 6]; 3]; 2],
+
+    /// The probabilities of the first nodes of DC coefficients,
+    /// by plane type and the number of neighbouring blocks with a DC coefficient.
+    pub coeff_dcct: [[[u8; 5]; 3]; 2],
+
+    /// The probabilities of the lengths of zero runs, by whether the run starts
+    /// at the sixth coefficient or later.
+    pub coeff_runv: [[u8; 14]; 2],
+
+    /// The band of each zigzag position.
+    pub coeff_reorder: [u8; 64],
+
+    /// The zigzag position of each coefficient, in the order they are coded.
+    pub coeff_index_to_pos: [u8; 64],
+
+    /// The statistics that the macroblock type probabilities are computed from.
+    pub mb_type_stats: [[[u8; 2]; 10]; 3],
+
+    /// The probabilities of macroblock types, by context and the type of the previous
+    /// macroblock.
+    pub mb_type: [[[u8; 10]; 10]; 3],
+}
+
+impl Model {
+    /// Creates the model that every keyframe starts with.
+    pub fn new(interlaced: bool) -> Self {
+        let mut model = Self {
+            vector_dct: DEFAULT_VECTOR_DCT,
+            vector_sig: DEFAULT_VECTOR_SIG,
+            vector_pdv: DEFAULT_VECTOR_PDV,
+            vector_fdv: DEFAULT_VECTOR_FDV,
+            coeff_dccv: [[128; 11]; 2],
+            coeff_ract: [[[[128; 11]; 6]; 3]; 2],
+            coeff_dcct: [[[128; 5]; 3]; 2],
+            coeff_runv: DEFAULT_RUNV,
+            coeff_reorder: if interlaced {
+                INTERLACED_COEFF_REORDER
+            } else {
+                DEFAULT_COEFF_REORDER
+            },
+            coeff_index_to_pos: [0; 64],
+            mb_type_stats: DEFAULT_MB_TYPE_STATS,
+            mb_type: [[[0; 10]; 10]; 3],
+        };
+        model.update_coeff_order();
+        model
+    }
+
+    /// Orders the coefficients by their band, and then by their zigzag position.
+    fn update_coeff_order(&mut self) {
+        let mut index = 1;
+        for band in 0..16 {
+            for position in 1..64 {
+                if self.coeff_reorder[position] == band {
+                    self.coeff_index_to_pos[index] = position as u8;
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    /// Parses the changes to the macroblock type statistics of an inter frame.
+    pub fn parse_mb_type_models(&mut self, decoder: &mut RangeDecoder) {
+        for (context, stats) in self.mb_type_stats.iter_mut().enumerate() {
+            if decoder.read_bit() {
+                let index = decoder.read_bits(4) as usize;
+                *stats = PREDEFINED_MB_TYPE_STATS[index][context];
+            }
+            if decoder.read_bit() {
+                for count in stats.iter_mut().flatten() {
+                    if decoder.read(205) {
+                        let is_negative = decoder.read_bit();
+                        let mut delta =
+                            decoder.read_tree(&MB_TYPE_DELTA_TREE, &MB_TYPE_DELTA_PROBABILITIES);
+                        if delta == 0 {
+                            delta = (4 * decoder.read_bits(7)) as u8;
+                        }
+                        *count = if is_negative {
+                            count.wrapping_sub(delta)
+                        } else {
+                            count.wrapping_add(delta)
+                        };
+                    }
+                }
+            }
+        }
+        self.update_mb_type_probabilities();
+    }
+
+    /// Computes the probabilities of the macroblock type tree from the statistics.
+    pub fn update_mb_type_probabilities(&mut self) {
+        for (stats, probabilities) in self.mb_type_stats.iter().zip(self.mb_type.iter_mut()) {
+            let weights: Vec<u32> = stats.iter().map(|s| 100 * u32::from(s[1])).collect();
+            for (previous, probabilities) in probabilities.iter_mut().enumerate() {
+                let repeated = u32::from(stats[previous][0]);
+                let not_repeated = u32::from(stats[previous][1]);
+                probabilities[0] = (255 - 255 * repeated / (1 + repeated + not_repeated)) as u8;
+
+                // The previous type is excluded from the tree.
+                let p = |mb_type: usize| {
+                    if mb_type == previous {
+                        0
+                    } else {
+                        weights[mb_type]
+                    }
+                };
+                let p02 = p(0) + p(2);
+                let p34 = p(3) + p(4);
+                let p0234 = p02 + p34;
+                let p17 = p(1) + p(7);
+                let p56 = p(5) + p(6);
+                let p89 = p(8) + p(9);
+                let p5689 = p56 + p89;
+                let p156789 = p17 + p5689;
+                let probability = |a: u32, b: u32| (1 + 255 * a / (1 + b)) as u8;
+                probabilities[1] = probability(p0234, p0234 + p156789);
+                probabilities[2] = probability(p02, p0234);
+                probabilities[3] = probability(p17, p156789);
+                probabilities[4] = probability(p(0), p02);
+                probabilities[5] = probability(p(3), p34);
+                probabilities[6] = probability(p(1), p17);
+                probabilities[7] = probability(p56, p5689);
+                probabilities[8] = probability(p(5), p56);
+                probabilities[9] = probability(p(8), p89);
+            }
+        }
+    }
+
+    /// Parses the changes to the motion vector probabilities of an inter frame.
+    pub fn parse_vector_models(&mut self, decoder: &mut RangeDecoder) {
+        for (component, updates) in VECTOR_SIG_DCT_UPDATE.iter().enumerate() {
+            if decoder.read(updates[0]) {
+                self.vector_dct[component] = decoder.read_probability();
+            }
+            if decoder.read(updates[1]) {
+                self.vector_sig[component] = decoder.read_probability();
+            }
+        }
+        for (probabilities, updates) in self.vector_pdv.iter_mut().zip(&VECTOR_PDV_UPDATE) {
+            for (probability, &update) in probabilities.iter_mut().zip(updates) {
+                if decoder.read(update) {
+                    *probability = decoder.read_probability();
+                }
+            }
+        }
+        for (probabilities, updates) in self.vector_fdv.iter_mut().zip(&VECTOR_FDV_UPDATE) {
+            for (probability, &update) in probabilities.iter_mut().zip(updates) {
+                if decoder.read(update) {
+                    *probability = decoder.read_probability();
+                }
+            }
+        }
+    }
+
+    /// Parses the changes to the coefficient probabilities of a frame.
+    ///
+    /// Keyframes reset the probabilities that they don't change to the last probability
+    /// that was coded for the same node.
+    pub fn parse_coeff_models(&mut self, decoder: &mut RangeDecoder, is_keyframe: bool) {
+        let mut default_probabilities = [128; 11];
+
+        for (probabilities, updates) in self.coeff_dccv.iter_mut().zip(&DCCV_UPDATE) {
+            for (node, probability) in probabilities.iter_mut().enumerate() {
+                if decoder.read(updates[node]) {
+                    default_probabilities[node] = decoder.read_probability();
+                    *probability = default_probabilities[node];
+                } else if is_keyframe {
+                    *probability = default_probabilities[node];
+                }
+            }
+        }
+
+        if decoder.read_bit() {
+            for (band, &update) in self
+                .coeff_reorder
+                .iter_mut()
+                .zip(&COEFF_REORDER_UPDATE)
+                .skip(1)
+            {
+                if decoder.read(update) {
+                    *band = decoder.read_bits(4) as u8;
+                }
+            }
+            self.update_coeff_order();
+        }
+
+        for (probabilities, updates) in self.coeff_runv.iter_mut().zip(&RUNV_UPDATE) {
+            for (probability, &update) in probabilities.iter_mut().zip(updates) {
+                if decoder.read(update) {
+                    *probability = decoder.read_probability();
+                }
+            }
+        }
+
+        for (code_type, updates) in RACT_UPDATE.iter().enumerate() {
+            for (plane_type, updates) in updates.iter().enumerate() {
+                for (group, updates) in updates.iter().enumerate() {
+                    let probabilities = &mut self.coeff_ract[plane_type][code_type][group];
+                    for (node, probability) in probabilities.iter_mut().enumerate() {
+                        if decoder.read(updates[node]) {
+                            default_probabilities[node] = decoder.read_probability();
+                            *probability = default_probabilities[node];
+                        } else if is_keyframe {
+                            *probability = default_probabilities[node];
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Computes the DC coefficient probabilities of each context.
+    pub fn update_dcct(&mut self) {
+        for (dcct, dccv) in self.coeff_dcct.iter_mut().zip(&self.coeff_dccv) {
+            for (probabilities, factors) in dcct.iter_mut().zip(&DCCT_LINEAR_COMBINATION) {
+                for (node, probability) in probabilities.iter_mut().enumerate() {
+                    let (factor, offset) = factors[node];
+                    let value = ((i32::from(dccv[node]) * factor + 128) >> 8) + offset;
+                    *probability = value.max(1).min(255) as u8;
+                }
+            }
+        }
+    }
+}
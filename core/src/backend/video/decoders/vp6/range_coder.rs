@@ -0,0 +1,85 @@
+//! The boolean range decoder that most of VP6 is coded with.
+
+use super::tables::Tree;
+
+/// Decodes booleans that are arithmetic coded with 8-bit probabilities.
+/// Reading past the end of the data reads zeros.
+pub struct RangeDecoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    value: u32,
+    range: u32,
+    bit_count: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut decoder = Self {
+            data,
+            position: 0,
+            value: 0,
+            range: 255,
+            bit_count: 0,
+        };
+        decoder.value = u32::from(decoder.next_byte()) << 8 | u32::from(decoder.next_byte());
+        decoder
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.data.get(self.position).copied().unwrap_or(0);
+        self.position += 1;
+        byte
+    }
+
+    /// Reads a boolean that is false with a probability of `probability / 256`.
+    pub fn read(&mut self, probability: u8) -> bool {
+        let split = 1 + (((self.range - 1) * u32::from(probability)) >> 8);
+        let big_split = split << 8;
+        let bit = if self.value >= big_split {
+            self.range -= split;
+            self.value -= big_split;
+            true
+        } else {
+            self.range = split;
+            false
+        };
+        while self.range < 128 {
+            self.value <<= 1;
+            self.range <<= 1;
+            self.bit_count += 1;
+            if self.bit_count == 8 {
+                self.bit_count = 0;
+                self.value |= u32::from(self.next_byte());
+            }
+        }
+        bit
+    }
+
+    pub fn read_bit(&mut self) -> bool {
+        self.read(128)
+    }
+
+    /// Reads an unsigned value, from the most significant bit.
+    pub fn read_bits(&mut self, bits: u32) -> u32 {
+        (0..bits).fold(0, |value, _| value << 1 | u32::from(self.read_bit()))
+    }
+
+    /// Reads a probability that is coded in 7 bits. It is never 0.
+    pub fn read_probability(&mut self) -> u8 {
+        ((self.read_bits(7) << 1) as u8).max(1)
+    }
+
+    /// Reads a value with a tree, using the probabilities of its branches.
+    pub fn read_tree(&mut self, tree: &Tree, probabilities: &[u8]) -> u8 {
+        let mut node = 0;
+        while tree[node].0 > 0 {
+            let (offset, probability) = tree[node];
+            if self.read(probabilities[usize::from(probability)]) {
+                node += offset as usize;
+            } else {
+                node += 1;
+            }
+        }
+        -tree[node].0 as u8
+    }
+}
@@ -0,0 +1,1147 @@
+//! The constant tables of VP6.
+
+/// A binary tree that a value is decoded with, as `(offset, probability index)` nodes.
+///
+/// Nodes with a positive offset are branches: a true bit skips ahead by the offset,
+/// and a false bit moves to the next node. The other nodes are leaves, whose value is
+/// the negated offset.
+pub type Tree = [(i8, u8)];
+
+/// The dequantization factors of the AC coefficients, by quantizer.
+pub const AC_DEQUANT: [u8; 64] = [
+    94, 92, 90, 88, 86, 82, 78, 74, 70, 66, 62, 58, 54, 53, 52, 51, 50, 49, 48, 47, 46, 45, 44, 43,
+    42, 40, 39, 37, 36, 35, 34, 33, 32, 31, 30, 29, 28, 27, 26, 25, 24, 23, 22, 21, 20, 19, 18, 17,
+    16, 15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1,
+];
+
+/// The dequantization factors of the DC coefficients, by quantizer.
+pub const DC_DEQUANT: [u8; 64] = [
+    47, 47, 47, 47, 45, 43, 43, 43, 43, 43, 42, 41, 41, 40, 40, 40, 40, 35, 35, 35, 35, 33, 33, 33,
+    33, 32, 32, 32, 27, 27, 26, 26, 25, 25, 24, 24, 23, 23, 19, 19, 19, 19, 18, 18, 17, 16, 16, 16,
+    16, 16, 15, 11, 11, 11, 10, 10, 9, 8, 7, 5, 3, 3, 2, 2,
+];
+
+/// The strength of the filter applied to block edges in predictions, by quantizer.
+pub const FILTER_THRESHOLD: [u8; 64] = [
+    14, 14, 13, 13, 12, 12, 10, 10, 10, 10, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8,
+    8, 8, 8, 8, 8, 8, 8, 8, 7, 7, 7, 7, 7, 7, 6, 6, 6, 6, 6, 6, 5, 5, 5, 5, 4, 4, 4, 4, 4, 4, 4, 3,
+    3, 3, 3, 2,
+];
+
+/// The positions of the macroblocks that motion vectors are predicted from,
+/// relative to the current macroblock, in order of preference.
+pub const CANDIDATE_POSITIONS: [(isize, isize); 12] = [
+    (0, -1),
+    (-1, 0),
+    (-1, -1),
+    (1, -1),
+    (0, -2),
+    (-2, 0),
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+    (-2, -2),
+    (2, -2),
+];
+
+/// The initial macroblock type statistics of each context, as the counts of each type
+/// being repeated and not being repeated.
+pub const DEFAULT_MB_TYPE_STATS: [[[u8; 2]; 10]; 3] = [
+    [
+        [69, 42],
+        [1, 2],
+        [1, 7],
+        [44, 42],
+        [6, 22],
+        [1, 3],
+        [0, 2],
+        [1, 5],
+        [0, 1],
+        [0, 0],
+    ],
+    [
+        [229, 8],
+        [1, 1],
+        [0, 8],
+        [0, 0],
+        [0, 0],
+        [1, 2],
+        [0, 1],
+        [0, 0],
+        [1, 1],
+        [0, 0],
+    ],
+    [
+        [122, 35],
+        [1, 1],
+        [1, 6],
+        [46, 34],
+        [0, 0],
+        [1, 2],
+        [0, 1],
+        [0, 1],
+        [1, 1],
+        [0, 0],
+    ],
+];
+
+/// Sets of macroblock type statistics that a frame can select instead of coding them.
+pub const PREDEFINED_MB_TYPE_STATS: [[[[u8; 2]; 10]; 3]; 16] = [
+    [
+        [
+            [9, 15],
+            [32, 25],
+            [7, 19],
+            [9, 21],
+            [1, 12],
+            [14, 12],
+            [3, 18],
+            [14, 23],
+            [3, 10],
+            [0, 4],
+        ],
+        [
+            [41, 22],
+            [1, 0],
+            [1, 31],
+            [0, 0],
+            [0, 0],
+            [0, 1],
+            [1, 7],
+            [0, 1],
+            [98, 25],
+            [4, 10],
+        ],
+        [
+            [2, 3],
+            [2, 3],
+            [0, 2],
+            [0, 2],
+            [0, 0],
+            [11, 4],
+            [1, 4],
+            [0, 2],
+            [3, 2],
+            [0, 4],
+        ],
+    ],
+    [
+        [
+            [48, 39],
+            [1, 2],
+            [11, 27],
+            [29, 44],
+            [7, 27],
+            [1, 4],
+            [0, 3],
+            [1, 6],
+            [1, 2],
+            [0, 0],
+        ],
+        [
+            [123, 37],
+            [6, 4],
+            [1, 27],
+            [0, 0],
+            [0, 0],
+            [5, 8],
+            [1, 7],
+            [0, 1],
+            [12, 10],
+            [0, 2],
+        ],
+        [
+            [49, 46],
+            [3, 4],
+            [7, 31],
+            [42, 41],
+            [0, 0],
+            [2, 6],
+            [1, 7],
+            [1, 4],
+            [2, 4],
+            [0, 1],
+        ],
+    ],
+    [
+        [
+            [21, 32],
+            [1, 2],
+            [4, 10],
+            [32, 43],
+            [6, 23],
+            [2, 3],
+            [1, 19],
+            [1, 6],
+            [12, 21],
+            [0, 7],
+        ],
+        [
+            [26, 14],
+            [14, 12],
+            [0, 24],
+            [0, 0],
+            [0, 0],
+            [55, 17],
+            [1, 9],
+            [0, 36],
+            [5, 7],
+            [1, 3],
+        ],
+        [
+            [26, 25],
+            [1, 1],
+            [2, 10],
+            [67, 39],
+            [0, 0],
+            [1, 1],
+            [0, 14],
+            [0, 2],
+            [31, 26],
+            [1, 6],
+        ],
+    ],
+    [
+        [
+            [69, 83],
+            [0, 0],
+            [0, 2],
+            [10, 29],
+            [3, 12],
+            [0, 1],
+            [0, 3],
+            [0, 3],
+            [2, 2],
+            [0, 0],
+        ],
+        [
+            [209, 5],
+            [0, 0],
+            [0, 27],
+            [0, 0],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+            [0, 0],
+        ],
+        [
+            [103, 46],
+            [1, 2],
+            [2, 10],
+            [33, 42],
+            [0, 0],
+            [1, 4],
+            [0, 3],
+            [0, 1],
+            [1, 3],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [11, 20],
+            [1, 4],
+            [18, 36],
+            [43, 48],
+            [13, 35],
+            [0, 2],
+            [0, 5],
+            [3, 12],
+            [1, 2],
+            [0, 0],
+        ],
+        [
+            [2, 5],
+            [4, 5],
+            [0, 121],
+            [0, 0],
+            [0, 0],
+            [0, 3],
+            [2, 4],
+            [1, 4],
+            [2, 2],
+            [0, 1],
+        ],
+        [
+            [14, 31],
+            [9, 13],
+            [14, 54],
+            [22, 29],
+            [0, 0],
+            [2, 6],
+            [4, 18],
+            [6, 13],
+            [1, 5],
+            [0, 1],
+        ],
+    ],
+    [
+        [
+            [70, 44],
+            [0, 1],
+            [2, 10],
+            [37, 46],
+            [8, 26],
+            [0, 2],
+            [0, 2],
+            [0, 2],
+            [0, 1],
+            [0, 0],
+        ],
+        [
+            [175, 5],
+            [0, 1],
+            [0, 48],
+            [0, 0],
+            [0, 0],
+            [0, 2],
+            [0, 1],
+            [0, 2],
+            [0, 1],
+            [0, 0],
+        ],
+        [
+            [85, 39],
+            [0, 0],
+            [1, 9],
+            [69, 40],
+            [0, 0],
+            [0, 1],
+            [0, 3],
+            [0, 1],
+            [2, 3],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [8, 15],
+            [0, 1],
+            [8, 21],
+            [74, 53],
+            [22, 42],
+            [0, 1],
+            [0, 2],
+            [0, 3],
+            [1, 2],
+            [0, 0],
+        ],
+        [
+            [83, 5],
+            [2, 3],
+            [0, 102],
+            [0, 0],
+            [0, 0],
+            [1, 3],
+            [0, 2],
+            [0, 1],
+            [0, 0],
+            [0, 0],
+        ],
+        [
+            [31, 28],
+            [0, 0],
+            [3, 14],
+            [130, 34],
+            [0, 0],
+            [0, 1],
+            [0, 3],
+            [0, 1],
+            [3, 3],
+            [0, 1],
+        ],
+    ],
+    [
+        [
+            [141, 42],
+            [0, 0],
+            [1, 4],
+            [11, 24],
+            [1, 11],
+            [0, 1],
+            [0, 1],
+            [0, 2],
+            [0, 0],
+            [0, 0],
+        ],
+        [
+            [233, 6],
+            [0, 0],
+            [0, 8],
+            [0, 0],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+            [0, 1],
+            [0, 0],
+        ],
+        [
+            [171, 25],
+            [0, 0],
+            [1, 5],
+            [25, 21],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [8, 19],
+            [4, 10],
+            [24, 45],
+            [21, 37],
+            [9, 29],
+            [0, 3],
+            [1, 7],
+            [11, 25],
+            [0, 2],
+            [0, 1],
+        ],
+        [
+            [34, 16],
+            [112, 21],
+            [1, 28],
+            [0, 0],
+            [0, 0],
+            [6, 8],
+            [1, 7],
+            [0, 3],
+            [2, 5],
+            [0, 2],
+        ],
+        [
+            [17, 21],
+            [68, 29],
+            [6, 15],
+            [13, 22],
+            [0, 0],
+            [6, 12],
+            [3, 14],
+            [4, 10],
+            [1, 7],
+            [0, 3],
+        ],
+    ],
+    [
+        [
+            [46, 42],
+            [0, 1],
+            [2, 10],
+            [54, 51],
+            [10, 30],
+            [0, 2],
+            [0, 2],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+        ],
+        [
+            [159, 35],
+            [2, 2],
+            [0, 25],
+            [0, 0],
+            [0, 0],
+            [3, 6],
+            [0, 5],
+            [0, 1],
+            [4, 4],
+            [0, 1],
+        ],
+        [
+            [51, 39],
+            [0, 1],
+            [2, 12],
+            [91, 44],
+            [0, 0],
+            [0, 2],
+            [0, 3],
+            [0, 1],
+            [2, 3],
+            [0, 1],
+        ],
+    ],
+    [
+        [
+            [28, 32],
+            [0, 0],
+            [3, 10],
+            [75, 51],
+            [14, 33],
+            [0, 1],
+            [0, 2],
+            [0, 1],
+            [1, 2],
+            [0, 0],
+        ],
+        [
+            [75, 39],
+            [5, 7],
+            [2, 48],
+            [0, 0],
+            [0, 0],
+            [3, 11],
+            [2, 16],
+            [1, 4],
+            [7, 10],
+            [0, 2],
+        ],
+        [
+            [81, 25],
+            [0, 0],
+            [2, 9],
+            [106, 26],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 1],
+            [1, 1],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [100, 46],
+            [0, 1],
+            [3, 9],
+            [21, 37],
+            [5, 20],
+            [0, 1],
+            [0, 2],
+            [1, 2],
+            [0, 1],
+            [0, 0],
+        ],
+        [
+            [212, 21],
+            [0, 1],
+            [0, 9],
+            [0, 0],
+            [0, 0],
+            [1, 2],
+            [0, 2],
+            [0, 0],
+            [2, 2],
+            [0, 0],
+        ],
+        [
+            [140, 37],
+            [0, 1],
+            [1, 8],
+            [24, 33],
+            [0, 0],
+            [1, 2],
+            [0, 2],
+            [0, 1],
+            [1, 2],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [27, 29],
+            [0, 1],
+            [9, 25],
+            [53, 51],
+            [12, 34],
+            [0, 1],
+            [0, 3],
+            [1, 5],
+            [0, 2],
+            [0, 0],
+        ],
+        [
+            [4, 2],
+            [0, 0],
+            [0, 172],
+            [0, 0],
+            [0, 0],
+            [0, 1],
+            [0, 2],
+            [0, 0],
+            [2, 0],
+            [0, 0],
+        ],
+        [
+            [14, 23],
+            [1, 3],
+            [11, 53],
+            [90, 31],
+            [0, 0],
+            [0, 3],
+            [1, 5],
+            [2, 6],
+            [1, 2],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [80, 38],
+            [0, 0],
+            [1, 4],
+            [69, 33],
+            [5, 16],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+            [0, 1],
+            [0, 0],
+        ],
+        [
+            [187, 22],
+            [1, 1],
+            [0, 17],
+            [0, 0],
+            [0, 0],
+            [3, 6],
+            [0, 4],
+            [0, 1],
+            [4, 4],
+            [0, 1],
+        ],
+        [
+            [123, 29],
+            [0, 0],
+            [1, 7],
+            [57, 30],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [16, 20],
+            [0, 0],
+            [2, 8],
+            [104, 49],
+            [15, 33],
+            [0, 1],
+            [0, 1],
+            [0, 1],
+            [1, 1],
+            [0, 0],
+        ],
+        [
+            [133, 6],
+            [1, 2],
+            [1, 70],
+            [0, 0],
+            [0, 0],
+            [0, 2],
+            [0, 4],
+            [0, 3],
+            [1, 1],
+            [0, 0],
+        ],
+        [
+            [13, 14],
+            [0, 0],
+            [4, 20],
+            [175, 20],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 1],
+            [1, 1],
+            [0, 0],
+        ],
+    ],
+    [
+        [
+            [194, 16],
+            [0, 0],
+            [1, 1],
+            [1, 9],
+            [1, 3],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+            [0, 0],
+        ],
+        [
+            [251, 1],
+            [0, 0],
+            [0, 2],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+        ],
+        [
+            [202, 23],
+            [0, 0],
+            [1, 3],
+            [2, 9],
+            [0, 0],
+            [0, 1],
+            [0, 1],
+            [0, 1],
+            [0, 0],
+            [0, 0],
+        ],
+    ],
+];
+
+/// The probabilities of the tree that changes to the macroblock type statistics are coded with.
+pub const MB_TYPE_DELTA_PROBABILITIES: [u8; 6] = [171, 83, 199, 140, 125, 104];
+
+/// The tree of changes to the macroblock type statistics.
+pub const MB_TYPE_DELTA_TREE: [(i8, u8); 13] = [
+    (4, 0),
+    (2, 1),
+    (-8, 0),
+    (-4, 0),
+    (8, 2),
+    (6, 3),
+    (4, 4),
+    (2, 5),
+    (-24, 0),
+    (-20, 0),
+    (-16, 0),
+    (-12, 0),
+    (0, 0),
+];
+
+/// The tree of macroblock types, for when the type differs from the previous macroblock.
+pub const MB_TYPE_TREE: [(i8, u8); 19] = [
+    (8, 1),
+    (4, 2),
+    (2, 4),
+    (0, 0),
+    (-2, 0),
+    (2, 5),
+    (-3, 0),
+    (-4, 0),
+    (4, 3),
+    (2, 6),
+    (-1, 0),
+    (-7, 0),
+    (4, 7),
+    (2, 8),
+    (-5, 0),
+    (-6, 0),
+    (2, 9),
+    (-8, 0),
+    (-9, 0),
+];
+
+/// The tree of short motion vector components.
+pub const SHORT_VECTOR_TREE: [(i8, u8); 15] = [
+    (8, 0),
+    (4, 1),
+    (2, 2),
+    (0, 0),
+    (-1, 0),
+    (2, 3),
+    (-2, 0),
+    (-3, 0),
+    (4, 4),
+    (2, 5),
+    (-4, 0),
+    (-5, 0),
+    (2, 6),
+    (-6, 0),
+    (-7, 0),
+];
+
+/// The tree of the categories of large coefficients.
+pub const COEFF_CATEGORY_TREE: [(i8, u8); 11] = [
+    (4, 6),
+    (2, 7),
+    (0, 0),
+    (-1, 0),
+    (4, 8),
+    (2, 9),
+    (-2, 0),
+    (-3, 0),
+    (2, 10),
+    (-4, 0),
+    (-5, 0),
+];
+
+/// The tree of the lengths of runs of zero coefficients.
+/// Runs of 9 or more decode to 0, and are followed by 6 more bits.
+pub const RUN_TREE: [(i8, u8); 17] = [
+    (8, 0),
+    (4, 1),
+    (2, 2),
+    (-1, 0),
+    (-2, 0),
+    (2, 3),
+    (-3, 0),
+    (-4, 0),
+    (8, 4),
+    (4, 5),
+    (2, 6),
+    (-5, 0),
+    (-6, 0),
+    (2, 7),
+    (-7, 0),
+    (-8, 0),
+    (0, 0),
+];
+
+/// The smallest value of each coefficient token.
+pub const COEFF_BIAS: [u16; 11] = [0, 1, 2, 3, 4, 5, 7, 11, 19, 35, 67];
+
+/// The number of extra bits of each coefficient category, minus one.
+pub const COEFF_BIT_LENGTH: [u8; 6] = [0, 1, 2, 3, 4, 10];
+
+/// The probabilities of the extra bits of each coefficient category, in the order that
+/// they are read, from the most significant bit.
+pub const COEFF_BIT_PROBABILITIES: [[u8; 11]; 6] = [
+    [159, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    [145, 165, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    [140, 148, 173, 0, 0, 0, 0, 0, 0, 0, 0],
+    [135, 140, 155, 176, 0, 0, 0, 0, 0, 0, 0],
+    [130, 134, 141, 157, 180, 0, 0, 0, 0, 0, 0],
+    [129, 130, 133, 140, 153, 177, 196, 230, 243, 254, 254],
+];
+
+/// The group of each coefficient index, which selects the AC coefficient probabilities.
+pub const COEFF_GROUPS: [u8; 64] = [
+    0, 0, 1, 1, 1, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4,
+    5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5,
+];
+
+pub const DEFAULT_VECTOR_DCT: [u8; 2] = [0xa2, 0xa4];
+
+pub const DEFAULT_VECTOR_SIG: [u8; 2] = [0x80, 0x80];
+
+pub const DEFAULT_VECTOR_FDV: [[u8; 8]; 2] = [
+    [247, 210, 135, 68, 138, 220, 239, 246],
+    [244, 184, 201, 44, 173, 221, 239, 253],
+];
+
+pub const DEFAULT_VECTOR_PDV: [[u8; 7]; 2] = [
+    [225, 146, 172, 147, 214, 39, 156],
+    [204, 170, 119, 235, 140, 230, 228],
+];
+
+pub const DEFAULT_RUNV: [[u8; 14]; 2] = [
+    [
+        198, 197, 196, 146, 198, 204, 169, 142, 130, 136, 149, 149, 191, 249,
+    ],
+    [
+        135, 201, 181, 154, 98, 117, 132, 126, 146, 169, 184, 240, 246, 254,
+    ],
+];
+
+/// The band of each zigzag position of progressive frames.
+/// Coefficients are coded in order of their band.
+pub const DEFAULT_COEFF_REORDER: [u8; 64] = [
+    0, 0, 1, 1, 1, 2, 2, 2, 2, 2, 2, 3, 3, 4, 4, 4, 5, 5, 5, 5, 6, 6, 7, 7, 7, 7, 7, 8, 8, 9, 9, 9,
+    9, 9, 9, 10, 10, 11, 11, 11, 11, 11, 11, 12, 12, 12, 12, 12, 12, 13, 13, 13, 13, 13, 14, 14,
+    14, 14, 15, 15, 15, 15, 15, 15,
+];
+
+/// The band of each zigzag position of interlaced frames.
+pub const INTERLACED_COEFF_REORDER: [u8; 64] = [
+    0, 1, 0, 1, 1, 2, 5, 3, 2, 2, 2, 2, 4, 7, 8, 10, 9, 7, 5, 4, 2, 3, 5, 6, 8, 9, 11, 12, 13, 12,
+    11, 10, 9, 7, 5, 4, 6, 7, 9, 11, 12, 12, 13, 13, 14, 12, 11, 9, 7, 9, 11, 12, 14, 14, 14, 15,
+    13, 11, 13, 15, 15, 15, 15, 15,
+];
+
+/// The probabilities that each of the motion vector probabilities is updated.
+pub const VECTOR_SIG_DCT_UPDATE: [[u8; 2]; 2] = [[237, 246], [231, 243]];
+
+pub const VECTOR_PDV_UPDATE: [[u8; 7]; 2] = [
+    [253, 253, 254, 254, 254, 254, 254],
+    [245, 253, 254, 254, 254, 254, 254],
+];
+
+pub const VECTOR_FDV_UPDATE: [[u8; 8]; 2] = [
+    [254, 254, 254, 254, 254, 250, 250, 252],
+    [254, 254, 254, 254, 254, 251, 251, 254],
+];
+
+/// The probabilities that each of the coefficient probabilities is updated.
+pub const DCCV_UPDATE: [[u8; 11]; 2] = [
+    [146, 255, 181, 207, 232, 243, 238, 251, 244, 250, 249],
+    [179, 255, 214, 240, 250, 255, 244, 255, 255, 255, 255],
+];
+
+pub const COEFF_REORDER_UPDATE: [u8; 64] = [
+    255, 132, 132, 159, 153, 151, 161, 170, 164, 162, 136, 110, 103, 114, 129, 118, 124, 125, 132,
+    136, 114, 110, 142, 135, 134, 123, 143, 126, 153, 183, 166, 161, 171, 180, 179, 164, 203, 218,
+    225, 217, 215, 206, 203, 217, 229, 241, 248, 243, 253, 255, 253, 255, 255, 255, 255, 255, 255,
+    255, 255, 255, 255, 255, 255, 255,
+];
+
+pub const RUNV_UPDATE: [[u8; 14]; 2] = [
+    [
+        219, 246, 238, 249, 232, 239, 249, 255, 248, 253, 239, 244, 241, 248,
+    ],
+    [
+        198, 232, 251, 253, 219, 241, 253, 255, 248, 249, 244, 238, 251, 255,
+    ],
+];
+
+/// Indexed by code type, plane type and coefficient group.
+pub const RACT_UPDATE: [[[[u8; 11]; 6]; 2]; 3] = [
+    [
+        [
+            [227, 246, 230, 247, 244, 255, 255, 255, 255, 255, 255],
+            [255, 255, 209, 231, 231, 249, 249, 253, 255, 255, 255],
+            [255, 255, 225, 242, 241, 251, 253, 255, 255, 255, 255],
+            [255, 255, 241, 253, 252, 255, 255, 255, 255, 255, 255],
+            [255, 255, 248, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [240, 255, 248, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 240, 253, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [206, 203, 227, 239, 247, 255, 253, 255, 255, 255, 255],
+            [207, 199, 220, 236, 243, 252, 252, 255, 255, 255, 255],
+            [212, 219, 230, 243, 244, 253, 252, 255, 255, 255, 255],
+            [236, 237, 247, 252, 253, 255, 255, 255, 255, 255, 255],
+            [240, 240, 248, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [230, 233, 249, 255, 255, 255, 255, 255, 255, 255, 255],
+            [238, 238, 250, 255, 255, 255, 255, 255, 255, 255, 255],
+            [248, 251, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [225, 239, 227, 231, 244, 253, 243, 255, 255, 253, 255],
+            [232, 234, 224, 228, 242, 249, 242, 252, 251, 251, 255],
+            [235, 249, 238, 240, 251, 255, 249, 255, 253, 253, 255],
+            [249, 253, 251, 250, 255, 255, 255, 255, 255, 255, 255],
+            [251, 250, 249, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [243, 244, 250, 250, 255, 255, 255, 255, 255, 255, 255],
+            [249, 248, 250, 253, 255, 255, 255, 255, 255, 255, 255],
+            [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+];
+
+/// The DC coefficient probabilities of each context are derived from the DC coefficient
+/// probabilities, as `(probability * factor + 128) / 256 + offset`.
+/// Indexed by context and probability, as `(factor, offset)` pairs.
+pub const DCCT_LINEAR_COMBINATION: [[(i32, i32); 5]; 3] = [
+    [(122, 133), (0, 1), (78, 171), (139, 117), (168, 79)],
+    [(133, 51), (0, 1), (169, 71), (214, 44), (210, 38)],
+    [(142, -16), (0, 1), (221, -30), (246, -3), (203, 17)],
+];
+
+/// The children of each node of the coefficient token tree, for building Huffman codes.
+/// Values of 12 and above are the inner nodes of the tree, starting from the root.
+pub const HUFFMAN_COEFF_MAP: [u8; 22] = [
+    13, 14, 11, 0, 1, 15, 16, 18, 2, 17, 3, 4, 19, 20, 5, 6, 21, 22, 7, 8, 9, 10,
+];
+
+/// The children of each node of the run length tree, for building Huffman codes.
+/// Values of 9 and above are the inner nodes of the tree, starting from the root.
+pub const HUFFMAN_RUN_MAP: [u8; 16] = [10, 13, 11, 12, 0, 1, 2, 3, 14, 8, 15, 16, 4, 5, 6, 7];
+
+/// The taps of the bicubic filters that luma predictions can be interpolated with,
+/// by filter and eighth of a pixel. The filters are the cubic convolution kernel with
+/// `a` decreasing from -0.25 in steps of -0.05, normalized to a sum of 128.
+pub const BICUBIC_FILTERS: [[[i32; 4]; 8]; 17] = [
+    [
+        [0, 128, 0, 0],
+        [-3, 122, 9, 0],
+        [-4, 109, 24, -1],
+        [-5, 91, 45, -3],
+        [-4, 68, 68, -4],
+        [-3, 45, 91, -5],
+        [-1, 24, 109, -4],
+        [0, 9, 122, -3],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-4, 124, 9, -1],
+        [-5, 110, 25, -2],
+        [-6, 91, 46, -3],
+        [-5, 69, 69, -5],
+        [-3, 46, 91, -6],
+        [-2, 25, 110, -5],
+        [-1, 9, 124, -4],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-4, 123, 10, -1],
+        [-6, 110, 26, -2],
+        [-7, 92, 47, -4],
+        [-6, 70, 70, -6],
+        [-4, 47, 92, -7],
+        [-2, 26, 110, -6],
+        [-1, 10, 123, -4],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-5, 124, 10, -1],
+        [-7, 110, 27, -2],
+        [-8, 93, 48, -5],
+        [-6, 70, 70, -6],
+        [-5, 48, 93, -8],
+        [-2, 27, 110, -7],
+        [-1, 10, 124, -5],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-6, 124, 11, -1],
+        [-8, 111, 28, -3],
+        [-8, 92, 49, -5],
+        [-7, 71, 71, -7],
+        [-5, 49, 92, -8],
+        [-3, 28, 111, -8],
+        [-1, 11, 124, -6],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-6, 123, 12, -1],
+        [-9, 111, 29, -3],
+        [-9, 93, 50, -6],
+        [-8, 72, 72, -8],
+        [-6, 50, 93, -9],
+        [-3, 29, 111, -9],
+        [-1, 12, 123, -6],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-7, 124, 12, -1],
+        [-10, 111, 30, -3],
+        [-10, 93, 51, -6],
+        [-9, 73, 73, -9],
+        [-6, 51, 93, -10],
+        [-3, 30, 111, -10],
+        [-1, 12, 124, -7],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-7, 123, 13, -1],
+        [-11, 112, 31, -4],
+        [-11, 94, 52, -7],
+        [-10, 74, 74, -10],
+        [-7, 52, 94, -11],
+        [-4, 31, 112, -11],
+        [-1, 13, 123, -7],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-8, 124, 13, -1],
+        [-12, 112, 32, -4],
+        [-12, 94, 53, -7],
+        [-10, 74, 74, -10],
+        [-7, 53, 94, -12],
+        [-4, 32, 112, -12],
+        [-1, 13, 124, -8],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-9, 124, 14, -1],
+        [-13, 112, 33, -4],
+        [-13, 95, 54, -8],
+        [-11, 75, 75, -11],
+        [-8, 54, 95, -13],
+        [-4, 33, 112, -13],
+        [-1, 14, 124, -9],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-9, 123, 15, -1],
+        [-13, 112, 33, -4],
+        [-14, 95, 55, -8],
+        [-12, 76, 76, -12],
+        [-8, 55, 95, -14],
+        [-4, 33, 112, -13],
+        [-1, 15, 123, -9],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-10, 124, 15, -1],
+        [-14, 113, 34, -5],
+        [-15, 97, 55, -9],
+        [-13, 77, 77, -13],
+        [-9, 55, 97, -15],
+        [-5, 34, 113, -14],
+        [-1, 15, 124, -10],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-10, 123, 16, -1],
+        [-15, 113, 35, -5],
+        [-16, 98, 56, -10],
+        [-14, 78, 78, -14],
+        [-10, 56, 98, -16],
+        [-5, 35, 113, -15],
+        [-1, 16, 123, -10],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-11, 124, 17, -2],
+        [-16, 113, 36, -5],
+        [-17, 98, 57, -10],
+        [-14, 78, 78, -14],
+        [-10, 57, 98, -17],
+        [-5, 36, 113, -16],
+        [-2, 17, 124, -11],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-12, 125, 17, -2],
+        [-17, 114, 37, -6],
+        [-18, 99, 58, -11],
+        [-15, 79, 79, -15],
+        [-11, 58, 99, -18],
+        [-6, 37, 114, -17],
+        [-2, 17, 125, -12],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-12, 124, 18, -2],
+        [-18, 114, 38, -6],
+        [-19, 99, 59, -11],
+        [-16, 80, 80, -16],
+        [-11, 59, 99, -19],
+        [-6, 38, 114, -18],
+        [-2, 18, 124, -12],
+    ],
+    [
+        [0, 128, 0, 0],
+        [-13, 125, 18, -2],
+        [-19, 114, 39, -6],
+        [-20, 100, 60, -12],
+        [-17, 81, 81, -17],
+        [-12, 60, 100, -20],
+        [-6, 39, 114, -19],
+        [-2, 18, 125, -13],
+    ],
+];
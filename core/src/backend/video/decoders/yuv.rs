@@ -0,0 +1,95 @@
+//! YUV pictures, as output by the block-based video codecs.
+
+/// The zigzag order that the transform coefficients of 8x8 blocks are coded in.
+pub const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// A plane of 8-bit samples, such as the luma of a picture.
+#[derive(Clone, Debug)]
+pub struct Plane {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl Plane {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width * height],
+        }
+    }
+
+    /// Returns the sample at the given position.
+    /// Positions outside of the plane are clamped to its edges.
+    #[inline]
+    pub fn get_clamped(&self, x: isize, y: isize) -> u8 {
+        let x = x.max(0).min(self.width as isize - 1) as usize;
+        let y = y.max(0).min(self.height as isize - 1) as usize;
+        self.data[y * self.width + x]
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.data[y * self.width + x]
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, value: u8) {
+        self.data[y * self.width + x] = value;
+    }
+}
+
+/// A picture with 4:2:0 chroma subsampling.
+/// The chroma planes are half of the width and height of the luma plane.
+#[derive(Clone, Debug)]
+pub struct YuvPicture {
+    pub y: Plane,
+    pub u: Plane,
+    pub v: Plane,
+}
+
+impl YuvPicture {
+    /// Creates a black picture. The luma plane size must be even.
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut picture = Self {
+            y: Plane::new(width, height),
+            u: Plane::new(width / 2, height / 2),
+            v: Plane::new(width / 2, height / 2),
+        };
+        for sample in picture.y.data.iter_mut() {
+            *sample = 16;
+        }
+        for sample in picture.u.data.iter_mut().chain(picture.v.data.iter_mut()) {
+            *sample = 128;
+        }
+        picture
+    }
+
+    /// Converts the top-left corner of the picture to RGBA,
+    /// using the BT.601 color space with studio swing.
+    pub fn to_rgba(&self, width: usize, height: usize) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let luma = 298 * (i32::from(self.y.get(x, y)) - 16);
+                let u = i32::from(self.u.get(x / 2, y / 2)) - 128;
+                let v = i32::from(self.v.get(x / 2, y / 2)) - 128;
+                rgba.push(clamp_u8((luma + 409 * v + 128) >> 8));
+                rgba.push(clamp_u8((luma - 100 * u - 208 * v + 128) >> 8));
+                rgba.push(clamp_u8((luma + 516 * u + 128) >> 8));
+                rgba.push(255);
+            }
+        }
+        rgba
+    }
+}
+
+#[inline]
+pub fn clamp_u8(value: i32) -> u8 {
+    value.max(0).min(255) as u8
+}
@@ -0,0 +1,249 @@
+//! Video backend that decodes frames in software.
+
+use crate::backend::render::{BitmapHandle, BitmapInfo, RenderBackend};
+use crate::backend::video::decoders::{CodecRegistry, VideoDecoder};
+use crate::backend::video::{EncodedFrame, FrameDependency, VideoBackend, VideoStreamHandle};
+use generational_arena::Arena;
+use swf::{CharacterId, VideoCodec, VideoDeblocking};
+
+type Error = Box<dyn std::error::Error>;
+
+/// Decodes video with the decoders in a `CodecRegistry`,
+/// and uploads the decoded frames to the render backend as bitmaps.
+pub struct SoftwareVideoBackend {
+    codecs: CodecRegistry,
+    streams: Arena<VideoStream>,
+}
+
+struct VideoStream {
+    id: CharacterId,
+    decoder: Box<dyn VideoDecoder>,
+
+    /// The size of the video. Decoders may output larger frames, such as whole macroblocks,
+    /// which are cropped to this size.
+    size: (u16, u16),
+
+    /// The bitmap that decoded frames are uploaded to.
+    bitmap: Option<BitmapHandle>,
+}
+
+impl SoftwareVideoBackend {
+    /// Creates a video backend with the built-in decoders.
+    pub fn new() -> Self {
+        Self::with_codecs(CodecRegistry::default())
+    }
+
+    /// Creates a video backend that decodes with the given codecs.
+    pub fn with_codecs(codecs: CodecRegistry) -> Self {
+        Self {
+            codecs,
+            streams: Arena::new(),
+        }
+    }
+
+    pub fn codecs_mut(&mut self) -> &mut CodecRegistry {
+        &mut self.codecs
+    }
+}
+
+impl Default for SoftwareVideoBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VideoBackend for SoftwareVideoBackend {
+    fn register_video_stream(
+        &mut self,
+        id: CharacterId,
+        _num_frames: u32,
+        size: (u16, u16),
+        codec: VideoCodec,
+        deblocking: VideoDeblocking,
+    ) -> Result<VideoStreamHandle, Error> {
+        let decoder = self
+            .codecs
+            .create_decoder(codec, deblocking)
+            .ok_or_else(|| format!("Unsupported video codec: {:?}", codec))?;
+        Ok(self.streams.insert(VideoStream {
+            id,
+            decoder,
+            size,
+            bitmap: None,
+        }))
+    }
+
+    fn preload_video_stream_frame(
+        &mut self,
+        stream: VideoStreamHandle,
+        encoded_frame: EncodedFrame<'_>,
+    ) -> Result<FrameDependency, Error> {
+        let stream = self
+            .streams
+            .get_mut(stream)
+            .ok_or("Unregistered video stream")?;
+        stream.decoder.preload_frame(encoded_frame)
+    }
+
+    fn decode_video_stream_frame(
+        &mut self,
+        stream: VideoStreamHandle,
+        encoded_frame: EncodedFrame<'_>,
+        renderer: &mut dyn RenderBackend,
+    ) -> Result<BitmapInfo, Error> {
+        let stream = self
+            .streams
+            .get_mut(stream)
+            .ok_or("Unregistered video stream")?;
+        let (stream_width, stream_height) = stream.size;
+        let frame = stream
+            .decoder
+            .decode_frame(encoded_frame)?
+            .crop(stream_width, stream_height);
+        let (width, height) = (frame.width, frame.height);
+        let handle = if let Some(bitmap) = stream.bitmap {
+            renderer.update_texture(bitmap, width.into(), height.into(), frame.rgba)?
+        } else {
+            renderer
+                .register_bitmap_raw(stream.id, width.into(), height.into(), frame.rgba)
+                .handle
+        };
+        stream.bitmap = Some(handle);
+        Ok(BitmapInfo {
+            handle,
+            width,
+            height,
+        })
+    }
+
+    fn unregister_video_stream(
+        &mut self,
+        stream: VideoStreamHandle,
+        renderer: &mut dyn RenderBackend,
+    ) {
+        if let Some(VideoStream {
+            bitmap: Some(bitmap),
+            ..
+        }) = self.streams.remove(stream)
+        {
+            renderer.unregister_bitmap(bitmap);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::render::{Color, SoftwareRenderBackend, Transform};
+    use crate::backend::video::DecodedFrame;
+
+    /// A decoder that outputs 4x3 frames, a macroblock larger than the 3x2 test video.
+    /// The red and green channels are the position of the pixel, and blue is the frame number.
+    struct PaddedDecoder;
+
+    impl VideoDecoder for PaddedDecoder {
+        fn preload_frame(
+            &mut self,
+            _encoded_frame: EncodedFrame<'_>,
+        ) -> Result<FrameDependency, Error> {
+            Ok(FrameDependency::None)
+        }
+
+        fn decode_frame(&mut self, encoded_frame: EncodedFrame<'_>) -> Result<DecodedFrame, Error> {
+            let rgba = (0..3u8)
+                .flat_map(|y| (0..4u8).map(move |x| (x, y)))
+                .flat_map(|(x, y)| vec![x * 50, y * 50, encoded_frame.frame_id as u8, 255])
+                .collect();
+            Ok(DecodedFrame {
+                width: 4,
+                height: 3,
+                rgba,
+            })
+        }
+    }
+
+    fn decode(
+        backend: &mut SoftwareVideoBackend,
+        stream: VideoStreamHandle,
+        frame_id: u32,
+        renderer: &mut dyn RenderBackend,
+    ) -> BitmapInfo {
+        let encoded_frame = EncodedFrame {
+            codec: VideoCodec::H263,
+            data: &[],
+            frame_id,
+        };
+        backend
+            .decode_video_stream_frame(stream, encoded_frame, renderer)
+            .unwrap()
+    }
+
+    #[test]
+    fn decoded_frames_are_cropped_and_reuse_bitmap() {
+        let mut codecs = CodecRegistry::new();
+        codecs.register(VideoCodec::H263, |_| Box::new(PaddedDecoder));
+        let mut backend = SoftwareVideoBackend::with_codecs(codecs);
+        let mut renderer = SoftwareRenderBackend::new(3, 2);
+        let register = |backend: &mut SoftwareVideoBackend| {
+            backend
+                .register_video_stream(
+                    1,
+                    2,
+                    (3, 2),
+                    VideoCodec::H263,
+                    VideoDeblocking::UseVideoPacketValue,
+                )
+                .unwrap()
+        };
+        let stream = register(&mut backend);
+
+        let first = decode(&mut backend, stream, 1, &mut renderer);
+        assert_eq!((first.width, first.height), (3, 2));
+        let second = decode(&mut backend, stream, 2, &mut renderer);
+        assert_eq!(second.handle.0, first.handle.0);
+
+        // Another stream decodes into its own bitmap.
+        let other_stream = register(&mut backend);
+        let other = decode(&mut backend, other_stream, 1, &mut renderer);
+        assert_ne!(other.handle.0, first.handle.0);
+
+        renderer.begin_frame();
+        renderer.render_bitmap(second.handle, &Transform::default(), false);
+        renderer.end_frame();
+        #[rustfmt::skip]
+        assert_eq!(
+            renderer.to_rgba(),
+            [
+                0, 0, 2, 255,   50, 0, 2, 255,   100, 0, 2, 255,
+                0, 50, 2, 255,  50, 50, 2, 255,  100, 50, 2, 255,
+            ]
+        );
+
+        // Unregistering a stream releases its bitmap.
+        backend.unregister_video_stream(stream, &mut renderer);
+        let encoded_frame = EncodedFrame {
+            codec: VideoCodec::H263,
+            data: &[],
+            frame_id: 3,
+        };
+        assert!(backend
+            .decode_video_stream_frame(stream, encoded_frame, &mut renderer)
+            .is_err());
+        renderer.begin_frame();
+        renderer.clear(Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0,
+        });
+        renderer.render_bitmap(second.handle, &Transform::default(), false);
+        renderer.end_frame();
+        assert_eq!(renderer.to_rgba(), [0; 24]);
+        assert_eq!(
+            decode(&mut backend, other_stream, 2, &mut renderer)
+                .handle
+                .0,
+            other.handle.0
+        );
+    }
+}
@@ -4,8 +4,12 @@ use crate::avm1;
 use crate::avm1::listeners::SystemListener;
 use crate::avm1::Value;
 use crate::backend::input::InputBackend;
-use crate::backend::{audio::AudioBackend, navigator::NavigatorBackend, render::RenderBackend};
+use crate::backend::{
+    audio::AudioBackend, audio::AudioManager, font::FontBackend, navigator::NavigatorBackend,
+    render::RenderBackend, video::VideoBackend,
+};
 use crate::library::Library;
+use crate::net_stream::NetStreamManager;
 use crate::prelude::*;
 use crate::tag_utils::SwfSlice;
 use crate::transform::TransformStack;
@@ -13,6 +17,7 @@ use core::fmt;
 use gc_arena::{Collect, MutationContext};
 use rand::rngs::SmallRng;
 use std::sync::Arc;
+use swf::Codepage;
 
 /// `UpdateContext` holds shared data that is used by the various subsystems of Ruffle.
 /// `Player` crates this when it begins a tick and passes it through the call stack to
@@ -50,9 +55,29 @@ pub struct UpdateContext<'a, 'gc, 'gc_context> {
     /// The raw data of the SWF file.
     pub swf_data: &'a Arc<Vec<u8>>,
 
+    /// The number of bytes of the SWF file that have loaded so far.
+    pub swf_bytes_loaded: usize,
+
+    /// The total length of the SWF file in bytes.
+    pub swf_bytes_total: usize,
+
+    /// The codepage of the system playing the movie.
+    /// Used to decode strings in SWF 5 and earlier, which aren't stored as Unicode.
+    pub system_codepage: Codepage,
+
+    /// Whether external text, such as ID3 tags, is decoded with the system codepage
+    /// instead of as Unicode. Set by `System.useCodepage`.
+    pub use_codepage: &'a mut bool,
+
     /// The audio backend, used by display objects and AVM to play audio.
     pub audio: &'a mut dyn AudioBackend,
 
+    /// The audio manager, which tracks playing sounds and the sound transforms applied to them.
+    pub audio_manager: &'a mut AudioManager<'gc>,
+
+    /// The `NetStream` objects that are loading or playing a file.
+    pub net_stream_manager: &'a mut NetStreamManager<'gc>,
+
     /// The navigator backend, used by the AVM to make HTTP requests and visit webpages.
     pub navigator: &'a mut dyn NavigatorBackend,
 
@@ -62,6 +87,12 @@ pub struct UpdateContext<'a, 'gc, 'gc_context> {
     /// The input backend, used to detect user interactions.
     pub input: &'a mut dyn InputBackend,
 
+    /// The font backend, used to load device fonts for text fields.
+    pub fonts: &'a mut dyn FontBackend,
+
+    /// The video backend, used to decode the frames of video.
+    pub video: &'a mut dyn VideoBackend,
+
     /// The RNG, used by the AVM `RandomNumber` opcode,  `Math.random(),` and `random()`.
     pub rng: &'a mut SmallRng,
 
@@ -82,6 +113,9 @@ pub struct UpdateContext<'a, 'gc, 'gc_context> {
     /// The object being dragged via a `startDrag` action.
     pub drag_object: &'a mut Option<crate::player::DragObject<'gc>>,
 
+    /// The text field that has keyboard focus, if any.
+    pub focused_text_field: &'a mut Option<crate::display_object::EditText<'gc>>,
+
     /// The dimensions of the stage.
     pub stage_size: (Twips, Twips),
 }
@@ -170,6 +204,13 @@ pub struct RenderContext<'a, 'gc> {
     /// The bounds of the current viewport in twips. Used for culling.
     pub view_bounds: BoundingBox,
 
+    /// The number of device pixels per stage pixel.
+    /// Filters are sized in stage pixels, regardless of the transforms of the objects.
+    pub view_scale: f32,
+
+    /// The number of offscreen layers being rendered into.
+    pub layer_depth: usize,
+
     /// The stack of clip depths, used in masking.
     pub clip_depth_stack: Vec<Depth>,
 }
@@ -186,6 +227,14 @@ pub enum ActionType<'gc> {
     /// An event handler method, e.g. `onEnterFrame`.
     Method { name: &'static str },
 
+    /// An event handler method on an object that is not a display object,
+    /// e.g. `Sound.onSoundComplete`.
+    Callback {
+        object: avm1::Object<'gc>,
+        name: &'static str,
+        args: Vec<Value<'gc>>,
+    },
+
     /// A system listener method,
     NotifyListeners {
         listener: SystemListener,
@@ -209,6 +258,12 @@ impl fmt::Debug for ActionType<'_> {
                 .debug_struct("ActionType::Method")
                 .field("name", name)
                 .finish(),
+            ActionType::Callback { object, name, args } => f
+                .debug_struct("ActionType::Callback")
+                .field("object", object)
+                .field("name", name)
+                .field("args", args)
+                .finish(),
             ActionType::NotifyListeners {
                 listener,
                 method,
@@ -226,8 +281,13 @@ impl fmt::Debug for ActionType<'_> {
 unsafe impl<'gc> Collect for ActionType<'gc> {
     #[inline]
     fn trace(&self, cc: gc_arena::CollectionContext) {
-        if let ActionType::NotifyListeners { args, .. } = self {
-            args.trace(cc);
+        match self {
+            ActionType::Callback { object, args, .. } => {
+                object.trace(cc);
+                args.trace(cc);
+            }
+            ActionType::NotifyListeners { args, .. } => args.trace(cc),
+            _ => (),
         }
     }
 }
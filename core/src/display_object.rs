@@ -8,6 +8,7 @@ use gc_arena::{Collect, MutationContext};
 use ruffle_macros::enum_trait_object;
 use std::cell::{Ref, RefMut};
 use std::fmt::Debug;
+use swf::BlendMode;
 
 mod bitmap;
 mod button;
@@ -16,15 +17,17 @@ mod graphic;
 mod morph_shape;
 mod movie_clip;
 mod text;
+mod video;
 
 use crate::events::{ButtonEvent, ButtonEventResult, ClipEvent};
 pub use bitmap::Bitmap;
 pub use button::Button;
-pub use edit_text::EditText;
+pub use edit_text::{AutoSizeMode, EditText};
 pub use graphic::Graphic;
 pub use morph_shape::{MorphShape, MorphShapeStatic};
 pub use movie_clip::MovieClip;
 pub use text::Text;
+pub use video::Video;
 
 #[derive(Clone, Debug)]
 pub struct DisplayObjectBase<'gc> {
@@ -35,6 +38,12 @@ pub struct DisplayObjectBase<'gc> {
     name: String,
     clip_depth: Depth,
 
+    /// The bitmap filters applied when rendering this object, in order.
+    filters: Vec<swf::Filter>,
+
+    /// How this object is composited onto the objects beneath it.
+    blend_mode: BlendMode,
+
     // Cached transform properties `_xscale`, `_yscale`, `_rotation`.
     // These are expensive to calculate, so they will be calculated and cached when AS requests
     // one of these properties.
@@ -66,6 +75,8 @@ impl<'gc> Default for DisplayObjectBase<'gc> {
             transform: Default::default(),
             name: Default::default(),
             clip_depth: Default::default(),
+            filters: vec![],
+            blend_mode: BlendMode::Normal,
             rotation: 0.0,
             scale_x: 1.0,
             scale_y: 1.0,
@@ -265,6 +276,18 @@ impl<'gc> DisplayObjectBase<'gc> {
     fn set_clip_depth(&mut self, _context: MutationContext<'gc, '_>, depth: Depth) {
         self.clip_depth = depth;
     }
+    fn filters(&self) -> &[swf::Filter] {
+        &self.filters
+    }
+    fn set_filters(&mut self, _context: MutationContext<'gc, '_>, filters: Vec<swf::Filter>) {
+        self.filters = filters;
+    }
+    fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+    fn set_blend_mode(&mut self, _context: MutationContext<'gc, '_>, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
     fn parent(&self) -> Option<DisplayObject<'gc>> {
         self.parent
     }
@@ -358,6 +381,7 @@ impl<'gc> DisplayObjectBase<'gc> {
         MorphShape(MorphShape<'gc>),
         MovieClip(MovieClip<'gc>),
         Text(Text<'gc>),
+        Video(Video<'gc>),
     }
 )]
 pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
@@ -595,6 +619,10 @@ pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
 
     fn clip_depth(&self) -> Depth;
     fn set_clip_depth(&mut self, context: MutationContext<'gc, '_>, depth: Depth);
+    fn filters(&self) -> Ref<[swf::Filter]>;
+    fn set_filters(&mut self, context: MutationContext<'gc, '_>, filters: Vec<swf::Filter>);
+    fn blend_mode(&self) -> BlendMode;
+    fn set_blend_mode(&mut self, context: MutationContext<'gc, '_>, blend_mode: BlendMode);
     fn parent(&self) -> Option<DisplayObject<'gc>>;
     fn set_parent(&mut self, context: MutationContext<'gc, '_>, parent: Option<DisplayObject<'gc>>);
     fn first_child(&self) -> Option<DisplayObject<'gc>>;
@@ -690,11 +718,15 @@ pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
     fn as_morph_shape(&self) -> Option<MorphShape<'gc>> {
         None
     }
+    fn as_video(&self) -> Option<Video<'gc>> {
+        None
+    }
     fn apply_place_object(
         &mut self,
-        gc_context: MutationContext<'gc, '_>,
+        context: &mut UpdateContext<'_, 'gc, '_>,
         place_object: &swf::PlaceObject,
     ) {
+        let gc_context = context.gc_context;
         // PlaceObject tags only apply if this onject has not been dynamically moved by AS code.
         if !self.transformed_by_script() {
             if let Some(matrix) = &place_object.matrix {
@@ -709,10 +741,22 @@ pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
             if let Some(clip_depth) = place_object.clip_depth {
                 self.set_clip_depth(gc_context, clip_depth.into());
             }
+            // An empty list means that the PlaceObject3 tag doesn't change the filters.
+            if !place_object.filters.is_empty() {
+                self.set_filters(gc_context, place_object.filters.clone());
+            }
+            // Likewise, a normal blend mode is also written when the tag doesn't change it.
+            if place_object.blend_mode != BlendMode::Normal {
+                self.set_blend_mode(gc_context, place_object.blend_mode);
+            }
             if let Some(ratio) = place_object.ratio {
                 if let Some(mut morph_shape) = self.as_morph_shape() {
                     morph_shape.set_ratio(gc_context, ratio);
                 }
+                // The ratio of a video is the frame to display.
+                if let Some(video) = self.as_video() {
+                    video.seek(context, ratio.into());
+                }
             }
             // Clip events only apply to movie clips.
             if let Some(clip) = self.as_movie_clip() {
@@ -740,6 +784,8 @@ pub trait TDisplayObject<'gc>: 'gc + Collect + Debug {
         self.set_matrix(gc_context, &*other.matrix());
         self.set_color_transform(gc_context, &*other.color_transform());
         self.set_clip_depth(gc_context, other.clip_depth());
+        self.set_filters(gc_context, other.filters().to_vec());
+        self.set_blend_mode(gc_context, other.blend_mode());
         self.set_name(gc_context, &*other.name());
         if let (Some(mut me), Some(other)) = (self.as_morph_shape(), other.as_morph_shape()) {
             me.set_ratio(gc_context, other.ratio());
@@ -880,6 +926,20 @@ macro_rules! impl_display_object {
             context: gc_arena::MutationContext<'gc, '_>, depth: crate::prelude::Depth) {
             self.0.write(context).$field.set_clip_depth(context, depth)
         }
+        fn filters(&self) -> std::cell::Ref<[swf::Filter]> {
+            std::cell::Ref::map(self.0.read(), |o| o.$field.filters())
+        }
+        fn set_filters(&mut self,
+            context: gc_arena::MutationContext<'gc, '_>, filters: Vec<swf::Filter>) {
+            self.0.write(context).$field.set_filters(context, filters)
+        }
+        fn blend_mode(&self) -> swf::BlendMode {
+            self.0.read().$field.blend_mode()
+        }
+        fn set_blend_mode(&mut self,
+            context: gc_arena::MutationContext<'gc, '_>, blend_mode: swf::BlendMode) {
+            self.0.write(context).$field.set_blend_mode(context, blend_mode)
+        }
         fn parent(&self) -> Option<crate::display_object::DisplayObject<'gc>> {
             self.0.read().$field.parent()
         }
@@ -966,7 +1026,7 @@ pub fn render_children<'gc>(
             context.renderer.activate_mask();
         } else if child.visible() {
             // Normal child.
-            child.render(context);
+            render_with_effects(context, child);
         }
     }
 
@@ -976,6 +1036,31 @@ pub fn render_children<'gc>(
     }
 }
 
+/// Renders a display object, on an offscreen layer if it has filters or a blend mode.
+pub fn render_with_effects<'gc>(
+    context: &mut RenderContext<'_, 'gc>,
+    display_object: DisplayObject<'gc>,
+) {
+    let blend_mode = display_object.blend_mode();
+    let filters = display_object.filters().to_vec();
+    if blend_mode == BlendMode::Normal && filters.is_empty() {
+        display_object.render(context);
+        return;
+    }
+    // The alpha and erase blend modes only knock out an enclosing layer.
+    // Without one, the object has nothing to affect.
+    if (blend_mode == BlendMode::Alpha || blend_mode == BlendMode::Erase)
+        && context.layer_depth == 0
+    {
+        return;
+    }
+    context.renderer.push_layer(blend_mode);
+    context.layer_depth += 1;
+    display_object.render(context);
+    context.layer_depth -= 1;
+    context.renderer.pop_layer(&filters, context.view_scale);
+}
+
 impl<'gc> DisplayObject<'gc> {
     pub fn ptr_eq(a: DisplayObject<'gc>, b: DisplayObject<'gc>) -> bool {
         a.as_ptr() == b.as_ptr()
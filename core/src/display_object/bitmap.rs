@@ -94,6 +94,7 @@ impl<'gc> TDisplayObject<'gc> for Bitmap<'gc> {
         context.renderer.render_bitmap(
             self.0.read().static_data.bitmap_handle,
             context.transform_stack.transform(),
+            true,
         );
 
         context.transform_stack.pop();
@@ -32,7 +32,7 @@ impl<'gc> Button<'gc> {
         let mut actions = vec![];
         for action in &button.actions {
             let action_data = crate::tag_utils::SwfSlice {
-                data: std::sync::Arc::new(action.action_data.clone()),
+                data: std::sync::Arc::new(action.action_data.clone().into()),
                 start: 0,
                 end: action.action_data.len(),
             };
@@ -307,19 +307,35 @@ impl<'gc> ButtonData<'gc> {
         match (cur_state, new_state) {
             (ButtonState::Up, ButtonState::Over) => {
                 self.run_actions(context, swf::ButtonActionCondition::IdleToOverUp, None);
-                self.play_sound(context, self.static_data.read().up_to_over_sound.as_ref());
+                self.play_sound(
+                    self_display_object,
+                    context,
+                    self.static_data.read().up_to_over_sound.as_ref(),
+                );
             }
             (ButtonState::Over, ButtonState::Up) => {
                 self.run_actions(context, swf::ButtonActionCondition::OverUpToIdle, None);
-                self.play_sound(context, self.static_data.read().over_to_up_sound.as_ref());
+                self.play_sound(
+                    self_display_object,
+                    context,
+                    self.static_data.read().over_to_up_sound.as_ref(),
+                );
             }
             (ButtonState::Over, ButtonState::Down) => {
                 self.run_actions(context, swf::ButtonActionCondition::OverUpToOverDown, None);
-                self.play_sound(context, self.static_data.read().over_to_down_sound.as_ref());
+                self.play_sound(
+                    self_display_object,
+                    context,
+                    self.static_data.read().over_to_down_sound.as_ref(),
+                );
             }
             (ButtonState::Down, ButtonState::Over) => {
                 self.run_actions(context, swf::ButtonActionCondition::OverDownToOverUp, None);
-                self.play_sound(context, self.static_data.read().down_to_over_sound.as_ref());
+                self.play_sound(
+                    self_display_object,
+                    context,
+                    self.static_data.read().down_to_over_sound.as_ref(),
+                );
             }
             _ => (),
         }
@@ -329,12 +345,19 @@ impl<'gc> ButtonData<'gc> {
 
     fn play_sound(
         &self,
+        self_display_object: DisplayObject<'gc>,
         context: &mut UpdateContext<'_, 'gc, '_>,
         sound: Option<&swf::ButtonSound>,
     ) {
         if let Some((id, sound_info)) = sound {
             if let Some(sound_handle) = context.library.get_sound(*id) {
-                context.audio.start_sound(sound_handle, sound_info);
+                context.audio_manager.start_sound(
+                    context.audio,
+                    sound_handle,
+                    sound_info,
+                    Some(self_display_object),
+                    None,
+                );
             }
         }
     }
@@ -1,11 +1,16 @@
 //! `EditText` display object and support code.
 use crate::avm1::globals::text_field::attach_virtual_properties;
 use crate::avm1::{Object, StageObject, Value};
+use crate::backend::render::ShapeHandle;
 use crate::context::{RenderContext, UpdateContext};
 use crate::display_object::{DisplayObjectBase, TDisplayObject};
+use crate::events::KeyCode;
+use crate::font::Font;
 use crate::prelude::*;
+use crate::text_layout::{self, LayoutParams, TextLayout};
 use crate::transform::Transform;
 use gc_arena::{Collect, Gc, GcCell, MutationContext};
+use std::borrow::Cow;
 
 /// A dynamic text field.
 /// The text in this text field can be changed dynamically.
@@ -31,6 +36,71 @@ pub struct EditTextData<'gc> {
     /// The current text displayed by this text field.
     text: String,
 
+    /// The bounds of this text field.
+    /// Initially set by the `DefineEditText` tag, but may be resized by `autoSize`.
+    bounds: BoundingBox,
+
+    /// The color of the text.
+    text_color: Color,
+
+    /// Whether hard line breaks in the text start new lines.
+    is_multiline: bool,
+
+    /// Whether text wraps at word boundaries when it exceeds the width of the field.
+    is_word_wrap: bool,
+
+    /// How the field resizes to fit its text.
+    autosize: AutoSizeMode,
+
+    /// Whether a border is drawn around this field.
+    has_border: bool,
+
+    /// The color of the border.
+    border_color: Color,
+
+    /// Whether the field has an opaque background.
+    has_background: bool,
+
+    /// The color of the background.
+    background_color: Color,
+
+    /// The 1-based index of the topmost visible line.
+    /// Set by the `scroll` ActionScript property.
+    scroll: usize,
+
+    /// The current layout of the text in this field.
+    layout: TextLayout,
+
+    /// The font used to render this field, resolved whenever the text is laid out.
+    font: Option<Font<'gc>>,
+
+    /// Whether the user can type into this field.
+    is_editable: bool,
+
+    /// Whether the user can select the text of this field.
+    is_selectable: bool,
+
+    /// Whether the text is displayed as asterisks.
+    is_password: bool,
+
+    /// The maximum number of characters that the user can enter, or `None` if unlimited.
+    /// Set by the `maxChars` ActionScript property.
+    max_chars: Option<usize>,
+
+    /// The characters that the user can enter, or `None` if all are allowed.
+    /// Set by the `restrict` ActionScript property.
+    restrict: Option<String>,
+
+    /// The current selection, or `None` if this field doesn't have focus.
+    selection: Option<TextSelection>,
+
+    /// The global time at which the caret started blinking.
+    /// Reset whenever the caret moves so that it's visible while typing.
+    caret_blink_start: u64,
+
+    /// Whether the caret is currently shown, toggled as the caret blinks.
+    show_caret: bool,
+
     // The AVM1 object handle
     object: Option<Object<'gc>>,
 }
@@ -38,24 +108,514 @@ pub struct EditTextData<'gc> {
 impl<'gc> EditText<'gc> {
     /// Creates a new `EditText` from an SWF `DefineEditText` tag.
     pub fn from_swf_tag(context: &mut UpdateContext<'_, 'gc, '_>, swf_tag: swf::EditText) -> Self {
-        EditText(GcCell::allocate(
+        let rect_shape = context.renderer.register_shape(&unit_rect_shape());
+        let black = Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let text_color = swf_tag.color.clone().unwrap_or_else(|| black.clone());
+        let edit_text = EditText(GcCell::allocate(
             context.gc_context,
             EditTextData {
                 base: Default::default(),
                 text: swf_tag.initial_text.clone().unwrap_or_default(),
-                static_data: gc_arena::Gc::allocate(context.gc_context, EditTextStatic(swf_tag)),
+                bounds: swf_tag.bounds.clone().into(),
+                text_color,
+                is_multiline: swf_tag.is_multiline,
+                is_word_wrap: swf_tag.is_word_wrap,
+                autosize: if swf_tag.is_auto_size {
+                    AutoSizeMode::Left
+                } else {
+                    AutoSizeMode::None
+                },
+                // The `HasBorder` flag of `DefineEditText` enables both
+                // the border and a white background.
+                has_border: swf_tag.has_border,
+                border_color: black,
+                has_background: swf_tag.has_border,
+                background_color: Color {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                    a: 255,
+                },
+                scroll: 1,
+                layout: Default::default(),
+                font: None,
+                is_editable: !swf_tag.is_read_only,
+                is_selectable: swf_tag.is_selectable,
+                is_password: swf_tag.is_password,
+                max_chars: swf_tag
+                    .max_length
+                    .filter(|&max_length| max_length > 0)
+                    .map(usize::from),
+                restrict: None,
+                selection: None,
+                caret_blink_start: 0,
+                show_caret: true,
+                static_data: gc_arena::Gc::allocate(
+                    context.gc_context,
+                    EditTextStatic {
+                        swf: swf_tag,
+                        rect_shape,
+                    },
+                ),
                 object: None,
             },
-        ))
+        ));
+        edit_text.relayout(context);
+        edit_text
     }
 
-    // TODO: This needs to strip away HTML
+    /// The plain text of this field, with any HTML tags stripped away.
     pub fn text(self) -> String {
-        self.0.read().text.to_owned()
+        self.0.read().displayed_text()
+    }
+
+    pub fn set_text(self, text: String, context: &mut UpdateContext<'_, 'gc, '_>) {
+        self.0.write(context.gc_context).text = text;
+        self.relayout(context);
+    }
+
+    pub fn text_color(self) -> Color {
+        self.0.read().text_color.clone()
+    }
+
+    pub fn set_text_color(self, color: Color, gc_context: MutationContext<'gc, '_>) {
+        self.0.write(gc_context).text_color = color;
+    }
+
+    pub fn is_multiline(self) -> bool {
+        self.0.read().is_multiline
+    }
+
+    pub fn set_multiline(self, is_multiline: bool, context: &mut UpdateContext<'_, 'gc, '_>) {
+        self.0.write(context.gc_context).is_multiline = is_multiline;
+        self.relayout(context);
+    }
+
+    pub fn is_word_wrap(self) -> bool {
+        self.0.read().is_word_wrap
+    }
+
+    pub fn set_word_wrap(self, is_word_wrap: bool, context: &mut UpdateContext<'_, 'gc, '_>) {
+        self.0.write(context.gc_context).is_word_wrap = is_word_wrap;
+        self.relayout(context);
+    }
+
+    pub fn autosize(self) -> AutoSizeMode {
+        self.0.read().autosize
+    }
+
+    pub fn set_autosize(self, autosize: AutoSizeMode, context: &mut UpdateContext<'_, 'gc, '_>) {
+        self.0.write(context.gc_context).autosize = autosize;
+        self.relayout(context);
+    }
+
+    pub fn has_border(self) -> bool {
+        self.0.read().has_border
+    }
+
+    pub fn set_has_border(self, has_border: bool, gc_context: MutationContext<'gc, '_>) {
+        self.0.write(gc_context).has_border = has_border;
+    }
+
+    pub fn border_color(self) -> Color {
+        self.0.read().border_color.clone()
+    }
+
+    pub fn set_border_color(self, color: Color, gc_context: MutationContext<'gc, '_>) {
+        self.0.write(gc_context).border_color = color;
+    }
+
+    pub fn has_background(self) -> bool {
+        self.0.read().has_background
+    }
+
+    pub fn set_has_background(self, has_background: bool, gc_context: MutationContext<'gc, '_>) {
+        self.0.write(gc_context).has_background = has_background;
+    }
+
+    pub fn background_color(self) -> Color {
+        self.0.read().background_color.clone()
+    }
+
+    pub fn set_background_color(self, color: Color, gc_context: MutationContext<'gc, '_>) {
+        self.0.write(gc_context).background_color = color;
+    }
+
+    /// The 1-based index of the topmost visible line.
+    pub fn scroll(self) -> usize {
+        self.0.read().scroll
+    }
+
+    /// Scrolls the field so that the given 1-based line is at the top.
+    /// The value is clamped between 1 and `maxscroll`.
+    pub fn set_scroll(self, scroll: usize, gc_context: MutationContext<'gc, '_>) {
+        let mut edit_text = self.0.write(gc_context);
+        let max_scroll = edit_text.max_scroll();
+        edit_text.scroll = scroll.max(1).min(max_scroll);
+    }
+
+    /// The maximum value of `scroll`.
+    pub fn max_scroll(self) -> usize {
+        self.0.read().max_scroll()
+    }
+
+    /// The 1-based index of the bottommost visible line.
+    pub fn bottom_scroll(self) -> usize {
+        let edit_text = self.0.read();
+        let visible_lines = edit_text
+            .layout
+            .visible_lines(edit_text.scroll, edit_text.visible_height());
+        edit_text.scroll + visible_lines - 1
+    }
+
+    /// The width of the laid out text in twips.
+    pub fn text_width(self) -> Twips {
+        self.0.read().layout.width
+    }
+
+    /// The height of the laid out text in twips.
+    pub fn text_height(self) -> Twips {
+        self.0.read().layout.height
+    }
+
+    /// Whether the user can type into this field.
+    /// This is an "input" text field in the Flash IDE.
+    pub fn is_editable(self) -> bool {
+        self.0.read().is_editable
+    }
+
+    /// Whether the user can select the text of this field.
+    /// Editable fields are always selectable.
+    pub fn is_selectable(self) -> bool {
+        let edit_text = self.0.read();
+        edit_text.is_selectable || edit_text.is_editable
     }
 
-    pub fn set_text(self, text: String, gc_context: MutationContext<'gc, '_>) {
-        self.0.write(gc_context).text = text;
+    pub fn is_password(self) -> bool {
+        self.0.read().is_password
+    }
+
+    pub fn set_password(self, is_password: bool, context: &mut UpdateContext<'_, 'gc, '_>) {
+        self.0.write(context.gc_context).is_password = is_password;
+        self.relayout(context);
+    }
+
+    pub fn max_chars(self) -> Option<usize> {
+        self.0.read().max_chars
+    }
+
+    pub fn set_max_chars(self, max_chars: Option<usize>, gc_context: MutationContext<'gc, '_>) {
+        self.0.write(gc_context).max_chars = max_chars;
+    }
+
+    pub fn restrict(self) -> Option<String> {
+        self.0.read().restrict.clone()
+    }
+
+    pub fn set_restrict(self, restrict: Option<String>, gc_context: MutationContext<'gc, '_>) {
+        self.0.write(gc_context).restrict = restrict;
+    }
+
+    /// The current selection, or `None` if this field doesn't have focus.
+    pub fn selection(self) -> Option<TextSelection> {
+        self.0.read().selection
+    }
+
+    /// Gives keyboard focus to this field, taking it away from the previously focused field.
+    /// The caret is placed at the end of the text.
+    pub fn focus(self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        if let Some(focused) = *context.focused_text_field {
+            if GcCell::ptr_eq(focused.0, self.0) {
+                return;
+            }
+            focused.0.write(context.gc_context).selection = None;
+        }
+        *context.focused_text_field = Some(self);
+
+        let mut edit_text = self.0.write(context.gc_context);
+        let len = edit_text.displayed_text().chars().count();
+        edit_text.selection = Some(TextSelection::caret(len));
+        edit_text.restart_caret_blink(context.global_time);
+    }
+
+    /// Removes keyboard focus from the focused text field, if any.
+    pub fn remove_focus(context: &mut UpdateContext<'_, 'gc, '_>) {
+        if let Some(focused) = context.focused_text_field.take() {
+            focused.0.write(context.gc_context).selection = None;
+        }
+    }
+
+    /// Places the caret at the mouse position, as when the user clicks on this field.
+    /// If `extend` is set, the selection is extended to the mouse position instead,
+    /// as when dragging or shift-clicking.
+    pub fn select_at_mouse(self, extend: bool, context: &mut UpdateContext<'_, 'gc, '_>) {
+        let local = self.global_to_local(*context.mouse_position);
+        let mut edit_text = self.0.write(context.gc_context);
+        let gutter = Twips::new(text_layout::GUTTER);
+        let index = edit_text.layout.char_index_at(
+            local.0 - edit_text.bounds.x_min - gutter,
+            local.1 - edit_text.bounds.y_min - gutter + edit_text.scroll_top(),
+        );
+        edit_text.move_caret(index, extend);
+        edit_text.restart_caret_blink(context.global_time);
+    }
+
+    /// Handles a character typed by the user while this field has focus.
+    /// Returns `true` if the field changed and needs to be redrawn.
+    pub fn handle_text_input(
+        self,
+        codepoint: char,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+    ) -> bool {
+        // Control characters are handled as key presses, and characters typed while
+        // holding control are shortcuts.
+        if codepoint.is_control() || context.input.is_key_down(KeyCode::Control) {
+            return false;
+        }
+        let mut buf = [0; 4];
+        self.replace_selection(codepoint.encode_utf8(&mut buf), context)
+    }
+
+    /// Handles a key press while this field has focus, such as caret movement,
+    /// deletion and clipboard shortcuts.
+    /// Returns `true` if the field changed and needs to be redrawn.
+    pub fn handle_key_down(
+        self,
+        key_code: KeyCode,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+    ) -> bool {
+        let selection = match self.selection() {
+            Some(selection) => selection,
+            None => return false,
+        };
+        let is_shift_down = context.input.is_key_down(KeyCode::Shift);
+        let is_control_down = context.input.is_key_down(KeyCode::Control);
+        let is_editable = self.is_editable();
+        let len = self.0.read().displayed_text().chars().count();
+
+        let new_caret = match key_code {
+            KeyCode::Left if !is_shift_down && !selection.is_caret() => Some(selection.start()),
+            KeyCode::Left => Some(selection.caret.saturating_sub(1)),
+            KeyCode::Right if !is_shift_down && !selection.is_caret() => Some(selection.end()),
+            KeyCode::Right => Some((selection.caret + 1).min(len)),
+            KeyCode::Up | KeyCode::Down => {
+                let edit_text = self.0.read();
+                let layout = &edit_text.layout;
+                layout.caret_position(selection.caret).map(|(line, x)| {
+                    if key_code == KeyCode::Up && line > 0 {
+                        layout.char_index_at(x, layout.lines[line - 1].top)
+                    } else if key_code == KeyCode::Down && line + 1 < layout.lines.len() {
+                        layout.char_index_at(x, layout.lines[line + 1].top)
+                    } else if key_code == KeyCode::Up {
+                        0
+                    } else {
+                        len
+                    }
+                })
+            }
+            KeyCode::Home if is_control_down => Some(0),
+            KeyCode::End if is_control_down => Some(len),
+            KeyCode::Home | KeyCode::End => {
+                let edit_text = self.0.read();
+                let layout = &edit_text.layout;
+                layout.caret_position(selection.caret).map(|(line, _)| {
+                    let line = &layout.lines[line];
+                    if key_code == KeyCode::Home {
+                        line.start
+                    } else {
+                        layout.char_index_at(Twips::new(i32::max_value()), line.top)
+                    }
+                })
+            }
+            _ => None,
+        };
+        if let Some(new_caret) = new_caret {
+            let mut edit_text = self.0.write(context.gc_context);
+            edit_text.move_caret(new_caret, is_shift_down);
+            edit_text.restart_caret_blink(context.global_time);
+            return true;
+        }
+
+        match key_code {
+            KeyCode::Backspace | KeyCode::Delete if is_editable => {
+                if selection.is_caret() {
+                    // Select the character before or after the caret to delete it.
+                    let caret = selection.caret;
+                    let anchor = if key_code == KeyCode::Backspace {
+                        caret.saturating_sub(1)
+                    } else {
+                        (caret + 1).min(len)
+                    };
+                    self.0.write(context.gc_context).selection =
+                        Some(TextSelection { anchor, caret });
+                }
+                self.replace_selection("", context)
+            }
+            KeyCode::Return if is_editable && self.is_multiline() => {
+                self.replace_selection("\r", context)
+            }
+            KeyCode::A if is_control_down => {
+                self.0.write(context.gc_context).selection = Some(TextSelection {
+                    anchor: 0,
+                    caret: len,
+                });
+                true
+            }
+            KeyCode::C | KeyCode::X if is_control_down => {
+                // Password fields can't be copied from.
+                if selection.is_caret() || self.is_password() {
+                    return false;
+                }
+                let selected_text: String = self
+                    .0
+                    .read()
+                    .displayed_text()
+                    .chars()
+                    .skip(selection.start())
+                    .take(selection.end() - selection.start())
+                    .collect();
+                context.input.set_clipboard_content(selected_text);
+                key_code == KeyCode::X && is_editable && self.replace_selection("", context)
+            }
+            KeyCode::V if is_control_down && is_editable => {
+                let content = context.input.clipboard_content();
+                self.replace_selection(&content, context)
+            }
+            _ => false,
+        }
+    }
+
+    /// Updates the visibility of the blinking caret.
+    /// Returns `true` if the caret visibility changed and the field needs to be redrawn.
+    pub fn update_caret_blink(
+        self,
+        global_time: u64,
+        gc_context: MutationContext<'gc, '_>,
+    ) -> bool {
+        let mut edit_text = self.0.write(gc_context);
+        let elapsed = global_time.saturating_sub(edit_text.caret_blink_start);
+        let show_caret = (elapsed / CARET_BLINK_INTERVAL) % 2 == 0;
+        let changed = show_caret != edit_text.show_caret;
+        edit_text.show_caret = show_caret;
+        changed
+    }
+
+    /// Replaces the selected text with the given text, as if it was typed by the user.
+    /// Characters that aren't allowed by `multiline`, `restrict` or `maxChars` are dropped.
+    /// Returns `true` if the text changed.
+    fn replace_selection(self, insert: &str, context: &mut UpdateContext<'_, 'gc, '_>) -> bool {
+        let mut edit_text = self.0.write(context.gc_context);
+        let selection = match edit_text.selection {
+            Some(selection) => selection,
+            None => return false,
+        };
+        let text: Vec<char> = edit_text.displayed_text().chars().collect();
+        let start = selection.start().min(text.len());
+        let end = selection.end().min(text.len());
+        let mut room = edit_text
+            .max_chars
+            .map(|max_chars| max_chars.saturating_sub(text.len() - (end - start)))
+            .unwrap_or(usize::max_value());
+
+        // Flash uses carriage returns for line breaks.
+        let insert = insert.replace("\r\n", "\r").replace('\n', "\r");
+        let mut inserted = String::with_capacity(insert.len());
+        let mut num_inserted = 0;
+        for c in insert.chars() {
+            if room == 0 {
+                break;
+            }
+            let is_allowed = if c == '\r' {
+                edit_text.is_multiline
+            } else {
+                !c.is_control()
+                    && edit_text
+                        .restrict
+                        .as_ref()
+                        .map(|restrict| is_allowed_by_restrict(restrict, c))
+                        .unwrap_or(true)
+            };
+            if is_allowed {
+                inserted.push(c);
+                num_inserted += 1;
+                room -= 1;
+            }
+        }
+        if num_inserted == 0 && start == end {
+            return false;
+        }
+
+        edit_text.text = if edit_text.static_data.swf.is_html {
+            splice_html(&edit_text.text, start, end, &inserted)
+        } else {
+            let mut new_text: String = text[..start].iter().collect();
+            new_text.push_str(&inserted);
+            new_text.extend(&text[end..]);
+            new_text
+        };
+        edit_text.selection = Some(TextSelection::caret(start + num_inserted));
+        edit_text.restart_caret_blink(context.global_time);
+        drop(edit_text);
+
+        self.relayout(context);
+        self.0.write(context.gc_context).scroll_to_caret();
+        true
+    }
+
+    /// Lays out the text of this field again.
+    /// This must be called whenever the text or any property that affects the layout changes.
+    /// If `autoSize` is enabled, the bounds of this field are resized to fit the text.
+    pub fn relayout(self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        let mut edit_text = self.0.write(context.gc_context);
+        edit_text.font = edit_text.load_font(context);
+        let font = match edit_text.font {
+            Some(font) => font,
+            None => {
+                edit_text.layout = Default::default();
+                return;
+            }
+        };
+
+        let text = edit_text.layout_text();
+        let gutter = Twips::new(text_layout::GUTTER);
+        let mut params = edit_text.layout_params();
+        let mut layout = TextLayout::lay_out(&text, &font, &params);
+
+        if edit_text.autosize != AutoSizeMode::None {
+            if !edit_text.is_word_wrap {
+                // Fit the width to the text, keeping the field anchored according to the autosize mode.
+                let old_width = edit_text.bounds.x_max - edit_text.bounds.x_min;
+                let new_width = layout.width + gutter * 2;
+                let x_offset = match edit_text.autosize {
+                    AutoSizeMode::Center => (old_width - new_width) / 2,
+                    AutoSizeMode::Right => old_width - new_width,
+                    _ => Twips::new(0),
+                };
+                edit_text.bounds.x_min += x_offset;
+                edit_text.bounds.x_max = edit_text.bounds.x_min + new_width;
+
+                // Lay out again so that alignment is relative to the new width.
+                params = edit_text.layout_params();
+                layout = TextLayout::lay_out(&text, &font, &params);
+            }
+            edit_text.bounds.y_max = edit_text.bounds.y_min + layout.height + gutter * 2;
+        }
+
+        edit_text.layout = layout;
+        let max_scroll = edit_text.max_scroll();
+        edit_text.scroll = edit_text.scroll.max(1).min(max_scroll);
+
+        // Keep the selection within the new text.
+        let len = text.chars().count();
+        if let Some(selection) = &mut edit_text.selection {
+            selection.anchor = selection.anchor.min(len);
+            selection.caret = selection.caret.min(len);
+        }
     }
 }
 
@@ -63,13 +623,34 @@ impl<'gc> TDisplayObject<'gc> for EditText<'gc> {
     impl_display_object!(base);
 
     fn id(&self) -> CharacterId {
-        self.0.read().static_data.0.id
+        self.0.read().static_data.swf.id
+    }
+
+    fn self_bounds(&self) -> BoundingBox {
+        self.0.read().bounds.clone()
     }
 
     fn run_frame(&mut self, _context: &mut UpdateContext) {
         // Noop
     }
 
+    fn hit_test(&self, point: (Twips, Twips)) -> bool {
+        self.world_bounds().contains(point)
+    }
+
+    fn mouse_pick(
+        &self,
+        self_node: DisplayObject<'gc>,
+        point: (Twips, Twips),
+    ) -> Option<DisplayObject<'gc>> {
+        // Selectable fields capture the mouse so that text can be clicked and dragged.
+        if self.is_selectable() && self.hit_test(point) {
+            Some(self_node)
+        } else {
+            None
+        }
+    }
+
     fn as_edit_text(&self) -> Option<EditText<'gc>> {
         Some(*self)
     }
@@ -99,83 +680,650 @@ impl<'gc> TDisplayObject<'gc> for EditText<'gc> {
             .unwrap_or(Value::Undefined)
     }
 
-    fn render(&self, context: &mut RenderContext) {
+    fn render(&self, context: &mut RenderContext<'_, 'gc>) {
         let edit_text = self.0.read();
-        // TODO: This is a stub implementation to just get some dynamic text rendering.
         context.transform_stack.push(&*self.transform());
-        let static_data = &edit_text.static_data.0;
-        let font_id = static_data.font_id.unwrap_or(0);
-        // TODO: Many of these properties should change be instance members instead
-        // of static data, because they can be altered via ActionScript.
-        let color = static_data.color.as_ref().unwrap_or_else(|| &swf::Color {
-            r: 0,
-            g: 0,
-            b: 0,
-            a: 255,
-        });
-        let mut transform: Transform = Default::default();
-        transform.color_transform.r_mult = f32::from(color.r) / 255.0;
-        transform.color_transform.g_mult = f32::from(color.g) / 255.0;
-        transform.color_transform.b_mult = f32::from(color.b) / 255.0;
-        transform.color_transform.a_mult = f32::from(color.a) / 255.0;
-        // If the font can't be found or has no glyph information, use the "device font" instead.
+
+        let rect_shape = edit_text.static_data.rect_shape;
+        let bounds = &edit_text.bounds;
+        let (x, y) = (bounds.x_min, bounds.y_min);
+        let (width, height) = (bounds.x_max - bounds.x_min, bounds.y_max - bounds.y_min);
+        if edit_text.has_background {
+            draw_rect(
+                context,
+                rect_shape,
+                &edit_text.background_color,
+                x,
+                y,
+                width,
+                height,
+            );
+        }
+        if edit_text.has_border {
+            let one_pixel = Twips::new(20);
+            let color = &edit_text.border_color;
+            draw_rect(context, rect_shape, color, x, y, width, one_pixel);
+            draw_rect(
+                context,
+                rect_shape,
+                color,
+                x,
+                y + height - one_pixel,
+                width,
+                one_pixel,
+            );
+            draw_rect(context, rect_shape, color, x, y, one_pixel, height);
+            draw_rect(
+                context,
+                rect_shape,
+                color,
+                x + width - one_pixel,
+                y,
+                one_pixel,
+                height,
+            );
+        }
+
+        // Clip the text to the bounds of the field.
+        context.renderer.push_mask();
+        draw_rect(
+            context,
+            rect_shape,
+            &edit_text.text_color,
+            x,
+            y,
+            width,
+            height,
+        );
+        context.renderer.activate_mask();
+
+        // If the font can't be found or has no glyph information, use a device font instead.
         // We're cheating a bit and not actually rendering text using the OS/web.
-        // Instead, we embed an SWF version of Noto Sans to use as the "device font", and render
-        // it the same as any other SWF outline text.
-        if let Some(font) = context
-            .library
-            .get_font(font_id)
-            .filter(|font| font.has_glyphs())
-            .or_else(|| context.library.device_font())
-        {
-            let scale = if let Some(height) = static_data.height {
-                transform.matrix.ty += f32::from(height);
-                f32::from(height) / font.scale()
-            } else {
-                1.0
-            };
-            if let Some(layout) = &static_data.layout {
-                transform.matrix.ty -= layout.leading.get() as f32;
-            }
+        // Instead, device fonts are converted to outlines and rendered the same as any other
+        // SWF outline text. If no matching font is installed, we use an embedded SWF version
+        // of Noto Sans.
+        if let Some(font) = edit_text.font {
+            let scale = edit_text.font_size().get() as f32 / font.scale();
+            let color = &edit_text.text_color;
+            let mut transform: Transform = Default::default();
             transform.matrix.a = scale;
             transform.matrix.d = scale;
-            let mut chars = edit_text.text.chars().peekable();
-            let has_kerning_info = font.has_kerning_info();
-            while let Some(c) = chars.next() {
-                // TODO: SWF text fields can contain a limited subset of HTML (and often do in SWF versions >6).
-                // This is a quicky-and-dirty way to skip the HTML tags. This is obviously not correct
-                // and we will need to properly parse and handle the HTML at some point.
-                // See SWF19 pp. 173-174 for supported HTML tags.
-                if edit_text.static_data.0.is_html && c == '<' {
-                    // Skip characters until we see a close bracket.
-                    chars.by_ref().skip_while(|&x| x != '>').next();
-                } else if let Some(glyph) = font.get_glyph_for_char(c) {
-                    // Render glyph.
-                    context.transform_stack.push(&transform);
-                    context
-                        .renderer
-                        .render_shape(glyph.shape, context.transform_stack.transform());
-                    context.transform_stack.pop();
-                    // Step horizontally.
-                    let mut advance = f32::from(glyph.advance);
-                    if has_kerning_info {
-                        advance += font
-                            .get_kerning_offset(c, chars.peek().cloned().unwrap_or('\0'))
-                            .get() as f32;
+            transform.color_transform.r_mult = f32::from(color.r) / 255.0;
+            transform.color_transform.g_mult = f32::from(color.g) / 255.0;
+            transform.color_transform.b_mult = f32::from(color.b) / 255.0;
+            transform.color_transform.a_mult = f32::from(color.a) / 255.0;
+
+            // Selected text is drawn inverted on a highlight of the text color.
+            let mut selected_transform = transform.clone();
+            selected_transform.color_transform.r_mult = 1.0 - transform.color_transform.r_mult;
+            selected_transform.color_transform.g_mult = 1.0 - transform.color_transform.g_mult;
+            selected_transform.color_transform.b_mult = 1.0 - transform.color_transform.b_mult;
+            let selection = edit_text
+                .selection
+                .filter(|selection| !selection.is_caret());
+
+            let gutter = Twips::new(text_layout::GUTTER);
+            let visible_height = edit_text.visible_height();
+            let first_line = edit_text.scroll.saturating_sub(1);
+            let scroll_top = edit_text.scroll_top();
+            for line in edit_text.layout.lines.iter().skip(first_line) {
+                if line.top - scroll_top >= visible_height {
+                    break;
+                }
+                let line_x = x + gutter + line.x;
+                let line_top = y + gutter + line.top - scroll_top;
+                let baseline = y + gutter + line.baseline - scroll_top;
+
+                if let Some(selection) = selection {
+                    let mut selected_glyphs = line.glyphs.iter().filter(|glyph| {
+                        glyph.index >= selection.start() && glyph.index < selection.end()
+                    });
+                    if let Some(first) = selected_glyphs.next() {
+                        let last = selected_glyphs.next_back().unwrap_or(first);
+                        draw_rect(
+                            context,
+                            rect_shape,
+                            color,
+                            line_x + first.x,
+                            line_top,
+                            last.x + last.advance - first.x,
+                            edit_text.layout.line_height,
+                        );
+                    }
+                }
+
+                for layout_glyph in &line.glyphs {
+                    let c = text_layout::display_char(layout_glyph.character);
+                    if let Some(glyph) = font.get_glyph_for_char(context.renderer, c) {
+                        let is_selected = selection
+                            .map(|selection| {
+                                layout_glyph.index >= selection.start()
+                                    && layout_glyph.index < selection.end()
+                            })
+                            .unwrap_or(false);
+                        let glyph_transform = if is_selected {
+                            &mut selected_transform
+                        } else {
+                            &mut transform
+                        };
+                        glyph_transform.matrix.tx = (line_x + layout_glyph.x).get() as f32;
+                        glyph_transform.matrix.ty = baseline.get() as f32;
+                        context.transform_stack.push(glyph_transform);
+                        context
+                            .renderer
+                            .render_shape(glyph.shape, context.transform_stack.transform());
+                        context.transform_stack.pop();
+                    }
+                }
+            }
+
+            // Draw the caret of a focused input field.
+            if let Some(selection) = edit_text.selection {
+                if selection.is_caret() && edit_text.is_editable && edit_text.show_caret {
+                    if let Some((line, caret_x)) = edit_text.layout.caret_position(selection.caret)
+                    {
+                        let top = edit_text.layout.lines[line].top - scroll_top;
+                        if line >= first_line && top < visible_height {
+                            draw_rect(
+                                context,
+                                rect_shape,
+                                color,
+                                x + gutter + caret_x,
+                                y + gutter + top,
+                                Twips::new(20),
+                                edit_text.layout.line_height,
+                            );
+                        }
                     }
-                    transform.matrix.tx += advance * scale;
                 }
             }
         }
+
+        context.renderer.pop_mask();
         context.transform_stack.pop();
     }
 }
 
+impl<'gc> EditTextData<'gc> {
+    /// Returns the font used to render this text field.
+    /// Fonts without glyph information are rendered with the device font of the same name and
+    /// style. If the font can't be found, the default device font is used instead.
+    fn load_font(&self, context: &mut UpdateContext<'_, 'gc, '_>) -> Option<Font<'gc>> {
+        let font_id = self.static_data.swf.font_id.unwrap_or(0);
+        match context.library.get_font(font_id) {
+            Some(font) if font.has_glyphs() => Some(font),
+            Some(font) => context.library.get_device_font(
+                context.gc_context,
+                context.fonts,
+                font.name(),
+                font.is_bold(),
+                font.is_italic(),
+            ),
+            None => context.library.device_font(),
+        }
+    }
+
+    /// The height of the font in twips.
+    fn font_size(&self) -> Twips {
+        // Flash defaults to 12pt text.
+        Twips::new(self.static_data.swf.height.unwrap_or(240))
+    }
+
+    /// The parameters used to lay out the text of this field.
+    fn layout_params(&self) -> LayoutParams {
+        let mut params = self
+            .static_data
+            .swf
+            .layout
+            .as_ref()
+            .map(LayoutParams::from)
+            .unwrap_or_default();
+        params.width = self.bounds.x_max - self.bounds.x_min - Twips::new(text_layout::GUTTER * 2);
+        params.font_size = self.font_size();
+        params.word_wrap = self.is_word_wrap;
+        params.multiline = self.is_multiline;
+        params
+    }
+
+    /// The height of the area in which text is visible, excluding the gutter.
+    fn visible_height(&self) -> Twips {
+        self.bounds.y_max - self.bounds.y_min - Twips::new(text_layout::GUTTER * 2)
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.layout.max_scroll(self.visible_height())
+    }
+
+    /// The text as it is displayed on screen.
+    fn displayed_text(&self) -> String {
+        if self.static_data.swf.is_html {
+            html_to_text(&self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+
+    /// The text that is laid out and rendered.
+    /// Password fields display an asterisk for each character.
+    fn layout_text(&self) -> String {
+        let text = self.displayed_text();
+        if self.is_password {
+            text.chars().map(|_| '*').collect()
+        } else {
+            text
+        }
+    }
+
+    /// The vertical offset of the text caused by scrolling.
+    fn scroll_top(&self) -> Twips {
+        self.layout
+            .lines
+            .get(self.scroll.saturating_sub(1))
+            .map(|line| line.top)
+            .unwrap_or_default()
+    }
+
+    /// Moves the caret to the given character index.
+    /// If `extend` is set, the selection anchor stays in place; otherwise, the selection is cleared.
+    fn move_caret(&mut self, index: usize, extend: bool) {
+        if let Some(selection) = &mut self.selection {
+            selection.caret = index;
+            if !extend {
+                selection.anchor = index;
+            }
+            self.scroll_to_caret();
+        }
+    }
+
+    /// Makes the caret visible and restarts its blinking.
+    fn restart_caret_blink(&mut self, global_time: u64) {
+        self.caret_blink_start = global_time;
+        self.show_caret = true;
+    }
+
+    /// Scrolls vertically so that the line containing the caret is visible.
+    fn scroll_to_caret(&mut self) {
+        let caret_line = match self
+            .selection
+            .and_then(|selection| self.layout.caret_position(selection.caret))
+        {
+            Some((line, _)) => line + 1,
+            None => return,
+        };
+        if caret_line < self.scroll {
+            self.scroll = caret_line;
+        } else {
+            let visible_lines = self
+                .layout
+                .visible_lines(self.scroll, self.visible_height());
+            if caret_line >= self.scroll + visible_lines {
+                self.scroll = caret_line + 1 - visible_lines;
+            }
+        }
+        self.scroll = self.scroll.max(1).min(self.max_scroll());
+    }
+}
+
+/// A range of selected text in a focused text field, in character indices.
+/// When nothing is selected, the anchor and caret are at the same position.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TextSelection {
+    /// The position where the selection started.
+    /// This stays in place while the selection is extended.
+    pub anchor: usize,
+
+    /// The position of the caret, which moves when the selection is extended.
+    pub caret: usize,
+}
+
+impl TextSelection {
+    /// An empty selection with the caret at the given position.
+    pub fn caret(index: usize) -> Self {
+        Self {
+            anchor: index,
+            caret: index,
+        }
+    }
+
+    /// The index of the first selected character.
+    pub fn start(self) -> usize {
+        self.anchor.min(self.caret)
+    }
+
+    /// The index one past the last selected character.
+    pub fn end(self) -> usize {
+        self.anchor.max(self.caret)
+    }
+
+    /// Whether no characters are selected.
+    pub fn is_caret(self) -> bool {
+        self.anchor == self.caret
+    }
+}
+
+/// How a text field resizes to fit its text.
+/// Set by the `autoSize` ActionScript property.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AutoSizeMode {
+    /// The field does not resize.
+    None,
+
+    /// The field resizes, keeping its left edge in place.
+    Left,
+
+    /// The field resizes, keeping its center in place.
+    Center,
+
+    /// The field resizes, keeping its right edge in place.
+    Right,
+}
+
+/// A piece of the HTML of a text field.
+enum HtmlToken<'a> {
+    /// A tag, with its source including the angle brackets and its lowercase contents.
+    Tag { source: &'a str, name: String },
+
+    /// Displayed text, with its source: a single character or an entity.
+    Text { source: &'a str, text: Cow<'a, str> },
+}
+
+impl HtmlToken<'_> {
+    /// Whether this tag ends a line in the displayed text.
+    fn is_line_break(&self) -> bool {
+        match self {
+            HtmlToken::Tag { name, .. } => name == "br" || name == "br/" || name == "/p",
+            HtmlToken::Text { .. } => false,
+        }
+    }
+
+    /// Whether this tag begins a paragraph.
+    fn is_paragraph_start(&self) -> bool {
+        match self {
+            HtmlToken::Tag { name, .. } => name == "p" || name.starts_with("p "),
+            HtmlToken::Text { .. } => false,
+        }
+    }
+}
+
+/// Splits the limited HTML of an HTML text field into tags and displayed text.
+fn html_tokens(html: &str) -> impl Iterator<Item = HtmlToken<'_>> {
+    let mut chars = html.char_indices();
+    std::iter::from_fn(move || {
+        let (start, c) = chars.next()?;
+        // Finds the end of a tag or entity, including the terminator if there is one.
+        let mut end_at = |terminator: char| {
+            for (i, c) in chars.by_ref() {
+                if c == terminator {
+                    return i + c.len_utf8();
+                }
+            }
+            html.len()
+        };
+        Some(match c {
+            '<' => {
+                let source = &html[start..end_at('>')];
+                let name = source[1..]
+                    .trim_end_matches('>')
+                    .trim()
+                    .to_ascii_lowercase();
+                HtmlToken::Tag { source, name }
+            }
+            '&' => {
+                let source = &html[start..end_at(';')];
+                let text = match source {
+                    "&lt;" => "<".into(),
+                    "&gt;" => ">".into(),
+                    "&amp;" => "&".into(),
+                    "&quot;" => "\"".into(),
+                    "&apos;" => "'".into(),
+                    "&nbsp;" => "\u{a0}".into(),
+                    _ if source.ends_with(';') => source.into(),
+                    _ => format!("{};", source).into(),
+                };
+                HtmlToken::Text { source, text }
+            }
+            c => {
+                let source = &html[start..start + c.len_utf8()];
+                HtmlToken::Text {
+                    source,
+                    text: source.into(),
+                }
+            }
+        })
+    })
+}
+
+/// Converts the limited HTML of an HTML text field to plain text.
+///
+/// SWF text fields can contain a limited subset of HTML (and often do in SWF versions >6).
+/// This strips away the tags, converting paragraph and line breaks to hard breaks and
+/// decoding entities.
+/// TODO: The formatting from these tags should be applied instead of thrown away.
+/// See SWF19 pp. 173-174 for supported HTML tags.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    for token in html_tokens(html) {
+        match token {
+            HtmlToken::Text {
+                text: displayed, ..
+            } => text.push_str(&displayed),
+            token if token.is_line_break() => text.push('\r'),
+            _ => (),
+        }
+    }
+
+    // The final closing paragraph tag doesn't start a new line.
+    if text.ends_with('\r') {
+        text.pop();
+    }
+    text
+}
+
+/// Escapes plain text for the HTML of an HTML text field. Line breaks become `<br>` tags.
+fn push_escaped_html(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '&' => html.push_str("&amp;"),
+            '\r' => html.push_str("<br>"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// Replaces the characters from `start` to `end` of the displayed text of an HTML text field
+/// with the plain text `insert`, keeping the markup around them.
+///
+/// The inserted text takes on the formatting of the character before it, or of the following
+/// character at the start of a line. Deleting the end of a paragraph joins it with the next.
+fn splice_html(html: &str, start: usize, end: usize, insert: &str) -> String {
+    let mut out = String::with_capacity(html.len() + insert.len());
+    let mut index = 0;
+    let mut is_inserted = false;
+    let mut is_joining_paragraphs = false;
+    for token in html_tokens(html) {
+        let (source, displayed) = match &token {
+            HtmlToken::Text { source, text } => (*source, text.clone()),
+            HtmlToken::Tag { source, .. } if token.is_line_break() => (*source, "\r".into()),
+            HtmlToken::Tag { source, .. } => {
+                if is_joining_paragraphs && token.is_paragraph_start() {
+                    is_joining_paragraphs = false;
+                } else {
+                    out.push_str(source);
+                }
+                continue;
+            }
+        };
+
+        let is_single_char = displayed.chars().count() == 1;
+        for c in displayed.chars() {
+            if index == start && !is_inserted {
+                push_escaped_html(&mut out, insert);
+                is_inserted = true;
+            }
+            if index >= start && index < end {
+                // The character is deleted, but the tags around it are kept.
+                if let HtmlToken::Tag { name, .. } = &token {
+                    is_joining_paragraphs |= name == "/p";
+                }
+            } else if is_single_char {
+                out.push_str(source);
+            } else {
+                push_escaped_html(&mut out, &c.to_string());
+            }
+            index += 1;
+
+            // Text typed after a character takes on its formatting.
+            if index == start && !is_inserted && !token.is_line_break() {
+                push_escaped_html(&mut out, insert);
+                is_inserted = true;
+            }
+        }
+    }
+    if !is_inserted {
+        push_escaped_html(&mut out, insert);
+    }
+    out
+}
+
+/// Returns whether the user is allowed to enter the given character,
+/// according to the `restrict` property of a text field.
+///
+/// The restrict string lists the allowed characters, with ranges such as `A-Z`.
+/// A `^` toggles between allowing and disallowing the characters that follow it;
+/// if the string begins with `^`, all characters not listed are allowed.
+/// A backslash escapes the following `-`, `^` or `\`.
+fn is_allowed_by_restrict(restrict: &str, c: char) -> bool {
+    let mut chars = restrict.chars().peekable();
+    let mut is_allowed = restrict.starts_with('^');
+    let mut is_including = true;
+
+    // Reads the next character, handling escapes.
+    fn next_char(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<char> {
+        match chars.next() {
+            Some('\\') => chars.next(),
+            c => c,
+        }
+    }
+
+    while let Some(&next) = chars.peek() {
+        if next == '^' {
+            chars.next();
+            is_including = !is_including;
+            continue;
+        }
+        let start = match next_char(&mut chars) {
+            Some(start) => start,
+            None => break,
+        };
+        let mut end = start;
+        if chars.peek() == Some(&'-') {
+            chars.next();
+            end = next_char(&mut chars).unwrap_or(start);
+        }
+        if start <= c && c <= end {
+            is_allowed = is_including;
+        }
+    }
+    is_allowed
+}
+
+/// Draws a solid rectangle using the unit rectangle shape of a text field.
+fn draw_rect(
+    context: &mut RenderContext,
+    rect_shape: ShapeHandle,
+    color: &Color,
+    x: Twips,
+    y: Twips,
+    width: Twips,
+    height: Twips,
+) {
+    let mut transform: Transform = Default::default();
+    transform.matrix.a = width.get() as f32 / UNIT_RECT_SIZE as f32;
+    transform.matrix.d = height.get() as f32 / UNIT_RECT_SIZE as f32;
+    transform.matrix.tx = x.get() as f32;
+    transform.matrix.ty = y.get() as f32;
+    transform.color_transform.r_mult = f32::from(color.r) / 255.0;
+    transform.color_transform.g_mult = f32::from(color.g) / 255.0;
+    transform.color_transform.b_mult = f32::from(color.b) / 255.0;
+    transform.color_transform.a_mult = f32::from(color.a) / 255.0;
+    context.transform_stack.push(&transform);
+    context
+        .renderer
+        .render_shape(rect_shape, context.transform_stack.transform());
+    context.transform_stack.pop();
+}
+
+/// The time in milliseconds that the caret stays visible or hidden while blinking.
+const CARET_BLINK_INTERVAL: u64 = 500;
+
+/// The size in twips of the rectangle shape used to draw text field backgrounds and borders.
+const UNIT_RECT_SIZE: i32 = 20;
+
+/// Creates a solid white square shape.
+/// Text fields scale and tint this shape to draw backgrounds, borders, and clipping masks.
+fn unit_rect_shape() -> swf::Shape {
+    use swf::{FillStyle, Rectangle, ShapeRecord, ShapeStyles, StyleChangeData};
+    let size = Twips::new(UNIT_RECT_SIZE);
+    let zero = Twips::new(0);
+    let bounds = Rectangle {
+        x_min: zero,
+        y_min: zero,
+        x_max: size,
+        y_max: size,
+    };
+    swf::Shape {
+        version: 1,
+        id: 0,
+        shape_bounds: bounds.clone(),
+        edge_bounds: bounds,
+        has_fill_winding_rule: false,
+        has_non_scaling_strokes: false,
+        has_scaling_strokes: false,
+        styles: ShapeStyles {
+            fill_styles: vec![FillStyle::Color(Color {
+                r: 255,
+                g: 255,
+                b: 255,
+                a: 255,
+            })],
+            line_styles: vec![],
+        },
+        shape: vec![
+            ShapeRecord::StyleChange(StyleChangeData {
+                move_to: Some((zero, zero)),
+                fill_style_0: None,
+                fill_style_1: Some(1),
+                line_style: None,
+                new_styles: None,
+            }),
+            ShapeRecord::StraightEdge {
+                delta_x: size,
+                delta_y: zero,
+            },
+            ShapeRecord::StraightEdge {
+                delta_x: zero,
+                delta_y: size,
+            },
+            ShapeRecord::StraightEdge {
+                delta_x: Twips::new(-UNIT_RECT_SIZE),
+                delta_y: zero,
+            },
+            ShapeRecord::StraightEdge {
+                delta_x: zero,
+                delta_y: Twips::new(-UNIT_RECT_SIZE),
+            },
+        ],
+    }
+}
+
 unsafe impl<'gc> gc_arena::Collect for EditTextData<'gc> {
     #[inline]
     fn trace(&self, cc: gc_arena::CollectionContext) {
         self.base.trace(cc);
         self.static_data.trace(cc);
+        self.font.trace(cc);
         self.object.trace(cc);
     }
 }
@@ -183,7 +1331,12 @@ unsafe impl<'gc> gc_arena::Collect for EditTextData<'gc> {
 /// Static data shared between all instances of a text object.
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
-struct EditTextStatic(swf::EditText);
+struct EditTextStatic {
+    swf: swf::EditText,
+
+    /// A white square used to draw the background and border of the field.
+    rect_shape: ShapeHandle,
+}
 
 unsafe impl<'gc> gc_arena::Collect for EditTextStatic {
     #[inline]
@@ -191,3 +1344,64 @@ unsafe impl<'gc> gc_arena::Collect for EditTextStatic {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restrict() {
+        assert!(is_allowed_by_restrict("A-Z 0-9", 'Q'));
+        assert!(is_allowed_by_restrict("A-Z 0-9", ' '));
+        assert!(!is_allowed_by_restrict("A-Z 0-9", 'q'));
+        assert!(!is_allowed_by_restrict("", 'a'));
+        assert!(is_allowed_by_restrict("^0-9", 'a'));
+        assert!(!is_allowed_by_restrict("^0-9", '5'));
+        assert!(!is_allowed_by_restrict("A-Z^Q", 'Q'));
+        assert!(is_allowed_by_restrict("\\-", '-'));
+        assert!(!is_allowed_by_restrict("\\-", 'a'));
+        assert!(is_allowed_by_restrict("\\^", '^'));
+    }
+
+    #[test]
+    fn html_round_trip() {
+        for text in &["", "a", "a < b & c", "one\rtwo", "trailing\r"] {
+            assert_eq!(html_to_text(&splice_html("<p></p>", 0, 0, text)), *text);
+        }
+    }
+
+    #[test]
+    fn html_edits_keep_formatting() {
+        let html = "<p><font color=\"#ff0000\">ab</font><b>cd</b></p><p>e&amp;f</p>";
+        assert_eq!(html_to_text(html), "abcd\re&f");
+
+        // Typed text takes on the formatting of the character before it.
+        assert_eq!(
+            splice_html(html, 2, 2, "x"),
+            "<p><font color=\"#ff0000\">abx</font><b>cd</b></p><p>e&amp;f</p>"
+        );
+        // At the start of a line, it takes on the formatting of the character after it.
+        assert_eq!(
+            splice_html(html, 0, 0, "<"),
+            "<p><font color=\"#ff0000\">&lt;ab</font><b>cd</b></p><p>e&amp;f</p>"
+        );
+        assert_eq!(
+            splice_html(html, 5, 5, "x"),
+            "<p><font color=\"#ff0000\">ab</font><b>cd</b></p><p>xe&amp;f</p>"
+        );
+        // Deleting across tags keeps the tags.
+        assert_eq!(
+            splice_html(html, 1, 3, "y"),
+            "<p><font color=\"#ff0000\">ay</font><b>d</b></p><p>e&amp;f</p>"
+        );
+        // Deleting a paragraph break joins the paragraphs.
+        assert_eq!(
+            splice_html(html, 4, 5, ""),
+            "<p><font color=\"#ff0000\">ab</font><b>cd</b>e&amp;f</p>"
+        );
+        assert_eq!(
+            splice_html(html, 8, 8, "\r"),
+            "<p><font color=\"#ff0000\">ab</font><b>cd</b></p><p>e&amp;f<br></p>"
+        );
+    }
+}
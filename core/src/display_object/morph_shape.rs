@@ -2,7 +2,7 @@ use crate::backend::render::{RenderBackend, ShapeHandle};
 use crate::context::{RenderContext, UpdateContext};
 use crate::display_object::{DisplayObjectBase, TDisplayObject};
 use crate::prelude::*;
-use gc_arena::{Collect, Gc, GcCell, MutationContext};
+use gc_arena::{Collect, GcCell, MutationContext};
 use swf::Twips;
 
 #[derive(Clone, Debug, Collect, Copy)]
@@ -12,7 +12,7 @@ pub struct MorphShape<'gc>(GcCell<'gc, MorphShapeData<'gc>>);
 #[derive(Clone, Debug)]
 pub struct MorphShapeData<'gc> {
     base: DisplayObjectBase<'gc>,
-    static_data: Gc<'gc, MorphShapeStatic>,
+    static_data: GcCell<'gc, MorphShapeStatic>,
     ratio: u16,
 }
 
@@ -25,7 +25,7 @@ impl<'gc> MorphShape<'gc> {
             gc_context,
             MorphShapeData {
                 base: Default::default(),
-                static_data: Gc::allocate(gc_context, static_data),
+                static_data: GcCell::allocate(gc_context, static_data),
                 ratio: 0,
             },
         ))
@@ -38,13 +38,28 @@ impl<'gc> MorphShape<'gc> {
     pub fn set_ratio(&mut self, gc_context: MutationContext<'gc, '_>, ratio: u16) {
         self.0.write(gc_context).ratio = ratio;
     }
+
+    /// Registers a ratio that this morph shape is displayed at.
+    /// The shared static data is updated, so this applies to every instance of the shape.
+    pub fn register_ratio(
+        self,
+        gc_context: MutationContext<'gc, '_>,
+        renderer: &mut dyn RenderBackend,
+        ratio: u16,
+    ) {
+        self.0
+            .read()
+            .static_data
+            .write(gc_context)
+            .register_ratio(renderer, ratio);
+    }
 }
 
 impl<'gc> TDisplayObject<'gc> for MorphShape<'gc> {
     impl_display_object!(base);
 
     fn id(&self) -> CharacterId {
-        self.0.read().static_data.id
+        self.0.read().static_data.read().id
     }
 
     fn as_morph_shape(&self) -> Option<Self> {
@@ -58,7 +73,7 @@ impl<'gc> TDisplayObject<'gc> for MorphShape<'gc> {
     fn render(&self, context: &mut RenderContext) {
         context.transform_stack.push(&*self.transform());
 
-        if let Some(shape) = self.0.read().static_data.frames.get(&self.ratio()) {
+        if let Some(shape) = self.0.read().static_data.read().frames.get(&self.ratio()) {
             context
                 .renderer
                 .render_shape(*shape, context.transform_stack.transform());
@@ -80,6 +95,7 @@ unsafe impl<'gc> gc_arena::Collect for MorphShapeData<'gc> {
 
 /// Static data shared between all instances of a morph shape.
 #[allow(dead_code)]
+#[derive(Debug)]
 pub struct MorphShapeStatic {
     id: CharacterId,
     start: swf::MorphShape,
@@ -1,10 +1,12 @@
 //! `MovieClip` display object and support code.
+use crate::avm1::globals::movie_clip::attach_virtual_properties;
 use crate::avm1::{Object, StageObject, Value};
 use crate::backend::audio::AudioStreamHandle;
 use crate::character::Character;
 use crate::context::{ActionType, RenderContext, UpdateContext};
 use crate::display_object::{
-    Bitmap, Button, DisplayObjectBase, EditText, Graphic, MorphShapeStatic, TDisplayObject, Text,
+    Bitmap, Button, DisplayObjectBase, EditText, Graphic, MorphShape, MorphShapeStatic,
+    TDisplayObject, Text, Video,
 };
 use crate::events::{ButtonKeyCode, ClipEvent};
 use crate::font::Font;
@@ -41,6 +43,7 @@ pub struct MovieClipData<'gc> {
     object: Option<Object<'gc>>,
     clip_actions: SmallVec<[ClipAction; 2]>,
     flags: EnumSet<MovieClipFlags>,
+    preload_progress: Option<Box<PreloadProgress>>,
 }
 
 impl<'gc> MovieClip<'gc> {
@@ -59,6 +62,7 @@ impl<'gc> MovieClip<'gc> {
                 object: None,
                 clip_actions: SmallVec::new(),
                 flags: EnumSet::empty(),
+                preload_progress: None,
             },
         ))
     }
@@ -83,6 +87,7 @@ impl<'gc> MovieClip<'gc> {
                         tag_stream_start,
                         tag_stream_len,
                         total_frames: num_frames,
+                        frames_loaded: 0,
                         audio_stream_info: None,
                         frame_labels: HashMap::new(),
                     },
@@ -94,18 +99,29 @@ impl<'gc> MovieClip<'gc> {
                 object: None,
                 clip_actions: SmallVec::new(),
                 flags: MovieClipFlags::Playing.into(),
+                preload_progress: None,
             },
         ))
     }
 
-    pub fn preload(
-        self,
-        context: &mut UpdateContext<'_, 'gc, '_>,
-        morph_shapes: &mut fnv::FnvHashMap<CharacterId, MorphShapeStatic>,
-    ) {
+    /// Preloads the definition tags in this clip's tag stream.
+    ///
+    /// If the clip's data is still loading, preloading stops at the first incomplete tag,
+    /// and resumes from there when this is called again. `is_data_complete` should be `true`
+    /// once all of the data has arrived.
+    pub fn preload(self, context: &mut UpdateContext<'_, 'gc, '_>, is_data_complete: bool) {
         self.0
             .write(context.gc_context)
-            .preload(context, morph_shapes, self.into())
+            .preload(context, is_data_complete, self.into())
+    }
+
+    /// Sets the length of this clip's tag stream.
+    /// Used to extend the root clip as more of the movie is loaded.
+    pub fn set_tag_stream_len(self, gc_context: MutationContext<'gc, '_>, tag_stream_len: usize) {
+        let mut clip = self.0.write(gc_context);
+        let mut static_data = (*clip.static_data).clone();
+        static_data.tag_stream_len = tag_stream_len;
+        clip.static_data = Gc::allocate(gc_context, static_data);
     }
 
     #[allow(dead_code)]
@@ -114,7 +130,7 @@ impl<'gc> MovieClip<'gc> {
     }
 
     pub fn next_frame(self, context: &mut UpdateContext<'_, 'gc, '_>) {
-        if self.current_frame() < self.total_frames() {
+        if self.current_frame() < self.frames_loaded() {
             self.goto_frame(context, self.current_frame() + 1, true);
         }
     }
@@ -155,8 +171,7 @@ impl<'gc> MovieClip<'gc> {
     }
 
     pub fn frames_loaded(self) -> FrameNumber {
-        // TODO(Herschel): root needs to progressively stream in frames.
-        self.0.read().static_data.total_frames
+        self.0.read().frames_loaded()
     }
 
     pub fn frame_label_to_number(self, frame_label: &str) -> Option<FrameNumber> {
@@ -236,6 +251,7 @@ impl<'gc> MovieClip<'gc> {
         let len = clip.tag_stream_len();
         let cursor = std::io::Cursor::new(&context.swf_data[start..start + len]);
         let mut reader = Reader::new(cursor, swf_version);
+        reader.set_codepage(context.system_codepage);
 
         // Iterate through this clip's tags, counting frames until we reach the target frame.
         while cur_frame <= frame && reader.get_ref().position() < len as u64 {
@@ -341,6 +357,7 @@ impl<'gc> TDisplayObject<'gc> for MovieClip<'gc> {
         let mut mc = self.0.write(gc_context);
         if mc.object.is_none() {
             let object = StageObject::for_display_object(gc_context, display_object, Some(proto));
+            attach_virtual_properties(gc_context, object.into());
             mc.object = Some(object.into());
         }
     }
@@ -354,6 +371,9 @@ impl<'gc> TDisplayObject<'gc> for MovieClip<'gc> {
     }
 
     fn unload(&mut self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        for mut child in self.children().collect::<Vec<_>>() {
+            child.unload(context);
+        }
         self.0.write(context.gc_context).run_clip_action(
             (*self).into(),
             context,
@@ -388,6 +408,10 @@ impl<'gc> MovieClipData<'gc> {
         self.static_data.total_frames
     }
 
+    fn frames_loaded(&self) -> FrameNumber {
+        self.static_data.frames_loaded
+    }
+
     fn playing(&self) -> bool {
         self.flags.contains(MovieClipFlags::Playing)
     }
@@ -422,7 +446,9 @@ impl<'gc> MovieClipData<'gc> {
         self.set_playing(false);
         // Stop audio stream if one is playing.
         if let Some(audio_stream) = self.audio_stream.take() {
-            context.audio.stop_stream(audio_stream);
+            context
+                .audio_manager
+                .stop_stream(context.audio, audio_stream);
         }
     }
 
@@ -451,10 +477,13 @@ impl<'gc> MovieClipData<'gc> {
         }
 
         // Clamp frame number in bounds.
-        if frame < 1 {
+        // Frames that haven't loaded yet can't be jumped to.
+        if self.frames_loaded() < 1 {
+            return;
+        } else if frame < 1 {
             frame = 1;
-        } else if frame > self.total_frames() {
-            frame = self.total_frames();
+        } else if frame > self.frames_loaded() {
+            frame = self.frames_loaded();
         }
 
         if frame != self.current_frame() {
@@ -471,7 +500,9 @@ impl<'gc> MovieClipData<'gc> {
                 ..self.tag_stream_start() as usize + self.tag_stream_len()],
         );
         cursor.set_position(self.tag_stream_pos);
-        swf::read::Reader::new(cursor, context.swf_version)
+        let mut reader = swf::read::Reader::new(cursor, context.swf_version);
+        reader.set_codepage(context.system_codepage);
+        reader
     }
 
     fn run_frame_internal(
@@ -482,6 +513,10 @@ impl<'gc> MovieClipData<'gc> {
     ) {
         // Advance frame number.
         if self.current_frame < self.total_frames() {
+            if self.current_frame >= self.frames_loaded() {
+                // Hold on the current frame until the next frame has loaded.
+                return;
+            }
             self.current_frame += 1;
         } else if self.total_frames() > 1 {
             // Looping acts exactly like a gotoAndPlay(1).
@@ -516,10 +551,10 @@ impl<'gc> MovieClipData<'gc> {
             TagCode::RemoveObject if run_display_actions => self.remove_object(context, reader, 1),
             TagCode::RemoveObject2 if run_display_actions => self.remove_object(context, reader, 2),
             TagCode::SetBackgroundColor => self.set_background_color(context, reader),
-            TagCode::StartSound => self.start_sound_1(context, reader),
+            TagCode::StartSound => self.start_sound_1(self_display_object, context, reader),
             TagCode::SoundStreamBlock => {
                 has_stream_block = true;
-                self.sound_stream_block(context, reader)
+                self.sound_stream_block(self_display_object, context, reader)
             }
             _ => Ok(()),
         };
@@ -530,7 +565,9 @@ impl<'gc> MovieClipData<'gc> {
         // If we are playing a streaming sound, there should(?) be a `SoundStreamBlock` on each frame.
         if let Some(audio_stream) = self.audio_stream {
             if !has_stream_block {
-                context.audio.stop_stream(audio_stream);
+                context
+                    .audio_manager
+                    .stop_stream(context.audio, audio_stream);
                 self.audio_stream = None;
             }
         }
@@ -568,7 +605,7 @@ impl<'gc> MovieClipData<'gc> {
                     }
                 }
                 // Run first frame.
-                child.apply_place_object(context.gc_context, place_object);
+                child.apply_place_object(context, place_object);
                 child.run_frame(context);
             }
             Some(child)
@@ -634,6 +671,14 @@ impl<'gc> MovieClipData<'gc> {
         //    the goto frame, so we should instead aggregate the deltas into a final list
         //    of commands, and THEN modify the children as necessary.
 
+        // A playing stream sound is restarted at the position of the new frame
+        // when the final frame runs its `SoundStreamBlock` below.
+        if let Some(audio_stream) = self.audio_stream.take() {
+            context
+                .audio_manager
+                .stop_stream(context.audio, audio_stream);
+        }
+
         // This map will maintain a map of depth -> placement commands.
         // TODO: Move this to UpdateContext to avoid allocations.
         let mut goto_commands = fnv::FnvHashMap::default();
@@ -714,7 +759,7 @@ impl<'gc> MovieClipData<'gc> {
                     // If it's a rewind, we removed any dead children above, so we always
                     // modify the previous child.
                     Some(mut prev_child) if params.id() == 0 || is_rewind => {
-                        prev_child.apply_place_object(context.gc_context, &params.place_object);
+                        prev_child.apply_place_object(context, &params.place_object);
                     }
                     _ => {
                         if let Some(mut child) = clip.instantiate_child(
@@ -894,16 +939,18 @@ impl<'gc, 'a> MovieClipData<'gc> {
     fn preload(
         &mut self,
         context: &mut UpdateContext<'_, 'gc, '_>,
-        morph_shapes: &mut fnv::FnvHashMap<CharacterId, MorphShapeStatic>,
+        is_data_complete: bool,
         self_display_object: DisplayObject<'gc>,
     ) {
         use swf::TagCode;
         // TODO: Re-creating static data because preload step occurs after construction.
         // Should be able to hoist this up somewhere, or use MaybeUninit.
         let mut static_data = (&*self.static_data).clone();
+        let mut progress = self.preload_progress.take().unwrap_or_default();
         let mut reader = self.reader(context);
-        let mut cur_frame = 1;
-        let mut ids = fnv::FnvHashMap::default();
+        reader.get_mut().set_position(progress.pos);
+        let mut cur_frame = progress.cur_frame;
+        let mut ids = std::mem::replace(&mut progress.ids, fnv::FnvHashMap::default());
         let tag_callback = |reader: &mut _, tag_code, tag_len| match tag_code {
             TagCode::DefineBits => self.define_bits(context, reader, tag_len),
             TagCode::DefineBitsJpeg2 => self.define_bits_jpeg_2(context, reader, tag_len),
@@ -919,17 +966,21 @@ impl<'gc, 'a> MovieClipData<'gc> {
             TagCode::DefineFont => self.define_font_1(context, reader),
             TagCode::DefineFont2 => self.define_font_2(context, reader),
             TagCode::DefineFont3 => self.define_font_3(context, reader),
-            TagCode::DefineFont4 => unimplemented!(),
-            TagCode::DefineMorphShape => self.define_morph_shape(context, reader, morph_shapes, 1),
-            TagCode::DefineMorphShape2 => self.define_morph_shape(context, reader, morph_shapes, 2),
+            TagCode::DefineFont4 => self.define_font_4(context, reader, tag_len),
+            TagCode::DefineFontInfo => self.define_font_info(context, reader, tag_len, 1),
+            TagCode::DefineFontInfo2 => self.define_font_info(context, reader, tag_len, 2),
+            TagCode::DefineFontName => self.define_font_name(context, reader, tag_len),
+            TagCode::DefineMorphShape => self.define_morph_shape(context, reader, 1),
+            TagCode::DefineMorphShape2 => self.define_morph_shape(context, reader, 2),
             TagCode::DefineShape => self.define_shape(context, reader, 1),
             TagCode::DefineShape2 => self.define_shape(context, reader, 2),
             TagCode::DefineShape3 => self.define_shape(context, reader, 3),
             TagCode::DefineShape4 => self.define_shape(context, reader, 4),
             TagCode::DefineSound => self.define_sound(context, reader, tag_len),
-            TagCode::DefineSprite => self.define_sprite(context, reader, tag_len, morph_shapes),
+            TagCode::DefineSprite => self.define_sprite(context, reader, tag_len),
             TagCode::DefineText => self.define_text(context, reader, 1),
             TagCode::DefineText2 => self.define_text(context, reader, 2),
+            TagCode::DefineVideoStream => self.define_video_stream(context, reader),
             TagCode::DoInitAction => {
                 self.do_init_action(self_display_object, context, reader, tag_len)
             }
@@ -939,20 +990,22 @@ impl<'gc, 'a> MovieClipData<'gc> {
             }
             TagCode::JpegTables => self.jpeg_tables(context, reader, tag_len),
             TagCode::PlaceObject => {
-                self.preload_place_object(context, reader, tag_len, &mut ids, morph_shapes, 1)
+                self.preload_place_object(context, reader, tag_len, &mut ids, 1)
             }
             TagCode::PlaceObject2 => {
-                self.preload_place_object(context, reader, tag_len, &mut ids, morph_shapes, 2)
+                self.preload_place_object(context, reader, tag_len, &mut ids, 2)
             }
             TagCode::PlaceObject3 => {
-                self.preload_place_object(context, reader, tag_len, &mut ids, morph_shapes, 3)
+                self.preload_place_object(context, reader, tag_len, &mut ids, 3)
             }
             TagCode::PlaceObject4 => {
-                self.preload_place_object(context, reader, tag_len, &mut ids, morph_shapes, 4)
+                self.preload_place_object(context, reader, tag_len, &mut ids, 4)
             }
             TagCode::RemoveObject => self.preload_remove_object(context, reader, &mut ids, 1),
             TagCode::RemoveObject2 => self.preload_remove_object(context, reader, &mut ids, 2),
-            TagCode::ShowFrame => self.preload_show_frame(context, reader, &mut cur_frame),
+            TagCode::ShowFrame => {
+                self.preload_show_frame(context, reader, &mut cur_frame, &mut static_data)
+            }
             TagCode::SoundStreamHead => {
                 self.preload_sound_stream_head(context, reader, cur_frame, &mut static_data, 1)
             }
@@ -966,13 +1019,25 @@ impl<'gc, 'a> MovieClipData<'gc> {
                 &mut static_data,
                 tag_len,
             ),
+            TagCode::VideoFrame => self.preload_video_frame(context, reader, tag_len),
             _ => Ok(()),
         };
-        let _ = tag_utils::decode_tags(&mut reader, tag_callback, TagCode::End);
+        let result = tag_utils::decode_tags(&mut reader, tag_callback, TagCode::End);
+
+        // If the data is incomplete, save our place to resume when more data arrives.
+        let is_complete = result.is_ok() || is_data_complete;
+        if is_complete {
+            static_data.frames_loaded = static_data.total_frames;
+        } else {
+            progress.pos = reader.get_ref().position();
+            progress.cur_frame = cur_frame;
+            progress.ids = ids;
+            self.preload_progress = Some(progress);
+        }
         self.static_data = Gc::allocate(context.gc_context, static_data);
 
         // Finalize audio stream.
-        if self.static_data.audio_stream_info.is_some() {
+        if is_complete && self.static_data.audio_stream_info.is_some() {
             context.audio.preload_sound_stream_end(self.id());
         }
     }
@@ -1004,13 +1069,16 @@ impl<'gc, 'a> MovieClipData<'gc> {
         &mut self,
         context: &mut UpdateContext<'_, 'gc, '_>,
         reader: &mut SwfStream<&'a [u8]>,
-        morph_shapes: &mut fnv::FnvHashMap<CharacterId, MorphShapeStatic>,
         version: u8,
     ) -> DecodeResult {
-        // Certain backends may have to preload morph shape frames, so defer registering until the end.
+        // Certain backends may have to preload morph shape frames,
+        // so the ratios are registered as the shape is placed.
         let swf_shape = reader.read_define_morph_shape(version)?;
-        let morph_shape = MorphShapeStatic::from_swf_tag(context.renderer, &swf_shape);
-        morph_shapes.insert(swf_shape.id, morph_shape);
+        let static_data = MorphShapeStatic::from_swf_tag(context.renderer, &swf_shape);
+        let morph_shape = MorphShape::new(context.gc_context, static_data);
+        context
+            .library
+            .register_character(swf_shape.id, Character::MorphShape(morph_shape));
         Ok(())
     }
 
@@ -1036,7 +1104,6 @@ impl<'gc, 'a> MovieClipData<'gc> {
         reader: &mut SwfStream<&'a [u8]>,
         tag_len: usize,
         ids: &mut fnv::FnvHashMap<Depth, CharacterId>,
-        morph_shapes: &mut fnv::FnvHashMap<CharacterId, MorphShapeStatic>,
         version: u8,
     ) -> DecodeResult {
         use swf::PlaceObjectAction;
@@ -1047,28 +1114,28 @@ impl<'gc, 'a> MovieClipData<'gc> {
         }?;
         match place_object.action {
             PlaceObjectAction::Place(id) => {
-                if let Some(morph_shape) = morph_shapes.get_mut(&id) {
+                if let Some(morph_shape) = context.library.get_morph_shape(id) {
                     ids.insert(place_object.depth.into(), id);
                     if let Some(ratio) = place_object.ratio {
-                        morph_shape.register_ratio(context.renderer, ratio);
+                        morph_shape.register_ratio(context.gc_context, context.renderer, ratio);
                     }
                 }
             }
             PlaceObjectAction::Modify => {
                 if let Some(&id) = ids.get(&place_object.depth.into()) {
-                    if let Some(morph_shape) = morph_shapes.get_mut(&id) {
+                    if let Some(morph_shape) = context.library.get_morph_shape(id) {
                         ids.insert(place_object.depth.into(), id);
                         if let Some(ratio) = place_object.ratio {
-                            morph_shape.register_ratio(context.renderer, ratio);
+                            morph_shape.register_ratio(context.gc_context, context.renderer, ratio);
                         }
                     }
                 }
             }
             PlaceObjectAction::Replace(id) => {
-                if let Some(morph_shape) = morph_shapes.get_mut(&id) {
+                if let Some(morph_shape) = context.library.get_morph_shape(id) {
                     ids.insert(place_object.depth.into(), id);
                     if let Some(ratio) = place_object.ratio {
-                        morph_shape.register_ratio(context.renderer, ratio);
+                        morph_shape.register_ratio(context.gc_context, context.renderer, ratio);
                     }
                 } else {
                     ids.remove(&place_object.depth.into());
@@ -1100,6 +1167,37 @@ impl<'gc, 'a> MovieClipData<'gc> {
         Ok(())
     }
 
+    #[inline]
+    fn preload_video_frame(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+        tag_len: usize,
+    ) -> DecodeResult {
+        use std::io::Read;
+        let stream_id = reader.read_u16()?;
+        let frame_num = reader.read_u16()?;
+        let data_len = tag_len.saturating_sub(4);
+        let mut data = Vec::with_capacity(data_len);
+        reader
+            .get_mut()
+            .take(data_len as u64)
+            .read_to_end(&mut data)?;
+        if let Some(video) = context.library.get_video(stream_id) {
+            video.preload_frame(
+                context,
+                swf::VideoFrame {
+                    stream_id,
+                    frame_num,
+                    data,
+                },
+            );
+        } else {
+            log::warn!("VideoFrame: Video stream {} doesn't exist", stream_id);
+        }
+        Ok(())
+    }
+
     #[inline]
     fn preload_sound_stream_head(
         &mut self,
@@ -1110,6 +1208,10 @@ impl<'gc, 'a> MovieClipData<'gc> {
         _version: u8,
     ) -> DecodeResult {
         let audio_stream_info = reader.read_sound_stream_head()?;
+        if audio_stream_info.stream_format.compression == swf::AudioCompression::Speex {
+            log::warn!("Speex stream sounds are not supported");
+            return Ok(());
+        }
         context
             .audio
             .preload_sound_stream_head(self.id(), cur_frame, &audio_stream_info);
@@ -1391,7 +1493,10 @@ impl<'gc, 'a> MovieClipData<'gc> {
         context: &mut UpdateContext<'_, 'gc, '_>,
         reader: &mut SwfStream<&'a [u8]>,
     ) -> DecodeResult {
-        let font = reader.read_define_font_2(2)?;
+        let mut font = reader.read_define_font_2(2)?;
+        if context.swf_version < 6 {
+            Font::convert_legacy_codes(&mut font, context.system_codepage);
+        }
         let font_object = Font::from_swf_tag(context.gc_context, context.renderer, &font).unwrap();
         context
             .library
@@ -1414,6 +1519,77 @@ impl<'gc, 'a> MovieClipData<'gc> {
         Ok(())
     }
 
+    #[inline]
+    fn define_font_4(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+        tag_len: usize,
+    ) -> DecodeResult {
+        // The font data extends to the end of the tag.
+        use std::io::Read;
+        let mut reader =
+            swf::read::Reader::new(reader.get_mut().take(tag_len as u64), context.swf_version);
+        let font = reader.read_define_font_4()?;
+        let font_object =
+            Font::from_font4_tag(context.gc_context, context.renderer, &font).unwrap();
+        context
+            .library
+            .register_character(font.id, Character::Font(font_object));
+
+        Ok(())
+    }
+
+    #[inline]
+    fn define_font_info(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+        tag_len: usize,
+        version: u8,
+    ) -> DecodeResult {
+        // The code table extends to the end of the tag.
+        use std::io::Read;
+        let mut reader =
+            swf::read::Reader::new(reader.get_mut().take(tag_len as u64), context.swf_version);
+        reader.set_codepage(context.system_codepage);
+        if let swf::Tag::DefineFontInfo(font_info) = reader.read_define_font_info(version)? {
+            context.library.set_font_name_and_style(
+                context.gc_context,
+                font_info.id,
+                &font_info.name,
+                font_info.is_bold,
+                font_info.is_italic,
+            );
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn define_font_name(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+        tag_len: usize,
+    ) -> DecodeResult {
+        use std::io::Read;
+        let mut reader =
+            swf::read::Reader::new(reader.get_mut().take(tag_len as u64), context.swf_version);
+        if let swf::Tag::DefineFontName { id, name, .. } = reader.read_define_font_name()? {
+            // Only the name is given, so keep the style of the font.
+            if let Some(font) = context.library.get_font(id) {
+                context.library.set_font_name_and_style(
+                    context.gc_context,
+                    id,
+                    &name,
+                    font.is_bold(),
+                    font.is_italic(),
+                );
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     fn define_sound(
         &mut self,
@@ -1438,7 +1614,6 @@ impl<'gc, 'a> MovieClipData<'gc> {
         context: &mut UpdateContext<'_, 'gc, '_>,
         reader: &mut SwfStream<&'a [u8]>,
         tag_len: usize,
-        morph_shapes: &mut fnv::FnvHashMap<CharacterId, MorphShapeStatic>,
     ) -> DecodeResult {
         let id = reader.read_character_id()?;
         let num_frames = reader.read_u16()?;
@@ -1451,7 +1626,7 @@ impl<'gc, 'a> MovieClipData<'gc> {
             num_frames,
         );
 
-        movie_clip.preload(context, morph_shapes);
+        movie_clip.preload(context, true);
 
         context
             .library
@@ -1475,6 +1650,21 @@ impl<'gc, 'a> MovieClipData<'gc> {
         Ok(())
     }
 
+    #[inline]
+    fn define_video_stream(
+        &mut self,
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        reader: &mut SwfStream<&'a [u8]>,
+    ) -> DecodeResult {
+        let define_video_stream = reader.read_define_video_stream()?;
+        let id = define_video_stream.id;
+        let video = Video::from_swf_tag(context, define_video_stream);
+        context
+            .library
+            .register_character(id, Character::Video(video));
+        Ok(())
+    }
+
     #[inline]
     fn export_assets(
         &mut self,
@@ -1549,7 +1739,9 @@ impl<'gc, 'a> MovieClipData<'gc> {
         _context: &mut UpdateContext<'_, 'gc, '_>,
         _reader: &mut SwfStream<&'a [u8]>,
         cur_frame: &mut FrameNumber,
+        static_data: &mut MovieClipStatic,
     ) -> DecodeResult {
+        static_data.frames_loaded = *cur_frame;
         *cur_frame += 1;
         Ok(())
     }
@@ -1651,7 +1843,7 @@ impl<'gc, 'a> MovieClipData<'gc> {
             PlaceObjectAction::Modify => {
                 if let Some(mut child) = self.children.get_mut(&place_object.depth.into()).copied()
                 {
-                    child.apply_place_object(context.gc_context, &place_object);
+                    child.apply_place_object(context, &place_object);
                     child
                 } else {
                     return Ok(());
@@ -1694,6 +1886,7 @@ impl<'gc, 'a> MovieClipData<'gc> {
     #[inline]
     fn sound_stream_block(
         &mut self,
+        self_display_object: DisplayObject<'gc>,
         context: &mut UpdateContext<'_, 'gc, '_>,
         _reader: &mut SwfStream<&'a [u8]>,
     ) -> DecodeResult {
@@ -1705,11 +1898,15 @@ impl<'gc, 'a> MovieClipData<'gc> {
                 start: pos as usize,
                 end: self.tag_stream_start() as usize + self.tag_stream_len(),
             };
-            self.audio_stream = Some(context.audio.start_stream(
+            // The frame number has already advanced to the frame containing this block,
+            // which is the frame the block was preloaded with. The stream starts here.
+            self.audio_stream = Some(context.audio_manager.start_stream(
+                context.audio,
                 self.id(),
-                self.current_frame() + 1,
+                self.current_frame(),
                 slice,
                 &stream_info,
+                Some(self_display_object),
             ));
         }
 
@@ -1719,6 +1916,7 @@ impl<'gc, 'a> MovieClipData<'gc> {
     #[inline]
     fn start_sound_1(
         &mut self,
+        self_display_object: DisplayObject<'gc>,
         context: &mut UpdateContext<'_, 'gc, '_>,
         reader: &mut SwfStream<&'a [u8]>,
     ) -> DecodeResult {
@@ -1729,18 +1927,34 @@ impl<'gc, 'a> MovieClipData<'gc> {
             match start_sound.sound_info.event {
                 // "Event" sounds always play, independent of the timeline.
                 SoundEvent::Event => {
-                    context.audio.start_sound(handle, &start_sound.sound_info);
+                    context.audio_manager.start_sound(
+                        context.audio,
+                        handle,
+                        &start_sound.sound_info,
+                        Some(self_display_object),
+                        None,
+                    );
                 }
 
                 // "Start" sounds only play if an instance of the same sound is not already playing.
                 SoundEvent::Start => {
                     if !context.audio.is_sound_playing_with_handle(handle) {
-                        context.audio.start_sound(handle, &start_sound.sound_info);
+                        context.audio_manager.start_sound(
+                            context.audio,
+                            handle,
+                            &start_sound.sound_info,
+                            Some(self_display_object),
+                            None,
+                        );
                     }
                 }
 
                 // "Stop" stops any active instances of a given sound.
-                SoundEvent::Stop => context.audio.stop_sounds_with_handle(handle),
+                SoundEvent::Stop => {
+                    context
+                        .audio_manager
+                        .stop_sounds_with_handle(context.audio, handle, None)
+                }
             }
         }
         Ok(())
@@ -1757,6 +1971,8 @@ struct MovieClipStatic {
     frame_labels: HashMap<String, FrameNumber>,
     audio_stream_info: Option<swf::SoundStreamHead>,
     total_frames: FrameNumber,
+    /// The number of frames whose tags have been preloaded.
+    frames_loaded: FrameNumber,
 }
 
 impl Default for MovieClipStatic {
@@ -1766,6 +1982,7 @@ impl Default for MovieClipStatic {
             tag_stream_start: 0,
             tag_stream_len: 0,
             total_frames: 1,
+            frames_loaded: 1,
             frame_labels: HashMap::new(),
             audio_stream_info: None,
         }
@@ -1779,6 +1996,27 @@ unsafe impl<'gc> Collect for MovieClipStatic {
     }
 }
 
+/// The progress of a clip's preload, so that it can resume once more data has arrived.
+#[derive(Clone, Debug)]
+struct PreloadProgress {
+    /// The position of the next tag to preload.
+    pos: u64,
+    /// The frame that is being preloaded.
+    cur_frame: FrameNumber,
+    /// The character IDs placed at each depth, used to track morph shape ratios.
+    ids: fnv::FnvHashMap<Depth, CharacterId>,
+}
+
+impl Default for PreloadProgress {
+    fn default() -> Self {
+        Self {
+            pos: 0,
+            cur_frame: 1,
+            ids: fnv::FnvHashMap::default(),
+        }
+    }
+}
+
 /// Stores the placement settings for display objects during a
 /// goto command.
 #[derive(Debug)]
@@ -1931,7 +2169,7 @@ impl From<swf::ClipAction> for ClipAction {
                 })
                 .collect(),
             action_data: SwfSlice {
-                data: std::sync::Arc::new(other.action_data.clone()),
+                data: std::sync::Arc::new(other.action_data.clone().into()),
                 start: 0,
                 end: other.action_data.len(),
             },
@@ -24,6 +24,7 @@ impl<'gc> Text<'gc> {
                     context.gc_context,
                     TextStatic {
                         id: tag.id,
+                        bounds: tag.bounds.clone().into(),
                         text_transform: tag.matrix.clone().into(),
                         text_blocks: tag.records.clone(),
                     },
@@ -40,6 +41,10 @@ impl<'gc> TDisplayObject<'gc> for Text<'gc> {
         self.0.read().static_data.id
     }
 
+    fn self_bounds(&self) -> BoundingBox {
+        self.0.read().static_data.bounds.clone()
+    }
+
     fn run_frame(&mut self, _context: &mut UpdateContext) {
         // Noop
     }
@@ -109,6 +114,7 @@ unsafe impl<'gc> gc_arena::Collect for TextData<'gc> {
 #[derive(Debug, Clone)]
 struct TextStatic {
     id: CharacterId,
+    bounds: BoundingBox,
     text_transform: Matrix,
     text_blocks: Vec<swf::TextRecord>,
 }
@@ -0,0 +1,566 @@
+//! Video display object
+
+use crate::avm1::{NetStreamObject, Object, StageObject, Value};
+use crate::backend::render::BitmapInfo;
+use crate::backend::video::{EncodedFrame, VideoStreamHandle};
+use crate::context::{RenderContext, UpdateContext};
+use crate::display_object::{DisplayObjectBase, TDisplayObject};
+use crate::prelude::*;
+use crate::transform::Transform;
+use gc_arena::{Collect, GcCell, MutationContext};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A Video display object plays the frames of an embedded video stream.
+///
+/// The video is defined by a `DefineVideoStream` tag, and its frames are stored in
+/// `VideoFrame` tags on the timeline of the clip that places it. The frame that is
+/// displayed is set by the ratio of the PlaceObject tag.
+///
+/// A video can instead display the frames of a `NetStream`, once ActionScript attaches
+/// one with `Video.attachVideo`.
+#[derive(Clone, Debug, Collect, Copy)]
+#[collect(no_drop)]
+pub struct Video<'gc>(GcCell<'gc, VideoData<'gc>>);
+
+#[derive(Clone, Debug)]
+pub struct VideoData<'gc> {
+    base: DisplayObjectBase<'gc>,
+    static_data: GcCell<'gc, VideoStatic>,
+
+    /// The frame of the video to display.
+    frame_id: u32,
+
+    /// The video stream that this instance decodes frames with.
+    /// It's registered when the first frame is decoded, so that each instance has its own decoder.
+    stream: Option<VideoStreamHandle>,
+
+    /// The last frame that this instance decoded, and the bitmap it was decoded to.
+    decoded_frame: Option<(u32, BitmapInfo)>,
+
+    /// The `NetStream` attached by `Video.attachVideo`, which replaces the embedded frames.
+    net_stream: Option<NetStreamObject<'gc>>,
+
+    object: Option<Object<'gc>>,
+}
+
+impl<'gc> Video<'gc> {
+    pub fn from_swf_tag(
+        context: &mut UpdateContext<'_, 'gc, '_>,
+        swf_tag: swf::DefineVideoStream,
+    ) -> Self {
+        let stream = if swf_tag.codec == swf::VideoCodec::None {
+            // The video is only played from ActionScript, and has no embedded frames.
+            None
+        } else {
+            match context.video.register_video_stream(
+                swf_tag.id,
+                swf_tag.num_frames.into(),
+                (swf_tag.width, swf_tag.height),
+                swf_tag.codec,
+                swf_tag.deblocking,
+            ) {
+                Ok(stream) => Some(stream),
+                Err(e) => {
+                    log::warn!("Unable to play video {}: {}", swf_tag.id, e);
+                    None
+                }
+            }
+        };
+        let static_data = VideoStatic {
+            definition: swf_tag,
+            preload_stream: stream,
+            frames: BTreeMap::new(),
+            keyframes: BTreeSet::new(),
+        };
+        Video(GcCell::allocate(
+            context.gc_context,
+            VideoData {
+                base: Default::default(),
+                static_data: GcCell::allocate(context.gc_context, static_data),
+                frame_id: 0,
+                stream: None,
+                decoded_frame: None,
+                net_stream: None,
+                object: None,
+            },
+        ))
+    }
+
+    /// Stores the data of a `VideoFrame` tag as it is preloaded.
+    /// The stored frames are shared by every instance of the video.
+    pub fn preload_frame(self, context: &mut UpdateContext<'_, 'gc, '_>, frame: swf::VideoFrame) {
+        let data = self.0.read();
+        let mut static_data = data.static_data.write(context.gc_context);
+        let frame_id = u32::from(frame.frame_num);
+        if let Some(stream) = static_data.preload_stream {
+            let encoded_frame = EncodedFrame {
+                codec: static_data.definition.codec,
+                data: &frame.data,
+                frame_id,
+            };
+            match context
+                .video
+                .preload_video_stream_frame(stream, encoded_frame)
+            {
+                Ok(dependency) if dependency.is_keyframe() => {
+                    static_data.keyframes.insert(frame_id);
+                }
+                Ok(_) => (),
+                Err(e) => log::warn!("Unable to preload video frame {}: {}", frame_id, e),
+            }
+        }
+        static_data.frames.insert(frame_id, frame.data);
+    }
+
+    pub fn frame_id(self) -> u32 {
+        self.0.read().frame_id
+    }
+
+    /// Attaches a `NetStream` that this video displays the frames of,
+    /// or detaches it to return to the embedded frames.
+    pub fn attach_net_stream(
+        self,
+        gc_context: MutationContext<'gc, '_>,
+        net_stream: Option<NetStreamObject<'gc>>,
+    ) {
+        self.0.write(gc_context).net_stream = net_stream;
+    }
+
+    /// The frame that is displayed, from the attached `NetStream` if any.
+    pub fn current_bitmap(self) -> Option<BitmapInfo> {
+        let data = self.0.read();
+        if let Some(net_stream) = data.net_stream {
+            net_stream.decoded_frame()
+        } else {
+            data.decoded_frame.map(|(_, bitmap)| bitmap)
+        }
+    }
+
+    /// Sets the frame of the video to display, decoding it if needed.
+    pub fn seek(self, context: &mut UpdateContext<'_, 'gc, '_>, frame_id: u32) {
+        self.0.write(context.gc_context).frame_id = frame_id;
+        self.decode_frame(context);
+    }
+
+    /// Decodes the frame that this video displays.
+    fn decode_frame(self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        let mut write = self.0.write(context.gc_context);
+        let data = &mut *write;
+        let static_data = data.static_data.read();
+        if static_data.preload_stream.is_none() {
+            // The codec isn't supported.
+            return;
+        }
+
+        let start = match first_frame_to_decode(
+            data.decoded_frame.map(|(id, _)| id),
+            &static_data.keyframes,
+            data.frame_id,
+        ) {
+            Some(start) => start,
+            None => return,
+        };
+
+        let definition = &static_data.definition;
+        let stream = match data.stream {
+            Some(stream) => stream,
+            None => match context.video.register_video_stream(
+                definition.id,
+                definition.num_frames.into(),
+                (definition.width, definition.height),
+                definition.codec,
+                definition.deblocking,
+            ) {
+                Ok(stream) => {
+                    data.stream = Some(stream);
+                    stream
+                }
+                Err(e) => {
+                    log::warn!("Unable to play video {}: {}", definition.id, e);
+                    return;
+                }
+            },
+        };
+
+        for (&id, frame_data) in static_data.frames.range(start..=data.frame_id) {
+            let encoded_frame = EncodedFrame {
+                codec: definition.codec,
+                data: frame_data,
+                frame_id: id,
+            };
+            match context
+                .video
+                .decode_video_stream_frame(stream, encoded_frame, context.renderer)
+            {
+                Ok(bitmap) => data.decoded_frame = Some((id, bitmap)),
+                Err(e) => {
+                    log::warn!("Unable to decode video frame {}: {}", id, e);
+                    data.decoded_frame = None;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the frame to start decoding from to display `frame_id`,
+/// or `None` if there is nothing to decode.
+///
+/// Frames are decoded in order from the closest keyframe, or from the last decoded
+/// frame if there are no keyframes between it and the frame to display.
+fn first_frame_to_decode(
+    decoded_frame: Option<u32>,
+    keyframes: &BTreeSet<u32>,
+    frame_id: u32,
+) -> Option<u32> {
+    let keyframe = keyframes.range(..=frame_id).next_back().copied();
+    match (decoded_frame, keyframe) {
+        (Some(decoded), _) if decoded == frame_id => None,
+        (Some(decoded), Some(keyframe)) if decoded < frame_id && decoded >= keyframe => {
+            Some(decoded + 1)
+        }
+        // The keyframe hasn't loaded yet if there is none.
+        (_, keyframe) => keyframe,
+    }
+}
+
+impl<'gc> TDisplayObject<'gc> for Video<'gc> {
+    impl_display_object!(base);
+
+    fn id(&self) -> CharacterId {
+        self.0.read().static_data.read().definition.id
+    }
+
+    fn self_bounds(&self) -> BoundingBox {
+        let static_data = self.0.read().static_data;
+        let definition = &static_data.read().definition;
+        BoundingBox {
+            x_min: Twips::new(0),
+            y_min: Twips::new(0),
+            x_max: Twips::from_pixels(definition.width.into()),
+            y_max: Twips::from_pixels(definition.height.into()),
+            valid: true,
+        }
+    }
+
+    fn as_video(&self) -> Option<Self> {
+        Some(*self)
+    }
+
+    fn post_instantiation(
+        &mut self,
+        gc_context: MutationContext<'gc, '_>,
+        display_object: DisplayObject<'gc>,
+        proto: Object<'gc>,
+    ) {
+        let mut video = self.0.write(gc_context);
+        if video.object.is_none() {
+            let object = StageObject::for_display_object(gc_context, display_object, Some(proto));
+            video.object = Some(object.into());
+        }
+    }
+
+    fn object(&self) -> Value<'gc> {
+        self.0
+            .read()
+            .object
+            .map(Value::from)
+            .unwrap_or(Value::Undefined)
+    }
+
+    fn run_frame(&mut self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        // The frame may not have loaded when it was seeked to.
+        self.decode_frame(context);
+    }
+
+    fn unload(&mut self, context: &mut UpdateContext<'_, 'gc, '_>) {
+        // Release the stream of this instance.
+        let mut video = self.0.write(context.gc_context);
+        if let Some(stream) = video.stream.take() {
+            context
+                .video
+                .unregister_video_stream(stream, context.renderer);
+        }
+        video.decoded_frame = None;
+        drop(video);
+        self.set_removed(context.gc_context, true);
+    }
+
+    fn render(&self, context: &mut RenderContext) {
+        if !self.world_bounds().intersects(&context.view_bounds) {
+            // Off-screen; culled
+            return;
+        }
+
+        let bitmap = match self.current_bitmap() {
+            Some(bitmap) => bitmap,
+            None => return,
+        };
+        let static_data = self.0.read().static_data;
+        let static_data = static_data.read();
+
+        // The decoded frame is stretched to the size of the video.
+        let definition = &static_data.definition;
+        let scale = Transform {
+            matrix: Matrix {
+                a: f32::from(definition.width) / f32::from(bitmap.width.max(1)),
+                d: f32::from(definition.height) / f32::from(bitmap.height.max(1)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        context.transform_stack.push(&*self.transform());
+        context.transform_stack.push(&scale);
+
+        context.renderer.render_bitmap(
+            bitmap.handle,
+            context.transform_stack.transform(),
+            definition.is_smoothed,
+        );
+
+        context.transform_stack.pop();
+        context.transform_stack.pop();
+    }
+}
+
+unsafe impl<'gc> gc_arena::Collect for VideoData<'gc> {
+    fn trace(&self, cc: gc_arena::CollectionContext) {
+        self.base.trace(cc);
+        self.static_data.trace(cc);
+        self.net_stream.trace(cc);
+        self.object.trace(cc);
+    }
+}
+
+/// Static data shared between all instances of a video.
+#[derive(Debug)]
+struct VideoStatic {
+    definition: swf::DefineVideoStream,
+
+    /// The stream that preloaded frames are inspected with, to find the keyframes.
+    /// Each instance decodes with a stream of its own.
+    preload_stream: Option<VideoStreamHandle>,
+
+    /// The encoded frames of the video, by frame number.
+    frames: BTreeMap<u32, Vec<u8>>,
+
+    /// The frames that decoding can start from.
+    keyframes: BTreeSet<u32>,
+}
+
+unsafe impl<'gc> gc_arena::Collect for VideoStatic {
+    #[inline]
+    fn needs_trace() -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avm1::test_utils::with_avm_and_video;
+    use crate::backend::render::{BitmapHandle, RenderBackend};
+    use crate::backend::video::{FrameDependency, VideoBackend};
+    use generational_arena::Arena;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use swf::{VideoCodec, VideoDeblocking};
+
+    type Error = Box<dyn std::error::Error>;
+
+    /// A video backend that records the frames it decodes.
+    /// Frames that are a single 1 byte are keyframes.
+    struct TestVideoBackend {
+        streams: Arena<()>,
+        decoded: Rc<RefCell<Vec<(VideoStreamHandle, u32)>>>,
+    }
+
+    impl TestVideoBackend {
+        fn new(decoded: Rc<RefCell<Vec<(VideoStreamHandle, u32)>>>) -> Self {
+            Self {
+                streams: Arena::new(),
+                decoded,
+            }
+        }
+    }
+
+    impl VideoBackend for TestVideoBackend {
+        fn register_video_stream(
+            &mut self,
+            _id: CharacterId,
+            _num_frames: u32,
+            _size: (u16, u16),
+            _codec: VideoCodec,
+            _deblocking: VideoDeblocking,
+        ) -> Result<VideoStreamHandle, Error> {
+            Ok(self.streams.insert(()))
+        }
+
+        fn preload_video_stream_frame(
+            &mut self,
+            _stream: VideoStreamHandle,
+            encoded_frame: EncodedFrame<'_>,
+        ) -> Result<FrameDependency, Error> {
+            if encoded_frame.data == [1] {
+                Ok(FrameDependency::None)
+            } else {
+                Ok(FrameDependency::Past)
+            }
+        }
+
+        fn decode_video_stream_frame(
+            &mut self,
+            stream: VideoStreamHandle,
+            encoded_frame: EncodedFrame<'_>,
+            _renderer: &mut dyn RenderBackend,
+        ) -> Result<BitmapInfo, Error> {
+            self.streams
+                .get(stream)
+                .ok_or("Unregistered video stream")?;
+            self.decoded
+                .borrow_mut()
+                .push((stream, encoded_frame.frame_id));
+            Ok(BitmapInfo {
+                handle: BitmapHandle(encoded_frame.frame_id as usize),
+                width: 4,
+                height: 4,
+            })
+        }
+
+        fn unregister_video_stream(
+            &mut self,
+            stream: VideoStreamHandle,
+            _renderer: &mut dyn RenderBackend,
+        ) {
+            self.streams.remove(stream);
+        }
+    }
+
+    #[test]
+    fn first_frame_to_decode_from_keyframes() {
+        let keyframes: BTreeSet<u32> = [1, 4].iter().copied().collect();
+        assert_eq!(first_frame_to_decode(None, &keyframes, 1), Some(1));
+        assert_eq!(first_frame_to_decode(None, &keyframes, 6), Some(4));
+        // Decoding continues from the last decoded frame.
+        assert_eq!(first_frame_to_decode(Some(2), &keyframes, 3), Some(3));
+        assert_eq!(first_frame_to_decode(Some(4), &keyframes, 6), Some(5));
+        // Unless there's a keyframe in between, or it's after the frame.
+        assert_eq!(first_frame_to_decode(Some(2), &keyframes, 5), Some(4));
+        assert_eq!(first_frame_to_decode(Some(6), &keyframes, 5), Some(4));
+        // The frame is already decoded.
+        assert_eq!(first_frame_to_decode(Some(5), &keyframes, 5), None);
+        // There is no keyframe to start from yet.
+        assert_eq!(first_frame_to_decode(None, &BTreeSet::new(), 3), None);
+    }
+
+    #[test]
+    fn seek_decodes_from_keyframe() {
+        let decoded = Rc::new(RefCell::new(vec![]));
+        let mut backend = TestVideoBackend::new(decoded.clone());
+        with_avm_and_video(8, &mut backend, |_avm, context, _root| {
+            let video = Video::from_swf_tag(
+                context,
+                swf::DefineVideoStream {
+                    id: 1,
+                    num_frames: 6,
+                    width: 4,
+                    height: 4,
+                    is_smoothed: false,
+                    deblocking: VideoDeblocking::UseVideoPacketValue,
+                    codec: VideoCodec::H263,
+                },
+            );
+            for frame_num in 1..=6 {
+                let is_keyframe = frame_num == 1 || frame_num == 4;
+                video.preload_frame(
+                    context,
+                    swf::VideoFrame {
+                        stream_id: 1,
+                        frame_num,
+                        data: vec![is_keyframe.into()],
+                    },
+                );
+            }
+            let mut first = video.instantiate(context.gc_context).as_video().unwrap();
+            let second = video.instantiate(context.gc_context).as_video().unwrap();
+            let frames = |decoded: &Rc<RefCell<Vec<(VideoStreamHandle, u32)>>>| {
+                decoded
+                    .borrow_mut()
+                    .drain(..)
+                    .map(|(_, id)| id)
+                    .collect::<Vec<_>>()
+            };
+
+            first.seek(context, 5);
+            assert_eq!(frames(&decoded), [4, 5]);
+            assert_eq!(first.current_bitmap().unwrap().handle.0, 5);
+
+            // The frame is already decoded.
+            first.seek(context, 5);
+            first.run_frame(context);
+            assert_eq!(frames(&decoded), []);
+
+            first.seek(context, 6);
+            assert_eq!(frames(&decoded), [6]);
+            first.seek(context, 2);
+            assert_eq!(frames(&decoded), [1, 2]);
+
+            // Each instance decodes with its own stream, and keeps its own frame.
+            second.seek(context, 3);
+            let second_stream = decoded.borrow()[0].0;
+            assert_eq!(frames(&decoded), [1, 2, 3]);
+            first.seek(context, 3);
+            let first_stream = decoded.borrow()[0].0;
+            assert_eq!(frames(&decoded), [3]);
+            assert_ne!(first_stream, second_stream);
+            second.seek(context, 1);
+            assert_eq!(frames(&decoded), [1]);
+            assert_eq!(first.current_bitmap().unwrap().handle.0, 3);
+            assert_eq!(second.current_bitmap().unwrap().handle.0, 1);
+        });
+    }
+
+    #[test]
+    fn unload_unregisters_stream() {
+        let decoded = Rc::new(RefCell::new(vec![]));
+        let mut backend = TestVideoBackend::new(decoded.clone());
+        with_avm_and_video(8, &mut backend, |_avm, context, _root| {
+            let video = Video::from_swf_tag(
+                context,
+                swf::DefineVideoStream {
+                    id: 1,
+                    num_frames: 1,
+                    width: 4,
+                    height: 4,
+                    is_smoothed: false,
+                    deblocking: VideoDeblocking::UseVideoPacketValue,
+                    codec: VideoCodec::H263,
+                },
+            );
+            video.preload_frame(
+                context,
+                swf::VideoFrame {
+                    stream_id: 1,
+                    frame_num: 1,
+                    data: vec![1],
+                },
+            );
+            let mut instance = video.instantiate(context.gc_context).as_video().unwrap();
+            instance.seek(context, 1);
+            let stream = decoded.borrow()[0].0;
+
+            instance.unload(context);
+            assert!(instance.removed());
+            assert!(instance.current_bitmap().is_none());
+            let encoded_frame = EncodedFrame {
+                codec: VideoCodec::H263,
+                data: &[1],
+                frame_id: 1,
+            };
+            assert!(context
+                .video
+                .decode_video_stream_frame(stream, encoded_frame, context.renderer)
+                .is_err());
+        });
+    }
+}
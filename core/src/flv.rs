@@ -0,0 +1,370 @@
+//! Demuxing of FLV files, as played by the AVM1 `NetStream` object.
+//!
+//! An FLV file is a header followed by a sequence of tags, each holding a packet of audio,
+//! a frame of video, or script data such as the `onMetaData` event. The audio and video
+//! use the same codecs as SWF files, so the packets are decoded by the audio and video
+//! backends like `SoundStreamBlock` and `VideoFrame` tags.
+pub mod amf0;
+
+use std::convert::TryInto;
+use swf::{AudioCompression, SoundFormat, VideoCodec};
+
+type Error = Box<dyn std::error::Error>;
+
+/// The length of the FLV header of version 1 files.
+const HEADER_LEN: usize = 9;
+
+/// The length of the header of each tag.
+const TAG_HEADER_LEN: usize = 11;
+
+/// A demuxed FLV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flv {
+    /// Whether the header says that the file has audio tags.
+    pub has_audio: bool,
+
+    /// Whether the header says that the file has video tags.
+    pub has_video: bool,
+
+    /// The tags of the file, in order.
+    pub tags: Vec<Tag>,
+}
+
+/// A tag of an FLV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    /// The time that the tag is played at, in milliseconds.
+    pub timestamp: u32,
+
+    pub data: TagData,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagData {
+    Audio(AudioTag),
+    Video(VideoTag),
+    Script(ScriptTag),
+}
+
+/// A packet of audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioTag {
+    /// The format of the audio, or `None` if it uses a codec that SWF files don't support,
+    /// such as AAC.
+    pub format: Option<SoundFormat>,
+
+    /// The encoded audio.
+    pub data: Vec<u8>,
+}
+
+/// A frame of video.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoTag {
+    /// Whether the frame can be decoded without the frames before it.
+    pub is_keyframe: bool,
+
+    /// The codec of the frame, or `None` if it uses a codec that SWF files don't support,
+    /// such as H.264.
+    pub codec: Option<VideoCodec>,
+
+    /// The encoded frame, in the same form as the data of a `VideoFrame` tag.
+    pub data: Vec<u8>,
+}
+
+/// A script data event, such as `onMetaData` or `onCuePoint`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptTag {
+    /// The name of the event.
+    pub name: String,
+
+    /// The argument of the event.
+    pub value: amf0::Value,
+}
+
+impl Flv {
+    /// Demuxes an FLV file.
+    ///
+    /// A truncated tag at the end of the data is ignored, so a file that is still loading
+    /// can be demuxed up to the last complete tag.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < HEADER_LEN || &data[..3] != b"FLV" {
+            return Err("Not an FLV file".into());
+        }
+        let flags = data[4];
+        let data_offset = u32::from_be_bytes(data[5..9].try_into().unwrap()) as usize;
+
+        // Each tag is preceded by the size of the previous tag.
+        let mut position = data_offset.max(HEADER_LEN) + 4;
+        let mut tags = vec![];
+        while position + TAG_HEADER_LEN <= data.len() {
+            let header = &data[position..position + TAG_HEADER_LEN];
+            // The top bits of the tag type are reserved or mark encrypted tags.
+            let tag_type = header[0] & 0x1f;
+            let len = u24(&header[1..4]) as usize;
+            let timestamp = u24(&header[4..7]) | (u32::from(header[7]) << 24);
+            let start = position + TAG_HEADER_LEN;
+            let end = start + len;
+            if end > data.len() {
+                break;
+            }
+            if let Some(data) = parse_tag_data(tag_type, &data[start..end])? {
+                tags.push(Tag { timestamp, data });
+            }
+            position = end + 4;
+        }
+
+        Ok(Self {
+            has_audio: flags & 0b100 != 0,
+            has_video: flags & 0b1 != 0,
+            tags,
+        })
+    }
+
+    /// Returns the value of the `onMetaData` event of the file, if any.
+    pub fn metadata(&self) -> Option<&amf0::Value> {
+        self.tags.iter().find_map(|tag| match &tag.data {
+            TagData::Script(script) if script.name == "onMetaData" => Some(&script.value),
+            _ => None,
+        })
+    }
+}
+
+/// Parses the data of a tag. Returns `None` for tags that are skipped, such as empty tags
+/// and the command frames of video.
+fn parse_tag_data(tag_type: u8, data: &[u8]) -> Result<Option<TagData>, Error> {
+    let tag_data = match tag_type {
+        8 if !data.is_empty() => TagData::Audio(parse_audio_tag(data)),
+        9 if !data.is_empty() => match parse_video_tag(data) {
+            Some(video) => TagData::Video(video),
+            None => return Ok(None),
+        },
+        18 => {
+            let mut reader = amf0::Reader::new(data);
+            let name = match reader.read_value()? {
+                amf0::Value::String(name) => name,
+                _ => return Err("Script data event has no name".into()),
+            };
+            let value = reader.read_value().unwrap_or(amf0::Value::Undefined);
+            TagData::Script(ScriptTag { name, value })
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(tag_data))
+}
+
+fn parse_audio_tag(data: &[u8]) -> AudioTag {
+    let flags = data[0];
+    let sample_rate = match (flags >> 2) & 0b11 {
+        0 => 5512,
+        1 => 11025,
+        2 => 22050,
+        _ => 44100,
+    };
+    let is_16_bit = flags & 0b10 != 0;
+    let is_stereo = flags & 0b1 != 0;
+    let (compression, sample_rate) = match flags >> 4 {
+        0 => (AudioCompression::UncompressedUnknownEndian, sample_rate),
+        1 => (AudioCompression::Adpcm, sample_rate),
+        2 => (AudioCompression::Mp3, sample_rate),
+        3 => (AudioCompression::Uncompressed, sample_rate),
+        4 => (AudioCompression::Nellymoser16Khz, 16000),
+        5 => (AudioCompression::Nellymoser8Khz, 8000),
+        6 => (AudioCompression::Nellymoser, sample_rate),
+        11 => (AudioCompression::Speex, 16000),
+        14 => (AudioCompression::Mp3, 8000),
+        format => {
+            log::warn!("FLV: Unsupported audio format {}", format);
+            return AudioTag {
+                format: None,
+                data: data[1..].to_vec(),
+            };
+        }
+    };
+    AudioTag {
+        format: Some(SoundFormat {
+            compression,
+            sample_rate,
+            is_16_bit,
+            is_stereo,
+        }),
+        data: data[1..].to_vec(),
+    }
+}
+
+fn parse_video_tag(data: &[u8]) -> Option<VideoTag> {
+    let frame_type = data[0] >> 4;
+    if frame_type == 5 {
+        // Video info and command frames have no picture.
+        return None;
+    }
+    let (codec, data) = match data[0] & 0xf {
+        2 => (Some(VideoCodec::H263), &data[1..]),
+        3 => (Some(VideoCodec::ScreenVideo), &data[1..]),
+        // VP6 frames start with the number of pixels to crop from the decoded picture,
+        // which isn't needed when the size of the video is known.
+        4 => (Some(VideoCodec::VP6), data.get(2..).unwrap_or_default()),
+        5 => (
+            Some(VideoCodec::VP6WithAlpha),
+            data.get(2..).unwrap_or_default(),
+        ),
+        6 => (Some(VideoCodec::ScreenVideoV2), &data[1..]),
+        codec => {
+            log::warn!("FLV: Unsupported video codec {}", codec);
+            (None, &data[1..])
+        }
+    };
+    Some(VideoTag {
+        is_keyframe: frame_type == 1 || frame_type == 4,
+        codec,
+        data: data.to_vec(),
+    })
+}
+
+fn u24(bytes: &[u8]) -> u32 {
+    (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(flags: u8) -> Vec<u8> {
+        vec![b'F', b'L', b'V', 1, flags, 0, 0, 0, 9, 0, 0, 0, 0]
+    }
+
+    fn tag(tag_type: u8, timestamp: u32, data: &[u8]) -> Vec<u8> {
+        let len = data.len() as u32;
+        let mut tag = vec![
+            tag_type,
+            (len >> 16) as u8,
+            (len >> 8) as u8,
+            len as u8,
+            (timestamp >> 16) as u8,
+            (timestamp >> 8) as u8,
+            timestamp as u8,
+            (timestamp >> 24) as u8,
+            0,
+            0,
+            0,
+        ];
+        tag.extend_from_slice(data);
+        tag.extend_from_slice(&(len + TAG_HEADER_LEN as u32).to_be_bytes());
+        tag
+    }
+
+    #[test]
+    fn parse_header() {
+        let flv = Flv::parse(&header(0b101)).unwrap();
+        assert!(flv.has_audio);
+        assert!(flv.has_video);
+        assert!(flv.tags.is_empty());
+
+        assert!(Flv::parse(b"FWS\x01\x05\0\0\0\x09").is_err());
+        assert!(Flv::parse(b"FLV").is_err());
+    }
+
+    #[test]
+    fn parse_audio_and_video_tags() {
+        let mut data = header(0b101);
+        // MP3, 44kHz, 16-bit, stereo.
+        data.extend(tag(8, 0, &[0x2f, 1, 2, 3]));
+        // H.263 keyframe.
+        data.extend(tag(9, 0, &[0x12, 4, 5]));
+        // VP6 inter frame with the crop byte.
+        data.extend(tag(9, 0x0100_0021, &[0x24, 0x00, 6]));
+        // Command frame.
+        data.extend(tag(9, 66, &[0x52, 0]));
+        let flv = Flv::parse(&data).unwrap();
+
+        assert_eq!(
+            flv.tags,
+            vec![
+                Tag {
+                    timestamp: 0,
+                    data: TagData::Audio(AudioTag {
+                        format: Some(SoundFormat {
+                            compression: AudioCompression::Mp3,
+                            sample_rate: 44100,
+                            is_16_bit: true,
+                            is_stereo: true,
+                        }),
+                        data: vec![1, 2, 3],
+                    }),
+                },
+                Tag {
+                    timestamp: 0,
+                    data: TagData::Video(VideoTag {
+                        is_keyframe: true,
+                        codec: Some(VideoCodec::H263),
+                        data: vec![4, 5],
+                    }),
+                },
+                Tag {
+                    timestamp: 0x0100_0021,
+                    data: TagData::Video(VideoTag {
+                        is_keyframe: false,
+                        codec: Some(VideoCodec::VP6),
+                        data: vec![6],
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_unsupported_codecs() {
+        let mut data = header(0b101);
+        // AAC.
+        data.extend(tag(8, 0, &[0xaf, 1, 0]));
+        // H.264.
+        data.extend(tag(9, 0, &[0x17, 1]));
+        let flv = Flv::parse(&data).unwrap();
+        assert_eq!(
+            flv.tags[0].data,
+            TagData::Audio(AudioTag {
+                format: None,
+                data: vec![1, 0],
+            })
+        );
+        assert_eq!(
+            flv.tags[1].data,
+            TagData::Video(VideoTag {
+                is_keyframe: true,
+                codec: None,
+                data: vec![1],
+            })
+        );
+    }
+
+    #[test]
+    fn parse_metadata() {
+        let mut script = vec![2, 0, 10];
+        script.extend_from_slice(b"onMetaData");
+        script.extend_from_slice(&[8, 0, 0, 0, 1, 0, 8]);
+        script.extend_from_slice(b"duration");
+        script.push(0);
+        script.extend_from_slice(&2.5f64.to_be_bytes());
+        script.extend_from_slice(&[0, 0, 9]);
+
+        let mut data = header(0b1);
+        data.extend(tag(18, 0, &script));
+        let flv = Flv::parse(&data).unwrap();
+        assert_eq!(
+            flv.metadata(),
+            Some(&amf0::Value::EcmaArray(vec![(
+                "duration".to_string(),
+                amf0::Value::Number(2.5)
+            )]))
+        );
+    }
+
+    #[test]
+    fn parse_truncated_file() {
+        let mut data = header(0b100);
+        data.extend(tag(8, 0, &[0x22, 1]));
+        data.extend(tag(8, 26, &[0x22, 2, 3, 4]));
+        data.truncate(data.len() - 6);
+        let flv = Flv::parse(&data).unwrap();
+        assert_eq!(flv.tags.len(), 1);
+    }
+}
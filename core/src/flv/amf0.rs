@@ -0,0 +1,202 @@
+//! Reading of AMF0, the serialization format of the script data of FLV files.
+use std::convert::TryInto;
+
+type Error = Box<dyn std::error::Error>;
+
+/// An AMF0 value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+    String(String),
+
+    /// An anonymous object, with its properties in order.
+    Object(Vec<(String, Value)>),
+
+    Null,
+    Undefined,
+
+    /// An associative array, with its properties in order.
+    /// The `onMetaData` event of most files is an associative array.
+    EcmaArray(Vec<(String, Value)>),
+
+    StrictArray(Vec<Value>),
+
+    /// A date, in milliseconds since the Unix epoch.
+    Date(f64),
+}
+
+impl Value {
+    /// Returns the value of the given property of an object or associative array.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        match self {
+            Value::Object(properties) | Value::EcmaArray(properties) => properties
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+/// Reads AMF0 values from a buffer.
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn read_value(&mut self) -> Result<Value, Error> {
+        let value = match self.read_u8()? {
+            0 => Value::Number(self.read_f64()?),
+            1 => Value::Bool(self.read_u8()? != 0),
+            2 => Value::String(self.read_string()?),
+            3 => Value::Object(self.read_properties()?),
+            5 => Value::Null,
+            6 => Value::Undefined,
+            8 => {
+                // The count of an associative array is only a hint;
+                // the properties end with an end marker like an object.
+                self.read_u32()?;
+                Value::EcmaArray(self.read_properties()?)
+            }
+            10 => {
+                let len = self.read_u32()?;
+                let mut values = Vec::with_capacity(len.min(1024) as usize);
+                for _ in 0..len {
+                    values.push(self.read_value()?);
+                }
+                Value::StrictArray(values)
+            }
+            11 => {
+                let time = self.read_f64()?;
+                // The time zone is reserved and should be ignored.
+                self.read_bytes(2)?;
+                Value::Date(time)
+            }
+            12 => {
+                let len = self.read_u32()? as usize;
+                let bytes = self.read_bytes(len)?;
+                Value::String(String::from_utf8_lossy(bytes).into_owned())
+            }
+            marker => return Err(format!("Unsupported AMF0 type {}", marker).into()),
+        };
+        Ok(value)
+    }
+
+    /// Reads the properties of an object, up to the end marker.
+    fn read_properties(&mut self) -> Result<Vec<(String, Value)>, Error> {
+        let mut properties = vec![];
+        loop {
+            let name = self.read_string()?;
+            if name.is_empty() && self.data.first() == Some(&9) {
+                self.read_u8()?;
+                return Ok(properties);
+            }
+            let value = self.read_value()?;
+            properties.push((name, value));
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, Error> {
+        let len = self.read_u16()?.into();
+        let bytes = self.read_bytes(len)?;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.data.len() {
+            return Err("Unexpected end of AMF0 data".into());
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Error> {
+        Ok(f64::from_be_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &[u8]) -> Result<Value, Error> {
+        Reader::new(data).read_value()
+    }
+
+    #[test]
+    fn read_primitives() {
+        let mut number = vec![0];
+        number.extend_from_slice(&(-1.5f64).to_be_bytes());
+        assert_eq!(read(&number).unwrap(), Value::Number(-1.5));
+        assert_eq!(read(&[1, 1]).unwrap(), Value::Bool(true));
+        assert_eq!(
+            read(&[2, 0, 3, b'a', b'b', b'c']).unwrap(),
+            Value::String("abc".to_string())
+        );
+        assert_eq!(
+            read(&[12, 0, 0, 0, 2, b'h', b'i']).unwrap(),
+            Value::String("hi".to_string())
+        );
+        assert_eq!(read(&[5]).unwrap(), Value::Null);
+        assert_eq!(read(&[6]).unwrap(), Value::Undefined);
+
+        let mut date = vec![11];
+        date.extend_from_slice(&1000.0f64.to_be_bytes());
+        date.extend_from_slice(&[0, 0]);
+        assert_eq!(read(&date).unwrap(), Value::Date(1000.0));
+    }
+
+    #[test]
+    fn read_objects() {
+        let data = [
+            3, 0, 1, b'a', 1, 0, 0, 1, b'b', 10, 0, 0, 0, 2, 5, 6, 0, 0, 9,
+        ];
+        let value = read(&data).unwrap();
+        assert_eq!(
+            value,
+            Value::Object(vec![
+                ("a".to_string(), Value::Bool(false)),
+                (
+                    "b".to_string(),
+                    Value::StrictArray(vec![Value::Null, Value::Undefined])
+                ),
+            ])
+        );
+        assert_eq!(value.get("a"), Some(&Value::Bool(false)));
+        assert_eq!(value.get("c"), None);
+    }
+
+    #[test]
+    fn read_invalid_data() {
+        assert!(read(&[]).is_err());
+        assert!(read(&[0, 1, 2]).is_err());
+        assert!(read(&[2, 0, 5, b'a']).is_err());
+        assert!(read(&[3, 0, 1, b'a', 5]).is_err());
+        assert!(read(&[13]).is_err());
+    }
+}
@@ -1,6 +1,12 @@
 use crate::backend::render::{RenderBackend, ShapeHandle};
 use crate::prelude::*;
 use gc_arena::{Collect, Gc, MutationContext};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use swf::Codepage;
+
+pub(crate) mod opentype;
 
 type Error = Box<dyn std::error::Error>;
 
@@ -19,6 +25,9 @@ struct FontData {
     /// Used by `DefineEditText` tags.
     code_point_to_glyph: fnv::FnvHashMap<u16, usize>,
 
+    /// The glyphs of a font created from OpenType data, which are used instead of `glyphs`.
+    opentype_glyphs: Option<Rc<OpenTypeGlyphs>>,
+
     /// The scaling applied to the font height to render at the proper size.
     /// This depends on the DefineFont tag version.
     scale: f32,
@@ -26,9 +35,128 @@ struct FontData {
     /// Kerning infomration.
     /// Maps from a pair of unicode code points to horizontal offset value.
     kerning_pairs: fnv::FnvHashMap<(u16, u16), Twips>,
+
+    /// The distance from the top of each glyph to the baseline.
+    ascent: u16,
+
+    /// The distance from the baseline to the bottom of each glyph.
+    descent: u16,
+
+    /// The name of the font, used to find a matching device font.
+    name: String,
+
+    /// Whether this is a bold font.
+    is_bold: bool,
+
+    /// Whether this is an italic font.
+    is_italic: bool,
 }
 
 impl<'gc> Font<'gc> {
+    /// Creates a font from a `DefineFont4` tag by converting its embedded OpenType outlines.
+    /// Fonts without usable font data have no glyphs, so text using them will fall back to a
+    /// device font.
+    pub fn from_font4_tag(
+        gc_context: MutationContext<'gc, '_>,
+        renderer: &mut dyn RenderBackend,
+        tag: &swf::Font4,
+    ) -> Result<Font<'gc>, Error> {
+        let opentype_font = match tag
+            .data
+            .as_ref()
+            .map(|data| opentype::OpenTypeFont::parse(data.clone(), 0))
+        {
+            Some(Ok(opentype_font)) => opentype_font,
+            Some(Err(e)) => {
+                log::warn!("Unable to parse OpenType font {}: {}", tag.name, e);
+                return Self::from_swf_tag(gc_context, renderer, &Self::empty_font4(tag));
+            }
+            None => return Self::from_swf_tag(gc_context, renderer, &Self::empty_font4(tag)),
+        };
+        let kerning = opentype_font.kerning();
+        Ok(Self::from_opentype(
+            gc_context,
+            opentype_font,
+            kerning,
+            &tag.name,
+            tag.is_bold,
+            tag.is_italic,
+        ))
+    }
+
+    /// Creates a device font from the data of an installed TrueType or OpenType font.
+    /// `index` is the index of the font in a font collection, or 0.
+    ///
+    /// Flash doesn't kern device text by default, so the kerning of the font isn't read.
+    pub fn from_device_font_data(
+        gc_context: MutationContext<'gc, '_>,
+        data: Vec<u8>,
+        index: u32,
+        name: &str,
+        is_bold: bool,
+        is_italic: bool,
+    ) -> Result<Font<'gc>, Error> {
+        let opentype_font = opentype::OpenTypeFont::parse(data, index)?;
+        Ok(Self::from_opentype(
+            gc_context,
+            opentype_font,
+            vec![],
+            name,
+            is_bold,
+            is_italic,
+        ))
+    }
+
+    /// Creates a font from a parsed OpenType font.
+    /// The glyphs are registered with the renderer when they are first used.
+    fn from_opentype(
+        gc_context: MutationContext<'gc, '_>,
+        opentype_font: opentype::OpenTypeFont,
+        kerning: Vec<swf::KerningRecord>,
+        name: &str,
+        is_bold: bool,
+        is_italic: bool,
+    ) -> Font<'gc> {
+        Font(Gc::allocate(
+            gc_context,
+            FontData {
+                glyphs: vec![],
+                code_point_to_glyph: fnv::FnvHashMap::default(),
+                scale: opentype::EM_SQUARE as f32,
+                kerning_pairs: kerning
+                    .into_iter()
+                    .map(|kerning| ((kerning.left_code, kerning.right_code), kerning.adjustment))
+                    .collect(),
+                ascent: opentype_font.ascent,
+                descent: opentype_font.descent,
+                name: name.trim_end_matches('\0').to_string(),
+                is_bold,
+                is_italic,
+                opentype_glyphs: Some(Rc::new(OpenTypeGlyphs {
+                    font: opentype_font,
+                    glyphs: RefCell::new(fnv::FnvHashMap::default()),
+                })),
+            },
+        ))
+    }
+
+    /// A DefineFont3 tag without glyphs, for a `DefineFont4` tag without usable font data.
+    fn empty_font4(tag: &swf::Font4) -> swf::Font {
+        swf::Font {
+            version: 3,
+            id: tag.id,
+            name: tag.name.clone(),
+            language: swf::Language::Unknown,
+            layout: None,
+            glyphs: vec![],
+            is_small_text: false,
+            is_shift_jis: false,
+            is_ansi: false,
+            is_bold: tag.is_bold,
+            is_italic: tag.is_italic,
+        }
+    }
+
     pub fn from_swf_tag(
         gc_context: MutationContext<'gc, '_>,
         renderer: &mut dyn RenderBackend,
@@ -54,24 +182,89 @@ impl<'gc> Font<'gc> {
         } else {
             fnv::FnvHashMap::default()
         };
+
+        // DefineFont3 stores coordinates at 20x the scale of DefineFont1/2.
+        // (SWF19 p.164)
+        let scale = if tag.version >= 3 { 20480.0 } else { 1024.0 };
+
+        // Fonts without layout info (such as DefineFont1) use a typical
+        // 80%/20% split of the EM square for ascent/descent.
+        let (ascent, descent) = if let Some(layout) = &tag.layout {
+            (layout.ascent, layout.descent)
+        } else {
+            ((scale * 0.8) as u16, (scale * 0.2) as u16)
+        };
         Ok(Font(Gc::allocate(
             gc_context,
             FontData {
                 glyphs,
                 code_point_to_glyph,
-
-                /// DefineFont3 stores coordinates at 20x the scale of DefineFont1/2.
-                /// (SWF19 p.164)
-                scale: if tag.version >= 3 { 20480.0 } else { 1024.0 },
+                scale,
                 kerning_pairs,
+                ascent,
+                descent,
+                // Font names may be null-terminated.
+                name: tag.name.trim_end_matches('\0').to_string(),
+                is_bold: tag.is_bold,
+                is_italic: tag.is_italic,
+                opentype_glyphs: None,
             },
         )))
     }
 
+    /// Converts the character codes of a `DefineFont2` tag from SWF 5 and earlier to Unicode.
+    ///
+    /// These fonts store codes in the codepage given by the font's flags, or in the
+    /// codepage of the playing system if neither flag is set. Double-byte codes are
+    /// stored as `lead_byte << 8 | trail_byte`.
+    pub fn convert_legacy_codes(tag: &mut swf::Font, system_codepage: Codepage) {
+        let codepage = if tag.is_shift_jis {
+            Codepage::ShiftJis
+        } else if tag.is_ansi {
+            Codepage::Windows1252
+        } else {
+            system_codepage
+        };
+        let to_unicode = |code: u16| {
+            codepage
+                .decode_char(code)
+                .and_then(|c| u16::try_from(u32::from(c)).ok())
+                .unwrap_or(code)
+        };
+        for glyph in &mut tag.glyphs {
+            glyph.code = to_unicode(glyph.code);
+        }
+        if let Some(layout) = &mut tag.layout {
+            for kerning in &mut layout.kerning {
+                kerning.left_code = to_unicode(kerning.left_code);
+                kerning.right_code = to_unicode(kerning.right_code);
+            }
+        }
+    }
+
+    /// Returns a copy of this font with the given name and style.
+    /// Used by `DefineFontInfo` and `DefineFontName` tags, which describe a previously defined font.
+    pub fn with_name_and_style(
+        self,
+        gc_context: MutationContext<'gc, '_>,
+        name: &str,
+        is_bold: bool,
+        is_italic: bool,
+    ) -> Font<'gc> {
+        let mut data = (*self.0).clone();
+        data.name = name.trim_end_matches('\0').to_string();
+        data.is_bold = is_bold;
+        data.is_italic = is_italic;
+        Font(Gc::allocate(gc_context, data))
+    }
+
     /// Returns whether this font contains glyph shapes.
     /// If not, this font should be rendered as a device font.
     pub fn has_glyphs(self) -> bool {
-        !self.0.glyphs.is_empty()
+        match &self.0.opentype_glyphs {
+            Some(opentype_glyphs) => opentype_glyphs.font.has_glyphs(),
+            None => !self.0.glyphs.is_empty(),
+        }
     }
 
     /// Returns a glyph entry by index.
@@ -82,9 +275,15 @@ impl<'gc> Font<'gc> {
 
     /// Returns a glyph entry by character.
     /// Used by `EditText` display objects.
-    pub fn get_glyph_for_char(self, c: char) -> Option<Glyph> {
-        // TODO: Properly handle UTF-16/out-of-bounds code points.
-        let code_point = c as u16;
+    ///
+    /// The glyphs of fonts created from OpenType data are registered with the renderer
+    /// the first time they are used.
+    pub fn get_glyph_for_char(self, renderer: &mut dyn RenderBackend, c: char) -> Option<Glyph> {
+        // Font code tables only contain characters in the Basic Multilingual Plane.
+        let code_point = u16::try_from(u32::from(c)).ok()?;
+        if let Some(opentype_glyphs) = &self.0.opentype_glyphs {
+            return opentype_glyphs.glyph(renderer, code_point);
+        }
         if let Some(index) = self.0.code_point_to_glyph.get(&code_point) {
             self.get_glyph(*index)
         } else {
@@ -92,18 +291,32 @@ impl<'gc> Font<'gc> {
         }
     }
 
+    /// Returns the advance of the glyph for a character, without registering the glyph.
+    pub fn get_advance_for_char(self, c: char) -> Option<i16> {
+        let code_point = u16::try_from(u32::from(c)).ok()?;
+        if let Some(opentype_glyphs) = &self.0.opentype_glyphs {
+            return opentype_glyphs.font.advance(code_point);
+        }
+        let index = self.0.code_point_to_glyph.get(&code_point)?;
+        self.0.glyphs.get(*index).map(|glyph| glyph.advance)
+    }
+
     /// Given a pair of characters, applies the offset that should be applied
     /// to the advance value between these two characters.
     /// Returns 0 twips if no kerning offset exists between these two characters.
     pub fn get_kerning_offset(self, left: char, right: char) -> Twips {
-        // TODO: Properly handle UTF-16/out-of-bounds code points.
-        let left_code_point = left as u16;
-        let right_code_point = right as u16;
-        self.0
-            .kerning_pairs
-            .get(&(left_code_point, right_code_point))
-            .cloned()
-            .unwrap_or_default()
+        match (
+            u16::try_from(u32::from(left)),
+            u16::try_from(u32::from(right)),
+        ) {
+            (Ok(left_code_point), Ok(right_code_point)) => self
+                .0
+                .kerning_pairs
+                .get(&(left_code_point, right_code_point))
+                .cloned()
+                .unwrap_or_default(),
+            _ => Twips::default(),
+        }
     }
 
     /// Returns whether this font contains kerning information.
@@ -114,6 +327,29 @@ impl<'gc> Font<'gc> {
     pub fn scale(self) -> f32 {
         self.0.scale
     }
+
+    /// The distance from the top of a line to its baseline, in font units.
+    pub fn ascent(self) -> u16 {
+        self.0.ascent
+    }
+
+    /// The distance from the baseline to the bottom of a line, in font units.
+    pub fn descent(self) -> u16 {
+        self.0.descent
+    }
+
+    /// The name of this font.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    pub fn is_bold(self) -> bool {
+        self.0.is_bold
+    }
+
+    pub fn is_italic(self) -> bool {
+        self.0.is_italic
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -121,3 +357,39 @@ pub struct Glyph {
     pub shape: ShapeHandle,
     pub advance: i16,
 }
+
+/// The glyphs of a font created from OpenType data.
+///
+/// Device fonts may contain thousands of glyphs, so each glyph is converted and
+/// registered with the renderer the first time it is used.
+struct OpenTypeGlyphs {
+    font: opentype::OpenTypeFont,
+
+    /// The glyphs registered so far, by code point.
+    /// `None` if the font has no glyph for the code point.
+    glyphs: RefCell<fnv::FnvHashMap<u16, Option<Glyph>>>,
+}
+
+impl OpenTypeGlyphs {
+    fn glyph(&self, renderer: &mut dyn RenderBackend, code_point: u16) -> Option<Glyph> {
+        self.glyphs
+            .borrow_mut()
+            .entry(code_point)
+            .or_insert_with(|| {
+                let swf_glyph = self.font.glyph(code_point)?;
+                Some(Glyph {
+                    shape: renderer.register_glyph_shape(&swf_glyph),
+                    advance: swf_glyph.advance.unwrap_or(0),
+                })
+            })
+            .clone()
+    }
+}
+
+impl std::fmt::Debug for OpenTypeGlyphs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenTypeGlyphs")
+            .field("font", &self.font)
+            .finish()
+    }
+}
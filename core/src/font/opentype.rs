@@ -0,0 +1,2249 @@
+//! Parsing of the OpenType font data embedded in `DefineFont4` tags and of installed device fonts.
+//!
+//! Flash embeds CFF-flavored OpenType fonts, but TrueType outlines are also accepted.
+//! The glyph outlines are converted into SWF glyph shapes in the coordinate space of
+//! `DefineFont3` (an EM square of 1024 * 20 units), so that they work like any other font.
+
+use crate::prelude::*;
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, SeekFrom};
+
+type Error = Box<dyn std::error::Error>;
+
+/// The size of the EM square of the converted glyphs, matching `DefineFont3`.
+pub const EM_SQUARE: f64 = 20480.0;
+
+/// The nesting limit of CFF subroutines and TrueType composite glyphs.
+const MAX_DEPTH: u32 = 10;
+
+/// A parsed OpenType font.
+///
+/// Glyph outlines are only converted into SWF glyphs when requested, as device fonts
+/// may contain thousands of glyphs.
+pub struct OpenTypeFont {
+    /// The data of the font file.
+    data: Vec<u8>,
+
+    /// The offset of the font's table directory, which is nonzero in a font collection.
+    offset: usize,
+
+    /// The scale from font units to `EM_SQUARE` units.
+    scale: f64,
+
+    num_glyphs: u16,
+
+    /// A map from each code point in the Basic Multilingual Plane to its glyph ID.
+    code_points: BTreeMap<u16, u16>,
+
+    metrics: HorizontalMetrics,
+
+    /// The distance from the baseline to the top of a line, in `EM_SQUARE` units.
+    pub ascent: u16,
+
+    /// The distance from the baseline to the bottom of a line, in `EM_SQUARE` units.
+    pub descent: u16,
+
+    /// The extra space between lines, in `EM_SQUARE` units.
+    pub leading: i16,
+}
+
+impl OpenTypeFont {
+    /// Parses an OpenType font, or the font with the given index of a font collection.
+    pub fn parse(data: Vec<u8>, index: u32) -> Result<Self, Error> {
+        let font = Sfnt::parse(&data, index)?;
+        let offset = font.offset;
+
+        let head = font.table(b"head")?;
+        let units_per_em = match head.u16(18)? {
+            0 => 1000,
+            n => n,
+        };
+        let scale = EM_SQUARE / f64::from(units_per_em);
+
+        let num_glyphs = font.table(b"maxp")?.u16(4)?;
+        let hhea = font.table(b"hhea")?;
+        let metrics = HorizontalMetrics::parse(font.table(b"hmtx")?, hhea.u16(34)?)?;
+        let ascent = f64::from(hhea.i16(4)?);
+        let descent = -f64::from(hhea.i16(6)?);
+        let line_gap = f64::from(hhea.i16(8)?);
+
+        let mut code_points = parse_cmap(font.table(b"cmap")?)?;
+        code_points.retain(|_, glyph_id| *glyph_id < num_glyphs);
+
+        // Make sure that the outlines can be read.
+        Outlines::parse(&font, num_glyphs)?;
+
+        Ok(Self {
+            data,
+            offset,
+            scale,
+            num_glyphs,
+            code_points,
+            metrics,
+            ascent: saturate_i16(ascent * scale).max(0) as u16,
+            descent: saturate_i16(descent * scale).max(0) as u16,
+            leading: saturate_i16(line_gap * scale),
+        })
+    }
+
+    /// Returns whether the font has a glyph for any character.
+    pub fn has_glyphs(&self) -> bool {
+        !self.code_points.is_empty()
+    }
+
+    /// The advance of the glyph for a character, in `EM_SQUARE` units.
+    pub fn advance(&self, code: u16) -> Option<i16> {
+        let glyph_id = *self.code_points.get(&code)?;
+        Some(self.glyph_advance(glyph_id))
+    }
+
+    /// Converts the outline of the glyph for a character into a SWF glyph.
+    pub fn glyph(&self, code: u16) -> Option<swf::Glyph> {
+        let glyph_id = *self.code_points.get(&code)?;
+        let outlines = Outlines::parse(&self.sfnt().ok()?, self.num_glyphs).ok()?;
+        Some(swf::Glyph {
+            shape_records: self.shape_records(&outlines, glyph_id),
+            code,
+            advance: Some(self.glyph_advance(glyph_id)),
+            bounds: None,
+        })
+    }
+
+    /// Reads the kerning adjustments between pairs of characters, in `EM_SQUARE` units.
+    pub fn kerning(&self) -> Vec<swf::KerningRecord> {
+        let pairs = self
+            .sfnt()
+            .and_then(|font| parse_kerning(&font, &self.code_points));
+        match pairs {
+            Ok(pairs) => pairs
+                .into_iter()
+                .map(|((left_code, right_code), adjustment)| swf::KerningRecord {
+                    left_code,
+                    right_code,
+                    adjustment: Twips::new(saturate_i16(
+                        (f64::from(adjustment) * self.scale).round(),
+                    )),
+                })
+                .collect(),
+            Err(e) => {
+                log::warn!("Unable to parse kerning: {}", e);
+                vec![]
+            }
+        }
+    }
+
+    fn sfnt(&self) -> Result<Sfnt<'_>, Error> {
+        Sfnt {
+            data: Data(&self.data),
+            offset: 0,
+        }
+        .at(self.offset)
+    }
+
+    fn glyph_advance(&self, glyph_id: u16) -> i16 {
+        saturate_i16((f64::from(self.metrics.advance(glyph_id)) * self.scale).round())
+    }
+
+    fn shape_records(&self, outlines: &Outlines, glyph_id: u16) -> Vec<swf::ShapeRecord> {
+        let outline = match outlines.outline(glyph_id) {
+            Ok(outline) => outline,
+            Err(e) => {
+                log::warn!("Unable to parse outline of glyph {}: {}", glyph_id, e);
+                Outline::default()
+            }
+        };
+        outline.into_shape_records(self.scale)
+    }
+}
+
+impl std::fmt::Debug for OpenTypeFont {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenTypeFont")
+            .field("num_glyphs", &self.num_glyphs)
+            .field("ascent", &self.ascent)
+            .field("descent", &self.descent)
+            .field("leading", &self.leading)
+            .finish()
+    }
+}
+
+/// The glyph outlines of a font, from either the `CFF ` or the `glyf` table.
+enum Outlines<'a> {
+    Cff(Cff<'a>),
+    TrueType(TrueTypeOutlines<'a>),
+}
+
+impl<'a> Outlines<'a> {
+    fn parse(font: &Sfnt<'a>, num_glyphs: u16) -> Result<Self, Error> {
+        if let Ok(cff) = font.table(b"CFF ") {
+            Ok(Outlines::Cff(Cff::parse(cff)?))
+        } else {
+            Ok(Outlines::TrueType(TrueTypeOutlines::parse(
+                font, num_glyphs,
+            )?))
+        }
+    }
+
+    fn outline(&self, glyph_id: u16) -> Result<Outline, Error> {
+        match self {
+            Outlines::Cff(cff) => cff.outline(glyph_id),
+            Outlines::TrueType(glyf) => glyf.outline(glyph_id),
+        }
+    }
+}
+
+/// The family name and style of a font.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontFace {
+    pub family: String,
+    pub is_bold: bool,
+    pub is_italic: bool,
+}
+
+/// Reads the family names and styles of the fonts in a font file.
+///
+/// Only the table directories and the `name`, `OS/2` and `head` tables are read, so that
+/// the installed fonts can be indexed without reading them in full. A font collection
+/// contains several fonts, which are returned in the order of their indices.
+pub fn read_font_faces<R: Read + Seek>(reader: &mut R) -> Result<Vec<FontFace>, Error> {
+    let mut header = [0; 12];
+    reader.read_exact(&mut header)?;
+    let header = Data(&header);
+    let offsets = if header.get(0, 4)? == b"ttcf" {
+        let num_fonts = header.u32(8)?;
+        let mut offsets = vec![];
+        for _ in 0..num_fonts {
+            let mut offset = [0; 4];
+            reader.read_exact(&mut offset)?;
+            offsets.push(u32::from_be_bytes(offset));
+        }
+        offsets
+    } else {
+        vec![0]
+    };
+
+    let mut faces = vec![];
+    for offset in offsets {
+        reader.seek(SeekFrom::Start(offset.into()))?;
+        let mut directory = vec![0; 12];
+        reader.read_exact(&mut directory)?;
+        check_sfnt_version(Data(&directory))?;
+        let num_tables = Data(&directory).u16(4)?;
+        directory.resize(12 + 16 * usize::from(num_tables), 0);
+        reader.read_exact(&mut directory[12..])?;
+
+        let mut tables = BTreeMap::new();
+        for tag in &[b"name", b"OS/2", b"head"] {
+            if let Some((offset, len)) = find_table(Data(&directory), 0, tag)? {
+                let mut table = vec![0; len];
+                reader.seek(SeekFrom::Start(offset as u64))?;
+                reader.read_exact(&mut table)?;
+                tables.insert(*tag, table);
+            }
+        }
+        let table = |tag: &[u8; 4]| {
+            tables.get(tag).map(|table| Data(table)).ok_or_else(|| {
+                Error::from(format!("Missing '{}' table", String::from_utf8_lossy(tag)))
+            })
+        };
+        faces.push(font_face(table(b"name")?, table(b"OS/2"), table(b"head"))?);
+    }
+    Ok(faces)
+}
+
+/// Reads the family name and style of a font from its `name`, `OS/2` and `head` tables.
+fn font_face(
+    name: Data,
+    os2: Result<Data, Error>,
+    head: Result<Data, Error>,
+) -> Result<FontFace, Error> {
+    // Prefer the style bits of the OS/2 table, and fall back to the `head` table.
+    let (is_bold, is_italic) = match os2.and_then(|os2| os2.u16(62)) {
+        Ok(fs_selection) => (fs_selection & 0b10_0000 != 0, fs_selection & 0b1 != 0),
+        Err(_) => {
+            let mac_style = head?.u16(44)?;
+            (mac_style & 0b1 != 0, mac_style & 0b10 != 0)
+        }
+    };
+
+    // Find the font family name (name ID 1), preferring the US English Windows name.
+    let count = usize::from(name.u16(2)?);
+    let strings = name.slice_from(usize::from(name.u16(4)?))?;
+    let mut best = None;
+    for i in 0..count {
+        let record = name.slice(6 + 12 * i, 12)?;
+        if record.u16(6)? != 1 {
+            continue;
+        }
+        let (platform_id, language_id) = (record.u16(0)?, record.u16(4)?);
+        let priority = match (platform_id, language_id) {
+            (3, 0x409) => 3,
+            (3, _) | (0, _) => 2,
+            (1, 0) => 1,
+            _ => continue,
+        };
+        if best.map(|(p, _, _)| priority > p).unwrap_or(true) {
+            let bytes = strings.get(record.u16(10)?.into(), record.u16(8)?.into())?;
+            best = Some((priority, platform_id, bytes));
+        }
+    }
+    let family = match best.ok_or("Missing font family name")? {
+        // Windows and Unicode names are UTF-16.
+        (_, 0, bytes) | (_, 3, bytes) => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        // Mac names are Mac Roman, which matches ASCII for typical names.
+        (_, _, bytes) => bytes.iter().map(|&b| char::from(b)).collect(),
+    };
+
+    Ok(FontFace {
+        family,
+        is_bold,
+        is_italic,
+    })
+}
+
+fn saturate_i16(n: f64) -> i16 {
+    if n >= f64::from(std::i16::MAX) {
+        std::i16::MAX
+    } else if n <= f64::from(std::i16::MIN) {
+        std::i16::MIN
+    } else {
+        n as i16
+    }
+}
+
+/// A bounds-checked view of big-endian font data.
+#[derive(Clone, Copy)]
+struct Data<'a>(&'a [u8]);
+
+impl<'a> Data<'a> {
+    fn get(self, offset: usize, len: usize) -> Result<&'a [u8], Error> {
+        self.0
+            .get(offset..offset.checked_add(len).ok_or("Invalid offset")?)
+            .ok_or_else(|| "Unexpected end of font data".into())
+    }
+
+    fn slice(self, offset: usize, len: usize) -> Result<Data<'a>, Error> {
+        self.get(offset, len).map(Data)
+    }
+
+    fn slice_from(self, offset: usize) -> Result<Data<'a>, Error> {
+        self.0
+            .get(offset..)
+            .map(Data)
+            .ok_or_else(|| "Unexpected end of font data".into())
+    }
+
+    fn u8(self, offset: usize) -> Result<u8, Error> {
+        Ok(self.get(offset, 1)?[0])
+    }
+
+    fn u16(self, offset: usize) -> Result<u16, Error> {
+        let bytes = self.get(offset, 2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(self, offset: usize) -> Result<i16, Error> {
+        Ok(self.u16(offset)? as i16)
+    }
+
+    fn u32(self, offset: usize) -> Result<u32, Error> {
+        let bytes = self.get(offset, 4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Reads an unsigned integer of 1 to 4 bytes.
+    fn uint(self, offset: usize, size: u8) -> Result<u32, Error> {
+        let bytes = self.get(offset, size.into())?;
+        Ok(bytes.iter().fold(0, |n, &b| (n << 8) | u32::from(b)))
+    }
+}
+
+/// The table directory of an OpenType file.
+struct Sfnt<'a> {
+    data: Data<'a>,
+
+    /// The offset of the table directory, which is nonzero in a font collection.
+    offset: usize,
+}
+
+impl<'a> Sfnt<'a> {
+    /// Parses the table directory of an OpenType file, or of the font with the given index
+    /// of a font collection.
+    fn parse(data: &'a [u8], index: u32) -> Result<Self, Error> {
+        let data = Data(data);
+        if data.get(0, 4)? == b"ttcf" {
+            if index >= data.u32(8)? {
+                return Err("Invalid font collection index".into());
+            }
+            let offset = data.u32(12 + 4 * index as usize)? as usize;
+            Self { data, offset: 0 }.at(offset)
+        } else {
+            Self { data, offset: 0 }.at(0)
+        }
+    }
+
+    /// Returns the font whose table directory is at the given offset.
+    fn at(self, offset: usize) -> Result<Self, Error> {
+        check_sfnt_version(self.data.slice_from(offset)?)?;
+        Ok(Self {
+            data: self.data,
+            offset,
+        })
+    }
+
+    fn table(&self, tag: &[u8; 4]) -> Result<Data<'a>, Error> {
+        let (offset, len) = find_table(self.data, self.offset, tag)?
+            .ok_or_else(|| format!("Missing '{}' table", String::from_utf8_lossy(tag)))?;
+        self.data.slice(offset, len)
+    }
+}
+
+fn check_sfnt_version(directory: Data) -> Result<(), Error> {
+    match directory.get(0, 4)? {
+        b"OTTO" | b"true" | [0, 1, 0, 0] => Ok(()),
+        _ => Err("Not an OpenType font".into()),
+    }
+}
+
+/// Finds a table in the table directory at the given offset.
+/// Returns the offset and length of the table, relative to the start of the file.
+fn find_table(
+    data: Data,
+    directory: usize,
+    tag: &[u8; 4],
+) -> Result<Option<(usize, usize)>, Error> {
+    let num_tables = data.u16(directory + 4)?;
+    for i in 0..usize::from(num_tables) {
+        let record = data.slice(directory + 12 + 16 * i, 16)?;
+        if record.get(0, 4)? == tag {
+            return Ok(Some((record.u32(8)? as usize, record.u32(12)? as usize)));
+        }
+    }
+    Ok(None)
+}
+
+/// The advance widths from the `hmtx` table.
+struct HorizontalMetrics(Vec<u16>);
+
+impl HorizontalMetrics {
+    fn parse(hmtx: Data, num_metrics: u16) -> Result<Self, Error> {
+        let advances = (0..usize::from(num_metrics))
+            .map(|i| hmtx.u16(4 * i))
+            .collect::<Result<_, _>>()?;
+        Ok(Self(advances))
+    }
+
+    /// Glyphs after the last metric use the last advance width.
+    fn advance(&self, glyph_id: u16) -> u16 {
+        self.0
+            .get(usize::from(glyph_id))
+            .or_else(|| self.0.last())
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// Parses the Unicode character map of the `cmap` table.
+/// Returns a map from each code point in the Basic Multilingual Plane to its glyph ID.
+fn parse_cmap(cmap: Data) -> Result<BTreeMap<u16, u16>, Error> {
+    let num_tables = cmap.u16(2)?;
+    let mut best = None;
+    for i in 0..usize::from(num_tables) {
+        let platform_id = cmap.u16(4 + 8 * i)?;
+        let encoding_id = cmap.u16(6 + 8 * i)?;
+        let offset = cmap.u32(8 + 8 * i)? as usize;
+        let priority = match (platform_id, encoding_id) {
+            (3, 10) | (0, 4) | (0, 6) => 3,
+            (3, 1) | (0, _) => 2,
+            (3, 0) => 1,
+            _ => continue,
+        };
+        let format = cmap.u16(offset)?;
+        if [0, 4, 6, 12].contains(&format) && best.map(|(p, _)| priority > p).unwrap_or(true) {
+            best = Some((priority, offset));
+        }
+    }
+    let (_, offset) = best.ok_or("No Unicode character map")?;
+    let subtable = cmap.slice_from(offset)?;
+
+    let mut map = BTreeMap::new();
+    match subtable.u16(0)? {
+        0 => {
+            for code in 0..256 {
+                map.insert(code, u16::from(subtable.u8(6 + usize::from(code))?));
+            }
+        }
+        4 => {
+            let seg_count = usize::from(subtable.u16(6)? / 2);
+            let end_codes = 14;
+            let start_codes = end_codes + 2 * seg_count + 2;
+            let id_deltas = start_codes + 2 * seg_count;
+            let id_range_offsets = id_deltas + 2 * seg_count;
+            for segment in 0..seg_count {
+                let end = subtable.u16(end_codes + 2 * segment)?;
+                let start = subtable.u16(start_codes + 2 * segment)?;
+                let delta = subtable.u16(id_deltas + 2 * segment)?;
+                let range_offset_pos = id_range_offsets + 2 * segment;
+                let range_offset = subtable.u16(range_offset_pos)?;
+                if start > end {
+                    continue;
+                }
+                for code in start..=end {
+                    let glyph_id = if range_offset == 0 {
+                        code.wrapping_add(delta)
+                    } else {
+                        let pos = range_offset_pos
+                            + usize::from(range_offset)
+                            + 2 * usize::from(code - start);
+                        match subtable.u16(pos)? {
+                            0 => 0,
+                            id => id.wrapping_add(delta),
+                        }
+                    };
+                    if glyph_id != 0 {
+                        map.insert(code, glyph_id);
+                    }
+                }
+            }
+        }
+        6 => {
+            let first_code = subtable.u16(6)?;
+            let count = subtable.u16(8)?;
+            for i in 0..count {
+                let glyph_id = subtable.u16(10 + 2 * usize::from(i))?;
+                if glyph_id != 0 {
+                    map.insert(first_code.wrapping_add(i), glyph_id);
+                }
+            }
+        }
+        12 => {
+            let num_groups = subtable.u32(12)? as usize;
+            for group in 0..num_groups {
+                let start = subtable.u32(16 + 12 * group)?;
+                let end = subtable.u32(20 + 12 * group)?.min(0xffff);
+                let start_glyph_id = subtable.u32(24 + 12 * group)?;
+                if start > end {
+                    continue;
+                }
+                for code in start..=end {
+                    let glyph_id = start_glyph_id + (code - start);
+                    if glyph_id != 0 && glyph_id <= 0xffff {
+                        map.insert(code as u16, glyph_id as u16);
+                    }
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(map)
+}
+
+/// Reads the horizontal kerning between pairs of characters in the character map.
+///
+/// The pair adjustments of the `kern` feature in the `GPOS` table are used if the font has
+/// any, otherwise the legacy `kern` table is used. Returns the adjustments in font units.
+fn parse_kerning(
+    font: &Sfnt,
+    code_points: &BTreeMap<u16, u16>,
+) -> Result<BTreeMap<(u16, u16), i16>, Error> {
+    let mut glyph_codes: BTreeMap<u16, Vec<u16>> = BTreeMap::new();
+    for (&code, &glyph_id) in code_points {
+        glyph_codes.entry(glyph_id).or_default().push(code);
+    }
+
+    let mut glyph_pairs = match font.table(b"GPOS") {
+        Ok(gpos) => parse_gpos_kerning(gpos, &glyph_codes)?,
+        Err(_) => BTreeMap::new(),
+    };
+    if glyph_pairs.is_empty() {
+        if let Ok(kern) = font.table(b"kern") {
+            glyph_pairs = parse_kern_table(kern)?;
+        }
+    }
+
+    let mut pairs = BTreeMap::new();
+    for ((left, right), adjustment) in glyph_pairs {
+        if let (Some(left_codes), Some(right_codes)) =
+            (glyph_codes.get(&left), glyph_codes.get(&right))
+        {
+            for &left_code in left_codes {
+                for &right_code in right_codes {
+                    pairs.insert((left_code, right_code), adjustment);
+                }
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Reads the horizontal format 0 subtables of a `kern` table.
+/// Both the OpenType and the Apple versions of the table header are accepted.
+fn parse_kern_table(kern: Data) -> Result<BTreeMap<(u16, u16), i16>, Error> {
+    let mut pairs = BTreeMap::new();
+    let is_apple = kern.u16(0)? == 1;
+    let (num_tables, mut offset) = if is_apple {
+        (kern.u32(4)? as usize, 8)
+    } else {
+        (usize::from(kern.u16(2)?), 4)
+    };
+    for _ in 0..num_tables {
+        let (len, format, is_horizontal, header_len) = if is_apple {
+            let coverage = kern.u16(offset + 4)?;
+            let is_horizontal = coverage & 0xe000 == 0;
+            (
+                kern.u32(offset)? as usize,
+                coverage & 0xff,
+                is_horizontal,
+                8,
+            )
+        } else {
+            let coverage = kern.u16(offset + 4)?;
+            let is_horizontal = coverage & 0b111 == 0b001;
+            (
+                usize::from(kern.u16(offset + 2)?),
+                coverage >> 8,
+                is_horizontal,
+                6,
+            )
+        };
+        if format == 0 && is_horizontal {
+            let subtable = kern.slice_from(offset + header_len)?;
+            for i in 0..usize::from(subtable.u16(0)?) {
+                let record = subtable.slice(8 + 6 * i, 6)?;
+                let adjustment = pairs.entry((record.u16(0)?, record.u16(2)?)).or_insert(0);
+                *adjustment = record.i16(4)?.saturating_add(*adjustment);
+            }
+        }
+        if len == 0 {
+            break;
+        }
+        offset += len;
+    }
+    Ok(pairs)
+}
+
+/// Reads the x-advance adjustments of the pair positioning lookups of the `kern` feature
+/// of a `GPOS` table, for glyphs in the character map.
+/// Earlier lookups and subtables take precedence over later ones.
+fn parse_gpos_kerning(
+    gpos: Data,
+    glyph_codes: &BTreeMap<u16, Vec<u16>>,
+) -> Result<BTreeMap<(u16, u16), i16>, Error> {
+    let features = gpos.slice_from(gpos.u16(6)?.into())?;
+    let mut lookup_indices = vec![];
+    for i in 0..usize::from(features.u16(0)?) {
+        let record = features.slice(2 + 6 * i, 6)?;
+        if record.get(0, 4)? == b"kern" {
+            let feature = features.slice_from(record.u16(4)?.into())?;
+            for j in 0..usize::from(feature.u16(2)?) {
+                lookup_indices.push(feature.u16(4 + 2 * j)?);
+            }
+        }
+    }
+    lookup_indices.sort_unstable();
+    lookup_indices.dedup();
+
+    let lookups = gpos.slice_from(gpos.u16(8)?.into())?;
+    let mut pairs = BTreeMap::new();
+    for lookup_index in lookup_indices {
+        let lookup = lookups.slice_from(lookups.u16(2 + 2 * usize::from(lookup_index))?.into())?;
+        let lookup_type = lookup.u16(0)?;
+        for i in 0..usize::from(lookup.u16(4)?) {
+            let subtable = lookup.slice_from(lookup.u16(6 + 2 * i)?.into())?;
+            match lookup_type {
+                2 => parse_pair_pos(subtable, glyph_codes, &mut pairs)?,
+                // Extension positioning, which wraps a subtable with a 32-bit offset.
+                9 if subtable.u16(2)? == 2 => parse_pair_pos(
+                    subtable.slice_from(subtable.u32(4)? as usize)?,
+                    glyph_codes,
+                    &mut pairs,
+                )?,
+                _ => (),
+            }
+        }
+    }
+    pairs.retain(|_, adjustment| *adjustment != 0);
+    Ok(pairs)
+}
+
+/// Reads a pair adjustment positioning subtable of a `GPOS` table.
+fn parse_pair_pos(
+    subtable: Data,
+    glyph_codes: &BTreeMap<u16, Vec<u16>>,
+    pairs: &mut BTreeMap<(u16, u16), i16>,
+) -> Result<(), Error> {
+    let coverage = parse_coverage(subtable.slice_from(subtable.u16(2)?.into())?)?;
+    let value_format_1 = subtable.u16(4)?;
+    let value_format_2 = subtable.u16(6)?;
+    // The x-advance of the first glyph, and the size of the value records.
+    let x_advance = if value_format_1 & 0b100 != 0 {
+        Some(2 * (value_format_1 & 0b11).count_ones() as usize)
+    } else {
+        None
+    };
+    let values_len = 2 * (value_format_1.count_ones() + value_format_2.count_ones()) as usize;
+
+    match subtable.u16(0)? {
+        1 => {
+            for (first, coverage_index) in coverage {
+                if !glyph_codes.contains_key(&first) {
+                    continue;
+                }
+                let pair_set_offset = subtable.u16(10 + 2 * usize::from(coverage_index))?;
+                let pair_set = subtable.slice_from(pair_set_offset.into())?;
+                for i in 0..usize::from(pair_set.u16(0)?) {
+                    let record = pair_set.slice_from(2 + (2 + values_len) * i)?;
+                    let adjustment = match x_advance {
+                        Some(offset) => record.i16(2 + offset)?,
+                        None => 0,
+                    };
+                    pairs.entry((first, record.u16(0)?)).or_insert(adjustment);
+                }
+            }
+        }
+        2 => {
+            let class_def_1 = subtable.slice_from(subtable.u16(8)?.into())?;
+            let class_def_2 = subtable.slice_from(subtable.u16(10)?.into())?;
+            let class_1_count = subtable.u16(12)?;
+            let class_2_count = usize::from(subtable.u16(14)?);
+            let second_classes = glyph_codes
+                .keys()
+                .map(|&glyph_id| Ok((glyph_id, class_of(class_def_2, glyph_id)?)))
+                .collect::<Result<Vec<_>, Error>>()?;
+            for (first, _) in coverage {
+                if !glyph_codes.contains_key(&first) {
+                    continue;
+                }
+                let class_1 = class_of(class_def_1, first)?;
+                if class_1 >= class_1_count {
+                    continue;
+                }
+                let class_1_record = 16 + usize::from(class_1) * class_2_count * values_len;
+                for &(second, class_2) in &second_classes {
+                    if usize::from(class_2) >= class_2_count {
+                        continue;
+                    }
+                    let adjustment = match x_advance {
+                        Some(offset) => subtable
+                            .i16(class_1_record + usize::from(class_2) * values_len + offset)?,
+                        None => 0,
+                    };
+                    pairs.entry((first, second)).or_insert(adjustment);
+                }
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Reads an OpenType coverage table.
+/// Returns each covered glyph ID with its coverage index.
+fn parse_coverage(coverage: Data) -> Result<Vec<(u16, u16)>, Error> {
+    let mut glyphs = vec![];
+    match coverage.u16(0)? {
+        1 => {
+            for i in 0..coverage.u16(2)? {
+                glyphs.push((coverage.u16(4 + 2 * usize::from(i))?, i));
+            }
+        }
+        2 => {
+            for i in 0..usize::from(coverage.u16(2)?) {
+                let range = coverage.slice(4 + 6 * i, 6)?;
+                let (start, end, start_index) = (range.u16(0)?, range.u16(2)?, range.u16(4)?);
+                if start <= end {
+                    for glyph_id in start..=end {
+                        glyphs.push((glyph_id, start_index.wrapping_add(glyph_id - start)));
+                    }
+                }
+            }
+        }
+        format => return Err(format!("Invalid coverage format {}", format).into()),
+    }
+    Ok(glyphs)
+}
+
+/// Looks up the class of a glyph in an OpenType class definition table.
+/// Glyphs that aren't listed are in class 0.
+fn class_of(class_def: Data, glyph_id: u16) -> Result<u16, Error> {
+    match class_def.u16(0)? {
+        1 => {
+            let start = class_def.u16(2)?;
+            let count = class_def.u16(4)?;
+            if glyph_id >= start && glyph_id - start < count {
+                class_def.u16(6 + 2 * usize::from(glyph_id - start))
+            } else {
+                Ok(0)
+            }
+        }
+        2 => {
+            for i in 0..usize::from(class_def.u16(2)?) {
+                let range = class_def.slice(4 + 6 * i, 6)?;
+                if (range.u16(0)?..=range.u16(2)?).contains(&glyph_id) {
+                    return range.u16(4);
+                }
+            }
+            Ok(0)
+        }
+        format => Err(format!("Invalid class definition format {}", format).into()),
+    }
+}
+
+/// A command of a glyph outline, in font units with the y-axis pointing up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PathCommand {
+    Move(f64, f64),
+    Line(f64, f64),
+    Quad(f64, f64, f64, f64),
+    Cubic(f64, f64, f64, f64, f64, f64),
+}
+
+/// The outline of a glyph. Contours are implicitly closed.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Outline {
+    commands: Vec<PathCommand>,
+    start: (f64, f64),
+    current: (f64, f64),
+}
+
+impl Outline {
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.close();
+        self.commands.push(PathCommand::Move(x, y));
+        self.start = (x, y);
+        self.current = (x, y);
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.commands.push(PathCommand::Line(x, y));
+        self.current = (x, y);
+    }
+
+    fn quad_to(&mut self, x1: f64, y1: f64, x: f64, y: f64) {
+        self.commands.push(PathCommand::Quad(x1, y1, x, y));
+        self.current = (x, y);
+    }
+
+    fn cubic_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64) {
+        self.commands.push(PathCommand::Cubic(x1, y1, x2, y2, x, y));
+        self.current = (x, y);
+    }
+
+    /// Closes the current contour with a line back to its start, if necessary.
+    fn close(&mut self) {
+        if !self.commands.is_empty() && self.current != self.start {
+            let (x, y) = self.start;
+            self.line_to(x, y);
+        }
+    }
+
+    /// Converts the outline to SWF shape records, scaling by `scale` and flipping the y-axis.
+    /// Cubic curves are approximated by quadratic curves.
+    fn into_shape_records(mut self, scale: f64) -> Vec<swf::ShapeRecord> {
+        self.close();
+
+        // Positions are rounded to twips, and the deltas are taken between the rounded
+        // positions so that rounding errors don't accumulate.
+        let to_twips = |x: f64, y: f64| {
+            (
+                Twips::new((x * scale).round() as i32),
+                Twips::new((-y * scale).round() as i32),
+            )
+        };
+        let mut records = vec![];
+        let mut pen = (Twips::new(0), Twips::new(0));
+        let mut prev = (0.0, 0.0);
+        let mut is_first = true;
+        let quad_to = |records: &mut Vec<_>, pen: &mut (Twips, Twips), x1, y1, x, y| {
+            let control = to_twips(x1, y1);
+            let anchor = to_twips(x, y);
+            records.push(swf::ShapeRecord::CurvedEdge {
+                control_delta_x: control.0 - pen.0,
+                control_delta_y: control.1 - pen.1,
+                anchor_delta_x: anchor.0 - control.0,
+                anchor_delta_y: anchor.1 - control.1,
+            });
+            *pen = anchor;
+        };
+        for command in self.commands {
+            match command {
+                PathCommand::Move(x, y) => {
+                    pen = to_twips(x, y);
+                    records.push(swf::ShapeRecord::StyleChange(swf::StyleChangeData {
+                        move_to: Some(pen),
+                        fill_style_0: None,
+                        fill_style_1: if is_first { Some(1) } else { None },
+                        line_style: None,
+                        new_styles: None,
+                    }));
+                    is_first = false;
+                    prev = (x, y);
+                }
+                PathCommand::Line(x, y) => {
+                    let point = to_twips(x, y);
+                    records.push(swf::ShapeRecord::StraightEdge {
+                        delta_x: point.0 - pen.0,
+                        delta_y: point.1 - pen.1,
+                    });
+                    pen = point;
+                    prev = (x, y);
+                }
+                PathCommand::Quad(x1, y1, x, y) => {
+                    quad_to(&mut records, &mut pen, x1, y1, x, y);
+                    prev = (x, y);
+                }
+                PathCommand::Cubic(x1, y1, x2, y2, x, y) => {
+                    // Split the cubic into 4 parts, and approximate each with a quadratic.
+                    const NUM_PARTS: u32 = 4;
+                    let (x0, y0) = prev;
+                    let point = |t: f64| {
+                        let mt = 1.0 - t;
+                        let a = mt * mt * mt;
+                        let b = 3.0 * mt * mt * t;
+                        let c = 3.0 * mt * t * t;
+                        let d = t * t * t;
+                        (
+                            a * x0 + b * x1 + c * x2 + d * x,
+                            a * y0 + b * y1 + c * y2 + d * y,
+                        )
+                    };
+                    let derivative = |t: f64| {
+                        let mt = 1.0 - t;
+                        (
+                            3.0 * (mt * mt * (x1 - x0)
+                                + 2.0 * mt * t * (x2 - x1)
+                                + t * t * (x - x2)),
+                            3.0 * (mt * mt * (y1 - y0)
+                                + 2.0 * mt * t * (y2 - y1)
+                                + t * t * (y - y2)),
+                        )
+                    };
+                    for i in 0..NUM_PARTS {
+                        let t0 = f64::from(i) / f64::from(NUM_PARTS);
+                        let t1 = f64::from(i + 1) / f64::from(NUM_PARTS);
+                        let p0 = point(t0);
+                        let p1 = point(t1);
+                        let d0 = derivative(t0);
+                        let d1 = derivative(t1);
+                        let dt = t1 - t0;
+                        // The control point is the average of the points reached by the
+                        // tangents at each end, which is exact for quadratic curves.
+                        let cx = (p0.0 + d0.0 * dt / 2.0 + p1.0 - d1.0 * dt / 2.0) / 2.0;
+                        let cy = (p0.1 + d0.1 * dt / 2.0 + p1.1 - d1.1 * dt / 2.0) / 2.0;
+                        quad_to(&mut records, &mut pen, cx, cy, p1.0, p1.1);
+                    }
+                    prev = (x, y);
+                }
+            }
+        }
+        records
+    }
+}
+
+/// The outlines of a font with TrueType outlines, from the `glyf` and `loca` tables.
+struct TrueTypeOutlines<'a> {
+    glyf: Data<'a>,
+
+    /// The offsets of each glyph in the `glyf` table.
+    loca: Data<'a>,
+
+    /// Whether the `loca` table has 32-bit offsets.
+    is_long: bool,
+}
+
+impl<'a> TrueTypeOutlines<'a> {
+    fn parse(font: &Sfnt<'a>, num_glyphs: u16) -> Result<Self, Error> {
+        let is_long = font.table(b"head")?.i16(50)? != 0;
+        let loca = font.table(b"loca")?;
+        let loca_len = (usize::from(num_glyphs) + 1) * if is_long { 4 } else { 2 };
+        Ok(Self {
+            glyf: font.table(b"glyf")?,
+            loca: loca.slice(0, loca_len)?,
+            is_long,
+        })
+    }
+
+    /// The offset of a glyph in the `glyf` table.
+    fn offset(&self, index: usize) -> Option<usize> {
+        if self.is_long {
+            self.loca.u32(4 * index).ok().map(|n| n as usize)
+        } else {
+            self.loca.u16(2 * index).ok().map(|n| 2 * usize::from(n))
+        }
+    }
+
+    fn outline(&self, glyph_id: u16) -> Result<Outline, Error> {
+        let mut outline = Outline::default();
+        self.add_glyph(&mut outline, glyph_id, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0], 0)?;
+        Ok(outline)
+    }
+
+    /// Adds the contours of a glyph to `outline`, transformed by the affine matrix `transform`.
+    fn add_glyph(
+        &self,
+        outline: &mut Outline,
+        glyph_id: u16,
+        transform: [f64; 6],
+        depth: u32,
+    ) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err("Composite glyphs are nested too deeply".into());
+        }
+        let index = usize::from(glyph_id);
+        let (start, end) = match (self.offset(index), self.offset(index + 1)) {
+            (Some(start), Some(end)) if end > start => (start, end),
+            // An empty glyph, such as a space.
+            _ => return Ok(()),
+        };
+        let glyph = self.glyf.slice(start, end - start)?;
+        let num_contours = glyph.i16(0)?;
+        if num_contours >= 0 {
+            Self::add_simple_glyph(outline, glyph, num_contours as usize, transform)
+        } else {
+            self.add_composite_glyph(outline, glyph, transform, depth)
+        }
+    }
+
+    fn add_simple_glyph(
+        outline: &mut Outline,
+        glyph: Data,
+        num_contours: usize,
+        transform: [f64; 6],
+    ) -> Result<(), Error> {
+        const ON_CURVE: u8 = 0x01;
+        const X_SHORT: u8 = 0x02;
+        const Y_SHORT: u8 = 0x04;
+        const REPEAT: u8 = 0x08;
+        const X_SAME_OR_POSITIVE: u8 = 0x10;
+        const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+        let end_points = (0..num_contours)
+            .map(|i| glyph.u16(10 + 2 * i).map(usize::from))
+            .collect::<Result<Vec<_>, _>>()?;
+        let num_points = end_points.last().map(|&n| n + 1).unwrap_or(0);
+        let instructions_len = usize::from(glyph.u16(10 + 2 * num_contours)?);
+        let mut pos = 12 + 2 * num_contours + instructions_len;
+
+        let mut flags = Vec::with_capacity(num_points);
+        while flags.len() < num_points {
+            let flag = glyph.u8(pos)?;
+            pos += 1;
+            flags.push(flag);
+            if flag & REPEAT != 0 {
+                let count = glyph.u8(pos)?;
+                pos += 1;
+                for _ in 0..count {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.truncate(num_points);
+
+        let mut read_coords = |short: u8, same_or_positive: u8| -> Result<Vec<f64>, Error> {
+            let mut value = 0i32;
+            flags
+                .iter()
+                .map(|&flag| {
+                    if flag & short != 0 {
+                        let delta = i32::from(glyph.u8(pos)?);
+                        pos += 1;
+                        value += if flag & same_or_positive != 0 {
+                            delta
+                        } else {
+                            -delta
+                        };
+                    } else if flag & same_or_positive == 0 {
+                        value += i32::from(glyph.i16(pos)?);
+                        pos += 2;
+                    }
+                    Ok(f64::from(value))
+                })
+                .collect()
+        };
+        let xs = read_coords(X_SHORT, X_SAME_OR_POSITIVE)?;
+        let ys = read_coords(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+        let [a, b, c, d, e, f] = transform;
+        let point = |i: usize| {
+            let (x, y) = (xs[i], ys[i]);
+            (
+                (a * x + c * y + e),
+                (b * x + d * y + f),
+                flags[i] & ON_CURVE != 0,
+            )
+        };
+        let midpoint =
+            |p: (f64, f64, bool), q: (f64, f64, bool)| ((p.0 + q.0) / 2.0, (p.1 + q.1) / 2.0, true);
+
+        let mut start = 0;
+        for &end in &end_points {
+            if end < start || end >= num_points {
+                return Err("Invalid contour".into());
+            }
+            let points: Vec<_> = (start..=end).map(point).collect();
+            start = end + 1;
+
+            if points.is_empty() {
+                continue;
+            }
+
+            // Start the contour at an on-curve point, and walk around back to it.
+            // If every point is off-curve, start at the implied point between the last and first.
+            let (first, rest) = match points.iter().position(|p| p.2) {
+                Some(i) => {
+                    let mut rest = points[i + 1..].to_vec();
+                    rest.extend_from_slice(&points[..=i]);
+                    (points[i], rest)
+                }
+                None => {
+                    let first = midpoint(points[points.len() - 1], points[0]);
+                    let mut rest = points.clone();
+                    rest.push(first);
+                    (first, rest)
+                }
+            };
+            outline.move_to(first.0, first.1);
+
+            // Consecutive off-curve points have an implied on-curve point between them.
+            let mut control = None;
+            for p in rest {
+                match (control, p.2) {
+                    (None, true) => outline.line_to(p.0, p.1),
+                    (None, false) => control = Some(p),
+                    (Some(c), true) => {
+                        outline.quad_to(c.0, c.1, p.0, p.1);
+                        control = None;
+                    }
+                    (Some(c), false) => {
+                        let mid = midpoint(c, p);
+                        outline.quad_to(c.0, c.1, mid.0, mid.1);
+                        control = Some(p);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_composite_glyph(
+        &self,
+        outline: &mut Outline,
+        glyph: Data,
+        transform: [f64; 6],
+        depth: u32,
+    ) -> Result<(), Error> {
+        const ARGS_ARE_WORDS: u16 = 0x0001;
+        const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+        const HAS_SCALE: u16 = 0x0008;
+        const MORE_COMPONENTS: u16 = 0x0020;
+        const HAS_X_Y_SCALE: u16 = 0x0040;
+        const HAS_TWO_BY_TWO: u16 = 0x0080;
+
+        let f2dot14 = |n: i16| f64::from(n) / 16384.0;
+        let mut pos = 10;
+        loop {
+            let flags = glyph.u16(pos)?;
+            let component_id = glyph.u16(pos + 2)?;
+            pos += 4;
+            let (dx, dy) = if flags & ARGS_ARE_WORDS != 0 {
+                pos += 4;
+                (
+                    f64::from(glyph.i16(pos - 4)?),
+                    f64::from(glyph.i16(pos - 2)?),
+                )
+            } else {
+                pos += 2;
+                (
+                    f64::from(glyph.u8(pos - 2)? as i8),
+                    f64::from(glyph.u8(pos - 1)? as i8),
+                )
+            };
+            // Components positioned by matching points are not supported.
+            let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 {
+                (dx, dy)
+            } else {
+                (0.0, 0.0)
+            };
+            let (a, b, c, d) = if flags & HAS_SCALE != 0 {
+                pos += 2;
+                let scale = f2dot14(glyph.i16(pos - 2)?);
+                (scale, 0.0, 0.0, scale)
+            } else if flags & HAS_X_Y_SCALE != 0 {
+                pos += 4;
+                (
+                    f2dot14(glyph.i16(pos - 4)?),
+                    0.0,
+                    0.0,
+                    f2dot14(glyph.i16(pos - 2)?),
+                )
+            } else if flags & HAS_TWO_BY_TWO != 0 {
+                pos += 8;
+                (
+                    f2dot14(glyph.i16(pos - 8)?),
+                    f2dot14(glyph.i16(pos - 6)?),
+                    f2dot14(glyph.i16(pos - 4)?),
+                    f2dot14(glyph.i16(pos - 2)?),
+                )
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+
+            // Combine the component transform with the parent transform.
+            let [pa, pb, pc, pd, pe, pf] = transform;
+            let component_transform = [
+                pa * a + pc * b,
+                pb * a + pd * b,
+                pa * c + pc * d,
+                pb * c + pd * d,
+                pa * dx + pc * dy + pe,
+                pb * dx + pd * dy + pf,
+            ];
+            self.add_glyph(outline, component_id, component_transform, depth + 1)?;
+
+            if flags & MORE_COMPONENTS == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The glyph outlines of a Compact Font Format font, from the `CFF ` table.
+struct Cff<'a> {
+    char_strings: Index<'a>,
+    global_subrs: Index<'a>,
+
+    /// The local subroutines of each font dict. Non-CID fonts have a single font dict.
+    local_subrs: Vec<Index<'a>>,
+
+    /// The font dict used by each glyph, for CID fonts.
+    fd_select: Option<FdSelect<'a>>,
+}
+
+impl<'a> Cff<'a> {
+    fn parse(data: Data<'a>) -> Result<Self, Error> {
+        let header_size = data.u8(2)?;
+        let name_index = Index::parse(data, header_size.into())?;
+        let top_dict_index = Index::parse(data, name_index.end)?;
+        let string_index = Index::parse(data, top_dict_index.end)?;
+        let global_subrs = Index::parse(data, string_index.end)?;
+
+        let top_dict = Dict::parse(top_dict_index.get(0)?)?;
+        if top_dict.get(0x0c06).map(|v| v[0] as i32).unwrap_or(2) != 2 {
+            return Err("Only Type 2 charstrings are supported".into());
+        }
+        let char_strings_offset = top_dict.offset(17)?.ok_or("Missing CharStrings")?;
+        let char_strings = Index::parse(data, char_strings_offset)?;
+
+        let (local_subrs, fd_select) = if let Some(fd_array_offset) = top_dict.offset(0x0c24)? {
+            // A CID-keyed font has a private dict for each font dict.
+            let fd_array = Index::parse(data, fd_array_offset)?;
+            let local_subrs = (0..fd_array.count)
+                .map(|i| Self::parse_local_subrs(data, &Dict::parse(fd_array.get(i)?)?))
+                .collect::<Result<_, _>>()?;
+            let fd_select_offset = top_dict.offset(0x0c25)?.ok_or("Missing FDSelect")?;
+            let fd_select = FdSelect::parse(data.slice_from(fd_select_offset)?)?;
+            (local_subrs, Some(fd_select))
+        } else {
+            (vec![Self::parse_local_subrs(data, &top_dict)?], None)
+        };
+
+        Ok(Self {
+            char_strings,
+            global_subrs,
+            local_subrs,
+            fd_select,
+        })
+    }
+
+    /// Parses the local subroutines in the private dict of a top or font dict.
+    fn parse_local_subrs(data: Data<'a>, dict: &Dict) -> Result<Index<'a>, Error> {
+        let private = match dict.get(18) {
+            Some(operands) if operands.len() >= 2 => operands,
+            _ => return Ok(Index::empty(data)),
+        };
+        let (size, offset) = (private[0] as usize, private[1] as usize);
+        let private_dict = Dict::parse(data.slice(offset, size)?)?;
+        match private_dict.offset(19)? {
+            // The Subrs offset is relative to the private dict.
+            Some(subrs_offset) => Index::parse(data, offset + subrs_offset),
+            None => Ok(Index::empty(data)),
+        }
+    }
+
+    fn outline(&self, glyph_id: u16) -> Result<Outline, Error> {
+        let font_dict = match &self.fd_select {
+            Some(fd_select) => usize::from(fd_select.font_dict(glyph_id)?),
+            None => 0,
+        };
+        let local_subrs = self
+            .local_subrs
+            .get(font_dict)
+            .ok_or("Invalid font dict index")?;
+        let mut interpreter = CharStringInterpreter {
+            global_subrs: &self.global_subrs,
+            local_subrs,
+            outline: Outline::default(),
+            stack: Vec::with_capacity(48),
+            x: 0.0,
+            y: 0.0,
+            num_stems: 0,
+            has_width: false,
+        };
+        interpreter.run(self.char_strings.get(glyph_id.into())?, 0)?;
+        Ok(interpreter.outline)
+    }
+}
+
+/// A CFF INDEX, which is an array of variable-length objects.
+struct Index<'a> {
+    data: Data<'a>,
+    count: usize,
+    offset_size: u8,
+
+    /// The start of the offset array.
+    offsets: usize,
+
+    /// The position before the first object. Offsets are relative to this.
+    base: usize,
+
+    /// The position after the end of the INDEX.
+    end: usize,
+}
+
+impl<'a> Index<'a> {
+    fn parse(data: Data<'a>, offset: usize) -> Result<Self, Error> {
+        let count = usize::from(data.u16(offset)?);
+        if count == 0 {
+            return Ok(Self {
+                data,
+                count,
+                offset_size: 1,
+                offsets: offset,
+                base: offset,
+                end: offset + 2,
+            });
+        }
+        let offset_size = data.u8(offset + 2)?;
+        if !(1..=4).contains(&offset_size) {
+            return Err("Invalid CFF INDEX offset size".into());
+        }
+        let offsets = offset + 3;
+        let base = offsets + (count + 1) * usize::from(offset_size) - 1;
+        let last = data.uint(offsets + count * usize::from(offset_size), offset_size)? as usize;
+        Ok(Self {
+            data,
+            count,
+            offset_size,
+            offsets,
+            base,
+            end: base + last,
+        })
+    }
+
+    fn empty(data: Data<'a>) -> Self {
+        Self {
+            data,
+            count: 0,
+            offset_size: 1,
+            offsets: 0,
+            base: 0,
+            end: 0,
+        }
+    }
+
+    fn get(&self, i: usize) -> Result<Data<'a>, Error> {
+        if i >= self.count {
+            return Err("CFF INDEX out of bounds".into());
+        }
+        let size = usize::from(self.offset_size);
+        let start = self.data.uint(self.offsets + i * size, self.offset_size)? as usize;
+        let end = self
+            .data
+            .uint(self.offsets + (i + 1) * size, self.offset_size)? as usize;
+        if end < start {
+            return Err("Invalid CFF INDEX offsets".into());
+        }
+        self.data.slice(self.base + start, end - start)
+    }
+}
+
+/// A CFF DICT, mapping operators to their operands.
+/// Two-byte operators are stored as `0x0c00 | op`.
+struct Dict(Vec<(u16, Vec<f64>)>);
+
+impl Dict {
+    fn parse(data: Data) -> Result<Self, Error> {
+        let mut entries = vec![];
+        let mut operands = vec![];
+        let mut pos = 0;
+        while pos < data.0.len() {
+            let b0 = data.u8(pos)?;
+            pos += 1;
+            match b0 {
+                0..=11 | 13..=21 => {
+                    entries.push((u16::from(b0), std::mem::take(&mut operands)));
+                }
+                12 => {
+                    let op = 0x0c00 | u16::from(data.u8(pos)?);
+                    pos += 1;
+                    entries.push((op, std::mem::take(&mut operands)));
+                }
+                28 => {
+                    operands.push(f64::from(data.i16(pos)?));
+                    pos += 2;
+                }
+                29 => {
+                    operands.push(f64::from(data.u32(pos)? as i32));
+                    pos += 4;
+                }
+                30 => {
+                    let (value, len) = Self::parse_real(data.slice_from(pos)?)?;
+                    operands.push(value);
+                    pos += len;
+                }
+                32..=246 => operands.push(f64::from(i32::from(b0) - 139)),
+                247..=250 => {
+                    let b1 = data.u8(pos)?;
+                    pos += 1;
+                    operands.push(f64::from((i32::from(b0) - 247) * 256 + i32::from(b1) + 108));
+                }
+                251..=254 => {
+                    let b1 = data.u8(pos)?;
+                    pos += 1;
+                    operands.push(f64::from(
+                        -(i32::from(b0) - 251) * 256 - i32::from(b1) - 108,
+                    ));
+                }
+                _ => return Err("Invalid CFF DICT data".into()),
+            }
+        }
+        Ok(Dict(entries))
+    }
+
+    /// Parses a real number encoded as nibbles. Returns the number and its length in bytes.
+    fn parse_real(data: Data) -> Result<(f64, usize), Error> {
+        let mut text = String::new();
+        let mut pos = 0;
+        loop {
+            let byte = data.u8(pos)?;
+            pos += 1;
+            for &nibble in &[byte >> 4, byte & 0xf] {
+                match nibble {
+                    0..=9 => text.push((b'0' + nibble) as char),
+                    0xa => text.push('.'),
+                    0xb => text.push('E'),
+                    0xc => text.push_str("E-"),
+                    0xe => text.push('-'),
+                    0xf => return Ok((text.parse().unwrap_or(0.0), pos)),
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn get(&self, op: u16) -> Option<&[f64]> {
+        self.0
+            .iter()
+            .find(|(o, _)| *o == op)
+            .map(|(_, operands)| &operands[..])
+    }
+
+    fn offset(&self, op: u16) -> Result<Option<usize>, Error> {
+        match self.get(op) {
+            Some(operands) => {
+                let offset = *operands.last().ok_or("Missing CFF DICT operand")?;
+                if offset < 0.0 {
+                    return Err("Invalid CFF offset".into());
+                }
+                Ok(Some(offset as usize))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Maps glyphs to font dicts in a CID-keyed CFF font.
+enum FdSelect<'a> {
+    Format0(Data<'a>),
+    Format3(Data<'a>),
+}
+
+impl<'a> FdSelect<'a> {
+    fn parse(data: Data<'a>) -> Result<Self, Error> {
+        match data.u8(0)? {
+            0 => Ok(FdSelect::Format0(data.slice_from(1)?)),
+            3 => Ok(FdSelect::Format3(data.slice_from(1)?)),
+            _ => Err("Unsupported FDSelect format".into()),
+        }
+    }
+
+    fn font_dict(&self, glyph_id: u16) -> Result<u8, Error> {
+        match self {
+            FdSelect::Format0(data) => data.u8(glyph_id.into()),
+            FdSelect::Format3(data) => {
+                let num_ranges = usize::from(data.u16(0)?);
+                for i in 0..num_ranges {
+                    let first = data.u16(2 + 3 * i)?;
+                    let next = data.u16(2 + 3 * (i + 1))?;
+                    if glyph_id >= first && glyph_id < next {
+                        return data.u8(4 + 3 * i);
+                    }
+                }
+                Err("Glyph is not in FDSelect".into())
+            }
+        }
+    }
+}
+
+/// Runs a Type 2 charstring to build the outline of a glyph.
+struct CharStringInterpreter<'a, 'b> {
+    global_subrs: &'b Index<'a>,
+    local_subrs: &'b Index<'a>,
+    outline: Outline,
+    stack: Vec<f64>,
+    x: f64,
+    y: f64,
+    num_stems: usize,
+
+    /// Whether the optional width argument has been handled.
+    has_width: bool,
+}
+
+/// The result of running a charstring.
+#[derive(PartialEq)]
+enum CharStringEnd {
+    Return,
+    EndChar,
+}
+
+impl<'a, 'b> CharStringInterpreter<'a, 'b> {
+    fn run(&mut self, data: Data, depth: u32) -> Result<CharStringEnd, Error> {
+        if depth > MAX_DEPTH {
+            return Err("Subroutines are nested too deeply".into());
+        }
+        let mut pos = 0;
+        while pos < data.0.len() {
+            let b0 = data.u8(pos)?;
+            pos += 1;
+            match b0 {
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => self.add_stems(),
+                // hintmask, cntrmask
+                19 | 20 => {
+                    // Arguments before a hint mask are an implicit vstem.
+                    // The mask has a bit for each stem, padded to whole bytes.
+                    self.add_stems();
+                    pos += (self.num_stems + 7) >> 3;
+                }
+                // rmoveto
+                21 => {
+                    self.take_width(2);
+                    let (dx, dy) = (self.arg(0)?, self.arg(1)?);
+                    self.move_to(dx, dy);
+                }
+                // hmoveto
+                22 => {
+                    self.take_width(1);
+                    let dx = self.arg(0)?;
+                    self.move_to(dx, 0.0);
+                }
+                // vmoveto
+                4 => {
+                    self.take_width(1);
+                    let dy = self.arg(0)?;
+                    self.move_to(0.0, dy);
+                }
+                // rlineto
+                5 => {
+                    for pair in self.stack.chunks_exact(2) {
+                        self.x += pair[0];
+                        self.y += pair[1];
+                        self.outline.line_to(self.x, self.y);
+                    }
+                }
+                // hlineto, vlineto
+                6 | 7 => {
+                    let mut horizontal = b0 == 6;
+                    for &d in &self.stack {
+                        if horizontal {
+                            self.x += d;
+                        } else {
+                            self.y += d;
+                        }
+                        self.outline.line_to(self.x, self.y);
+                        horizontal = !horizontal;
+                    }
+                }
+                // rrcurveto
+                8 => {
+                    let args = std::mem::take(&mut self.stack);
+                    for c in args.chunks_exact(6) {
+                        self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    let index = self.stack.pop().ok_or("Stack underflow")?;
+                    let subrs = if b0 == 10 {
+                        self.local_subrs
+                    } else {
+                        self.global_subrs
+                    };
+                    let bias = match subrs.count {
+                        n if n < 1240 => 107,
+                        n if n < 33900 => 1131,
+                        _ => 32768,
+                    };
+                    let index = index as i32 + bias;
+                    if index < 0 {
+                        return Err("Invalid subroutine index".into());
+                    }
+                    let subr = subrs.get(index as usize)?;
+                    if self.run(subr, depth + 1)? == CharStringEnd::EndChar {
+                        return Ok(CharStringEnd::EndChar);
+                    }
+                    continue;
+                }
+                // return
+                11 => return Ok(CharStringEnd::Return),
+                // endchar
+                14 => {
+                    self.take_width(0);
+                    self.outline.close();
+                    return Ok(CharStringEnd::EndChar);
+                }
+                // rcurveline
+                24 => {
+                    let args = std::mem::take(&mut self.stack);
+                    if args.len() < 2 {
+                        return Err("Stack underflow".into());
+                    }
+                    let (curves, line) = args.split_at(args.len() - 2);
+                    for c in curves.chunks_exact(6) {
+                        self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                    self.x += line[0];
+                    self.y += line[1];
+                    self.outline.line_to(self.x, self.y);
+                }
+                // rlinecurve
+                25 => {
+                    let args = std::mem::take(&mut self.stack);
+                    if args.len() < 6 {
+                        return Err("Stack underflow".into());
+                    }
+                    let (lines, c) = args.split_at(args.len() - 6);
+                    for pair in lines.chunks_exact(2) {
+                        self.x += pair[0];
+                        self.y += pair[1];
+                        self.outline.line_to(self.x, self.y);
+                    }
+                    self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                }
+                // vvcurveto
+                26 => {
+                    let mut args = &std::mem::take(&mut self.stack)[..];
+                    let mut dx1 = 0.0;
+                    if args.len() % 4 == 1 {
+                        dx1 = args[0];
+                        args = &args[1..];
+                    }
+                    for c in args.chunks_exact(4) {
+                        self.curve_to(dx1, c[0], c[1], c[2], 0.0, c[3]);
+                        dx1 = 0.0;
+                    }
+                }
+                // hhcurveto
+                27 => {
+                    let mut args = &std::mem::take(&mut self.stack)[..];
+                    let mut dy1 = 0.0;
+                    if args.len() % 4 == 1 {
+                        dy1 = args[0];
+                        args = &args[1..];
+                    }
+                    for c in args.chunks_exact(4) {
+                        self.curve_to(c[0], dy1, c[1], c[2], c[3], 0.0);
+                        dy1 = 0.0;
+                    }
+                }
+                // shortint
+                28 => {
+                    self.stack.push(f64::from(data.i16(pos)?));
+                    pos += 2;
+                    continue;
+                }
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    let args = std::mem::take(&mut self.stack);
+                    let mut horizontal = b0 == 31;
+                    let num_curves = args.len() / 4;
+                    for (i, c) in args.chunks_exact(4).enumerate() {
+                        // The last curve may have an extra argument.
+                        let last = if i + 1 == num_curves && args.len() % 4 == 1 {
+                            args[args.len() - 1]
+                        } else {
+                            0.0
+                        };
+                        if horizontal {
+                            self.curve_to(c[0], 0.0, c[1], c[2], last, c[3]);
+                        } else {
+                            self.curve_to(0.0, c[0], c[1], c[2], c[3], last);
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                12 => {
+                    let op = data.u8(pos)?;
+                    pos += 1;
+                    self.flex(op)?;
+                }
+                32..=246 => {
+                    self.stack.push(f64::from(i32::from(b0) - 139));
+                    continue;
+                }
+                247..=250 => {
+                    let b1 = data.u8(pos)?;
+                    pos += 1;
+                    self.stack
+                        .push(f64::from((i32::from(b0) - 247) * 256 + i32::from(b1) + 108));
+                    continue;
+                }
+                251..=254 => {
+                    let b1 = data.u8(pos)?;
+                    pos += 1;
+                    self.stack.push(f64::from(
+                        -(i32::from(b0) - 251) * 256 - i32::from(b1) - 108,
+                    ));
+                    continue;
+                }
+                255 => {
+                    // A 16.16 fixed point number.
+                    self.stack.push(f64::from(data.u32(pos)? as i32) / 65536.0);
+                    pos += 4;
+                    continue;
+                }
+                _ => return Err(format!("Unknown charstring operator {}", b0).into()),
+            }
+            self.stack.clear();
+        }
+        Ok(CharStringEnd::Return)
+    }
+
+    /// Handles the flex operators. Other escaped operators are ignored.
+    fn flex(&mut self, op: u8) -> Result<(), Error> {
+        let a = std::mem::take(&mut self.stack);
+        let arg = |i: usize| a.get(i).copied().ok_or("Stack underflow");
+        match op {
+            // flex
+            35 => {
+                self.curve_to(arg(0)?, arg(1)?, arg(2)?, arg(3)?, arg(4)?, arg(5)?);
+                self.curve_to(arg(6)?, arg(7)?, arg(8)?, arg(9)?, arg(10)?, arg(11)?);
+            }
+            // hflex
+            34 => {
+                let y = self.y;
+                self.curve_to(arg(0)?, 0.0, arg(1)?, arg(2)?, arg(3)?, 0.0);
+                let dy = y - self.y;
+                self.curve_to(arg(4)?, 0.0, arg(5)?, dy, arg(6)?, 0.0);
+            }
+            // hflex1
+            36 => {
+                let y = self.y;
+                self.curve_to(arg(0)?, arg(1)?, arg(2)?, arg(3)?, arg(4)?, 0.0);
+                let dy = y - self.y - arg(7)?;
+                self.curve_to(arg(5)?, 0.0, arg(6)?, arg(7)?, arg(8)?, dy);
+            }
+            // flex1
+            37 => {
+                let (x, y) = (self.x, self.y);
+                let dx = arg(0)? + arg(2)? + arg(4)? + arg(6)? + arg(8)?;
+                let dy = arg(1)? + arg(3)? + arg(5)? + arg(7)? + arg(9)?;
+                self.curve_to(arg(0)?, arg(1)?, arg(2)?, arg(3)?, arg(4)?, arg(5)?);
+                let (d6, d7, d8, d9) = (arg(6)?, arg(7)?, arg(8)?, arg(9)?);
+                let (d10x, d10y) = if dx.abs() > dy.abs() {
+                    (arg(10)?, y - (self.y + d7 + d9))
+                } else {
+                    (x - (self.x + d6 + d8), arg(10)?)
+                };
+                self.curve_to(d6, d7, d8, d9, d10x, d10y);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn arg(&self, i: usize) -> Result<f64, Error> {
+        self.stack
+            .get(i)
+            .copied()
+            .ok_or_else(|| "Stack underflow".into())
+    }
+
+    /// The first stack-clearing operator may have an extra width argument at the bottom of the
+    /// stack. `num_args` is the number of arguments the operator takes otherwise.
+    fn take_width(&mut self, num_args: usize) {
+        if !self.has_width {
+            self.has_width = true;
+            if self.stack.len() > num_args {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    fn add_stems(&mut self) {
+        if !self.has_width {
+            self.has_width = true;
+            if self.stack.len() % 2 == 1 {
+                self.stack.remove(0);
+            }
+        }
+        self.num_stems += self.stack.len() / 2;
+        self.stack.clear();
+    }
+
+    fn move_to(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+        self.outline.move_to(self.x, self.y);
+    }
+
+    fn curve_to(&mut self, dx1: f64, dy1: f64, dx2: f64, dy2: f64, dx3: f64, dy3: f64) {
+        let (x1, y1) = (self.x + dx1, self.y + dy1);
+        let (x2, y2) = (x1 + dx2, y1 + dy2);
+        self.x = x2 + dx3;
+        self.y = y2 + dy3;
+        self.outline.cubic_to(x1, y1, x2, y2, self.x, self.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Builds a CFF INDEX with 2-byte offsets.
+    fn cff_index(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = (items.len() as u16).to_be_bytes().to_vec();
+        if items.is_empty() {
+            return data;
+        }
+        data.push(2);
+        let mut offset = 1u16;
+        data.extend_from_slice(&offset.to_be_bytes());
+        for item in items {
+            offset += item.len() as u16;
+            data.extend_from_slice(&offset.to_be_bytes());
+        }
+        for item in items {
+            data.extend_from_slice(item);
+        }
+        data
+    }
+
+    /// Encodes a charstring operand as a shortint.
+    fn cs(numbers: &[i16], op: u8) -> Vec<u8> {
+        let mut data = vec![];
+        for n in numbers {
+            data.push(28);
+            data.extend_from_slice(&n.to_be_bytes());
+        }
+        data.push(op);
+        data
+    }
+
+    /// Builds a CFF font with a `.notdef` glyph and a 500x700 square glyph with a width of 600.
+    /// The right side of the square is drawn by a global subroutine.
+    fn square_cff() -> Vec<u8> {
+        let square = [
+            cs(&[600, 100, 0], 21),
+            cs(&[500, 0], 5),
+            cs(&[-107], 29),
+            cs(&[-500, 0], 5),
+            vec![14],
+        ]
+        .concat();
+        let global_subrs = vec![[cs(&[0, 700], 5), vec![11]].concat()];
+        let char_strings = cff_index(&[vec![14], square]);
+
+        let top_dict = |char_strings_offset: u32| {
+            let mut dict = vec![29];
+            dict.extend_from_slice(&char_strings_offset.to_be_bytes());
+            dict.push(17);
+            dict
+        };
+        let build = |char_strings_offset| {
+            [
+                vec![1, 0, 4, 2],
+                cff_index(&[b"Square".to_vec()]),
+                cff_index(&[top_dict(char_strings_offset)]),
+                cff_index(&[]),
+                cff_index(&global_subrs),
+            ]
+            .concat()
+        };
+        let offset = build(0).len() as u32;
+        [build(offset), char_strings].concat()
+    }
+
+    /// Builds an OpenType font with the given outline table and 2 glyphs,
+    /// mapping 'A' to the second glyph.
+    fn build_opentype(outline_tables: Vec<(&[u8; 4], Vec<u8>)>) -> Vec<u8> {
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        let mut hhea = vec![0; 36];
+        hhea[4..6].copy_from_slice(&800i16.to_be_bytes());
+        hhea[6..8].copy_from_slice(&(-200i16).to_be_bytes());
+        hhea[34..36].copy_from_slice(&2u16.to_be_bytes());
+        let hmtx = [500u16, 0, 600, 0]
+            .iter()
+            .flat_map(|n| n.to_be_bytes().to_vec())
+            .collect();
+        let mut maxp = vec![0; 6];
+        maxp[4..6].copy_from_slice(&2u16.to_be_bytes());
+        // A format 4 subtable with the segments 'A'..'A' and the final 0xFFFF segment.
+        let cmap = [
+            0u16,
+            1,
+            3,
+            1,
+            0,
+            12, // Header and encoding record
+            4,
+            32,
+            0,
+            4,
+            4,
+            1,
+            0, // Format 4 header
+            65,
+            0xffff,
+            0,
+            65,
+            0xffff, // End and start codes
+            (1u16).wrapping_sub(65),
+            1,
+            0,
+            0, // Deltas and range offsets
+        ]
+        .iter()
+        .flat_map(|n| n.to_be_bytes().to_vec())
+        .collect();
+
+        let mut tables = vec![
+            (b"cmap", cmap),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"maxp", maxp),
+        ];
+        tables.extend(outline_tables);
+
+        let mut data = b"OTTO".to_vec();
+        data.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+        data.extend_from_slice(&[0; 6]);
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, table) in &tables {
+            data.extend_from_slice(&tag[..]);
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&(offset as u32).to_be_bytes());
+            data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            offset += table.len();
+        }
+        for (_, table) in &tables {
+            data.extend_from_slice(table);
+        }
+        data
+    }
+
+    /// Builds a TrueType collection of the given fonts.
+    fn build_collection(fonts: &[&[u8]]) -> Vec<u8> {
+        let mut data = b"ttcf".to_vec();
+        data.extend_from_slice(&[0, 1, 0, 0]);
+        data.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+        let mut offset = 12 + 4 * fonts.len();
+        for font in fonts {
+            data.extend_from_slice(&(offset as u32).to_be_bytes());
+            offset += font.len();
+        }
+        for font in fonts {
+            // Table offsets are relative to the start of the collection.
+            let start = data.len();
+            data.extend_from_slice(font);
+            let num_tables = usize::from(u16::from_be_bytes([font[4], font[5]]));
+            for i in 0..num_tables {
+                let pos = start + 12 + 16 * i + 8;
+                let table_offset =
+                    u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+                data[pos..pos + 4].copy_from_slice(&(table_offset + start as u32).to_be_bytes());
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn cff_outline() {
+        let data = square_cff();
+        let cff = Cff::parse(Data(&data)).unwrap();
+        assert_eq!(cff.outline(0).unwrap().commands, vec![]);
+        assert_eq!(
+            cff.outline(1).unwrap().commands,
+            vec![
+                PathCommand::Move(100.0, 0.0),
+                PathCommand::Line(600.0, 0.0),
+                PathCommand::Line(600.0, 700.0),
+                PathCommand::Line(100.0, 700.0),
+                PathCommand::Line(100.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn truetype_outline() {
+        // A contour with an off-curve point between two on-curve points.
+        let glyph: Vec<u8> = [
+            1i16, 0, 0, 100, 100, // Contour count and bounds
+            2, 0, // End point and instruction length
+        ]
+        .iter()
+        .flat_map(|n| n.to_be_bytes().to_vec())
+        .chain(vec![1, 0, 1])
+        .chain(
+            [0i16, 100, 0, 0, 0, 100]
+                .iter()
+                .flat_map(|n| n.to_be_bytes().to_vec()),
+        )
+        .collect();
+        let loca: Vec<u8> = [0, glyph.len() as u32]
+            .iter()
+            .flat_map(|n| n.to_be_bytes().to_vec())
+            .collect();
+        let outlines = TrueTypeOutlines {
+            glyf: Data(&glyph),
+            loca: Data(&loca),
+            is_long: true,
+        };
+        assert_eq!(
+            outlines.outline(0).unwrap().commands,
+            vec![
+                PathCommand::Move(0.0, 0.0),
+                PathCommand::Quad(100.0, 0.0, 100.0, 100.0),
+                PathCommand::Line(0.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn cubic_to_quadratics() {
+        let mut outline = Outline::default();
+        outline.move_to(0.0, 0.0);
+        outline.cubic_to(0.0, 100.0, 100.0, 100.0, 100.0, 0.0);
+        let records = outline.into_shape_records(1.0);
+        // The move, 4 quadratic curves, and the closing line.
+        assert_eq!(records.len(), 6);
+        let mut x = Twips::new(0);
+        let mut y = Twips::new(0);
+        for record in &records[1..5] {
+            if let swf::ShapeRecord::CurvedEdge {
+                control_delta_x,
+                control_delta_y,
+                anchor_delta_x,
+                anchor_delta_y,
+            } = *record
+            {
+                x += control_delta_x + anchor_delta_x;
+                y += control_delta_y + anchor_delta_y;
+            } else {
+                panic!("Expected a curve");
+            }
+        }
+        assert_eq!((x, y), (Twips::new(100), Twips::new(0)));
+    }
+
+    #[test]
+    fn opentype_font() {
+        let data = build_opentype(vec![(b"CFF ", square_cff())]);
+        let font = OpenTypeFont::parse(data, 0).unwrap();
+        assert_eq!(font.ascent, 16384);
+        assert_eq!(font.descent, 4096);
+        assert_eq!(font.leading, 0);
+        assert_eq!(font.advance(u16::from(b'A')), Some(12288));
+        assert!(font.glyph(u16::from(b'B')).is_none());
+        let glyph = font.glyph(u16::from(b'A')).unwrap();
+        assert_eq!(glyph.code, u16::from(b'A'));
+        assert_eq!(glyph.advance, Some(12288));
+        assert_eq!(
+            glyph.shape_records[1..],
+            [
+                swf::ShapeRecord::StraightEdge {
+                    delta_x: Twips::new(10240),
+                    delta_y: Twips::new(0),
+                },
+                swf::ShapeRecord::StraightEdge {
+                    delta_x: Twips::new(0),
+                    delta_y: Twips::new(-14336),
+                },
+                swf::ShapeRecord::StraightEdge {
+                    delta_x: Twips::new(-10240),
+                    delta_y: Twips::new(0),
+                },
+                swf::ShapeRecord::StraightEdge {
+                    delta_x: Twips::new(0),
+                    delta_y: Twips::new(14336),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn font_face() {
+        // A name table with a Mac and a Windows family name.
+        let family: Vec<u8> = "Square"
+            .encode_utf16()
+            .flat_map(|c| c.to_be_bytes().to_vec())
+            .collect();
+        let mut name: Vec<u8> = [
+            0u16, 2, 30, // Header
+            1, 0, 0, 1, 3, 0, // Mac record
+            3, 1, 0x409, 1, 12, 3, // Windows record
+        ]
+        .iter()
+        .flat_map(|n| n.to_be_bytes().to_vec())
+        .collect();
+        name.extend_from_slice(b"Sq\0");
+        name.extend_from_slice(&family);
+        let mut os2 = vec![0; 64];
+        os2[63] = 0b10_0001;
+
+        let data = build_opentype(vec![(b"name", name), (b"OS/2", os2)]);
+        let face = FontFace {
+            family: "Square".to_string(),
+            is_bold: true,
+            is_italic: true,
+        };
+        assert_eq!(
+            read_font_faces(&mut Cursor::new(&data)).unwrap(),
+            vec![face.clone()]
+        );
+
+        // A collection of the same font twice.
+        let collection = build_collection(&[&data, &data]);
+        assert_eq!(
+            read_font_faces(&mut Cursor::new(&collection)).unwrap(),
+            vec![face.clone(), face]
+        );
+    }
+
+    #[test]
+    fn font_collection() {
+        let data = build_opentype(vec![(b"CFF ", square_cff())]);
+        let collection = build_collection(&[&build_opentype(vec![]), &data]);
+        assert!(OpenTypeFont::parse(collection.clone(), 0).is_err());
+        let font = OpenTypeFont::parse(collection.clone(), 1).unwrap();
+        assert_eq!(
+            font.glyph(u16::from(b'A')),
+            OpenTypeFont::parse(data, 0).unwrap().glyph(u16::from(b'A'))
+        );
+        assert!(OpenTypeFont::parse(collection, 2).is_err());
+    }
+
+    fn words(words: &[i32]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|&n| (n as u16).to_be_bytes().to_vec())
+            .collect()
+    }
+
+    /// Builds a `GPOS` table with a `kern` feature using a single lookup of the given type.
+    fn gpos_kern(lookup_type: i32, subtable: Vec<u8>) -> Vec<u8> {
+        let mut gpos = words(&[
+            1,
+            0,
+            10,
+            12,
+            26, // Header
+            0,  // Script list
+            1,
+            0x6b65,
+            0x726e,
+            8,
+            0,
+            1,
+            0, // Feature list
+            1,
+            4, // Lookup list
+            lookup_type,
+            0,
+            1,
+            8, // Lookup
+        ]);
+        gpos.extend(subtable);
+        gpos
+    }
+
+    fn kerning(data: Vec<u8>) -> Vec<(u16, u16, i32)> {
+        OpenTypeFont::parse(data, 0)
+            .unwrap()
+            .kerning()
+            .iter()
+            .map(|k| (k.left_code, k.right_code, k.adjustment.get()))
+            .collect()
+    }
+
+    #[test]
+    fn kern_table() {
+        let kern = words(&[
+            0, 1, // Header
+            0, 20, 0x0001, // Format 0 horizontal subtable header
+            1, 6, 0, 0, // Pair count and search fields
+            1, 1, -50, // Pair of glyph 1 with itself
+        ]);
+        let data = build_opentype(vec![(b"CFF ", square_cff()), (b"kern", kern)]);
+        assert_eq!(kerning(data), vec![(65, 65, -1024)]);
+    }
+
+    #[test]
+    fn gpos_pair_kerning() {
+        // A format 1 subtable with an x-advance for the pair of glyph 1 with itself.
+        let pair_pos = words(&[
+            1, 12, 0b100, 0, 1, 18, // Header
+            1, 1, 1, // Coverage
+            1, 1, -100, // Pair set
+        ]);
+        let data = build_opentype(vec![
+            (b"CFF ", square_cff()),
+            (b"GPOS", gpos_kern(2, pair_pos)),
+        ]);
+        assert_eq!(kerning(data), vec![(65, 65, -2048)]);
+    }
+
+    #[test]
+    fn gpos_class_kerning() {
+        // A format 2 subtable in an extension lookup, with glyph 1 in class 1 on both sides.
+        let extension = words(&[
+            1, 2, 0, 8, // Extension header
+            2, 24, 0b100, 0, 30, 40, 2, 2, // Header
+            0, 0, 0, -25, // Class records
+            1, 1, 1, // Coverage
+            2, 1, 1, 1, 1, // First class definition
+            1, 1, 1, 1, // Second class definition
+        ]);
+        let data = build_opentype(vec![
+            (b"CFF ", square_cff()),
+            (b"GPOS", gpos_kern(9, extension)),
+        ]);
+        assert_eq!(kerning(data), vec![(65, 65, -512)]);
+    }
+
+    #[test]
+    fn invalid_font() {
+        assert!(OpenTypeFont::parse(b"not a font".to_vec(), 0).is_err());
+        assert!(OpenTypeFont::parse(build_opentype(vec![]), 0).is_err());
+        assert!(read_font_faces(&mut Cursor::new(b"not a font")).is_err());
+    }
+}
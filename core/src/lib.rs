@@ -12,13 +12,17 @@ mod character;
 mod color_transform;
 mod context;
 pub mod events;
+mod flv;
 mod font;
 mod library;
 pub mod matrix;
+mod net_stream;
 mod player;
 mod prelude;
 pub mod shape_utils;
+mod streaming;
 pub mod tag_utils;
+mod text_layout;
 mod transform;
 mod xml;
 
@@ -27,4 +31,5 @@ pub mod backend;
 pub use events::PlayerEvent;
 pub use player::Player;
 pub use swf;
+pub use swf::Codepage;
 pub use swf::Color;
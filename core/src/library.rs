@@ -1,8 +1,9 @@
 use crate::avm1::globals::SystemPrototypes;
 use crate::avm1::Object;
 use crate::backend::audio::SoundHandle;
+use crate::backend::font::{device_font_families, FontBackend};
 use crate::character::Character;
-use crate::display_object::TDisplayObject;
+use crate::display_object::{MorphShape, TDisplayObject, Video};
 use crate::font::Font;
 use crate::prelude::*;
 use gc_arena::MutationContext;
@@ -14,6 +15,10 @@ pub struct Library<'gc> {
     export_characters: HashMap<String, Character<'gc>>,
     jpeg_tables: Option<Vec<u8>>,
     device_font: Option<Font<'gc>>,
+
+    /// The device fonts loaded from the font backend, by name and style.
+    /// `None` if no matching font is installed.
+    device_fonts: HashMap<(String, bool, bool), Option<Font<'gc>>>,
 }
 
 impl<'gc> Library<'gc> {
@@ -23,6 +28,7 @@ impl<'gc> Library<'gc> {
             export_characters: HashMap::new(),
             jpeg_tables: None,
             device_font: None,
+            device_fonts: HashMap::new(),
         }
     }
 
@@ -128,6 +134,7 @@ impl<'gc> Library<'gc> {
             }
             Character::Button(button) => (button.instantiate(gc_context), prototypes.object),
             Character::Text(text) => (text.instantiate(gc_context), prototypes.object),
+            Character::Video(video) => (video.instantiate(gc_context), prototypes.video),
             _ => return Err("Not a DisplayObject".into()),
         };
         obj.post_instantiation(gc_context, obj, proto);
@@ -142,6 +149,22 @@ impl<'gc> Library<'gc> {
         }
     }
 
+    pub fn get_morph_shape(&self, id: CharacterId) -> Option<MorphShape<'gc>> {
+        if let Some(&Character::MorphShape(morph_shape)) = self.characters.get(&id) {
+            Some(morph_shape)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_video(&self, id: CharacterId) -> Option<Video<'gc>> {
+        if let Some(&Character::Video(video)) = self.characters.get(&id) {
+            Some(video)
+        } else {
+            None
+        }
+    }
+
     pub fn get_sound(&self, id: CharacterId) -> Option<SoundHandle> {
         if let Some(Character::Sound(sound)) = self.characters.get(&id) {
             Some(*sound)
@@ -179,6 +202,52 @@ impl<'gc> Library<'gc> {
     pub fn set_device_font(&mut self, font: Option<Font<'gc>>) {
         self.device_font = font;
     }
+
+    /// Returns the installed device font with the given name and style.
+    /// If no matching font is installed, the default device font is returned instead.
+    pub fn get_device_font(
+        &mut self,
+        gc_context: MutationContext<'gc, '_>,
+        font_backend: &mut dyn FontBackend,
+        name: &str,
+        is_bold: bool,
+        is_italic: bool,
+    ) -> Option<Font<'gc>> {
+        let key = (name.to_lowercase(), is_bold, is_italic);
+        let font = *self.device_fonts.entry(key).or_insert_with(|| {
+            device_font_families(name).into_iter().find_map(|family| {
+                let (data, index) = font_backend.load_font(family, is_bold, is_italic)?;
+                match Font::from_device_font_data(
+                    gc_context, data, index, family, is_bold, is_italic,
+                ) {
+                    Ok(font) if font.has_glyphs() => Some(font),
+                    Ok(_) => None,
+                    Err(e) => {
+                        log::warn!("Unable to load device font {}: {}", family, e);
+                        None
+                    }
+                }
+            })
+        });
+        font.or(self.device_font)
+    }
+
+    /// Sets the name and style of a font, as described by a `DefineFontInfo` or
+    /// `DefineFontName` tag. These are used to find a matching device font.
+    pub fn set_font_name_and_style(
+        &mut self,
+        gc_context: MutationContext<'gc, '_>,
+        id: CharacterId,
+        name: &str,
+        is_bold: bool,
+        is_italic: bool,
+    ) {
+        if let Some(Character::Font(font)) = self.characters.get_mut(&id) {
+            *font = font.with_name_and_style(gc_context, name, is_bold, is_italic);
+        } else {
+            log::warn!("Can't set font info: Character ID {} is not a font", id);
+        }
+    }
 }
 
 unsafe impl<'gc> gc_arena::Collect for Library<'gc> {
@@ -188,6 +257,9 @@ unsafe impl<'gc> gc_arena::Collect for Library<'gc> {
             character.trace(cc);
         }
         self.device_font.trace(cc);
+        for font in self.device_fonts.values() {
+            font.trace(cc);
+        }
     }
 }
 
@@ -0,0 +1,635 @@
+//! Playback of the files played by AVM1 `NetStream` objects.
+mod audio;
+
+use crate::avm1::{NetStreamObject, Object};
+use crate::backend::audio::AudioStreamHandle;
+use crate::backend::navigator::{FetchHandle, FetchStatus};
+use crate::backend::render::BitmapInfo;
+use crate::backend::video::{EncodedFrame, VideoStreamHandle};
+use crate::context::UpdateContext;
+use crate::flv::{amf0, Flv, ScriptTag, Tag, TagData, VideoTag};
+use audio::StreamAudio;
+use gc_arena::{Collect, CollectionContext};
+use std::sync::Arc;
+use swf::{VideoCodec, VideoDeblocking};
+
+/// Keeps track of the `NetStream` objects that are loading or playing a file,
+/// and advances them every frame.
+pub struct NetStreamManager<'gc> {
+    streams: Vec<NetStreamObject<'gc>>,
+}
+
+impl<'gc> NetStreamManager<'gc> {
+    pub fn new() -> Self {
+        Self {
+            streams: Vec::new(),
+        }
+    }
+
+    /// Starts updating a stream every frame, until it stops playing.
+    pub fn add(&mut self, stream: NetStreamObject<'gc>) {
+        if !self
+            .streams
+            .iter()
+            .any(|s| Object::ptr_eq((*s).into(), stream.into()))
+        {
+            self.streams.push(stream);
+        }
+    }
+
+    /// Polls the loads of the active streams and advances their playback by the duration
+    /// of a frame, queueing any `onStatus` and script data events.
+    pub fn update_streams(context: &mut UpdateContext<'_, 'gc, '_>, frame_time: f64) {
+        let streams = std::mem::take(&mut context.net_stream_manager.streams);
+        for stream in &streams {
+            stream.update(context, frame_time);
+        }
+        // Streams may have been started while updating.
+        let started = std::mem::replace(&mut context.net_stream_manager.streams, streams);
+        for stream in started {
+            context.net_stream_manager.add(stream);
+        }
+        context
+            .net_stream_manager
+            .streams
+            .retain(|stream| stream.is_active());
+    }
+}
+
+impl Default for NetStreamManager<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<'gc> Collect for NetStreamManager<'gc> {
+    fn trace(&self, cc: CollectionContext) {
+        for stream in &self.streams {
+            stream.trace(cc);
+        }
+    }
+}
+
+/// An event of a `NetStream`, dispatched to the script object that plays it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetStreamEvent {
+    /// A call of `onStatus`, with the code and level of the info object.
+    Status {
+        code: &'static str,
+        level: &'static str,
+    },
+
+    /// A script data event of the file, such as `onMetaData`.
+    Script(ScriptTag),
+}
+
+/// The playback of an FLV file.
+///
+/// The file is fetched through the navigator backend as soon as `play` is called, and is
+/// played once it has loaded. The navigator backend doesn't expose the data of a fetch
+/// until it is complete, so playback can't start while the file is still loading.
+///
+/// The playhead follows the audio of the file while it plays, and the frame rate of the
+/// movie otherwise.
+pub struct NetStream {
+    /// The fetch of the file being played, until it has loaded.
+    load: Option<FetchHandle>,
+
+    /// The demuxed file being played.
+    flv: Option<Arc<Flv>>,
+
+    /// The video stream that the frames of the file are decoded by.
+    video_stream: Option<VideoStreamHandle>,
+
+    /// The codec of the video stream.
+    video_codec: Option<VideoCodec>,
+
+    /// The last frame that was decoded. Displayed by the `Video` objects attached to this stream.
+    decoded_frame: Option<BitmapInfo>,
+
+    /// The audio packets of the file, played as stream sounds.
+    audio: Option<Arc<StreamAudio>>,
+
+    /// The stream sound playing the audio from the playhead.
+    audio_stream: Option<AudioStreamHandle>,
+
+    /// The time that the playing stream sound starts at, in milliseconds.
+    audio_stream_start: u32,
+
+    /// The index of the next tag to play.
+    next_tag: usize,
+
+    /// The position of the playhead in milliseconds.
+    time: f64,
+
+    /// The duration of the file in milliseconds.
+    duration: f64,
+
+    /// Whether the stream is playing a file, until it reaches the end or is closed.
+    is_playing: bool,
+
+    is_paused: bool,
+
+    bytes_loaded: u32,
+    bytes_total: u32,
+
+    /// The amount of data to buffer before playback starts, in seconds.
+    /// Set by `NetStream.setBufferTime`.
+    buffer_time: f64,
+
+    /// The events that haven't been dispatched yet.
+    events: Vec<NetStreamEvent>,
+}
+
+impl NetStream {
+    pub fn new() -> Self {
+        Self {
+            load: None,
+            flv: None,
+            video_stream: None,
+            video_codec: None,
+            decoded_frame: None,
+            audio: None,
+            audio_stream: None,
+            audio_stream_start: 0,
+            next_tag: 0,
+            time: 0.0,
+            duration: 0.0,
+            is_playing: false,
+            is_paused: false,
+            bytes_loaded: 0,
+            bytes_total: 0,
+            buffer_time: 0.1,
+            events: vec![],
+        }
+    }
+
+    /// The position of the playhead in seconds.
+    pub fn time(&self) -> f64 {
+        self.time / 1000.0
+    }
+
+    /// The number of seconds of the file that are loaded ahead of the playhead.
+    pub fn buffer_length(&self) -> f64 {
+        if self.flv.is_some() {
+            ((self.duration - self.time) / 1000.0).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn buffer_time(&self) -> f64 {
+        self.buffer_time
+    }
+
+    pub fn set_buffer_time(&mut self, buffer_time: f64) {
+        self.buffer_time = buffer_time;
+    }
+
+    pub fn bytes_loaded(&self) -> u32 {
+        self.bytes_loaded
+    }
+
+    pub fn bytes_total(&self) -> u32 {
+        self.bytes_total
+    }
+
+    /// The last frame of video that was decoded.
+    pub fn decoded_frame(&self) -> Option<BitmapInfo> {
+        self.decoded_frame
+    }
+
+    /// Whether the stream is loading or playing a file.
+    pub fn is_active(&self) -> bool {
+        self.load.is_some() || self.is_playing
+    }
+
+    /// Returns the events that happened since the last call, in order.
+    pub fn take_events(&mut self) -> Vec<NetStreamEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Begins loading and playing the file at the given URL.
+    pub fn play(&mut self, context: &mut UpdateContext<'_, '_, '_>, url: String) {
+        self.close(context);
+        self.load = Some(context.navigator.fetch(url));
+        self.is_playing = true;
+        self.queue_status("NetStream.Play.Start", "status");
+    }
+
+    /// Stops playing and unloads the current file.
+    pub fn close(&mut self, context: &mut UpdateContext<'_, '_, '_>) {
+        self.stop_sound(context);
+        self.release_video(context);
+        self.load = None;
+        self.flv = None;
+        self.audio = None;
+        self.next_tag = 0;
+        self.time = 0.0;
+        self.duration = 0.0;
+        self.is_playing = false;
+        self.is_paused = false;
+        self.bytes_loaded = 0;
+        self.bytes_total = 0;
+    }
+
+    /// Pauses or resumes playback. `None` toggles between the two.
+    pub fn pause(&mut self, context: &mut UpdateContext<'_, '_, '_>, is_paused: Option<bool>) {
+        let is_paused = is_paused.unwrap_or(!self.is_paused);
+        if is_paused == self.is_paused || !self.is_playing {
+            return;
+        }
+        self.is_paused = is_paused;
+        if is_paused {
+            self.stop_sound(context);
+            self.queue_status("NetStream.Pause.Notify", "status");
+        } else {
+            self.start_sound(context);
+            self.queue_status("NetStream.Unpause.Notify", "status");
+        }
+    }
+
+    /// Moves the playhead to the keyframe closest before the given time in seconds.
+    pub fn seek(&mut self, context: &mut UpdateContext<'_, '_, '_>, seconds: f64) {
+        let flv = match &self.flv {
+            Some(flv) => Arc::clone(flv),
+            None => return,
+        };
+        let target = (seconds * 1000.0).max(0.0);
+        let video_codec = self.video_codec;
+        let keyframe = flv.tags.iter().enumerate().rev().find(|(_, tag)| {
+            f64::from(tag.timestamp) <= target
+                && match &tag.data {
+                    TagData::Video(video) => video.is_keyframe && video.codec == video_codec,
+                    _ => false,
+                }
+        });
+        let (next_tag, time) = match keyframe {
+            Some((index, tag)) => (index, f64::from(tag.timestamp)),
+            // Files without video can seek to any time.
+            None => (
+                flv.tags
+                    .iter()
+                    .position(|tag| f64::from(tag.timestamp) >= target)
+                    .unwrap_or_else(|| flv.tags.len()),
+                target.min(self.duration),
+            ),
+        };
+
+        self.stop_sound(context);
+        self.next_tag = next_tag;
+        self.time = time;
+        // A stream that reached its end plays again from the new position.
+        self.is_playing = true;
+        self.advance(context, time);
+        if !self.is_paused {
+            self.start_sound(context);
+        }
+        self.queue_status("NetStream.Seek.Notify", "status");
+    }
+
+    /// Polls the load of the file, and advances the playhead by the given number of
+    /// milliseconds. Called by the `NetStreamManager` every frame.
+    pub fn update(&mut self, context: &mut UpdateContext<'_, '_, '_>, frame_time: f64) {
+        if let Some(handle) = self.load {
+            match context.navigator.poll_fetch(handle) {
+                FetchStatus::Loading {
+                    bytes_loaded,
+                    bytes_total,
+                } => {
+                    self.bytes_loaded = bytes_loaded as u32;
+                    self.bytes_total = bytes_total.unwrap_or(0) as u32;
+                    return;
+                }
+                FetchStatus::Complete(data) => self.load_file(context, &data),
+                FetchStatus::Failed => {
+                    self.close(context);
+                    self.queue_status("NetStream.Play.StreamNotFound", "error");
+                }
+            }
+            // Playback starts on the next frame.
+            return;
+        }
+
+        if !self.is_playing || self.is_paused {
+            return;
+        }
+
+        let audio_stream = self.audio_stream;
+        let stream_position = audio_stream.and_then(|s| context.audio.get_stream_position(s));
+        if audio_stream.is_some() && stream_position.is_none() {
+            // The audio reached a gap or the end of the file.
+            self.stop_sound(context);
+        }
+        self.time = if let Some(position) = stream_position {
+            f64::from(self.audio_stream_start) + f64::from(position)
+        } else {
+            self.time + frame_time
+        };
+        self.advance(context, self.time);
+
+        let num_tags = self.flv.as_ref().map(|flv| flv.tags.len()).unwrap_or(0);
+        if self.next_tag >= num_tags && self.time >= self.duration && stream_position.is_none() {
+            self.stop_sound(context);
+            self.is_playing = false;
+            self.time = self.duration;
+            self.queue_status("NetStream.Play.Stop", "status");
+            self.queue_status("NetStream.Buffer.Empty", "status");
+        }
+    }
+
+    /// Demuxes a loaded file and prepares its audio and video for playback.
+    fn load_file(&mut self, context: &mut UpdateContext<'_, '_, '_>, file_data: &[u8]) {
+        let flv = match Flv::parse(file_data) {
+            Ok(flv) => flv,
+            Err(e) => {
+                log::warn!("NetStream.play: Unable to play FLV: {}", e);
+                self.close(context);
+                self.queue_status("NetStream.Play.StreamNotFound", "error");
+                return;
+            }
+        };
+
+        let metadata = flv.metadata();
+        let metadata_number = |name| {
+            metadata
+                .and_then(|metadata| metadata.get(name))
+                .and_then(amf0::Value::as_number)
+        };
+        let last_timestamp = flv.tags.last().map(|tag| tag.timestamp).unwrap_or(0);
+        let duration = metadata_number("duration")
+            .map(|duration| duration * 1000.0)
+            .unwrap_or_else(|| f64::from(last_timestamp));
+
+        // Without metadata, the size of the video isn't known, and the decoded frames
+        // are displayed uncropped.
+        let size = (
+            metadata_number("width")
+                .map(|n| n as u16)
+                .unwrap_or(u16::MAX),
+            metadata_number("height")
+                .map(|n| n as u16)
+                .unwrap_or(u16::MAX),
+        );
+        let video_codec = flv.tags.iter().find_map(|tag| match &tag.data {
+            TagData::Video(video) => video.codec,
+            _ => None,
+        });
+        self.release_video(context);
+        let video_stream = video_codec.and_then(|codec| {
+            let num_frames = flv
+                .tags
+                .iter()
+                .filter(|tag| matches_video(tag, codec))
+                .count();
+            match context.video.register_video_stream(
+                0,
+                num_frames as u32,
+                size,
+                codec,
+                VideoDeblocking::UseVideoPacketValue,
+            ) {
+                Ok(stream) => Some(stream),
+                Err(e) => {
+                    log::warn!("NetStream.play: Unable to play video: {}", e);
+                    None
+                }
+            }
+        });
+
+        let len = file_data.len() as u32;
+        self.load = None;
+        self.bytes_loaded = len;
+        self.bytes_total = len;
+        self.duration = duration;
+        self.video_codec = video_codec;
+        self.video_stream = video_stream;
+        self.audio = StreamAudio::from_flv(&flv).map(Arc::new);
+        self.flv = Some(Arc::new(flv));
+
+        self.queue_status("NetStream.Buffer.Full", "status");
+        self.advance(context, 0.0);
+        if !self.is_paused {
+            self.start_sound(context);
+        }
+    }
+
+    /// Plays the tags up to the given time, decoding video frames and queueing script events.
+    fn advance(&mut self, context: &mut UpdateContext<'_, '_, '_>, time: f64) {
+        let flv = match &self.flv {
+            Some(flv) => Arc::clone(flv),
+            None => return,
+        };
+        while let Some(tag) = flv.tags.get(self.next_tag) {
+            if f64::from(tag.timestamp) > time {
+                break;
+            }
+            let index = self.next_tag;
+            self.next_tag += 1;
+            match &tag.data {
+                TagData::Video(video) => self.decode_video(context, video),
+                TagData::Script(script) => self.events.push(NetStreamEvent::Script(script.clone())),
+                TagData::Audio(_) => self.start_audio_run(context, index),
+            }
+        }
+    }
+
+    fn decode_video(&mut self, context: &mut UpdateContext<'_, '_, '_>, video: &VideoTag) {
+        let (stream, codec) = match (self.video_stream, self.video_codec) {
+            (Some(stream), Some(codec)) if video.codec == Some(codec) => (stream, codec),
+            _ => return,
+        };
+        let encoded_frame = EncodedFrame {
+            codec,
+            data: &video.data,
+            frame_id: self.next_tag as u32,
+        };
+        self.decoded_frame =
+            match context
+                .video
+                .decode_video_stream_frame(stream, encoded_frame, context.renderer)
+            {
+                Ok(bitmap) => Some(bitmap),
+                Err(e) => {
+                    log::warn!("NetStream: Unable to decode video frame: {}", e);
+                    None
+                }
+            };
+    }
+
+    /// Unregisters the video stream of the file, releasing its decoded frame.
+    fn release_video(&mut self, context: &mut UpdateContext<'_, '_, '_>) {
+        if let Some(stream) = self.video_stream.take() {
+            context
+                .video
+                .unregister_video_stream(stream, context.renderer);
+        }
+        self.video_codec = None;
+        self.decoded_frame = None;
+    }
+
+    /// Starts the audio of the file from the playhead, unless it's already playing.
+    fn start_sound(&mut self, context: &mut UpdateContext<'_, '_, '_>) {
+        let audio = match &self.audio {
+            Some(audio) if self.is_playing && self.audio_stream.is_none() => Arc::clone(audio),
+            _ => return,
+        };
+        let (packet, clip_data) = match audio.stream_from(self.time) {
+            Some(stream) => stream,
+            // The playhead is in a gap in the audio.
+            None => return,
+        };
+        let stream = context.audio_manager.start_stream(
+            context.audio,
+            0,
+            0,
+            clip_data,
+            &audio.stream_info,
+            None,
+        );
+        self.audio_stream = Some(stream);
+        self.audio_stream_start = packet.timestamp;
+    }
+
+    /// Restarts the audio when the playhead reaches a tag of audio that follows a gap.
+    fn start_audio_run(&mut self, context: &mut UpdateContext<'_, '_, '_>, tag_index: usize) {
+        let is_run_start = match &self.audio {
+            Some(audio) => !self.is_paused && audio.is_run_start(tag_index),
+            None => false,
+        };
+        if is_run_start {
+            self.stop_sound(context);
+            self.start_sound(context);
+        }
+    }
+
+    fn stop_sound(&mut self, context: &mut UpdateContext<'_, '_, '_>) {
+        if let Some(stream) = self.audio_stream.take() {
+            context.audio_manager.stop_stream(context.audio, stream);
+        }
+    }
+
+    fn queue_status(&mut self, code: &'static str, level: &'static str) {
+        self.events.push(NetStreamEvent::Status { code, level });
+    }
+}
+
+impl Default for NetStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn matches_video(tag: &Tag, codec: VideoCodec) -> bool {
+    match &tag.data {
+        TagData::Video(video) => video.codec == Some(codec),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avm1::test_utils::with_avm_and_video;
+    use crate::backend::render::{BitmapHandle, RenderBackend};
+    use crate::backend::video::{FrameDependency, VideoBackend};
+    use crate::prelude::*;
+    use generational_arena::Arena;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    type Error = Box<dyn std::error::Error>;
+
+    /// An FFmpeg-written FLV file with metadata and four frames of 8x8 H.263 video.
+    const DUMMY_FLV: &[u8] = include_bytes!("../../swf/tests/swfs/dummy.flv");
+
+    /// A video backend that counts the streams that are registered.
+    struct TestVideoBackend {
+        streams: Arena<()>,
+        num_streams: Rc<Cell<usize>>,
+    }
+
+    impl VideoBackend for TestVideoBackend {
+        fn register_video_stream(
+            &mut self,
+            _id: CharacterId,
+            _num_frames: u32,
+            _size: (u16, u16),
+            _codec: VideoCodec,
+            _deblocking: VideoDeblocking,
+        ) -> Result<VideoStreamHandle, Error> {
+            let stream = self.streams.insert(());
+            self.num_streams.set(self.streams.len());
+            Ok(stream)
+        }
+
+        fn preload_video_stream_frame(
+            &mut self,
+            _stream: VideoStreamHandle,
+            _encoded_frame: EncodedFrame<'_>,
+        ) -> Result<FrameDependency, Error> {
+            Ok(FrameDependency::None)
+        }
+
+        fn decode_video_stream_frame(
+            &mut self,
+            _stream: VideoStreamHandle,
+            encoded_frame: EncodedFrame<'_>,
+            _renderer: &mut dyn RenderBackend,
+        ) -> Result<BitmapInfo, Error> {
+            Ok(BitmapInfo {
+                handle: BitmapHandle(encoded_frame.frame_id as usize),
+                width: 8,
+                height: 8,
+            })
+        }
+
+        fn unregister_video_stream(
+            &mut self,
+            stream: VideoStreamHandle,
+            _renderer: &mut dyn RenderBackend,
+        ) {
+            self.streams.remove(stream);
+            self.num_streams.set(self.streams.len());
+        }
+    }
+
+    #[test]
+    fn load_and_close_file() {
+        let num_streams = Rc::new(Cell::new(0));
+        let mut backend = TestVideoBackend {
+            streams: Arena::new(),
+            num_streams: num_streams.clone(),
+        };
+        with_avm_and_video(8, &mut backend, |_avm, context, _root| {
+            let mut stream = NetStream::new();
+            stream.load_file(context, DUMMY_FLV);
+            assert_eq!(num_streams.get(), 1);
+            assert_eq!(stream.bytes_total(), DUMMY_FLV.len() as u32);
+            assert!((stream.buffer_length() - 0.16).abs() < 1e-9);
+            // The first frame is shown as soon as the file has loaded.
+            assert_eq!(stream.decoded_frame().unwrap().handle.0, 2);
+            let events = stream.take_events();
+            assert_eq!(
+                events[0],
+                NetStreamEvent::Status {
+                    code: "NetStream.Buffer.Full",
+                    level: "status"
+                }
+            );
+            match &events[1] {
+                NetStreamEvent::Script(script) => assert_eq!(script.name, "onMetaData"),
+                event => panic!("Unexpected event {:?}", event),
+            }
+
+            // Loading another file replaces the video stream.
+            stream.load_file(context, DUMMY_FLV);
+            assert_eq!(num_streams.get(), 1);
+
+            stream.close(context);
+            assert_eq!(num_streams.get(), 0);
+            assert!(stream.decoded_frame().is_none());
+            assert!(!stream.is_active());
+        });
+    }
+}
@@ -0,0 +1,234 @@
+//! The audio of the files played by `NetStream`s.
+
+use crate::backend::audio::decoders;
+use crate::flv::{Flv, TagData};
+use crate::tag_utils::SwfSlice;
+use std::sync::Arc;
+use swf::{AudioCompression, SoundStreamHead, TagCode};
+
+/// Gaps between audio packets up to this many milliseconds are played without silence,
+/// as the timestamps are rounded and the lengths of some packets aren't known exactly.
+const MAX_AUDIO_GAP: f64 = 50.0;
+
+/// The audio of a file, rewrapped in `SoundStreamBlock` tags to be decoded by the
+/// stream decoders as it plays.
+///
+/// The audio is split into runs of packets that follow each other without a gap.
+/// Each run is played as a stream sound starting at the timestamp of its first packet.
+#[derive(Debug)]
+pub struct StreamAudio {
+    /// The format of the audio, from the first packet.
+    pub stream_info: SoundStreamHead,
+
+    /// The `SoundStreamBlock` tags of the packets.
+    data: Arc<Vec<u8>>,
+
+    packets: Vec<AudioPacket>,
+}
+
+/// A packet of audio in a `StreamAudio`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioPacket {
+    /// The index of the FLV tag of the packet.
+    tag_index: usize,
+
+    /// The time that the packet starts at, in milliseconds.
+    pub timestamp: u32,
+
+    /// The time that the audio of the packet ends at, in milliseconds.
+    end: f64,
+
+    /// The position of the `SoundStreamBlock` tag of the packet.
+    offset: usize,
+
+    /// Whether the packet starts a run, after a gap or at the start of the audio.
+    is_run_start: bool,
+}
+
+impl StreamAudio {
+    /// Collects the audio tags of a file. Tags in other formats than the first are skipped.
+    pub fn from_flv(flv: &Flv) -> Option<Self> {
+        let mut audio_tags = flv
+            .tags
+            .iter()
+            .enumerate()
+            .filter_map(|(index, tag)| match &tag.data {
+                TagData::Audio(audio) => Some((index, tag.timestamp, audio)),
+                _ => None,
+            })
+            .peekable();
+        let format = audio_tags.peek()?.2.format.clone()?;
+        if format.compression == AudioCompression::Speex {
+            log::warn!("NetStream: Speex audio is not supported");
+            return None;
+        }
+        let sample_rate = f64::from(decoders::decoded_sample_rate(&format));
+
+        let mut data = vec![];
+        let mut packets: Vec<AudioPacket> = vec![];
+        for (tag_index, timestamp, audio) in audio_tags {
+            if audio.format.as_ref() != Some(&format) {
+                continue;
+            }
+            let duration = decoders::num_sample_frames(&format, &audio.data)
+                .map(|num_sample_frames| f64::from(num_sample_frames) * 1000.0 / sample_rate);
+            let is_run_start = match packets.last() {
+                Some(previous) => f64::from(timestamp) > previous.end + MAX_AUDIO_GAP,
+                None => true,
+            };
+            let start = match packets.last() {
+                Some(previous) if !is_run_start => previous.end,
+                _ => f64::from(timestamp),
+            };
+            packets.push(AudioPacket {
+                tag_index,
+                timestamp,
+                // Packets of unknown length continue until the next packet.
+                end: duration.map_or(f64::INFINITY, |duration| start + duration),
+                offset: data.len(),
+                is_run_start,
+            });
+
+            // MP3 stream blocks start with the sample count and the samples to skip.
+            let header_len = if format.compression == AudioCompression::Mp3 {
+                4
+            } else {
+                0
+            };
+            let len = (audio.data.len() + header_len) as u32;
+            // Always use the long tag header.
+            data.extend_from_slice(&((TagCode::SoundStreamBlock as u16) << 6 | 0x3f).to_le_bytes());
+            data.extend_from_slice(&len.to_le_bytes());
+            if header_len > 0 {
+                let num_sample_frames = decoders::num_sample_frames(&format, &audio.data);
+                data.extend_from_slice(&(num_sample_frames.unwrap_or(0) as u16).to_le_bytes());
+                data.extend_from_slice(&0i16.to_le_bytes());
+            }
+            data.extend_from_slice(&audio.data);
+        }
+
+        Some(Self {
+            stream_info: SoundStreamHead {
+                stream_format: format.clone(),
+                playback_format: format,
+                num_samples_per_block: 0,
+                latency_seek: 0,
+            },
+            data: Arc::new(data),
+            packets,
+        })
+    }
+
+    pub fn is_run_start(&self, tag_index: usize) -> bool {
+        match self
+            .packets
+            .binary_search_by_key(&tag_index, |packet| packet.tag_index)
+        {
+            Ok(index) => self.packets[index].is_run_start,
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the packet to play from at the given time, and the stream blocks from that
+    /// packet to the end of its run. Returns `None` if there's no audio at that time.
+    pub fn stream_from(&self, time: f64) -> Option<(&AudioPacket, SwfSlice)> {
+        let index = self
+            .packets
+            .iter()
+            .rposition(|packet| f64::from(packet.timestamp) <= time)?;
+        let run_end = self.packets[index + 1..]
+            .iter()
+            .position(|packet| packet.is_run_start)
+            .map_or(self.packets.len(), |len| index + 1 + len);
+        if time >= self.packets[run_end - 1].end {
+            return None;
+        }
+        let end = self
+            .packets
+            .get(run_end)
+            .map_or(self.data.len(), |packet| packet.offset);
+        let packet = &self.packets[index];
+        Some((
+            packet,
+            SwfSlice {
+                data: Arc::clone(&self.data),
+                start: packet.offset,
+                end,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flv::{AudioTag, Tag};
+    use swf::SoundFormat;
+
+    const FORMAT: SoundFormat = SoundFormat {
+        compression: AudioCompression::Uncompressed,
+        sample_rate: 8000,
+        is_16_bit: true,
+        is_stereo: false,
+    };
+
+    /// A 10ms packet of 16-bit PCM at 8kHz, with every sample set to `value`.
+    fn audio_tag(timestamp: u32, format: &SoundFormat, value: i16) -> Tag {
+        Tag {
+            timestamp,
+            data: TagData::Audio(AudioTag {
+                format: Some(format.clone()),
+                data: value.to_le_bytes().repeat(80),
+            }),
+        }
+    }
+
+    /// Decodes the stream blocks with the stream decoders, and returns the first sample
+    /// of each packet.
+    fn decode_packets(audio: &StreamAudio, clip_data: SwfSlice) -> Vec<i16> {
+        decoders::make_stream_decoder(&audio.stream_info.stream_format, clip_data, 8)
+            .map(|frame| frame[0])
+            .step_by(80)
+            .collect()
+    }
+
+    #[test]
+    fn audio_runs_follow_timestamps() {
+        let other_format = SoundFormat {
+            sample_rate: 11025,
+            ..FORMAT
+        };
+        let flv = Flv {
+            has_audio: true,
+            has_video: false,
+            tags: vec![
+                audio_tag(0, &FORMAT, 1),
+                audio_tag(10, &FORMAT, 2),
+                // Packets in other formats are skipped.
+                audio_tag(15, &other_format, 99),
+                // A gap shorter than `MAX_AUDIO_GAP` is played without silence.
+                audio_tag(30, &FORMAT, 3),
+                audio_tag(200, &FORMAT, 4),
+            ],
+        };
+        let audio = StreamAudio::from_flv(&flv).unwrap();
+        let run_starts: Vec<_> = audio.packets.iter().map(|p| p.is_run_start).collect();
+        assert_eq!(run_starts, [true, false, false, true]);
+        assert!(audio.is_run_start(0));
+        assert!(!audio.is_run_start(1));
+        assert!(!audio.is_run_start(2));
+        assert!(audio.is_run_start(4));
+
+        // The stream plays from the packet at the playhead to the end of its run.
+        let (packet, clip_data) = audio.stream_from(15.0).unwrap();
+        assert_eq!(packet.timestamp, 10);
+        assert_eq!(decode_packets(&audio, clip_data), [2, 3]);
+        let (packet, clip_data) = audio.stream_from(205.0).unwrap();
+        assert_eq!(packet.timestamp, 200);
+        assert_eq!(decode_packets(&audio, clip_data), [4]);
+
+        // There's no audio in the gap, or after the end.
+        assert!(audio.stream_from(100.0).is_none());
+        assert!(audio.stream_from(210.0).is_none());
+    }
+}
@@ -2,19 +2,24 @@ use crate::avm1::listeners::SystemListener;
 use crate::avm1::Avm1;
 use crate::backend::input::InputBackend;
 use crate::backend::{
-    audio::AudioBackend, navigator::NavigatorBackend, render::Letterbox, render::RenderBackend,
+    audio::AudioBackend, audio::AudioManager, audio::StreamSync, font::FontBackend,
+    navigator::NavigatorBackend, render::Letterbox, render::RenderBackend, video::VideoBackend,
 };
 use crate::context::{ActionQueue, ActionType, RenderContext, UpdateContext};
-use crate::display_object::{MorphShape, MovieClip};
-use crate::events::{ButtonEvent, ButtonKeyCode, ClipEvent, PlayerEvent};
+use crate::display_object::{EditText, MovieClip};
+use crate::events::{ButtonEvent, ButtonKeyCode, ClipEvent, KeyCode, PlayerEvent};
 use crate::library::Library;
+use crate::net_stream::NetStreamManager;
 use crate::prelude::*;
+use crate::streaming::SwfDecoder;
+use crate::tag_utils;
 use crate::transform::TransformStack;
 use gc_arena::{make_arena, ArenaParameters, Collect, GcCell};
 use log::info;
 use rand::{rngs::SmallRng, SeedableRng};
 use std::convert::TryFrom;
 use std::sync::Arc;
+use swf::Codepage;
 
 static DEVICE_FONT_TAG: &[u8] = include_bytes!("../assets/noto-sans-definefont3.bin");
 
@@ -36,6 +41,15 @@ struct GcRootData<'gc> {
     /// The object being dragged via a `startDrag` action.
     drag_object: Option<DragObject<'gc>>,
 
+    /// The text field that has keyboard focus.
+    focused_text_field: Option<EditText<'gc>>,
+
+    /// The sounds started by the movie and their sound transforms.
+    audio_manager: AudioManager<'gc>,
+
+    /// The `NetStream` objects that are loading or playing a file.
+    net_stream_manager: NetStreamManager<'gc>,
+
     avm: Avm1<'gc>,
     action_queue: ActionQueue<'gc>,
 }
@@ -51,6 +65,9 @@ impl<'gc> GcRootData<'gc> {
         &mut ActionQueue<'gc>,
         &mut Avm1<'gc>,
         &mut Option<DragObject<'gc>>,
+        &mut Option<EditText<'gc>>,
+        &mut AudioManager<'gc>,
+        &mut NetStreamManager<'gc>,
     ) {
         (
             self.root,
@@ -58,6 +75,9 @@ impl<'gc> GcRootData<'gc> {
             &mut self.action_queue,
             &mut self.avm,
             &mut self.drag_object,
+            &mut self.focused_text_field,
+            &mut self.audio_manager,
+            &mut self.net_stream_manager,
         )
     }
 }
@@ -70,6 +90,8 @@ pub struct Player<
     Renderer: RenderBackend,
     Navigator: NavigatorBackend,
     Input: InputBackend,
+    Fonts: FontBackend,
+    Video: VideoBackend,
 > {
     /// The version of the player we're emulating.
     ///
@@ -86,12 +108,24 @@ pub struct Player<
     swf_data: Arc<Vec<u8>>,
     swf_version: u8,
 
+    /// Decompresses the SWF file as it is loaded.
+    swf_decoder: SwfDecoder,
+
+    /// The codepage used to decode strings in SWF 5 and earlier.
+    /// This emulates the system locale of the machine playing the movie.
+    codepage: Codepage,
+
+    /// Whether external text is decoded with `codepage`. Set by `System.useCodepage`.
+    use_codepage: bool,
+
     is_playing: bool,
 
     audio: Audio,
     renderer: Renderer,
     navigator: Navigator,
     input: Input,
+    fonts: Fonts,
+    video: Video,
     transform_stack: TransformStack,
     view_matrix: Matrix,
     inverse_view_matrix: Matrix,
@@ -105,6 +139,12 @@ pub struct Player<
     frame_accumulator: f64,
     global_time: u64,
 
+    /// The last position of the stream sound that the timeline is synced to.
+    stream_position: Option<u32>,
+
+    /// How long the stream sound has been stuck at `stream_position`, in milliseconds.
+    stream_stall_time: f64,
+
     viewport_width: u32,
     viewport_height: u32,
     movie_width: u32,
@@ -120,51 +160,110 @@ impl<
         Renderer: RenderBackend,
         Navigator: NavigatorBackend,
         Input: InputBackend,
-    > Player<Audio, Renderer, Navigator, Input>
+        Fonts: FontBackend,
+        Video: VideoBackend,
+    > Player<Audio, Renderer, Navigator, Input, Fonts, Video>
 {
+    /// Creates a player for the given SWF file.
+    ///
+    /// `codepage` is the codepage of the emulated system, used to decode strings
+    /// in SWF 5 and earlier.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        mut renderer: Renderer,
+        renderer: Renderer,
         audio: Audio,
         navigator: Navigator,
         input: Input,
+        fonts: Fonts,
+        video: Video,
+        codepage: Codepage,
         swf_data: Vec<u8>,
     ) -> Result<Self, Error> {
-        let swf_stream = swf::read::read_swf_header(&swf_data[..]).unwrap();
-        let header = swf_stream.header;
-        let mut reader = swf_stream.reader;
-
-        // Decompress the entire SWF in memory.
-        // Sometimes SWFs will have an incorrectly compressed stream,
-        // but will otherwise decompress fine up to the End tag.
-        // So just warn on this case and try to continue gracefully.
-        let data = if header.compression == swf::Compression::Lzma {
-            // TODO: The LZMA decoder is still funky.
-            // It always errors, and doesn't return all the data if you use read_to_end,
-            // but read_exact at least returns the data... why?
-            // Does the decoder need to be flushed somehow?
-            let mut data = vec![0u8; swf_stream.uncompressed_length];
-            let _ = reader.get_mut().read_exact(&mut data);
-            data
-        } else {
-            let mut data = Vec::with_capacity(swf_stream.uncompressed_length);
-            if let Err(e) = reader.get_mut().read_to_end(&mut data) {
-                log::error!("Error decompressing SWF, may be corrupt: {}", e);
-            }
-            data
-        };
+        let mut swf_decoder = SwfDecoder::new();
+        let mut data = swf_decoder.decode(&swf_data)?;
+        data.extend(swf_decoder.finish()?);
+        Self::new_with_decoder(
+            renderer,
+            audio,
+            navigator,
+            input,
+            fonts,
+            video,
+            codepage,
+            swf_decoder,
+            data,
+        )
+    }
+
+    /// Creates a player for an SWF file that is still loading.
+    ///
+    /// `swf_data` is the start of the file, and must be long enough to contain the SWF header.
+    /// The rest of the file is passed to `append_swf_data` as it arrives, followed by a call
+    /// to `finish_swf_data`. The movie starts playing as soon as its first frame has loaded.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_streaming(
+        renderer: Renderer,
+        audio: Audio,
+        navigator: Navigator,
+        input: Input,
+        fonts: Fonts,
+        video: Video,
+        codepage: Codepage,
+        swf_data: &[u8],
+    ) -> Result<Self, Error> {
+        let mut swf_decoder = SwfDecoder::new();
+        let data = swf_decoder.decode(swf_data)?;
+        Self::new_with_decoder(
+            renderer,
+            audio,
+            navigator,
+            input,
+            fonts,
+            video,
+            codepage,
+            swf_decoder,
+            data,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_with_decoder(
+        mut renderer: Renderer,
+        audio: Audio,
+        navigator: Navigator,
+        input: Input,
+        fonts: Fonts,
+        video: Video,
+        codepage: Codepage,
+        swf_decoder: SwfDecoder,
+        mut data: Vec<u8>,
+    ) -> Result<Self, Error> {
+        let header = swf_decoder
+            .header()
+            .ok_or("Not enough data to read the SWF header")?;
+        let (version, stage_size, frame_rate, num_frames) = (
+            header.version,
+            header.stage_size.clone(),
+            header.frame_rate,
+            header.num_frames,
+        );
 
         let swf_len = data.len();
 
-        info!("{}x{}", header.stage_size.x_max, header.stage_size.y_max);
+        // Reserve space for the whole file, so that appending data doesn't reallocate.
+        data.reserve_exact(swf_decoder.tag_data_len().saturating_sub(swf_len));
 
-        let movie_width = (header.stage_size.x_max - header.stage_size.x_min).to_pixels() as u32;
-        let movie_height = (header.stage_size.y_max - header.stage_size.y_min).to_pixels() as u32;
+        info!("{}x{}", stage_size.x_max, stage_size.y_max);
+
+        let movie_width = (stage_size.x_max - stage_size.x_min).to_pixels() as u32;
+        let movie_height = (stage_size.y_max - stage_size.y_min).to_pixels() as u32;
 
         let mut player = Player {
             player_version: NEWEST_PLAYER_VERSION,
 
             swf_data: Arc::new(data),
-            swf_version: header.version,
+            swf_version: version,
+            swf_decoder,
 
             is_playing: false,
 
@@ -198,26 +297,27 @@ impl<
                     GcRootData {
                         library,
                         root: MovieClip::new_with_data(
-                            header.version,
-                            gc_context,
-                            0,
-                            0,
-                            swf_len,
-                            header.num_frames,
+                            version, gc_context, 0, 0, swf_len, num_frames,
                         )
                         .into(),
                         mouse_hovered_object: None,
                         drag_object: None,
+                        focused_text_field: None,
+                        audio_manager: AudioManager::new(),
+                        net_stream_manager: NetStreamManager::new(),
                         avm: Avm1::new(gc_context, NEWEST_PLAYER_VERSION),
                         action_queue: ActionQueue::new(),
                     },
                 ))
             }),
 
-            frame_rate: header.frame_rate.into(),
+            frame_rate: frame_rate.into(),
             frame_accumulator: 0.0,
             global_time: 0,
 
+            stream_position: None,
+            stream_stall_time: 0.0,
+
             movie_width,
             movie_height,
             viewport_width: movie_width,
@@ -227,10 +327,15 @@ impl<
             mouse_pos: (Twips::new(0), Twips::new(0)),
             is_mouse_down: false,
 
+            codepage,
+            use_codepage: false,
+
             renderer,
             audio,
             navigator,
             input,
+            fonts,
+            video,
         };
 
         player.gc_arena.mutate(|gc_context, gc_root| {
@@ -246,6 +351,48 @@ impl<
         Ok(player)
     }
 
+    /// Appends the next chunk of a loading SWF file, and preloads any newly available tags.
+    pub fn append_swf_data(&mut self, data: &[u8]) {
+        if self.swf_decoder.is_finished() {
+            return;
+        }
+
+        match self.swf_decoder.decode(data) {
+            Ok(data) => self.append_tag_data(&data),
+            Err(e) => {
+                log::error!("Error loading SWF: {}", e);
+                let _ = self.swf_decoder.finish();
+            }
+        }
+        self.preload();
+    }
+
+    /// Signals that the whole SWF file has been loaded.
+    pub fn finish_swf_data(&mut self) {
+        if self.swf_decoder.is_finished() {
+            return;
+        }
+
+        match self.swf_decoder.finish() {
+            Ok(data) => self.append_tag_data(&data),
+            Err(e) => log::error!("Error loading SWF: {}", e),
+        }
+        self.preload();
+    }
+
+    /// Adds newly loaded tag data to the end of the SWF data.
+    fn append_tag_data(&mut self, data: &[u8]) {
+        if self.swf_data.len() + data.len() > self.swf_data.capacity() {
+            log::warn!("SWF is longer than the length in its header");
+        }
+        tag_utils::append_swf_data(&mut self.swf_data, data);
+    }
+
+    /// Returns whether the SWF file has finished loading.
+    pub fn is_swf_loaded(&self) -> bool {
+        self.swf_decoder.is_finished()
+    }
+
     pub fn tick(&mut self, dt: f64) {
         // Don't run until preloading is complete.
         // TODO: Eventually we want to stream content similar to the Flash player.
@@ -258,20 +405,57 @@ impl<
             self.global_time += dt as u64;
             let frame_time = 1000.0 / self.frame_rate;
 
-            let needs_render = self.frame_accumulator >= frame_time;
+            let mut needs_render = self.frame_accumulator >= frame_time;
 
-            const MAX_FRAMES_PER_TICK: u32 = 5; // Sanity cap on frame tick.
-            let mut frame = 0;
-            while frame < MAX_FRAMES_PER_TICK && self.frame_accumulator >= frame_time {
-                self.frame_accumulator -= frame_time;
-                self.run_frame();
-                frame += 1;
+            // Audio can stop advancing without the stream ending, such as when the browser
+            // suspends the audio context. Stop waiting for the stream if it stalls.
+            const MAX_STREAM_STALL_TIME: f64 = 500.0;
+            let stream_sync = self.stream_sync();
+            let stream_position = stream_sync.map(|sync| sync.position);
+            if stream_position == self.stream_position {
+                self.stream_stall_time += dt;
+            } else {
+                self.stream_position = stream_position;
+                self.stream_stall_time = 0.0;
             }
+            let is_stream_stalled = self.stream_stall_time >= MAX_STREAM_STALL_TIME;
+
+            match stream_sync.map(|sync| sync.frames_behind) {
+                // The timeline fell behind a stream sound, so skip frames to catch up.
+                // Only the last frame is rendered.
+                Some(frames_behind) if frames_behind > 1 => {
+                    const MAX_SYNC_FRAMES_PER_TICK: i32 = 60; // Sanity cap on skipped frames.
+                    for _ in 0..frames_behind.min(MAX_SYNC_FRAMES_PER_TICK) {
+                        self.run_frame();
+                    }
+                    self.frame_accumulator = 0.0;
+                    needs_render = true;
+                }
+                // The timeline is ahead of a stream sound, so wait for the audio.
+                // If the audio has stalled, fall back to the frame rate.
+                Some(frames_behind) if frames_behind < -1 && !is_stream_stalled => {
+                    self.frame_accumulator = 0.0;
+                    needs_render = false;
+                }
+                _ => {
+                    const MAX_FRAMES_PER_TICK: u32 = 5; // Sanity cap on frame tick.
+                    let mut frame = 0;
+                    while frame < MAX_FRAMES_PER_TICK && self.frame_accumulator >= frame_time {
+                        self.frame_accumulator -= frame_time;
+                        self.run_frame();
+                        frame += 1;
+                    }
 
-            // Sanity: If we had too many frames to tick, just reset the accumulator
-            // to prevent running at turbo speed.
-            if self.frame_accumulator >= frame_time {
-                self.frame_accumulator = 0.0;
+                    // Sanity: If we had too many frames to tick, just reset the accumulator
+                    // to prevent running at turbo speed.
+                    if self.frame_accumulator >= frame_time {
+                        self.frame_accumulator = 0.0;
+                    }
+                }
+            }
+
+            if self.update_caret_blink() {
+                needs_render = true;
             }
 
             if needs_render {
@@ -282,6 +466,15 @@ impl<
         }
     }
 
+    /// Returns the progress of the playing stream sound that the timeline is synced to,
+    /// or `None` if there is no stream sound to sync to.
+    fn stream_sync(&mut self) -> Option<StreamSync> {
+        let frame_rate = self.frame_rate;
+        self.mutate_with_update_context(|_avm, context| {
+            context.audio_manager.stream_sync(context.audio, frame_rate)
+        })
+    }
+
     /// Returns the approximate duration of time until the next frame is due to run.
     /// This is only an approximation to be used for sleep durations.
     pub fn time_til_next_frame(&self) -> std::time::Duration {
@@ -373,15 +566,31 @@ impl<
         }
 
         // Propagte clip events.
-        let (clip_event, mouse_event_name) = match event {
-            PlayerEvent::KeyDown { .. } => (Some(ClipEvent::KeyDown), Some("onKeyDown")),
-            PlayerEvent::MouseMove { .. } => (Some(ClipEvent::MouseMove), Some("onMouseMove")),
-            PlayerEvent::MouseUp { .. } => (Some(ClipEvent::MouseUp), Some("onMouseUp")),
-            PlayerEvent::MouseDown { .. } => (Some(ClipEvent::MouseDown), Some("onMouseDown")),
+        let (clip_event, listener) = match event {
+            PlayerEvent::KeyDown { .. } => (
+                Some(ClipEvent::KeyDown),
+                Some((SystemListener::Key, "onKeyDown")),
+            ),
+            PlayerEvent::KeyUp { .. } => (
+                Some(ClipEvent::KeyUp),
+                Some((SystemListener::Key, "onKeyUp")),
+            ),
+            PlayerEvent::MouseMove { .. } => (
+                Some(ClipEvent::MouseMove),
+                Some((SystemListener::Mouse, "onMouseMove")),
+            ),
+            PlayerEvent::MouseUp { .. } => (
+                Some(ClipEvent::MouseUp),
+                Some((SystemListener::Mouse, "onMouseUp")),
+            ),
+            PlayerEvent::MouseDown { .. } => (
+                Some(ClipEvent::MouseDown),
+                Some((SystemListener::Mouse, "onMouseDown")),
+            ),
             _ => (None, None),
         };
 
-        if clip_event.is_some() || mouse_event_name.is_some() {
+        if clip_event.is_some() || listener.is_some() {
             self.mutate_with_update_context(|_avm, context| {
                 let root = context.root;
 
@@ -389,12 +598,12 @@ impl<
                     root.propagate_clip_event(context, clip_event);
                 }
 
-                if let Some(mouse_event_name) = mouse_event_name {
+                if let Some((listener, method)) = listener {
                     context.action_queue.queue_actions(
                         root,
                         ActionType::NotifyListeners {
-                            listener: SystemListener::Mouse,
-                            method: mouse_event_name,
+                            listener,
+                            method,
                             args: vec![],
                         },
                         false,
@@ -425,6 +634,62 @@ impl<
                 }
             }
 
+            // Clear focus from a text field that was removed from the display list.
+            if let Some(text_field) = *context.focused_text_field {
+                if text_field.removed() {
+                    EditText::remove_focus(context);
+                }
+            }
+
+            // Focus, select and edit text fields.
+            match event {
+                PlayerEvent::MouseDown { .. } => {
+                    let text_field = context
+                        .mouse_hovered_object
+                        .and_then(|node| node.as_edit_text());
+                    if let Some(text_field) = text_field {
+                        is_mouse_down = true;
+                        needs_render = true;
+                        text_field.focus(context);
+                        let extend = context.input.is_key_down(KeyCode::Shift);
+                        text_field.select_at_mouse(extend, context);
+                    } else if context.focused_text_field.is_some() {
+                        needs_render = true;
+                        EditText::remove_focus(context);
+                    }
+                }
+
+                PlayerEvent::MouseMove { .. } if is_mouse_down => {
+                    if let Some(text_field) = *context.focused_text_field {
+                        needs_render = true;
+                        text_field.select_at_mouse(true, context);
+                    }
+                }
+
+                PlayerEvent::MouseUp { .. } if context.focused_text_field.is_some() => {
+                    is_mouse_down = false;
+                }
+
+                PlayerEvent::TextInput { codepoint } => {
+                    if let Some(text_field) = *context.focused_text_field {
+                        if text_field.handle_text_input(codepoint, context) {
+                            needs_render = true;
+                        }
+                    }
+                }
+
+                PlayerEvent::KeyDown { key_code } => {
+                    if let Some(text_field) = *context.focused_text_field {
+                        if text_field.handle_key_down(key_code, context) {
+                            needs_render = true;
+                        }
+                    }
+                }
+
+                _ => (),
+            }
+
+            Self::update_sounds(context);
             Self::run_actions(avm, context);
         });
         self.is_mouse_down = is_mouse_down;
@@ -462,6 +727,18 @@ impl<
         });
     }
 
+    /// Blinks the caret of the focused text field.
+    /// Returns `true` if the caret was shown or hidden and the display needs to be re-rendered.
+    fn update_caret_blink(&mut self) -> bool {
+        self.mutate_with_update_context(|_avm, context| {
+            if let Some(text_field) = *context.focused_text_field {
+                text_field.update_caret_blink(context.global_time, context.gc_context)
+            } else {
+                false
+            }
+        })
+    }
+
     fn update_roll_over(&mut self) -> bool {
         // TODO: While the mouse is down, maintain the hovered node.
         if self.is_mouse_down {
@@ -490,6 +767,7 @@ impl<
 
                 context.mouse_hovered_object = new_hovered;
 
+                Self::update_sounds(context);
                 Self::run_actions(avm, context);
                 true
             } else {
@@ -498,28 +776,24 @@ impl<
         })
     }
 
+    /// Preloads the tags of the root movie that have loaded so far.
     fn preload(&mut self) {
+        let is_data_complete = self.swf_decoder.is_finished();
+        let swf_len = self.swf_data.len();
         self.mutate_with_update_context(|_avm, context| {
-            let mut morph_shapes = fnv::FnvHashMap::default();
-            let root = context.root;
-            root.as_movie_clip()
-                .unwrap()
-                .preload(context, &mut morph_shapes);
-
-            // Finalize morph shapes.
-            for (id, static_data) in morph_shapes {
-                let morph_shape = MorphShape::new(context.gc_context, static_data);
-                context
-                    .library
-                    .register_character(id, crate::character::Character::MorphShape(morph_shape));
-            }
+            let root = context.root.as_movie_clip().unwrap();
+            root.set_tag_stream_len(context.gc_context, swf_len);
+            root.preload(context, is_data_complete);
         });
     }
 
     pub fn run_frame(&mut self) {
+        let frame_time = 1000.0 / self.frame_rate;
         self.mutate_with_update_context(|avm, context| {
             let mut root = context.root;
             root.run_frame(context);
+            Self::update_sounds(context);
+            NetStreamManager::update_streams(context, frame_time);
             Self::run_actions(avm, context);
         });
 
@@ -545,6 +819,7 @@ impl<
         self.renderer.clear(self.background_color.clone());
 
         let (renderer, transform_stack) = (&mut self.renderer, &mut self.transform_stack);
+        let view_scale = self.view_matrix.a;
 
         transform_stack.push(&crate::transform::Transform {
             matrix: self.view_matrix,
@@ -557,9 +832,11 @@ impl<
                 library: &root_data.library,
                 transform_stack,
                 view_bounds,
+                view_scale,
+                layer_depth: 0,
                 clip_depth_stack: vec![],
             };
-            root_data.root.render(&mut render_context);
+            crate::display_object::render_with_effects(&mut render_context, root_data.root);
         });
         transform_stack.pop();
 
@@ -600,6 +877,38 @@ impl<
         &mut self.input
     }
 
+    pub fn fonts(&self) -> &Fonts {
+        &self.fonts
+    }
+
+    pub fn fonts_mut(&mut self) -> &mut Fonts {
+        &mut self.fonts
+    }
+
+    pub fn video(&self) -> &Video {
+        &self.video
+    }
+
+    pub fn video_mut(&mut self) -> &mut Video {
+        &mut self.video
+    }
+
+    /// The codepage used to decode strings in SWF 5 and earlier.
+    pub fn codepage(&self) -> Codepage {
+        self.codepage
+    }
+
+    /// Updates the state of playing sounds, queueing any `onSoundComplete` events.
+    fn update_sounds<'gc>(context: &mut UpdateContext<'_, 'gc, '_>) {
+        context.audio_manager.update_sounds(
+            context.audio,
+            context.gc_context,
+            context.action_queue,
+            context.root,
+        );
+        AudioManager::update_loads(context);
+    }
+
     fn run_actions<'gc>(avm: &mut Avm1<'gc>, context: &mut UpdateContext<'_, 'gc, '_>) {
         while let Some(actions) = context.action_queue.pop() {
             // We don't run frame actions if the clip was removed after it queued the action.
@@ -636,6 +945,18 @@ impl<
                     );
                 }
 
+                // Event handler method call on a non-display object (e.g. onSoundComplete)
+                ActionType::Callback { object, name, args } => {
+                    avm.insert_stack_frame_for_method(
+                        actions.clip,
+                        object,
+                        context.swf_version,
+                        context,
+                        name,
+                        &args,
+                    );
+                }
+
                 // Event handler method call (e.g. onEnterFrame)
                 ActionType::NotifyListeners {
                     listener,
@@ -708,12 +1029,18 @@ impl<
             player_version,
             global_time,
             swf_data,
+            swf_bytes_loaded,
+            swf_bytes_total,
             swf_version,
+            system_codepage,
+            use_codepage,
             background_color,
             renderer,
             audio,
             navigator,
             input,
+            fonts,
+            video,
             rng,
             mouse_position,
             stage_width,
@@ -722,12 +1049,18 @@ impl<
             self.player_version,
             self.global_time,
             &mut self.swf_data,
+            self.swf_decoder.bytes_loaded(),
+            self.swf_decoder.bytes_total(),
             self.swf_version,
+            self.codepage,
+            &mut self.use_codepage,
             &mut self.background_color,
             &mut self.renderer,
             &mut self.audio,
             &mut self.navigator,
             &mut self.input,
+            &mut self.fonts,
+            &mut self.video,
             &mut self.rng,
             &self.mouse_pos,
             Twips::from_pixels(self.movie_width.into()),
@@ -737,19 +1070,36 @@ impl<
         self.gc_arena.mutate(|gc_context, gc_root| {
             let mut root_data = gc_root.0.write(gc_context);
             let mouse_hovered_object = root_data.mouse_hovered_object;
-            let (root, library, action_queue, avm, drag_object) = root_data.update_context_params();
+            let (
+                root,
+                library,
+                action_queue,
+                avm,
+                drag_object,
+                focused_text_field,
+                audio_manager,
+                net_stream_manager,
+            ) = root_data.update_context_params();
             let mut update_context = UpdateContext {
                 player_version,
                 global_time,
                 swf_data,
+                swf_bytes_loaded,
+                swf_bytes_total,
                 swf_version,
+                system_codepage,
+                use_codepage,
                 library,
                 background_color,
                 rng,
                 renderer,
                 audio,
+                audio_manager,
+                net_stream_manager,
                 navigator,
                 input,
+                fonts,
+                video,
                 action_queue,
                 gc_context,
                 root,
@@ -757,6 +1107,7 @@ impl<
                 mouse_hovered_object,
                 mouse_position,
                 drag_object,
+                focused_text_field,
                 stage_size: (stage_width, stage_height),
             };
 
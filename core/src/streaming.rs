@@ -0,0 +1,341 @@
+//! Decompression of SWF files that are still being downloaded.
+//!
+//! Flash Player starts playing a movie as soon as its first frame arrives, so the
+//! SWF data is decompressed in chunks as it is received. LZMA-compressed (ZWS) files
+//! are the exception, and are only decompressed once they have loaded completely.
+use libflate::non_blocking::zlib;
+use std::collections::VecDeque;
+use std::io::{self, Cursor, Read};
+use swf::read::SwfRead;
+use swf::{Compression, Header};
+
+type Error = Box<dyn std::error::Error>;
+
+/// The length of the uncompressed part of the SWF header:
+/// the signature, the version and the uncompressed length.
+const FILE_HEADER_LEN: usize = 8;
+
+/// Decompresses an SWF file as its bytes arrive.
+///
+/// The decompressed data is returned in two parts: the movie header (the stage size,
+/// frame rate and frame count) available from `header`, and the tag data after it,
+/// which is returned from `decode` as it becomes available.
+pub struct SwfDecoder {
+    /// The start of the file, until the uncompressed part of the header has arrived.
+    file_header: Vec<u8>,
+
+    state: DecoderState,
+
+    /// The movie header, once enough data has been decompressed to read it.
+    header: Option<Header>,
+
+    /// Decompressed data that hasn't been returned yet, because the header
+    /// hasn't been read.
+    pending: Vec<u8>,
+
+    /// The total uncompressed length of the file, as given in the file header.
+    uncompressed_len: usize,
+
+    /// The length of the file header and the movie header.
+    header_len: usize,
+
+    /// The number of uncompressed bytes that have been decoded so far,
+    /// including the file header.
+    decoded_len: usize,
+}
+
+enum DecoderState {
+    /// The file header hasn't arrived yet.
+    FileHeader,
+
+    Uncompressed,
+
+    Zlib(Box<zlib::Decoder<InputBuffer>>),
+
+    /// The `swf` crate only decompresses whole LZMA files, so the compressed data is
+    /// collected until the whole file has arrived. ZWS movies don't start playing
+    /// until they have loaded completely.
+    // TODO: Decompress LZMA data as it arrives, using an `xz2` stream directly.
+    Lzma(Vec<u8>),
+
+    /// The end of the stream was reached, or the stream was corrupt.
+    Finished,
+}
+
+impl SwfDecoder {
+    pub fn new() -> Self {
+        Self {
+            file_header: Vec::with_capacity(FILE_HEADER_LEN),
+            state: DecoderState::FileHeader,
+            header: None,
+            pending: vec![],
+            uncompressed_len: 0,
+            header_len: 0,
+            decoded_len: 0,
+        }
+    }
+
+    /// The movie header, or `None` if not enough data has arrived to read it.
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// The number of uncompressed bytes that have been loaded, including the file header.
+    /// Used by `MovieClip.getBytesLoaded`.
+    pub fn bytes_loaded(&self) -> usize {
+        self.decoded_len
+    }
+
+    /// The total uncompressed length of the file, as given by the file header.
+    /// Used by `MovieClip.getBytesTotal`.
+    pub fn bytes_total(&self) -> usize {
+        self.uncompressed_len
+    }
+
+    /// The expected length of the tag data, according to the file header.
+    pub fn tag_data_len(&self) -> usize {
+        self.uncompressed_len.saturating_sub(self.header_len)
+    }
+
+    /// Returns whether the whole file has been decoded.
+    pub fn is_finished(&self) -> bool {
+        if let DecoderState::Finished = self.state {
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Decodes the next chunk of the file, and returns any newly available tag data.
+    pub fn decode(&mut self, mut data: &[u8]) -> Result<Vec<u8>, Error> {
+        if let DecoderState::FileHeader = self.state {
+            let len = (FILE_HEADER_LEN - self.file_header.len()).min(data.len());
+            self.file_header.extend_from_slice(&data[..len]);
+            data = &data[len..];
+            if self.file_header.len() < FILE_HEADER_LEN {
+                return Ok(vec![]);
+            }
+            self.read_file_header()?;
+        }
+
+        let mut decompressed = vec![];
+        match &mut self.state {
+            DecoderState::Uncompressed => decompressed.extend_from_slice(data),
+            DecoderState::Zlib(decoder) => {
+                decoder.as_inner_mut().data.extend(data);
+                match decoder.read_to_end(&mut decompressed) {
+                    Ok(_) => self.state = DecoderState::Finished,
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => (),
+                    Err(e) => {
+                        self.state = DecoderState::Finished;
+                        log::error!("Error decompressing SWF, may be corrupt: {}", e);
+                    }
+                }
+            }
+            DecoderState::Lzma(compressed) => {
+                compressed.extend_from_slice(data);
+                // The compressed length is stored after the file header,
+                // and doesn't include the LZMA properties.
+                if compressed.len() >= 17 {
+                    let compressed_len = u32::from_le_bytes([
+                        compressed[8],
+                        compressed[9],
+                        compressed[10],
+                        compressed[11],
+                    ]);
+                    if compressed.len() >= 17 + compressed_len as usize {
+                        return self.finish();
+                    }
+                }
+            }
+            DecoderState::FileHeader | DecoderState::Finished => (),
+        }
+        self.decoded(decompressed)
+    }
+
+    /// Signals that the whole file has arrived, and returns any remaining tag data.
+    pub fn finish(&mut self) -> Result<Vec<u8>, Error> {
+        let state = std::mem::replace(&mut self.state, DecoderState::Finished);
+        match state {
+            DecoderState::Zlib(mut decoder) => {
+                decoder.as_inner_mut().is_finished = true;
+                let mut decompressed = vec![];
+                if let Err(e) = decoder.read_to_end(&mut decompressed) {
+                    log::error!("Error decompressing SWF, may be corrupt: {}", e);
+                }
+                self.decoded(decompressed)
+            }
+            DecoderState::Lzma(compressed) => {
+                let swf_stream = swf::read::read_swf_header(&compressed[..])?;
+                // TODO: The LZMA decoder is still funky.
+                // It always errors, and doesn't return all the data if you use read_to_end,
+                // but read_exact at least returns the data... why?
+                // Does the decoder need to be flushed somehow?
+                let mut reader = swf_stream.reader;
+                let mut data = vec![0u8; swf_stream.uncompressed_length];
+                let _ = reader.get_mut().read_exact(&mut data);
+                self.header = Some(swf_stream.header);
+                self.decoded_len = self.uncompressed_len;
+                Ok(data)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn read_file_header(&mut self) -> Result<(), Error> {
+        let header = &self.file_header;
+        self.uncompressed_len =
+            u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        self.decoded_len = FILE_HEADER_LEN;
+        self.state = match &header[..3] {
+            b"FWS" => DecoderState::Uncompressed,
+            b"CWS" => DecoderState::Zlib(Box::new(zlib::Decoder::new(InputBuffer::default()))),
+            b"ZWS" => DecoderState::Lzma(header.clone()),
+            _ => return Err("Invalid SWF".into()),
+        };
+        Ok(())
+    }
+
+    /// Handles newly decompressed data, and returns the tag data that is now available.
+    fn decoded(&mut self, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        self.decoded_len += data.len();
+        if self.header.is_some() {
+            return Ok(data);
+        }
+
+        // The movie header is at the start of the decompressed data.
+        self.pending.extend(data);
+        let version = self.file_header[3];
+        let mut reader = swf::read::Reader::new(Cursor::new(&self.pending[..]), version);
+        let header = (|| {
+            Ok::<_, swf::error::Error>(Header {
+                version,
+                compression: match self.state {
+                    DecoderState::Zlib(_) => Compression::Zlib,
+                    _ => Compression::None,
+                },
+                stage_size: reader.read_rectangle()?,
+                frame_rate: reader.read_fixed8()?,
+                num_frames: reader.read_u16()?,
+            })
+        })();
+        match header {
+            Ok(header) => {
+                let header_len = reader.get_ref().position() as usize;
+                self.header = Some(header);
+                self.header_len = FILE_HEADER_LEN + header_len;
+                Ok(self.pending.split_off(header_len))
+            }
+            // Wait for more data.
+            Err(swf::error::Error::IoError(ref e))
+                if e.kind() == io::ErrorKind::UnexpectedEof && !self.is_finished() =>
+            {
+                Ok(vec![])
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Default for SwfDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compressed data waiting to be read by the decompressor.
+/// Reading blocks until more data arrives or the file is finished.
+#[derive(Default)]
+struct InputBuffer {
+    data: VecDeque<u8>,
+    is_finished: bool,
+}
+
+impl Read for InputBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.data.is_empty() && !self.is_finished {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "Waiting for data",
+            ));
+        }
+        self.data.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal SWF with a 550x400 stage, 24 FPS and 1 frame.
+    fn swf_body() -> Vec<u8> {
+        let mut body = vec![
+            0x78, 0x00, 0x05, 0x5f, 0x00, 0x00, 0x0f, 0xa0, 0x00, 0x00, 0x18, 0x01, 0x00,
+        ];
+        // ShowFrame and End tags.
+        body.extend(&[0x40, 0x00, 0x00, 0x00]);
+        body
+    }
+
+    fn swf_file(signature: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut file = signature.to_vec();
+        file.push(10);
+        file.extend(&((FILE_HEADER_LEN + swf_body().len()) as u32).to_le_bytes());
+        file.extend(body);
+        file
+    }
+
+    /// Decodes a file one byte at a time.
+    fn decode_bytewise(file: &[u8]) -> (SwfDecoder, Vec<u8>) {
+        let mut decoder = SwfDecoder::new();
+        let mut data = vec![];
+        for byte in file.chunks(1) {
+            data.extend(decoder.decode(byte).unwrap());
+        }
+        data.extend(decoder.finish().unwrap());
+        (decoder, data)
+    }
+
+    #[test]
+    fn decode_uncompressed() {
+        let (decoder, data) = decode_bytewise(&swf_file(b"FWS", &swf_body()));
+        let header = decoder.header().unwrap();
+        assert_eq!(header.num_frames, 1);
+        assert_eq!(header.frame_rate, 24.0);
+        assert_eq!(data, [0x40, 0x00, 0x00, 0x00]);
+        assert_eq!(decoder.tag_data_len(), data.len());
+        assert_eq!(decoder.bytes_loaded(), decoder.bytes_total());
+    }
+
+    #[test]
+    fn decode_zlib() {
+        let mut encoder = libflate::zlib::Encoder::new(vec![]).unwrap();
+        std::io::Write::write_all(&mut encoder, &swf_body()).unwrap();
+        let compressed = encoder.finish().into_result().unwrap();
+
+        let (decoder, data) = decode_bytewise(&swf_file(b"CWS", &compressed));
+        let header = decoder.header().unwrap();
+        assert_eq!(header.compression, Compression::Zlib);
+        assert_eq!(header.num_frames, 1);
+        assert_eq!(data, [0x40, 0x00, 0x00, 0x00]);
+        assert_eq!(decoder.bytes_loaded(), decoder.bytes_total());
+        assert!(decoder.is_finished());
+    }
+
+    #[test]
+    fn lzma_waits_for_whole_file() {
+        // The compressed length and the LZMA properties follow the file header.
+        let mut file = swf_file(b"ZWS", &4u32.to_le_bytes());
+        file.extend(&[0x5d, 0x00, 0x00, 0x10, 0x00]);
+        file.extend(&[0; 4]);
+
+        // No tag data is returned before the last byte arrives.
+        let mut decoder = SwfDecoder::new();
+        for byte in file[..file.len() - 1].chunks(1) {
+            assert_eq!(decoder.decode(byte).unwrap(), []);
+        }
+        assert!(decoder.header().is_none());
+        assert_eq!(decoder.bytes_loaded(), FILE_HEADER_LEN);
+        assert!(!decoder.is_finished());
+    }
+}
@@ -5,6 +5,24 @@ use swf::TagCode;
 pub type DecodeResult = Result<(), Box<dyn std::error::Error>>;
 pub type SwfStream<R> = swf::read::Reader<std::io::Cursor<R>>;
 
+/// Appends data to the SWF data of a movie that is still loading.
+///
+/// The data is appended in place if nothing else refers to the buffer. Otherwise, the
+/// loaded data is copied to a new buffer with the same capacity, and existing `SwfSlice`s
+/// keep referring to the old one. Slices only store offsets into the data, so slices
+/// created from either buffer refer to the same bytes.
+pub fn append_swf_data(swf_data: &mut Arc<Vec<u8>>, data: &[u8]) {
+    if let Some(buffer) = Arc::get_mut(swf_data) {
+        buffer.extend_from_slice(data);
+    } else {
+        let len = swf_data.len() + data.len();
+        let mut buffer = Vec::with_capacity(swf_data.capacity().max(len));
+        buffer.extend_from_slice(swf_data);
+        buffer.extend_from_slice(data);
+        *swf_data = Arc::new(buffer);
+    }
+}
+
 /// A shared-ownership reference to some portion of an immutable datastream.
 #[derive(Debug, Clone, Collect)]
 #[collect(no_drop)]
@@ -51,6 +69,11 @@ impl SwfSlice {
     }
 }
 
+/// Decodes tags from the stream until `stop_tag` is reached, calling `tag_callback` for each tag.
+///
+/// Decoding also stops at a tag that extends past the end of the data, such as a tag that
+/// hasn't finished loading. The stream is left at the start of this tag so that decoding
+/// can resume once more data is available.
 pub fn decode_tags<'a, R, F>(
     reader: &'a mut SwfStream<R>,
     mut tag_callback: F,
@@ -62,8 +85,20 @@ where
 {
     use std::io::{Seek, SeekFrom};
     loop {
-        let (tag_code, tag_len) = reader.read_tag_code_and_length()?;
+        let tag_start = reader.get_ref().position();
+        let data_len = reader.get_ref().get_ref().as_ref().len() as u64;
+        let (tag_code, tag_len) = match reader.read_tag_code_and_length() {
+            Ok(tag_code_and_length) => tag_code_and_length,
+            Err(e) => {
+                reader.get_mut().seek(SeekFrom::Start(tag_start))?;
+                return Err(e.into());
+            }
+        };
         let end_pos = reader.get_ref().position() + tag_len as u64;
+        if end_pos > data_len {
+            reader.get_mut().seek(SeekFrom::Start(tag_start))?;
+            return Err("Incomplete tag".into());
+        }
 
         let tag = TagCode::from_u16(tag_code);
         if let Some(tag) = tag {
@@ -86,3 +121,36 @@ where
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_to_swf_data() {
+        let mut data = Arc::new(Vec::with_capacity(6));
+        append_swf_data(&mut data, &[1, 2]);
+        let slice = SwfSlice {
+            data: Arc::clone(&data),
+            start: 0,
+            end: 2,
+        };
+
+        // The data is copied while a slice refers to it, and the slice keeps the old data.
+        append_swf_data(&mut data, &[3, 4, 5]);
+        assert!(!Arc::ptr_eq(&data, &slice.data));
+        assert_eq!(slice.as_ref(), [1, 2]);
+        assert_eq!(data[..], [1, 2, 3, 4, 5]);
+        assert_eq!(data.capacity(), 6);
+
+        // Otherwise, the data is appended in place.
+        let ptr = data.as_ptr();
+        append_swf_data(&mut data, &[6]);
+        assert_eq!(data[..], [1, 2, 3, 4, 5, 6]);
+        assert_eq!(data.as_ptr(), ptr);
+
+        // Data past the capacity reallocates.
+        append_swf_data(&mut data, &[7]);
+        assert_eq!(data[..], [1, 2, 3, 4, 5, 6, 7]);
+    }
+}
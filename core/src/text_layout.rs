@@ -0,0 +1,666 @@
+//! Layout of text into lines, used by `EditText` fields.
+//!
+//! The layout engine breaks text into lines (by hard breaks and by word wrapping),
+//! and positions each glyph on its line according to the paragraph alignment,
+//! margins, indent and leading of the text field.
+use crate::font::Font;
+use swf::{TextAlign, Twips};
+
+/// The gutter between the edge of a text field and its text, in twips.
+/// Flash always leaves 2 pixels of space on each side of the text.
+pub const GUTTER: i32 = 40;
+
+/// Metrics of a font used to lay out text.
+/// All units are in the font's own coordinate space, which is scaled
+/// by `font_size / scale` to get twips.
+pub trait FontMetrics {
+    /// The advance of the glyph for the given character, or `None`
+    /// if the font has no glyph for this character.
+    fn advance(&self, c: char) -> Option<i16>;
+
+    /// The kerning adjustment to apply between the given pair of characters.
+    fn kerning(&self, left: char, right: char) -> i16;
+
+    /// The distance from the baseline to the top of the line.
+    fn ascent(&self) -> u16;
+
+    /// The distance from the baseline to the bottom of the line.
+    fn descent(&self) -> u16;
+
+    /// The size of the EM square of this font.
+    fn scale(&self) -> f32;
+}
+
+impl<'gc> FontMetrics for Font<'gc> {
+    fn advance(&self, c: char) -> Option<i16> {
+        self.get_advance_for_char(c)
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        if self.has_kerning_info() {
+            self.get_kerning_offset(left, right).get() as i16
+        } else {
+            0
+        }
+    }
+
+    fn ascent(&self) -> u16 {
+        Font::ascent(*self)
+    }
+
+    fn descent(&self) -> u16 {
+        Font::descent(*self)
+    }
+
+    fn scale(&self) -> f32 {
+        Font::scale(*self)
+    }
+}
+
+/// Parameters controlling how text is laid out.
+#[derive(Clone, Debug)]
+pub struct LayoutParams {
+    /// The width available for text, not including the gutter.
+    /// Margins and indent are subtracted from this width.
+    pub width: Twips,
+
+    /// The height of the font (size of the EM square) in twips.
+    pub font_size: Twips,
+
+    /// The paragraph alignment.
+    pub align: TextAlign,
+
+    /// Space between the left edge of the text area and the text.
+    pub left_margin: Twips,
+
+    /// Space between the right edge of the text area and the text.
+    pub right_margin: Twips,
+
+    /// Additional space before the first line of each paragraph.
+    pub indent: Twips,
+
+    /// Additional space between lines.
+    pub leading: Twips,
+
+    /// Whether lines wrap at word boundaries when they exceed the width.
+    pub word_wrap: bool,
+
+    /// Whether hard line breaks in the text start new lines.
+    /// Single-line fields lay out all text on one line.
+    pub multiline: bool,
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        Self {
+            width: Twips::new(0),
+            font_size: Twips::new(240),
+            align: TextAlign::Left,
+            left_margin: Twips::new(0),
+            right_margin: Twips::new(0),
+            indent: Twips::new(0),
+            leading: Twips::new(0),
+            word_wrap: false,
+            multiline: false,
+        }
+    }
+}
+
+impl From<&swf::TextLayout> for LayoutParams {
+    fn from(layout: &swf::TextLayout) -> Self {
+        Self {
+            align: layout.align,
+            left_margin: layout.left_margin,
+            right_margin: layout.right_margin,
+            indent: layout.indent,
+            leading: layout.leading,
+            ..Default::default()
+        }
+    }
+}
+
+/// A single positioned glyph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutGlyph {
+    /// The character that this glyph represents.
+    pub character: char,
+
+    /// The index of this character in the source text.
+    pub index: usize,
+
+    /// The horizontal position of the glyph origin, relative to the left edge of the text area.
+    pub x: Twips,
+
+    /// The horizontal space that this glyph occupies.
+    pub advance: Twips,
+}
+
+/// A line of laid out text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutLine {
+    /// The positioned glyphs on this line.
+    pub glyphs: Vec<LayoutGlyph>,
+
+    /// The vertical position of the top of this line, relative to the top of the text area.
+    pub top: Twips,
+
+    /// The vertical position of the baseline, relative to the top of the text area.
+    pub baseline: Twips,
+
+    /// The horizontal position of the start of this line, relative to the left edge of the text area.
+    pub x: Twips,
+
+    /// The width of the text on this line, excluding trailing whitespace.
+    pub width: Twips,
+
+    /// The index into the source text of the first character on this line.
+    pub start: usize,
+
+    /// The index into the source text one past the last character on this line.
+    pub end: usize,
+}
+
+/// The result of laying out a block of text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextLayout {
+    /// The lines of text, in order from top to bottom.
+    pub lines: Vec<LayoutLine>,
+
+    /// The width of the widest line, including margins and indent but not the offset
+    /// from alignment. Returned by `TextField.textWidth`.
+    pub width: Twips,
+
+    /// The height of all lines, including leading between them.
+    /// Returned by `TextField.textHeight`.
+    pub height: Twips,
+
+    /// The height of a single line, not including leading.
+    pub line_height: Twips,
+
+    /// The distance between the tops of two consecutive lines.
+    pub line_spacing: Twips,
+}
+
+impl TextLayout {
+    /// Lays out the given text with the given font.
+    ///
+    /// Characters are indexed by their position in `text` counted in `char`s, not bytes.
+    pub fn lay_out<F: FontMetrics>(text: &str, font: &F, params: &LayoutParams) -> Self {
+        let scale = params.font_size.get() as f32 / font.scale();
+        let ascent = Twips::new((f32::from(font.ascent()) * scale).round() as i32);
+        let descent = Twips::new((f32::from(font.descent()) * scale).round() as i32);
+        let line_height = ascent + descent;
+        let line_spacing = line_height + params.leading;
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut layout = TextLayout {
+            line_height,
+            line_spacing,
+            ..Default::default()
+        };
+
+        let mut paragraph_start = 0;
+        loop {
+            // Find the end of this paragraph.
+            let mut paragraph_end = paragraph_start;
+            while paragraph_end < chars.len()
+                && !(params.multiline && is_line_break(chars[paragraph_end]))
+            {
+                paragraph_end += 1;
+            }
+
+            layout.lay_out_paragraph(
+                &chars,
+                paragraph_start,
+                paragraph_end,
+                font,
+                scale,
+                ascent,
+                params,
+            );
+
+            if paragraph_end >= chars.len() {
+                break;
+            }
+
+            // Treat CRLF as a single line break.
+            paragraph_start = paragraph_end + 1;
+            if chars[paragraph_end] == '\r' && chars.get(paragraph_start) == Some(&'\n') {
+                paragraph_start += 1;
+            }
+        }
+
+        let num_lines = layout.lines.len() as i32;
+        layout.height = if num_lines > 0 {
+            Twips::new(line_height.get() * num_lines + params.leading.get() * (num_lines - 1))
+        } else {
+            Twips::new(0)
+        };
+
+        layout
+    }
+
+    /// Lays out a single paragraph (a run of text without any hard line breaks).
+    #[allow(clippy::too_many_arguments)]
+    fn lay_out_paragraph<F: FontMetrics>(
+        &mut self,
+        chars: &[char],
+        paragraph_start: usize,
+        paragraph_end: usize,
+        font: &F,
+        scale: f32,
+        ascent: Twips,
+        params: &LayoutParams,
+    ) {
+        let advance_of = |i: usize| -> Option<Twips> {
+            let c = chars[i];
+            let mut advance = f32::from(font.advance(display_char(c))?);
+            if i + 1 < paragraph_end {
+                advance += f32::from(font.kerning(c, chars[i + 1]));
+            }
+            Some(Twips::new((advance * scale).round() as i32))
+        };
+
+        let text_width = params.width - params.left_margin - params.right_margin;
+        let mut line_start = paragraph_start;
+        let mut is_first_line = true;
+        loop {
+            let indent = if is_first_line {
+                params.indent
+            } else {
+                Twips::new(0)
+            };
+            let available_width = text_width - indent;
+
+            // Add characters until we run out of space.
+            let mut x = Twips::new(0);
+            let mut i = line_start;
+            let mut last_break = None;
+            while i < paragraph_end {
+                let advance = advance_of(i).unwrap_or_default();
+                if params.word_wrap
+                    && i > line_start
+                    && x + advance > available_width
+                    && !chars[i].is_whitespace()
+                {
+                    break;
+                }
+                if chars[i].is_whitespace() {
+                    last_break = Some(i + 1);
+                }
+                x += advance;
+                i += 1;
+            }
+            let line_end = if i < paragraph_end {
+                last_break.unwrap_or(i)
+            } else {
+                paragraph_end
+            };
+            let is_last_line = line_end >= paragraph_end;
+
+            // Position the glyphs on this line.
+            let mut glyphs = Vec::with_capacity(line_end - line_start);
+            let mut x = Twips::new(0);
+            for (i, &c) in chars.iter().enumerate().take(line_end).skip(line_start) {
+                if let Some(advance) = advance_of(i) {
+                    glyphs.push(LayoutGlyph {
+                        character: c,
+                        index: i,
+                        x,
+                        advance,
+                    });
+                    x += advance;
+                }
+            }
+
+            // Trailing whitespace doesn't count towards the alignment of the line.
+            let width = glyphs
+                .iter()
+                .rev()
+                .find(|glyph| !glyph.character.is_whitespace())
+                .map(|glyph| glyph.x + glyph.advance)
+                .unwrap_or_default();
+
+            let extra_space = available_width - width;
+            let align_offset = match params.align {
+                TextAlign::Left | TextAlign::Justify => Twips::new(0),
+                TextAlign::Center => Twips::new(extra_space.get() / 2),
+                TextAlign::Right => extra_space,
+            };
+            if params.align == TextAlign::Justify
+                && params.word_wrap
+                && !is_last_line
+                && extra_space > Twips::new(0)
+            {
+                justify(&mut glyphs, extra_space);
+            }
+
+            let line_x = params.left_margin + indent + align_offset.max(Twips::new(0));
+            let top = Twips::new(self.line_spacing.get() * self.lines.len() as i32);
+            self.width = self
+                .width
+                .max(params.left_margin + indent + width + params.right_margin);
+            self.lines.push(LayoutLine {
+                glyphs,
+                top,
+                baseline: top + ascent,
+                x: line_x,
+                width,
+                start: line_start,
+                end: line_end,
+            });
+
+            if is_last_line {
+                break;
+            }
+            line_start = line_end;
+            is_first_line = false;
+        }
+    }
+
+    /// The maximum value of the 1-based `scroll` property, given the height
+    /// of the visible text area.
+    /// This is the first line such that the remaining lines all fit in the visible area.
+    pub fn max_scroll(&self, visible_height: Twips) -> usize {
+        for (i, line) in self.lines.iter().enumerate() {
+            if self.height - line.top <= visible_height {
+                return i + 1;
+            }
+        }
+        self.lines.len().max(1)
+    }
+
+    /// The number of lines that are visible when scrolled to the given 1-based line.
+    pub fn visible_lines(&self, scroll: usize, visible_height: Twips) -> usize {
+        let first = scroll.saturating_sub(1).min(self.lines.len());
+        let scroll_top = self.lines.get(first).map(|l| l.top).unwrap_or_default();
+        self.lines[first..]
+            .iter()
+            .take_while(|line| line.top + self.line_height - scroll_top <= visible_height)
+            .count()
+            .max(1)
+    }
+
+    /// The index of the line at the given vertical position, relative to the top of the text area.
+    /// Positions above the first line or below the last line are clamped to those lines.
+    pub fn line_at_y(&self, y: Twips) -> Option<usize> {
+        if self.lines.is_empty() {
+            return None;
+        }
+        Some(
+            self.lines
+                .iter()
+                .rposition(|line| line.top <= y)
+                .unwrap_or(0),
+        )
+    }
+
+    /// The index of the character before which a caret would be placed when
+    /// clicking at the given position, relative to the top-left of the text area.
+    pub fn char_index_at(&self, x: Twips, y: Twips) -> usize {
+        let line_index = match self.line_at_y(y) {
+            Some(line_index) => line_index,
+            None => return 0,
+        };
+        let line = &self.lines[line_index];
+        let x = x - line.x;
+        for glyph in &line.glyphs {
+            if x < glyph.x + glyph.advance / 2 {
+                return glyph.index;
+            }
+        }
+
+        // A caret at the end of a wrapped line would appear at the start of the next line,
+        // so place it before the character where the line wrapped instead.
+        let wraps = self
+            .lines
+            .get(line_index + 1)
+            .map(|next| next.start == line.end)
+            .unwrap_or(false);
+        if wraps && line.end > line.start {
+            line.end - 1
+        } else {
+            line.end
+        }
+    }
+
+    /// The line index and horizontal position (relative to the left of the text area)
+    /// of a caret placed before the character at the given index.
+    pub fn caret_position(&self, index: usize) -> Option<(usize, Twips)> {
+        let line_index = self.lines.iter().rposition(|line| line.start <= index)?;
+        let line = &self.lines[line_index];
+        let x = line
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.index >= index)
+            .map(|glyph| glyph.x)
+            .or_else(|| line.glyphs.last().map(|glyph| glyph.x + glyph.advance))
+            .unwrap_or_default();
+        Some((line_index, line.x + x))
+    }
+}
+
+/// Returns whether this character causes a hard line break.
+fn is_line_break(c: char) -> bool {
+    c == '\n' || c == '\r'
+}
+
+/// Returns the character whose glyph should be used to measure and render `c`.
+/// Tabs and other control characters are rendered as a space.
+pub fn display_char(c: char) -> char {
+    if c.is_control() {
+        ' '
+    } else {
+        c
+    }
+}
+
+/// Spreads the given extra space evenly between the words on a line.
+fn justify(glyphs: &mut [LayoutGlyph], extra_space: Twips) {
+    // Only spaces between words get stretched; ignore trailing whitespace.
+    let last_word_end = glyphs
+        .iter()
+        .rposition(|glyph| !glyph.character.is_whitespace())
+        .unwrap_or(0);
+    let num_spaces = glyphs[..last_word_end]
+        .iter()
+        .filter(|glyph| glyph.character == ' ')
+        .count() as i32;
+    if num_spaces == 0 {
+        return;
+    }
+
+    let mut offset = 0;
+    let mut spaces_seen = 0;
+    for glyph in glyphs.iter_mut().take(last_word_end + 1) {
+        glyph.x += Twips::new(offset);
+        if glyph.character == ' ' {
+            spaces_seen += 1;
+            let new_offset = extra_space.get() * spaces_seen / num_spaces;
+            glyph.advance += Twips::new(new_offset - offset);
+            offset = new_offset;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A monospace font where every glyph is 10 twips wide at a 100 twip font size.
+    struct TestFont;
+
+    impl FontMetrics for TestFont {
+        fn advance(&self, c: char) -> Option<i16> {
+            if c == '\u{fffd}' {
+                None
+            } else {
+                Some(100)
+            }
+        }
+
+        fn kerning(&self, _left: char, _right: char) -> i16 {
+            0
+        }
+
+        fn ascent(&self) -> u16 {
+            800
+        }
+
+        fn descent(&self) -> u16 {
+            200
+        }
+
+        fn scale(&self) -> f32 {
+            1000.0
+        }
+    }
+
+    fn params(width: i32) -> LayoutParams {
+        LayoutParams {
+            width: Twips::new(width),
+            font_size: Twips::new(100),
+            multiline: true,
+            ..Default::default()
+        }
+    }
+
+    fn line_text(text: &str, line: &LayoutLine) -> String {
+        text.chars()
+            .skip(line.start)
+            .take(line.end - line.start)
+            .collect()
+    }
+
+    #[test]
+    fn single_line() {
+        let layout = TextLayout::lay_out("Hello", &TestFont, &params(1000));
+        assert_eq!(layout.lines.len(), 1);
+        assert_eq!(layout.width, Twips::new(50));
+        assert_eq!(layout.height, Twips::new(100));
+        assert_eq!(layout.lines[0].baseline, Twips::new(80));
+        assert_eq!(layout.lines[0].glyphs[4].x, Twips::new(40));
+    }
+
+    #[test]
+    fn hard_breaks() {
+        let text = "one\rtwo\r\nthree\nfour";
+        let layout = TextLayout::lay_out(text, &TestFont, &params(1000));
+        let lines: Vec<_> = layout.lines.iter().map(|l| line_text(text, l)).collect();
+        assert_eq!(lines, vec!["one", "two", "three", "four"]);
+        assert_eq!(layout.lines[3].top, Twips::new(300));
+
+        let mut single_line = params(1000);
+        single_line.multiline = false;
+        let layout = TextLayout::lay_out(text, &TestFont, &single_line);
+        assert_eq!(layout.lines.len(), 1);
+    }
+
+    #[test]
+    fn word_wrap() {
+        let text = "the quick brown fox";
+        let mut params = params(100);
+        params.word_wrap = true;
+        let layout = TextLayout::lay_out(text, &TestFont, &params);
+        let lines: Vec<_> = layout.lines.iter().map(|l| line_text(text, l)).collect();
+        assert_eq!(lines, vec!["the quick ", "brown fox"]);
+        assert_eq!(layout.lines[0].width, Twips::new(90));
+    }
+
+    #[test]
+    fn word_wrap_long_word() {
+        let text = "abcdefghijkl";
+        let mut params = params(50);
+        params.word_wrap = true;
+        let layout = TextLayout::lay_out(text, &TestFont, &params);
+        let lines: Vec<_> = layout.lines.iter().map(|l| line_text(text, l)).collect();
+        assert_eq!(lines, vec!["abcde", "fghij", "kl"]);
+    }
+
+    #[test]
+    fn alignment() {
+        let mut params = params(100);
+        params.align = TextAlign::Center;
+        let layout = TextLayout::lay_out("ab", &TestFont, &params);
+        assert_eq!(layout.lines[0].x, Twips::new(40));
+
+        params.align = TextAlign::Right;
+        params.right_margin = Twips::new(10);
+        let layout = TextLayout::lay_out("ab", &TestFont, &params);
+        assert_eq!(layout.lines[0].x, Twips::new(70));
+    }
+
+    #[test]
+    fn autosize_with_margins() {
+        // Autosizing fits the field to the text and lays it out again in the new width.
+        let mut params = params(300);
+        params.align = TextAlign::Center;
+        params.left_margin = Twips::new(10);
+        params.right_margin = Twips::new(20);
+        let layout = TextLayout::lay_out("ab", &TestFont, &params);
+        assert_eq!(layout.width, Twips::new(50));
+
+        params.width = layout.width;
+        let layout = TextLayout::lay_out("ab", &TestFont, &params);
+        assert_eq!(layout.lines[0].x, Twips::new(10));
+        assert_eq!(layout.width, Twips::new(50));
+    }
+
+    #[test]
+    fn justify_spreads_spaces() {
+        let text = "a b c dd";
+        let mut params = params(70);
+        params.align = TextAlign::Justify;
+        params.word_wrap = true;
+        let layout = TextLayout::lay_out(text, &TestFont, &params);
+        assert_eq!(layout.lines.len(), 2);
+        // "a b c " is 50 twips wide, leaving 20 twips to spread over two spaces.
+        let glyphs = &layout.lines[0].glyphs;
+        assert_eq!(glyphs[2].x, Twips::new(30));
+        assert_eq!(glyphs[4].x, Twips::new(60));
+        // The last line of a paragraph is not justified.
+        assert_eq!(layout.lines[1].glyphs[1].x, Twips::new(10));
+    }
+
+    #[test]
+    fn margins_and_indent() {
+        let text = "aaa aaa";
+        let mut params = params(80);
+        params.left_margin = Twips::new(10);
+        params.indent = Twips::new(20);
+        params.word_wrap = true;
+        let layout = TextLayout::lay_out(text, &TestFont, &params);
+        assert_eq!(layout.lines.len(), 2);
+        assert_eq!(layout.lines[0].x, Twips::new(30));
+        assert_eq!(layout.lines[1].x, Twips::new(10));
+    }
+
+    #[test]
+    fn leading_and_scroll() {
+        let mut params = params(1000);
+        params.leading = Twips::new(20);
+        let layout = TextLayout::lay_out("1\r2\r3\r4", &TestFont, &params);
+        assert_eq!(layout.height, Twips::new(460));
+        assert_eq!(layout.lines[1].top, Twips::new(120));
+        assert_eq!(layout.max_scroll(Twips::new(220)), 3);
+        assert_eq!(layout.max_scroll(Twips::new(1000)), 1);
+        assert_eq!(layout.visible_lines(1, Twips::new(220)), 2);
+    }
+
+    #[test]
+    fn caret_positions() {
+        let text = "ab\rcd";
+        let layout = TextLayout::lay_out(text, &TestFont, &params(1000));
+        assert_eq!(layout.caret_position(0), Some((0, Twips::new(0))));
+        assert_eq!(layout.caret_position(2), Some((0, Twips::new(20))));
+        assert_eq!(layout.caret_position(4), Some((1, Twips::new(10))));
+        assert_eq!(layout.char_index_at(Twips::new(12), Twips::new(5)), 1);
+        assert_eq!(layout.char_index_at(Twips::new(500), Twips::new(5)), 2);
+        assert_eq!(layout.char_index_at(Twips::new(0), Twips::new(150)), 3);
+
+        let mut params = params(50);
+        params.word_wrap = true;
+        let layout = TextLayout::lay_out("abc def", &TestFont, &params);
+        assert_eq!(layout.char_index_at(Twips::new(500), Twips::new(5)), 3);
+    }
+}
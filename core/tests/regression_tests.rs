@@ -1,15 +1,26 @@
 //! Tests running SWFs in a headless Ruffle instance.
 //!
 //! Trace output can be compared with correct output from the official Flash Payer.
+//! Rendered frames can be compared with reference images.
+//! Mouse and keyboard input can be scripted.
 
 use approx::assert_abs_diff_eq;
 use log::{Metadata, Record};
+use ruffle_core::backend::audio::capture::{
+    read_wav, AudioFingerprint, CaptureAudioBackend, CAPTURE_SAMPLE_RATE,
+};
 use ruffle_core::backend::{
-    audio::NullAudioBackend, input::NullInputBackend, navigator::NullNavigatorBackend,
-    render::NullRenderer,
+    font::NullFontBackend,
+    input::{InputBackend, NullInputBackend},
+    navigator::NullNavigatorBackend,
+    render::{NullRenderer, RenderBackend, SoftwareRenderBackend},
+    video::SoftwareVideoBackend,
 };
-use ruffle_core::Player;
+use ruffle_core::events::{KeyCode, PlayerEvent};
+use ruffle_core::{Codepage, Player};
 use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 
 type Error = Box<dyn std::error::Error>;
 
@@ -34,6 +45,12 @@ macro_rules! swf_tests {
 // Format: (test_name, test_folder, number_of_frames_to_run)
 // The test folder is a relative to core/tests/swfs
 // Inside the folder is expected to be "test.swf" and "output.txt" with the correct output.
+// The folder may also contain "audio.txt" with the expected audio fingerprint,
+// or "audio.wav" with the expected waveform, captured at 44.1kHz.
+// It may also contain "frameN.png" images of how the stage should look after frame N,
+// and "image_tolerance.txt" with the largest difference allowed in each color channel.
+// Mouse and keyboard events can be sent to the movie with an "input.txt" script;
+// see `read_input_script` for its format.
 swf_tests! {
     (add_property, "avm1/add_property", 1),
     (as_transformed_flag, "avm1/as_transformed_flag", 3),
@@ -45,6 +62,8 @@ swf_tests! {
     (create_empty_movie_clip, "avm1/create_empty_movie_clip", 2),
     (duplicate_movie_clip, "avm1/duplicate_movie_clip", 1),
     (mouse_listeners, "avm1/mouse_listeners", 1),
+    (input_events, "avm1/input_events", 6),
+    (key_listeners, "avm1/key_listeners", 4),
     (do_init_action, "avm1/do_init_action", 3),
     (execution_order1, "avm1/execution_order1", 3),
     (execution_order2, "avm1/execution_order2", 15),
@@ -83,7 +102,7 @@ swf_tests! {
     (object_prototypes, "avm1/object_prototypes", 1),
     (movieclip_prototype_extension, "avm1/movieclip_prototype_extension", 1),
     (movieclip_hittest, "avm1/movieclip_hittest", 1),
-    #[ignore] (textfield_text, "avm1/textfield_text", 1),
+    (textfield_text, "avm1/textfield_text", 1),
     (recursive_prototypes, "avm1/recursive_prototypes", 1),
     (stage_object_children, "avm1/stage_object_children", 2),
     (has_own_property, "avm1/has_own_property", 1),
@@ -128,8 +147,137 @@ swf_tests! {
     #[ignore] (xml_inspect_xmldecl, "avm1/xml_inspect_xmldecl", 1),
     (xml_inspect_createmethods, "avm1/xml_inspect_createmethods", 1),
     (xml_inspect_parsexml, "avm1/xml_inspect_parsexml", 1),
+    (start_sound_envelope, "audio/start_sound_envelope", 10),
+    (blend_mode, "avm1/blend_mode", 1),
+    (filters, "avm1/filters", 1),
+    (render_blend_modes, "render/blend_modes", 1),
+    (render_filters, "render/filters", 1),
+    (render_shapes, "render/shapes", 2),
+}
+
+/// Tests that the timeline skips frames to stay in sync with a stream sound,
+/// and waits for the stream when it gets ahead.
+#[test]
+fn test_stream_sound_sync() -> Result<(), Error> {
+    let _ = log::set_logger(&TRACE_LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
+
+    let swf_data = std::fs::read("tests/swfs/audio/stream_sync/test.swf")?;
+    let mut player = Player::new(
+        NullRenderer,
+        CaptureAudioBackend::new(),
+        NullNavigatorBackend::new(),
+        NullInputBackend::new(),
+        NullFontBackend::new(),
+        SoftwareVideoBackend::new(),
+        Codepage::default(),
+        swf_data,
+    )?;
+    player.set_is_playing(true);
+
+    // The movie runs at 10 FPS, so each frame is 100ms.
+    // The stream sound starts on frame 1.
+    player.tick(100.0);
+    assert_eq!(trace_log(), "1\n");
+
+    // The audio has played 500ms, so the timeline should skip to frame 6.
+    player.audio_mut().advance(500.0);
+    player.tick(0.0);
+    assert_eq!(trace_log(), "1\n2\n3\n4\n5\n6\n");
+
+    // Without the audio advancing, the timeline runs one frame ahead at most.
+    player.tick(100.0);
+    player.tick(100.0);
+    player.tick(100.0);
+    assert_eq!(trace_log(), "1\n2\n3\n4\n5\n6\n7\n8\n");
+
+    // After the audio has stalled for 500ms, the timeline falls back to the frame rate.
+    player.tick(100.0);
+    assert_eq!(trace_log(), "1\n2\n3\n4\n5\n6\n7\n8\n");
+    player.tick(100.0);
+    assert_eq!(trace_log(), "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+
+    // Once the audio advances again, the timeline waits for it.
+    player.audio_mut().advance(100.0);
+    player.tick(100.0);
+    assert_eq!(trace_log(), "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+
+    Ok(())
 }
 
+/// Tests that a stream sound is synced relative to the frame of its first `SoundStreamBlock`.
+#[test]
+fn test_stream_sound_sync_late_start() -> Result<(), Error> {
+    let _ = log::set_logger(&TRACE_LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
+
+    let swf_data = std::fs::read("tests/swfs/audio/stream_sync_late/test.swf")?;
+    let mut player = Player::new(
+        NullRenderer,
+        CaptureAudioBackend::new(),
+        NullNavigatorBackend::new(),
+        NullInputBackend::new(),
+        NullFontBackend::new(),
+        SoftwareVideoBackend::new(),
+        Codepage::default(),
+        swf_data,
+    )?;
+    player.set_is_playing(true);
+
+    // The stream sound starts on frame 3.
+    player.tick(100.0);
+    player.tick(100.0);
+    player.tick(100.0);
+    assert_eq!(trace_log(), "1\n2\n3\n");
+
+    // 500ms into the stream is frame 8.
+    player.audio_mut().advance(500.0);
+    player.tick(0.0);
+    assert_eq!(trace_log(), "1\n2\n3\n4\n5\n6\n7\n8\n");
+
+    Ok(())
+}
+
+/// Tests that SWF 5 strings are decoded with the player's codepage,
+/// and that the multibyte string actions count Shift-JIS characters.
+#[test]
+fn test_shift_jis_swf5() -> Result<(), Error> {
+    let _ = log::set_logger(&TRACE_LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
+
+    let expected_output = std::fs::read_to_string("tests/swfs/avm1/shift_jis_swf5/output.txt")?;
+    let swf_data = std::fs::read("tests/swfs/avm1/shift_jis_swf5/test.swf")?;
+    let mut player = Player::new(
+        NullRenderer,
+        CaptureAudioBackend::new(),
+        NullNavigatorBackend::new(),
+        NullInputBackend::new(),
+        NullFontBackend::new(),
+        SoftwareVideoBackend::new(),
+        Codepage::ShiftJis,
+        swf_data,
+    )?;
+    player.run_frame();
+    assert_eq!(trace_log(), expected_output.replace("\r\n", "\n"));
+
+    Ok(())
+}
+
+/// Tests that a preloader waits on the progress of a streaming movie.
+#[test]
+fn test_preloader() -> Result<(), Error> {
+    let expected_output = std::fs::read_to_string("tests/swfs/avm1/preloader/output.txt")?;
+    let trace_log = run_swf_streaming("tests/swfs/avm1/preloader/test.swf", 10, 400)?;
+    assert_eq!(trace_log, expected_output.replace("\r\n", "\n"));
+    Ok(())
+}
+
+/// The maximum difference allowed between the levels of audio fingerprints.
+const FINGERPRINT_TOLERANCE: f32 = 0.01;
+
+/// The maximum difference allowed between the samples of waveforms.
+const WAVEFORM_TOLERANCE: f32 = 0.01;
+
+/// The default maximum difference allowed in each color channel of rendered images.
+const IMAGE_TOLERANCE: u8 = 2;
+
 #[test]
 fn test_prototype_enumerate() -> Result<(), Error> {
     let trace_log = run_swf("tests/swfs/avm1/prototype_enumerate/test.swf", 1)?;
@@ -184,33 +332,478 @@ fn test_stage_object_properties() -> Result<(), Error> {
 fn test_swf(swf_path: &str, num_frames: u32, expected_output_path: &str) -> Result<(), Error> {
     let expected_output = std::fs::read_to_string(expected_output_path)?.replace("\r\n", "\n");
 
-    let trace_log = run_swf(swf_path, num_frames)?;
-    assert_eq!(trace_log, expected_output, "actual == expected");
+    let test_dir = Path::new(expected_output_path)
+        .parent()
+        .ok_or("Invalid output path")?;
+    let reference_images = reference_images(test_dir, num_frames)?;
+    let capture_frames: Vec<u32> = reference_images.iter().map(|(frame, _)| *frame).collect();
+
+    let input_path = test_dir.join("input.txt");
+    let input_events = if input_path.exists() {
+        read_input_script(&input_path)?
+    } else {
+        vec![]
+    };
+
+    let output = run_swf_with_capture(swf_path, num_frames, &capture_frames, input_events)?;
+    assert_eq!(output.trace_log, expected_output, "actual == expected");
+
+    test_audio(test_dir, &output.audio_samples)?;
+    test_images(test_dir, &reference_images, &output.images)?;
+
+    Ok(())
+}
+
+/// Tests that the captured audio matches the expected fingerprint or waveform
+/// in the test folder, if either exists.
+fn test_audio(test_dir: &Path, samples: &[f32]) -> Result<(), Error> {
+    let fingerprint_path = test_dir.join("audio.txt");
+    if fingerprint_path.exists() {
+        let expected: AudioFingerprint = std::fs::read_to_string(fingerprint_path)?.parse()?;
+        let actual = AudioFingerprint::new(samples, CAPTURE_SAMPLE_RATE, expected.window_ms);
+        if let Err(e) = actual.compare(&expected, FINGERPRINT_TOLERANCE) {
+            panic!("Audio fingerprint mismatch: {}\nActual:\n{}", e, actual);
+        }
+    }
+
+    let waveform_path = test_dir.join("audio.wav");
+    if waveform_path.exists() {
+        let expected = read_wav(std::fs::File::open(waveform_path)?)?;
+        assert_eq!(expected.num_channels, 2, "Waveform must be stereo");
+        assert_eq!(
+            expected.sample_rate, CAPTURE_SAMPLE_RATE,
+            "Waveform sample rate must be {}",
+            CAPTURE_SAMPLE_RATE
+        );
+        assert_eq!(
+            samples.len(),
+            expected.samples.len(),
+            "# of audio samples didn't match"
+        );
+        for (i, (actual, expected)) in samples.iter().zip(&expected.samples).enumerate() {
+            assert!(
+                (actual - expected).abs() <= WAVEFORM_TOLERANCE,
+                "Audio sample {} of frame {}: {} does not match expected {}",
+                i % 2,
+                i / 2,
+                actual,
+                expected
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the reference images in the test folder, named "frameN.png",
+/// with the frames they show in order.
+fn reference_images(test_dir: &Path, num_frames: u32) -> Result<Vec<(u32, PathBuf)>, Error> {
+    let mut images = vec![];
+    for entry in std::fs::read_dir(test_dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if !name.starts_with("frame") || !name.ends_with(".png") {
+            continue;
+        }
+        // Images written by failed tests, such as "frame1-actual.png", aren't references.
+        if let Ok(frame) = name["frame".len()..name.len() - ".png".len()].parse::<u32>() {
+            if frame == 0 || frame > num_frames {
+                return Err(format!("{} is not one of the {} frames run", name, num_frames).into());
+            }
+            images.push((frame, path));
+        }
+    }
+    images.sort();
+    Ok(images)
+}
+
+/// Tests that the rendered frames match the reference images, within the tolerance
+/// in "image_tolerance.txt" if it exists.
+/// On a mismatch, the rendered image and an image of the differing pixels are written
+/// next to the reference, as "frameN-actual.png" and "frameN-diff.png".
+fn test_images(
+    test_dir: &Path,
+    reference_images: &[(u32, PathBuf)],
+    images: &[Image],
+) -> Result<(), Error> {
+    let tolerance_path = test_dir.join("image_tolerance.txt");
+    let tolerance = if tolerance_path.exists() {
+        std::fs::read_to_string(tolerance_path)?.trim().parse()?
+    } else {
+        IMAGE_TOLERANCE
+    };
+
+    let mut failures = vec![];
+    for ((frame, reference_path), actual) in reference_images.iter().zip(images) {
+        let expected = Image::read_png(reference_path)?;
+        let actual_path = test_dir.join(format!("frame{}-actual.png", frame));
+        let diff_path = test_dir.join(format!("frame{}-diff.png", frame));
+        if (actual.width, actual.height) != (expected.width, expected.height) {
+            actual.write_png(&actual_path)?;
+            failures.push(format!(
+                "Frame {} is {}x{}, but {} is {}x{}. Wrote {}",
+                frame,
+                actual.width,
+                actual.height,
+                reference_path.display(),
+                expected.width,
+                expected.height,
+                actual_path.display()
+            ));
+            continue;
+        }
+
+        let (diff, num_different, max_difference) = actual.diff(&expected, tolerance);
+        if num_different > 0 {
+            actual.write_png(&actual_path)?;
+            diff.write_png(&diff_path)?;
+            failures.push(format!(
+                "Frame {} differs from {} at {} pixels, by up to {}. Wrote {} and {}",
+                frame,
+                reference_path.display(),
+                num_different,
+                max_difference,
+                actual_path.display(),
+                diff_path.display()
+            ));
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!("{}", failures.join("\n"));
+    }
 
     Ok(())
 }
 
+/// Reads an input script, returning its events with the frames they are sent before.
+///
+/// Each line holds a 1-based frame number, an event and its arguments:
+/// ```text
+/// # Click at (20, 30) on frame 2, then type "a" on frame 3.
+/// 2 mouse_move 20 30
+/// 2 mouse_down 20 30
+/// 2 mouse_up 20 30
+/// 3 key_down 65
+/// 3 text_input a
+/// 3 key_up 65
+/// ```
+/// Mouse positions are in stage pixels, and keys are Flash key codes.
+/// `mouse_leave` sends the mouse off the stage.
+/// Events must be listed in frame order, and are sent in the order listed.
+fn read_input_script(path: &Path) -> Result<Vec<(u32, PlayerEvent)>, Error> {
+    let script = std::fs::read_to_string(path)?;
+    let mut events = vec![];
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: &str| format!("{}:{}: {}", path.display(), i + 1, message);
+
+        let mut args = line.split_whitespace();
+        let frame: u32 = args
+            .next()
+            .and_then(|frame| frame.parse().ok())
+            .ok_or_else(|| error("Expected a frame number"))?;
+        if events
+            .last()
+            .map_or(false, |(last_frame, _)| frame < *last_frame)
+        {
+            return Err(error("Events must be listed in frame order").into());
+        }
+
+        let name = args.next().ok_or_else(|| error("Expected an event"))?;
+        let args: Vec<&str> = args.collect();
+        let position = || -> Result<(f64, f64), String> {
+            match args[..] {
+                [x, y] => match (x.parse(), y.parse()) {
+                    (Ok(x), Ok(y)) => Ok((x, y)),
+                    _ => Err(error("Invalid mouse position")),
+                },
+                _ => Err(error("Expected a mouse position")),
+            }
+        };
+        let key_code = || -> Result<KeyCode, String> {
+            match args[..] {
+                [key_code] => key_code
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(|key_code| KeyCode::try_from(key_code).ok())
+                    .ok_or_else(|| error("Invalid key code")),
+                _ => Err(error("Expected a key code")),
+            }
+        };
+        let event = match name {
+            "mouse_move" => {
+                let (x, y) = position()?;
+                PlayerEvent::MouseMove { x, y }
+            }
+            "mouse_down" => {
+                let (x, y) = position()?;
+                PlayerEvent::MouseDown { x, y }
+            }
+            "mouse_up" => {
+                let (x, y) = position()?;
+                PlayerEvent::MouseUp { x, y }
+            }
+            "mouse_leave" => PlayerEvent::MouseLeft,
+            "key_down" => PlayerEvent::KeyDown {
+                key_code: key_code()?,
+            },
+            "key_up" => PlayerEvent::KeyUp {
+                key_code: key_code()?,
+            },
+            "text_input" => {
+                let mut chars = args.get(0).map(|text| text.chars());
+                match chars.as_mut().map(|chars| (chars.next(), chars.next())) {
+                    Some((Some(codepoint), None)) => PlayerEvent::TextInput { codepoint },
+                    _ => return Err(error("Expected a single character").into()),
+                }
+            }
+            _ => return Err(error(&format!("Unknown event {}", name)).into()),
+        };
+        events.push((frame, event));
+    }
+    Ok(events)
+}
+
 /// Loads an SWF and runs it through the Ruffle core for a number of frames.
-/// Tests that the trace output matches the given expected output.
+/// Returns the trace output.
 fn run_swf(swf_path: &str, num_frames: u32) -> Result<String, Error> {
+    run_swf_with_capture(swf_path, num_frames, &[], vec![]).map(|output| output.trace_log)
+}
+
+/// The output captured from running an SWF.
+struct Capture {
+    trace_log: String,
+
+    /// The stereo audio samples.
+    audio_samples: Vec<f32>,
+
+    /// The rendered images of the frames that were asked for, in order.
+    images: Vec<Image>,
+}
+
+/// Loads an SWF and runs it through the Ruffle core for a number of frames.
+/// Audio is advanced by one frame's time after each frame.
+/// The stage is rendered after each of the 1-based frames in `capture_frames`.
+/// Each input event is sent before the frame it is paired with runs.
+fn run_swf_with_capture(
+    swf_path: &str,
+    num_frames: u32,
+    capture_frames: &[u32],
+    input_events: Vec<(u32, PlayerEvent)>,
+) -> Result<Capture, Error> {
     let _ = log::set_logger(&TRACE_LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
 
     let swf_data = std::fs::read(swf_path)?;
     let mut player = Player::new(
+        SoftwareRenderBackend::new(0, 0),
+        CaptureAudioBackend::new(),
+        NullNavigatorBackend::new(),
+        ScriptedInputBackend::new(),
+        NullFontBackend::new(),
+        SoftwareVideoBackend::new(),
+        Codepage::default(),
+        swf_data,
+    )?;
+    let (width, height) = (player.movie_width(), player.movie_height());
+    player.renderer_mut().set_viewport_dimensions(width, height);
+    // A paused player is rendered with an overlay.
+    player.set_is_playing(true);
+
+    let frame_time = 1000.0 / player.frame_rate();
+    let mut images = vec![];
+    let mut input_events = input_events.into_iter().peekable();
+    for frame in 1..=num_frames {
+        while input_events.peek().map(|(event_frame, _)| *event_frame) == Some(frame) {
+            let (_, event) = input_events.next().unwrap();
+            player.input_mut().update(&event);
+            player.handle_event(event);
+        }
+        player.run_frame();
+        player.audio_mut().advance(frame_time);
+        if capture_frames.contains(&frame) {
+            player.render();
+            let renderer = player.renderer();
+            images.push(Image {
+                width: renderer.width(),
+                height: renderer.height(),
+                rgba: renderer.to_rgba(),
+            });
+        }
+    }
+
+    Ok(Capture {
+        trace_log: trace_log(),
+        audio_samples: player.audio().samples().to_vec(),
+        images,
+    })
+}
+
+/// Loads an SWF progressively, as if it were being downloaded at `bytes_per_frame`,
+/// and runs it through the Ruffle core for a number of frames.
+/// Returns the trace output.
+fn run_swf_streaming(
+    swf_path: &str,
+    num_frames: u32,
+    bytes_per_frame: usize,
+) -> Result<String, Error> {
+    let _ = log::set_logger(&TRACE_LOGGER).map(|()| log::set_max_level(log::LevelFilter::Info));
+
+    let swf_data = std::fs::read(swf_path)?;
+    let mut chunks = swf_data.chunks(bytes_per_frame);
+    let mut player = Player::new_streaming(
         NullRenderer,
-        NullAudioBackend::new(),
+        CaptureAudioBackend::new(),
         NullNavigatorBackend::new(),
         NullInputBackend::new(),
-        swf_data,
+        NullFontBackend::new(),
+        SoftwareVideoBackend::new(),
+        Codepage::default(),
+        chunks.next().unwrap_or_default(),
     )?;
 
     for _ in 0..num_frames {
         player.run_frame();
+        if let Some(chunk) = chunks.next() {
+            player.append_swf_data(chunk);
+        } else {
+            player.finish_swf_data();
+        }
     }
 
     Ok(trace_log())
 }
 
+/// An input backend that tracks which keys are held down by an input script.
+struct ScriptedInputBackend {
+    keys_down: Vec<KeyCode>,
+    clipboard: String,
+}
+
+impl ScriptedInputBackend {
+    fn new() -> Self {
+        Self {
+            keys_down: vec![],
+            clipboard: String::new(),
+        }
+    }
+
+    /// Updates the held keys for an event about to be sent to the player.
+    fn update(&mut self, event: &PlayerEvent) {
+        match *event {
+            PlayerEvent::KeyDown { key_code } if !self.keys_down.contains(&key_code) => {
+                self.keys_down.push(key_code)
+            }
+            PlayerEvent::KeyUp { key_code } => self.keys_down.retain(|key| *key != key_code),
+            _ => (),
+        }
+    }
+}
+
+impl InputBackend for ScriptedInputBackend {
+    fn is_key_down(&self, key: KeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    fn mouse_visible(&self) -> bool {
+        true
+    }
+
+    fn hide_mouse(&mut self) {}
+
+    fn show_mouse(&mut self) {}
+
+    fn clipboard_content(&mut self) -> String {
+        self.clipboard.clone()
+    }
+
+    fn set_clipboard_content(&mut self, content: String) {
+        self.clipboard = content;
+    }
+}
+
+/// An image in RGBA with straight alpha.
+struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Image {
+    fn read_png(path: &Path) -> Result<Self, Error> {
+        let mut decoder = png::Decoder::new(std::fs::File::open(path)?);
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let (info, mut reader) = decoder.read_info()?;
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data)?;
+        let rgba = match (info.color_type, info.bit_depth) {
+            (png::ColorType::RGBA, png::BitDepth::Eight) => data,
+            (png::ColorType::RGB, png::BitDepth::Eight) => data
+                .chunks_exact(3)
+                .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            format => {
+                return Err(
+                    format!("{}: Unsupported PNG format {:?}", path.display(), format).into(),
+                )
+            }
+        };
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            rgba,
+        })
+    }
+
+    fn write_png(&self, path: &Path) -> Result<(), Error> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.rgba)?;
+        Ok(())
+    }
+
+    /// Compares with an image of the same size.
+    /// Returns an image of the pixels that differ by more than `tolerance` in any channel,
+    /// the number of those pixels, and the largest difference.
+    fn diff(&self, other: &Image, tolerance: u8) -> (Image, usize, u8) {
+        let mut rgba = Vec::with_capacity(self.rgba.len());
+        let mut num_different = 0;
+        let mut max_difference = 0;
+        for (a, b) in self.rgba.chunks_exact(4).zip(other.rgba.chunks_exact(4)) {
+            let difference = a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| (i16::from(*a) - i16::from(*b)).abs() as u8)
+                .max()
+                .unwrap_or(0);
+            max_difference = max_difference.max(difference);
+            if difference > tolerance {
+                num_different += 1;
+                rgba.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                // Matching pixels are faded, to show where the differences are.
+                let luma = (u16::from(a[0]) * 3 + u16::from(a[1]) * 6 + u16::from(a[2])) / 10;
+                let faded = 192 + (luma / 4) as u8;
+                rgba.extend_from_slice(&[faded, faded, faded, 255]);
+            }
+        }
+        let diff = Image {
+            width: self.width,
+            height: self.height,
+            rgba,
+        };
+        (diff, num_different, max_difference)
+    }
+}
+
 thread_local! {
     static TRACE_LOG: RefCell<String> = RefCell::new(String::new());
 }
@@ -0,0 +1,139 @@
+use ruffle_core::backend::font::{read_font_faces, FontBackend, FontFace};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+
+/// An installed font: the font file, the index of the font in the file, and its face.
+type InstalledFont = (PathBuf, u32, FontFace);
+
+/// A font backend that loads the TrueType and OpenType fonts installed on the system.
+pub struct SystemFontBackend {
+    /// The installed fonts, once the font directories have been scanned.
+    fonts: Vec<InstalledFont>,
+
+    /// The scan of the font directories, which runs in the background until a device font
+    /// is needed.
+    scan: Option<JoinHandle<Vec<InstalledFont>>>,
+}
+
+impl SystemFontBackend {
+    pub fn new() -> Self {
+        let scan = std::thread::spawn(|| {
+            let mut fonts = vec![];
+            for directory in Self::font_directories() {
+                Self::scan_directory(&directory, &mut fonts);
+            }
+            log::info!("Found {} installed fonts", fonts.len());
+            fonts
+        });
+        Self {
+            fonts: vec![],
+            scan: Some(scan),
+        }
+    }
+
+    /// Returns the directories that fonts are installed in on this platform.
+    fn font_directories() -> Vec<PathBuf> {
+        let mut directories = vec![];
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        if cfg!(target_os = "windows") {
+            if let Some(windows) = std::env::var_os("WINDIR") {
+                directories.push(Path::new(&windows).join("Fonts"));
+            }
+            if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
+                directories.push(Path::new(&local_app_data).join("Microsoft/Windows/Fonts"));
+            }
+        } else if cfg!(target_os = "macos") {
+            directories.push("/System/Library/Fonts".into());
+            directories.push("/Library/Fonts".into());
+            if let Some(home) = home {
+                directories.push(home.join("Library/Fonts"));
+            }
+        } else {
+            directories.push("/usr/share/fonts".into());
+            directories.push("/usr/local/share/fonts".into());
+            if let Some(data_home) = std::env::var_os("XDG_DATA_HOME") {
+                directories.push(Path::new(&data_home).join("fonts"));
+            }
+            if let Some(home) = home {
+                directories.push(home.join(".local/share/fonts"));
+                directories.push(home.join(".fonts"));
+            }
+        }
+        directories
+    }
+
+    /// Recursively finds the font files in a directory and reads their faces.
+    /// Only the tables describing each face are read, not the whole file.
+    fn scan_directory(directory: &Path, fonts: &mut Vec<InstalledFont>) {
+        let entries = match std::fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.is_dir() {
+                Self::scan_directory(&path, fonts);
+                continue;
+            }
+            let is_font_file = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| {
+                    ["ttf", "otf", "ttc", "otc"]
+                        .iter()
+                        .any(|font_extension| extension.eq_ignore_ascii_case(font_extension))
+                })
+                .unwrap_or(false);
+            if !is_font_file {
+                continue;
+            }
+            let faces = File::open(&path)
+                .map_err(Box::<dyn std::error::Error>::from)
+                .and_then(|file| read_font_faces(&mut BufReader::new(file)));
+            match faces {
+                Ok(faces) => {
+                    for (index, face) in faces.into_iter().enumerate() {
+                        fonts.push((path.clone(), index as u32, face));
+                    }
+                }
+                Err(e) => log::warn!("Unable to read font {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Returns the installed fonts, waiting for the scan of the font directories to finish.
+    fn fonts(&mut self) -> &[InstalledFont] {
+        if let Some(scan) = self.scan.take() {
+            match scan.join() {
+                Ok(fonts) => self.fonts = fonts,
+                Err(_) => log::error!("Unable to scan the installed fonts"),
+            }
+        }
+        &self.fonts
+    }
+}
+
+impl FontBackend for SystemFontBackend {
+    fn load_font(
+        &mut self,
+        family: &str,
+        is_bold: bool,
+        is_italic: bool,
+    ) -> Option<(Vec<u8>, u32)> {
+        // Find the closest style in the family, preferring a matching weight over a matching slant.
+        let (path, index, _) = self
+            .fonts()
+            .iter()
+            .filter(|(_, _, face)| face.family.eq_ignore_ascii_case(family))
+            .max_by_key(|(_, _, face)| (face.is_bold == is_bold, face.is_italic == is_italic))?;
+        match std::fs::read(path) {
+            Ok(data) => Some((data, *index)),
+            Err(e) => {
+                log::warn!("Unable to load font {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
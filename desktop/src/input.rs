@@ -1,3 +1,4 @@
+use clipboard::{ClipboardContext, ClipboardProvider};
 use glium::Display;
 use ruffle_core::backend::input::InputBackend;
 use ruffle_core::events::{KeyCode, PlayerEvent};
@@ -8,6 +9,7 @@ pub struct WinitInputBackend {
     keys_down: HashSet<VirtualKeyCode>,
     display: Display,
     cursor_visible: bool,
+    clipboard: Option<ClipboardContext>,
 }
 
 impl WinitInputBackend {
@@ -15,6 +17,9 @@ impl WinitInputBackend {
         Self {
             keys_down: HashSet::new(),
             cursor_visible: true,
+            clipboard: ClipboardProvider::new()
+                .map_err(|e| log::warn!("Unable to access clipboard: {}", e))
+                .ok(),
             display,
         }
     }
@@ -34,6 +39,9 @@ impl WinitInputBackend {
                 ElementState::Released => {
                     if let Some(key) = input.virtual_keycode {
                         self.keys_down.remove(&key);
+                        if let Some(key_code) = winit_to_ruffle_key_code(key) {
+                            return Some(PlayerEvent::KeyUp { key_code });
+                        }
                     }
                 }
             },
@@ -169,6 +177,21 @@ impl InputBackend for WinitInputBackend {
         self.display.gl_window().window().set_cursor_visible(true);
         self.cursor_visible = true;
     }
+
+    fn clipboard_content(&mut self) -> String {
+        self.clipboard
+            .as_mut()
+            .and_then(|clipboard| clipboard.get_contents().ok())
+            .unwrap_or_default()
+    }
+
+    fn set_clipboard_content(&mut self, content: String) {
+        if let Some(clipboard) = &mut self.clipboard {
+            if let Err(e) = clipboard.set_contents(content) {
+                log::warn!("Unable to set clipboard contents: {}", e);
+            }
+        }
+    }
 }
 
 /// Converts a winit `VirtualKeyCode` into a Ruffle `KeyCode`.
@@ -1,6 +1,7 @@
 #![allow(clippy::unneeded_field_pattern)]
 
 mod audio;
+mod font;
 mod input;
 mod navigator;
 mod render;
@@ -16,7 +17,8 @@ use glutin::{
 use ruffle_core::{
     backend::audio::{AudioBackend, NullAudioBackend},
     backend::render::RenderBackend,
-    Player,
+    backend::video::SoftwareVideoBackend,
+    Codepage, Player,
 };
 use std::path::PathBuf;
 use std::time::Instant;
@@ -27,6 +29,19 @@ use structopt::StructOpt;
 struct Opt {
     #[structopt(name = "FILE", parse(from_os_str))]
     input_path: PathBuf,
+
+    /// The codepage used to decode text in SWF 5 and earlier movies.
+    /// Japanese movies need "shift-jis".
+    #[structopt(long, default_value = "windows-1252", parse(try_from_str = parse_codepage))]
+    codepage: Codepage,
+}
+
+fn parse_codepage(name: &str) -> Result<Codepage, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "windows-1252" | "cp1252" => Ok(Codepage::Windows1252),
+        "shift-jis" | "shift_jis" | "sjis" | "cp932" => Ok(Codepage::ShiftJis),
+        _ => Err(format!("Unknown codepage: {}", name)),
+    }
 }
 
 fn main() {
@@ -34,7 +49,7 @@ fn main() {
 
     let opt = Opt::from_args();
 
-    let ret = run_player(opt.input_path);
+    let ret = run_player(opt.input_path, opt.codepage);
 
     if let Err(e) = ret {
         eprintln!("Fatal error:\n{}", e);
@@ -42,8 +57,8 @@ fn main() {
     }
 }
 
-fn run_player(input_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let swf_data = std::fs::read(input_path)?;
+fn run_player(input_path: PathBuf, codepage: Codepage) -> Result<(), Box<dyn std::error::Error>> {
+    let swf_data = std::fs::read(&input_path)?;
 
     let event_loop = EventLoop::new();
     let window_builder = WindowBuilder::new().with_title("Ruffle");
@@ -61,10 +76,18 @@ fn run_player(input_path: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
         }
     };
     let renderer = GliumRenderBackend::new(windowed_context)?;
-    let navigator = navigator::ExternalNavigatorBackend::new(); //TODO: actually implement this backend type
+    let navigator = navigator::ExternalNavigatorBackend::new(
+        input_path
+            .parent()
+            .unwrap_or_else(|| std::path::Path::new("")),
+    );
     let display = renderer.display().clone();
     let input = input::WinitInputBackend::new(display.clone());
-    let mut player = Player::new(renderer, audio, navigator, input, swf_data)?;
+    let fonts = font::SystemFontBackend::new();
+    let video = SoftwareVideoBackend::new();
+    let mut player = Player::new(
+        renderer, audio, navigator, input, fonts, video, codepage, swf_data,
+    )?;
     player.set_is_playing(true); // Desktop player will auto-play.
 
     let logical_size: LogicalSize = (player.movie_width(), player.movie_height()).into();
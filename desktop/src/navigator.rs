@@ -1,18 +1,44 @@
 //! Navigator backend for web
 
+use generational_arena::Arena;
 use log;
-use ruffle_core::backend::navigator::{NavigationMethod, NavigatorBackend};
+use ruffle_core::backend::navigator::{
+    FetchHandle, FetchStatus, NavigationMethod, NavigatorBackend,
+};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use url::Url;
 use webbrowser;
 
 /// Implementation of `NavigatorBackend` for non-web environments that can call
 /// out to a web browser.
-pub struct ExternalNavigatorBackend {}
+pub struct ExternalNavigatorBackend {
+    /// The directory containing the SWF. Relative URLs are loaded from here.
+    base_path: PathBuf,
+
+    /// Pending fetches. Each file is read on its own thread.
+    fetches: Arena<Receiver<Result<Vec<u8>, String>>>,
+}
 
 impl ExternalNavigatorBackend {
-    pub fn new() -> Self {
-        ExternalNavigatorBackend {}
+    pub fn new(base_path: &Path) -> Self {
+        ExternalNavigatorBackend {
+            base_path: base_path.to_path_buf(),
+            fetches: Arena::new(),
+        }
+    }
+
+    /// Resolves a URL to a local file path.
+    fn resolve_path(&self, url: &str) -> Result<PathBuf, String> {
+        match Url::parse(url) {
+            Ok(parsed_url) if parsed_url.scheme() == "file" => parsed_url
+                .to_file_path()
+                .map_err(|_| format!("Invalid file URL: {}", url)),
+            // TODO: Support loading over HTTP.
+            Ok(parsed_url) => Err(format!("Unsupported URL scheme: {}", parsed_url.scheme())),
+            Err(_) => Ok(self.base_path.join(url)),
+        }
     }
 }
 
@@ -60,4 +86,47 @@ impl NavigatorBackend for ExternalNavigatorBackend {
             Err(e) => log::error!("Could not open URL {}: {}", modified_url, e),
         };
     }
+
+    fn fetch(&mut self, url: String) -> FetchHandle {
+        let (sender, receiver) = channel();
+        match self.resolve_path(&url) {
+            Ok(path) => {
+                std::thread::spawn(move || {
+                    let result = std::fs::read(&path)
+                        .map_err(|e| format!("Could not read {}: {}", path.display(), e));
+                    let _ = sender.send(result);
+                });
+            }
+            Err(e) => {
+                let _ = sender.send(Err(e));
+            }
+        }
+        self.fetches.insert(receiver)
+    }
+
+    fn poll_fetch(&mut self, handle: FetchHandle) -> FetchStatus {
+        let result = match self.fetches.get(handle) {
+            Some(receiver) => receiver.try_recv(),
+            None => return FetchStatus::Failed,
+        };
+        match result {
+            Err(TryRecvError::Empty) => FetchStatus::Loading {
+                bytes_loaded: 0,
+                bytes_total: None,
+            },
+            Ok(Ok(data)) => {
+                self.fetches.remove(handle);
+                FetchStatus::Complete(data)
+            }
+            Ok(Err(e)) => {
+                log::error!("{}", e);
+                self.fetches.remove(handle);
+                FetchStatus::Failed
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.fetches.remove(handle);
+                FetchStatus::Failed
+            }
+        }
+    }
 }
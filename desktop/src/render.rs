@@ -1,3 +1,5 @@
+use glium::framebuffer::{SimpleFrameBuffer, StencilRenderBuffer};
+use glium::texture::{RawImage2d, StencilFormat};
 use glium::uniforms::{Sampler, UniformValue, Uniforms};
 use glium::{draw_parameters::DrawParameters, implement_vertex, uniform, Display, Frame, Surface};
 use glutin::WindowedContext;
@@ -7,6 +9,7 @@ use lyon::tessellation::{
     geometry_builder::{BuffersBuilder, FillVertexConstructor, VertexBuffers},
     FillAttributes, FillTessellator, StrokeAttributes, StrokeTessellator, StrokeVertexConstructor,
 };
+use ruffle_core::backend::render::filters;
 use ruffle_core::backend::render::swf::{self, FillStyle};
 use ruffle_core::backend::render::{
     BitmapHandle, BitmapInfo, Color, Letterbox, RenderBackend, ShapeHandle, Transform,
@@ -23,15 +26,17 @@ pub struct GliumRenderBackend {
     shader_program: glium::Program,
     gradient_shader_program: glium::Program,
     bitmap_shader_program: glium::Program,
+    layer_shader_program: glium::Program,
+    blend_shader_program: glium::Program,
     meshes: Vec<Mesh>,
     quad_shape: ShapeHandle,
     textures: Vec<(swf::CharacterId, Texture)>,
-    num_masks: u32,
-    num_masks_active: u32,
-    write_stencil_mask: u32,
-    test_stencil_mask: u32,
-    next_stencil_mask: u32,
-    mask_stack: Vec<(u32, u32)>,
+    masks: MaskState,
+    /// The offscreen layers being rendered, from the bottom.
+    /// Drawing goes to the top layer, or to `target` when there are none.
+    layers: Vec<Layer>,
+    /// The buffers of popped layers, reused by later layers of the same size.
+    free_layer_buffers: Vec<LayerBuffers>,
     viewport_width: f32,
     viewport_height: f32,
     view_matrix: [[f32; 4]; 4],
@@ -86,6 +91,34 @@ impl GliumRenderBackend {
             },
         )?;
 
+        let layer_shader_program = glium::Program::new(
+            &display,
+            ProgramCreationInput::SourceCode {
+                vertex_shader: LAYER_VERTEX_SHADER,
+                fragment_shader: LAYER_FRAGMENT_SHADER,
+                geometry_shader: None,
+                tessellation_control_shader: None,
+                tessellation_evaluation_shader: None,
+                transform_feedback_varyings: None,
+                outputs_srgb: true,
+                uses_point_size: false,
+            },
+        )?;
+
+        let blend_shader_program = glium::Program::new(
+            &display,
+            ProgramCreationInput::SourceCode {
+                vertex_shader: LAYER_VERTEX_SHADER,
+                fragment_shader: BLEND_FRAGMENT_SHADER,
+                geometry_shader: None,
+                tessellation_control_shader: None,
+                tessellation_evaluation_shader: None,
+                transform_feedback_varyings: None,
+                outputs_srgb: true,
+                uses_point_size: false,
+            },
+        )?;
+
         let quad_mesh = Self::build_quad_mesh(&display)?;
         let quad_shape = ShapeHandle(0);
 
@@ -94,6 +127,8 @@ impl GliumRenderBackend {
             shader_program,
             gradient_shader_program,
             bitmap_shader_program,
+            layer_shader_program,
+            blend_shader_program,
             target: None,
             meshes: vec![quad_mesh],
             quad_shape,
@@ -101,12 +136,9 @@ impl GliumRenderBackend {
             viewport_width: 500.0,
             viewport_height: 500.0,
             view_matrix: [[0.0; 4]; 4],
-            num_masks: 0,
-            num_masks_active: 0,
-            write_stencil_mask: 0,
-            test_stencil_mask: 0,
-            next_stencil_mask: 1,
-            mask_stack: vec![],
+            masks: MaskState::new(),
+            layers: vec![],
+            free_layer_buffers: vec![],
         };
         renderer.build_matrices();
         Ok(renderer)
@@ -147,7 +179,7 @@ impl GliumRenderBackend {
                 draw_type: DrawType::Bitmap {
                     uniforms: BitmapUniforms {
                         matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
-                        id: 0,
+                        texture: 0,
                     },
                     is_smoothed: true,
                     is_repeating: false,
@@ -402,12 +434,12 @@ impl GliumRenderBackend {
                             continue;
                         }
 
-                        let texture = &self
+                        let texture_index = self
                             .textures
                             .iter()
-                            .find(|(other_id, _tex)| *other_id == *id)
-                            .unwrap()
-                            .1;
+                            .position(|(other_id, _tex)| *other_id == *id)
+                            .unwrap();
+                        let texture = &self.textures[texture_index].1;
 
                         let uniforms = BitmapUniforms {
                             matrix: swf_bitmap_to_gl_matrix(
@@ -415,7 +447,7 @@ impl GliumRenderBackend {
                                 texture.width,
                                 texture.height,
                             ),
-                            id: *id,
+                            texture: texture_index,
                         };
 
                         flush_draw(
@@ -502,6 +534,214 @@ impl GliumRenderBackend {
             [-1.0, 1.0, 0.0, 1.0],
         ];
     }
+
+    /// Draws a shape to the given surface.
+    fn draw_shape<S: Surface>(&self, target: &mut S, shape: ShapeHandle, transform: &Transform) {
+        let mesh = &self.meshes[shape.0];
+
+        let world_matrix = [
+            [transform.matrix.a, transform.matrix.b, 0.0, 0.0],
+            [transform.matrix.c, transform.matrix.d, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [
+                transform.matrix.tx / 20.0,
+                transform.matrix.ty / 20.0,
+                0.0,
+                1.0,
+            ],
+        ];
+
+        let mult_color = [
+            transform.color_transform.r_mult,
+            transform.color_transform.g_mult,
+            transform.color_transform.b_mult,
+            transform.color_transform.a_mult,
+        ];
+
+        let add_color = [
+            transform.color_transform.r_add,
+            transform.color_transform.g_add,
+            transform.color_transform.b_add,
+            transform.color_transform.a_add,
+        ];
+
+        let mut draw_parameters = DrawParameters::default();
+        mask_draw_parameters(
+            &mut draw_parameters,
+            self.masks.num_masks,
+            self.masks.num_masks_active,
+            self.masks.write_stencil_mask,
+            self.masks.test_stencil_mask,
+        );
+
+        for draw in &mesh.draws {
+            match &draw.draw_type {
+                DrawType::Color => {
+                    draw_parameters.blend = color_blend();
+
+                    target
+                        .draw(
+                            &draw.vertex_buffer,
+                            &draw.index_buffer,
+                            &self.shader_program,
+                            &uniform! { view_matrix: self.view_matrix, world_matrix: world_matrix, mult_color: mult_color, add_color: add_color },
+                            &draw_parameters
+                        )
+                        .unwrap();
+                }
+                DrawType::Gradient(gradient_uniforms) => {
+                    let uniforms = GradientUniformsFull {
+                        view_matrix: self.view_matrix,
+                        world_matrix,
+                        mult_color,
+                        add_color,
+                        gradient: gradient_uniforms.clone(),
+                    };
+
+                    draw_parameters.blend = color_blend();
+
+                    target
+                        .draw(
+                            &draw.vertex_buffer,
+                            &draw.index_buffer,
+                            &self.gradient_shader_program,
+                            &uniforms,
+                            &draw_parameters,
+                        )
+                        .unwrap();
+                }
+                DrawType::Bitmap {
+                    uniforms,
+                    is_smoothed,
+                    is_repeating,
+                } => {
+                    let texture = &self.textures[uniforms.texture].1;
+
+                    // Set texture sampler smooth/repeat parameters.
+                    use glium::uniforms::{
+                        MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction,
+                    };
+                    let texture = &texture
+                        .texture
+                        .sampled()
+                        .magnify_filter(if *is_smoothed {
+                            MagnifySamplerFilter::Linear
+                        } else {
+                            MagnifySamplerFilter::Nearest
+                        })
+                        .minify_filter(if *is_smoothed {
+                            MinifySamplerFilter::LinearMipmapLinear
+                        } else {
+                            MinifySamplerFilter::Nearest
+                        })
+                        .wrap_function(if *is_repeating {
+                            SamplerWrapFunction::Repeat
+                        } else {
+                            SamplerWrapFunction::Clamp
+                        });
+
+                    let uniforms = BitmapUniformsFull {
+                        view_matrix: self.view_matrix,
+                        world_matrix,
+                        mult_color,
+                        add_color,
+                        matrix: uniforms.matrix,
+                        texture,
+                    };
+
+                    draw_parameters.blend = bitmap_blend();
+
+                    target
+                        .draw(
+                            &draw.vertex_buffer,
+                            &draw.index_buffer,
+                            &self.bitmap_shader_program,
+                            &uniforms,
+                            &draw_parameters,
+                        )
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Takes unused layer buffers, or creates them.
+    fn take_layer_buffers(&mut self) -> LayerBuffers {
+        match self.free_layer_buffers.pop() {
+            Some(buffers) => buffers,
+            None => LayerBuffers::new(
+                &self.display,
+                self.viewport_width as u32,
+                self.viewport_height as u32,
+            ),
+        }
+    }
+
+    /// Composites the texture of a popped layer onto the given surface, like
+    /// `blend::composite_rgba`. The blend modes other than normal read a copy of the surface
+    /// made in `backdrop`.
+    fn composite_layer<S: Surface>(
+        &self,
+        target: &mut S,
+        texture: &glium::Texture2d,
+        backdrop: &glium::Texture2d,
+        blend_mode: swf::BlendMode,
+    ) {
+        use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
+
+        let mut draw_parameters = DrawParameters::default();
+        mask_draw_parameters(
+            &mut draw_parameters,
+            self.masks.num_masks,
+            self.masks.num_masks_active,
+            self.masks.write_stencil_mask,
+            self.masks.test_stencil_mask,
+        );
+
+        let layer = texture
+            .sampled()
+            .magnify_filter(MagnifySamplerFilter::Nearest)
+            .minify_filter(MinifySamplerFilter::Nearest);
+
+        // The quad covers the whole surface.
+        let draw = &self.meshes[self.quad_shape.0].draws[0];
+
+        // A layer drawn into a mask covers it where it isn't transparent, whatever its blend mode.
+        let is_drawing_mask = self.masks.num_masks_active < self.masks.num_masks;
+        let blend_mode_index = match blend_mode {
+            swf::BlendMode::Normal | swf::BlendMode::Layer => None,
+            _ if is_drawing_mask => None,
+            _ => Some(blend_mode_index(blend_mode)),
+        };
+        if let Some(blend_mode_index) = blend_mode_index {
+            target.fill(&backdrop.as_surface(), MagnifySamplerFilter::Nearest);
+            let backdrop = backdrop
+                .sampled()
+                .magnify_filter(MagnifySamplerFilter::Nearest)
+                .minify_filter(MinifySamplerFilter::Nearest);
+            // The shader outputs the composited color, replacing the surface.
+            target
+                .draw(
+                    &draw.vertex_buffer,
+                    &draw.index_buffer,
+                    &self.blend_shader_program,
+                    &uniform! { u_layer: layer, u_backdrop: backdrop, u_blend_mode: blend_mode_index },
+                    &draw_parameters,
+                )
+                .unwrap();
+        } else {
+            draw_parameters.blend = bitmap_blend();
+            target
+                .draw(
+                    &draw.vertex_buffer,
+                    &draw.index_buffer,
+                    &self.layer_shader_program,
+                    &uniform! { u_layer: layer },
+                    &draw_parameters,
+                )
+                .unwrap();
+        }
+    }
 }
 
 impl RenderBackend for GliumRenderBackend {
@@ -509,6 +749,7 @@ impl RenderBackend for GliumRenderBackend {
         self.viewport_width = width as f32;
         self.viewport_height = height as f32;
         self.build_matrices();
+        self.free_layer_buffers.clear();
     }
 
     fn register_shape(&mut self, shape: &swf::Shape) -> ShapeHandle {
@@ -636,14 +877,77 @@ impl RenderBackend for GliumRenderBackend {
         }
     }
 
+    fn register_bitmap_raw(
+        &mut self,
+        id: swf::CharacterId,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> BitmapInfo {
+        let image = glium::texture::RawImage2d::from_raw_rgba(rgba, (width, height));
+        let texture = glium::texture::Texture2d::new(&self.display, image).unwrap();
+        let handle = BitmapHandle(self.textures.len());
+        self.textures.push((
+            id,
+            Texture {
+                texture,
+                width,
+                height,
+            },
+        ));
+
+        BitmapInfo {
+            handle,
+            width: width.try_into().unwrap(),
+            height: height.try_into().unwrap(),
+        }
+    }
+
+    fn update_texture(
+        &mut self,
+        bitmap: BitmapHandle,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> Result<BitmapHandle, Error> {
+        let image = glium::texture::RawImage2d::from_raw_rgba(rgba, (width, height));
+        let texture = glium::texture::Texture2d::new(&self.display, image)?;
+        let (_id, old_texture) = self
+            .textures
+            .get_mut(bitmap.0)
+            .ok_or("Unknown bitmap handle")?;
+        *old_texture = Texture {
+            texture,
+            width,
+            height,
+        };
+        Ok(bitmap)
+    }
+
+    fn unregister_bitmap(&mut self, bitmap: BitmapHandle) {
+        // Handles are indices into `textures`, so the texture is replaced by an empty one.
+        if let Some((_id, texture)) = self.textures.get_mut(bitmap.0) {
+            match glium::texture::Texture2d::empty(&self.display, 1, 1) {
+                Ok(empty) => {
+                    *texture = Texture {
+                        texture: empty,
+                        width: 1,
+                        height: 1,
+                    }
+                }
+                Err(e) => log::error!("Unable to release texture: {}", e),
+            }
+        }
+    }
+
     fn begin_frame(&mut self) {
         assert!(self.target.is_none());
         self.target = Some(self.display.draw());
-        self.num_masks = 0;
-        self.num_masks_active = 0;
-        self.write_stencil_mask = 0;
-        self.test_stencil_mask = 0;
-        self.next_stencil_mask = 1;
+        self.masks = MaskState::new();
+        if !self.layers.is_empty() {
+            log::warn!("Layers were not popped in the previous frame");
+            self.layers.clear();
+        }
     }
 
     fn end_frame(&mut self) {
@@ -665,22 +969,25 @@ impl RenderBackend for GliumRenderBackend {
         );
     }
 
-    fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform) {
+    fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform, smoothing: bool) {
         // TODO: Might be better to make this separate code to render the bitmap
         // instead of going through render_shape. But render_shape already handles
         // masking etc.
-        if let Some((id, bitmap)) = self.textures.get(bitmap.0) {
+        if let Some((_id, texture)) = self.textures.get(bitmap.0) {
             // Adjust the quad draw to use the target bitmap.
             let mesh = &mut self.meshes[self.quad_shape.0];
             let draw = &mut mesh.draws[0];
-            let width = bitmap.width as f32;
-            let height = bitmap.height as f32;
+            let width = texture.width as f32;
+            let height = texture.height as f32;
             if let DrawType::Bitmap {
-                uniforms: BitmapUniforms { id: draw_id, .. },
+                uniforms,
+                is_smoothed,
                 ..
             } = &mut draw.draw_type
             {
-                *draw_id = *id;
+                // The texture is found by its handle, as video frames can share a character ID.
+                uniforms.texture = bitmap.0;
+                *is_smoothed = smoothing;
             }
 
             // Scale the quad to the bitmap's dimensions.
@@ -701,138 +1008,13 @@ impl RenderBackend for GliumRenderBackend {
     }
 
     fn render_shape(&mut self, shape: ShapeHandle, transform: &Transform) {
-        let target = self.target.as_mut().unwrap();
-
-        let mesh = &self.meshes[shape.0];
-
-        let world_matrix = [
-            [transform.matrix.a, transform.matrix.b, 0.0, 0.0],
-            [transform.matrix.c, transform.matrix.d, 0.0, 0.0],
-            [0.0, 0.0, 1.0, 0.0],
-            [
-                transform.matrix.tx / 20.0,
-                transform.matrix.ty / 20.0,
-                0.0,
-                1.0,
-            ],
-        ];
-
-        let mult_color = [
-            transform.color_transform.r_mult,
-            transform.color_transform.g_mult,
-            transform.color_transform.b_mult,
-            transform.color_transform.a_mult,
-        ];
-
-        let add_color = [
-            transform.color_transform.r_add,
-            transform.color_transform.g_add,
-            transform.color_transform.b_add,
-            transform.color_transform.a_add,
-        ];
-
-        let mut draw_parameters = DrawParameters::default();
-        mask_draw_parameters(
-            &mut draw_parameters,
-            self.num_masks,
-            self.num_masks_active,
-            self.write_stencil_mask,
-            self.test_stencil_mask,
-        );
-
-        for draw in &mesh.draws {
-            match &draw.draw_type {
-                DrawType::Color => {
-                    draw_parameters.blend = color_blend();
-
-                    target
-                        .draw(
-                            &draw.vertex_buffer,
-                            &draw.index_buffer,
-                            &self.shader_program,
-                            &uniform! { view_matrix: self.view_matrix, world_matrix: world_matrix, mult_color: mult_color, add_color: add_color },
-                            &draw_parameters
-                        )
-                        .unwrap();
-                }
-                DrawType::Gradient(gradient_uniforms) => {
-                    let uniforms = GradientUniformsFull {
-                        view_matrix: self.view_matrix,
-                        world_matrix,
-                        mult_color,
-                        add_color,
-                        gradient: gradient_uniforms.clone(),
-                    };
-
-                    draw_parameters.blend = color_blend();
-
-                    target
-                        .draw(
-                            &draw.vertex_buffer,
-                            &draw.index_buffer,
-                            &self.gradient_shader_program,
-                            &uniforms,
-                            &draw_parameters,
-                        )
-                        .unwrap();
-                }
-                DrawType::Bitmap {
-                    uniforms,
-                    is_smoothed,
-                    is_repeating,
-                } => {
-                    let texture = &self
-                        .textures
-                        .iter()
-                        .find(|(id, _tex)| *id == uniforms.id)
-                        .unwrap()
-                        .1;
-
-                    // Set texture sampler smooth/repeat parameters.
-                    use glium::uniforms::{
-                        MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction,
-                    };
-                    let texture = &texture
-                        .texture
-                        .sampled()
-                        .magnify_filter(if *is_smoothed {
-                            MagnifySamplerFilter::Linear
-                        } else {
-                            MagnifySamplerFilter::Nearest
-                        })
-                        .minify_filter(if *is_smoothed {
-                            MinifySamplerFilter::LinearMipmapLinear
-                        } else {
-                            MinifySamplerFilter::Nearest
-                        })
-                        .wrap_function(if *is_repeating {
-                            SamplerWrapFunction::Repeat
-                        } else {
-                            SamplerWrapFunction::Clamp
-                        });
-
-                    let uniforms = BitmapUniformsFull {
-                        view_matrix: self.view_matrix,
-                        world_matrix,
-                        mult_color,
-                        add_color,
-                        matrix: uniforms.matrix,
-                        texture,
-                    };
-
-                    draw_parameters.blend = bitmap_blend();
-
-                    target
-                        .draw(
-                            &draw.vertex_buffer,
-                            &draw.index_buffer,
-                            &self.bitmap_shader_program,
-                            &uniforms,
-                            &draw_parameters,
-                        )
-                        .unwrap();
-                }
-            }
+        if let Some(layer) = self.layers.last() {
+            let mut target = layer.buffers.framebuffer(&self.display);
+            self.draw_shape(&mut target, shape, transform);
+        } else {
+            let mut target = self.target.take().unwrap();
+            self.draw_shape(&mut target, shape, transform);
+            self.target = Some(target);
         }
     }
 
@@ -902,39 +1084,93 @@ impl RenderBackend for GliumRenderBackend {
         // Desktop draws the masker to the stencil buffer, one bit per mask.
         // Masks-within-masks are handled as a bitmask.
         // This does unfortunately mean we are limited in the number of masks at once (usually 8 bits).
-        if self.next_stencil_mask >= 0x100 {
+        if self.masks.next_stencil_mask >= 0x100 {
             // If we've reached the limit of masks, clear the stencil buffer and start over.
             // But this may not be correct if there is still a mask active (mask-within-mask).
-            let target = self.target.as_mut().unwrap();
-            if self.test_stencil_mask != 0 {
+            if self.masks.test_stencil_mask != 0 {
                 log::warn!(
                     "Too many masks active for stencil buffer; possibly incorrect rendering"
                 );
             }
-            self.next_stencil_mask = 1;
-            target.clear_stencil(self.test_stencil_mask as i32);
+            self.masks.next_stencil_mask = 1;
+            let value = self.masks.test_stencil_mask as i32;
+            if let Some(layer) = self.layers.last() {
+                layer
+                    .buffers
+                    .framebuffer(&self.display)
+                    .clear_stencil(value);
+            } else {
+                self.target.as_mut().unwrap().clear_stencil(value);
+            }
         }
-        self.num_masks += 1;
-        self.mask_stack
-            .push((self.write_stencil_mask, self.test_stencil_mask));
-        self.write_stencil_mask = self.next_stencil_mask;
-        self.test_stencil_mask |= self.next_stencil_mask;
-        self.next_stencil_mask <<= 1;
+        let masks = &mut self.masks;
+        masks.num_masks += 1;
+        masks
+            .mask_stack
+            .push((masks.write_stencil_mask, masks.test_stencil_mask));
+        masks.write_stencil_mask = masks.next_stencil_mask;
+        masks.test_stencil_mask |= masks.next_stencil_mask;
+        masks.next_stencil_mask <<= 1;
     }
     fn activate_mask(&mut self) {
-        self.num_masks_active += 1;
+        self.masks.num_masks_active += 1;
     }
     fn pop_mask(&mut self) {
-        if !self.mask_stack.is_empty() {
-            self.num_masks -= 1;
-            self.num_masks_active -= 1;
-            let (write, test) = self.mask_stack.pop().unwrap();
-            self.write_stencil_mask = write;
-            self.test_stencil_mask = test;
+        let masks = &mut self.masks;
+        if !masks.mask_stack.is_empty() {
+            masks.num_masks -= 1;
+            masks.num_masks_active -= 1;
+            let (write, test) = masks.mask_stack.pop().unwrap();
+            masks.write_stencil_mask = write;
+            masks.test_stencil_mask = test;
         } else {
             log::warn!("Mask stack underflow\n");
         }
     }
+
+    fn push_layer(&mut self, blend_mode: swf::BlendMode) {
+        let buffers = self.take_layer_buffers();
+        buffers
+            .framebuffer(&self.display)
+            .clear_color_srgb_and_stencil((0.0, 0.0, 0.0, 0.0), 0);
+        // The layer starts without masks. Those of the target beneath it clip the layer
+        // when it is composited.
+        let parent_masks = std::mem::replace(&mut self.masks, MaskState::new());
+        self.layers.push(Layer {
+            buffers,
+            blend_mode,
+            parent_masks,
+        });
+    }
+
+    fn pop_layer(&mut self, filters: &[swf::Filter], scale: f32) {
+        let Layer {
+            buffers,
+            blend_mode,
+            parent_masks,
+        } = match self.layers.pop() {
+            Some(layer) => layer,
+            None => {
+                log::warn!("Layer stack underflow");
+                return;
+            }
+        };
+        self.masks = parent_masks;
+        if !filters.is_empty() {
+            buffers.apply_filters(filters, scale);
+        }
+        let backdrop = self.take_layer_buffers();
+        if let Some(layer) = self.layers.last() {
+            let mut target = layer.buffers.framebuffer(&self.display);
+            self.composite_layer(&mut target, &buffers.texture, &backdrop.texture, blend_mode);
+        } else {
+            let mut target = self.target.take().unwrap();
+            self.composite_layer(&mut target, &buffers.texture, &backdrop.texture, blend_mode);
+            self.target = Some(target);
+        }
+        self.free_layer_buffers.push(buffers);
+        self.free_layer_buffers.push(backdrop);
+    }
 }
 
 struct Texture {
@@ -943,6 +1179,90 @@ struct Texture {
     texture: glium::Texture2d,
 }
 
+/// The state of the masks of a render target.
+struct MaskState {
+    num_masks: u32,
+    num_masks_active: u32,
+    write_stencil_mask: u32,
+    test_stencil_mask: u32,
+    next_stencil_mask: u32,
+    mask_stack: Vec<(u32, u32)>,
+}
+
+impl MaskState {
+    fn new() -> Self {
+        Self {
+            num_masks: 0,
+            num_masks_active: 0,
+            write_stencil_mask: 0,
+            test_stencil_mask: 0,
+            next_stencil_mask: 1,
+            mask_stack: vec![],
+        }
+    }
+}
+
+/// An offscreen layer, drawn to until it is composited onto the target beneath it.
+struct Layer {
+    buffers: LayerBuffers,
+    /// How the layer is composited onto the target beneath it.
+    blend_mode: swf::BlendMode,
+    /// The masks of the target beneath the layer, restored when the layer is popped.
+    parent_masks: MaskState,
+}
+
+/// The color and stencil buffers of a layer, the size of the viewport.
+/// The color is RGBA with premultiplied alpha.
+struct LayerBuffers {
+    texture: glium::Texture2d,
+    stencil: StencilRenderBuffer,
+}
+
+impl LayerBuffers {
+    fn new(display: &Display, width: u32, height: u32) -> Self {
+        // The viewport is empty while the window is minimized.
+        let (width, height) = (width.max(1), height.max(1));
+        let texture = glium::Texture2d::empty_with_format(
+            display,
+            glium::texture::UncompressedFloatFormat::U8U8U8U8,
+            glium::texture::MipmapsOption::NoMipmap,
+            width,
+            height,
+        )
+        .unwrap();
+        let stencil = StencilRenderBuffer::new(display, StencilFormat::I8, width, height).unwrap();
+        Self { texture, stencil }
+    }
+
+    fn framebuffer(&self, display: &Display) -> SimpleFrameBuffer<'_> {
+        SimpleFrameBuffer::with_stencil_buffer(display, &self.texture, &self.stencil).unwrap()
+    }
+
+    /// Applies filters to the layer by reading back its pixels.
+    fn apply_filters(&self, filters: &[swf::Filter], scale: f32) {
+        let image: RawImage2d<u8> = self.texture.read();
+        let (width, height) = (image.width, image.height);
+        // The rows of the texture are from the bottom, and the filters expect them from the top.
+        let mut rgba: Vec<u8> = image
+            .data
+            .chunks_exact(width as usize * 4)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        filters::apply_filters(filters, width, height, &mut rgba, scale);
+        self.texture.write(
+            glium::Rect {
+                left: 0,
+                bottom: 0,
+                width,
+                height,
+            },
+            RawImage2d::from_raw_rgba_reversed(&rgba, (width, height)),
+        );
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 struct Vertex {
     position: [f32; 2],
@@ -1007,7 +1327,9 @@ impl Uniforms for GradientUniformsFull {
 #[derive(Clone, Debug)]
 struct BitmapUniforms {
     matrix: [[f32; 3]; 3],
-    id: swf::CharacterId,
+
+    /// The index of the texture in `textures`.
+    texture: usize,
 }
 
 impl Uniforms for BitmapUniforms {
@@ -1179,6 +1501,115 @@ const BITMAP_FRAGMENT_SHADER: &str = r#"
     }
 "#;
 
+const LAYER_VERTEX_SHADER: &str = r#"
+    #version 140
+
+    in vec2 position;
+
+    void main() {
+        gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+    }
+"#;
+
+const LAYER_FRAGMENT_SHADER: &str = r#"
+#version 140
+    uniform sampler2D u_layer;
+
+    out vec4 out_color;
+
+    void main() {
+        // The layer is the size of the surface, so it is read pixel for pixel.
+        vec4 color = texelFetch(u_layer, ivec2(gl_FragCoord.xy), 0);
+        // Transparent pixels don't affect the surface, nor draw into a mask.
+        if( color.a == 0.0 ) {
+            discard;
+        }
+        out_color = color;
+    }
+"#;
+
+const BLEND_FRAGMENT_SHADER: &str = r#"
+#version 140
+    uniform sampler2D u_layer;
+    uniform sampler2D u_backdrop;
+    uniform int u_blend_mode;
+
+    out vec4 out_color;
+
+    float screen(float src, float dst) {
+        return src + dst - src * dst;
+    }
+
+    float hard_light(float src, float dst) {
+        if( src <= 0.5 ) {
+            return dst * 2.0 * src;
+        } else {
+            return screen(dst, 2.0 * src - 1.0);
+        }
+    }
+
+    float blend(float src, float dst) {
+        if( u_blend_mode == 1 ) {
+            return src * dst;
+        } else if( u_blend_mode == 2 ) {
+            return screen(src, dst);
+        } else if( u_blend_mode == 3 ) {
+            return max(src, dst);
+        } else if( u_blend_mode == 4 ) {
+            return min(src, dst);
+        } else if( u_blend_mode == 5 ) {
+            return abs(src - dst);
+        } else if( u_blend_mode == 6 ) {
+            return min(src + dst, 1.0);
+        } else if( u_blend_mode == 7 ) {
+            return max(dst - src, 0.0);
+        } else if( u_blend_mode == 8 ) {
+            return 1.0 - dst;
+        } else if( u_blend_mode == 11 ) {
+            return hard_light(dst, src);
+        } else if( u_blend_mode == 12 ) {
+            return hard_light(src, dst);
+        }
+        return src;
+    }
+
+    void main() {
+        // Both textures are the size of the surface, so they are read pixel for pixel.
+        ivec2 position = ivec2(gl_FragCoord.xy);
+        vec4 src = texelFetch(u_layer, position, 0);
+        vec4 dst = texelFetch(u_backdrop, position, 0);
+
+        if( u_blend_mode == 9 ) {
+            // Alpha knocks out the surface, even where the layer is transparent.
+            out_color = dst * src.a;
+            return;
+        }
+        // Transparent pixels of the layer don't affect the surface in the other modes.
+        if( src.a == 0.0 ) {
+            discard;
+        }
+        if( u_blend_mode == 10 ) {
+            out_color = dst * (1.0 - src.a);
+            return;
+        }
+
+        // Where only one of the colors is present, it is kept as is.
+        // Where both are, the colors are blended.
+        vec3 src_color = src.rgb / src.a;
+        vec3 dst_color = dst.a > 0.0 ? dst.rgb / dst.a : vec3(0.0);
+        vec3 blended = vec3(
+            blend(src_color.r, dst_color.r),
+            blend(src_color.g, dst_color.g),
+            blend(src_color.b, dst_color.b)
+        );
+        float both = src.a * dst.a;
+        out_color = vec4(
+            src.rgb * (1.0 - dst.a) + dst.rgb * (1.0 - src.a) + both * blended,
+            src.a + dst.a - both
+        );
+    }
+"#;
+
 struct Mesh {
     draws: Vec<Draw>,
 }
@@ -1325,9 +1756,21 @@ fn mask_draw_parameters(
 }
 
 /// Returns the drawing parameters for standard color/gradient fills.
+/// The alpha is kept as is, so that layers have premultiplied alpha.
 #[inline]
 fn color_blend() -> glium::Blend {
-    glium::Blend::alpha_blending()
+    use glium::{BlendingFunction, LinearBlendingFactor};
+    glium::Blend {
+        color: BlendingFunction::Addition {
+            source: LinearBlendingFactor::SourceAlpha,
+            destination: LinearBlendingFactor::OneMinusSourceAlpha,
+        },
+        alpha: BlendingFunction::Addition {
+            source: LinearBlendingFactor::One,
+            destination: LinearBlendingFactor::OneMinusSourceAlpha,
+        },
+        ..Default::default()
+    }
 }
 
 /// Returns the drawing parameters for bitmaps with pre-multipled alpha.
@@ -1340,13 +1783,32 @@ fn bitmap_blend() -> glium::Blend {
             destination: LinearBlendingFactor::OneMinusSourceAlpha,
         },
         alpha: BlendingFunction::Addition {
-            source: LinearBlendingFactor::SourceAlpha,
+            source: LinearBlendingFactor::One,
             destination: LinearBlendingFactor::OneMinusSourceAlpha,
         },
         ..Default::default()
     }
 }
 
+/// Returns the number of a blend mode in `BLEND_FRAGMENT_SHADER`.
+fn blend_mode_index(blend_mode: swf::BlendMode) -> i32 {
+    match blend_mode {
+        swf::BlendMode::Normal | swf::BlendMode::Layer => 0,
+        swf::BlendMode::Multiply => 1,
+        swf::BlendMode::Screen => 2,
+        swf::BlendMode::Lighten => 3,
+        swf::BlendMode::Darken => 4,
+        swf::BlendMode::Difference => 5,
+        swf::BlendMode::Add => 6,
+        swf::BlendMode::Subtract => 7,
+        swf::BlendMode::Invert => 8,
+        swf::BlendMode::Alpha => 9,
+        swf::BlendMode::Erase => 10,
+        swf::BlendMode::Overlay => 11,
+        swf::BlendMode::HardLight => 12,
+    }
+}
+
 struct RuffleVertexCtor {
     color: [f32; 4],
 }
@@ -2,14 +2,17 @@
 
 use crate::avm1::opcode::OpCode;
 use crate::avm1::types::*;
+use crate::codepage::Codepage;
 use crate::error::{Error, Result};
 use crate::read::SwfRead;
+use std::borrow::Cow;
 use std::io::Cursor;
 
 #[allow(dead_code)]
 pub struct Reader<'a> {
     inner: Cursor<&'a [u8]>,
     version: u8,
+    codepage: Codepage,
 }
 
 impl<'a> SwfRead<Cursor<&'a [u8]>> for Reader<'a> {
@@ -23,9 +26,21 @@ impl<'a> Reader<'a> {
         Self {
             inner: Cursor::new(input),
             version,
+            codepage: Codepage::default(),
         }
     }
 
+    /// Returns the codepage used to decode strings in SWF 5 and earlier.
+    pub fn codepage(&self) -> Codepage {
+        self.codepage
+    }
+
+    /// Sets the codepage used to decode strings in SWF 5 and earlier.
+    /// SWF 6 and later always use UTF-8.
+    pub fn set_codepage(&mut self, codepage: Codepage) {
+        self.codepage = codepage;
+    }
+
     #[inline]
     pub fn pos(&self) -> usize {
         self.inner.position() as usize
@@ -48,7 +63,7 @@ impl<'a> Reader<'a> {
     }
 
     #[inline]
-    fn read_c_string(&mut self) -> Result<&'a str> {
+    fn read_c_string(&mut self) -> Result<Cow<'a, str>> {
         // Find zero terminator.
         let str_slice = {
             let start_pos = self.pos();
@@ -60,10 +75,16 @@ impl<'a> Reader<'a> {
             }
             &self.inner.get_ref()[start_pos..self.pos() - 1]
         };
-        // TODO: What does Flash do on invalid UTF8?
-        // Do we silently let it pass?
-        // TODO: Verify ANSI for SWF 5 and earlier.
-        std::str::from_utf8(str_slice).map_err(|_| Error::invalid_data("Invalid string data"))
+        if self.version >= 6 {
+            // TODO: What does Flash do on invalid UTF8?
+            // Do we silently let it pass?
+            std::str::from_utf8(str_slice)
+                .map(Cow::Borrowed)
+                .map_err(|_| Error::invalid_data("Invalid string data"))
+        } else {
+            // SWF 5 and earlier use the codepage of the playing system.
+            Ok(self.codepage.decode(str_slice))
+        }
     }
 
     #[inline]
@@ -430,7 +451,7 @@ pub mod tests {
         assert_eq!(
             action,
             Action::DefineFunction {
-                name: "foo",
+                name: "foo".into(),
                 params: vec![],
                 actions: &[0x96, 0x06, 0x00, 0x00, 0x74, 0x65, 0x73, 0x74, 0x00, 0x26],
             }
@@ -439,7 +460,7 @@ pub mod tests {
         if let Action::DefineFunction { actions, .. } = action {
             let mut reader = Reader::new(actions, 5);
             let action = reader.read_action().unwrap().unwrap();
-            assert_eq!(action, Action::Push(vec![Value::Str("test")]));
+            assert_eq!(action, Action::Push(vec![Value::Str("test".into())]));
         }
     }
 
@@ -452,4 +473,21 @@ pub mod tests {
         let action = reader.read_action().unwrap().unwrap();
         assert_eq!(action, Action::Push(vec![Value::Null, Value::Undefined]));
     }
+
+    #[test]
+    fn read_legacy_strings() {
+        // SWF 5 and earlier decode strings using the codepage of the playing system.
+        let action_bytes = [0x96, 6, 0, 0, 0x93, 0xfa, 0x96, 0x7b, 0];
+        let mut reader = Reader::new(&action_bytes[..], 5);
+        reader.set_codepage(Codepage::ShiftJis);
+        let action = reader.read_action().unwrap().unwrap();
+        assert_eq!(action, Action::Push(vec![Value::Str("日本".into())]));
+
+        let mut reader = Reader::new(&action_bytes[..], 5);
+        let action = reader.read_action().unwrap().unwrap();
+        assert_eq!(
+            action,
+            Action::Push(vec![Value::Str("\u{201C}ú\u{2013}{".into())])
+        );
+    }
 }
@@ -0,0 +1,174 @@
+//! Legacy text encodings used by SWF 5 and earlier.
+//!
+//! SWF 6 and later store strings as UTF-8. Earlier versions store strings in the codepage
+//! of the system that authored the movie, and Flash Player decodes them with the codepage
+//! of the system playing it.
+use std::borrow::Cow;
+
+/// The Shift-JIS double-byte table, indexed by lead byte and then trail byte.
+/// Each entry is a big-endian Unicode code point, or 0 if the pair is unmapped.
+static SHIFT_JIS_TABLE: &[u8] = include_bytes!("codepage/shift_jis.bin");
+
+const SHIFT_JIS_TRAIL_BYTES: std::ops::RangeInclusive<u8> = 0x40..=0xFC;
+
+/// The characters of Windows-1252 in the range 0x80 to 0x9F.
+/// The rest of the codepage matches Latin-1.
+const WINDOWS_1252_TABLE: [u16; 32] = [
+    0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+];
+
+/// A legacy codepage used to decode strings in SWF 5 and earlier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Codepage {
+    /// Windows-1252, used by Western European systems.
+    #[default]
+    Windows1252,
+
+    /// Shift-JIS (Windows codepage 932), used by Japanese systems.
+    ShiftJis,
+}
+
+impl Codepage {
+    /// Decodes a string in this codepage.
+    /// Invalid byte sequences are replaced with U+FFFD.
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        // ASCII is the same in every codepage.
+        if bytes.is_ascii() {
+            // This can't fail, because ASCII is valid UTF-8.
+            return Cow::Borrowed(std::str::from_utf8(bytes).unwrap());
+        }
+
+        let mut text = String::with_capacity(bytes.len());
+        let mut bytes = bytes.iter().copied().peekable();
+        while let Some(byte) = bytes.next() {
+            let code = match self {
+                Codepage::ShiftJis if is_shift_jis_lead_byte(byte) => match bytes.peek() {
+                    Some(trail) if SHIFT_JIS_TRAIL_BYTES.contains(trail) => {
+                        let code = (u16::from(byte) << 8) | u16::from(*trail);
+                        bytes.next();
+                        code
+                    }
+                    _ => u16::from(byte),
+                },
+                _ => u16::from(byte),
+            };
+            text.push(self.decode_char(code).unwrap_or('\u{FFFD}'));
+        }
+        Cow::Owned(text)
+    }
+
+    /// Decodes a single character code in this codepage.
+    /// Double-byte characters are given as `lead_byte << 8 | trail_byte`.
+    /// Returns `None` if the code isn't a valid character.
+    pub fn decode_char(self, code: u16) -> Option<char> {
+        let code_point = match (self, code) {
+            (_, 0..=0x7F) => u32::from(code),
+            (Codepage::Windows1252, 0x80..=0x9F) => {
+                u32::from(WINDOWS_1252_TABLE[usize::from(code - 0x80)])
+            }
+            (Codepage::Windows1252, 0xA0..=0xFF) => u32::from(code),
+            (Codepage::ShiftJis, 0x80) => 0x80,
+            // Half-width katakana.
+            (Codepage::ShiftJis, 0xA1..=0xDF) => 0xFF61 + u32::from(code - 0xA1),
+            (Codepage::ShiftJis, _) => {
+                let (lead, trail) = ((code >> 8) as u8, code as u8);
+                if !is_shift_jis_lead_byte(lead) || !SHIFT_JIS_TRAIL_BYTES.contains(&trail) {
+                    return None;
+                }
+                let index = 2 * (shift_jis_lead_index(lead) * 189 + usize::from(trail - 0x40));
+                u32::from(u16::from_be_bytes([
+                    SHIFT_JIS_TABLE[index],
+                    SHIFT_JIS_TABLE[index + 1],
+                ]))
+            }
+            _ => 0,
+        };
+        if code_point == 0 && code != 0 {
+            None
+        } else {
+            std::char::from_u32(code_point)
+        }
+    }
+
+    /// Encodes a single character in this codepage.
+    /// Double-byte characters are returned as `lead_byte << 8 | trail_byte`.
+    /// Returns `None` if the character doesn't exist in this codepage.
+    pub fn encode_char(self, c: char) -> Option<u16> {
+        let code_point = u32::from(c);
+        match self {
+            _ if code_point < 0x80 => Some(code_point as u16),
+            Codepage::Windows1252 => {
+                if (0xA0..=0xFF).contains(&code_point) {
+                    Some(code_point as u16)
+                } else {
+                    WINDOWS_1252_TABLE
+                        .iter()
+                        .position(|&n| u32::from(n) == code_point)
+                        .map(|i| 0x80 + i as u16)
+                }
+            }
+            Codepage::ShiftJis => {
+                if code_point == 0x80 {
+                    Some(0x80)
+                } else if (0xFF61..=0xFF9F).contains(&code_point) {
+                    Some((code_point - 0xFF61 + 0xA1) as u16)
+                } else {
+                    let index = SHIFT_JIS_TABLE
+                        .chunks_exact(2)
+                        .position(|c| u32::from(u16::from_be_bytes([c[0], c[1]])) == code_point)?;
+                    let lead = match index / 189 {
+                        i if i < 0x1F => 0x81 + i,
+                        i => 0xE0 + (i - 0x1F),
+                    };
+                    Some(((lead << 8) | (0x40 + index % 189)) as u16)
+                }
+            }
+        }
+    }
+}
+
+fn is_shift_jis_lead_byte(byte: u8) -> bool {
+    (0x81..=0x9F).contains(&byte) || (0xE0..=0xFC).contains(&byte)
+}
+
+/// Returns the row of a Shift-JIS lead byte in `SHIFT_JIS_TABLE`.
+fn shift_jis_lead_index(lead: u8) -> usize {
+    if lead <= 0x9F {
+        usize::from(lead - 0x81)
+    } else {
+        usize::from(lead - 0xE0) + 0x1F
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_windows_1252() {
+        let codepage = Codepage::Windows1252;
+        assert_eq!(codepage.decode(b"Ruffle"), "Ruffle");
+        assert_eq!(codepage.decode(b"caf\xe9 \x80"), "café €");
+        assert_eq!(codepage.encode_char('€'), Some(0x80));
+        assert_eq!(codepage.encode_char('é'), Some(0xE9));
+        assert_eq!(codepage.encode_char('あ'), None);
+    }
+
+    #[test]
+    fn decode_shift_jis() {
+        let codepage = Codepage::ShiftJis;
+        assert_eq!(
+            codepage.decode(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd Flash"),
+            "こんにちは Flash"
+        );
+        assert_eq!(codepage.decode(b"\x93\xfa\x96\x7b\xb6\xc0"), "日本ｶﾀ");
+        // An incomplete double-byte character.
+        assert_eq!(codepage.decode(b"a\x82"), "a\u{FFFD}");
+        assert_eq!(codepage.decode_char(0x82A0), Some('あ'));
+        assert_eq!(codepage.encode_char('あ'), Some(0x82A0));
+        assert_eq!(codepage.encode_char('ｶ'), Some(0xB6));
+        assert_eq!(codepage.encode_char('€'), None);
+    }
+}
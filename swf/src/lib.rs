@@ -20,6 +20,7 @@ extern crate xz2;
 
 pub mod avm1;
 pub mod avm2;
+pub mod codepage;
 pub mod error;
 pub mod read;
 mod tag_code;
@@ -30,6 +31,7 @@ pub mod write;
 mod test_data;
 
 /// Reexports
+pub use codepage::Codepage;
 pub use read::{read_swf, read_swf_header};
 pub use tag_code::TagCode;
 pub use types::*;
@@ -4,6 +4,7 @@
     clippy::unreadable_literal
 )]
 
+use crate::codepage::Codepage;
 use crate::error::{Error, Result};
 use crate::types::*;
 use byteorder::{LittleEndian, ReadBytesExt};
@@ -267,8 +268,12 @@ pub trait SwfRead<R: Read> {
             }
             bytes.push(byte)
         }
-        // TODO: There is probably a better way to do this.
-        // TODO: Verify ANSI for SWF 5 and earlier.
+        self.decode_string(bytes)
+    }
+
+    /// Converts the bytes of a string to a `String`.
+    /// Strings are UTF-8 unless the reader knows otherwise.
+    fn decode_string(&self, bytes: Vec<u8>) -> Result<String> {
         String::from_utf8(bytes).map_err(|_| Error::invalid_data("Invalid string data"))
     }
 }
@@ -277,6 +282,9 @@ pub struct Reader<R: Read> {
     input: R,
     version: u8,
 
+    /// The codepage of strings in SWF 5 and earlier.
+    codepage: Codepage,
+
     byte: u8,
     bit_index: u8,
 
@@ -328,6 +336,14 @@ impl<R: Read> SwfRead<R> for Reader<R> {
         self.byte_align();
         self.input.read_f64::<LittleEndian>()
     }
+
+    fn decode_string(&self, bytes: Vec<u8>) -> Result<String> {
+        if self.version >= 6 {
+            String::from_utf8(bytes).map_err(|_| Error::invalid_data("Invalid string data"))
+        } else {
+            Ok(self.codepage.decode(&bytes).into_owned())
+        }
+    }
 }
 
 impl<R: Read> Reader<R> {
@@ -335,6 +351,7 @@ impl<R: Read> Reader<R> {
         Reader {
             input,
             version,
+            codepage: Codepage::default(),
             byte: 0,
             bit_index: 0,
             num_fill_bits: 0,
@@ -342,6 +359,17 @@ impl<R: Read> Reader<R> {
         }
     }
 
+    /// Returns the codepage used to decode strings in SWF 5 and earlier.
+    pub fn codepage(&self) -> Codepage {
+        self.codepage
+    }
+
+    /// Sets the codepage used to decode strings in SWF 5 and earlier.
+    /// SWF 6 and later always use UTF-8.
+    pub fn set_codepage(&mut self, codepage: Codepage) {
+        self.codepage = codepage;
+    }
+
     /// Returns a reference to the underlying `Reader`.
     pub fn get_ref(&self) -> &R {
         &self.input
@@ -376,6 +404,7 @@ impl<R: Read> Reader<R> {
 
     fn read_tag_with_code(&mut self, tag_code: u16, length: usize) -> Result<Tag> {
         let mut tag_reader = Reader::new(self.input.by_ref().take(length as u64), self.version);
+        tag_reader.codepage = self.codepage;
         use crate::tag_code::TagCode;
         let tag = match TagCode::from_u16(tag_code) {
             Some(TagCode::End) => Tag::End,
@@ -448,7 +477,9 @@ impl<R: Read> Reader<R> {
             Some(TagCode::DefineText2) => {
                 Tag::DefineText(Box::new(tag_reader.read_define_text(2)?))
             }
-            Some(TagCode::DefineVideoStream) => tag_reader.read_define_video_stream()?,
+            Some(TagCode::DefineVideoStream) => {
+                Tag::DefineVideoStream(tag_reader.read_define_video_stream()?)
+            }
             Some(TagCode::EnableTelemetry) => {
                 tag_reader.read_u16()?; // Reserved
                 let password_hash = if length > 2 {
@@ -626,6 +657,7 @@ impl<R: Read> Reader<R> {
                 // read_tag_list for Reader<Take<R>> to enforce this.
                 let mut sprite_reader =
                     Reader::new(&mut tag_reader.input as &mut dyn Read, self.version);
+                sprite_reader.codepage = self.codepage;
                 sprite_reader.read_define_sprite()?
             }
 
@@ -646,7 +678,7 @@ impl<R: Read> Reader<R> {
 
             Some(TagCode::RemoveObject2) => Tag::RemoveObject(tag_reader.read_remove_object_2()?),
 
-            Some(TagCode::VideoFrame) => tag_reader.read_video_frame()?,
+            Some(TagCode::VideoFrame) => Tag::VideoFrame(tag_reader.read_video_frame()?),
             Some(TagCode::ProductInfo) => Tag::ProductInfo(tag_reader.read_product_info()?),
             _ => {
                 let size = length as usize;
@@ -1199,11 +1231,9 @@ impl<R: Read> Reader<R> {
 
         let language = self.read_language()?;
         let name_len = self.read_u8()?;
-        let mut name = String::with_capacity(name_len as usize);
-        self.input
-            .by_ref()
-            .take(name_len.into())
-            .read_to_string(&mut name)?;
+        let mut name = vec![0; name_len.into()];
+        self.input.read_exact(&mut name)?;
+        let name = self.decode_string(name)?;
         // TODO: SWF19 states that the font name should not have a terminating null byte,
         // but it often does (depends on Flash IDE version?)
         // We should probably strip anything past the first null.
@@ -1382,15 +1412,13 @@ impl<R: Read> Reader<R> {
         Ok(zone)
     }
 
-    fn read_define_font_info(&mut self, version: u8) -> Result<Tag> {
+    pub fn read_define_font_info(&mut self, version: u8) -> Result<Tag> {
         let id = self.read_u16()?;
 
         let font_name_len = self.read_u8()?;
-        let mut font_name = String::with_capacity(font_name_len as usize);
-        self.input
-            .by_ref()
-            .take(font_name_len.into())
-            .read_to_string(&mut font_name)?;
+        let mut font_name = vec![0; font_name_len.into()];
+        self.input.read_exact(&mut font_name)?;
+        let font_name = self.decode_string(font_name)?;
 
         let flags = self.read_u8()?;
         let use_wide_codes = flags & 0b1 != 0; // TODO(Herschel): Warn if false for version 2.
@@ -1427,7 +1455,7 @@ impl<R: Read> Reader<R> {
         })))
     }
 
-    fn read_define_font_name(&mut self) -> Result<Tag> {
+    pub fn read_define_font_name(&mut self) -> Result<Tag> {
         Ok(Tag::DefineFontName {
             id: self.read_character_id()?,
             name: self.read_c_string()?,
@@ -2356,7 +2384,8 @@ impl<R: Read> Reader<R> {
                 strength: self.read_fixed8()?,
                 is_inner: self.read_bit()?,
                 is_knockout: self.read_bit()?,
-                num_passes: self.read_ubits(6)? as u8 & 0b011111,
+                is_composite_source: self.read_bit()?,
+                num_passes: self.read_ubits(5)? as u8,
             })),
             1 => Filter::BlurFilter(Box::new(BlurFilter {
                 blur_x: self.read_fixed16()?,
@@ -2715,7 +2744,7 @@ impl<R: Read> Reader<R> {
         })
     }
 
-    fn read_define_video_stream(&mut self) -> Result<Tag> {
+    pub fn read_define_video_stream(&mut self) -> Result<DefineVideoStream> {
         let id = self.read_character_id()?;
         let num_frames = self.read_u16()?;
         let width = self.read_u16()?;
@@ -2723,20 +2752,22 @@ impl<R: Read> Reader<R> {
         let flags = self.read_u8()?;
         // TODO(Herschel): Check SWF version.
         let codec = match self.read_u8()? {
+            0 => VideoCodec::None,
             2 => VideoCodec::H263,
             3 => VideoCodec::ScreenVideo,
             4 => VideoCodec::VP6,
             5 => VideoCodec::VP6WithAlpha,
+            6 => VideoCodec::ScreenVideoV2,
             _ => return Err(Error::invalid_data("Invalid video codec.")),
         };
-        Ok(Tag::DefineVideoStream(DefineVideoStream {
+        Ok(DefineVideoStream {
             id,
             num_frames,
             width,
             height,
             is_smoothed: flags & 0b1 != 0,
             codec,
-            deblocking: match flags & 0b100_0 {
+            deblocking: match flags & 0b111_0 {
                 0b000_0 => VideoDeblocking::UseVideoPacketValue,
                 0b001_0 => VideoDeblocking::None,
                 0b010_0 => VideoDeblocking::Level1,
@@ -2745,19 +2776,21 @@ impl<R: Read> Reader<R> {
                 0b101_0 => VideoDeblocking::Level4,
                 _ => return Err(Error::invalid_data("Invalid video deblocking value.")),
             },
-        }))
+        })
     }
 
-    fn read_video_frame(&mut self) -> Result<Tag> {
+    /// Reads a `VideoFrame` tag.
+    /// The frame data is the remainder of the stream, so the stream should only contain this tag.
+    pub fn read_video_frame(&mut self) -> Result<VideoFrame> {
         let stream_id = self.read_character_id()?;
         let frame_num = self.read_u16()?;
         let mut data = vec![];
         self.input.read_to_end(&mut data)?;
-        Ok(Tag::VideoFrame(VideoFrame {
+        Ok(VideoFrame {
             stream_id,
             frame_num,
             data,
-        }))
+        })
     }
 
     fn read_define_bits_jpeg_3(&mut self, version: u8) -> Result<Tag> {
@@ -3142,9 +3175,18 @@ pub mod tests {
         }
         {
             let buf = "12🤖12\0".as_bytes();
-            let mut reader = Reader::new(&buf[..], 1);
+            let mut reader = Reader::new(&buf[..], 6);
             assert_eq!(reader.read_c_string().unwrap(), "12🤖12");
         }
+        {
+            // SWF 5 and earlier use the system codepage.
+            let buf = b"caf\xe9 \x82\xa0\0";
+            let mut reader = Reader::new(&buf[..], 5);
+            assert_eq!(reader.read_c_string().unwrap(), "caf\u{e9} \u{201a}\u{a0}");
+            let mut reader = Reader::new(&buf[..], 5);
+            reader.set_codepage(Codepage::ShiftJis);
+            assert_eq!(reader.read_c_string().unwrap(), "caf\u{fffd} \u{3042}");
+        }
     }
 
     #[test]
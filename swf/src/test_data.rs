@@ -1394,7 +1394,7 @@ pub fn tag_tests() -> Vec<TagTestData> {
             ),
         ),
         (
-            1, // Minimum version not listed in SWF19.
+            9, // Minimum version not listed in SWF19, but the tag is used by AVM2 movies.
             Tag::DefineSceneAndFrameLabelData(DefineSceneAndFrameLabelData {
                 scenes: vec![
                     FrameLabelData {
@@ -2600,8 +2600,8 @@ pub fn avm1_tests() -> Vec<Avm1TestData> {
         (
             3,
             Action::GetUrl {
-                url: "a",
-                target: "b",
+                url: "a".into(),
+                target: "b".into(),
             },
             vec![0x83, 4, 0, 97, 0, 98, 0],
         ),
@@ -2634,7 +2634,7 @@ pub fn avm1_tests() -> Vec<Avm1TestData> {
         ),
         (
             3,
-            Action::GotoLabel("testb"),
+            Action::GotoLabel("testb".into()),
             vec![0x8C, 6, 0, 116, 101, 115, 116, 98, 0],
         ),
         (4, Action::If { offset: 1 }, vec![0x9D, 2, 0, 1, 0]),
@@ -2654,7 +2654,7 @@ pub fn avm1_tests() -> Vec<Avm1TestData> {
         (3, Action::PreviousFrame, vec![0x05]),
         (
             4,
-            Action::Push(vec![Value::Str("test")]),
+            Action::Push(vec![Value::Str("test".into())]),
             vec![0x96, 6, 0, 0, 116, 101, 115, 116, 0],
         ),
         (
@@ -2717,7 +2717,7 @@ pub fn avm1_tests() -> Vec<Avm1TestData> {
         (4, Action::RandomNumber, vec![0x30]),
         (
             3,
-            Action::SetTarget("test"),
+            Action::SetTarget("test".into()),
             vec![0x8B, 5, 0, 116, 101, 115, 116, 0],
         ),
         (4, Action::SetVariable, vec![0x1D]),
@@ -2766,8 +2766,8 @@ pub fn avm1_tests() -> Vec<Avm1TestData> {
         (
             5,
             Action::DefineFunction {
-                name: "cliche",
-                params: vec!["greeting", "name"],
+                name: "cliche".into(),
+                params: vec!["greeting".into(), "name".into()],
                 actions: &[
                     0x96, 0x0a, 0x00, 0x00, 0x67, 0x72, 0x65, 0x65, 0x74, 0x69, 0x6e, 0x67, 0x00,
                     0x1c, 0x96, 0x03, 0x00, 0x00, 0x20, 0x00, 0x47, 0x96, 0x06, 0x00, 0x00, 0x6e,
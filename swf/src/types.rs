@@ -314,6 +314,8 @@ pub struct DropShadowFilter {
     pub strength: f32,
     pub is_inner: bool,
     pub is_knockout: bool,
+    /// Whether the object is drawn along with its shadow. `hideObject` in ActionScript.
+    pub is_composite_source: bool,
     pub num_passes: u8,
 }
 
@@ -1082,10 +1084,14 @@ pub enum VideoDeblocking {
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum VideoCodec {
+    /// No codec. Used by videos that are only played from ActionScript,
+    /// such as the target of `Video.attachVideo`.
+    None,
     H263,
     ScreenVideo,
     VP6,
     VP6WithAlpha,
+    ScreenVideoV2,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -2084,7 +2084,7 @@ impl<W: Write> Writer<W> {
                 self.write_fixed8(drop_shadow.strength)?;
                 self.write_bit(drop_shadow.is_inner)?;
                 self.write_bit(drop_shadow.is_knockout)?;
-                self.write_bit(true)?;
+                self.write_bit(drop_shadow.is_composite_source)?;
                 self.write_ubits(5, drop_shadow.num_passes.into())?;
             }
 
@@ -2662,10 +2662,12 @@ impl<W: Write> Writer<W> {
             } | if video.is_smoothed { 0b1 } else { 0 },
         )?;
         self.write_u8(match video.codec {
+            VideoCodec::None => 0,
             VideoCodec::H263 => 2,
             VideoCodec::ScreenVideo => 3,
             VideoCodec::VP6 => 4,
             VideoCodec::VP6WithAlpha => 5,
+            VideoCodec::ScreenVideoV2 => 6,
         })?;
         Ok(())
     }
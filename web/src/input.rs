@@ -10,6 +10,11 @@ pub struct WebInputBackend {
     keys_down: HashSet<String>,
     canvas: HtmlCanvasElement,
     cursor_visible: bool,
+
+    /// The text most recently copied from or pasted into the movie.
+    /// Browsers only allow clipboard access during clipboard events, so this
+    /// is synchronized with the system clipboard by the `copy`, `cut` and `paste` handlers.
+    clipboard: String,
 }
 
 impl WebInputBackend {
@@ -18,6 +23,7 @@ impl WebInputBackend {
             keys_down: HashSet::new(),
             canvas: canvas.clone(),
             cursor_visible: true,
+            clipboard: String::new(),
         }
     }
 
@@ -158,6 +164,14 @@ impl InputBackend for WebInputBackend {
             .warn_on_error();
         self.cursor_visible = true;
     }
+
+    fn clipboard_content(&mut self) -> String {
+        self.clipboard.clone()
+    }
+
+    fn set_clipboard_content(&mut self, content: String) {
+        self.clipboard = content;
+    }
 }
 
 /// Converts a Web `KeyboardEvent.code` value into a Ruffle `KeyCode`.
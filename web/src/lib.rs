@@ -11,10 +11,19 @@ use crate::{
 };
 use generational_arena::{Arena, Index};
 use js_sys::Uint8Array;
-use ruffle_core::{backend::render::RenderBackend, PlayerEvent};
+use ruffle_core::{
+    backend::{
+        font::NullFontBackend, input::InputBackend, render::RenderBackend,
+        video::SoftwareVideoBackend,
+    },
+    events::KeyCode,
+    Codepage, PlayerEvent,
+};
 use std::{cell::RefCell, error::Error, num::NonZeroI32};
 use wasm_bindgen::{prelude::*, JsCast, JsValue};
-use web_sys::{Element, EventTarget, HtmlCanvasElement, KeyboardEvent, PointerEvent};
+use web_sys::{
+    ClipboardEvent, Element, EventTarget, HtmlCanvasElement, KeyboardEvent, PointerEvent,
+};
 
 thread_local! {
     /// We store the actual instances of the ruffle core in a static pool.
@@ -31,6 +40,8 @@ struct RuffleInstance {
         WebCanvasRenderBackend,
         WebNavigatorBackend,
         WebInputBackend,
+        NullFontBackend,
+        SoftwareVideoBackend,
     >,
     canvas: HtmlCanvasElement,
     canvas_width: i32,
@@ -46,6 +57,9 @@ struct RuffleInstance {
     window_mouse_down_callback: Option<Closure<dyn FnMut(PointerEvent)>>,
     key_down_callback: Option<Closure<dyn FnMut(KeyboardEvent)>>,
     key_up_callback: Option<Closure<dyn FnMut(KeyboardEvent)>>,
+    copy_callback: Option<Closure<dyn FnMut(ClipboardEvent)>>,
+    cut_callback: Option<Closure<dyn FnMut(ClipboardEvent)>>,
+    paste_callback: Option<Closure<dyn FnMut(ClipboardEvent)>>,
     has_focus: bool,
 }
 
@@ -97,10 +111,19 @@ impl Ruffle {
         let audio = WebAudioBackend::new()?;
         let navigator = WebNavigatorBackend::new();
         let input = WebInputBackend::new(&canvas);
+        // Browsers don't give access to the installed fonts, so always use the built-in device font.
+        let fonts = NullFontBackend::new();
+        let video = SoftwareVideoBackend::new();
+
+        // Match the codepage that Flash Player would use with the browser's language.
+        let codepage = match window.navigator().language() {
+            Some(language) if language.starts_with("ja") => Codepage::ShiftJis,
+            _ => Codepage::Windows1252,
+        };
 
-        let mut core = ruffle_core::Player::new(renderer, audio, navigator, input, data)?;
-        let frame_rate = core.frame_rate();
-        core.audio_mut().set_frame_rate(frame_rate);
+        let core = ruffle_core::Player::new(
+            renderer, audio, navigator, input, fonts, video, codepage, data,
+        )?;
         // Create instance.
         let instance = RuffleInstance {
             core,
@@ -116,6 +139,9 @@ impl Ruffle {
             mouse_up_callback: None,
             key_down_callback: None,
             key_up_callback: None,
+            copy_callback: None,
+            cut_callback: None,
+            paste_callback: None,
             timestamp: None,
             has_focus: false,
         };
@@ -294,21 +320,40 @@ impl Ruffle {
                                 let code = js_event.code();
                                 instance.core.input_mut().keydown(code.clone());
 
-                                if let Some(codepoint) =
-                                    input::web_key_to_codepoint(&js_event.key())
-                                {
-                                    instance
-                                        .core
-                                        .handle_event(PlayerEvent::TextInput { codepoint });
+                                // Keyboard shortcuts don't produce text input.
+                                let is_shortcut = js_event.ctrl_key() || js_event.meta_key();
+                                if !is_shortcut {
+                                    if let Some(codepoint) =
+                                        input::web_key_to_codepoint(&js_event.key())
+                                    {
+                                        instance
+                                            .core
+                                            .handle_event(PlayerEvent::TextInput { codepoint });
+                                    }
                                 }
 
-                                if let Some(key_code) = input::web_to_ruffle_key_code(&code) {
-                                    instance
-                                        .core
-                                        .handle_event(PlayerEvent::KeyDown { key_code });
+                                let key_code = input::web_to_ruffle_key_code(&code);
+
+                                // Let the browser fire its clipboard events for copy, cut and paste;
+                                // the clipboard is only accessible from those event handlers.
+                                let is_clipboard_shortcut = is_shortcut
+                                    && (key_code == Some(KeyCode::C)
+                                        || key_code == Some(KeyCode::X)
+                                        || key_code == Some(KeyCode::V));
+
+                                // Pasting is forwarded to the player by the paste handler,
+                                // once the clipboard contents are known.
+                                if let Some(key_code) = key_code {
+                                    if !(is_clipboard_shortcut && key_code == KeyCode::V) {
+                                        instance
+                                            .core
+                                            .handle_event(PlayerEvent::KeyDown { key_code });
+                                    }
                                 }
 
-                                js_event.prevent_default();
+                                if !is_clipboard_shortcut {
+                                    js_event.prevent_default();
+                                }
                             }
                         }
                     });
@@ -331,7 +376,11 @@ impl Ruffle {
                     INSTANCES.with(|instances| {
                         if let Some(instance) = instances.borrow_mut().get_mut(index) {
                             if instance.has_focus {
-                                instance.core.input_mut().keyup(js_event.code());
+                                let code = js_event.code();
+                                instance.core.input_mut().keyup(code.clone());
+                                if let Some(key_code) = input::web_to_ruffle_key_code(&code) {
+                                    instance.core.handle_event(PlayerEvent::KeyUp { key_code });
+                                }
                                 js_event.prevent_default();
                             }
                         }
@@ -348,6 +397,70 @@ impl Ruffle {
                 instance.key_up_callback = Some(key_up_callback);
             }
 
+            // Create clipboard event handlers.
+            // Copying and cutting has already updated the clipboard of the input backend
+            // during the preceding keydown event, so copy it to the system clipboard.
+            {
+                let copy_to_clipboard = move |js_event: ClipboardEvent| {
+                    INSTANCES.with(|instances| {
+                        if let Some(instance) = instances.borrow_mut().get_mut(index) {
+                            if instance.has_focus {
+                                if let Some(clipboard_data) = js_event.clipboard_data() {
+                                    let content = instance.core.input_mut().clipboard_content();
+                                    let _ = clipboard_data.set_data("text/plain", &content);
+                                }
+                                js_event.prevent_default();
+                            }
+                        }
+                    });
+                };
+                let copy_callback =
+                    Closure::wrap(Box::new(copy_to_clipboard) as Box<dyn FnMut(ClipboardEvent)>);
+                let cut_callback =
+                    Closure::wrap(Box::new(copy_to_clipboard) as Box<dyn FnMut(ClipboardEvent)>);
+                window
+                    .add_event_listener_with_callback(
+                        "copy",
+                        copy_callback.as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+                window
+                    .add_event_listener_with_callback("cut", cut_callback.as_ref().unchecked_ref())
+                    .unwrap();
+                let instance = instances.get_mut(index).unwrap();
+                instance.copy_callback = Some(copy_callback);
+                instance.cut_callback = Some(cut_callback);
+            }
+
+            {
+                let paste_callback = Closure::wrap(Box::new(move |js_event: ClipboardEvent| {
+                    INSTANCES.with(|instances| {
+                        if let Some(instance) = instances.borrow_mut().get_mut(index) {
+                            if instance.has_focus {
+                                if let Some(clipboard_data) = js_event.clipboard_data() {
+                                    if let Ok(content) = clipboard_data.get_data("text/plain") {
+                                        instance.core.input_mut().set_clipboard_content(content);
+                                    }
+                                }
+                                instance.core.handle_event(PlayerEvent::KeyDown {
+                                    key_code: KeyCode::V,
+                                });
+                                js_event.prevent_default();
+                            }
+                        }
+                    });
+                })
+                    as Box<dyn FnMut(ClipboardEvent)>);
+                window
+                    .add_event_listener_with_callback(
+                        "paste",
+                        paste_callback.as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+                let instance = instances.get_mut(index).unwrap();
+                instance.paste_callback = Some(paste_callback);
+            }
+
             ruffle
         });
 
@@ -1,15 +1,78 @@
 //! Navigator backend for web
 
-use ruffle_core::backend::navigator::{NavigationMethod, NavigatorBackend};
+use generational_arena::Arena;
+use ruffle_core::backend::navigator::{
+    FetchHandle, FetchStatus, NavigationMethod, NavigatorBackend,
+};
+use std::cell::RefCell;
 use std::collections::HashMap;
-use wasm_bindgen::JsCast;
-use web_sys::window;
+use std::rc::Rc;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{window, ProgressEvent, XmlHttpRequest, XmlHttpRequestResponseType};
 
-pub struct WebNavigatorBackend {}
+pub struct WebNavigatorBackend {
+    /// The state of pending fetches, updated by the `XMLHttpRequest` callbacks.
+    fetches: Rc<RefCell<Arena<FetchStatus>>>,
+}
 
 impl WebNavigatorBackend {
     pub fn new() -> Self {
-        WebNavigatorBackend {}
+        WebNavigatorBackend {
+            fetches: Rc::new(RefCell::new(Arena::new())),
+        }
+    }
+
+    /// Starts an `XMLHttpRequest` that writes its progress into `fetches[handle]`.
+    fn start_request(&self, url: &str, handle: FetchHandle) -> Result<(), wasm_bindgen::JsValue> {
+        let request = XmlHttpRequest::new()?;
+        request.open("GET", url)?;
+        request.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+
+        let fetches = Rc::clone(&self.fetches);
+        let progress_closure = Closure::wrap(Box::new(move |event: ProgressEvent| {
+            if let Some(status) = fetches.borrow_mut().get_mut(handle) {
+                *status = FetchStatus::Loading {
+                    bytes_loaded: event.loaded() as usize,
+                    bytes_total: if event.length_computable() {
+                        Some(event.total() as usize)
+                    } else {
+                        None
+                    },
+                };
+            }
+        }) as Box<dyn FnMut(ProgressEvent)>);
+
+        let fetches = Rc::clone(&self.fetches);
+        let load_request = request.clone();
+        let load_closure = Closure::wrap(Box::new(move || {
+            let status = match load_request.response() {
+                Ok(response) if load_request.status().unwrap_or(0) < 400 => {
+                    FetchStatus::Complete(js_sys::Uint8Array::new(&response).to_vec())
+                }
+                _ => FetchStatus::Failed,
+            };
+            if let Some(fetch) = fetches.borrow_mut().get_mut(handle) {
+                *fetch = status;
+            }
+        }) as Box<dyn FnMut()>);
+
+        let fetches = Rc::clone(&self.fetches);
+        let error_closure = Closure::wrap(Box::new(move || {
+            if let Some(fetch) = fetches.borrow_mut().get_mut(handle) {
+                *fetch = FetchStatus::Failed;
+            }
+        }) as Box<dyn FnMut()>);
+
+        request.set_onprogress(Some(progress_closure.as_ref().unchecked_ref()));
+        request.set_onload(Some(load_closure.as_ref().unchecked_ref()));
+        request.set_onerror(Some(error_closure.as_ref().unchecked_ref()));
+        request.send()?;
+
+        // TODO: This will leak memory per request. Remember and properly drop the closures.
+        progress_closure.forget();
+        load_closure.forget();
+        error_closure.forget();
+        Ok(())
     }
 }
 
@@ -72,4 +135,31 @@ impl NavigatorBackend for WebNavigatorBackend {
             };
         }
     }
+
+    fn fetch(&mut self, url: String) -> FetchHandle {
+        let handle = self.fetches.borrow_mut().insert(FetchStatus::Loading {
+            bytes_loaded: 0,
+            bytes_total: None,
+        });
+        if let Err(e) = self.start_request(&url, handle) {
+            log::error!("Unable to fetch {}: {:?}", url, e);
+            self.fetches.borrow_mut()[handle] = FetchStatus::Failed;
+        }
+        handle
+    }
+
+    fn poll_fetch(&mut self, handle: FetchHandle) -> FetchStatus {
+        let mut fetches = self.fetches.borrow_mut();
+        match fetches.get(handle) {
+            Some(FetchStatus::Loading {
+                bytes_loaded,
+                bytes_total,
+            }) => FetchStatus::Loading {
+                bytes_loaded: *bytes_loaded,
+                bytes_total: *bytes_total,
+            },
+            Some(_) => fetches.remove(handle).unwrap(),
+            None => FetchStatus::Failed,
+        }
+    }
 }
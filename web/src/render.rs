@@ -1,12 +1,12 @@
 use crate::utils::JsResult;
 use ruffle_core::backend::render::{
-    swf, swf::CharacterId, BitmapHandle, BitmapInfo, Color, Letterbox, RenderBackend, ShapeHandle,
-    Transform,
+    blend, filters, premultiply_alpha_rgba, swf, swf::CharacterId, unmultiply_alpha_rgba,
+    BitmapHandle, BitmapInfo, Color, Letterbox, RenderBackend, ShapeHandle, Transform,
 };
 use std::collections::HashMap;
 use std::convert::TryInto;
-use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, Element, HtmlCanvasElement, HtmlImageElement};
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, Element, HtmlCanvasElement, HtmlImageElement, ImageData};
 
 pub struct WebCanvasRenderBackend {
     canvas: HtmlCanvasElement,
@@ -14,6 +14,8 @@ pub struct WebCanvasRenderBackend {
     root_canvas: HtmlCanvasElement,
     render_targets: Vec<(HtmlCanvasElement, CanvasRenderingContext2d)>,
     cur_render_target: usize,
+    /// The blend modes of the layers being rendered, from the bottom.
+    layer_blend_modes: Vec<swf::BlendMode>,
     color_matrix: Element,
     shapes: Vec<ShapeData>,
     bitmaps: Vec<BitmapData>,
@@ -130,6 +132,7 @@ impl WebCanvasRenderBackend {
             root_canvas: canvas.clone(),
             render_targets,
             cur_render_target: 0,
+            layer_blend_modes: vec![],
             color_matrix,
             context,
             shapes: vec![],
@@ -452,6 +455,72 @@ impl RenderBackend for WebCanvasRenderBackend {
         }
     }
 
+    fn register_bitmap_raw(
+        &mut self,
+        id: CharacterId,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> BitmapInfo {
+        let png = Self::rgba_to_png_data_uri(&rgba[..], width, height).unwrap();
+
+        let image = HtmlImageElement::new().unwrap();
+        image.set_src(&png);
+
+        let handle = BitmapHandle(self.bitmaps.len());
+        self.bitmaps.push(BitmapData {
+            image,
+            width,
+            height,
+            data: png,
+        });
+        self.id_to_bitmap.insert(id, handle);
+        BitmapInfo {
+            handle,
+            width: width.try_into().unwrap(),
+            height: height.try_into().unwrap(),
+        }
+    }
+
+    fn update_texture(
+        &mut self,
+        bitmap: BitmapHandle,
+        width: u32,
+        height: u32,
+        rgba: Vec<u8>,
+    ) -> Result<BitmapHandle, Box<dyn std::error::Error>> {
+        let png = Self::rgba_to_png_data_uri(&rgba[..], width, height)?;
+
+        let image = HtmlImageElement::new().map_err(|_| "Unable to create image")?;
+        image.set_src(&png);
+
+        let old_bitmap = self
+            .bitmaps
+            .get_mut(bitmap.0)
+            .ok_or("Unknown bitmap handle")?;
+        *old_bitmap = BitmapData {
+            image,
+            width,
+            height,
+            data: png,
+        };
+        Ok(bitmap)
+    }
+
+    fn unregister_bitmap(&mut self, bitmap: BitmapHandle) {
+        // Handles are indices into `bitmaps`, so the bitmap is replaced by an empty one.
+        if let Some(old_bitmap) = self.bitmaps.get_mut(bitmap.0) {
+            if let Ok(image) = HtmlImageElement::new() {
+                *old_bitmap = BitmapData {
+                    image,
+                    width: 0,
+                    height: 0,
+                    data: String::new(),
+                };
+            }
+        }
+    }
+
     fn begin_frame(&mut self) {
         // Reset canvas transform in case it was left in a dirty state.
         self.context.reset_transform().unwrap();
@@ -471,13 +540,15 @@ impl RenderBackend for WebCanvasRenderBackend {
             .fill_rect(0.0, 0.0, width.into(), height.into());
     }
 
-    fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform) {
+    fn render_bitmap(&mut self, bitmap: BitmapHandle, transform: &Transform, smoothing: bool) {
         self.set_transform(transform);
+        self.context.set_image_smoothing_enabled(smoothing);
         if let Some(bitmap) = self.bitmaps.get(bitmap.0) {
             let _ = self
                 .context
                 .draw_image_with_html_image_element(&bitmap.image, 0.0, 0.0);
         }
+        self.context.set_image_smoothing_enabled(true);
         self.clear_transform();
     }
 
@@ -572,6 +643,116 @@ impl RenderBackend for WebCanvasRenderBackend {
             .draw_image_with_html_canvas_element(&masker_canvas, 0.0, 0.0)
             .unwrap();
     }
+
+    fn push_layer(&mut self, blend_mode: swf::BlendMode) {
+        self.layer_blend_modes.push(blend_mode);
+        self.push_render_target();
+    }
+
+    fn pop_layer(&mut self, filters: &[swf::Filter], scale: f32) {
+        let (layer_canvas, layer_context) = self.pop_render_target();
+        let blend_mode = self
+            .layer_blend_modes
+            .pop()
+            .unwrap_or(swf::BlendMode::Normal);
+
+        // Canvas filters can't express the SWF filters,
+        // so we read back the pixels of the layer and filter them in software.
+        if !filters.is_empty() {
+            let (width, height) = (layer_canvas.width(), layer_canvas.height());
+            match layer_context.get_image_data(0.0, 0.0, width.into(), height.into()) {
+                Ok(image_data) => {
+                    let mut rgba = image_data.data().0;
+                    premultiply_alpha_rgba(&mut rgba);
+                    filters::apply_filters(filters, width, height, &mut rgba, scale);
+                    unmultiply_alpha_rgba(&mut rgba);
+                    let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+                        Clamped(&mut rgba),
+                        width,
+                        height,
+                    );
+                    if let Ok(image_data) = image_data {
+                        layer_context
+                            .put_image_data(&image_data, 0.0, 0.0)
+                            .warn_on_error();
+                    }
+                }
+                Err(error) => log::warn!("Unable to read back a layer: {:?}", error),
+            }
+        }
+
+        self.context.reset_transform().warn_on_error();
+        if let Some(operation) = composite_operation(blend_mode) {
+            self.context
+                .set_global_composite_operation(operation)
+                .warn_on_error();
+            self.context
+                .draw_image_with_html_canvas_element(&layer_canvas, 0.0, 0.0)
+                .warn_on_error();
+            self.context
+                .set_global_composite_operation("source-over")
+                .warn_on_error();
+        } else {
+            self.composite_layer_pixels(&layer_context, blend_mode);
+        }
+    }
+}
+
+impl WebCanvasRenderBackend {
+    /// Composites a layer by reading back the pixels of both canvases, for the blend modes
+    /// that have no canvas composite operation.
+    fn composite_layer_pixels(
+        &mut self,
+        layer_context: &CanvasRenderingContext2d,
+        blend_mode: swf::BlendMode,
+    ) {
+        let (width, height) = (self.canvas.width(), self.canvas.height());
+        let image_data = (
+            layer_context.get_image_data(0.0, 0.0, width.into(), height.into()),
+            self.context
+                .get_image_data(0.0, 0.0, width.into(), height.into()),
+        );
+        match image_data {
+            (Ok(layer_data), Ok(image_data)) => {
+                let mut layer = layer_data.data().0;
+                let mut rgba = image_data.data().0;
+                premultiply_alpha_rgba(&mut layer);
+                premultiply_alpha_rgba(&mut rgba);
+                blend::composite_rgba(blend_mode, &layer, &mut rgba);
+                unmultiply_alpha_rgba(&mut rgba);
+                let image_data =
+                    ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut rgba), width, height);
+                if let Ok(image_data) = image_data {
+                    self.context
+                        .put_image_data(&image_data, 0.0, 0.0)
+                        .warn_on_error();
+                }
+            }
+            (Err(error), _) | (_, Err(error)) => {
+                log::warn!("Unable to read back a layer: {:?}", error)
+            }
+        }
+    }
+}
+
+/// Returns the canvas composite operation that implements a blend mode, if there is one.
+fn composite_operation(blend_mode: swf::BlendMode) -> Option<&'static str> {
+    use swf::BlendMode;
+    let operation = match blend_mode {
+        BlendMode::Normal | BlendMode::Layer => "source-over",
+        BlendMode::Multiply => "multiply",
+        BlendMode::Screen => "screen",
+        BlendMode::Lighten => "lighten",
+        BlendMode::Darken => "darken",
+        BlendMode::Difference => "difference",
+        BlendMode::Add => "lighter",
+        BlendMode::Overlay => "overlay",
+        BlendMode::HardLight => "hard-light",
+        BlendMode::Alpha => "destination-in",
+        BlendMode::Erase => "destination-out",
+        BlendMode::Subtract | BlendMode::Invert => return None,
+    };
+    Some(operation)
 }
 
 fn swf_shape_to_svg(